@@ -1,25 +1,50 @@
 use serde::{Deserialize, Serialize};
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 use crate::commands::assets::{
-    read_file_bytes, read_file_text, resolve_project_root, resolve_study_root,
+    app_data_root, read_file_decoded, read_file_text, resolve_project_root, resolve_study_root,
+    resolve_study_roots,
+};
+use crate::commands::progress::{
+    store_generation_report, StageEvent, StageRecorder, RERENDER_PROGRESS_EVENT,
+    SPEC_PROGRESS_EVENT,
 };
 use crate::llm::commands::llm_extract_prereg_models;
 use crate::llm::model_manager::{
-    download_model_with_policy, model_provenance_from_status, read_project_lock,
+    download_model_with_progress, model_provenance_from_status, read_project_lock,
 };
+use crate::llm::settings::{load_llm_settings, LlmProvider};
+use crate::llm::types::{LlmModelLock, ModelStatus};
 use crate::prereg::parse_docx::parse_prereg_docx;
 use crate::prereg::parse_json::parse_prereg_json;
 use crate::prereg::parse_md::parse_prereg_md;
-use crate::prereg::types::PreregSpec;
-use crate::qsf::parse::{parse_qsf_json, parse_qsf_json_with_tokens};
+use crate::prereg::parse_pdf::parse_prereg_pdf;
+use crate::prereg::types::{PreregSpec, PREREG_LOSSY_DECODE_WARNING};
+use crate::qsf::parse::{parse_qsf_json, parse_qsf_json_with_options, DEFAULT_LOOP_ITERATIONS};
 use crate::qsf::types::QsfSurveySpec;
 use crate::render::helpers::{analysis_paths, ensure_dir, write_string};
 use crate::render::templates::{render_from_spec, template_root_from_cwd};
-use crate::spec::builder::build_analysis_spec;
-use crate::spec::types::{AnalysisSpec, MappingResult};
-use tauri::AppHandle;
+use crate::spec::aliases::{self, read_variable_aliases, upsert_variable_aliases, VariableAlias};
+use crate::spec::builder::{build_analysis_spec, remap_models};
+use crate::spec::codebook::{write_codebook, CodebookOutput, CODEBOOK_DIR};
+use crate::spec::contract::{apply_contract_warning, check_data_contract, DataContractReport};
+use crate::spec::diff::{diff_specs, SpecDiff};
+use crate::spec::persist::{self, read_spec_file, write_spec, SpecHistoryEntry};
+use crate::spec::types::{
+    AnalysisSpec, MappingResult, ResolutionKind, WarningItem, WarningSeverity,
+};
+use crate::spec::value_labels::{
+    apply_value_label_overrides, qsf_value_labels, read_overrides, write_overrides, ValueLabels,
+};
+use crate::spec::warnings::{
+    blocking_codes, WarningCodeInfo, BLOCKING_WARNINGS_UNRESOLVED, WARNING_CATALOG,
+};
+use crate::store::activity::record_activity;
+use crate::store::sqlite::track_generated_artifact;
+use crate::util::csv_stream::{read_header, CsvReadOptions};
+use crate::util::hash::sha256_file;
+use tauri::{AppHandle, Manager};
 
 #[derive(Debug, Clone, Deserialize)]
 #[serde(rename_all = "camelCase")]
@@ -33,6 +58,17 @@ pub struct GenerateSpecArgs {
     pub candidate_tokens: Vec<String>,
     pub template_set: String,
     pub style_profile: String,
+    #[serde(default)]
+    pub model_table_format: Option<String>,
+    #[serde(default)]
+    pub allow_empty_prereg: bool,
+    #[serde(default)]
+    pub llm_enrichment: Option<bool>,
+    /// Weight column applied to every model; overrides a weight found in the prereg text.
+    #[serde(default)]
+    pub weight_var: Option<String>,
+    #[serde(default)]
+    pub loop_iterations: Option<usize>,
 }
 
 #[derive(Debug, Clone, Deserialize)]
@@ -41,6 +77,18 @@ pub struct ParseQsfArgs {
     pub qsf_path: String,
     #[serde(default)]
     pub candidate_tokens: Vec<String>,
+    /// Iterations assumed for Loop & Merge blocks driven by an earlier answer.
+    #[serde(default)]
+    pub loop_iterations: Option<usize>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SaveValueLabelOverridesArgs {
+    pub project_id: String,
+    pub study_id: String,
+    pub analysis_id: String,
+    pub overrides: ValueLabels,
 }
 
 #[derive(Debug, Clone, Deserialize)]
@@ -68,12 +116,52 @@ pub struct ResolveMappingsArgs {
     pub mapping_updates: Vec<MappingUpdate>,
 }
 
+/// Either spec may be passed inline; `before` defaults to the saved spec.json and a missing
+/// `after` is built fresh from `generate` without being saved.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DiffSpecArgs {
+    pub project_id: String,
+    pub study_id: String,
+    pub analysis_id: String,
+    #[serde(default)]
+    pub before: Option<AnalysisSpec>,
+    #[serde(default)]
+    pub after: Option<AnalysisSpec>,
+    #[serde(default)]
+    pub generate: Option<GenerateSpecArgs>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RestoreSpecVersionArgs {
+    pub project_id: String,
+    pub study_id: String,
+    pub analysis_id: String,
+    /// History file name from `list_spec_history`.
+    pub version: String,
+}
+
 #[derive(Debug, Clone, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct RenderArgs {
     pub project_id: String,
     pub study_id: String,
     pub analysis_id: String,
+    /// Renders even while error-severity warnings remain.
+    #[serde(default)]
+    pub force: bool,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ValidateDataContractArgs {
+    pub project_id: String,
+    pub study_id: String,
+    pub analysis_id: String,
+    /// Data file to check; defaults to the spec's `data_raw` binding, relative to the study.
+    #[serde(default)]
+    pub csv_path: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize)]
@@ -83,29 +171,94 @@ pub struct RenderOutput {
     pub r_path: String,
 }
 
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RerenderAllArgs {
+    pub project_id: String,
+    #[serde(default)]
+    pub dry_run: bool,
+    #[serde(default)]
+    pub force: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RerenderItem {
+    pub study_id: String,
+    pub analysis_id: String,
+    pub spec_path: String,
+    pub status: String,
+    pub message: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RerenderReport {
+    pub project_id: String,
+    pub dry_run: bool,
+    pub rendered: usize,
+    pub skipped: usize,
+    pub failed: usize,
+    pub items: Vec<RerenderItem>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct RerenderProgress {
+    project_id: String,
+    index: usize,
+    total: usize,
+    item: RerenderItem,
+}
+
 #[tauri::command]
 pub fn parse_qsf(args: ParseQsfArgs) -> Result<QsfSurveySpec, String> {
     let raw = read_file_text(&args.qsf_path)?;
-    if args.candidate_tokens.is_empty() {
-        parse_qsf_json(&raw)
-    } else {
-        parse_qsf_json_with_tokens(&raw, &args.candidate_tokens)
+    match (args.candidate_tokens.is_empty(), args.loop_iterations) {
+        (true, None) => parse_qsf_json(&raw),
+        (_, loop_iterations) => parse_qsf_json_with_options(
+            &raw,
+            &args.candidate_tokens,
+            loop_iterations.unwrap_or(DEFAULT_LOOP_ITERATIONS),
+        ),
     }
 }
 
+/// Per-column value → label maps from a QSF, for correcting labels before spec generation.
+#[tauri::command]
+pub fn get_qsf_value_labels(args: ParseQsfArgs) -> Result<ValueLabels, String> {
+    Ok(qsf_value_labels(&parse_qsf(args)?))
+}
+
+/// Saves value label overrides for one analysis; the next spec generation merges them in.
+#[tauri::command]
+pub fn save_value_label_overrides(
+    app: AppHandle,
+    args: SaveValueLabelOverridesArgs,
+) -> Result<String, String> {
+    let root = analysis_root(&app, &args.project_id, &args.study_id, &args.analysis_id)?;
+    let path = write_overrides(&root, &args.overrides)?;
+    Ok(path.to_string_lossy().to_string())
+}
+
 #[tauri::command]
 pub fn parse_prereg(prereg_path: String) -> Result<PreregSpec, String> {
     if prereg_path.ends_with(".docx") {
         return parse_prereg_docx(&prereg_path);
     }
-    if prereg_path.ends_with(".md") || prereg_path.ends_with(".markdown") {
-        return Ok(parse_prereg_md(&read_file_text(&prereg_path)?));
+    if prereg_path.to_lowercase().ends_with(".pdf") {
+        return parse_prereg_pdf(&prereg_path);
     }
-    if prereg_path.ends_with(".json") {
-        return parse_prereg_json(&read_file_text(&prereg_path)?);
+    let decoded = read_file_decoded(&prereg_path)?;
+    let mut spec = if prereg_path.ends_with(".json") {
+        parse_prereg_json(&decoded.text)?
+    } else {
+        parse_prereg_md(&decoded.text)
+    };
+    if decoded.lossy {
+        spec.warnings.push(PREREG_LOSSY_DECODE_WARNING.to_string());
     }
-    let text = read_file_text(&prereg_path)?;
-    Ok(parse_prereg_md(&text))
+    Ok(spec)
 }
 
 fn analysis_root(
@@ -123,33 +276,161 @@ pub fn generate_analysis_spec(
     _app: AppHandle,
     args: GenerateSpecArgs,
 ) -> Result<AnalysisSpec, String> {
-    let qsf_bytes = read_file_bytes(&args.qsf_path)?;
-    let prereg_bytes = read_file_bytes(&args.prereg_path)?;
-    let prereg = parse_prereg(args.prereg_path.clone())?;
-    let inferred_tokens = if args.candidate_tokens.is_empty() {
-        collect_candidate_tokens_from_prereg(&prereg)
+    let emitter = _app.clone();
+    let mut recorder = StageRecorder::new(&args.analysis_id, move |event| {
+        let _ = emitter.emit_all(SPEC_PROGRESS_EVENT, event);
+    });
+    let result = run_generation_stages(&_app, &args, &mut recorder);
+    store_generation_report(recorder.finish());
+    result
+}
+
+fn run_generation_stages(
+    app: &AppHandle,
+    args: &GenerateSpecArgs,
+    recorder: &mut StageRecorder,
+) -> Result<AnalysisSpec, String> {
+    let (qsf_sha256, prereg_sha256) = recorder.run("read_inputs", || {
+        Ok((
+            sha256_file(Path::new(&args.qsf_path))?,
+            sha256_file(Path::new(&args.prereg_path))?,
+        ))
+    })?;
+    let prereg = recorder.run("parse_prereg", || {
+        let prereg = parse_prereg(args.prereg_path.clone())?;
+        check_prereg_extraction(&prereg, args.allow_empty_prereg)?;
+        Ok(prereg)
+    })?;
+    let qsf = recorder.run("parse_qsf", || {
+        let inferred_tokens = if args.candidate_tokens.is_empty() {
+            collect_candidate_tokens_from_prereg(&prereg)
+        } else {
+            args.candidate_tokens.clone()
+        };
+        parse_qsf(ParseQsfArgs {
+            qsf_path: args.qsf_path.clone(),
+            candidate_tokens: inferred_tokens,
+            loop_iterations: args.loop_iterations,
+        })
+    })?;
+    let project_root = resolve_project_root(app, &args.project_id)?;
+    let enrichment = if args.llm_enrichment == Some(false) {
+        LlmEnrichment::Disabled
     } else {
-        args.candidate_tokens.clone()
+        // Hosted providers need no local model; the heuristic runs beside the downloaded one.
+        let provider = load_llm_settings(app)
+            .map(|settings| settings.provider)
+            .unwrap_or_default();
+        // A failed model or enrichment stage is recorded in the report, but generation goes
+        // on without enrichment.
+        let model = if provider == LlmProvider::Heuristic {
+            recorder
+                .run("model", || {
+                    download_model_with_progress(
+                        app,
+                        Some(project_root.clone()),
+                        false,
+                        &mut |downloaded, total| {
+                            let event =
+                                StageEvent::progress(&args.analysis_id, "model", downloaded, total);
+                            let _ = app.emit_all(SPEC_PROGRESS_EVENT, event);
+                        },
+                    )
+                })
+                .map(|status| Some(Box::new(status)))
+        } else {
+            Ok(None)
+        };
+        match model {
+            Err(e) => LlmEnrichment::Unavailable(e),
+            Ok(status) => {
+                let output = recorder.run("llm_enrichment", || {
+                    let prereg_text =
+                        read_file_text(&args.prereg_path).unwrap_or_else(|_| String::new());
+                    let qsf_context_for_llm = serde_json::json!({
+                      "expectedColumns": qsf.expected_columns,
+                      "labelMap": qsf.label_map
+                    })
+                    .to_string();
+                    llm_extract_prereg_models(
+                        app.clone(),
+                        prereg_text,
+                        qsf_context_for_llm,
+                        Some(project_root.to_string_lossy().to_string()),
+                    )
+                });
+                match output {
+                    Ok(output_json) => LlmEnrichment::Applied {
+                        status,
+                        output_json,
+                    },
+                    Err(e) => LlmEnrichment::Unavailable(e),
+                }
+            }
+        }
     };
-    let qsf = parse_qsf(ParseQsfArgs {
-        qsf_path: args.qsf_path.clone(),
-        candidate_tokens: inferred_tokens,
-    })?;
-    let prereg_text = read_file_text(&args.prereg_path).unwrap_or_else(|_| String::new());
-    let project_root = resolve_project_root(&_app, &args.project_id)?;
-    let qsf_context_for_llm = serde_json::json!({
-      "expectedColumns": qsf.expected_columns,
-      "labelMap": qsf.label_map
+
+    recorder.run("build_spec", || {
+        let saved = load_saved_spec(app, &args.project_id, &args.study_id, &args.analysis_id).ok();
+        let root = analysis_root(app, &args.project_id, &args.study_id, &args.analysis_id)?;
+        let overrides = read_overrides(&root)?;
+        let aliases = read_variable_aliases(&project_root)?;
+        let mut spec = assemble_spec(
+            args,
+            &qsf_sha256,
+            &prereg_sha256,
+            &qsf,
+            &prereg,
+            enrichment,
+            saved.as_ref(),
+            &aliases,
+        );
+        apply_value_label_overrides(&mut spec, &overrides);
+        Ok(spec)
     })
-    .to_string();
-    let llm_output = llm_extract_prereg_models(
-        _app.clone(),
-        prereg_text,
-        qsf_context_for_llm,
-        Some(project_root.to_string_lossy().to_string()),
-    )?;
+}
+
+/// Outcome of the optional LLM pass; the heuristic prereg parse is used on its own
+/// when the model pipeline is disabled or fails.
+enum LlmEnrichment {
+    Applied {
+        /// Local model the heuristic ran beside; `None` for hosted providers.
+        status: Option<Box<ModelStatus>>,
+        output_json: String,
+    },
+    Disabled,
+    Unavailable(String),
+}
+
+#[allow(clippy::too_many_arguments)]
+fn assemble_spec(
+    args: &GenerateSpecArgs,
+    qsf_sha256: &str,
+    prereg_sha256: &str,
+    qsf: &QsfSurveySpec,
+    prereg: &PreregSpec,
+    enrichment: LlmEnrichment,
+    saved: Option<&AnalysisSpec>,
+    aliases: &[VariableAlias],
+) -> AnalysisSpec {
     let mut prereg_for_build = prereg.clone();
-    apply_llm_prereg_enrichment(&mut prereg_for_build, &llm_output);
+    if let LlmEnrichment::Applied { output_json, .. } = &enrichment {
+        apply_llm_prereg_enrichment(&mut prereg_for_build, output_json);
+    }
+    if let Some(weight) = args
+        .weight_var
+        .as_deref()
+        .map(str::trim)
+        .filter(|w| !w.is_empty())
+    {
+        for model in prereg_for_build
+            .main_analyses
+            .iter_mut()
+            .chain(prereg_for_build.exploratory_analyses.iter_mut())
+        {
+            model.weight_var = Some(weight.to_string());
+        }
+    }
 
     let mut spec = build_analysis_spec(
         &args.project_id,
@@ -157,25 +438,65 @@ pub fn generate_analysis_spec(
         &args.analysis_id,
         &args.qsf_path,
         &args.prereg_path,
-        &qsf_bytes,
-        &prereg_bytes,
-        &qsf,
+        qsf_sha256,
+        prereg_sha256,
+        qsf,
         &prereg_for_build,
         &args.template_set,
         &args.style_profile,
+        aliases,
     );
-    if let Ok(saved) = load_saved_spec(&_app, &args.project_id, &args.study_id, &args.analysis_id) {
-        apply_saved_mappings(&mut spec, &saved);
-    }
-    let model_status = download_model_with_policy(&_app, Some(project_root), false)?;
-    spec.model_provenance = model_provenance_from_status(&model_status);
-    spec.model_lock = model_status.lock.clone();
-    spec.warnings.push(crate::spec::types::WarningItem {
-        code: "LLM_ENRICHMENT_APPLIED".to_string(),
-        message: "LLM extraction enrichment applied to prereg parsing.".to_string(),
-        details: serde_json::json!({}),
-    });
-    Ok(spec)
+    if let Some(saved) = saved {
+        apply_saved_mappings(&mut spec, saved);
+    }
+    spec.outputs.model_table_format = args.model_table_format.clone();
+    match enrichment {
+        LlmEnrichment::Applied {
+            status,
+            output_json,
+        } => {
+            if let Some(status) = status {
+                spec.model_provenance = model_provenance_from_status(&status);
+                spec.model_lock = status.lock.clone();
+            }
+            let provider = serde_json::from_str::<serde_json::Value>(&output_json)
+                .ok()
+                .and_then(|output| output.get("provider").cloned())
+                .unwrap_or(serde_json::Value::Null);
+            spec.warnings.push(WarningItem {
+                code: "LLM_ENRICHMENT_APPLIED".to_string(),
+                message: "LLM extraction enrichment applied to prereg parsing.".to_string(),
+                details: serde_json::json!({ "provider": provider }),
+                severity: WarningSeverity::Info,
+            });
+        }
+        LlmEnrichment::Disabled => {}
+        LlmEnrichment::Unavailable(error) => {
+            spec.warnings.push(WarningItem {
+                code: "LLM_UNAVAILABLE".to_string(),
+                message: "LLM enrichment unavailable; using heuristic prereg extraction only."
+                    .to_string(),
+                details: serde_json::json!({ "error": error }),
+                severity: WarningSeverity::Info,
+            });
+        }
+    }
+    spec
+}
+
+/// Refuses preregs where nothing usable was extracted (e.g. a cover letter was picked),
+/// returning a structured `PREREG_EXTRACTION_EMPTY` error the UI can parse.
+fn check_prereg_extraction(prereg: &PreregSpec, allow_empty: bool) -> Result<(), String> {
+    if allow_empty || !prereg.extraction_summary.is_empty() {
+        return Ok(());
+    }
+    let error = WarningItem {
+        code: "PREREG_EXTRACTION_EMPTY".to_string(),
+        message: "No models or variables were found in the preregistration. Check that the correct file was selected.".to_string(),
+        details: serde_json::to_value(&prereg.extraction_summary).unwrap_or_default(),
+        severity: WarningSeverity::Error,
+    };
+    Err(serde_json::to_string(&error).map_err(|e| e.to_string())?)
 }
 
 fn apply_llm_prereg_enrichment(prereg: &mut PreregSpec, llm_output_json: &str) {
@@ -186,6 +507,16 @@ fn apply_llm_prereg_enrichment(prereg: &mut PreregSpec, llm_output_json: &str) {
         .cloned()
         .unwrap_or_else(|| serde_json::json!({}));
 
+    // LLM models replace the heuristic ones, so keep any weight or family declared in the
+    // prereg text.
+    let heuristic_weight = prereg
+        .main_analyses
+        .iter()
+        .find_map(|m| m.weight_var.clone());
+    let heuristic_family = prereg
+        .main_analyses
+        .iter()
+        .find_map(|m| m.family_hint.clone());
     let map_models = |items: &serde_json::Value| -> Vec<crate::prereg::types::AnalysisModelSpec> {
         items
             .as_array()
@@ -233,6 +564,10 @@ fn apply_llm_prereg_enrichment(prereg: &mut PreregSpec, llm_output_json: &str) {
                     controls,
                     interaction_terms,
                     formula,
+                    weight_var: heuristic_weight.clone(),
+                    family_hint: heuristic_family.clone(),
+                    dv_transform: None,
+                    predictor_transforms: Vec::new(),
                 })
             })
             .collect()
@@ -329,9 +664,7 @@ fn load_saved_spec(
     if !spec_path.exists() {
         return Err("No saved spec".to_string());
     }
-    let raw =
-        fs::read_to_string(&spec_path).map_err(|e| format!("Unable to read saved spec: {e}"))?;
-    serde_json::from_str(&raw).map_err(|e| format!("Invalid saved spec.json: {e}"))
+    read_spec_file(&spec_path)
 }
 
 fn apply_saved_mappings(spec: &mut AnalysisSpec, saved: &AnalysisSpec) {
@@ -343,6 +676,8 @@ fn apply_saved_mappings(spec: &mut AnalysisSpec, saved: &AnalysisSpec) {
         {
             if previous.resolved_to.is_some() {
                 current.resolved_to = previous.resolved_to.clone();
+                current.resolution_kind = previous.resolution_kind;
+                current.derived_sources = previous.derived_sources.clone();
             }
         }
     }
@@ -372,13 +707,49 @@ pub fn save_analysis_spec(app: AppHandle, args: SaveSpecArgs) -> Result<(), Stri
     let root = analysis_root(&app, &args.project_id, &args.study_id, &args.analysis_id)?;
     ensure_dir(&root.join("analysis"))?;
     let (spec_path, _, _) = analysis_paths(&root);
-    write_string(
-        &spec_path,
-        &serde_json::to_string_pretty(&args.spec).map_err(|e| e.to_string())?,
-    )
+    write_spec(&spec_path, &args.spec)
 }
 
-fn read_spec(
+#[tauri::command]
+pub fn list_spec_history(
+    app: AppHandle,
+    args: RenderArgs,
+) -> Result<Vec<SpecHistoryEntry>, String> {
+    let root = analysis_root(&app, &args.project_id, &args.study_id, &args.analysis_id)?;
+    let (spec_path, _, _) = analysis_paths(&root);
+    Ok(persist::list_spec_history(&spec_path))
+}
+
+#[tauri::command]
+pub fn restore_spec_version(
+    app: AppHandle,
+    args: RestoreSpecVersionArgs,
+) -> Result<AnalysisSpec, String> {
+    let root = analysis_root(&app, &args.project_id, &args.study_id, &args.analysis_id)?;
+    let (spec_path, _, _) = analysis_paths(&root);
+    persist::restore_spec_version(&spec_path, &args.version)
+}
+
+#[tauri::command]
+pub fn diff_analysis_spec(app: AppHandle, args: DiffSpecArgs) -> Result<SpecDiff, String> {
+    let before = match args.before {
+        Some(spec) => spec,
+        None => load_saved_spec(&app, &args.project_id, &args.study_id, &args.analysis_id)?,
+    };
+    let after = match (args.after, args.generate) {
+        (Some(spec), _) => spec,
+        (None, Some(generate)) => {
+            let mut recorder = StageRecorder::new(&generate.analysis_id, |_| {});
+            run_generation_stages(&app, &generate, &mut recorder)?
+        }
+        (None, None) => {
+            return Err("Pass the regenerated spec or generation arguments to diff against.".into())
+        }
+    };
+    Ok(diff_specs(&before, &after))
+}
+
+pub(crate) fn read_spec(
     app: &AppHandle,
     project_id: &str,
     study_id: &str,
@@ -386,40 +757,212 @@ fn read_spec(
 ) -> Result<AnalysisSpec, String> {
     let root = analysis_root(app, project_id, study_id, analysis_id)?;
     let (spec_path, _, _) = analysis_paths(&root);
-    let raw = fs::read_to_string(&spec_path).map_err(|e| format!("Unable to read spec: {e}"))?;
-    serde_json::from_str(&raw).map_err(|e| format!("Invalid spec.json: {e}"))
+    read_spec_file(&spec_path)
 }
 
-#[tauri::command]
-pub fn resolve_mappings(app: AppHandle, args: ResolveMappingsArgs) -> Result<AnalysisSpec, String> {
-    let mut spec = read_spec(&app, &args.project_id, &args.study_id, &args.analysis_id)?;
-    for upd in args.mapping_updates {
-        if let Some(m) = spec
+#[derive(Debug, Clone, Serialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct MappingChange {
+    pub prereg_var: String,
+    pub from: Option<String>,
+    pub to: String,
+}
+
+#[derive(Debug, Clone, Serialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct FormulaChange {
+    pub group: String,
+    pub model_id: String,
+    pub from: String,
+    pub to: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MappingResolutionPreview {
+    pub mapping_changes: Vec<MappingChange>,
+    pub removed_warnings: Vec<WarningItem>,
+    pub formula_changes: Vec<FormulaChange>,
+    pub spec: AnalysisSpec,
+}
+
+/// Applies mapping updates to a copy of the spec, recomputing model formulas and dropping
+/// warnings that the new resolutions settle.
+fn apply_mapping_updates(
+    spec: &AnalysisSpec,
+    updates: &[MappingUpdate],
+) -> MappingResolutionPreview {
+    let mut next = spec.clone();
+    let mut mapping_changes = Vec::new();
+    for upd in updates {
+        if let Some(m) = next
             .variable_mappings
             .iter_mut()
             .find(|m| m.prereg_var.eq_ignore_ascii_case(&upd.prereg_var))
         {
+            if m.resolved_to.as_deref() != Some(upd.resolved_to.as_str()) {
+                mapping_changes.push(MappingChange {
+                    prereg_var: m.prereg_var.clone(),
+                    from: m.resolved_to.clone(),
+                    to: upd.resolved_to.clone(),
+                });
+            }
             m.resolved_to = Some(upd.resolved_to.clone());
+            m.resolution_kind = ResolutionKind::Manual;
+            m.derived_sources.clear();
+            m.reason = None;
         } else {
-            spec.variable_mappings.push(MappingResult {
-                prereg_var: upd.prereg_var,
-                resolved_to: Some(upd.resolved_to),
+            mapping_changes.push(MappingChange {
+                prereg_var: upd.prereg_var.clone(),
+                from: None,
+                to: upd.resolved_to.clone(),
+            });
+            next.variable_mappings.push(MappingResult {
+                prereg_var: upd.prereg_var.clone(),
+                resolved_to: Some(upd.resolved_to.clone()),
                 candidates: Vec::new(),
+                resolution_kind: ResolutionKind::Manual,
+                derived_sources: Vec::new(),
+                reason: None,
             });
         }
     }
-    spec.warnings
-        .retain(|w| !(w.code == "UNRESOLVED_VARIABLE" && is_mapped(&spec.variable_mappings, w)));
 
+    let (kept, removed_warnings): (Vec<WarningItem>, Vec<WarningItem>) = next
+        .warnings
+        .into_iter()
+        .partition(|w| !(w.code == "UNRESOLVED_VARIABLE" && is_mapped(&next.variable_mappings, w)));
+    next.warnings = kept;
+
+    next.models = remap_models(
+        &spec.models,
+        &spec.variable_mappings,
+        &next.variable_mappings,
+    );
+    let mut formula_changes = Vec::new();
+    for (group, before, after) in [
+        ("main", &spec.models.main, &next.models.main),
+        (
+            "exploratory",
+            &spec.models.exploratory,
+            &next.models.exploratory,
+        ),
+        (
+            "robustness",
+            &spec.models.robustness,
+            &next.models.robustness,
+        ),
+    ] {
+        for (old, new) in before.iter().zip(after.iter()) {
+            if old.formula != new.formula {
+                formula_changes.push(FormulaChange {
+                    group: group.to_string(),
+                    model_id: new.id.clone(),
+                    from: old.formula.clone(),
+                    to: new.formula.clone(),
+                });
+            }
+        }
+    }
+
+    MappingResolutionPreview {
+        mapping_changes,
+        removed_warnings,
+        formula_changes,
+        spec: next,
+    }
+}
+
+fn preview_resolution_at(
+    spec_path: &Path,
+    updates: &[MappingUpdate],
+) -> Result<MappingResolutionPreview, String> {
+    Ok(apply_mapping_updates(&read_spec_file(spec_path)?, updates))
+}
+
+fn commit_resolution_at(
+    spec_path: &Path,
+    updates: &[MappingUpdate],
+) -> Result<AnalysisSpec, String> {
+    let spec = preview_resolution_at(spec_path, updates)?.spec;
+    write_spec(spec_path, &spec)?;
+    Ok(spec)
+}
+
+/// Shows what `resolve_mappings` would change without writing spec.json.
+#[tauri::command]
+pub fn preview_mapping_resolution(
+    app: AppHandle,
+    args: ResolveMappingsArgs,
+) -> Result<MappingResolutionPreview, String> {
     let root = analysis_root(&app, &args.project_id, &args.study_id, &args.analysis_id)?;
     let (spec_path, _, _) = analysis_paths(&root);
-    write_string(
-        &spec_path,
-        &serde_json::to_string_pretty(&spec).map_err(|e| e.to_string())?,
-    )?;
+    preview_resolution_at(&spec_path, &args.mapping_updates)
+}
+
+/// Commits the updates to spec.json and remembers each one in the project alias dictionary.
+#[tauri::command]
+pub fn resolve_mappings(app: AppHandle, args: ResolveMappingsArgs) -> Result<AnalysisSpec, String> {
+    let root = analysis_root(&app, &args.project_id, &args.study_id, &args.analysis_id)?;
+    let (spec_path, _, _) = analysis_paths(&root);
+    let spec = commit_resolution_at(&spec_path, &args.mapping_updates)?;
+    let project_root = resolve_project_root(&app, &args.project_id)?;
+    upsert_variable_aliases(&project_root, &alias_entries(&args.mapping_updates))?;
     Ok(spec)
 }
 
+/// Manual resolutions worth remembering: placeholders and empty picks are skipped.
+fn alias_entries(updates: &[MappingUpdate]) -> Vec<(String, String)> {
+    updates
+        .iter()
+        .filter(|u| !u.resolved_to.trim().is_empty() && !u.resolved_to.starts_with("TODO_"))
+        .map(|u| (u.prereg_var.clone(), u.resolved_to.clone()))
+        .collect()
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct VariableAliasArgs {
+    project_id: String,
+    #[serde(default)]
+    prereg_var: Option<String>,
+    #[serde(default)]
+    column: Option<String>,
+}
+
+#[tauri::command]
+pub fn list_variable_aliases(
+    app: AppHandle,
+    args: VariableAliasArgs,
+) -> Result<Vec<VariableAlias>, String> {
+    read_variable_aliases(&resolve_project_root(&app, &args.project_id)?)
+}
+
+#[tauri::command]
+pub fn add_variable_alias(
+    app: AppHandle,
+    args: VariableAliasArgs,
+) -> Result<Vec<VariableAlias>, String> {
+    let project_root = resolve_project_root(&app, &args.project_id)?;
+    let entry = (
+        args.prereg_var.unwrap_or_default(),
+        args.column.unwrap_or_default(),
+    );
+    upsert_variable_aliases(&project_root, &[entry])
+}
+
+#[tauri::command]
+pub fn remove_variable_alias(
+    app: AppHandle,
+    args: VariableAliasArgs,
+) -> Result<Vec<VariableAlias>, String> {
+    let project_root = resolve_project_root(&app, &args.project_id)?;
+    aliases::remove_variable_alias(
+        &project_root,
+        args.prereg_var.as_deref().unwrap_or_default(),
+    )
+}
+
 fn is_mapped(mappings: &[MappingResult], warning: &crate::spec::types::WarningItem) -> bool {
     let prereg_var = warning
         .details
@@ -431,20 +974,152 @@ fn is_mapped(mappings: &[MappingResult], warning: &crate::spec::types::WarningIt
         .any(|m| m.prereg_var.eq_ignore_ascii_case(prereg_var) && m.resolved_to.is_some())
 }
 
+fn validate_contract_at(
+    spec_path: &Path,
+    study_root: &Path,
+    csv_path: Option<&str>,
+) -> Result<DataContractReport, String> {
+    let mut spec = read_spec_file(spec_path)?;
+    let csv_path = match csv_path.map(str::trim).filter(|p| !p.is_empty()) {
+        Some(path) => PathBuf::from(path),
+        None => {
+            let data_raw = spec
+                .template_bindings
+                .paths
+                .get("data_raw")
+                .ok_or_else(|| "The spec has no data_raw path; choose a data file.".to_string())?;
+            study_root.join(data_raw.trim())
+        }
+    };
+    if !csv_path.is_file() {
+        return Err(format!("Data file not found: {}", csv_path.display()));
+    }
+    let header = read_header(&csv_path, &CsvReadOptions::default())?;
+    let report = check_data_contract(&spec, &csv_path.to_string_lossy(), &header.columns);
+    if apply_contract_warning(&mut spec, &report) {
+        write_spec(spec_path, &spec)?;
+    }
+    Ok(report)
+}
+
+/// Checks a data file's header against the saved spec and records the outcome as a
+/// `DATA_CONTRACT_MISMATCH` warning in spec.json.
+#[tauri::command]
+pub fn validate_data_contract(
+    app: AppHandle,
+    args: ValidateDataContractArgs,
+) -> Result<DataContractReport, String> {
+    let study_root = resolve_study_root(&app, &args.project_id, &args.study_id)?;
+    let root = analysis_root(&app, &args.project_id, &args.study_id, &args.analysis_id)?;
+    let (spec_path, _, _) = analysis_paths(&root);
+    validate_contract_at(&spec_path, &study_root, args.csv_path.as_deref())
+}
+
+/// Writes codebook.csv and codebook.md for the saved spec into the study's reports folder.
+#[tauri::command]
+pub fn generate_codebook(app: AppHandle, args: RenderArgs) -> Result<CodebookOutput, String> {
+    let spec = read_spec(&app, &args.project_id, &args.study_id, &args.analysis_id)?;
+    let study_root = resolve_study_root(&app, &args.project_id, &args.study_id)?;
+    let output = write_codebook(&spec, &study_root.join(CODEBOOK_DIR))?;
+
+    let app_root = app_data_root(&app)?;
+    for path in [&output.csv_path, &output.markdown_path] {
+        let path = PathBuf::from(path);
+        track_generated_artifact(
+            &app_root,
+            &args.study_id,
+            "codebook",
+            &path,
+            path.file_name().and_then(|name| name.to_str()),
+        );
+    }
+    record_activity(
+        &app_root,
+        "generate_codebook",
+        Some(&args.project_id),
+        Some(&args.study_id),
+        &format!("Generated codebook for analysis {}", args.analysis_id),
+    );
+    Ok(output)
+}
+
 #[tauri::command]
 pub fn render_analysis_from_spec(app: AppHandle, args: RenderArgs) -> Result<RenderOutput, String> {
     let spec = read_spec(&app, &args.project_id, &args.study_id, &args.analysis_id)?;
+    if !args.force {
+        ensure_no_blocking_warnings(&spec)?;
+    }
     let root = analysis_root(&app, &args.project_id, &args.study_id, &args.analysis_id)?;
+    let project_root = resolve_project_root(&app, &args.project_id)?;
+    let project_lock = read_project_lock(&project_root)?;
+    let template_root = template_root_from_cwd(Some(&project_root));
+    let output = render_spec_in_root(&spec, &root, template_root.as_deref(), project_lock)?;
+
+    let app_root = app_data_root(&app)?;
+    let rendered = [
+        PathBuf::from(&output.rmd_path),
+        PathBuf::from(&output.r_path),
+        provenance_path(&root),
+    ];
+    for path in &rendered {
+        track_generated_artifact(
+            &app_root,
+            &args.study_id,
+            "rendered_analysis",
+            path,
+            path.file_name().and_then(|name| name.to_str()),
+        );
+    }
+    record_activity(
+        &app_root,
+        "render_analysis_from_spec",
+        Some(&args.project_id),
+        Some(&args.study_id),
+        &format!("Rendered analysis {}", args.analysis_id),
+    );
+    Ok(output)
+}
+
+/// Structured error (JSON `WarningItem`) listing the error-severity codes still on `spec`.
+fn ensure_no_blocking_warnings(spec: &AnalysisSpec) -> Result<(), String> {
+    let codes = blocking_codes(&spec.warnings);
+    if codes.is_empty() {
+        return Ok(());
+    }
+    let error = WarningItem {
+        code: BLOCKING_WARNINGS_UNRESOLVED.to_string(),
+        message: format!(
+            "Resolve {} before rendering, or render with force.",
+            codes.join(", ")
+        ),
+        details: serde_json::json!({ "codes": codes }),
+        severity: WarningSeverity::Error,
+    };
+    Err(serde_json::to_string(&error).map_err(|e| e.to_string())?)
+}
+
+#[tauri::command]
+pub fn list_warning_codes() -> Vec<WarningCodeInfo> {
+    WARNING_CATALOG.to_vec()
+}
+
+fn provenance_path(root: &Path) -> PathBuf {
+    root.join("analysis").join("analysis_provenance.json")
+}
+
+fn render_spec_in_root(
+    spec: &AnalysisSpec,
+    root: &Path,
+    template_root: Option<&Path>,
+    project_lock: Option<LlmModelLock>,
+) -> Result<RenderOutput, String> {
     ensure_dir(&root.join("analysis"))?;
     ensure_dir(&root.join("tables"))?;
     ensure_dir(&root.join("figures"))?;
 
-    let (_, rmd_path, r_path) = analysis_paths(&root);
-    let metadata_path = root.join("analysis").join("analysis_provenance.json");
-    let project_root = resolve_project_root(&app, &args.project_id)?;
-    let project_lock = read_project_lock(&project_root)?;
-    let template_root = template_root_from_cwd()?;
-    render_from_spec(&spec, &template_root, &rmd_path, &r_path)?;
+    let (_, rmd_path, r_path) = analysis_paths(root);
+    let metadata_path = provenance_path(root);
+    let template_source = render_from_spec(spec, template_root, &rmd_path, &r_path)?;
     write_string(
         &metadata_path,
         &serde_json::to_string_pretty(&serde_json::json!({
@@ -454,6 +1129,7 @@ pub fn render_analysis_from_spec(app: AppHandle, args: RenderArgs) -> Result<Ren
           "appVersion": env!("CARGO_PKG_VERSION"),
           "modelProvenance": spec.model_provenance,
           "projectLock": spec.model_lock.clone().or(project_lock),
+          "templateSource": template_source,
         }))
         .map_err(|e| e.to_string())?,
     )?;
@@ -463,3 +1139,636 @@ pub fn render_analysis_from_spec(app: AppHandle, args: RenderArgs) -> Result<Ren
         r_path: r_path.to_string_lossy().to_string(),
     })
 }
+
+/// Re-renders every spec-based analysis in a project, e.g. after a style kit update.
+#[tauri::command]
+pub fn rerender_all_analyses(
+    app: AppHandle,
+    args: RerenderAllArgs,
+) -> Result<RerenderReport, String> {
+    let studies = resolve_study_roots(&app, &args.project_id)?;
+    let project_root = resolve_project_root(&app, &args.project_id)?;
+    let project_lock = read_project_lock(&project_root)?;
+    let template_root = template_root_from_cwd(Some(&project_root));
+    Ok(rerender_analyses(
+        &args,
+        &studies,
+        template_root.as_deref(),
+        project_lock,
+        &mut |index, total, item| {
+            let _ = app.emit_all(
+                RERENDER_PROGRESS_EVENT,
+                RerenderProgress {
+                    project_id: args.project_id.clone(),
+                    index,
+                    total,
+                    item: item.clone(),
+                },
+            );
+        },
+    ))
+}
+
+fn discover_spec_paths(studies: &[(String, PathBuf)]) -> Vec<(String, String, PathBuf)> {
+    let mut out = Vec::new();
+    for (study_id, study_root) in studies {
+        let Ok(entries) = fs::read_dir(study_root.join("06_analysis")) else {
+            continue;
+        };
+        let mut found = entries
+            .filter_map(|entry| entry.ok())
+            .map(|entry| entry.path())
+            .filter(|path| path.is_dir())
+            .filter_map(|dir| {
+                let (spec_path, _, _) = analysis_paths(&dir);
+                let analysis_id = dir.file_name()?.to_string_lossy().to_string();
+                spec_path
+                    .is_file()
+                    .then(|| (study_id.clone(), analysis_id, spec_path))
+            })
+            .collect::<Vec<(String, String, PathBuf)>>();
+        found.sort_by(|a, b| a.1.cmp(&b.1));
+        out.extend(found);
+    }
+    out
+}
+
+fn rerender_analyses(
+    args: &RerenderAllArgs,
+    studies: &[(String, PathBuf)],
+    template_root: Option<&Path>,
+    project_lock: Option<LlmModelLock>,
+    on_item: &mut dyn FnMut(usize, usize, &RerenderItem),
+) -> RerenderReport {
+    let targets = discover_spec_paths(studies);
+    let total = targets.len();
+    let mut report = RerenderReport {
+        project_id: args.project_id.clone(),
+        dry_run: args.dry_run,
+        rendered: 0,
+        skipped: 0,
+        failed: 0,
+        items: Vec::new(),
+    };
+
+    for (index, (study_id, analysis_id, spec_path)) in targets.into_iter().enumerate() {
+        let spec = read_spec_file(&spec_path);
+        let (status, message) = match spec {
+            Err(e) => ("failed", Some(e)),
+            Ok(spec) => {
+                let unresolved = spec
+                    .warnings
+                    .iter()
+                    .filter(|w| w.code == "UNRESOLVED_VARIABLE")
+                    .count();
+                if unresolved > 0 && !args.force {
+                    (
+                        "skipped",
+                        Some(format!(
+                            "{unresolved} unresolved variable(s); resolve mappings or use force."
+                        )),
+                    )
+                } else if args.dry_run {
+                    ("would_render", None)
+                } else {
+                    // spec.json lives in <analysis_root>/analysis/.
+                    let root = spec_path
+                        .parent()
+                        .and_then(Path::parent)
+                        .unwrap_or(Path::new("."));
+                    match render_spec_in_root(&spec, root, template_root, project_lock.clone()) {
+                        Ok(_) => ("rendered", None),
+                        Err(e) => ("failed", Some(e)),
+                    }
+                }
+            }
+        };
+        match status {
+            "failed" => report.failed += 1,
+            "skipped" => report.skipped += 1,
+            _ => report.rendered += 1,
+        }
+        let item = RerenderItem {
+            study_id,
+            analysis_id,
+            spec_path: spec_path.to_string_lossy().to_string(),
+            status: status.to_string(),
+            message,
+        };
+        on_item(index + 1, total, &item);
+        report.items.push(item);
+    }
+    report
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        apply_llm_prereg_enrichment, assemble_spec, check_prereg_extraction, commit_resolution_at,
+        ensure_no_blocking_warnings, preview_resolution_at, rerender_analyses,
+        validate_contract_at, GenerateSpecArgs, LlmEnrichment, MappingUpdate, RerenderAllArgs,
+    };
+    use crate::llm::commands::prereg_models_output;
+    use crate::llm::model_manager::{ensure_model_downloaded, resolve_target_model};
+    use crate::llm::settings::{LlmProvider, LlmSettings, UpdatePolicy};
+    use crate::prereg::parse_md::parse_prereg_md;
+    use crate::qsf::parse::parse_qsf_json;
+    use crate::render::templates::template_root_from_cwd;
+    use crate::spec::builder::build_analysis_spec;
+    use crate::spec::persist::read_spec_file;
+    use crate::spec::types::{AnalysisSpec, ResolutionKind, WarningSeverity};
+    use std::fs;
+    use std::path::Path;
+
+    fn write_spec_folder(study_root: &Path, analysis_id: &str, contents: &str) {
+        let dir = study_root
+            .join("06_analysis")
+            .join(analysis_id)
+            .join("analysis");
+        fs::create_dir_all(&dir).expect("mkdir");
+        fs::write(dir.join("spec.json"), contents).expect("write spec");
+    }
+
+    fn fixture_spec(analysis_id: &str) -> AnalysisSpec {
+        let qsf = parse_qsf_json(
+            r#"{"SurveyEntry":{"SurveyName":"T"},"SurveyElements":[
+              {"Element":"SQ","Payload":{"QuestionID":"QID1","DataExportTag":"wellbeing","QuestionText":"Wellbeing","QuestionType":{"Type":"TE"}}},
+              {"Element":"SQ","Payload":{"QuestionID":"QID2","DataExportTag":"condition","QuestionText":"Condition","QuestionType":{"Type":"MC"}}}
+            ]}"#,
+        )
+        .expect("qsf");
+        let prereg = parse_prereg_md("DV: wellbeing\nIV: condition\n\nwellbeing ~ condition\n");
+        build_analysis_spec(
+            "p",
+            "S1",
+            analysis_id,
+            "q",
+            "p",
+            "q",
+            "p",
+            &qsf,
+            &prereg,
+            "apa_v1",
+            "apa_flextable_ggpubr",
+            &[],
+        )
+    }
+
+    #[test]
+    fn empty_prereg_is_refused_with_structured_error() {
+        let prereg = parse_prereg_md("Dear editor,\n\nPlease find our manuscript attached.\n");
+        assert!(prereg.extraction_summary.is_empty());
+        let err = check_prereg_extraction(&prereg, false).expect_err("should refuse");
+        let parsed: serde_json::Value = serde_json::from_str(&err).expect("json error");
+        assert_eq!(parsed["code"], "PREREG_EXTRACTION_EMPTY");
+        assert_eq!(parsed["details"]["models"], 0);
+        assert_eq!(parsed["details"]["variables"], 0);
+    }
+
+    #[test]
+    fn spec_without_severities_loads_and_blocks_render_on_errors() {
+        let mut value = serde_json::to_value(fixture_spec("legacy")).expect("spec json");
+        // spec.json as written before warnings carried a severity.
+        value["warnings"] = serde_json::json!([
+            {"code": "UNRESOLVED_VARIABLE", "message": "m", "details": {"preregVar": "age"}},
+            {"code": "LLM_ENRICHMENT_APPLIED", "message": "m", "details": {}},
+            {"code": "SOMETHING_NEWER", "message": "m", "details": {}}
+        ]);
+        let mut spec: AnalysisSpec = serde_json::from_value(value).expect("legacy spec loads");
+        let severities = spec.warnings.iter().map(|w| w.severity).collect::<Vec<_>>();
+        assert_eq!(
+            severities,
+            vec![
+                WarningSeverity::Error,
+                WarningSeverity::Info,
+                WarningSeverity::Warning
+            ]
+        );
+        let saved = serde_json::to_value(&spec).expect("json");
+        assert_eq!(saved["warnings"][0]["severity"], "error");
+
+        let err = ensure_no_blocking_warnings(&spec).expect_err("blocked");
+        let parsed: serde_json::Value = serde_json::from_str(&err).expect("json error");
+        assert_eq!(parsed["code"], "BLOCKING_WARNINGS_UNRESOLVED");
+        assert_eq!(
+            parsed["details"]["codes"],
+            serde_json::json!(["UNRESOLVED_VARIABLE"])
+        );
+        spec.warnings.retain(|w| w.code != "UNRESOLVED_VARIABLE");
+        assert!(ensure_no_blocking_warnings(&spec).is_ok());
+    }
+
+    #[test]
+    fn allow_empty_prereg_overrides_gate() {
+        let prereg = parse_prereg_md("");
+        assert!(check_prereg_extraction(&prereg, true).is_ok());
+    }
+
+    #[test]
+    fn normal_prereg_passes_gate() {
+        let prereg =
+            parse_prereg_md("DV: wellbeing\nIV: condition\n\nwellbeing ~ condition + age\n");
+        assert!(prereg.extraction_summary.models > 0);
+        assert!(prereg.extraction_summary.variables > 0);
+        assert!(prereg.extraction_summary.score > 0);
+        assert!(check_prereg_extraction(&prereg, false).is_ok());
+    }
+
+    /// Answers one chat-completions request per entry in `replies` with that message content
+    /// and hands back each request's head and body.
+    fn serve_chat_completions(
+        replies: Vec<&'static str>,
+    ) -> (String, std::sync::mpsc::Receiver<String>) {
+        use std::io::{BufRead, Read, Write};
+
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").expect("bind");
+        let addr = listener.local_addr().expect("addr");
+        let (sender, requests) = std::sync::mpsc::channel();
+        std::thread::spawn(move || {
+            for content in replies {
+                let (mut stream, _) = listener.accept().expect("accept");
+                let mut reader = std::io::BufReader::new(stream.try_clone().expect("clone"));
+                let mut head = String::new();
+                let mut length = 0usize;
+                loop {
+                    let mut line = String::new();
+                    reader.read_line(&mut line).expect("request line");
+                    if line.trim().is_empty() {
+                        break;
+                    }
+                    if let Some((name, value)) = line.split_once(':') {
+                        if name.eq_ignore_ascii_case("content-length") {
+                            length = value.trim().parse().expect("length");
+                        }
+                    }
+                    head.push_str(&line);
+                }
+                let mut body = vec![0u8; length];
+                reader.read_exact(&mut body).expect("body");
+                let _ = sender.send(format!("{head}\n{}", String::from_utf8_lossy(&body)));
+                let payload = serde_json::json!({
+                  "choices": [{ "message": { "role": "assistant", "content": content } }]
+                })
+                .to_string();
+                let _ = write!(
+                    stream,
+                    "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{payload}",
+                    payload.len()
+                );
+            }
+        });
+        (format!("http://{addr}/v1"), requests)
+    }
+
+    #[test]
+    fn provider_output_merges_like_heuristic_output_and_falls_back_on_bad_replies() {
+        let (base_url, requests) = serve_chat_completions(vec![
+            "```json\n{\"mainModels\": [{\"dv\": \"wellbeing\", \"iv\": [\"condition\"], \"controls\": [\"age\"]}], \"robustnessChecks\": [\"with_without_controls\"]}\n```",
+            "{\"mainModels\": [{\"iv\": [\"condition\"]}]}",
+        ]);
+        let settings = LlmSettings {
+            model_dir: String::new(),
+            update_policy: UpdatePolicy::Stable,
+            stable_tag: String::new(),
+            asset_name: "m.gguf".to_string(),
+            stable_sha256: None,
+            github_owner: String::new(),
+            github_repo: String::new(),
+            allow_prerelease: false,
+            auto_check_days: 1,
+            last_checked_utc: None,
+            last_error: None,
+            provider: LlmProvider::OpenaiCompatible,
+            base_url: Some(base_url.clone()),
+            api_key: Some("secret".to_string()),
+            model: Some("llama-3".to_string()),
+        };
+        let doc = "We will regress mood on condition.\n\nmood ~ condition\n";
+        let context =
+            serde_json::json!({ "expectedColumns": ["wellbeing", "condition", "age"] }).to_string();
+
+        let output = prereg_models_output(&settings, doc, &context, None).expect("remote output");
+        let request = requests.recv().expect("request");
+        assert!(request.starts_with("POST /v1/chat/completions "));
+        assert!(request.contains("Bearer secret"));
+        assert!(request.contains("\"model\":\"llama-3\""));
+        let value: serde_json::Value = serde_json::from_str(&output).expect("output json");
+        assert_eq!(
+            value["provider"],
+            serde_json::json!({ "name": "openai_compatible", "baseUrl": base_url, "model": "llama-3" })
+        );
+        let mut prereg = parse_prereg_md(doc);
+        apply_llm_prereg_enrichment(&mut prereg, &output);
+        assert_eq!(prereg.main_analyses.len(), 1);
+        let model = &prereg.main_analyses[0];
+        assert_eq!(model.id, "llm_m1");
+        assert_eq!(model.dv, "wellbeing");
+        assert_eq!(model.controls, vec!["age".to_string()]);
+        assert_eq!(
+            model.formula.as_deref(),
+            Some("wellbeing ~ condition + age")
+        );
+        assert_eq!(
+            prereg.robustness_checks,
+            vec!["with_without_controls".to_string()]
+        );
+
+        let output = prereg_models_output(&settings, doc, &context, None).expect("fallback");
+        let value: serde_json::Value = serde_json::from_str(&output).expect("output json");
+        assert_eq!(value["provider"]["name"], "heuristic");
+        assert_eq!(value["provider"]["fallbackFrom"], "openai_compatible");
+        let ambiguities = value["parsed"]["ambiguities"]
+            .as_array()
+            .expect("ambiguities");
+        assert!(ambiguities
+            .iter()
+            .any(|a| a.as_str().is_some_and(|a| a.contains("missing \"dv\""))));
+        let mut prereg = parse_prereg_md(doc);
+        apply_llm_prereg_enrichment(&mut prereg, &output);
+        assert_eq!(prereg.main_analyses[0].dv, "mood");
+    }
+
+    #[test]
+    fn spec_generation_survives_unavailable_model() {
+        let model_dir = std::env::temp_dir().join(format!("llm-missing-{}", uuid::Uuid::new_v4()));
+        let settings = LlmSettings {
+            model_dir: model_dir.to_string_lossy().to_string(),
+            update_policy: UpdatePolicy::Stable,
+            stable_tag: "v1.0.0".to_string(),
+            asset_name: "m.gguf".to_string(),
+            stable_sha256: None,
+            github_owner: String::new(),
+            github_repo: String::new(),
+            allow_prerelease: false,
+            auto_check_days: 1,
+            last_checked_utc: None,
+            last_error: None,
+            provider: LlmProvider::Heuristic,
+            base_url: None,
+            api_key: None,
+            model: None,
+        };
+        let target = resolve_target_model(None, &settings).expect("target");
+        let model_error = ensure_model_downloaded(target, &settings, &mut |_, _| {})
+            .expect_err("model should be unavailable");
+
+        let qsf = parse_qsf_json(
+            r#"{"SurveyEntry":{"SurveyName":"T"},"SurveyElements":[
+              {"Element":"SQ","Payload":{"QuestionID":"QID1","DataExportTag":"wellbeing","QuestionText":"Wellbeing","QuestionType":{"Type":"TE"}}}
+            ]}"#,
+        )
+        .expect("qsf");
+        let prereg = parse_prereg_md("DV: wellbeing\nIV: condition\n\nwellbeing ~ condition\n");
+        let args = GenerateSpecArgs {
+            project_id: "p".to_string(),
+            study_id: "s".to_string(),
+            analysis_id: "a".to_string(),
+            qsf_path: "survey.qsf".to_string(),
+            prereg_path: "prereg.md".to_string(),
+            candidate_tokens: vec![],
+            template_set: "apa_v1".to_string(),
+            style_profile: "apa_flextable_ggpubr".to_string(),
+            model_table_format: None,
+            allow_empty_prereg: false,
+            llm_enrichment: None,
+            weight_var: None,
+            loop_iterations: None,
+        };
+
+        let spec = assemble_spec(
+            &args,
+            "q",
+            "p",
+            &qsf,
+            &prereg,
+            LlmEnrichment::Unavailable(model_error.clone()),
+            None,
+            &[],
+        );
+        assert!(!spec.models.main.is_empty());
+        assert!(spec.model_provenance.is_none());
+        let warning = spec
+            .warnings
+            .iter()
+            .find(|w| w.code == "LLM_UNAVAILABLE")
+            .expect("LLM_UNAVAILABLE warning");
+        assert_eq!(warning.details["error"], model_error);
+        assert!(!spec
+            .warnings
+            .iter()
+            .any(|w| w.code == "LLM_ENRICHMENT_APPLIED"));
+
+        let disabled = assemble_spec(
+            &args,
+            "q",
+            "p",
+            &qsf,
+            &prereg,
+            LlmEnrichment::Disabled,
+            None,
+            &[],
+        );
+        assert!(!disabled.warnings.iter().any(|w| w.code.starts_with("LLM_")));
+        let _ = std::fs::remove_dir_all(model_dir);
+    }
+
+    #[test]
+    fn rerender_reports_per_analysis_results_and_honors_dry_run() {
+        let base = std::env::temp_dir().join(format!("rerender-{}", uuid::Uuid::new_v4()));
+        let study_root = base.join("S1");
+        let valid = fixture_spec("a_valid");
+        assert!(!valid
+            .warnings
+            .iter()
+            .any(|w| w.code == "UNRESOLVED_VARIABLE"));
+        write_spec_folder(
+            &study_root,
+            "a_valid",
+            &serde_json::to_string(&valid).expect("json"),
+        );
+        write_spec_folder(&study_root, "b_broken", "{ not json");
+        let mut unresolved = fixture_spec("c_unresolved");
+        unresolved.warnings.push(crate::spec::types::WarningItem {
+            code: "UNRESOLVED_VARIABLE".to_string(),
+            message: "unmapped".to_string(),
+            details: serde_json::json!({ "preregVar": "age" }),
+            severity: crate::spec::types::WarningSeverity::Error,
+        });
+        write_spec_folder(
+            &study_root,
+            "c_unresolved",
+            &serde_json::to_string(&unresolved).expect("json"),
+        );
+        let studies = vec![("S1".to_string(), study_root.clone())];
+        let template_root = template_root_from_cwd(None);
+        let template_root = template_root.as_deref();
+        let rendered_rmd = study_root
+            .join("06_analysis")
+            .join("a_valid")
+            .join("analysis")
+            .join("analysis.Rmd");
+        let mut args = RerenderAllArgs {
+            project_id: "p".to_string(),
+            dry_run: true,
+            force: false,
+        };
+
+        let mut progress = Vec::new();
+        let dry = rerender_analyses(&args, &studies, template_root, None, &mut |i, total, _| {
+            progress.push((i, total))
+        });
+        assert_eq!(progress, vec![(1, 3), (2, 3), (3, 3)]);
+        let statuses = dry
+            .items
+            .iter()
+            .map(|item| (item.analysis_id.as_str(), item.status.as_str()))
+            .collect::<Vec<(&str, &str)>>();
+        assert_eq!(
+            statuses,
+            vec![
+                ("a_valid", "would_render"),
+                ("b_broken", "failed"),
+                ("c_unresolved", "skipped")
+            ]
+        );
+        assert!(!rendered_rmd.exists());
+
+        args.dry_run = false;
+        let report = rerender_analyses(&args, &studies, template_root, None, &mut |_, _, _| {});
+        assert_eq!((report.rendered, report.failed, report.skipped), (1, 1, 1));
+        assert!(rendered_rmd.exists());
+        assert!(report.items[1]
+            .message
+            .as_deref()
+            .unwrap_or("")
+            .contains("Invalid spec.json"));
+
+        args.force = true;
+        let forced = rerender_analyses(&args, &studies, template_root, None, &mut |_, _, _| {});
+        assert_eq!((forced.rendered, forced.failed, forced.skipped), (2, 1, 0));
+        let _ = fs::remove_dir_all(base);
+    }
+
+    #[test]
+    fn mapping_preview_is_read_only_and_commit_rewrites_formulas() {
+        let qsf = parse_qsf_json(
+            r#"{"SurveyEntry":{"SurveyName":"T"},"SurveyElements":[
+              {"Element":"SQ","Payload":{"QuestionID":"QID1","DataExportTag":"wb_total","QuestionText":"Total","QuestionType":{"Type":"TE"}}},
+              {"Element":"SQ","Payload":{"QuestionID":"QID2","DataExportTag":"condition","QuestionText":"Condition","QuestionType":{"Type":"MC"}}}
+            ]}"#,
+        )
+        .expect("qsf");
+        let prereg = parse_prereg_md("DV: happiness\nIV: condition\n\nhappiness ~ condition\n");
+        let spec = build_analysis_spec(
+            "p",
+            "S1",
+            "a1",
+            "q",
+            "p",
+            "q",
+            "p",
+            &qsf,
+            &prereg,
+            "apa_v1",
+            "apa_flextable_ggpubr",
+            &[],
+        );
+        assert_eq!(spec.models.main[0].formula, "TODO_happiness ~ condition");
+
+        let dir = std::env::temp_dir().join(format!("mapping-preview-{}", uuid::Uuid::new_v4()));
+        fs::create_dir_all(&dir).expect("mkdir");
+        let spec_path = dir.join("spec.json");
+        let original = serde_json::to_string_pretty(&spec).expect("serialize");
+        fs::write(&spec_path, &original).expect("write spec");
+        let updates = vec![MappingUpdate {
+            prereg_var: "happiness".to_string(),
+            resolved_to: "wb_total".to_string(),
+        }];
+
+        let preview = preview_resolution_at(&spec_path, &updates).expect("preview");
+        assert_eq!(fs::read_to_string(&spec_path).expect("read"), original);
+        assert_eq!(preview.mapping_changes.len(), 1);
+        assert_eq!(preview.mapping_changes[0].to, "wb_total");
+        assert!(preview
+            .removed_warnings
+            .iter()
+            .any(|w| w.code == "UNRESOLVED_VARIABLE"));
+        assert!(preview
+            .formula_changes
+            .iter()
+            .any(|c| c.group == "main" && c.to == "wb_total ~ condition"));
+
+        let committed = commit_resolution_at(&spec_path, &updates).expect("commit");
+        assert_eq!(committed.models.main[0].formula, "wb_total ~ condition");
+        assert!(committed.models.main[0].unresolved_variables.is_empty());
+        let saved: AnalysisSpec =
+            serde_json::from_str(&fs::read_to_string(&spec_path).expect("read")).expect("json");
+        assert_eq!(saved.models.main[0].dv, "wb_total");
+        assert!(saved
+            .variable_mappings
+            .iter()
+            .any(|m| m.prereg_var == "happiness"
+                && m.resolved_to.as_deref() == Some("wb_total")
+                && m.resolution_kind == ResolutionKind::Manual));
+        let _ = fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn data_contract_check_reports_near_matches_and_updates_spec_warnings() {
+        let study_root = std::env::temp_dir().join(format!("contract-{}", uuid::Uuid::new_v4()));
+        write_spec_folder(
+            &study_root,
+            "A1",
+            &serde_json::to_string(&fixture_spec("A1")).expect("json"),
+        );
+        let spec_path = study_root
+            .join("06_analysis")
+            .join("A1")
+            .join("analysis")
+            .join("spec.json");
+        let data_dir = study_root.join("05_data").join("raw");
+        fs::create_dir_all(&data_dir).expect("data dir");
+        let meta = "StartDate,EndDate,Status,Progress,Duration (in seconds),Finished,RecordedDate,DistributionChannel,UserLanguage";
+        fs::write(
+            data_dir.join("data.csv"),
+            format!("{meta},ResponseId,Wellbeing,condition_1,age\n"),
+        )
+        .expect("csv");
+
+        let report = validate_contract_at(&spec_path, &study_root, None).expect("report");
+        assert!(!report.ok);
+        assert!(report.missing_columns.is_empty());
+        let near: Vec<(&str, &str, &str)> = report
+            .near_matches
+            .iter()
+            .map(|n| (n.expected.as_str(), n.found.as_str(), n.kind.as_str()))
+            .collect();
+        assert!(near.contains(&("wellbeing", "Wellbeing", "case")));
+        assert!(near.contains(&("condition", "condition_1", "suffix")));
+        assert_eq!(report.extra_columns, vec!["age"]);
+        assert!(report.models.iter().all(|m| !m.all_present));
+        let saved = read_spec_file(&spec_path).expect("spec");
+        let warning = saved
+            .warnings
+            .iter()
+            .find(|w| w.code == "DATA_CONTRACT_MISMATCH")
+            .expect("mismatch warning");
+        assert_eq!(
+            warning.details["incompleteModels"].as_array().map(Vec::len),
+            Some(report.models.len())
+        );
+
+        let fixed = study_root.join("fixed.tsv");
+        let header = format!("{meta},ResponseId,wellbeing,condition").replace(',', "\t");
+        fs::write(&fixed, format!("{header}\n")).expect("tsv");
+        let report = validate_contract_at(&spec_path, &study_root, Some(&fixed.to_string_lossy()))
+            .expect("report");
+        assert!(report.ok, "{report:?}");
+        let saved = read_spec_file(&spec_path).expect("spec");
+        assert!(saved
+            .warnings
+            .iter()
+            .all(|w| w.code != "DATA_CONTRACT_MISMATCH"));
+        let _ = fs::remove_dir_all(study_root);
+    }
+}
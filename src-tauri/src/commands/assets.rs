@@ -3,6 +3,11 @@ use std::fs;
 use std::path::{Path, PathBuf};
 use tauri::AppHandle;
 
+use crate::commands::watch::indexed_files_under;
+pub(crate) use crate::store::storage::app_data_root;
+use crate::store::{self, ProjectsStore};
+use crate::util::text::{decode_text, DecodedText};
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct AssetRef {
@@ -10,57 +15,8 @@ pub struct AssetRef {
     pub path: String,
 }
 
-#[derive(Debug, Clone, Deserialize)]
-#[serde(rename_all = "camelCase")]
-struct StudyRef {
-    id: String,
-    #[serde(default)]
-    #[serde(alias = "folder_path")]
-    folder_path: String,
-}
-
-#[derive(Debug, Clone, Deserialize)]
-#[serde(rename_all = "camelCase")]
-struct ProjectRef {
-    id: String,
-    #[serde(alias = "root_path")]
-    root_path: String,
-    #[serde(default)]
-    studies: Vec<StudyRef>,
-}
-
-#[derive(Debug, Clone, Deserialize)]
-#[serde(rename_all = "camelCase")]
-struct ProjectsStore {
-    projects: Vec<ProjectRef>,
-}
-
-fn app_data_root(app: &AppHandle) -> Result<PathBuf, String> {
-    let base = tauri::api::path::app_data_dir(&app.config())
-        .ok_or_else(|| "Unable to resolve app data dir".to_string())?;
-    let root = base.join("research-workflow");
-    fs::create_dir_all(&root).map_err(|e| e.to_string())?;
-    Ok(root)
-}
-
-fn projects_store_path(app: &AppHandle) -> Result<PathBuf, String> {
-    Ok(app_data_root(app)?.join("projects.json"))
-}
-
 fn read_projects_store(app: &AppHandle) -> Result<ProjectsStore, String> {
-    let path = projects_store_path(app)?;
-    if !path.exists() {
-        return Ok(ProjectsStore {
-            projects: Vec::new(),
-        });
-    }
-    let raw = fs::read_to_string(path).map_err(|e| e.to_string())?;
-    if raw.trim().is_empty() {
-        return Ok(ProjectsStore {
-            projects: Vec::new(),
-        });
-    }
-    serde_json::from_str(&raw).map_err(|e| format!("Invalid projects.json: {e}"))
+    store::read_projects_store(&app_data_root(app)?)
 }
 
 pub(crate) fn resolve_study_root(
@@ -99,7 +55,34 @@ pub(crate) fn resolve_project_root(app: &AppHandle, project_id: &str) -> Result<
     Ok(PathBuf::from(project.root_path.clone()))
 }
 
-fn visit_files_recursive(dir: &Path, out: &mut Vec<PathBuf>) -> Result<(), String> {
+/// Every study of a project as `(study_id, study_root)`, resolved like `resolve_study_root`.
+pub(crate) fn resolve_study_roots(
+    app: &AppHandle,
+    project_id: &str,
+) -> Result<Vec<(String, PathBuf)>, String> {
+    let store = read_projects_store(app)?;
+    let project = store
+        .projects
+        .iter()
+        .find(|p| p.id == project_id)
+        .ok_or_else(|| "Project not found.".to_string())?;
+    Ok(project
+        .studies
+        .iter()
+        .map(|study| {
+            let root = if !study.folder_path.trim().is_empty() {
+                PathBuf::from(study.folder_path.clone())
+            } else {
+                PathBuf::from(project.root_path.clone())
+                    .join("studies")
+                    .join(&study.id)
+            };
+            (study.id.clone(), root)
+        })
+        .collect())
+}
+
+pub(crate) fn visit_files_recursive(dir: &Path, out: &mut Vec<PathBuf>) -> Result<(), String> {
     if !dir.exists() {
         return Ok(());
     }
@@ -116,9 +99,16 @@ fn visit_files_recursive(dir: &Path, out: &mut Vec<PathBuf>) -> Result<(), Strin
     Ok(())
 }
 
+/// Files under `dir`, from the project's watch index when one is live, else from disk.
 fn list_files_in(dir: &Path) -> Result<Vec<AssetRef>, String> {
-    let mut files = Vec::new();
-    visit_files_recursive(dir, &mut files)?;
+    let files = match indexed_files_under(dir) {
+        Some(files) => files,
+        None => {
+            let mut files = Vec::new();
+            visit_files_recursive(dir, &mut files)?;
+            files
+        }
+    };
     let mut out = files
         .into_iter()
         .filter_map(|path| {
@@ -133,26 +123,94 @@ fn list_files_in(dir: &Path) -> Result<Vec<AssetRef>, String> {
     Ok(out)
 }
 
+/// Assets of one study input kind. Files registered on import into `canonical` come first,
+/// then anything found under `inputs/<input_dir>` (or `canonical` when that is empty).
+fn list_study_assets(
+    app_root: &Path,
+    project_id: &str,
+    study_id: &str,
+    input_dir: &str,
+    canonical: &str,
+    keep: fn(&str) -> bool,
+) -> Result<Vec<AssetRef>, String> {
+    let store = store::read_projects_store(app_root)?;
+    let project = store
+        .projects
+        .iter()
+        .find(|p| p.id == project_id)
+        .ok_or_else(|| "Project not found.".to_string())?;
+    let study = project
+        .studies
+        .iter()
+        .find(|s| s.id == study_id)
+        .ok_or_else(|| "Study not found.".to_string())?;
+    let root = store::resolve_study_root(project, study);
+    let project_root = PathBuf::from(&project.root_path);
+
+    let mut out: Vec<AssetRef> = study
+        .files
+        .iter()
+        .filter(|file| file.folder.as_deref() == Some(canonical))
+        .map(|file| project_root.join(&file.path))
+        .filter(|path| path.is_file())
+        .map(|path| AssetRef {
+            name: path
+                .file_name()
+                .map(|name| name.to_string_lossy().to_string())
+                .unwrap_or_default(),
+            path: path.to_string_lossy().to_string(),
+        })
+        .collect();
+    let mut found = list_files_in(&root.join("inputs").join(input_dir))?;
+    if found.is_empty() {
+        found = list_files_in(&root.join(canonical))?;
+    }
+    for asset in found {
+        if !out
+            .iter()
+            .any(|known| Path::new(&known.path) == Path::new(&asset.path))
+        {
+            out.push(asset);
+        }
+    }
+    Ok(out
+        .into_iter()
+        .filter(|a| keep(&a.path.to_lowercase()))
+        .collect())
+}
+
+pub(crate) fn list_build_assets_at(
+    app_root: &Path,
+    project_id: &str,
+    study_id: &str,
+) -> Result<Vec<AssetRef>, String> {
+    list_study_assets(app_root, project_id, study_id, "build", "02_build", |p| {
+        p.ends_with(".qsf") || p.ends_with(".qsf.json") || p.ends_with(".json")
+    })
+}
+
+pub(crate) fn list_prereg_assets_at(
+    app_root: &Path,
+    project_id: &str,
+    study_id: &str,
+) -> Result<Vec<AssetRef>, String> {
+    list_study_assets(app_root, project_id, study_id, "prereg", "04_prereg", |p| {
+        p.ends_with(".docx")
+            || p.ends_with(".pdf")
+            || p.ends_with(".md")
+            || p.ends_with(".markdown")
+            || p.ends_with(".json")
+            || p.ends_with(".txt")
+    })
+}
+
 #[tauri::command]
 pub fn list_build_assets(
     app: AppHandle,
     project_id: String,
     study_id: String,
 ) -> Result<Vec<AssetRef>, String> {
-    let root = resolve_study_root(&app, &project_id, &study_id)?;
-    let primary = root.join("inputs").join("build");
-    let fallback = root.join("02_build");
-    let mut out = list_files_in(&primary)?;
-    if out.is_empty() {
-        out = list_files_in(&fallback)?;
-    }
-    Ok(out
-        .into_iter()
-        .filter(|a| {
-            let p = a.path.to_lowercase();
-            p.ends_with(".qsf") || p.ends_with(".qsf.json") || p.ends_with(".json")
-        })
-        .collect())
+    list_build_assets_at(&app_data_root(&app)?, &project_id, &study_id)
 }
 
 #[tauri::command]
@@ -161,30 +219,15 @@ pub fn list_prereg_assets(
     project_id: String,
     study_id: String,
 ) -> Result<Vec<AssetRef>, String> {
-    let root = resolve_study_root(&app, &project_id, &study_id)?;
-    let primary = root.join("inputs").join("prereg");
-    let fallback = root.join("04_prereg");
-    let mut out = list_files_in(&primary)?;
-    if out.is_empty() {
-        out = list_files_in(&fallback)?;
-    }
-    Ok(out
-        .into_iter()
-        .filter(|a| {
-            let p = a.path.to_lowercase();
-            p.ends_with(".docx")
-                || p.ends_with(".md")
-                || p.ends_with(".markdown")
-                || p.ends_with(".json")
-                || p.ends_with(".txt")
-        })
-        .collect())
+    list_prereg_assets_at(&app_data_root(&app)?, &project_id, &study_id)
 }
 
-pub(crate) fn read_file_bytes(path: &str) -> Result<Vec<u8>, String> {
-    fs::read(path).map_err(|e| format!("Unable to read bytes from {path}: {e}"))
+pub(crate) fn read_file_text(path: &str) -> Result<String, String> {
+    Ok(read_file_decoded(path)?.text)
 }
 
-pub(crate) fn read_file_text(path: &str) -> Result<String, String> {
-    fs::read_to_string(path).map_err(|e| format!("Unable to read text from {path}: {e}"))
+/// Reads a text file tolerating BOMs, CRLF line endings and non-UTF-8 (Windows-1252) bytes.
+pub(crate) fn read_file_decoded(path: &str) -> Result<DecodedText, String> {
+    let bytes = fs::read(path).map_err(|e| format!("Unable to read text from {path}: {e}"))?;
+    Ok(decode_text(&bytes))
 }
@@ -0,0 +1,24 @@
+use serde::Deserialize;
+use std::path::Path;
+
+use crate::util::csv_stream::{read_sample, CsvReadOptions, CsvSample, DEFAULT_SAMPLE_ROWS};
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct InspectDataFileArgs {
+    pub path: String,
+    /// Data rows to sample; defaults to `DEFAULT_SAMPLE_ROWS`, 0 reads the header only.
+    #[serde(default)]
+    pub sample_rows: Option<usize>,
+}
+
+/// Header, leading rows and per-column profile of a CSV/TSV export, read without loading
+/// the whole file.
+#[tauri::command]
+pub fn inspect_data_file(args: InspectDataFileArgs) -> Result<CsvSample, String> {
+    read_sample(
+        Path::new(args.path.trim()),
+        args.sample_rows.unwrap_or(DEFAULT_SAMPLE_ROWS),
+        &CsvReadOptions::default(),
+    )
+}
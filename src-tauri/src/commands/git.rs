@@ -0,0 +1,799 @@
+use serde::{Deserialize, Serialize};
+use std::path::{Component, Path, PathBuf};
+use std::process::{Command, Output};
+use tauri::AppHandle;
+
+use crate::commands::assets::app_data_root;
+use crate::spec::types::{WarningItem, WarningSeverity};
+use crate::store::read_projects_store;
+
+pub const GIT_NO_REPO: &str = "GIT_NO_REPO";
+pub const GIT_COMMIT_FAILED: &str = "GIT_COMMIT_FAILED";
+pub const GIT_PUSH_REJECTED: &str = "GIT_PUSH_REJECTED";
+pub const GIT_NO_REMOTE: &str = "GIT_NO_REMOTE";
+pub const GIT_NO_UPSTREAM: &str = "GIT_NO_UPSTREAM";
+pub const GIT_AUTH_FAILED: &str = "GIT_AUTH_FAILED";
+pub const GIT_PUSH_FAILED: &str = "GIT_PUSH_FAILED";
+
+const AUTH_FAILURE_PATTERNS: &[&str] = &[
+    "authentication failed",
+    "permission denied",
+    "could not read username",
+    "could not read password",
+    "invalid username or password",
+    "the requested url returned error: 403",
+    "terminal prompts disabled",
+];
+
+/// Commits returned by `git_log` when no limit is given.
+const DEFAULT_LOG_LIMIT: usize = 20;
+const MAX_LOG_LIMIT: usize = 500;
+
+const REJECTION_PATTERNS: &[&str] = &[
+    "[rejected]",
+    "[remote rejected]",
+    "non-fast-forward",
+    "fetch first",
+];
+
+#[derive(Debug, Serialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct GitRepoInfo {
+    /// None when HEAD is detached.
+    pub branch: Option<String>,
+    pub has_origin: bool,
+    /// Upstream ref such as "origin/main", if the branch tracks one.
+    pub upstream: Option<String>,
+    pub ahead: u32,
+    pub behind: u32,
+    pub dirty_files: usize,
+}
+
+#[derive(Debug, Serialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct GitFileChange {
+    pub path: String,
+    /// Porcelain status letter: M, A, D, R, C or T.
+    pub status: String,
+    /// Source of a rename or copy.
+    pub original_path: Option<String>,
+}
+
+/// Working tree state parsed from `git status --porcelain=v2 --branch`.
+#[derive(Debug, Serialize, Clone, PartialEq, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct GitSummary {
+    /// None when HEAD is detached.
+    pub branch: Option<String>,
+    pub upstream: Option<String>,
+    pub ahead: u32,
+    pub behind: u32,
+    pub staged: Vec<GitFileChange>,
+    pub unstaged: Vec<GitFileChange>,
+    pub untracked: Vec<String>,
+    /// Paths with unresolved merge conflicts.
+    pub conflicted: Vec<String>,
+}
+
+#[derive(Debug, Serialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct GitLogEntry {
+    pub hash: String,
+    pub author: String,
+    /// Author date, ISO 8601.
+    pub date: String,
+    pub subject: String,
+}
+
+#[derive(Debug, Serialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub enum GitCommitOutcome {
+    /// The working tree was clean and the branch had nothing left to push.
+    NothingToCommit,
+    Pushed,
+}
+
+#[derive(Debug, Serialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct GitCommitPushReport {
+    pub outcome: GitCommitOutcome,
+    /// Files in the new commit; 0 when only earlier commits were pushed.
+    pub files_committed: usize,
+    /// Hash of the new commit, if one was made.
+    pub commit_hash: Option<String>,
+    pub pushed: bool,
+    /// `remote:` lines the server sent back during the push, e.g. a pull request link.
+    pub remote_message: Option<String>,
+    pub branch: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GitProjectArgs {
+    project_id: String,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GitLogArgs {
+    project_id: String,
+    #[serde(default)]
+    limit: Option<usize>,
+    /// Project-relative folder, e.g. `studies/S-ABC123`, to restrict the history to.
+    #[serde(default)]
+    subpath: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GitCommitPushArgs {
+    message: String,
+    project_id: String,
+    /// Pushes with `-u origin <branch>` when the branch has no upstream yet.
+    #[serde(default)]
+    set_upstream: bool,
+}
+
+/// Structured error (JSON `WarningItem`) the UI can parse.
+fn git_error(code: &str, message: &str, details: serde_json::Value) -> String {
+    let error = WarningItem {
+        code: code.to_string(),
+        message: message.to_string(),
+        details,
+        severity: WarningSeverity::Error,
+    };
+    serde_json::to_string(&error).unwrap_or_else(|_| message.to_string())
+}
+
+fn run_git(root: &Path, args: &[&str]) -> Result<Output, String> {
+    Command::new("git")
+        .args(args)
+        .current_dir(root)
+        .output()
+        .map_err(|err| format!("Unable to run git: {err}"))
+}
+
+/// Trimmed stdout of a git command, or None when it exits non-zero.
+fn git_stdout(root: &Path, args: &[&str]) -> Result<Option<String>, String> {
+    let output = run_git(root, args)?;
+    if !output.status.success() {
+        return Ok(None);
+    }
+    Ok(Some(
+        String::from_utf8_lossy(&output.stdout).trim().to_string(),
+    ))
+}
+
+/// Whether `root` itself holds a repository (a `.git` folder, or a file for worktrees). A
+/// folder nested inside some other repository does not count.
+pub fn has_git_dir(root: &Path) -> bool {
+    root.is_dir() && root.join(".git").exists()
+}
+
+fn require_repo(root: &Path) -> Result<(), String> {
+    if has_git_dir(root) {
+        return Ok(());
+    }
+    Err(git_error(
+        GIT_NO_REPO,
+        "The project folder is not a git repository.",
+        serde_json::json!({ "root": root.to_string_lossy() }),
+    ))
+}
+
+fn line_count(value: Option<String>) -> usize {
+    value
+        .unwrap_or_default()
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .count()
+}
+
+pub fn repo_info_at(root: &Path) -> Result<GitRepoInfo, String> {
+    require_repo(root)?;
+    // symbolic-ref also names the branch of a repo without commits.
+    let branch = git_stdout(root, &["symbolic-ref", "--quiet", "--short", "HEAD"])?;
+    let has_origin = git_stdout(root, &["remote"])?
+        .unwrap_or_default()
+        .lines()
+        .any(|remote| remote.trim() == "origin");
+    let upstream = git_stdout(
+        root,
+        &["rev-parse", "--abbrev-ref", "--symbolic-full-name", "@{u}"],
+    )?
+    .filter(|value| !value.is_empty());
+
+    let (mut ahead, mut behind) = (0, 0);
+    if upstream.is_some() {
+        if let Some(counts) = git_stdout(
+            root,
+            &["rev-list", "--left-right", "--count", "HEAD...@{u}"],
+        )? {
+            let mut parts = counts.split_whitespace().map(|n| n.parse().unwrap_or(0));
+            ahead = parts.next().unwrap_or(0);
+            behind = parts.next().unwrap_or(0);
+        }
+    }
+    let dirty_files = line_count(git_stdout(root, &["status", "--porcelain"])?);
+
+    Ok(GitRepoInfo {
+        branch,
+        has_origin,
+        upstream,
+        ahead,
+        behind,
+        dirty_files,
+    })
+}
+
+/// Maps `git push` stderr to a structured error code.
+fn classify_push_failure(stderr: &str) -> &'static str {
+    let lower = stderr.to_lowercase();
+    if lower.contains("has no upstream branch") {
+        GIT_NO_UPSTREAM
+    } else if REJECTION_PATTERNS.iter().any(|p| lower.contains(p)) {
+        GIT_PUSH_REJECTED
+    } else if lower.contains("no configured push destination")
+        || lower.contains("does not appear to be a git repository")
+    {
+        GIT_NO_REMOTE
+    } else if AUTH_FAILURE_PATTERNS.iter().any(|p| lower.contains(p)) {
+        GIT_AUTH_FAILED
+    } else {
+        GIT_PUSH_FAILED
+    }
+}
+
+fn push_failure_message(code: &str) -> &'static str {
+    match code {
+        GIT_NO_UPSTREAM => "The current branch has no upstream branch on origin.",
+        GIT_NO_REMOTE => "This repository has no remote named origin.",
+        GIT_AUTH_FAILED => "Git could not authenticate with the remote.",
+        GIT_PUSH_REJECTED => "The remote rejected the push; pull its changes first.",
+        _ => "git push failed.",
+    }
+}
+
+fn file_change(status: char, path: &str, original_path: Option<&str>) -> GitFileChange {
+    GitFileChange {
+        path: path.to_string(),
+        status: status.to_string(),
+        original_path: original_path.map(str::to_string),
+    }
+}
+
+/// Parses `git status --porcelain=v2 --branch -z` output. Fields are NUL-separated; a
+/// rename or copy entry is followed by its source path as a separate field.
+fn parse_porcelain_v2(output: &str) -> GitSummary {
+    let mut summary = GitSummary::default();
+    let mut fields = output.split('\0').filter(|field| !field.is_empty());
+    while let Some(field) = fields.next() {
+        if let Some(header) = field.strip_prefix("# ") {
+            let (key, value) = header.split_once(' ').unwrap_or((header, ""));
+            match key {
+                "branch.head" if value != "(detached)" => summary.branch = Some(value.to_string()),
+                "branch.upstream" => summary.upstream = Some(value.to_string()),
+                "branch.ab" => {
+                    let mut counts = value
+                        .split_whitespace()
+                        .map(|count| count.trim_start_matches(['+', '-']).parse().unwrap_or(0));
+                    summary.ahead = counts.next().unwrap_or(0);
+                    summary.behind = counts.next().unwrap_or(0);
+                }
+                _ => {}
+            }
+            continue;
+        }
+        let (kind, rest) = field.split_at(1);
+        let rest = rest.trim_start();
+        match kind {
+            "1" | "2" => {
+                // "1 XY sub mH mI mW hH hI path"; "2" adds a score before the path.
+                let columns = if kind == "1" { 8 } else { 9 };
+                let mut parts = rest.splitn(columns, ' ');
+                let xy = parts.next().unwrap_or("..");
+                let Some(path) = parts.nth(columns - 2) else {
+                    continue;
+                };
+                let original = if kind == "2" { fields.next() } else { None };
+                let mut codes = xy.chars();
+                let (staged, unstaged) = (codes.next().unwrap_or('.'), codes.next().unwrap_or('.'));
+                if staged != '.' {
+                    summary.staged.push(file_change(staged, path, original));
+                }
+                if unstaged != '.' {
+                    summary.unstaged.push(file_change(unstaged, path, None));
+                }
+            }
+            "u" => {
+                if let Some(path) = rest.splitn(10, ' ').nth(9) {
+                    summary.conflicted.push(path.to_string());
+                }
+            }
+            "?" => summary.untracked.push(rest.to_string()),
+            _ => {}
+        }
+    }
+    summary
+}
+
+/// Branch, upstream and changed files of the repository at `root`.
+pub fn status_at(root: &Path) -> Result<GitSummary, String> {
+    require_repo(root)?;
+    let output = run_git(
+        root,
+        &[
+            "status",
+            "--porcelain=v2",
+            "--branch",
+            "--untracked-files=all",
+            "-z",
+        ],
+    )?;
+    if !output.status.success() {
+        return Err(String::from_utf8_lossy(&output.stderr).to_string());
+    }
+    Ok(parse_porcelain_v2(&String::from_utf8_lossy(&output.stdout)))
+}
+
+/// Current branch of the repository at `root`, or None when HEAD is detached.
+pub fn current_branch_at(root: &Path) -> Result<Option<String>, String> {
+    require_repo(root)?;
+    git_stdout(root, &["symbolic-ref", "--quiet", "--short", "HEAD"])
+}
+
+/// Field and record separators for `git log --format`, which cannot occur in commit fields.
+const LOG_FORMAT: &str = "--format=%H%x1f%an%x1f%aI%x1f%s%x1e";
+
+fn parse_log(output: &str) -> Vec<GitLogEntry> {
+    output
+        .split('\u{1e}')
+        .filter_map(|record| {
+            let mut fields = record.trim_start_matches('\n').split('\u{1f}');
+            Some(GitLogEntry {
+                hash: fields.next().filter(|hash| !hash.is_empty())?.to_string(),
+                author: fields.next()?.to_string(),
+                date: fields.next()?.to_string(),
+                subject: fields.next()?.to_string(),
+            })
+        })
+        .collect()
+}
+
+/// The last `limit` commits on HEAD, optionally only those touching `subpath`.
+pub fn log_at(
+    root: &Path,
+    limit: Option<usize>,
+    subpath: Option<&str>,
+) -> Result<Vec<GitLogEntry>, String> {
+    require_repo(root)?;
+    if git_stdout(root, &["rev-parse", "--verify", "--quiet", "HEAD"])?.is_none() {
+        return Ok(Vec::new());
+    }
+    let count = format!(
+        "--max-count={}",
+        limit.unwrap_or(DEFAULT_LOG_LIMIT).clamp(1, MAX_LOG_LIMIT)
+    );
+    let mut args = vec!["log", count.as_str(), LOG_FORMAT];
+    let subpath = subpath.map(str::trim).filter(|subpath| !subpath.is_empty());
+    if let Some(subpath) = subpath {
+        if !Path::new(subpath)
+            .components()
+            .all(|component| matches!(component, Component::Normal(_)))
+        {
+            return Err(format!("Log path must stay inside the project: {subpath}"));
+        }
+        args.extend(["--", subpath]);
+    }
+    let output = run_git(root, &args)?;
+    if !output.status.success() {
+        return Err(String::from_utf8_lossy(&output.stderr).trim().to_string());
+    }
+    Ok(parse_log(&String::from_utf8_lossy(&output.stdout)))
+}
+
+/// The `remote:` lines of `git push` stderr, without the prefix.
+fn remote_message(stderr: &str) -> Option<String> {
+    let lines = stderr
+        .lines()
+        .filter_map(|line| line.strip_prefix("remote:"))
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .collect::<Vec<&str>>();
+    (!lines.is_empty()).then(|| lines.join("\n"))
+}
+
+fn commit_error(step: &str, output: &Output) -> String {
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    git_error(
+        GIT_COMMIT_FAILED,
+        &format!("git {step} failed."),
+        serde_json::json!({ "stderr": stderr.trim() }),
+    )
+}
+
+/// Stages everything, commits and pushes. A clean tree still pushes commits the upstream
+/// lacks. A branch without an upstream is pushed with `-u origin <branch>` only when
+/// `set_upstream` is set.
+pub fn commit_push_at(
+    root: &Path,
+    message: &str,
+    set_upstream: bool,
+) -> Result<GitCommitPushReport, String> {
+    require_repo(root)?;
+    let add_output = run_git(root, &["add", "-A"])?;
+    if !add_output.status.success() {
+        return Err(commit_error("add", &add_output));
+    }
+
+    let files_committed = line_count(git_stdout(root, &["diff", "--cached", "--name-only"])?);
+    let mut commit_hash = None;
+    if files_committed > 0 {
+        let commit_output = run_git(root, &["commit", "-m", message])?;
+        if !commit_output.status.success() {
+            return Err(commit_error("commit", &commit_output));
+        }
+        commit_hash = git_stdout(root, &["rev-parse", "HEAD"])?;
+    }
+
+    let info = repo_info_at(root)?;
+    let has_commits = git_stdout(root, &["rev-parse", "--verify", "--quiet", "HEAD"])?.is_some();
+    let up_to_date = info.upstream.is_some() && info.ahead == 0;
+    if files_committed == 0 && (up_to_date || !has_commits) {
+        return Ok(GitCommitPushReport {
+            outcome: GitCommitOutcome::NothingToCommit,
+            files_committed,
+            commit_hash,
+            pushed: false,
+            remote_message: None,
+            branch: info.branch,
+        });
+    }
+
+    let details = serde_json::json!({ "branch": info.branch });
+    if !info.has_origin {
+        return Err(git_error(
+            GIT_NO_REMOTE,
+            push_failure_message(GIT_NO_REMOTE),
+            details,
+        ));
+    }
+    let push_args: Vec<&str> = match (&info.upstream, &info.branch) {
+        (Some(_), _) => vec!["push"],
+        (None, Some(branch)) if set_upstream => vec!["push", "-u", "origin", branch],
+        (None, _) => {
+            return Err(git_error(
+                GIT_NO_UPSTREAM,
+                push_failure_message(GIT_NO_UPSTREAM),
+                details,
+            ))
+        }
+    };
+
+    let push_output = run_git(root, &push_args)?;
+    if !push_output.status.success() {
+        let push_stderr = String::from_utf8_lossy(&push_output.stderr).to_string();
+        let code = classify_push_failure(&push_stderr);
+        return Err(git_error(
+            code,
+            push_failure_message(code),
+            serde_json::json!({ "branch": info.branch, "stderr": push_stderr.trim() }),
+        ));
+    }
+    Ok(GitCommitPushReport {
+        outcome: GitCommitOutcome::Pushed,
+        files_committed,
+        commit_hash,
+        pushed: true,
+        remote_message: remote_message(&String::from_utf8_lossy(&push_output.stderr)),
+        branch: info.branch,
+    })
+}
+
+/// Root folder of a project from the projects store. Git commands never fall back to the
+/// process working directory, which is the app install folder rather than a project.
+fn project_repo_root(app_root: &Path, project_id: &str) -> Result<PathBuf, String> {
+    let store = read_projects_store(app_root)?;
+    let project = store
+        .projects
+        .iter()
+        .find(|p| p.id == project_id.trim())
+        .ok_or_else(|| "Project not found.".to_string())?;
+    Ok(PathBuf::from(project.root_path.trim()))
+}
+
+#[tauri::command]
+pub fn git_status(app: AppHandle, args: GitProjectArgs) -> Result<GitSummary, String> {
+    status_at(&project_repo_root(&app_data_root(&app)?, &args.project_id)?)
+}
+
+#[tauri::command]
+pub fn git_current_branch(app: AppHandle, args: GitProjectArgs) -> Result<Option<String>, String> {
+    current_branch_at(&project_repo_root(&app_data_root(&app)?, &args.project_id)?)
+}
+
+#[tauri::command]
+pub fn git_log(app: AppHandle, args: GitLogArgs) -> Result<Vec<GitLogEntry>, String> {
+    log_at(
+        &project_repo_root(&app_data_root(&app)?, &args.project_id)?,
+        args.limit,
+        args.subpath.as_deref(),
+    )
+}
+
+#[tauri::command]
+pub fn git_repo_info(app: AppHandle, args: GitProjectArgs) -> Result<GitRepoInfo, String> {
+    repo_info_at(&project_repo_root(&app_data_root(&app)?, &args.project_id)?)
+}
+
+#[tauri::command]
+pub fn git_commit_push(
+    app: AppHandle,
+    args: GitCommitPushArgs,
+) -> Result<GitCommitPushReport, String> {
+    commit_push_at(
+        &project_repo_root(&app_data_root(&app)?, &args.project_id)?,
+        &args.message,
+        args.set_upstream,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use uuid::Uuid;
+
+    fn git(root: &Path, args: &[&str]) {
+        let output = run_git(root, args).expect("git runs");
+        assert!(
+            output.status.success(),
+            "git {args:?} failed: {}",
+            String::from_utf8_lossy(&output.stderr)
+        );
+    }
+
+    fn temp_repo(base: &Path) -> PathBuf {
+        let repo = base.join("repo");
+        fs::create_dir_all(&repo).expect("repo dir");
+        git(&repo, &["init", "--quiet"]);
+        git(&repo, &["config", "user.email", "test@example.com"]);
+        git(&repo, &["config", "user.name", "Test"]);
+        git(&repo, &["config", "commit.gpgsign", "false"]);
+        repo
+    }
+
+    fn error_code(err: &str) -> String {
+        serde_json::from_str::<WarningItem>(err)
+            .expect("structured error")
+            .code
+    }
+
+    /// `git status --porcelain=v2 --branch -z` after staging a rename, editing a staged file
+    /// again, deleting a tracked file and adding an untracked one with a space in its name.
+    const PORCELAIN_TRACKING: &str = "# branch.oid 4f94c8e0c2a1b9d7e6f5a4b3c2d1e0f9a8b7c6d5\0\
+# branch.head main\0\
+# branch.upstream origin/main\0\
+# branch.ab +2 -1\0\
+1 MM N... 100644 100644 100644 3b18e512dba79e4c8300dd08aeb37f8e728b8dad 4b18e512dba79e4c8300dd08aeb37f8e728b8dae studies/S-ABC123/00_admin/README.md\0\
+1 .D N... 100644 100644 000000 5b18e512dba79e4c8300dd08aeb37f8e728b8daf 5b18e512dba79e4c8300dd08aeb37f8e728b8daf notes.md\0\
+2 R. N... 100644 100644 100644 6b18e512dba79e4c8300dd08aeb37f8e728b8da0 6b18e512dba79e4c8300dd08aeb37f8e728b8da0 R100 studies/S-ABC123/02_build/survey v2.qsf\0studies/S-ABC123/02_build/survey.qsf\0\
+1 A. N... 000000 100644 100644 0000000000000000000000000000000000000000 7b18e512dba79e4c8300dd08aeb37f8e728b8da1 paper/draft.md\0\
+? studies/S-ABC123/05_data/raw wave.csv\0";
+
+    /// A detached HEAD in the middle of a merge with one conflicted file.
+    const PORCELAIN_DETACHED_CONFLICT: &str = "# branch.oid 9a8b7c6d5e4f3a2b1c0d9e8f7a6b5c4d3e2f1a0b\0\
+# branch.head (detached)\0\
+u UU N... 100644 100644 100644 100644 1b18e512dba79e4c8300dd08aeb37f8e728b8da2 2b18e512dba79e4c8300dd08aeb37f8e728b8da3 3b18e512dba79e4c8300dd08aeb37f8e728b8da4 analysis/models.Rmd\0";
+
+    /// A fresh `git init` with one untracked file.
+    const PORCELAIN_INITIAL: &str = "# branch.oid (initial)\0# branch.head main\0? data.csv\0";
+
+    #[test]
+    fn porcelain_v2_status_is_parsed_into_a_summary() {
+        let summary = parse_porcelain_v2(PORCELAIN_TRACKING);
+        assert_eq!(summary.branch.as_deref(), Some("main"));
+        assert_eq!(summary.upstream.as_deref(), Some("origin/main"));
+        assert_eq!((summary.ahead, summary.behind), (2, 1));
+        assert_eq!(
+            summary.staged,
+            vec![
+                file_change('M', "studies/S-ABC123/00_admin/README.md", None),
+                file_change(
+                    'R',
+                    "studies/S-ABC123/02_build/survey v2.qsf",
+                    Some("studies/S-ABC123/02_build/survey.qsf")
+                ),
+                file_change('A', "paper/draft.md", None),
+            ]
+        );
+        assert_eq!(
+            summary.unstaged,
+            vec![
+                file_change('M', "studies/S-ABC123/00_admin/README.md", None),
+                file_change('D', "notes.md", None),
+            ]
+        );
+        assert_eq!(
+            summary.untracked,
+            vec!["studies/S-ABC123/05_data/raw wave.csv"]
+        );
+        assert!(summary.conflicted.is_empty());
+
+        let detached = parse_porcelain_v2(PORCELAIN_DETACHED_CONFLICT);
+        assert_eq!(detached.branch, None);
+        assert_eq!(detached.upstream, None);
+        assert_eq!(detached.conflicted, vec!["analysis/models.Rmd"]);
+        assert!(detached.staged.is_empty() && detached.unstaged.is_empty());
+
+        let initial = parse_porcelain_v2(PORCELAIN_INITIAL);
+        assert_eq!(initial.branch.as_deref(), Some("main"));
+        assert_eq!((initial.ahead, initial.behind), (0, 0));
+        assert_eq!(initial.untracked, vec!["data.csv"]);
+    }
+
+    #[test]
+    fn log_output_and_push_messages_are_parsed() {
+        let output =
+            "4f94c8e0\u{1f}Ada Lovelace\u{1f}2026-10-14T09:30:00+02:00\u{1f}Add pilot data\u{1e}\n\
+019a41d0\u{1f}Test\u{1f}2026-10-13T17:05:12+00:00\u{1f}Fix: a | b; c\u{1e}\n";
+        let entries = parse_log(output);
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].author, "Ada Lovelace");
+        assert_eq!(entries[0].date, "2026-10-14T09:30:00+02:00");
+        assert_eq!(entries[1].hash, "019a41d0");
+        assert_eq!(entries[1].subject, "Fix: a | b; c");
+        assert!(parse_log("").is_empty());
+
+        let stderr = "To github.com:lab/study.git\n\
+remote: \n\
+remote: Create a pull request for 'wave-2' on GitHub by visiting:\n\
+remote:      https://github.com/lab/study/pull/new/wave-2\n\
+ * [new branch]      wave-2 -> wave-2\n";
+        assert_eq!(
+            remote_message(stderr).as_deref(),
+            Some("Create a pull request for 'wave-2' on GitHub by visiting:\nhttps://github.com/lab/study/pull/new/wave-2")
+        );
+        assert_eq!(remote_message(" * [new branch] main -> main\n"), None);
+    }
+
+    #[test]
+    fn repo_without_remote_reports_info_and_refuses_push() {
+        let base = std::env::temp_dir().join(format!("git-info-{}", Uuid::new_v4()));
+        let repo = temp_repo(&base);
+        fs::write(repo.join("notes.md"), "draft").expect("write");
+        fs::write(repo.join("data.csv"), "id\n1\n").expect("write");
+
+        let info = repo_info_at(&repo).expect("info");
+        assert!(info.branch.is_some());
+        assert!(!info.has_origin);
+        assert_eq!(info.upstream, None);
+        assert_eq!((info.ahead, info.behind), (0, 0));
+        assert_eq!(info.dirty_files, 2);
+
+        let err = commit_push_at(&repo, "Initial commit", true).expect_err("no remote");
+        assert_eq!(error_code(&err), GIT_NO_REMOTE);
+        assert_eq!(repo_info_at(&repo).expect("info").dirty_files, 0);
+
+        assert!(repo_info_at(&base).is_err());
+        assert_eq!(
+            classify_push_failure("fatal: Authentication failed for 'https://x'"),
+            GIT_AUTH_FAILED
+        );
+        let _ = fs::remove_dir_all(base);
+    }
+
+    #[test]
+    fn missing_upstream_is_reported_or_set_on_request() {
+        let base = std::env::temp_dir().join(format!("git-upstream-{}", Uuid::new_v4()));
+        let repo = temp_repo(&base);
+        let remote = base.join("remote.git");
+        fs::create_dir_all(&remote).expect("remote dir");
+        git(&remote, &["init", "--quiet", "--bare"]);
+        git(
+            &repo,
+            &["remote", "add", "origin", &remote.to_string_lossy()],
+        );
+        fs::write(repo.join("notes.md"), "draft").expect("write");
+
+        let err = commit_push_at(&repo, "Initial commit", false).expect_err("no upstream");
+        assert_eq!(error_code(&err), GIT_NO_UPSTREAM);
+        let info = repo_info_at(&repo).expect("info");
+        assert!(info.has_origin);
+        assert_eq!(info.upstream, None);
+
+        // The refused push above already made the commit; this run only pushes it.
+        let report = commit_push_at(&repo, "Initial commit", true).expect("push with -u");
+        assert!(report.pushed);
+        assert_eq!((report.files_committed, report.commit_hash), (0, None));
+        let info = repo_info_at(&repo).expect("info");
+        let branch = info.branch.clone().expect("branch");
+        assert_eq!(info.upstream, Some(format!("origin/{branch}")));
+        assert_eq!((info.ahead, info.behind, info.dirty_files), (0, 0, 0));
+
+        fs::write(repo.join("notes.md"), "revised").expect("write");
+        git(&repo, &["commit", "--quiet", "-am", "Local only"]);
+        let info = repo_info_at(&repo).expect("info");
+        assert_eq!((info.ahead, info.behind), (1, 0));
+
+        let report = commit_push_at(&repo, "Nothing new", false).expect("push to upstream");
+        assert_eq!(report.outcome, GitCommitOutcome::Pushed);
+        assert_eq!(report.files_committed, 0);
+        assert_eq!(repo_info_at(&repo).expect("info").ahead, 0);
+        let report = commit_push_at(&repo, "Still nothing", false).expect("clean tree");
+        assert_eq!(report.outcome, GitCommitOutcome::NothingToCommit);
+        assert!(!report.pushed && report.commit_hash.is_none());
+
+        fs::write(repo.join("data.csv"), "id\n1\n").expect("write");
+        let report = commit_push_at(&repo, "Add data", false).expect("commit and push");
+        assert!(report.pushed);
+        assert_eq!(report.files_committed, 1);
+        assert_eq!(
+            report.commit_hash,
+            git_stdout(&repo, &["rev-parse", "HEAD"]).expect("head")
+        );
+        let _ = fs::remove_dir_all(base);
+    }
+
+    #[test]
+    fn commands_use_the_project_root_not_the_process_cwd() {
+        let base = std::env::temp_dir().join(format!("git-project-{}", Uuid::new_v4()));
+        let app_root = base.join("app");
+        fs::create_dir_all(&app_root).expect("app root");
+        let repo = temp_repo(&base);
+        let create = |root: &Path| {
+            let args = serde_json::from_value(serde_json::json!({
+                "name": "Demo",
+                "rootDir": root.to_string_lossy(),
+                "useExistingRoot": true,
+            }))
+            .expect("args");
+            crate::store::projects::create_project(&app_root, args).expect("project")
+        };
+        let project = create(&repo);
+        let cwd = std::env::current_dir().expect("cwd");
+        let cwd_head = git_stdout(&cwd, &["rev-parse", "HEAD"]).expect("cwd head");
+
+        fs::write(repo.join("notes.md"), "draft").expect("write");
+        let root = project_repo_root(&app_root, &project.id).expect("root");
+        assert_eq!(root, repo);
+        let err = commit_push_at(&root, "Project commit", false).expect_err("no remote");
+        assert_eq!(error_code(&err), GIT_NO_REMOTE);
+        let log = git_stdout(&repo, &["log", "--format=%s"]).expect("log");
+        assert_eq!(log.as_deref(), Some("Project commit"));
+        let log = log_at(&root, None, Some("notes.md")).expect("log");
+        assert_eq!(log.len(), 1);
+        assert_eq!(
+            (log[0].author.as_str(), log[0].subject.as_str()),
+            ("Test", "Project commit")
+        );
+        assert!(log_at(&root, Some(5), Some("studies"))
+            .expect("log")
+            .is_empty());
+        assert!(log_at(&root, None, Some("../elsewhere")).is_err());
+        assert_eq!(
+            current_branch_at(&root).expect("branch"),
+            status_at(&root).expect("status").branch
+        );
+        let status = status_at(&root).expect("status");
+        assert!(status.branch.is_some());
+        assert!(status.staged.is_empty() && status.untracked.is_empty());
+        assert_eq!(
+            git_stdout(&cwd, &["rev-parse", "HEAD"]).expect("cwd head"),
+            cwd_head
+        );
+
+        // A plain folder is "no repo" even when some parent directory is a repository.
+        let plain = repo.join("nested");
+        fs::create_dir_all(&plain).expect("nested");
+        let nested = create(&plain);
+        let root = project_repo_root(&app_root, &nested.id).expect("root");
+        let err = commit_push_at(&root, "Nested", false).expect_err("no repo");
+        assert_eq!(error_code(&err), GIT_NO_REPO);
+        assert_eq!(
+            error_code(&status_at(&root).expect_err("no repo")),
+            GIT_NO_REPO
+        );
+        let _ = fs::remove_dir_all(base);
+    }
+}
@@ -1,2 +1,8 @@
 pub mod analysis;
 pub mod assets;
+pub mod data;
+pub mod git;
+pub mod progress;
+pub mod r_env;
+pub mod run;
+pub mod watch;
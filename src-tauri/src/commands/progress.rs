@@ -0,0 +1,187 @@
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::{Mutex, OnceLock};
+use std::time::Instant;
+
+pub const SPEC_PROGRESS_EVENT: &str = "analysis-spec-progress";
+pub const RERENDER_PROGRESS_EVENT: &str = "analysis-rerender-progress";
+
+static LAST_REPORTS: OnceLock<Mutex<HashMap<String, GenerationReport>>> = OnceLock::new();
+
+fn reports_cell() -> &'static Mutex<HashMap<String, GenerationReport>> {
+    LAST_REPORTS.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct StageTiming {
+    pub stage: String,
+    pub started_at_utc: String,
+    pub duration_ms: u64,
+    pub ok: bool,
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StageEvent {
+    pub analysis_id: String,
+    pub stage: String,
+    pub status: String,
+    pub duration_ms: Option<u64>,
+    pub bytes_downloaded: Option<u64>,
+    pub bytes_total: Option<u64>,
+}
+
+impl StageEvent {
+    pub fn progress(analysis_id: &str, stage: &str, downloaded: u64, total: Option<u64>) -> Self {
+        Self {
+            analysis_id: analysis_id.to_string(),
+            stage: stage.to_string(),
+            status: "progress".to_string(),
+            duration_ms: None,
+            bytes_downloaded: Some(downloaded),
+            bytes_total: total,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GenerationReport {
+    pub analysis_id: String,
+    pub ok: bool,
+    pub total_ms: u64,
+    pub stages: Vec<StageTiming>,
+}
+
+/// Runs named stages in order, timing each one and forwarding start/finish events.
+pub struct StageRecorder<'a> {
+    analysis_id: String,
+    emit: Box<dyn FnMut(StageEvent) + 'a>,
+    stages: Vec<StageTiming>,
+}
+
+impl<'a> StageRecorder<'a> {
+    pub fn new(analysis_id: &str, emit: impl FnMut(StageEvent) + 'a) -> Self {
+        Self {
+            analysis_id: analysis_id.to_string(),
+            emit: Box::new(emit),
+            stages: Vec::new(),
+        }
+    }
+
+    pub fn run<T>(
+        &mut self,
+        stage: &str,
+        f: impl FnOnce() -> Result<T, String>,
+    ) -> Result<T, String> {
+        let started_at_utc = Utc::now().to_rfc3339();
+        self.send(stage, "started", None);
+        let started = Instant::now();
+        let result = f();
+        let duration_ms = started.elapsed().as_millis() as u64;
+        let status = if result.is_ok() { "finished" } else { "failed" };
+        self.send(stage, status, Some(duration_ms));
+        self.stages.push(StageTiming {
+            stage: stage.to_string(),
+            started_at_utc,
+            duration_ms,
+            ok: result.is_ok(),
+            error: result.as_ref().err().cloned(),
+        });
+        result
+    }
+
+    pub fn finish(self) -> GenerationReport {
+        GenerationReport {
+            analysis_id: self.analysis_id,
+            ok: self.stages.iter().all(|s| s.ok),
+            total_ms: self.stages.iter().map(|s| s.duration_ms).sum(),
+            stages: self.stages,
+        }
+    }
+
+    fn send(&mut self, stage: &str, status: &str, duration_ms: Option<u64>) {
+        (self.emit)(StageEvent {
+            analysis_id: self.analysis_id.clone(),
+            stage: stage.to_string(),
+            status: status.to_string(),
+            duration_ms,
+            bytes_downloaded: None,
+            bytes_total: None,
+        });
+    }
+}
+
+pub fn store_generation_report(report: GenerationReport) {
+    if let Ok(mut guard) = reports_cell().lock() {
+        guard.insert(report.analysis_id.clone(), report);
+    }
+}
+
+#[tauri::command]
+pub fn get_last_generation_report(analysis_id: String) -> Result<Option<GenerationReport>, String> {
+    let guard = reports_cell()
+        .lock()
+        .map_err(|_| "Unable to acquire generation report lock".to_string())?;
+    Ok(guard.get(&analysis_id).cloned())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn recorder_assembles_report_and_stops_on_failure() {
+        let mut events: Vec<(String, String)> = Vec::new();
+        let report = {
+            let mut recorder = StageRecorder::new("a1", |e: StageEvent| {
+                events.push((e.stage, e.status));
+            });
+            let value = recorder.run("read_inputs", || Ok(3)).expect("stage ok");
+            assert_eq!(value, 3);
+            let failed: Result<(), String> =
+                recorder.run("parse_qsf", || Err("bad qsf".to_string()));
+            assert!(failed.is_err());
+            recorder.finish()
+        };
+
+        assert_eq!(report.analysis_id, "a1");
+        assert!(!report.ok);
+        assert_eq!(
+            report
+                .stages
+                .iter()
+                .map(|s| s.stage.as_str())
+                .collect::<Vec<&str>>(),
+            vec!["read_inputs", "parse_qsf"]
+        );
+        assert_eq!(report.stages[1].error.as_deref(), Some("bad qsf"));
+        assert_eq!(
+            events,
+            vec![
+                ("read_inputs".to_string(), "started".to_string()),
+                ("read_inputs".to_string(), "finished".to_string()),
+                ("parse_qsf".to_string(), "started".to_string()),
+                ("parse_qsf".to_string(), "failed".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn stored_report_is_returned_by_analysis_id() {
+        let mut recorder = StageRecorder::new("report-store-test", |_| {});
+        recorder.run("build_spec", || Ok(())).expect("stage ok");
+        store_generation_report(recorder.finish());
+        let report = get_last_generation_report("report-store-test".to_string())
+            .expect("lookup")
+            .expect("report present");
+        assert!(report.ok);
+        assert_eq!(report.stages.len(), 1);
+        assert!(get_last_generation_report("missing".to_string())
+            .expect("lookup")
+            .is_none());
+    }
+}
@@ -0,0 +1,476 @@
+use serde::{Deserialize, Serialize};
+use std::io::{BufRead, BufReader, Read};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::sync::mpsc;
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Manager};
+
+use crate::commands::analysis::read_spec;
+use crate::template::DEFAULT_PACKAGES;
+
+pub const R_INSTALL_OUTPUT_EVENT: &str = "r-install-output";
+
+const CHECK_TIMEOUT: Duration = Duration::from_secs(20);
+/// `CREATE_NO_WINDOW`: keeps Rscript from flashing a console window on Windows.
+#[cfg(windows)]
+const CREATE_NO_WINDOW: u32 = 0x0800_0000;
+const CRAN_MIRROR: &str = "https://cloud.r-project.org";
+
+// Prints the R version, then one MISSING= line per package that is not installed.
+// system.file() avoids loading each namespace, which keeps the check fast.
+const CHECK_SCRIPT: &str = "cat('R_VERSION=', R.version$major, '.', R.version$minor, '\\n', sep = ''); \
+for (p in commandArgs(TRUE)) if (!nzchar(system.file(package = p))) cat('MISSING=', p, '\\n', sep = '')";
+// After an install, install.packages() exits 0 even when a package failed to build, so each
+// package is loaded to confirm it is usable.
+const LOAD_CHECK_SCRIPT: &str = "for (p in commandArgs(TRUE)) \
+if (!suppressWarnings(requireNamespace(p, quietly = TRUE))) cat('MISSING=', p, '\\n', sep = '')";
+const LOAD_CHECK_TIMEOUT: Duration = Duration::from_secs(120);
+
+#[derive(Debug, Serialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum REnvironmentStatus {
+    /// No Rscript on PATH or in the usual install folders.
+    RNotFound,
+    /// Rscript was found but did not answer within the timeout.
+    TimedOut,
+    PackagesMissing,
+    Ready,
+}
+
+#[derive(Debug, Serialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct REnvironmentReport {
+    pub status: REnvironmentStatus,
+    pub rscript_path: Option<String>,
+    pub r_version: Option<String>,
+    pub checked_packages: Vec<String>,
+    pub missing_packages: Vec<String>,
+    /// `install.packages(...)` call for the missing packages, if any.
+    pub install_command: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CheckREnvironmentArgs {
+    /// Explicit Rscript location; otherwise PATH and common install folders are searched.
+    #[serde(default)]
+    rscript_path: Option<String>,
+    /// When all three ids are given, the spec's `template_bindings.packages` are checked
+    /// instead of the default package list.
+    #[serde(default)]
+    project_id: Option<String>,
+    #[serde(default)]
+    study_id: Option<String>,
+    #[serde(default)]
+    analysis_id: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct InstallRPackagesArgs {
+    packages: Vec<String>,
+    #[serde(default)]
+    rscript_path: Option<String>,
+}
+
+#[derive(Debug, Serialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct RInstallLine {
+    /// "stdout" or "stderr".
+    pub stream: String,
+    pub line: String,
+}
+
+#[derive(Debug, Serialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct RInstallReport {
+    pub success: bool,
+    pub exit_code: Option<i32>,
+    pub installed_packages: Vec<String>,
+}
+
+fn rscript_file_name() -> &'static str {
+    if cfg!(windows) {
+        "Rscript.exe"
+    } else {
+        "Rscript"
+    }
+}
+
+/// Install folders R uses outside PATH: `C:\Program Files\R\R-x.y.z\bin` on Windows, the
+/// framework and Homebrew prefixes on macOS.
+fn common_rscript_locations() -> Vec<PathBuf> {
+    let mut out = Vec::new();
+    if cfg!(windows) {
+        for base in ["C:\\Program Files\\R", "C:\\Program Files (x86)\\R"] {
+            let Ok(entries) = std::fs::read_dir(base) else {
+                continue;
+            };
+            let mut versions: Vec<PathBuf> = entries.flatten().map(|e| e.path()).collect();
+            // Newest version first.
+            versions.sort();
+            versions.reverse();
+            for version in versions {
+                out.push(version.join("bin").join("x64").join("Rscript.exe"));
+                out.push(version.join("bin").join("Rscript.exe"));
+            }
+        }
+    } else {
+        out.push(PathBuf::from(
+            "/Library/Frameworks/R.framework/Resources/bin/Rscript",
+        ));
+        out.push(PathBuf::from("/opt/homebrew/bin/Rscript"));
+        out.push(PathBuf::from("/usr/local/bin/Rscript"));
+        out.push(PathBuf::from("/usr/bin/Rscript"));
+    }
+    out
+}
+
+pub fn locate_rscript(explicit: Option<&str>) -> Option<PathBuf> {
+    if let Some(path) = explicit.map(str::trim).filter(|p| !p.is_empty()) {
+        let path = PathBuf::from(path);
+        return path.is_file().then_some(path);
+    }
+    let on_path = std::env::var_os("PATH")
+        .map(|paths| {
+            std::env::split_paths(&paths)
+                .map(|dir| dir.join(rscript_file_name()))
+                .collect::<Vec<PathBuf>>()
+        })
+        .unwrap_or_default();
+    on_path
+        .into_iter()
+        .chain(common_rscript_locations())
+        .find(|candidate| candidate.is_file())
+}
+
+fn spawn_reader<R: Read + Send + 'static>(
+    reader: R,
+    stream: &'static str,
+    tx: mpsc::Sender<RInstallLine>,
+) -> std::thread::JoinHandle<()> {
+    std::thread::spawn(move || {
+        for line in BufReader::new(reader).lines().map_while(Result::ok) {
+            let _ = tx.send(RInstallLine {
+                stream: stream.to_string(),
+                line,
+            });
+        }
+    })
+}
+
+/// Runs Rscript in `cwd` (or the app's working directory), forwarding each output line to
+/// `on_line`. Returns `Ok(None)` when the process outlives `timeout` (it is killed), otherwise
+/// its exit code.
+pub(crate) fn run_rscript(
+    rscript: &Path,
+    args: &[String],
+    cwd: Option<&Path>,
+    timeout: Option<Duration>,
+    on_line: &mut dyn FnMut(RInstallLine),
+) -> Result<Option<Option<i32>>, String> {
+    let mut command = Command::new(rscript);
+    command
+        .args(args)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped());
+    if let Some(cwd) = cwd {
+        command.current_dir(cwd);
+    }
+    #[cfg(windows)]
+    {
+        use std::os::windows::process::CommandExt;
+        command.creation_flags(CREATE_NO_WINDOW);
+    }
+    let mut child = command
+        .spawn()
+        .map_err(|err| format!("Unable to run {}: {err}", rscript.display()))?;
+
+    let (tx, rx) = mpsc::channel();
+    let readers = [
+        child
+            .stdout
+            .take()
+            .map(|out| spawn_reader(out, "stdout", tx.clone())),
+        child
+            .stderr
+            .take()
+            .map(|err| spawn_reader(err, "stderr", tx.clone())),
+    ];
+    drop(tx);
+
+    let deadline = timeout.map(|t| Instant::now() + t);
+    let status = loop {
+        while let Ok(line) = rx.try_recv() {
+            on_line(line);
+        }
+        if let Some(status) = child.try_wait().map_err(|e| e.to_string())? {
+            break Some(status);
+        }
+        if deadline.is_some_and(|d| Instant::now() >= d) {
+            let _ = child.kill();
+            let _ = child.wait();
+            break None;
+        }
+        std::thread::sleep(Duration::from_millis(25));
+    };
+    let Some(status) = status else {
+        // Grandchildren may still hold the pipes open, so the readers are not joined.
+        return Ok(None);
+    };
+    for reader in readers.into_iter().flatten() {
+        let _ = reader.join();
+    }
+    for line in rx.try_iter() {
+        on_line(line);
+    }
+    Ok(Some(status.code()))
+}
+
+fn install_command(packages: &[String]) -> String {
+    format!(
+        "install.packages(c({}))",
+        packages
+            .iter()
+            .map(|p| format!("\"{p}\""))
+            .collect::<Vec<String>>()
+            .join(", ")
+    )
+}
+
+/// Runs `script` with `packages` as its arguments and returns its stdout lines, or `None`
+/// when Rscript outlives `timeout`.
+fn run_check_script(
+    rscript: &Path,
+    script: &str,
+    packages: &[String],
+    timeout: Duration,
+) -> Result<Option<Vec<String>>, String> {
+    let mut args = vec!["-e".to_string(), script.to_string()];
+    args.extend(packages.iter().cloned());
+    let mut stdout = Vec::new();
+    let mut stderr = Vec::new();
+    let outcome = run_rscript(rscript, &args, None, Some(timeout), &mut |line| {
+        if line.stream == "stdout" {
+            stdout.push(line.line);
+        } else {
+            stderr.push(line.line);
+        }
+    })?;
+    let Some(exit_code) = outcome else {
+        return Ok(None);
+    };
+    if exit_code != Some(0) {
+        return Err(format!(
+            "Rscript exited with status {}: {}",
+            exit_code.map_or("unknown".to_string(), |c| c.to_string()),
+            stderr.join("\n")
+        ));
+    }
+    Ok(Some(stdout))
+}
+
+fn missing_packages(stdout: &[String]) -> Vec<String> {
+    stdout
+        .iter()
+        .filter_map(|line| line.strip_prefix("MISSING="))
+        .map(|package| package.trim().to_string())
+        .collect()
+}
+
+pub fn check_r_environment_with(
+    rscript: Option<&Path>,
+    packages: &[String],
+    timeout: Duration,
+) -> Result<REnvironmentReport, String> {
+    let mut report = REnvironmentReport {
+        status: REnvironmentStatus::RNotFound,
+        rscript_path: rscript.map(|p| p.to_string_lossy().to_string()),
+        r_version: None,
+        checked_packages: packages.to_vec(),
+        missing_packages: Vec::new(),
+        install_command: None,
+    };
+    let Some(rscript) = rscript else {
+        return Ok(report);
+    };
+
+    let Some(stdout) = run_check_script(rscript, CHECK_SCRIPT, packages, timeout)? else {
+        report.status = REnvironmentStatus::TimedOut;
+        return Ok(report);
+    };
+    report.r_version = stdout
+        .iter()
+        .find_map(|line| line.strip_prefix("R_VERSION="))
+        .map(|version| version.trim().to_string());
+    report.missing_packages = missing_packages(&stdout);
+    if report.missing_packages.is_empty() {
+        report.status = REnvironmentStatus::Ready;
+    } else {
+        report.status = REnvironmentStatus::PackagesMissing;
+        report.install_command = Some(install_command(&report.missing_packages));
+    }
+    Ok(report)
+}
+
+fn is_valid_package_name(name: &str) -> bool {
+    name.chars().next().is_some_and(|c| c.is_ascii_alphabetic())
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '.')
+}
+
+/// Installs `packages`, then loads each one; only packages that load count as installed and
+/// `success` means all of them did.
+pub fn install_r_packages_with(
+    rscript: &Path,
+    packages: &[String],
+    on_line: &mut dyn FnMut(RInstallLine),
+) -> Result<RInstallReport, String> {
+    if let Some(invalid) = packages.iter().find(|p| !is_valid_package_name(p)) {
+        return Err(format!("'{invalid}' is not a valid R package name."));
+    }
+    if packages.is_empty() {
+        return Ok(RInstallReport {
+            success: true,
+            exit_code: None,
+            installed_packages: Vec::new(),
+        });
+    }
+    let mut args = vec![
+        "-e".to_string(),
+        format!("install.packages(commandArgs(TRUE), repos = '{CRAN_MIRROR}')"),
+    ];
+    args.extend(packages.iter().cloned());
+    let exit_code = run_rscript(rscript, &args, None, None, on_line)?.flatten();
+    let Some(stdout) = run_check_script(rscript, LOAD_CHECK_SCRIPT, packages, LOAD_CHECK_TIMEOUT)?
+    else {
+        return Err("Rscript did not answer while loading the installed packages.".to_string());
+    };
+    let missing = missing_packages(&stdout);
+    let installed_packages: Vec<String> = packages
+        .iter()
+        .filter(|p| !missing.contains(p))
+        .cloned()
+        .collect();
+    Ok(RInstallReport {
+        success: installed_packages.len() == packages.len(),
+        exit_code,
+        installed_packages,
+    })
+}
+
+/// Runs off the main thread so a slow Rscript start-up does not freeze the UI.
+#[tauri::command(async)]
+pub fn check_r_environment(
+    app: AppHandle,
+    args: CheckREnvironmentArgs,
+) -> Result<REnvironmentReport, String> {
+    let spec_packages = match (&args.project_id, &args.study_id, &args.analysis_id) {
+        (Some(project_id), Some(study_id), Some(analysis_id)) => {
+            read_spec(&app, project_id, study_id, analysis_id)?
+                .template_bindings
+                .packages
+        }
+        _ => Vec::new(),
+    };
+    let packages = if spec_packages.is_empty() {
+        DEFAULT_PACKAGES.iter().map(|p| p.to_string()).collect()
+    } else {
+        spec_packages
+    };
+    let rscript = locate_rscript(args.rscript_path.as_deref());
+    check_r_environment_with(rscript.as_deref(), &packages, CHECK_TIMEOUT)
+}
+
+/// Installs packages from CRAN non-interactively, emitting each output line as an
+/// `R_INSTALL_OUTPUT_EVENT`. Runs off the main thread so long installs do not freeze the UI.
+#[tauri::command(async)]
+pub fn install_r_packages(
+    app: AppHandle,
+    args: InstallRPackagesArgs,
+) -> Result<RInstallReport, String> {
+    let rscript = locate_rscript(args.rscript_path.as_deref())
+        .ok_or_else(|| "Rscript was not found; install R first.".to_string())?;
+    install_r_packages_with(&rscript, &args.packages, &mut |line| {
+        let _ = app.emit_all(R_INSTALL_OUTPUT_EVENT, line);
+    })
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use std::fs;
+    use std::os::unix::fs::PermissionsExt;
+    use uuid::Uuid;
+
+    fn fake_rscript(body: &str) -> (PathBuf, PathBuf) {
+        let dir = std::env::temp_dir().join(format!("fake-rscript-{}", Uuid::new_v4()));
+        fs::create_dir_all(&dir).expect("mkdir");
+        let path = dir.join("Rscript");
+        fs::write(&path, format!("#!/bin/sh\n{body}\n")).expect("write script");
+        fs::set_permissions(&path, fs::Permissions::from_mode(0o755)).expect("chmod");
+        (dir, path)
+    }
+
+    #[test]
+    fn check_distinguishes_missing_r_missing_packages_and_hangs() {
+        let packages = vec!["tidyverse".to_string(), "brms".to_string()];
+        let report = check_r_environment_with(None, &packages, CHECK_TIMEOUT).expect("report");
+        assert_eq!(report.status, REnvironmentStatus::RNotFound);
+
+        let (dir, rscript) = fake_rscript("echo R_VERSION=4.3.2\necho MISSING=brms");
+        assert_eq!(
+            locate_rscript(Some(&rscript.to_string_lossy())),
+            Some(rscript.clone())
+        );
+        let report =
+            check_r_environment_with(Some(&rscript), &packages, CHECK_TIMEOUT).expect("report");
+        assert_eq!(report.status, REnvironmentStatus::PackagesMissing);
+        assert_eq!(report.r_version.as_deref(), Some("4.3.2"));
+        assert_eq!(report.missing_packages, vec!["brms"]);
+        assert_eq!(
+            report.install_command.as_deref(),
+            Some("install.packages(c(\"brms\"))")
+        );
+
+        let (hang_dir, hanging) = fake_rscript("exec sleep 10");
+        let started = Instant::now();
+        let report =
+            check_r_environment_with(Some(&hanging), &packages, Duration::from_millis(200))
+                .expect("report");
+        assert_eq!(report.status, REnvironmentStatus::TimedOut);
+        assert!(started.elapsed() < Duration::from_secs(5));
+        let _ = fs::remove_dir_all(dir);
+        let _ = fs::remove_dir_all(hang_dir);
+    }
+
+    #[test]
+    fn install_streams_output_lines_and_rejects_bad_names() {
+        // install.packages() exits 0 even though rstan failed to build and does not load.
+        let (dir, rscript) = fake_rscript(
+            "case \"$2\" in\n\
+             install.packages*) echo installing; echo 'done' >&2 ;;\n\
+             *requireNamespace*) echo MISSING=rstan ;;\n\
+             esac",
+        );
+        let mut lines = Vec::new();
+        let packages = vec!["brms".to_string(), "rstan".to_string()];
+        let report = install_r_packages_with(&rscript, &packages, &mut |line| lines.push(line))
+            .expect("install");
+        assert_eq!(report.exit_code, Some(0));
+        assert!(!report.success);
+        assert_eq!(report.installed_packages, vec!["brms"]);
+        assert!(lines
+            .iter()
+            .any(|l| l.stream == "stdout" && l.line == "installing"));
+        assert!(lines
+            .iter()
+            .any(|l| l.stream == "stderr" && l.line == "done"));
+
+        let err = install_r_packages_with(&rscript, &["x'); unlink('~".to_string()], &mut |_| {})
+            .unwrap_err();
+        assert!(err.contains("not a valid R package name"));
+        let _ = fs::remove_dir_all(dir);
+    }
+}
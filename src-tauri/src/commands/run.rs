@@ -0,0 +1,380 @@
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashSet, VecDeque};
+use std::fs::{self, File};
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, OnceLock};
+use tauri::{AppHandle, Manager};
+
+use crate::commands::assets::{app_data_root, resolve_project_root, resolve_study_root};
+use crate::commands::r_env::{locate_rscript, run_rscript, RInstallLine};
+use crate::render::helpers::analysis_paths;
+use crate::store::activity::record_activity;
+use crate::store::sqlite::track_generated_artifact;
+use crate::template::ANALYSIS_FOLDER;
+
+pub const ANALYSIS_RUN_OUTPUT_EVENT: &str = "analysis-run-output";
+
+/// Study-relative folder the knitted report and the run log are written to.
+const REPORTS_DIR: &str = "07_outputs/reports";
+/// Log lines returned with a failed run.
+const FAILURE_TAIL_LINES: usize = 50;
+const OUTPUT_MARKER: &str = "RUN_OUTPUT=";
+
+// Paths come in through commandArgs() so nothing needs quoting for R. The report goes
+// to the study's reports folder and its path is printed after a marker.
+const RENDER_SCRIPT: &str = "args <- commandArgs(TRUE); \
+out <- rmarkdown::render(args[1], output_format = 'html_document', output_dir = args[2], \
+knit_root_dir = getwd(), envir = new.env()); \
+cat('\\nRUN_OUTPUT=', normalizePath(out), '\\n', sep = '')";
+
+/// Studies with a render in progress; a second run for the same study is refused.
+static RUNNING_STUDIES: OnceLock<Mutex<HashSet<String>>> = OnceLock::new();
+
+fn running_studies() -> &'static Mutex<HashSet<String>> {
+    RUNNING_STUDIES.get_or_init(|| Mutex::new(HashSet::new()))
+}
+
+/// Marks a study as running until dropped.
+struct StudyRunGuard(String);
+
+impl StudyRunGuard {
+    fn acquire(study_id: &str) -> Result<Self, String> {
+        let mut running = running_studies()
+            .lock()
+            .unwrap_or_else(|err| err.into_inner());
+        if !running.insert(study_id.to_string()) {
+            return Err(format!(
+                "An analysis is already running for study {study_id}; wait for it to finish."
+            ));
+        }
+        Ok(Self(study_id.to_string()))
+    }
+}
+
+impl Drop for StudyRunGuard {
+    fn drop(&mut self) {
+        running_studies()
+            .lock()
+            .unwrap_or_else(|err| err.into_inner())
+            .remove(&self.0);
+    }
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RunAnalysisArgs {
+    project_id: String,
+    study_id: String,
+    /// Template file in the study's `06_analysis` folder, with or without `.Rmd`.
+    #[serde(default)]
+    analysis_name: Option<String>,
+    /// Spec-driven analysis; its `analysis/analysis.Rmd` is knitted.
+    #[serde(default)]
+    analysis_id: Option<String>,
+    #[serde(default)]
+    rscript_path: Option<String>,
+}
+
+#[derive(Debug, Serialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct AnalysisRunLine {
+    pub study_id: String,
+    /// "stdout" or "stderr".
+    pub stream: String,
+    pub line: String,
+}
+
+#[derive(Debug, Serialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct AnalysisRunReport {
+    pub success: bool,
+    pub exit_code: Option<i32>,
+    pub rmd_path: String,
+    /// Knitted HTML; set only when the run succeeded.
+    pub report_path: Option<String>,
+    pub log_path: String,
+    /// Last lines of the log when the run failed.
+    pub log_tail: Vec<String>,
+}
+
+/// Adds a log line to the failure tail. Once the tail is full the oldest stdout line is
+/// dropped first: stdout and stderr are read on separate threads, so a burst of stdout could
+/// otherwise push R's error (on stderr) out of the tail.
+fn push_tail(tail: &mut VecDeque<(bool, String)>, is_stderr: bool, line: String) {
+    if tail.len() == FAILURE_TAIL_LINES {
+        match tail.iter().position(|(stderr, _)| !stderr) {
+            Some(index) => {
+                tail.remove(index);
+            }
+            None => {
+                tail.pop_front();
+            }
+        }
+    }
+    tail.push_back((is_stderr, line));
+}
+
+fn single_name<'a>(value: &'a str, what: &str) -> Result<&'a str, String> {
+    let value = value.trim();
+    if value.is_empty() || value.contains(['/', '\\']) || value.contains("..") {
+        return Err(format!("{what} must be a single file name."));
+    }
+    Ok(value)
+}
+
+/// The Rmd a run knits: `06_analysis/<analysis_id>/analysis/analysis.Rmd` for the spec-driven
+/// path, otherwise `06_analysis/<analysis_name>.Rmd`.
+pub(crate) fn locate_analysis_rmd(
+    study_root: &Path,
+    analysis_name: Option<&str>,
+    analysis_id: Option<&str>,
+) -> Result<PathBuf, String> {
+    let analysis_dir = study_root.join(ANALYSIS_FOLDER);
+    let rmd = match (analysis_id, analysis_name) {
+        (Some(id), _) => analysis_paths(&analysis_dir.join(single_name(id, "Analysis id")?)).1,
+        (None, Some(name)) => {
+            let name = single_name(name, "Analysis name")?;
+            if Path::new(name)
+                .extension()
+                .is_some_and(|ext| ext.eq_ignore_ascii_case("rmd"))
+            {
+                analysis_dir.join(name)
+            } else {
+                analysis_dir.join(format!("{name}.Rmd"))
+            }
+        }
+        (None, None) => return Err("Choose an analysis to run.".to_string()),
+    };
+    if !rmd.is_file() {
+        return Err(format!("Analysis file not found: {}", rmd.display()));
+    }
+    Ok(rmd)
+}
+
+/// Knits `rmd` to HTML in the study's reports folder with `project_root` as the working
+/// directory. Every output line goes to `on_line` and to a timestamped log next to the
+/// report; a successful run registers the HTML as an `analysis_report` artifact.
+pub(crate) fn run_analysis_at(
+    app_root: &Path,
+    rscript: &Path,
+    project_root: &Path,
+    study_root: &Path,
+    study_id: &str,
+    rmd: &Path,
+    on_line: &mut dyn FnMut(RInstallLine),
+) -> Result<AnalysisRunReport, String> {
+    let _guard = StudyRunGuard::acquire(study_id)?;
+    let reports_dir = study_root.join(REPORTS_DIR);
+    fs::create_dir_all(&reports_dir).map_err(|err| err.to_string())?;
+    let stem = rmd
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().to_string())
+        .unwrap_or_else(|| "analysis".to_string());
+    let log_path = reports_dir.join(format!(
+        "{stem}_run_{}.log",
+        Utc::now().format("%Y%m%dT%H%M%SZ")
+    ));
+    let mut log = BufWriter::new(File::create(&log_path).map_err(|err| err.to_string())?);
+
+    let args = vec![
+        "-e".to_string(),
+        RENDER_SCRIPT.to_string(),
+        rmd.to_string_lossy().to_string(),
+        reports_dir.to_string_lossy().to_string(),
+    ];
+    let mut tail: VecDeque<(bool, String)> = VecDeque::with_capacity(FAILURE_TAIL_LINES);
+    let mut output: Option<PathBuf> = None;
+    let mut log_error: Option<std::io::Error> = None;
+    let outcome = run_rscript(rscript, &args, Some(project_root), None, &mut |line| {
+        if let Some(path) = line.line.strip_prefix(OUTPUT_MARKER) {
+            output = Some(PathBuf::from(path.trim()));
+        }
+        let is_stderr = line.stream == "stderr";
+        let logged = if is_stderr {
+            format!("[stderr] {}", line.line)
+        } else {
+            line.line.clone()
+        };
+        if let Err(err) = writeln!(log, "{logged}") {
+            log_error.get_or_insert(err);
+        }
+        push_tail(&mut tail, is_stderr, logged);
+        on_line(line);
+    })?;
+    log.flush().map_err(|err| err.to_string())?;
+    if let Some(err) = log_error {
+        return Err(format!("Unable to write {}: {err}", log_path.display()));
+    }
+
+    let exit_code = outcome.flatten();
+    let report_path = output
+        .filter(|path| path.is_file())
+        .filter(|_| exit_code == Some(0));
+    let mut report = AnalysisRunReport {
+        success: report_path.is_some(),
+        exit_code,
+        rmd_path: rmd.to_string_lossy().to_string(),
+        report_path: report_path
+            .as_ref()
+            .map(|path| path.to_string_lossy().to_string()),
+        log_path: log_path.to_string_lossy().to_string(),
+        log_tail: Vec::new(),
+    };
+    match &report_path {
+        Some(path) => {
+            track_generated_artifact(
+                app_root,
+                study_id,
+                "analysis_report",
+                path,
+                path.file_name().and_then(|name| name.to_str()),
+            );
+            record_activity(
+                app_root,
+                "run_analysis",
+                None,
+                Some(study_id),
+                &format!("Knitted {}", path.display()),
+            );
+        }
+        None => report.log_tail = tail.into_iter().map(|(_, line)| line).collect(),
+    }
+    Ok(report)
+}
+
+/// Knits an analysis with Rscript, emitting each output line as an
+/// `ANALYSIS_RUN_OUTPUT_EVENT`. Runs off the main thread so long renders do not freeze the UI.
+#[tauri::command(async)]
+pub fn run_analysis(app: AppHandle, args: RunAnalysisArgs) -> Result<AnalysisRunReport, String> {
+    let study_root = resolve_study_root(&app, &args.project_id, &args.study_id)?;
+    let project_root = resolve_project_root(&app, &args.project_id)?;
+    let rmd = locate_analysis_rmd(
+        &study_root,
+        args.analysis_name.as_deref(),
+        args.analysis_id.as_deref(),
+    )?;
+    let rscript = locate_rscript(args.rscript_path.as_deref())
+        .ok_or_else(|| "Rscript was not found; install R first.".to_string())?;
+    let app_root = app_data_root(&app)?;
+    let study_id = args.study_id.clone();
+    run_analysis_at(
+        &app_root,
+        &rscript,
+        &project_root,
+        &study_root,
+        &args.study_id,
+        &rmd,
+        &mut |line| {
+            let _ = app.emit_all(
+                ANALYSIS_RUN_OUTPUT_EVENT,
+                AnalysisRunLine {
+                    study_id: study_id.clone(),
+                    stream: line.stream,
+                    line: line.line,
+                },
+            );
+        },
+    )
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use std::os::unix::fs::PermissionsExt;
+    use uuid::Uuid;
+
+    /// A study folder with `06_analysis/analysis.Rmd` and a fake Rscript running `body`.
+    fn fixture(body: &str) -> (PathBuf, PathBuf, PathBuf) {
+        let base = std::env::temp_dir().join(format!("run-analysis-{}", Uuid::new_v4()));
+        let study_root = base.join("studies").join("S-RUN001");
+        fs::create_dir_all(study_root.join(ANALYSIS_FOLDER)).expect("mkdir");
+        fs::write(
+            study_root.join(ANALYSIS_FOLDER).join("analysis.Rmd"),
+            "---\ntitle: x\n---\n",
+        )
+        .expect("write rmd");
+        let rscript = base.join("Rscript");
+        fs::write(&rscript, format!("#!/bin/sh\n{body}\n")).expect("write script");
+        fs::set_permissions(&rscript, fs::Permissions::from_mode(0o755)).expect("chmod");
+        (base, study_root, rscript)
+    }
+
+    #[test]
+    fn successful_run_returns_the_report_and_logs_every_line() {
+        // $3 is the Rmd and $4 the reports folder, after `-e <script>`.
+        let (base, study_root, rscript) = fixture(
+            "pwd\necho 'processing file' >&2\necho '<html></html>' > \"$4/analysis.html\"\necho \"RUN_OUTPUT=$4/analysis.html\"",
+        );
+        let rmd = locate_analysis_rmd(&study_root, Some("analysis"), None).expect("rmd");
+        let mut lines = Vec::new();
+        let report = run_analysis_at(
+            &base.join("app"),
+            &rscript,
+            &base,
+            &study_root,
+            "S-RUN001",
+            &rmd,
+            &mut |line| lines.push(line.line),
+        )
+        .expect("run");
+
+        assert!(report.success);
+        assert_eq!(report.exit_code, Some(0));
+        let html = study_root.join(REPORTS_DIR).join("analysis.html");
+        assert_eq!(
+            report.report_path.as_deref(),
+            Some(html.to_string_lossy().as_ref())
+        );
+        assert!(report.log_tail.is_empty());
+        assert_eq!(
+            fs::canonicalize(&lines[0]).expect("cwd"),
+            fs::canonicalize(&base).expect("base")
+        );
+        let log = fs::read_to_string(&report.log_path).expect("log");
+        assert!(log.contains("[stderr] processing file\n"));
+        assert!(log.contains("RUN_OUTPUT="));
+        let _ = fs::remove_dir_all(base);
+    }
+
+    #[test]
+    fn failed_run_returns_the_log_tail_and_a_second_run_is_refused() {
+        let (base, study_root, rscript) = fixture(
+            "for i in $(seq 1 200); do echo \"line $i\"; done\necho 'Error: boom' >&2\nexit 3",
+        );
+        let rmd = locate_analysis_rmd(&study_root, Some("analysis.Rmd"), None).expect("rmd");
+        let run = || {
+            run_analysis_at(
+                &base.join("app"),
+                &rscript,
+                &base,
+                &study_root,
+                "S-RUN002",
+                &rmd,
+                &mut |_| {},
+            )
+        };
+
+        let report = run().expect("run");
+        assert!(!report.success);
+        assert_eq!(report.exit_code, Some(3));
+        assert!(report.report_path.is_none());
+        assert_eq!(report.log_tail.len(), FAILURE_TAIL_LINES);
+        // The stdout flood may be read after the error, but it cannot evict it.
+        assert!(report
+            .log_tail
+            .iter()
+            .any(|line| line == "[stderr] Error: boom"));
+
+        let guard = StudyRunGuard::acquire("S-RUN002").expect("guard");
+        let err = run().unwrap_err();
+        assert!(err.contains("already running"));
+        drop(guard);
+        assert!(run().is_ok());
+
+        assert!(locate_analysis_rmd(&study_root, Some("../x"), None).is_err());
+        assert!(locate_analysis_rmd(&study_root, None, Some("missing")).is_err());
+        let _ = fs::remove_dir_all(base);
+    }
+}
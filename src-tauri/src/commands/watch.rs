@@ -0,0 +1,520 @@
+use notify::event::{EventKind, ModifyKind, RenameMode};
+use notify::{Event, RecursiveMode, Watcher};
+use notify_debouncer_full::{new_debouncer, DebounceEventResult, Debouncer, FileIdMap};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeSet, HashMap};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard, OnceLock};
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+use tauri::{AppHandle, Manager};
+
+use crate::commands::assets::{resolve_project_root, visit_files_recursive};
+
+pub const PROJECT_FILES_EVENT: &str = "project-files-changed";
+
+const DEBOUNCE: Duration = Duration::from_millis(500);
+/// How often the supervisor checks that the watched folder is still there.
+const ROOT_CHECK_INTERVAL: Duration = Duration::from_secs(3);
+
+#[derive(Debug, Serialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum FileChangeKind {
+    Created,
+    Removed,
+    Renamed,
+    /// The `studies/` folder went away (deleted, or its drive was unmounted).
+    Unavailable,
+    /// The folder came back or the watcher lost events; the index was rebuilt from disk.
+    Resynced,
+}
+
+#[derive(Debug, Serialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct ProjectFileEvent {
+    pub project_id: String,
+    pub kind: FileChangeKind,
+    /// First folder under `studies/` the file sits in.
+    pub study_id: Option<String>,
+    pub path: Option<String>,
+    /// Previous path of a renamed file.
+    pub from_path: Option<String>,
+}
+
+#[derive(Debug, Serialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct WatchStatus {
+    pub project_id: String,
+    pub watching: bool,
+    /// Whether `studies/` exists right now; the watch resumes when it reappears.
+    pub available: bool,
+    pub indexed_files: usize,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WatchProjectArgs {
+    project_id: String,
+}
+
+/// Where change notifications go; the app forwards them to the frontend.
+pub trait FileEventSink: Send + Sync + 'static {
+    fn emit(&self, event: ProjectFileEvent);
+}
+
+impl FileEventSink for AppHandle {
+    fn emit(&self, event: ProjectFileEvent) {
+        let _ = self.emit_all(PROJECT_FILES_EVENT, event);
+    }
+}
+
+/// Files under a project's `studies/` folder, kept current from watcher events.
+#[derive(Debug, Default)]
+struct FileIndex {
+    files: BTreeSet<PathBuf>,
+    available: bool,
+}
+
+impl FileIndex {
+    fn rescan(&mut self, studies_dir: &Path) {
+        self.files.clear();
+        self.available = studies_dir.is_dir();
+        let mut found = Vec::new();
+        if self.available && visit_files_recursive(studies_dir, &mut found).is_ok() {
+            self.files.extend(found);
+        }
+    }
+
+    fn insert_existing(&mut self, path: &Path) -> Vec<PathBuf> {
+        let mut found = Vec::new();
+        if path.is_dir() {
+            let _ = visit_files_recursive(path, &mut found);
+        } else if path.is_file() {
+            found.push(path.to_path_buf());
+        }
+        found
+            .into_iter()
+            .filter(|file| self.files.insert(file.clone()))
+            .collect()
+    }
+
+    fn remove_under(&mut self, path: &Path) -> Vec<PathBuf> {
+        let removed = self
+            .files
+            .iter()
+            .filter(|file| file.starts_with(path))
+            .cloned()
+            .collect::<Vec<PathBuf>>();
+        for file in &removed {
+            self.files.remove(file);
+        }
+        removed
+    }
+
+    /// Applies watcher events and returns the changes they made to the index.
+    fn apply(
+        &mut self,
+        project_id: &str,
+        studies_dir: &Path,
+        events: &[Event],
+    ) -> Vec<ProjectFileEvent> {
+        let change = |kind, path: &Path, from: Option<&Path>| ProjectFileEvent {
+            project_id: project_id.to_string(),
+            kind,
+            study_id: path
+                .strip_prefix(studies_dir)
+                .ok()
+                .and_then(|rest| rest.components().next())
+                .map(|first| first.as_os_str().to_string_lossy().to_string()),
+            path: Some(path.to_string_lossy().to_string()),
+            from_path: from.map(|from| from.to_string_lossy().to_string()),
+        };
+        let mut changes = Vec::new();
+        for event in events {
+            if event.need_rescan() {
+                self.rescan(studies_dir);
+                changes.push(ProjectFileEvent {
+                    project_id: project_id.to_string(),
+                    kind: FileChangeKind::Resynced,
+                    study_id: None,
+                    path: None,
+                    from_path: None,
+                });
+                continue;
+            }
+            let paths = event
+                .paths
+                .iter()
+                .filter(|path| path.starts_with(studies_dir));
+            match event.kind {
+                EventKind::Modify(ModifyKind::Name(RenameMode::Both)) if event.paths.len() == 2 => {
+                    let (from, to) = (&event.paths[0], &event.paths[1]);
+                    let moved = self.remove_under(from);
+                    let added = if to.starts_with(studies_dir) {
+                        self.insert_existing(to)
+                    } else {
+                        Vec::new()
+                    };
+                    for file in &added {
+                        let old = file.strip_prefix(to).ok().map(|rest| from.join(rest));
+                        match old.filter(|old| moved.contains(old)) {
+                            Some(old) => changes.push(change(
+                                FileChangeKind::Renamed,
+                                file,
+                                Some(old.as_path()),
+                            )),
+                            None => changes.push(change(FileChangeKind::Created, file, None)),
+                        }
+                    }
+                    for file in moved {
+                        let renamed = file
+                            .strip_prefix(from)
+                            .is_ok_and(|rest| added.contains(&to.join(rest)));
+                        if !renamed {
+                            changes.push(change(FileChangeKind::Removed, &file, None));
+                        }
+                    }
+                }
+                EventKind::Remove(_) | EventKind::Modify(ModifyKind::Name(RenameMode::From)) => {
+                    for path in paths {
+                        for file in self.remove_under(path) {
+                            changes.push(change(FileChangeKind::Removed, &file, None));
+                        }
+                    }
+                }
+                EventKind::Create(_) | EventKind::Modify(_) => {
+                    // Covers plain creates, the `To` half of a split rename and platforms that
+                    // only report "renamed": whatever exists now is added, the rest dropped.
+                    for path in paths {
+                        if path.exists() {
+                            for file in self.insert_existing(path) {
+                                changes.push(change(FileChangeKind::Created, &file, None));
+                            }
+                        } else {
+                            for file in self.remove_under(path) {
+                                changes.push(change(FileChangeKind::Removed, &file, None));
+                            }
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+        changes
+    }
+}
+
+struct ProjectWatch {
+    studies_dir: PathBuf,
+    index: Arc<Mutex<FileIndex>>,
+    stop: Arc<AtomicBool>,
+    thread: JoinHandle<()>,
+}
+
+static WATCHES: OnceLock<Mutex<HashMap<String, ProjectWatch>>> = OnceLock::new();
+
+fn watches() -> MutexGuard<'static, HashMap<String, ProjectWatch>> {
+    WATCHES
+        .get_or_init(|| Mutex::new(HashMap::new()))
+        .lock()
+        .unwrap_or_else(|err| err.into_inner())
+}
+
+fn lock_index(index: &Mutex<FileIndex>) -> MutexGuard<'_, FileIndex> {
+    index.lock().unwrap_or_else(|err| err.into_inner())
+}
+
+/// Files under `dir` from a live project index, or `None` when no available watch covers it.
+pub(crate) fn indexed_files_under(dir: &Path) -> Option<Vec<PathBuf>> {
+    let watches = watches();
+    let watch = watches
+        .values()
+        .find(|watch| dir.starts_with(&watch.studies_dir))?;
+    let index = lock_index(&watch.index);
+    if !index.available {
+        return None;
+    }
+    Some(
+        index
+            .files
+            .iter()
+            .filter(|file| file.starts_with(dir))
+            .cloned()
+            .collect(),
+    )
+}
+
+fn start_debouncer(
+    project_id: &str,
+    studies_dir: &Path,
+    index: &Arc<Mutex<FileIndex>>,
+    sink: &Arc<dyn FileEventSink>,
+) -> Result<Debouncer<notify::RecommendedWatcher, FileIdMap>, String> {
+    let (project_id, root) = (project_id.to_string(), studies_dir.to_path_buf());
+    let (index_for_events, sink_for_events) = (Arc::clone(index), Arc::clone(sink));
+    let mut debouncer = new_debouncer(DEBOUNCE, None, move |result: DebounceEventResult| {
+        // Watcher errors are left to the supervisor, which notices a missing root.
+        let Ok(events) = result else {
+            return;
+        };
+        let events = events
+            .into_iter()
+            .map(|debounced| debounced.event)
+            .collect::<Vec<Event>>();
+        let changes = {
+            let mut index = lock_index(&index_for_events);
+            if !index.available {
+                return;
+            }
+            index.apply(&project_id, &root, &events)
+        };
+        for change in changes {
+            sink_for_events.emit(change);
+        }
+    })
+    .map_err(|err| format!("Unable to start the file watcher: {err}"))?;
+    debouncer
+        .watcher()
+        .watch(studies_dir, RecursiveMode::Recursive)
+        .map_err(|err| format!("Unable to watch {}: {err}", studies_dir.display()))?;
+    debouncer
+        .cache()
+        .add_root(studies_dir, RecursiveMode::Recursive);
+    Ok(debouncer)
+}
+
+/// Keeps a watch on `studies_dir` while it exists. When it disappears the watcher is dropped and
+/// the index marked unavailable; when it returns the watch restarts from a fresh scan.
+fn supervise(
+    project_id: String,
+    studies_dir: PathBuf,
+    index: Arc<Mutex<FileIndex>>,
+    stop: Arc<AtomicBool>,
+    sink: Arc<dyn FileEventSink>,
+    mut debouncer: Option<Debouncer<notify::RecommendedWatcher, FileIdMap>>,
+) {
+    let notice = |kind| ProjectFileEvent {
+        project_id: project_id.clone(),
+        kind,
+        study_id: None,
+        path: None,
+        from_path: None,
+    };
+    while !stop.load(Ordering::SeqCst) {
+        thread::park_timeout(ROOT_CHECK_INTERVAL);
+        if stop.load(Ordering::SeqCst) {
+            break;
+        }
+        let present = studies_dir.is_dir();
+        if debouncer.is_some() && !present {
+            debouncer = None;
+            let mut index = lock_index(&index);
+            index.files.clear();
+            index.available = false;
+            drop(index);
+            sink.emit(notice(FileChangeKind::Unavailable));
+        } else if debouncer.is_none() && present {
+            if let Ok(started) = start_debouncer(&project_id, &studies_dir, &index, &sink) {
+                debouncer = Some(started);
+                lock_index(&index).rescan(&studies_dir);
+                sink.emit(notice(FileChangeKind::Resynced));
+            }
+        }
+    }
+}
+
+fn status(project_id: &str, watch: Option<&ProjectWatch>) -> WatchStatus {
+    let (available, indexed_files) = watch
+        .map(|watch| {
+            let index = lock_index(&watch.index);
+            (index.available, index.files.len())
+        })
+        .unwrap_or((false, 0));
+    WatchStatus {
+        project_id: project_id.to_string(),
+        watching: watch.is_some(),
+        available,
+        indexed_files,
+    }
+}
+
+fn stop_watch(watch: ProjectWatch) {
+    watch.stop.store(true, Ordering::SeqCst);
+    watch.thread.thread().unpark();
+    let _ = watch.thread.join();
+}
+
+/// Starts watching `<project_root>/studies`, replacing a watch on a different folder. A
+/// folder that is missing now is picked up once it appears.
+pub(crate) fn watch_project_at(
+    project_id: &str,
+    project_root: &Path,
+    sink: Arc<dyn FileEventSink>,
+) -> Result<WatchStatus, String> {
+    let studies_dir = project_root.join("studies");
+    let mut watches = watches();
+    if let Some(existing) = watches.get(project_id) {
+        if existing.studies_dir == studies_dir {
+            return Ok(status(project_id, Some(existing)));
+        }
+    }
+    if let Some(previous) = watches.remove(project_id) {
+        stop_watch(previous);
+    }
+
+    let index = Arc::new(Mutex::new(FileIndex::default()));
+    let debouncer = if studies_dir.is_dir() {
+        Some(start_debouncer(project_id, &studies_dir, &index, &sink)?)
+    } else {
+        None
+    };
+    // Scan after the watcher is up so nothing created in between is missed.
+    lock_index(&index).rescan(&studies_dir);
+    let stop = Arc::new(AtomicBool::new(false));
+    let thread = {
+        let (project_id, studies_dir) = (project_id.to_string(), studies_dir.clone());
+        let (index, stop) = (Arc::clone(&index), Arc::clone(&stop));
+        thread::Builder::new()
+            .name(format!("watch-{project_id}"))
+            .spawn(move || supervise(project_id, studies_dir, index, stop, sink, debouncer))
+            .map_err(|err| format!("Unable to start the file watcher: {err}"))?
+    };
+    let watch = ProjectWatch {
+        studies_dir,
+        index,
+        stop,
+        thread,
+    };
+    let current = status(project_id, Some(&watch));
+    watches.insert(project_id.to_string(), watch);
+    Ok(current)
+}
+
+pub(crate) fn unwatch_project_at(project_id: &str) -> WatchStatus {
+    let removed = watches().remove(project_id);
+    if let Some(watch) = removed {
+        stop_watch(watch);
+    }
+    status(project_id, None)
+}
+
+#[tauri::command]
+pub fn watch_project(app: AppHandle, args: WatchProjectArgs) -> Result<WatchStatus, String> {
+    let project_root = resolve_project_root(&app, &args.project_id)?;
+    watch_project_at(&args.project_id, &project_root, Arc::new(app))
+}
+
+#[tauri::command]
+pub fn unwatch_project(args: WatchProjectArgs) -> WatchStatus {
+    unwatch_project_at(&args.project_id)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use notify::event::{CreateKind, RemoveKind};
+    use std::fs;
+    use uuid::Uuid;
+
+    #[derive(Default)]
+    struct RecordingSink(Mutex<Vec<ProjectFileEvent>>);
+
+    impl FileEventSink for RecordingSink {
+        fn emit(&self, event: ProjectFileEvent) {
+            self.0.lock().expect("sink").push(event);
+        }
+    }
+
+    fn event(kind: EventKind, paths: &[&Path]) -> Event {
+        paths.iter().fold(Event::new(kind), |event, path| {
+            event.add_path(path.to_path_buf())
+        })
+    }
+
+    #[test]
+    fn index_follows_creates_renames_and_removals() {
+        let studies = std::env::temp_dir()
+            .join(format!("watch-index-{}", Uuid::new_v4()))
+            .join("studies");
+        let build = studies.join("S-ONE").join("02_build");
+        fs::create_dir_all(&build).expect("mkdir");
+        fs::write(build.join("old.qsf"), "{}").expect("write");
+        let mut index = FileIndex::default();
+        index.rescan(&studies);
+        assert!(index.available);
+
+        // A collaborator drops in a folder of files and renames the survey export.
+        let pilots = studies.join("S-ONE").join("03_pilots");
+        fs::create_dir_all(&pilots).expect("mkdir");
+        fs::write(pilots.join("a.csv"), "x").expect("write");
+        fs::write(pilots.join("b.csv"), "y").expect("write");
+        fs::rename(build.join("old.qsf"), build.join("new.qsf")).expect("rename");
+        let changes = index.apply(
+            "p1",
+            &studies,
+            &[
+                event(EventKind::Create(CreateKind::Folder), &[&pilots]),
+                event(
+                    EventKind::Modify(ModifyKind::Name(RenameMode::Both)),
+                    &[&build.join("old.qsf"), &build.join("new.qsf")],
+                ),
+                // Paths outside studies/ are ignored.
+                event(
+                    EventKind::Create(CreateKind::File),
+                    &[&studies.parent().expect("parent").join("x")],
+                ),
+            ],
+        );
+        let summary = changes
+            .iter()
+            .map(|c| (c.kind, c.study_id.as_deref(), c.from_path.is_some()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            summary,
+            vec![
+                (FileChangeKind::Created, Some("S-ONE"), false),
+                (FileChangeKind::Created, Some("S-ONE"), false),
+                (FileChangeKind::Renamed, Some("S-ONE"), true),
+            ]
+        );
+        assert_eq!(index.files.len(), 3);
+
+        fs::remove_dir_all(&pilots).expect("remove");
+        let changes = index.apply(
+            "p1",
+            &studies,
+            &[event(EventKind::Remove(RemoveKind::Folder), &[&pilots])],
+        );
+        assert_eq!(changes.len(), 2);
+        assert!(changes.iter().all(|c| c.kind == FileChangeKind::Removed));
+        assert_eq!(
+            index.files.iter().collect::<Vec<_>>(),
+            vec![&build.join("new.qsf")]
+        );
+        let _ = fs::remove_dir_all(studies.parent().expect("parent"));
+    }
+
+    #[test]
+    fn watch_serves_the_index_and_unwatch_releases_it() {
+        let root = std::env::temp_dir().join(format!("watch-project-{}", Uuid::new_v4()));
+        let build = root.join("studies").join("S-ONE").join("02_build");
+        fs::create_dir_all(&build).expect("mkdir");
+        fs::write(build.join("survey.qsf"), "{}").expect("write");
+        let sink = Arc::new(RecordingSink::default());
+
+        let status = watch_project_at("watch-p1", &root, sink.clone()).expect("watch");
+        assert!(status.watching && status.available);
+        assert_eq!(status.indexed_files, 1);
+        assert_eq!(
+            indexed_files_under(&build),
+            Some(vec![build.join("survey.qsf")])
+        );
+        assert!(indexed_files_under(&root.join("paper")).is_none());
+
+        let status = unwatch_project_at("watch-p1");
+        assert!(!status.watching);
+        assert!(indexed_files_under(&build).is_none());
+        let _ = fs::remove_dir_all(root);
+    }
+}
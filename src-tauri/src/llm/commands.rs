@@ -3,13 +3,21 @@ use std::path::PathBuf;
 use tauri::AppHandle;
 
 use super::model_manager::{
-    apply_project_preset, clear_project_lock, download_model_with_policy, load_model_from_disk,
-    lock_project_to_current_model, model_provenance_from_status, read_project_lock,
-    read_project_preset, resolve_target_model, verify_model, write_project_lock,
-    write_project_preset,
+    apply_project_preset, clear_project_lock, delete_local_model, download_model_with_policy,
+    list_local_models, load_model_from_disk, lock_project_to_current_model, model_dir_usage,
+    model_provenance_from_status, read_project_lock, read_project_preset, resolve_target_model,
+    verify_model, write_project_lock, write_project_preset,
 };
-use super::settings::{load_llm_settings, save_llm_settings, UpdatePolicy};
-use super::types::{LlmModelLock, LlmProjectPreset, ModelStatus};
+use super::provider::extract_with_fallback;
+use super::settings::{
+    load_llm_settings, save_llm_settings, LlmProvider, LlmSettings, UpdatePolicy,
+};
+use super::types::{
+    DeleteModelReport, LlmModelLock, LlmProjectPreset, LocalModel, ModelDirUsage, ModelProvenance,
+    ModelStatus,
+};
+use crate::store::read_projects_store;
+use crate::store::storage::app_data_root;
 
 fn root_opt(project_root: Option<String>) -> Option<PathBuf> {
     project_root
@@ -126,6 +134,40 @@ pub fn llm_load_model_from_disk(
     load_model_from_disk(&app, root_opt(project_root))
 }
 
+/// `(id, name, root)` of every known project, for matching model files to project locks.
+fn known_projects(app: &AppHandle) -> Result<Vec<(String, String, PathBuf)>, String> {
+    let store = read_projects_store(&app_data_root(app)?)?;
+    Ok(store
+        .projects
+        .into_iter()
+        .map(|project| (project.id, project.name, PathBuf::from(project.root_path)))
+        .collect())
+}
+
+#[tauri::command]
+pub fn llm_list_local_models(app: AppHandle) -> Result<Vec<LocalModel>, String> {
+    list_local_models(&load_llm_settings(&app)?, &known_projects(&app)?)
+}
+
+#[tauri::command]
+pub fn llm_model_dir_usage(app: AppHandle) -> Result<ModelDirUsage, String> {
+    model_dir_usage(&load_llm_settings(&app)?, &known_projects(&app)?)
+}
+
+#[tauri::command]
+pub fn llm_delete_local_model(
+    app: AppHandle,
+    file_name: String,
+    force: Option<bool>,
+) -> Result<DeleteModelReport, String> {
+    delete_local_model(
+        &load_llm_settings(&app)?,
+        &known_projects(&app)?,
+        &file_name,
+        force.unwrap_or(false),
+    )
+}
+
 #[tauri::command]
 pub fn llm_get_project_lock(project_root: String) -> Result<Option<LlmModelLock>, String> {
     read_project_lock(&PathBuf::from(project_root))
@@ -183,6 +225,19 @@ pub fn llm_apply_project_preset(
     apply_project_preset(&app, &preset)
 }
 
+/// Provenance of the downloaded local model; only the heuristic provider runs next to it.
+fn local_model_provenance(
+    app: AppHandle,
+    settings: &LlmSettings,
+    project_root: Option<String>,
+) -> Result<Option<ModelProvenance>, String> {
+    if settings.provider != LlmProvider::Heuristic {
+        return Ok(None);
+    }
+    let status = llm_load_model_from_disk(app, project_root)?;
+    Ok(model_provenance_from_status(&status))
+}
+
 #[tauri::command]
 pub fn llm_extract_model_spec(
     app: AppHandle,
@@ -190,8 +245,25 @@ pub fn llm_extract_model_spec(
     qsf_context_json: String,
     project_root: Option<String>,
 ) -> Result<String, String> {
-    let status = llm_load_model_from_disk(app, project_root)?;
-    let provenance = model_provenance_from_status(&status);
+    let settings = load_llm_settings(&app)?;
+    let provenance = local_model_provenance(app, &settings, project_root)?;
+    let (extracted, provider) = extract_with_fallback(
+        &settings,
+        |client| client.extract_model_spec(&text, &qsf_context_json),
+        || Ok(heuristic_model_spec(&text)),
+    )?;
+    Ok(serde_json::json!({
+      "kind": "model_spec",
+      "text": text,
+      "qsfContextJson": qsf_context_json,
+      "model": provenance,
+      "provider": provider,
+      "extracted": extracted
+    })
+    .to_string())
+}
+
+fn heuristic_model_spec(text: &str) -> serde_json::Value {
     let lower = text.to_lowercase();
     let (dv, iv) = if let Some(idx) = lower.find(" from ") {
         (
@@ -229,19 +301,12 @@ pub fn llm_extract_model_spec(
     if iv.is_empty() {
         ambiguities.push("Could not confidently identify independent variable(s).".to_string());
     }
-    Ok(serde_json::json!({
-      "kind": "model_spec",
-      "text": text,
-      "qsfContextJson": qsf_context_json,
-      "model": provenance,
-      "extracted": {
-        "dv": dv,
-        "iv": iv,
-        "controls": Vec::<String>::new(),
-        "ambiguities": ambiguities
-      }
+    serde_json::json!({
+      "dv": dv,
+      "iv": iv,
+      "controls": Vec::<String>::new(),
+      "ambiguities": ambiguities
     })
-    .to_string())
 }
 
 #[tauri::command]
@@ -251,10 +316,40 @@ pub fn llm_extract_prereg_models(
     qsf_context_json: String,
     project_root: Option<String>,
 ) -> Result<String, String> {
-    let status = llm_load_model_from_disk(app, project_root)?;
-    let provenance = model_provenance_from_status(&status);
+    let settings = load_llm_settings(&app)?;
+    let provenance = local_model_provenance(app, &settings, project_root)?;
+    prereg_models_output(&settings, &doc_text, &qsf_context_json, provenance)
+}
 
-    let qsf_vars = parse_qsf_variables(&qsf_context_json);
+/// JSON returned by `llm_extract_prereg_models`; `parsed` is what
+/// `apply_llm_prereg_enrichment` merges into the prereg.
+pub(crate) fn prereg_models_output(
+    settings: &LlmSettings,
+    doc_text: &str,
+    qsf_context_json: &str,
+    provenance: Option<ModelProvenance>,
+) -> Result<String, String> {
+    let (parsed, provider) = extract_with_fallback(
+        settings,
+        |client| client.extract_prereg_models(doc_text, qsf_context_json),
+        || heuristic_prereg_models(doc_text, qsf_context_json),
+    )?;
+    Ok(serde_json::json!({
+      "kind": "prereg_models",
+      "docTextPreview": doc_text.chars().take(600).collect::<String>(),
+      "qsfContextJson": qsf_context_json,
+      "model": provenance,
+      "provider": provider,
+      "parsed": parsed
+    })
+    .to_string())
+}
+
+fn heuristic_prereg_models(
+    doc_text: &str,
+    qsf_context_json: &str,
+) -> Result<serde_json::Value, String> {
+    let qsf_vars = parse_qsf_variables(qsf_context_json);
     let lower = doc_text.to_lowercase();
 
     let mut main_models = Vec::<serde_json::Value>::new();
@@ -266,7 +361,7 @@ pub fn llm_extract_prereg_models(
     let formula_re = Regex::new(r"(?m)([A-Za-z][A-Za-z0-9_]*)\s*~\s*([A-Za-z0-9_ +:*.-]+)")
         .map_err(|e| format!("Regex error: {e}"))?;
 
-    for (idx, cap) in formula_re.captures_iter(&doc_text).enumerate() {
+    for (idx, cap) in formula_re.captures_iter(doc_text).enumerate() {
         let dv = cap
             .get(1)
             .map(|m| m.as_str().trim())
@@ -340,24 +435,17 @@ pub fn llm_extract_prereg_models(
         .collect::<Vec<String>>();
 
     Ok(serde_json::json!({
-      "kind": "prereg_models",
-      "docTextPreview": doc_text.chars().take(600).collect::<String>(),
-      "qsfContextJson": qsf_context_json,
-      "model": provenance,
-      "parsed": {
-        "mainModels": main_models,
-        "exploratoryModels": exploratory_models,
-        "mechanismModels": mechanism_models,
-        "robustnessChecks": robustness_checks,
-        "variables": {
-          "mediators": mediators,
-          "moderators": moderators,
-          "exploratory": exploratory_vars
-        },
-        "ambiguities": ambiguities
-      }
-    })
-    .to_string())
+      "mainModels": main_models,
+      "exploratoryModels": exploratory_models,
+      "mechanismModels": mechanism_models,
+      "robustnessChecks": robustness_checks,
+      "variables": {
+        "mediators": mediators,
+        "moderators": moderators,
+        "exploratory": exploratory_vars
+      },
+      "ambiguities": ambiguities
+    }))
 }
 
 #[tauri::command]
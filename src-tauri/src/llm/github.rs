@@ -1,10 +1,14 @@
 use reqwest::blocking::Client;
-use reqwest::header::{ACCEPT, AUTHORIZATION, USER_AGENT};
-use serde::Deserialize;
+use reqwest::header::{
+    HeaderMap, ACCEPT, AUTHORIZATION, CONTENT_RANGE, ETAG, IF_RANGE, RANGE, USER_AGENT,
+};
+use reqwest::StatusCode;
+use serde::{Deserialize, Serialize};
 use sha2::{Digest, Sha256};
 use std::fs;
 use std::io::{Read, Write};
-use std::path::Path;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
 
 use super::settings::LlmSettings;
 
@@ -100,35 +104,239 @@ pub fn find_asset<'a>(
         })
 }
 
+/// Returned when a `.part` file exists but the server answered the range request with the
+/// whole asset; the caller should discard the partial file and start over.
+pub const DOWNLOAD_RANGE_UNSUPPORTED: &str =
+    "Download server does not support resuming (Range request ignored).";
+
+const PROGRESS_INTERVAL: Duration = Duration::from_millis(250);
+
 pub fn download_asset_and_sha256(
     url: &str,
     model_dir: &Path,
     asset_name: &str,
+    on_progress: &mut dyn FnMut(u64, Option<u64>),
+) -> Result<(String, u64), String> {
+    let client = github_client()?;
+    download_with_client(
+        &client,
+        url,
+        auth_token(),
+        model_dir,
+        asset_name,
+        on_progress,
+    )
+}
+
+/// Written next to `<asset>.part` so a later resume only appends to bytes of the same file.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+struct PartInfo {
+    url: String,
+    #[serde(default)]
+    etag: Option<String>,
+    #[serde(default)]
+    total_len: Option<u64>,
+}
+
+fn part_info_path(part_path: &Path) -> PathBuf {
+    let mut name = part_path.as_os_str().to_os_string();
+    name.push(".json");
+    PathBuf::from(name)
+}
+
+fn read_part_info(part_path: &Path) -> Option<PartInfo> {
+    let raw = fs::read_to_string(part_info_path(part_path)).ok()?;
+    serde_json::from_str(&raw).ok()
+}
+
+fn write_part_info(part_path: &Path, info: &PartInfo) -> Result<(), String> {
+    let payload = serde_json::to_string_pretty(info).map_err(|e| e.to_string())?;
+    fs::write(part_info_path(part_path), payload).map_err(|e| e.to_string())
+}
+
+/// Suffix of the sidecar kept next to a partial download.
+pub const PART_INFO_SUFFIX: &str = ".part.json";
+
+/// Deletes `<asset_name>.part` and its sidecar so the next download starts over.
+pub fn discard_partial_download(model_dir: &Path, asset_name: &str) -> Result<(), String> {
+    remove_part(&model_dir.join(format!("{asset_name}.part")))
+}
+
+fn remove_part(part_path: &Path) -> Result<(), String> {
+    for path in [part_path.to_path_buf(), part_info_path(part_path)] {
+        if path.exists() {
+            fs::remove_file(&path)
+                .map_err(|e| format!("Unable to remove {}: {e}", path.display()))?;
+        }
+    }
+    Ok(())
+}
+
+/// Total length from a `Content-Range` header (`bytes 0-9/10` or `bytes */10`).
+fn content_range_total(headers: &HeaderMap) -> Option<u64> {
+    headers
+        .get(CONTENT_RANGE)?
+        .to_str()
+        .ok()?
+        .rsplit_once('/')?
+        .1
+        .trim()
+        .parse()
+        .ok()
+}
+
+/// Start offset from a `206` response's `Content-Range` header.
+fn content_range_start(headers: &HeaderMap) -> Option<u64> {
+    let value = headers.get(CONTENT_RANGE)?.to_str().ok()?;
+    let range = value.trim().strip_prefix("bytes ")?;
+    range.split_once('-')?.0.trim().parse().ok()
+}
+
+/// Moves a complete `.part` file into place and returns its hash and length.
+fn finish_part(
+    part_path: &Path,
+    final_path: &Path,
+    hasher: Sha256,
+    bytes: u64,
+) -> Result<(String, u64), String> {
+    fs::rename(part_path, final_path)
+        .map_err(|e| format!("Unable to finalize {}: {e}", final_path.display()))?;
+    let _ = fs::remove_file(part_info_path(part_path));
+    Ok((hex::encode(hasher.finalize()), bytes))
+}
+
+fn hash_existing_prefix(path: &Path, hasher: &mut Sha256) -> Result<u64, String> {
+    let mut file =
+        fs::File::open(path).map_err(|e| format!("Unable to read {}: {e}", path.display()))?;
+    let mut buf = [0u8; 64 * 1024];
+    let mut len: u64 = 0;
+    loop {
+        let n = file
+            .read(&mut buf)
+            .map_err(|e| format!("Unable to read {}: {e}", path.display()))?;
+        if n == 0 {
+            return Ok(len);
+        }
+        hasher.update(&buf[..n]);
+        len += n as u64;
+    }
+}
+
+/// Streams `url` into `<asset_name>.part`, resuming from an existing partial file with a
+/// Range request. The partial file is kept when the transfer breaks off so the next call
+/// picks up where this one stopped. A partial file is only resumed when its sidecar names
+/// the same URL; the recorded ETag is sent as `If-Range` and the recorded length finishes a
+/// part that is already complete.
+fn download_with_client(
+    client: &Client,
+    url: &str,
+    token: Option<String>,
+    model_dir: &Path,
+    asset_name: &str,
+    on_progress: &mut dyn FnMut(u64, Option<u64>),
 ) -> Result<(String, u64), String> {
     fs::create_dir_all(model_dir).map_err(|e| e.to_string())?;
     let final_path = model_dir.join(asset_name);
     let part_path = model_dir.join(format!("{asset_name}.part"));
 
-    let client = github_client()?;
+    let mut hasher = Sha256::new();
+    let part_info = read_part_info(&part_path).filter(|info| info.url == url);
+    if part_path.exists() && part_info.is_none() {
+        // Left over from another URL, or from before sidecars were written.
+        remove_part(&part_path)?;
+    }
+    let resume_from = if part_path.exists() {
+        hash_existing_prefix(&part_path, &mut hasher)?
+    } else {
+        0
+    };
+    let recorded_len = part_info.as_ref().and_then(|info| info.total_len);
+    if resume_from > 0 {
+        match recorded_len {
+            Some(total) if resume_from == total => {
+                return finish_part(&part_path, &final_path, hasher, resume_from);
+            }
+            Some(total) if resume_from > total => {
+                remove_part(&part_path)?;
+                return download_with_client(
+                    client,
+                    url,
+                    token,
+                    model_dir,
+                    asset_name,
+                    on_progress,
+                );
+            }
+            _ => {}
+        }
+    }
+
     let mut request = client
         .get(url)
         .header(USER_AGENT, "research-workflow/0.1")
         .header(ACCEPT, "application/octet-stream");
-    if let Some(token) = auth_token() {
+    if let Some(token) = token {
         request = request.header(AUTHORIZATION, format!("Bearer {token}"));
     }
+    if resume_from > 0 {
+        request = request.header(RANGE, format!("bytes={resume_from}-"));
+        if let Some(etag) = part_info.as_ref().and_then(|info| info.etag.as_deref()) {
+            request = request.header(IF_RANGE, etag);
+        }
+    }
 
     let mut response = request
         .send()
         .map_err(|e| format!("Download failed: {e}"))?;
-    if !response.status().is_success() {
-        return Err(format!("Download failed with status {}", response.status()));
+    let status = response.status();
+    if resume_from > 0 && status == StatusCode::RANGE_NOT_SATISFIABLE {
+        // The part may already hold the whole file.
+        if content_range_total(response.headers()) == Some(resume_from) {
+            return finish_part(&part_path, &final_path, hasher, resume_from);
+        }
+        return Err(DOWNLOAD_RANGE_UNSUPPORTED.to_string());
+    }
+    // 200 means the range was ignored or, with If-Range, that the file changed.
+    let resumed_elsewhere = status == StatusCode::PARTIAL_CONTENT
+        && content_range_start(response.headers()) != Some(resume_from);
+    if resume_from > 0 && (status == StatusCode::OK || resumed_elsewhere) {
+        return Err(DOWNLOAD_RANGE_UNSUPPORTED.to_string());
+    }
+    if !status.is_success() {
+        return Err(format!("Download failed with status {status}"));
     }
+    let bytes_total = response.content_length().map(|len| len + resume_from);
+    if resume_from > 0
+        && recorded_len.is_some()
+        && bytes_total.is_some()
+        && recorded_len != bytes_total
+    {
+        return Err(DOWNLOAD_RANGE_UNSUPPORTED.to_string());
+    }
+    let etag = response
+        .headers()
+        .get(ETAG)
+        .and_then(|value| value.to_str().ok())
+        .map(str::to_string)
+        .or_else(|| part_info.as_ref().and_then(|info| info.etag.clone()));
+    write_part_info(
+        &part_path,
+        &PartInfo {
+            url: url.to_string(),
+            etag,
+            total_len: bytes_total,
+        },
+    )?;
 
-    let mut file = fs::File::create(&part_path)
+    let mut file = fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&part_path)
         .map_err(|e| format!("Unable to create {}: {e}", part_path.display()))?;
-    let mut hasher = Sha256::new();
-    let mut bytes_total: u64 = 0;
+    let mut bytes_done = resume_from;
+    let mut last_report = Instant::now();
+    on_progress(bytes_done, bytes_total);
     let mut buf = [0u8; 64 * 1024];
     loop {
         let n = response
@@ -140,11 +348,245 @@ pub fn download_asset_and_sha256(
         file.write_all(&buf[..n])
             .map_err(|e| format!("Unable to write {}: {e}", part_path.display()))?;
         hasher.update(&buf[..n]);
-        bytes_total += n as u64;
+        bytes_done += n as u64;
+        if last_report.elapsed() >= PROGRESS_INTERVAL {
+            on_progress(bytes_done, bytes_total);
+            last_report = Instant::now();
+        }
+    }
+    on_progress(bytes_done, bytes_total);
+    if bytes_total.is_some_and(|total| bytes_done < total) {
+        return Err(format!(
+            "Download interrupted after {bytes_done} bytes; it will resume on the next attempt."
+        ));
     }
 
-    fs::rename(&part_path, &final_path)
-        .map_err(|e| format!("Unable to finalize {}: {e}", final_path.display()))?;
-    let sha = hex::encode(hasher.finalize());
-    Ok((sha, bytes_total))
+    finish_part(&part_path, &final_path, hasher, bytes_done)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::BufRead;
+    use std::net::TcpListener;
+
+    fn asset_bytes() -> Vec<u8> {
+        (0..300_000u32).map(|i| (i * 7 % 251) as u8).collect()
+    }
+
+    fn range_start(headers: &[String]) -> Option<usize> {
+        headers.iter().find_map(|line| {
+            let (name, value) = line.split_once(':')?;
+            if !name.eq_ignore_ascii_case("range") {
+                return None;
+            }
+            value
+                .trim()
+                .strip_prefix("bytes=")?
+                .trim_end_matches('-')
+                .parse()
+                .ok()
+        })
+    }
+
+    fn header<'a>(headers: &'a [String], wanted: &str) -> Option<&'a str> {
+        headers.iter().find_map(|line| {
+            let (name, value) = line.split_once(':')?;
+            name.eq_ignore_ascii_case(wanted).then(|| value.trim())
+        })
+    }
+
+    /// Serves `asset_bytes()` with ETag `"v1"` once per entry in `honor_range`. The first
+    /// connection sends only half of the body before hanging up.
+    fn serve(honor_range: Vec<bool>) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").expect("bind");
+        let addr = listener.local_addr().expect("addr");
+        std::thread::spawn(move || {
+            let body = asset_bytes();
+            for (index, honor) in honor_range.into_iter().enumerate() {
+                let (mut stream, _) = listener.accept().expect("accept");
+                let mut reader = std::io::BufReader::new(stream.try_clone().expect("clone"));
+                let mut headers = Vec::new();
+                loop {
+                    let mut line = String::new();
+                    reader.read_line(&mut line).expect("request line");
+                    if line.trim().is_empty() {
+                        break;
+                    }
+                    headers.push(line.trim().to_string());
+                }
+                let same_file = header(&headers, "if-range").is_none_or(|tag| tag == "\"v1\"");
+                let start = range_start(&headers)
+                    .filter(|_| honor && same_file)
+                    .unwrap_or(0);
+                let status = if start > 0 {
+                    format!(
+                        "206 Partial Content\r\nContent-Range: bytes {start}-{}/{}",
+                        body.len() - 1,
+                        body.len()
+                    )
+                } else {
+                    "200 OK".to_string()
+                };
+                let rest = &body[start..];
+                let head = format!(
+                    "HTTP/1.1 {status}\r\nETag: \"v1\"\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+                    rest.len()
+                );
+                stream.write_all(head.as_bytes()).expect("head");
+                let sent = if index == 0 {
+                    rest.len() / 2
+                } else {
+                    rest.len()
+                };
+                stream.write_all(&rest[..sent]).expect("body");
+            }
+        });
+        format!("http://{addr}/model.gguf")
+    }
+
+    fn local_client() -> Client {
+        Client::builder().no_proxy().build().expect("client")
+    }
+
+    #[test]
+    fn interrupted_download_resumes_from_part_file() {
+        let dir = std::env::temp_dir().join(format!("llm-resume-{}", uuid::Uuid::new_v4()));
+        let url = serve(vec![true, true]);
+        let client = local_client();
+        let mut reports = Vec::new();
+
+        let first = download_with_client(&client, &url, None, &dir, "model.gguf", &mut |d, t| {
+            reports.push((d, t))
+        });
+        assert!(first.is_err());
+        let partial = fs::metadata(dir.join("model.gguf.part"))
+            .expect("part")
+            .len();
+        assert!(partial > 0 && partial < asset_bytes().len() as u64);
+
+        let (sha, bytes) =
+            download_with_client(&client, &url, None, &dir, "model.gguf", &mut |d, t| {
+                reports.push((d, t))
+            })
+            .expect("resumed download");
+        assert_eq!(sha, hex::encode(Sha256::digest(asset_bytes())));
+        assert_eq!(bytes, asset_bytes().len() as u64);
+        assert!(!dir.join("model.gguf.part").exists());
+        assert_eq!(
+            fs::read(dir.join("model.gguf")).expect("model"),
+            asset_bytes()
+        );
+        assert_eq!(
+            reports.last(),
+            Some(&(bytes, Some(asset_bytes().len() as u64)))
+        );
+        let _ = fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn part_file_from_another_download_is_not_resumed() {
+        let dir = std::env::temp_dir().join(format!("llm-stale-{}", uuid::Uuid::new_v4()));
+        let client = local_client();
+        fs::create_dir_all(&dir).expect("dir");
+        let part = dir.join("model.gguf.part");
+
+        // Another URL: the part is discarded before the request is made.
+        let url = serve(vec![true, true]);
+        fs::write(&part, b"stale bytes from another release").expect("stale part");
+        write_part_info(
+            &part,
+            &PartInfo {
+                url: "http://example.invalid/v0/model.gguf".to_string(),
+                etag: None,
+                total_len: None,
+            },
+        )
+        .expect("info");
+        let _ = download_with_client(&client, &url, None, &dir, "model.gguf", &mut |_, _| {});
+        let (sha, _) =
+            download_with_client(&client, &url, None, &dir, "model.gguf", &mut |_, _| {})
+                .expect("fresh download");
+        assert_eq!(sha, hex::encode(Sha256::digest(asset_bytes())));
+        assert!(!part_info_path(&part).exists());
+
+        // Same URL but a changed ETag: If-Range makes the server send the whole file.
+        let url = serve(vec![true]);
+        fs::write(&part, b"stale bytes").expect("stale part");
+        write_part_info(
+            &part,
+            &PartInfo {
+                url: url.clone(),
+                etag: Some("\"v0\"".to_string()),
+                total_len: None,
+            },
+        )
+        .expect("info");
+        let err = download_with_client(&client, &url, None, &dir, "model.gguf", &mut |_, _| {})
+            .expect_err("changed file");
+        assert_eq!(err, DOWNLOAD_RANGE_UNSUPPORTED);
+        let _ = fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn complete_part_file_is_finished_on_416() {
+        let dir = std::env::temp_dir().join(format!("llm-complete-{}", uuid::Uuid::new_v4()));
+        fs::create_dir_all(&dir).expect("dir");
+        let listener = TcpListener::bind("127.0.0.1:0").expect("bind");
+        let url = format!("http://{}/model.gguf", listener.local_addr().expect("addr"));
+        let len = asset_bytes().len();
+        std::thread::spawn(move || {
+            let (mut stream, _) = listener.accept().expect("accept");
+            let mut reader = std::io::BufReader::new(stream.try_clone().expect("clone"));
+            loop {
+                let mut line = String::new();
+                reader.read_line(&mut line).expect("request line");
+                if line.trim().is_empty() {
+                    break;
+                }
+            }
+            let head = format!(
+                "HTTP/1.1 416 Range Not Satisfiable\r\nContent-Range: bytes */{len}\r\nContent-Length: 0\r\nConnection: close\r\n\r\n"
+            );
+            stream.write_all(head.as_bytes()).expect("head");
+        });
+        let part = dir.join("model.gguf.part");
+        fs::write(&part, asset_bytes()).expect("part");
+        write_part_info(
+            &part,
+            &PartInfo {
+                url: url.clone(),
+                etag: None,
+                total_len: None,
+            },
+        )
+        .expect("info");
+        let (sha, bytes) = download_with_client(
+            &local_client(),
+            &url,
+            None,
+            &dir,
+            "model.gguf",
+            &mut |_, _| {},
+        )
+        .expect("finished");
+        assert_eq!(sha, hex::encode(Sha256::digest(asset_bytes())));
+        assert_eq!(bytes, len as u64);
+        assert!(dir.join("model.gguf").is_file());
+        assert!(!part.exists());
+        let _ = fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn ignored_range_request_is_reported_distinctly() {
+        let dir = std::env::temp_dir().join(format!("llm-norange-{}", uuid::Uuid::new_v4()));
+        let url = serve(vec![true, false]);
+        let client = local_client();
+        let _ = download_with_client(&client, &url, None, &dir, "model.gguf", &mut |_, _| {});
+        let err = download_with_client(&client, &url, None, &dir, "model.gguf", &mut |_, _| {})
+            .expect_err("range ignored");
+        assert_eq!(err, DOWNLOAD_RANGE_UNSUPPORTED);
+        assert!(dir.join("model.gguf.part").exists());
+        let _ = fs::remove_dir_all(dir);
+    }
 }
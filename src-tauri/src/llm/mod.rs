@@ -1,5 +1,6 @@
 pub mod commands;
 pub mod github;
 pub mod model_manager;
+pub mod provider;
 pub mod settings;
 pub mod types;
@@ -1,12 +1,22 @@
 use chrono::{DateTime, Duration, Utc};
-use sha2::Digest;
 use std::fs;
-use std::path::{Path, PathBuf};
+use std::path::{Component, Path, PathBuf};
 use std::sync::{Mutex, OnceLock};
+use tauri::Manager;
 
-use super::github::{download_asset_and_sha256, fetch_release_by_tag, find_asset, newest_release};
+use crate::util::hash::sha256_file;
+
+use super::github::{
+    discard_partial_download, download_asset_and_sha256, fetch_release_by_tag, find_asset,
+    newest_release, DOWNLOAD_RANGE_UNSUPPORTED, PART_INFO_SUFFIX,
+};
 use super::settings::{load_llm_settings, save_llm_settings, LlmSettings, UpdatePolicy};
-use super::types::{LlmModelLock, LlmProjectPreset, ModelProvenance, ModelStatus, TargetModel};
+use super::types::{
+    DeleteModelReport, DownloadProgress, LlmModelLock, LlmProjectPreset, LocalModel, ModelDirUsage,
+    ModelLockRef, ModelProvenance, ModelStatus, TargetModel,
+};
+
+pub const DOWNLOAD_PROGRESS_EVENT: &str = "llm://download-progress";
 
 static LOADED_MODEL: OnceLock<Mutex<Option<String>>> = OnceLock::new();
 
@@ -18,9 +28,34 @@ fn normalize_sha(value: &str) -> String {
     value.trim().trim_start_matches("sha256:").to_lowercase()
 }
 
-fn compute_sha256(path: &Path) -> Result<String, String> {
-    let bytes = fs::read(path).map_err(|e| format!("Unable to read {}: {e}", path.display()))?;
-    Ok(hex::encode(sha2::Sha256::digest(bytes)))
+/// `<model>.sha256`, in `sha256sum` format, next to a downloaded or verified model.
+fn sha256_sidecar_path(model_path: &Path) -> PathBuf {
+    let mut name = model_path.as_os_str().to_owned();
+    name.push(".sha256");
+    PathBuf::from(name)
+}
+
+fn write_sha256_sidecar(model_path: &Path, sha: &str) -> Result<(), String> {
+    let name = model_path
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let path = sha256_sidecar_path(model_path);
+    fs::write(&path, format!("{sha}  {name}\n"))
+        .map_err(|e| format!("Unable to write {}: {e}", path.display()))
+}
+
+/// Hash from the sidecar, unless the model was modified after the sidecar was written.
+fn cached_sha256(model_path: &Path) -> Option<String> {
+    let sidecar = sha256_sidecar_path(model_path);
+    let sidecar_modified = fs::metadata(&sidecar).and_then(|m| m.modified()).ok()?;
+    let model_modified = fs::metadata(model_path).and_then(|m| m.modified()).ok()?;
+    if model_modified > sidecar_modified {
+        return None;
+    }
+    let raw = fs::read_to_string(&sidecar).ok()?;
+    let sha = normalize_sha(raw.split_whitespace().next()?);
+    (sha.len() == 64 && sha.chars().all(|c| c.is_ascii_hexdigit())).then_some(sha)
 }
 
 pub fn lock_file_path(project_root: &Path) -> PathBuf {
@@ -171,7 +206,8 @@ fn finalize_status_with_file(
 ) -> Result<(), String> {
     let metadata = fs::metadata(path)
         .map_err(|e| format!("Unable to read metadata for {}: {e}", path.display()))?;
-    let sha = compute_sha256(path)?;
+    let sha = sha256_file(path)?;
+    write_sha256_sidecar(path, &sha)?;
     status.model_path = Some(path.to_string_lossy().to_string());
     status.bytes_on_disk = Some(metadata.len());
     status.sha256 = Some(sha.clone());
@@ -179,9 +215,27 @@ fn finalize_status_with_file(
     Ok(())
 }
 
+/// Downloads the asset, resuming a leftover `.part` file when possible. Servers that ignore
+/// the range request, or report that the file changed, get a fresh download instead.
+fn download_resuming(
+    url: &str,
+    model_dir: &Path,
+    asset_name: &str,
+    on_progress: &mut dyn FnMut(u64, Option<u64>),
+) -> Result<(String, u64), String> {
+    match download_asset_and_sha256(url, model_dir, asset_name, on_progress) {
+        Err(e) if e == DOWNLOAD_RANGE_UNSUPPORTED => {
+            discard_partial_download(model_dir, asset_name)?;
+            download_asset_and_sha256(url, model_dir, asset_name, on_progress)
+        }
+        other => other,
+    }
+}
+
 pub fn ensure_model_downloaded(
     target: TargetModel,
     settings: &LlmSettings,
+    on_progress: &mut dyn FnMut(u64, Option<u64>),
 ) -> Result<ModelStatus, String> {
     let model_dir = PathBuf::from(settings.model_dir.trim());
     fs::create_dir_all(&model_dir).map_err(|e| e.to_string())?;
@@ -189,7 +243,7 @@ pub fn ensure_model_downloaded(
     let mut status = empty_status(settings, &target);
 
     if model_path.exists() {
-        let current_sha = compute_sha256(&model_path)?;
+        let current_sha = sha256_file(&model_path)?;
         if target.is_locked {
             if let Some(expected) = &target.expected_sha256 {
                 if current_sha != normalize_sha(expected) {
@@ -218,8 +272,12 @@ pub fn ensure_model_downloaded(
     let release = fetch_release_by_tag(settings, &target.tag)?;
     let asset = find_asset(&release, &target.asset_name)?;
 
-    let downloaded =
-        download_asset_and_sha256(&asset.browser_download_url, &model_dir, &target.asset_name);
+    let downloaded = download_resuming(
+        &asset.browser_download_url,
+        &model_dir,
+        &target.asset_name,
+        on_progress,
+    );
     let (downloaded_sha, _downloaded_bytes) = match downloaded {
         Ok(v) => v,
         Err(e) => {
@@ -239,13 +297,17 @@ pub fn ensure_model_downloaded(
     if let Some(expected) = &target.expected_sha256 {
         if downloaded_sha != normalize_sha(expected) {
             if target.is_locked {
+                // Don't leave a file that can never pass the lock check at the model path.
+                fs::remove_file(&model_path)
+                    .map_err(|e| format!("Unable to remove {}: {e}", model_path.display()))?;
                 return Err("Locked model hash mismatch; redownload or unlock project.".to_string());
             }
 
-            let retry = download_asset_and_sha256(
+            let retry = download_resuming(
                 &asset.browser_download_url,
                 &model_dir,
                 &target.asset_name,
+                on_progress,
             )?;
             if retry.0 != normalize_sha(expected) {
                 return Err(format!(
@@ -296,6 +358,16 @@ pub fn download_model_with_policy(
     app: &tauri::AppHandle,
     project_root: Option<PathBuf>,
     force: bool,
+) -> Result<ModelStatus, String> {
+    download_model_with_progress(app, project_root, force, &mut |_, _| {})
+}
+
+/// Same as `download_model_with_policy`, reporting (downloaded, total) bytes while fetching.
+pub fn download_model_with_progress(
+    app: &tauri::AppHandle,
+    project_root: Option<PathBuf>,
+    force: bool,
+    on_progress: &mut dyn FnMut(u64, Option<u64>),
 ) -> Result<ModelStatus, String> {
     let mut settings = load_llm_settings(app)?;
     let target = resolve_target_model(project_root, &settings)?;
@@ -309,7 +381,16 @@ pub fn download_model_with_policy(
         return Ok(status);
     }
 
-    let result = ensure_model_downloaded(target.clone(), &settings);
+    let asset_name = target.asset_name.clone();
+    let result = ensure_model_downloaded(target.clone(), &settings, &mut |done, total| {
+        let event = DownloadProgress {
+            bytes_done: done,
+            bytes_total: total,
+            asset_name: asset_name.clone(),
+        };
+        let _ = app.emit_all(DOWNLOAD_PROGRESS_EVENT, event);
+        on_progress(done, total);
+    });
     settings.last_checked_utc = Some(Utc::now().to_rfc3339());
     settings.last_error = result.as_ref().err().cloned();
     save_llm_settings(app, &settings)?;
@@ -355,7 +436,7 @@ pub fn lock_project_to_current_model(
 ) -> Result<LlmModelLock, String> {
     let settings = load_llm_settings(app)?;
     let target = resolve_target_model(None, &settings)?;
-    let status = ensure_model_downloaded(target.clone(), &settings)?;
+    let status = ensure_model_downloaded(target.clone(), &settings, &mut |_, _| {})?;
     let lock = LlmModelLock {
         locked: true,
         tag: target.tag,
@@ -368,6 +449,125 @@ pub fn lock_project_to_current_model(
     Ok(lock)
 }
 
+/// Projects whose lock pins `file_name`, from `(project_id, project_name, project_root)`.
+/// Unreadable lock files are skipped.
+fn lock_refs(projects: &[(String, String, PathBuf)], file_name: &str) -> Vec<ModelLockRef> {
+    projects
+        .iter()
+        .filter_map(|(id, name, root)| {
+            let lock = read_project_lock(root).ok().flatten()?;
+            (lock.locked && lock.asset_name == file_name).then(|| ModelLockRef {
+                project_id: id.clone(),
+                project_name: name.clone(),
+                tag: lock.tag,
+            })
+        })
+        .collect()
+}
+
+/// Model files in `model_dir` (sidecars excluded), largest first. Hashes come only from
+/// sidecars so listing never rereads multi-gigabyte files.
+pub fn list_local_models(
+    settings: &LlmSettings,
+    projects: &[(String, String, PathBuf)],
+) -> Result<Vec<LocalModel>, String> {
+    let model_dir = PathBuf::from(settings.model_dir.trim());
+    if !model_dir.is_dir() {
+        return Ok(Vec::new());
+    }
+    let mut models = Vec::new();
+    for entry in fs::read_dir(&model_dir).map_err(|e| e.to_string())? {
+        let entry = entry.map_err(|e| e.to_string())?;
+        let metadata = entry.metadata().map_err(|e| e.to_string())?;
+        let file_name = entry.file_name().to_string_lossy().to_string();
+        if !metadata.is_file()
+            || file_name.ends_with(".sha256")
+            || file_name.ends_with(PART_INFO_SUFFIX)
+        {
+            continue;
+        }
+        let path = entry.path();
+        models.push(LocalModel {
+            sha256: cached_sha256(&path),
+            path: path.to_string_lossy().to_string(),
+            bytes: metadata.len(),
+            is_current_target: file_name == settings.asset_name,
+            locked_by: lock_refs(projects, &file_name),
+            file_name,
+        });
+    }
+    models.sort_by(|a, b| {
+        b.bytes
+            .cmp(&a.bytes)
+            .then_with(|| a.file_name.cmp(&b.file_name))
+    });
+    Ok(models)
+}
+
+pub fn model_dir_usage(
+    settings: &LlmSettings,
+    projects: &[(String, String, PathBuf)],
+) -> Result<ModelDirUsage, String> {
+    let models = list_local_models(settings, projects)?;
+    Ok(ModelDirUsage {
+        model_dir: settings.model_dir.clone(),
+        file_count: models.len(),
+        total_bytes: models.iter().map(|m| m.bytes).sum(),
+        reclaimable_bytes: models
+            .iter()
+            .filter(|m| !m.is_current_target && m.locked_by.is_empty())
+            .map(|m| m.bytes)
+            .sum(),
+    })
+}
+
+/// Deletes `file_name` (and its sidecar) from the model directory. A file some project is
+/// locked to is left in place unless `force` is set; the report lists those projects.
+pub fn delete_local_model(
+    settings: &LlmSettings,
+    projects: &[(String, String, PathBuf)],
+    file_name: &str,
+    force: bool,
+) -> Result<DeleteModelReport, String> {
+    let file_name = file_name.trim();
+    let mut components = Path::new(file_name).components();
+    if !matches!(
+        (components.next(), components.next()),
+        (Some(Component::Normal(_)), None)
+    ) {
+        return Err(format!("Invalid model file name: {file_name}"));
+    }
+    let path = PathBuf::from(settings.model_dir.trim()).join(file_name);
+    let metadata = fs::metadata(&path)
+        .ok()
+        .filter(|m| m.is_file())
+        .ok_or_else(|| format!("Model file not found: {}", path.display()))?;
+    let referenced_by = lock_refs(projects, file_name);
+    if !referenced_by.is_empty() && !force {
+        return Ok(DeleteModelReport {
+            deleted: false,
+            bytes_freed: 0,
+            referenced_by,
+        });
+    }
+    fs::remove_file(&path).map_err(|e| format!("Unable to remove {}: {e}", path.display()))?;
+    let sidecar = sha256_sidecar_path(&path);
+    if sidecar.exists() {
+        fs::remove_file(&sidecar)
+            .map_err(|e| format!("Unable to remove {}: {e}", sidecar.display()))?;
+    }
+    if let Ok(mut loaded) = loaded_model_cell().lock() {
+        if loaded.as_deref() == Some(path.to_string_lossy().as_ref()) {
+            *loaded = None;
+        }
+    }
+    Ok(DeleteModelReport {
+        deleted: true,
+        bytes_freed: metadata.len(),
+        referenced_by,
+    })
+}
+
 pub fn model_provenance_from_status(status: &ModelStatus) -> Option<ModelProvenance> {
     Some(ModelProvenance {
         model_tag: status.selected_tag.clone()?,
@@ -383,7 +583,7 @@ pub fn model_provenance_from_status(status: &ModelStatus) -> Option<ModelProvena
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::llm::settings::{LlmSettings, UpdatePolicy};
+    use crate::llm::settings::{LlmProvider, LlmSettings, UpdatePolicy};
 
     fn test_settings() -> LlmSettings {
         LlmSettings {
@@ -398,6 +598,10 @@ mod tests {
             auto_check_days: 1,
             last_checked_utc: None,
             last_error: None,
+            provider: LlmProvider::Heuristic,
+            base_url: None,
+            api_key: None,
+            model: None,
         }
     }
 
@@ -435,7 +639,8 @@ mod tests {
             is_locked: true,
             lock: None,
         };
-        let err = ensure_model_downloaded(target, &settings).expect_err("should fail");
+        let err =
+            ensure_model_downloaded(target, &settings, &mut |_, _| {}).expect_err("should fail");
         assert_eq!(
             err,
             "Locked model hash mismatch; redownload or unlock project."
@@ -462,6 +667,64 @@ mod tests {
         let _ = fs::remove_dir_all(temp);
     }
 
+    #[test]
+    fn local_models_report_sidecar_hashes_and_locks_before_deletion() {
+        let temp = std::env::temp_dir().join(format!("llm-models-{}", uuid::Uuid::new_v4()));
+        let model_dir = temp.join("models");
+        fs::create_dir_all(&model_dir).expect("mkdir");
+        fs::write(model_dir.join("m.gguf"), b"current").expect("write current");
+        fs::write(model_dir.join("old.gguf"), b"old model bytes").expect("write old");
+        fs::write(model_dir.join("stale.gguf"), b"stale").expect("write stale");
+        let mut settings = test_settings();
+        settings.model_dir = model_dir.to_string_lossy().to_string();
+        let target = TargetModel {
+            tag: "v1.0.0".to_string(),
+            asset_name: "m.gguf".to_string(),
+            expected_sha256: None,
+            is_locked: false,
+            lock: None,
+        };
+        let status =
+            ensure_model_downloaded(target, &settings, &mut |_, _| {}).expect("verify existing");
+        assert!(model_dir.join("m.gguf.sha256").exists());
+
+        let project_root = temp.join("project");
+        write_project_lock(
+            &project_root,
+            &LlmModelLock {
+                locked: true,
+                tag: "v0.9".to_string(),
+                asset_name: "old.gguf".to_string(),
+                sha256: "123".to_string(),
+                locked_at_utc: Utc::now().to_rfc3339(),
+                note: None,
+            },
+        )
+        .expect("write lock");
+        let projects = vec![("p1".to_string(), "Demo".to_string(), project_root)];
+
+        let models = list_local_models(&settings, &projects).expect("list");
+        let names: Vec<&str> = models.iter().map(|m| m.file_name.as_str()).collect();
+        assert_eq!(names, vec!["old.gguf", "m.gguf", "stale.gguf"]);
+        assert!(models[1].is_current_target);
+        assert_eq!(models[1].sha256, status.sha256);
+        assert_eq!(models[0].sha256, None);
+        assert_eq!(models[0].locked_by[0].project_id, "p1");
+        let usage = model_dir_usage(&settings, &projects).expect("usage");
+        assert_eq!(usage.total_bytes, 7 + 15 + 5);
+        assert_eq!(usage.reclaimable_bytes, 5);
+
+        let refused = delete_local_model(&settings, &projects, "old.gguf", false).expect("report");
+        assert!(!refused.deleted);
+        assert_eq!(refused.referenced_by[0].project_name, "Demo");
+        assert!(model_dir.join("old.gguf").exists());
+        let forced = delete_local_model(&settings, &projects, "old.gguf", true).expect("delete");
+        assert!(forced.deleted);
+        assert_eq!(forced.bytes_freed, 15);
+        assert!(delete_local_model(&settings, &projects, "../m.gguf", true).is_err());
+        let _ = fs::remove_dir_all(temp);
+    }
+
     #[test]
     fn lock_roundtrip() {
         let temp = std::env::temp_dir().join(format!("llm-lock-{}", uuid::Uuid::new_v4()));
@@ -0,0 +1,269 @@
+use reqwest::blocking::Client;
+use reqwest::header::{AUTHORIZATION, USER_AGENT};
+use serde_json::{json, Value};
+use std::time::Duration;
+
+use super::settings::{LlmProvider, LlmSettings};
+
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(120);
+
+const PREREG_SYSTEM_PROMPT: &str = "You extract statistical models from preregistration documents. \
+Reply with one JSON object and nothing else, using exactly these keys: \
+\"mainModels\", \"exploratoryModels\", \"mechanismModels\" (arrays of models), \
+\"robustnessChecks\" (array of strings), \
+\"variables\" (object with \"mediators\", \"moderators\", \"exploratory\" string arrays) and \
+\"ambiguities\" (array of strings describing anything you were unsure about). \
+Each model is an object with \"id\" (string), \"dv\" (string), \"iv\", \"controls\" and \
+\"interactionTerms\" (string arrays, interactions written as a:b) and \"formula\" (R formula string). \
+Use the survey column names from expectedColumns in the survey context whenever a variable matches one.";
+
+const MODEL_SPEC_SYSTEM_PROMPT: &str = "You turn a one-line description of a statistical model into variables. \
+Reply with one JSON object and nothing else, with keys \"dv\" (string), \"iv\" and \"controls\" \
+(string arrays) and \"ambiguities\" (array of strings). \
+Use the survey column names from expectedColumns in the survey context whenever a variable matches one.";
+
+/// Client for a chat-completions endpoint (`POST {base_url}/chat/completions`).
+pub struct OpenAiCompatibleClient {
+    base_url: String,
+    api_key: Option<String>,
+    model: String,
+}
+
+impl OpenAiCompatibleClient {
+    pub fn from_settings(settings: &LlmSettings) -> Result<Self, String> {
+        let base_url = settings
+            .base_url
+            .as_deref()
+            .map(|url| url.trim().trim_end_matches('/'))
+            .filter(|url| !url.is_empty())
+            .ok_or_else(|| "The openai_compatible provider needs a base URL.".to_string())?;
+        let model = settings
+            .model
+            .as_deref()
+            .map(str::trim)
+            .filter(|model| !model.is_empty())
+            .ok_or_else(|| "The openai_compatible provider needs a model name.".to_string())?;
+        Ok(Self {
+            base_url: base_url.to_string(),
+            api_key: settings
+                .api_key
+                .clone()
+                .filter(|key| !key.trim().is_empty()),
+            model: model.to_string(),
+        })
+    }
+
+    /// Provenance block recorded in extraction output; never includes the API key.
+    pub fn provenance(&self) -> Value {
+        json!({
+          "name": LlmProvider::OpenaiCompatible.as_str(),
+          "baseUrl": self.base_url,
+          "model": self.model,
+        })
+    }
+
+    fn chat_json(&self, system: &str, user: String) -> Result<Value, String> {
+        let client = Client::builder()
+            .timeout(REQUEST_TIMEOUT)
+            .build()
+            .map_err(|e| e.to_string())?;
+        let mut request = client
+            .post(format!("{}/chat/completions", self.base_url))
+            .header(USER_AGENT, "research-workflow/0.1")
+            .json(&json!({
+              "model": self.model,
+              "temperature": 0,
+              "response_format": { "type": "json_object" },
+              "messages": [
+                { "role": "system", "content": system },
+                { "role": "user", "content": user }
+              ]
+            }));
+        if let Some(key) = &self.api_key {
+            request = request.header(AUTHORIZATION, format!("Bearer {key}"));
+        }
+        let response = request
+            .send()
+            .map_err(|e| format!("LLM provider request failed: {e}"))?;
+        if !response.status().is_success() {
+            return Err(format!(
+                "LLM provider request failed with status {}",
+                response.status()
+            ));
+        }
+        let body = response
+            .json::<Value>()
+            .map_err(|e| format!("Unable to parse LLM provider response: {e}"))?;
+        let content = body
+            .pointer("/choices/0/message/content")
+            .and_then(Value::as_str)
+            .ok_or_else(|| "LLM provider response has no message content.".to_string())?;
+        json_content(content)
+    }
+
+    pub fn extract_prereg_models(
+        &self,
+        doc_text: &str,
+        qsf_context_json: &str,
+    ) -> Result<Value, String> {
+        let reply = self.chat_json(
+            PREREG_SYSTEM_PROMPT,
+            format!("Survey context:\n{qsf_context_json}\n\nPreregistration:\n{doc_text}"),
+        )?;
+        prereg_models_from_reply(&reply)
+    }
+
+    pub fn extract_model_spec(&self, text: &str, qsf_context_json: &str) -> Result<Value, String> {
+        let reply = self.chat_json(
+            MODEL_SPEC_SYSTEM_PROMPT,
+            format!("Survey context:\n{qsf_context_json}\n\nModel description:\n{text}"),
+        )?;
+        Ok(json!({
+          "dv": required_string(&reply, "dv")?,
+          "iv": string_list(&reply, "iv")?,
+          "controls": string_list(&reply, "controls")?,
+          "ambiguities": string_list(&reply, "ambiguities")?,
+        }))
+    }
+}
+
+/// Parses message content as JSON, tolerating a Markdown code fence around it.
+fn json_content(content: &str) -> Result<Value, String> {
+    let trimmed = content.trim();
+    let unfenced = trimmed
+        .strip_prefix("```")
+        .map(|rest| rest.trim_start_matches("json"))
+        .and_then(|rest| rest.trim_end().strip_suffix("```"))
+        .unwrap_or(trimmed);
+    serde_json::from_str(unfenced.trim())
+        .map_err(|e| format!("LLM provider did not return valid JSON: {e}"))
+}
+
+fn required_string(value: &Value, key: &str) -> Result<String, String> {
+    value
+        .get(key)
+        .and_then(Value::as_str)
+        .map(str::trim)
+        .filter(|s| !s.is_empty())
+        .map(str::to_string)
+        .ok_or_else(|| format!("LLM provider reply is missing \"{key}\"."))
+}
+
+/// String array under `key`; a missing key or null is an empty list.
+fn string_list(value: &Value, key: &str) -> Result<Vec<String>, String> {
+    match value.get(key) {
+        None | Some(Value::Null) => Ok(Vec::new()),
+        Some(Value::Array(items)) => items
+            .iter()
+            .map(|item| {
+                item.as_str()
+                    .map(|s| s.trim().to_string())
+                    .ok_or_else(|| format!("LLM provider reply has a non-string in \"{key}\"."))
+            })
+            .collect(),
+        Some(_) => Err(format!("LLM provider reply has a non-array \"{key}\".")),
+    }
+}
+
+fn models_from_reply(reply: &Value, key: &str, id_prefix: &str) -> Result<Vec<Value>, String> {
+    let items = match reply.get(key) {
+        None | Some(Value::Null) => return Ok(Vec::new()),
+        Some(Value::Array(items)) => items,
+        Some(_) => return Err(format!("LLM provider reply has a non-array \"{key}\".")),
+    };
+    items
+        .iter()
+        .enumerate()
+        .map(|(idx, item)| {
+            let dv = required_string(item, "dv")?;
+            let iv = string_list(item, "iv")?;
+            let controls = string_list(item, "controls")?;
+            let id = item
+                .get("id")
+                .and_then(Value::as_str)
+                .map(str::trim)
+                .filter(|id| !id.is_empty())
+                .map(str::to_string)
+                .unwrap_or_else(|| format!("{id_prefix}{}", idx + 1));
+            let formula = item
+                .get("formula")
+                .and_then(Value::as_str)
+                .map(str::trim)
+                .filter(|f| f.contains('~'))
+                .map(str::to_string)
+                .unwrap_or_else(|| {
+                    let rhs: Vec<&str> = iv.iter().chain(&controls).map(String::as_str).collect();
+                    format!("{dv} ~ {}", rhs.join(" + "))
+                });
+            Ok(json!({
+              "id": id,
+              "dv": dv,
+              "iv": iv,
+              "controls": controls,
+              "interactionTerms": string_list(item, "interactionTerms")?,
+              "formula": formula,
+            }))
+        })
+        .collect()
+}
+
+/// Checks a provider reply against the `parsed` schema the heuristic extractor produces and
+/// fills in optional fields, so both sources merge into the prereg the same way.
+pub fn prereg_models_from_reply(reply: &Value) -> Result<Value, String> {
+    if !reply.is_object() {
+        return Err("LLM provider reply is not a JSON object.".to_string());
+    }
+    let variables = reply.get("variables").cloned().unwrap_or(Value::Null);
+    Ok(json!({
+      "mainModels": models_from_reply(reply, "mainModels", "llm_m")?,
+      "exploratoryModels": models_from_reply(reply, "exploratoryModels", "llm_e")?,
+      "mechanismModels": models_from_reply(reply, "mechanismModels", "llm_mech")?,
+      "robustnessChecks": string_list(reply, "robustnessChecks")?,
+      "variables": {
+        "mediators": string_list(&variables, "mediators")?,
+        "moderators": string_list(&variables, "moderators")?,
+        "exploratory": string_list(&variables, "exploratory")?
+      },
+      "ambiguities": string_list(reply, "ambiguities")?,
+    }))
+}
+
+/// Runs `remote` against the configured provider, or `heuristic` when the provider is the
+/// offline one. Any provider failure falls back to `heuristic` with a note in its
+/// `ambiguities`. Returns the extraction and a provenance block naming the backend used.
+pub fn extract_with_fallback(
+    settings: &LlmSettings,
+    remote: impl FnOnce(&OpenAiCompatibleClient) -> Result<Value, String>,
+    heuristic: impl FnOnce() -> Result<Value, String>,
+) -> Result<(Value, Value), String> {
+    if settings.provider == LlmProvider::Heuristic {
+        return Ok((
+            heuristic()?,
+            json!({ "name": LlmProvider::Heuristic.as_str() }),
+        ));
+    }
+    let error = match OpenAiCompatibleClient::from_settings(settings)
+        .and_then(|client| Ok((remote(&client)?, client.provenance())))
+    {
+        Ok(result) => return Ok(result),
+        Err(error) => error,
+    };
+    let mut extracted = heuristic()?;
+    if let Some(ambiguities) = extracted
+        .get_mut("ambiguities")
+        .and_then(Value::as_array_mut)
+    {
+        ambiguities.push(Value::String(format!(
+            "The {} provider failed ({error}); heuristic extraction was used instead.",
+            settings.provider.as_str()
+        )));
+    }
+    Ok((
+        extracted,
+        json!({
+          "name": LlmProvider::Heuristic.as_str(),
+          "fallbackFrom": settings.provider.as_str(),
+          "error": error,
+        }),
+    ))
+}
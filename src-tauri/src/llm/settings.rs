@@ -3,6 +3,8 @@ use std::fs;
 use std::path::PathBuf;
 use tauri::AppHandle;
 
+pub use crate::store::storage::app_data_root;
+
 #[derive(Serialize, Deserialize, Clone, Debug)]
 #[serde(rename_all = "lowercase")]
 pub enum UpdatePolicy {
@@ -19,6 +21,26 @@ impl UpdatePolicy {
     }
 }
 
+/// Backend used for prereg and model-spec extraction.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum LlmProvider {
+    /// Offline pattern matching; needs no network access.
+    #[default]
+    Heuristic,
+    /// A chat-completions endpoint such as a local llama.cpp server or a hosted provider.
+    OpenaiCompatible,
+}
+
+impl LlmProvider {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Heuristic => "heuristic",
+            Self::OpenaiCompatible => "openai_compatible",
+        }
+    }
+}
+
 #[derive(Serialize, Deserialize, Clone, Debug)]
 #[serde(rename_all = "camelCase")]
 pub struct LlmSettings {
@@ -35,14 +57,16 @@ pub struct LlmSettings {
     pub last_checked_utc: Option<String>,
     #[serde(default)]
     pub last_error: Option<String>,
-}
-
-pub fn app_data_root(app: &AppHandle) -> Result<PathBuf, String> {
-    let base = tauri::api::path::app_data_dir(&app.config())
-        .ok_or_else(|| "Unable to resolve app data dir".to_string())?;
-    let root = base.join("research-workflow");
-    fs::create_dir_all(&root).map_err(|e| e.to_string())?;
-    Ok(root)
+    #[serde(default)]
+    pub provider: LlmProvider,
+    /// Endpoint root for `openai_compatible`, e.g. `http://127.0.0.1:8080/v1`.
+    #[serde(default)]
+    pub base_url: Option<String>,
+    #[serde(default)]
+    pub api_key: Option<String>,
+    /// Model name sent to the provider.
+    #[serde(default)]
+    pub model: Option<String>,
 }
 
 pub fn settings_path(app: &AppHandle) -> Result<PathBuf, String> {
@@ -68,6 +92,10 @@ impl LlmSettings {
             auto_check_days: 1,
             last_checked_utc: None,
             last_error: None,
+            provider: LlmProvider::Heuristic,
+            base_url: None,
+            api_key: None,
+            model: None,
         })
     }
 }
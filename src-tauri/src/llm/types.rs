@@ -28,6 +28,15 @@ pub struct ModelStatus {
     pub lock: Option<LlmModelLock>,
 }
 
+/// Payload of `llm://download-progress`, emitted while a model asset is being fetched.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct DownloadProgress {
+    pub bytes_done: u64,
+    pub bytes_total: Option<u64>,
+    pub asset_name: String,
+}
+
 #[derive(Serialize, Deserialize, Clone, Debug)]
 #[serde(rename_all = "camelCase")]
 pub struct ModelProvenance {
@@ -60,3 +69,44 @@ pub struct LlmProjectPreset {
     pub auto_check_days: u32,
     pub note: Option<String>,
 }
+
+/// A project whose `llm_lock.json` pins a model file.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct ModelLockRef {
+    pub project_id: String,
+    pub project_name: String,
+    pub tag: String,
+}
+
+/// A file in the model directory, as listed by `llm_list_local_models`.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct LocalModel {
+    pub file_name: String,
+    pub path: String,
+    pub bytes: u64,
+    /// From the `.sha256` sidecar; `None` until the file has been downloaded or verified.
+    pub sha256: Option<String>,
+    pub is_current_target: bool,
+    pub locked_by: Vec<ModelLockRef>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct ModelDirUsage {
+    pub model_dir: String,
+    pub file_count: usize,
+    pub total_bytes: u64,
+    /// Bytes held by files that are neither the current target nor locked by a project.
+    pub reclaimable_bytes: u64,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct DeleteModelReport {
+    pub deleted: bool,
+    pub bytes_freed: u64,
+    /// Projects locked to the file; a locked file is only deleted with `force`.
+    pub referenced_by: Vec<ModelLockRef>,
+}
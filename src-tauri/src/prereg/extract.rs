@@ -1,8 +1,41 @@
 use regex::Regex;
+use std::collections::HashMap;
+use std::sync::{Mutex, OnceLock};
 
-use crate::util::text::tokenize_identifiers;
+use crate::qsf::normalize::DURATION_COLUMN;
+use crate::util::text::{is_concept_stopword, tokenize_identifiers};
 
-use super::types::{AnalysisModelSpec, DerivedScale, ExclusionRule, PreregSpec};
+use super::types::{
+    AnalysisModelSpec, DerivedScale, ExclusionRule, ExtractionSummary, HypothesisSpec, PreregSpec,
+    TermTransform,
+};
+
+/// Compiles a fixed pattern once per call site and returns the shared `Regex`.
+macro_rules! static_regex {
+    ($pattern:expr) => {{
+        static RE: OnceLock<Regex> = OnceLock::new();
+        RE.get_or_init(|| Regex::new($pattern).expect("regex"))
+    }};
+}
+
+/// Patterns built at run time from markers and variable names, compiled once each.
+static DYNAMIC_REGEXES: OnceLock<Mutex<HashMap<String, Regex>>> = OnceLock::new();
+/// The cache is cleared rather than grown past this many patterns.
+const DYNAMIC_REGEX_LIMIT: usize = 1024;
+
+fn cached_regex(pattern: &str) -> Regex {
+    let cache = DYNAMIC_REGEXES.get_or_init(|| Mutex::new(HashMap::new()));
+    let mut cache = cache.lock().unwrap_or_else(|err| err.into_inner());
+    if let Some(re) = cache.get(pattern) {
+        return re.clone();
+    }
+    if cache.len() >= DYNAMIC_REGEX_LIMIT {
+        cache.clear();
+    }
+    let re = Regex::new(pattern).expect("regex");
+    cache.insert(pattern.to_string(), re.clone());
+    re
+}
 
 pub fn fill_from_text(spec: &mut PreregSpec, text: &str) {
     if spec.variables.dv.is_empty() {
@@ -109,17 +142,55 @@ pub fn fill_from_text(spec: &mut PreregSpec, text: &str) {
                 spec.variables.dv[0],
                 spec.variables.iv.join(" + ")
             )),
+            weight_var: None,
+            family_hint: None,
+            dv_transform: None,
+            predictor_transforms: Vec::new(),
         });
     }
+    if let Some(weight) = extract_weight_var(text) {
+        for model in &mut spec.main_analyses {
+            model.weight_var.get_or_insert_with(|| weight.clone());
+        }
+    }
+    if let Some(family) = extract_family_hint(text) {
+        for model in &mut spec.main_analyses {
+            model.family_hint.get_or_insert_with(|| family.to_string());
+        }
+    }
 
     spec.exclusion_rules = extract_exclusions(text);
     spec.derived_scales = extract_scales(text);
     spec.robustness_checks = extract_robustness(text);
     spec.missing_data_plan = extract_missing_data_plan(text);
+    if spec.hypotheses.is_empty() {
+        spec.hypotheses = extract_hypotheses(text);
+    }
+    link_hypotheses(&mut spec.hypotheses, &spec.main_analyses);
 
     if spec.main_analyses.is_empty() {
         spec.warnings.push("NO_MAIN_ANALYSIS_EXTRACTED".to_string());
     }
+    spec.extraction_summary = summarize_extraction(spec);
+}
+
+pub fn summarize_extraction(spec: &PreregSpec) -> ExtractionSummary {
+    let vars = &spec.variables;
+    let variables = vars.dv.len()
+        + vars.iv.len()
+        + vars.controls.len()
+        + vars.moderators.len()
+        + vars.mediators.len();
+    let models = spec.main_analyses.len() + spec.exploratory_analyses.len();
+    let sections = spec.sections.len();
+    let exclusions = spec.exclusion_rules.len();
+    ExtractionSummary {
+        sections,
+        variables,
+        models,
+        exclusions,
+        score: sections + variables + models + exclusions,
+    }
 }
 
 pub fn extract_variable_tokens(text: &str) -> Vec<String> {
@@ -131,16 +202,15 @@ pub fn extract_variable_tokens(text: &str) -> Vec<String> {
 
 pub fn extract_list_after_markers(text: &str, markers: &[&str]) -> Vec<String> {
     let mut out = Vec::new();
-    let heading_re =
-        Regex::new(r"(?im)^\s*(\d+\)|#+\s+|[A-Za-z][A-Za-z \t]{0,60}:)\s*$").expect("regex");
+    let heading_re = static_regex!(r"(?im)^\s*(\d+\)|#+\s+|[A-Za-z][A-Za-z \t]{0,60}:)\s*$");
+    let explicit_backtick = static_regex!(r"`([A-Za-z][A-Za-z0-9_]*)`");
     for marker in markers {
         let pattern = format!(r"(?im){}\s*[:\-]\s*([^\n\.]+)", regex::escape(marker));
-        let re = Regex::new(&pattern).expect("regex");
+        let re = cached_regex(&pattern);
         for cap in re.captures_iter(text) {
             let line = cap.get(1).map(|m| m.as_str()).unwrap_or("");
             for item in line.split(&[',', ';'][..]) {
                 let raw = item.trim();
-                let explicit_backtick = Regex::new(r"`([A-Za-z][A-Za-z0-9_]*)`").expect("regex");
                 for explicit in explicit_backtick.captures_iter(raw) {
                     let token = explicit[1].to_string();
                     if plausible_variable_token(&token) && !out.iter().any(|v| v == &token) {
@@ -165,8 +235,7 @@ pub fn extract_list_after_markers(text: &str, markers: &[&str]) -> Vec<String> {
 
         // Capture block-style lists under marker headings, e.g.:
         // "Dependent variables" then bullet/list lines.
-        let marker_heading =
-            Regex::new(&format!(r"(?im)^\s*{}\s*:?\s*$", regex::escape(marker))).expect("regex");
+        let marker_heading = cached_regex(&format!(r"(?im)^\s*{}\s*:?\s*$", regex::escape(marker)));
         let lines: Vec<&str> = text.lines().collect();
         let mut i = 0usize;
         while i < lines.len() {
@@ -199,28 +268,51 @@ pub fn extract_list_after_markers(text: &str, markers: &[&str]) -> Vec<String> {
 }
 
 pub fn extract_model_specs(text: &str) -> Vec<AnalysisModelSpec> {
-    let formula_re = Regex::new(r"([A-Za-z][A-Za-z0-9_]*)\s*~\s*([^\n\r]+)").expect("regex");
-    let regress_re = Regex::new(
-    r"(?im)(?:regress|predict|model)\s+([A-Za-z][A-Za-z0-9_ ]{1,80})\s+(?:on|from|using)\s+([A-Za-z][A-Za-z0-9_, +*:\- ]{1,200})"
-  ).expect("regex");
+    let formula_re = static_regex!(
+        r"(?:\b(log|log1p|log2|log10|sqrt)\(\s*([A-Za-z][A-Za-z0-9_]*)\s*\)|([A-Za-z][A-Za-z0-9_]*))\s*~\s*([^\n\r]+)"
+    );
+    let regress_re = static_regex!(
+        r"(?im)(?:regress|predict|model)\s+([A-Za-z][A-Za-z0-9_ ]{1,80})\s+(?:on|from|using)\s+([A-Za-z][A-Za-z0-9_, +*:\- ]{1,200})"
+    );
     let mut out = Vec::new();
     for (idx, cap) in formula_re.captures_iter(text).enumerate() {
-        let dv = cap[1].trim().to_string();
-        if !plausible_variable_token(&dv) {
+        let Some(dv) = cap.get(2).or_else(|| cap.get(3)) else {
+            continue;
+        };
+        let dv = dv.as_str().to_string();
+        // Formula outcomes are often a bare `y`, too short for the prose heuristic.
+        let short_name = dv.len() <= 2 && !is_concept_stopword(&dv.to_lowercase());
+        if !short_name && !plausible_variable_token(&dv) {
             continue;
         }
-        let rhs = cap[2].trim().to_string();
-        let (iv, controls, interactions) = parse_rhs_predictors(&rhs);
-        if iv.is_empty() {
+        let lhs = match cap.get(1) {
+            Some(transform) => format!("{}({dv})", transform.as_str()),
+            None => dv.clone(),
+        };
+        let rhs = cap[4].trim().to_string();
+        let formula = format!("{lhs} ~ {rhs}");
+        // A formula repeated later in the document (e.g. in a summary) is the same model.
+        if out
+            .iter()
+            .any(|m: &AnalysisModelSpec| m.formula.as_deref() == Some(formula.as_str()))
+        {
+            continue;
+        }
+        let terms = parse_rhs_predictors(&rhs);
+        if terms.iv.is_empty() {
             continue;
         }
         out.push(AnalysisModelSpec {
             id: format!("main_{}", idx + 1),
             dv,
-            iv,
-            controls,
-            interaction_terms: interactions,
-            formula: Some(format!("{} ~ {}", cap[1].trim(), rhs)),
+            iv: terms.iv,
+            controls: terms.controls,
+            interaction_terms: terms.interactions,
+            formula: Some(formula),
+            weight_var: None,
+            family_hint: None,
+            dv_transform: cap.get(1).map(|transform| transform.as_str().to_string()),
+            predictor_transforms: terms.transforms,
         });
     }
 
@@ -232,25 +324,32 @@ pub fn extract_model_specs(text: &str) -> Vec<AnalysisModelSpec> {
             }
             let dv = dv_tokens[0].clone();
             let rhs = cap[2].trim().to_string();
-            let (iv, controls, interactions) = parse_rhs_predictors(&rhs);
-            if iv.is_empty() {
+            let terms = parse_rhs_predictors(&rhs);
+            if terms.iv.is_empty() {
                 continue;
             }
+            let formula = format!(
+                "{} ~ {}",
+                dv,
+                terms
+                    .iv
+                    .iter()
+                    .chain(terms.controls.iter())
+                    .cloned()
+                    .collect::<Vec<String>>()
+                    .join(" + ")
+            );
             out.push(AnalysisModelSpec {
                 id: format!("main_{}", idx + 1),
-                dv: dv.clone(),
-                iv: iv.clone(),
-                controls: controls.clone(),
-                interaction_terms: interactions.clone(),
-                formula: Some(format!(
-                    "{} ~ {}",
-                    dv,
-                    iv.iter()
-                        .chain(controls.iter())
-                        .cloned()
-                        .collect::<Vec<String>>()
-                        .join(" + ")
-                )),
+                dv,
+                iv: terms.iv,
+                controls: terms.controls,
+                interaction_terms: terms.interactions,
+                formula: Some(formula),
+                weight_var: None,
+                family_hint: None,
+                dv_transform: None,
+                predictor_transforms: terms.transforms,
             });
         }
     }
@@ -258,24 +357,98 @@ pub fn extract_model_specs(text: &str) -> Vec<AnalysisModelSpec> {
 }
 
 pub fn extract_exclusions(text: &str) -> Vec<ExclusionRule> {
-    let re = Regex::new(r"(?im)(exclude|remove|drop)\s+([^\n\.]+)").expect("regex");
-    let mut out = Vec::new();
-    for (idx, cap) in re.captures_iter(text).enumerate() {
-        out.push(ExclusionRule {
-            id: format!("exclusion_{}", idx + 1),
-            rule_type: "filter".to_string(),
-            variable: None,
-            criterion: cap[2].trim().to_string(),
-        });
+    let active = static_regex!(r"(?im)(exclude|remove|drop)\s+([^\n\.]+)");
+    let passive = static_regex!(
+        r"(?im)\b((?:participants|respondents|responses)\b[^\n\.]*?)\s+(?:will be|are|were)\s+(?:excluded|removed|dropped)\b"
+    );
+    let mut found = active
+        .captures_iter(text)
+        .map(|cap| {
+            (
+                cap.get(0).map(|m| m.start()).unwrap_or(0),
+                cap[2].trim().to_string(),
+            )
+        })
+        .chain(passive.captures_iter(text).map(|cap| {
+            (
+                cap.get(0).map(|m| m.start()).unwrap_or(0),
+                cap[1].trim().to_string(),
+            )
+        }))
+        .collect::<Vec<(usize, String)>>();
+    found.sort_by_key(|(start, _)| *start);
+    found
+        .into_iter()
+        .enumerate()
+        .map(|(idx, (_, criterion))| {
+            let (rule_type, variable) = classify_exclusion(&criterion);
+            ExclusionRule {
+                id: format!("exclusion_{}", idx + 1),
+                rule_type: rule_type.to_string(),
+                variable,
+                criterion,
+            }
+        })
+        .collect()
+}
+
+/// Rule type and column for common exclusion phrasings: `duration`, `attention_check`,
+/// `duplicate`, `consent` and `completion`. Anything else is a plain `filter` with no column.
+pub fn classify_exclusion(criterion: &str) -> (&'static str, Option<String>) {
+    let attention = static_regex!(
+        r"(?i)attention|manipulation check|\bimc\b|instructed.response|trap question"
+    );
+    let duration = static_regex!(
+        r"(?i)\b(?:duration|time|finish\w*|complet\w*|respon\w*)\b.*?(?:<=|<|under|less than|fewer than|faster than|shorter than|below)\s*\d"
+    );
+    let duplicate =
+        static_regex!(r"(?i)duplicate|more than once|multiple (?:times|submissions|responses)");
+    let consent = static_regex!(
+        r"(?i)\b(?:not|no|don'?t|didn'?t|fail\w* to|refus\w*|declin\w*|withdr\w*)\b[^.]*\bconsent|\bnon-?consent|without consent"
+    );
+    let completion = static_regex!(
+        r"(?i)\b(?:not|didn'?t|fail\w* to)\s+(?:finish|complete)|\bincomplete\b|\bunfinished\b|\bpartial (?:responses|completions)"
+    );
+
+    if attention.is_match(criterion) {
+        ("attention_check", None)
+    } else if duration.is_match(criterion) {
+        ("duration", Some(DURATION_COLUMN.to_string()))
+    } else if duplicate.is_match(criterion) {
+        ("duplicate", Some(duplicate_key(criterion)))
+    } else if consent.is_match(criterion) {
+        ("consent", Some("consent".to_string()))
+    } else if completion.is_match(criterion) {
+        ("completion", Some("Finished".to_string()))
+    } else {
+        ("filter", None)
+    }
+}
+
+/// Column a duplicate-response rule de-duplicates on: the IP address, an identifier named in
+/// the criterion, or a recruitment-panel ID.
+fn duplicate_key(criterion: &str) -> String {
+    let lower = criterion.to_lowercase();
+    if static_regex!(r"\bip\b|ip address").is_match(&lower) {
+        return "IPAddress".to_string();
+    }
+    if let Some(id) = tokenize_identifiers(criterion).into_iter().next() {
+        return id;
+    }
+    if lower.contains("prolific") {
+        "PROLIFIC_PID".to_string()
+    } else if lower.contains("mturk") || lower.contains("worker") {
+        "workerId".to_string()
+    } else {
+        "participant_id".to_string()
     }
-    out
 }
 
 pub fn extract_scales(text: &str) -> Vec<DerivedScale> {
-    let re = Regex::new(r"(?im)(\d+)-item\s+([A-Za-z][A-Za-z0-9_]*)").expect("regex");
-    let text_re =
-    Regex::new(r"(?im)([A-Za-z][A-Za-z0-9 \-]{3,80})\s*\((four|five|six|seven|eight|nine|ten|\d+)\s+items?\)")
-      .expect("regex");
+    let re = static_regex!(r"(?im)(\d+)-item\s+([A-Za-z][A-Za-z0-9_]*)");
+    let text_re = static_regex!(
+        r"(?im)([A-Za-z][A-Za-z0-9 \-]{3,80})\s*\((four|five|six|seven|eight|nine|ten|\d+)\s+items?\)"
+    );
     let mut out = Vec::new();
     for cap in re.captures_iter(text) {
         let name = cap[2].to_string();
@@ -324,23 +497,51 @@ pub fn extract_robustness(text: &str) -> Vec<String> {
     out
 }
 
+/// Weight column named by phrases like "weighted by pop_weight" or "weight variable: w".
+fn extract_weight_var(text: &str) -> Option<String> {
+    let re = static_regex!(
+        r"(?i)\b(?:weighted\s+(?:by|using)|weight\s+variable\s*[:\-]?)\s+(?:the\s+)?`?([A-Za-z][A-Za-z0-9_.]*)`?"
+    );
+    let weight = re
+        .captures_iter(text)
+        .map(|cap| cap[1].trim_end_matches('.').to_string())
+        .find(|token| plausible_variable_token(token));
+    weight
+}
+
+/// GLM family implied by phrases like "Poisson regression", "count outcome" or "logistic
+/// regression".
+fn extract_family_hint(text: &str) -> Option<&'static str> {
+    let count = static_regex!(
+        r"(?i)\b(?:poisson|negative\s+binomial|count\s+(?:data|outcome|variable|model|regression)|number\s+of\s+times)\b"
+    );
+    let binary = static_regex!(
+        r"(?i)\b(?:logistic\s+regression|logit\s+model|binary\s+(?:outcome|dependent\s+variable|dv))\b"
+    );
+    if count.is_match(text) {
+        Some("poisson")
+    } else if binary.is_match(text) {
+        Some("binomial")
+    } else {
+        None
+    }
+}
+
 fn extract_missing_data_plan(text: &str) -> Option<String> {
-    let re = Regex::new(r"(?im)(missing data|missingness)\s*[:\-]\s*([^\n]+)").expect("regex");
+    let re = static_regex!(r"(?im)(missing data|missingness)\s*[:\-]\s*([^\n]+)");
     re.captures(text)
         .and_then(|cap| cap.get(2).map(|m| m.as_str().trim().to_string()))
 }
 
 fn extract_concepts_after_markers(text: &str, markers: &[&str]) -> Vec<String> {
     let mut out: Vec<String> = Vec::new();
-    let heading_re =
-        Regex::new(r"(?im)^\s*(\d+\)|#+\s+|[A-Za-z][A-Za-z \t]{0,60}:)\s*$").expect("regex");
+    let heading_re = static_regex!(r"(?im)^\s*(\d+\)|#+\s+|[A-Za-z][A-Za-z \t]{0,60}:)\s*$");
     let lines: Vec<&str> = text.lines().collect();
     for marker in markers {
-        let inline = Regex::new(&format!(
+        let inline = cached_regex(&format!(
             r"(?im){}\s*[:\-]\s*([^\n]+)",
             regex::escape(marker)
-        ))
-        .expect("regex");
+        ));
         for cap in inline.captures_iter(text) {
             if let Some(m) = cap.get(1) {
                 for item in split_candidates(m.as_str()) {
@@ -354,8 +555,7 @@ fn extract_concepts_after_markers(text: &str, markers: &[&str]) -> Vec<String> {
             }
         }
 
-        let marker_heading =
-            Regex::new(&format!(r"(?im)^\s*{}\s*:?\s*$", regex::escape(marker))).expect("regex");
+        let marker_heading = cached_regex(&format!(r"(?im)^\s*{}\s*:?\s*$", regex::escape(marker)));
         let mut i = 0usize;
         while i < lines.len() {
             if marker_heading.is_match(lines[i]) {
@@ -388,6 +588,119 @@ fn extract_concepts_after_markers(text: &str, markers: &[&str]) -> Vec<String> {
     out
 }
 
+/// Hypotheses labelled "H1:", "Hypothesis 2." or "Prediction 3:" anywhere in the text, plus
+/// numbered items ("1." or "(1)") under a hypothesis or prediction heading. A labelled
+/// hypothesis wins over a numbered item with the same number. Sorted by id, since docx
+/// sections are not joined in document order.
+pub fn extract_hypotheses(text: &str) -> Vec<HypothesisSpec> {
+    let label_re = static_regex!(
+        r"(?i)^[\s>*•\-]*(?:H|Hypothesis\s+|Prediction\s+)(\d+[a-z]?)(?:\*\*)?\s*[:.)\-]\s*(?:\*\*)?\s*(.+)$"
+    );
+    let heading_re = static_regex!(r"^\s*(?:#+\s+|\d+\)\s+)");
+    let item_re = static_regex!(r"^\s*(?:\((\d+)\)|(\d+)\.)\s+(.+)$");
+
+    let mut labelled: Vec<(String, String)> = Vec::new();
+    let mut numbered: Vec<(String, String)> = Vec::new();
+    let mut in_section = false;
+    for line in text.lines() {
+        if let Some(cap) = label_re.captures(line) {
+            labelled.push((
+                format!("H{}", cap[1].to_lowercase()),
+                cap[2].trim().to_string(),
+            ));
+            continue;
+        }
+        if let Some(cap) = item_re.captures(line) {
+            let body = cap[3].trim();
+            if in_section && !body.contains('~') {
+                let n = cap.get(1).or_else(|| cap.get(2)).map_or("", |m| m.as_str());
+                numbered.push((format!("H{n}"), body.to_string()));
+            }
+            continue;
+        }
+        let trimmed = line.trim();
+        let colon_heading = trimmed.ends_with(':') && trimmed.len() <= 80;
+        if heading_re.is_match(line) || colon_heading {
+            let lc = trimmed.to_lowercase();
+            in_section =
+                (lc.contains("hypothes") || lc.contains("predict")) && !lc.contains("analys");
+        }
+    }
+
+    let mut out: Vec<HypothesisSpec> = Vec::new();
+    for (id, text) in labelled.into_iter().chain(numbered) {
+        if text.is_empty() || out.iter().any(|h| h.id == id) {
+            continue;
+        }
+        out.push(HypothesisSpec {
+            direction: hypothesis_direction(&text),
+            id,
+            text,
+            linked_model_ids: Vec::new(),
+        });
+    }
+    out.sort_by_key(|h| {
+        let digits = h.id[1..]
+            .chars()
+            .take_while(|c| c.is_ascii_digit())
+            .collect::<String>();
+        (digits.parse::<u32>().unwrap_or(u32::MAX), h.id.clone())
+    });
+    out
+}
+
+fn hypothesis_direction(text: &str) -> Option<String> {
+    let lc = text.to_lowercase();
+    let null_re = static_regex!(
+        r"\b(?:no\s+(?:significant\s+)?(?:effect|difference|relationship|association|correlation)|(?:will|does|do)\s+not\s+(?:differ|affect|predict|influence|change|be\s+(?:associated|related|correlated))|null)\b"
+    );
+    if null_re.is_match(&lc) {
+        return Some("null".to_string());
+    }
+    let negative = static_regex!(
+        r"\b(?:decrease[sd]?|reduce[sd]?|reduction|lower|less|fewer|weaker|negative(?:ly)?|worse)\b"
+    )
+    .is_match(&lc);
+    let positive = static_regex!(r"\b(?:increase[sd]?|higher|more|greater|stronger|positive(?:ly)?|improve[sd]?|enhance[sd]?|better)\b")
+    .is_match(&lc);
+    match (positive, negative) {
+        (true, false) => Some("positive".to_string()),
+        (false, true) => Some("negative".to_string()),
+        _ => None,
+    }
+}
+
+/// Links each hypothesis to the models whose outcome and at least one predictor it names,
+/// falling back to models whose outcome alone it names. A model whose id equals the
+/// hypothesis id ("H1") is always the link.
+pub fn link_hypotheses(hypotheses: &mut [HypothesisSpec], models: &[AnalysisModelSpec]) {
+    for hypothesis in hypotheses.iter_mut() {
+        let lc = hypothesis.text.to_lowercase();
+        let mentions = |var: &str| {
+            let var = var.to_lowercase();
+            [var.clone(), var.replace('_', " ")]
+                .iter()
+                .any(|form| cached_regex(&format!(r"\b{}\b", regex::escape(form))).is_match(&lc))
+        };
+        let ids = |pred: &dyn Fn(&AnalysisModelSpec) -> bool| {
+            models
+                .iter()
+                .filter(|m| pred(m))
+                .map(|m| m.id.clone())
+                .collect::<Vec<String>>()
+        };
+        let by_id = ids(&|m| m.id.eq_ignore_ascii_case(&hypothesis.id));
+        let by_terms = ids(&|m| mentions(&m.dv) && m.iv.iter().any(|iv| mentions(iv)));
+        hypothesis.linked_model_ids = if !by_id.is_empty() {
+            by_id
+        } else if !by_terms.is_empty() {
+            by_terms
+        } else {
+            ids(&|m| mentions(&m.dv))
+        };
+    }
+}
+
 fn split_candidates(raw: &str) -> Vec<String> {
     raw.split(&[',', ';', '\n'][..])
         .map(|s| s.trim().to_string())
@@ -395,61 +708,360 @@ fn split_candidates(raw: &str) -> Vec<String> {
         .collect()
 }
 
-fn parse_rhs_predictors(rhs: &str) -> (Vec<String>, Vec<String>, Vec<String>) {
-    let coef_re = Regex::new(r"(?i)\b(?:b|beta)\d*\b").expect("regex");
-    let cleaned_rhs = coef_re.replace_all(rhs, "").to_string();
-    let mut iv: Vec<String> = Vec::new();
-    let mut controls: Vec<String> = Vec::new();
-    let mut interactions: Vec<String> = Vec::new();
-
-    for term in cleaned_rhs.split('+') {
-        let clean = term
-            .trim()
-            .trim_matches('`')
-            .trim_matches(|c: char| c == '*' || c == ':' || c == '=')
-            .to_string();
-        if clean.is_empty() || clean == "0" || clean == "1" {
+/// Predictors read off the right-hand side of a model formula.
+#[derive(Debug, Default)]
+struct RhsTerms {
+    iv: Vec<String>,
+    controls: Vec<String>,
+    interactions: Vec<String>,
+    transforms: Vec<TermTransform>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum RhsToken {
+    /// One word of a variable name or a prose phrase; adjacent words form one phrase.
+    Word(String),
+    /// A function name immediately followed by `(`, e.g. `log` or `scale`.
+    Call(String),
+    /// The inside of an `I(...)` wrapper, kept verbatim.
+    AsIs(String),
+    Plus,
+    Minus,
+    Star,
+    Colon,
+    Open,
+    Close,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Factor {
+    name: String,
+    transform: Option<String>,
+}
+
+/// Expanded formula terms; a term with several factors is an interaction.
+type Terms = Vec<Vec<Factor>>;
+
+fn tokenize_rhs(rhs: &str) -> Vec<RhsToken> {
+    let chars = rhs.chars().collect::<Vec<char>>();
+    let is_word = |c: char| c.is_alphanumeric() || matches!(c, '_' | '.' | '`' | '\'' | '"');
+    let mut tokens = Vec::new();
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        if is_word(c) {
+            let start = i;
+            // A hyphen between letters is part of the word ("self-esteem"), not a minus.
+            while i < chars.len()
+                && (is_word(chars[i])
+                    || (chars[i] == '-'
+                        && chars[i - 1].is_alphabetic()
+                        && chars.get(i + 1).is_some_and(|n| n.is_alphabetic())))
+            {
+                i += 1;
+            }
+            let word = chars[start..i].iter().collect::<String>();
+            if chars.get(i) != Some(&'(') {
+                tokens.push(RhsToken::Word(word));
+            } else if word == "I" {
+                let mut depth = 0;
+                let inner_start = i + 1;
+                while i < chars.len() {
+                    match chars[i] {
+                        '(' => depth += 1,
+                        ')' => depth -= 1,
+                        _ => {}
+                    }
+                    i += 1;
+                    if depth == 0 {
+                        break;
+                    }
+                }
+                let inner_end = if depth == 0 { i - 1 } else { i };
+                tokens.push(RhsToken::AsIs(
+                    chars[inner_start..inner_end].iter().collect(),
+                ));
+            } else {
+                tokens.push(RhsToken::Call(word));
+            }
             continue;
         }
+        match c {
+            '+' | ',' | ';' => tokens.push(RhsToken::Plus),
+            '-' => tokens.push(RhsToken::Minus),
+            '*' | '×' => tokens.push(RhsToken::Star),
+            ':' => tokens.push(RhsToken::Colon),
+            '(' | '[' => tokens.push(RhsToken::Open),
+            ')' | ']' => tokens.push(RhsToken::Close),
+            _ => {}
+        }
+        i += 1;
+    }
+    // Prose writes interactions as "income condition x information condition"; a lone `x`
+    // followed directly by another operand is that operator rather than a variable.
+    for idx in 0..tokens.len() {
+        let is_x = matches!(&tokens[idx], RhsToken::Word(w) if w.eq_ignore_ascii_case("x"));
+        let operand_follows = matches!(
+            tokens.get(idx + 1),
+            Some(RhsToken::Word(_) | RhsToken::Call(_) | RhsToken::AsIs(_) | RhsToken::Open)
+        );
+        if is_x && operand_follows {
+            tokens[idx] = RhsToken::Star;
+        }
+    }
+    tokens
+}
+
+fn same_term(a: &[Factor], b: &[Factor]) -> bool {
+    a.len() == b.len() && a.iter().all(|factor| b.contains(factor))
+}
 
-        let interaction_split = Regex::new(r"(?i)\s*(?:x|\*|:)\s*").expect("regex");
-        let parts = interaction_split
-            .split(&clean)
-            .map(normalize_concept_phrase)
-            .filter(|p| !p.is_empty())
-            .collect::<Vec<String>>();
-        if parts.len() >= 2 {
-            let interaction = parts.join(":");
-            if !interactions.iter().any(|i| i == &interaction) {
-                interactions.push(interaction);
+fn push_term(terms: &mut Terms, term: Vec<Factor>) {
+    if !term.is_empty() && !terms.iter().any(|t| same_term(t, &term)) {
+        terms.push(term);
+    }
+}
+
+/// `a:b` for every pair of terms, plus both sides when `with_main_effects` (`a*b`).
+fn cross_terms(left: Terms, right: Terms, with_main_effects: bool) -> Terms {
+    if left.is_empty() {
+        return right;
+    }
+    if right.is_empty() {
+        return left;
+    }
+    let mut out = Terms::new();
+    if with_main_effects {
+        for term in left.iter().chain(right.iter()) {
+            push_term(&mut out, term.clone());
+        }
+    }
+    for a in &left {
+        for b in &right {
+            let mut term = a.clone();
+            for factor in b {
+                if !term.contains(factor) {
+                    term.push(factor.clone());
+                }
             }
-            for part in parts {
-                if !iv.iter().any(|v| v == &part) {
-                    iv.push(part);
+            push_term(&mut out, term);
+        }
+    }
+    out
+}
+
+/// Recursive-descent reader for R formula right-hand sides: `+`/`-` bind loosest, then `*`,
+/// then `:`. Missing operands and stray operators from prose are skipped rather than rejected.
+struct RhsParser {
+    tokens: Vec<RhsToken>,
+    pos: usize,
+}
+
+impl RhsParser {
+    fn peek(&self) -> Option<&RhsToken> {
+        self.tokens.get(self.pos)
+    }
+
+    fn eat(&mut self, token: &RhsToken) -> bool {
+        if self.peek() == Some(token) {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn sum(&mut self) -> Terms {
+        let mut out = Terms::new();
+        let mut negate = false;
+        loop {
+            match self.peek() {
+                None | Some(RhsToken::Close) => break,
+                Some(RhsToken::Plus) => {
+                    self.pos += 1;
+                    negate = false;
+                    continue;
                 }
+                Some(RhsToken::Minus) => {
+                    self.pos += 1;
+                    negate = true;
+                    continue;
+                }
+                _ => {}
             }
-            continue;
+            let before = self.pos;
+            let terms = self.product();
+            if self.pos == before {
+                self.pos += 1;
+                continue;
+            }
+            if negate {
+                out.retain(|term| !terms.iter().any(|removed| same_term(term, removed)));
+            } else {
+                for term in terms {
+                    push_term(&mut out, term);
+                }
+            }
+            negate = false;
         }
+        out
+    }
 
-        let single = normalize_concept_phrase(&clean);
-        if single.is_empty() {
-            continue;
+    fn product(&mut self) -> Terms {
+        let mut acc = self.interaction();
+        while self.eat(&RhsToken::Star) {
+            let right = self.interaction();
+            acc = cross_terms(acc, right, true);
         }
-        let lower = single.to_lowercase();
-        if lower.contains("control") || lower.contains("covariat") || lower.contains("demograph") {
-            if !controls.iter().any(|v| v == &single) {
-                controls.push(single);
+        acc
+    }
+
+    fn interaction(&mut self) -> Terms {
+        let mut acc = self.primary();
+        while self.eat(&RhsToken::Colon) {
+            let right = self.primary();
+            acc = cross_terms(acc, right, false);
+        }
+        acc
+    }
+
+    fn primary(&mut self) -> Terms {
+        match self.peek().cloned() {
+            Some(RhsToken::Open) => {
+                self.pos += 1;
+                let terms = self.sum();
+                self.eat(&RhsToken::Close);
+                terms
             }
-        } else if !iv.iter().any(|v| v == &single) {
-            iv.push(single);
+            Some(RhsToken::Call(name)) => {
+                self.pos += 1;
+                self.eat(&RhsToken::Open);
+                let mut terms = self.sum();
+                self.eat(&RhsToken::Close);
+                for factor in terms.iter_mut().flatten() {
+                    factor.transform = Some(match factor.transform.take() {
+                        Some(inner) => format!("{name}({inner})"),
+                        None => name.clone(),
+                    });
+                }
+                terms
+            }
+            Some(RhsToken::AsIs(expr)) => {
+                self.pos += 1;
+                let ident = static_regex!(r"[A-Za-z][A-Za-z0-9_.]*(?:\s*\()?");
+                let mut terms = Terms::new();
+                for found in ident.find_iter(&expr) {
+                    if found.as_str().ends_with('(') {
+                        continue;
+                    }
+                    push_term(
+                        &mut terms,
+                        vec![Factor {
+                            name: found.as_str().to_string(),
+                            transform: Some(format!("I({})", expr.trim())),
+                        }],
+                    );
+                }
+                terms
+            }
+            Some(RhsToken::Word(_)) => {
+                let mut words = Vec::new();
+                while let Some(RhsToken::Word(word)) = self.peek() {
+                    words.push(word.clone());
+                    self.pos += 1;
+                }
+                let name = rhs_atom_name(&words);
+                if name.is_empty() {
+                    Vec::new()
+                } else {
+                    vec![vec![Factor {
+                        name,
+                        transform: None,
+                    }]]
+                }
+            }
+            _ => Vec::new(),
+        }
+    }
+}
+
+/// A single identifier is kept as written; numbers (the intercept terms `0`/`1`) yield
+/// nothing and prose phrases go through `normalize_concept_phrase`.
+fn rhs_atom_name(words: &[String]) -> String {
+    if let [word] = words {
+        let word = word
+            .trim_matches(|c: char| matches!(c, '`' | '\'' | '"'))
+            .trim_end_matches('.');
+        if word.parse::<f64>().is_ok() {
+            return String::new();
+        }
+        if static_regex!(r"^[A-Za-z][A-Za-z0-9_.]*$").is_match(word)
+            && !is_concept_stopword(&word.to_lowercase())
+        {
+            return word.to_string();
+        }
+    }
+    normalize_concept_phrase(&words.join(" "))
+}
+
+fn parse_rhs_predictors(rhs: &str) -> RhsTerms {
+    let coef_re = static_regex!(r"(?i)\b(?:b|beta)\d*\b");
+    let cleaned_rhs = coef_re.replace_all(rhs, "").to_string();
+    let mut parser = RhsParser {
+        tokens: tokenize_rhs(&cleaned_rhs),
+        pos: 0,
+    };
+    let mut terms = Terms::new();
+    while parser.pos < parser.tokens.len() {
+        // A stray `)` ends `sum` early; skip it and keep reading.
+        for term in parser.sum() {
+            push_term(&mut terms, term);
         }
+        parser.pos += 1;
     }
 
-    (iv, controls, interactions)
+    let mut out = RhsTerms::default();
+    let push_unique = |list: &mut Vec<String>, value: &str| {
+        if !list.iter().any(|v| v == value) {
+            list.push(value.to_string());
+        }
+    };
+    for term in &terms {
+        for factor in term {
+            if let Some(transform) = &factor.transform {
+                let annotation = TermTransform {
+                    variable: factor.name.clone(),
+                    transform: transform.clone(),
+                };
+                if !out.transforms.contains(&annotation) {
+                    out.transforms.push(annotation);
+                }
+            }
+        }
+        if let [single] = term.as_slice() {
+            let lower = single.name.to_lowercase();
+            if single.transform.is_none()
+                && (lower.contains("control")
+                    || lower.contains("covariat")
+                    || lower.contains("demograph"))
+            {
+                push_unique(&mut out.controls, &single.name);
+            } else {
+                push_unique(&mut out.iv, &single.name);
+            }
+            continue;
+        }
+        let names = term.iter().map(|f| f.name.as_str()).collect::<Vec<_>>();
+        push_unique(&mut out.interactions, &names.join(":"));
+        for name in names {
+            push_unique(&mut out.iv, name);
+        }
+    }
+    out
 }
 
 fn normalize_concept_phrase(raw: &str) -> String {
-    let explicit = Regex::new(r"`([A-Za-z][A-Za-z0-9_]*)`").expect("regex");
+    let explicit = static_regex!(r"`([A-Za-z][A-Za-z0-9_]*)`");
     if let Some(cap) = explicit.captures(raw) {
         return cap[1].to_string();
     }
@@ -476,93 +1088,6 @@ fn normalize_concept_phrase(raw: &str) -> String {
     tokens.join("_")
 }
 
-fn is_concept_stopword(word: &str) -> bool {
-    let stop = [
-        "a",
-        "an",
-        "the",
-        "of",
-        "for",
-        "to",
-        "in",
-        "on",
-        "at",
-        "by",
-        "and",
-        "or",
-        "is",
-        "are",
-        "be",
-        "being",
-        "been",
-        "that",
-        "this",
-        "these",
-        "those",
-        "our",
-        "their",
-        "participant",
-        "participants",
-        "self",
-        "reported",
-        "measure",
-        "measured",
-        "item",
-        "items",
-        "question",
-        "questions",
-        "respond",
-        "response",
-        "responses",
-        "asked",
-        "ask",
-        "student",
-        "students",
-        "advisee",
-        "advisors",
-        "advisor",
-        "company",
-        "offer",
-        "offers",
-        "option",
-        "options",
-        "using",
-        "will",
-        "would",
-        "should",
-        "can",
-        "could",
-        "anything",
-        "after",
-        "before",
-        "during",
-        "between",
-        "then",
-        "than",
-        "where",
-        "which",
-        "what",
-        "when",
-        "with",
-        "without",
-        "include",
-        "excluding",
-        "exclude",
-        "remove",
-        "drop",
-        "control",
-        "controls",
-        "covariate",
-        "covariates",
-        "outcome",
-        "outcomes",
-        "analysis",
-        "variable",
-        "variables",
-    ];
-    stop.iter().any(|v| v == &word)
-}
-
 fn is_allowed_single_token(word: &str) -> bool {
     let allowed = [
         "age",
@@ -587,28 +1112,55 @@ fn plausible_variable_token(token: &str) -> bool {
     if is_concept_stopword(&lower) {
         return false;
     }
-    if Regex::new(r"^QID\d+$").expect("regex").is_match(value) {
+    if static_regex!(r"^QID\d+$").is_match(value) {
         return true;
     }
     if value.contains('_') {
         return true;
     }
-    if Regex::new(r"^[a-z]+[A-Z][A-Za-z0-9]*$")
-        .expect("regex")
-        .is_match(value)
-    {
+    if static_regex!(r"^[a-z]+[A-Z][A-Za-z0-9]*$").is_match(value) {
         return true;
     }
-    Regex::new(r"^[A-Za-z][A-Za-z0-9]{2,}$")
-        .expect("regex")
-        .is_match(value)
-        && value.len() <= 64
+    static_regex!(r"^[A-Za-z][A-Za-z0-9]{2,}$").is_match(value) && value.len() <= 64
 }
 
 #[cfg(test)]
 mod tests {
-    use super::fill_from_text;
+    use super::{extract_list_after_markers, extract_model_specs, fill_from_text};
+    use crate::prereg::parse_docx::build_structured_spec;
     use crate::prereg::types::PreregSpec;
+    use crate::prereg::types::TermTransform;
+    use crate::util::text::decode_text;
+
+    #[test]
+    fn exclusion_sentences_are_classified_with_their_columns() {
+        let mut spec = PreregSpec::default();
+        fill_from_text(
+            &mut spec,
+            "We will exclude participants who fail the attention check.\n\
+             Participants who did not finish the survey will be excluded.\n\
+             We will remove duplicate submissions by the same prolific_pid.\n\
+             We will drop anyone we suspect of being a bot.\n",
+        );
+        let rules = spec
+            .exclusion_rules
+            .iter()
+            .map(|r| (r.rule_type.as_str(), r.variable.as_deref()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            rules,
+            vec![
+                ("attention_check", None),
+                ("completion", Some("Finished")),
+                ("duplicate", Some("prolific_pid")),
+                ("filter", None),
+            ]
+        );
+        assert_eq!(
+            spec.exclusion_rules[1].criterion,
+            "Participants who did not finish the survey"
+        );
+    }
 
     #[test]
     fn extracts_models_from_prereg_prose_with_coefficient_style_formula() {
@@ -642,6 +1194,91 @@ As a robustness check, we will run our regressions both without any control vari
             .any(|w| w == "NO_MAIN_ANALYSIS_EXTRACTED"));
     }
 
+    fn transform(variable: &str, transform: &str) -> TermTransform {
+        TermTransform {
+            variable: variable.to_string(),
+            transform: transform.to_string(),
+        }
+    }
+
+    #[test]
+    fn formula_with_intercept_suppression_keeps_both_predictors() {
+        let models = extract_model_specs("y ~ x1 + x2 - 1");
+        assert_eq!(models.len(), 1);
+        assert_eq!(models[0].dv, "y");
+        assert_eq!(models[0].iv, vec!["x1", "x2"]);
+        assert!(models[0].interaction_terms.is_empty());
+        assert_eq!(models[0].formula.as_deref(), Some("y ~ x1 + x2 - 1"));
+
+        let dropped = extract_model_specs("y ~ (x1 + x2 + x3) - x3");
+        assert_eq!(dropped[0].iv, vec!["x1", "x2"]);
+    }
+
+    #[test]
+    fn formula_transforms_are_unwrapped_and_recorded() {
+        let models = extract_model_specs("log(y) ~ x*z + I(age^2)");
+        assert_eq!(models.len(), 1);
+        let model = &models[0];
+        assert_eq!(model.dv, "y");
+        assert_eq!(model.dv_transform.as_deref(), Some("log"));
+        assert_eq!(model.iv, vec!["x", "z", "age"]);
+        assert_eq!(model.interaction_terms, vec!["x:z"]);
+        assert_eq!(
+            model.predictor_transforms,
+            vec![transform("age", "I(age^2)")]
+        );
+        assert_eq!(model.formula.as_deref(), Some("log(y) ~ x*z + I(age^2)"));
+
+        let scaled = extract_model_specs("sqrt(rt_ms) ~ scale(age) + factor(cond)");
+        assert_eq!(scaled[0].dv, "rt_ms");
+        assert_eq!(scaled[0].dv_transform.as_deref(), Some("sqrt"));
+        assert_eq!(scaled[0].iv, vec!["age", "cond"]);
+        assert_eq!(
+            scaled[0].predictor_transforms,
+            vec![transform("age", "scale"), transform("cond", "factor")]
+        );
+    }
+
+    #[test]
+    fn grouped_predictors_interact_with_each_member() {
+        let models = extract_model_specs("wellbeing ~ (x1 + x2)*cond");
+        assert_eq!(models.len(), 1);
+        assert_eq!(models[0].iv, vec!["x1", "x2", "cond"]);
+        assert_eq!(models[0].interaction_terms, vec!["x1:cond", "x2:cond"]);
+        assert!(models[0].predictor_transforms.is_empty());
+    }
+
+    #[test]
+    fn cached_patterns_escape_marker_metacharacters_and_scale_to_long_documents() {
+        let text = "DV (primary): wellbeing_score\nIV*: treatment_arm\nIVVV: not_a_marker\n\nCovariates (optional)\n- age_years\n- household_income\n";
+        for _ in 0..2 {
+            assert_eq!(
+                extract_list_after_markers(text, &["dv (primary)"]),
+                vec!["wellbeing_score".to_string()]
+            );
+            assert_eq!(
+                extract_list_after_markers(text, &["iv*"]),
+                vec!["treatment_arm".to_string()]
+            );
+            assert_eq!(
+                extract_list_after_markers(text, &["covariates (optional)"]),
+                vec!["age_years".to_string(), "household_income".to_string()]
+            );
+        }
+
+        let section = "DV: wellbeing_score\nIV: treatment_arm\nControls: age_years\n\nwellbeing_score ~ treatment_arm + age_years\n\n";
+        let mut single = PreregSpec::default();
+        fill_from_text(&mut single, section);
+        let mut long = PreregSpec::default();
+        fill_from_text(&mut long, &section.repeat(400));
+        assert_eq!(long.variables.dv, single.variables.dv);
+        assert_eq!(long.variables.iv, single.variables.iv);
+        assert_eq!(long.variables.controls, single.variables.controls);
+        assert_eq!(long.main_analyses.len(), single.main_analyses.len());
+        assert_eq!(long.main_analyses[0].dv, single.main_analyses[0].dv);
+        assert_eq!(long.main_analyses[0].iv, single.main_analyses[0].iv);
+    }
+
     #[test]
     fn does_not_promote_generic_words_to_variables() {
         let txt = r#"
@@ -660,4 +1297,72 @@ Participants will be asked to advise the student.
         assert!(!vars.iter().any(|v| v == "student"));
         assert!(vars.iter().any(|v| v == "advice_choice"));
     }
+
+    #[test]
+    fn decoded_cr_line_endings_allow_block_list_extraction() {
+        let raw =
+            b"Dependent variables\r- outcome_y\r- outcome_z\r\rIndependent variables:\r- treat_x\r";
+        let decoded = decode_text(raw);
+        let mut spec = PreregSpec::default();
+        fill_from_text(&mut spec, &decoded.text);
+        assert!(spec.variables.dv.iter().any(|v| v == "outcome_y"));
+        assert!(spec.variables.dv.iter().any(|v| v == "outcome_z"));
+        assert!(spec.variables.iv.iter().any(|v| v == "treat_x"));
+    }
+
+    #[test]
+    fn decoded_windows_1252_prereg_extracts_variables() {
+        let raw = b"\x93Primary\x94 outcomes\r\nDV: outcome_y \x96 measured after\r\noutcome_y ~ treat_x + age\r\n";
+        let decoded = decode_text(raw);
+        assert!(decoded.lossy);
+        let mut spec = PreregSpec::default();
+        fill_from_text(&mut spec, &decoded.text);
+        assert!(spec.variables.dv.iter().any(|v| v == "outcome_y"));
+        assert_eq!(spec.main_analyses[0].dv, "outcome_y");
+    }
+
+    #[test]
+    fn aspredicted_hypotheses_link_to_formulas_in_a_later_section() {
+        let txt = r#"
+1) Have any data been collected for this study already?
+No, no data have been collected for this study yet.
+
+2) What's the main question being asked or hypothesis being tested in this study?
+1. Participants in the gratitude condition will report lower loneliness than controls.
+2. Gratitude will increase wellbeing relative to the control condition.
+H3: Age will not be associated with wellbeing.
+
+5) Specify exactly which analyses you will conduct to examine the main question/hypothesis.
+(1) loneliness ~ condition + age
+(2) wellbeing ~ condition + age
+"#;
+        let mut spec = PreregSpec::default();
+        fill_from_text(&mut spec, txt);
+        let summary = spec
+            .hypotheses
+            .iter()
+            .map(|h| {
+                (
+                    h.id.as_str(),
+                    h.direction.as_deref(),
+                    h.linked_model_ids.clone(),
+                )
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            summary,
+            vec![
+                ("H1", Some("negative"), vec!["main_1".to_string()]),
+                ("H2", Some("positive"), vec!["main_2".to_string()]),
+                ("H3", Some("null"), vec!["main_2".to_string()]),
+            ]
+        );
+        assert!(spec.hypotheses[0]
+            .text
+            .starts_with("Participants in the gratitude"));
+
+        // The docx path joins sections out of order but yields the same hypotheses.
+        let docx = build_structured_spec(txt).expect("structured");
+        assert_eq!(docx.hypotheses, spec.hypotheses);
+    }
 }
@@ -2,4 +2,5 @@ pub mod extract;
 pub mod parse_docx;
 pub mod parse_json;
 pub mod parse_md;
+pub mod parse_pdf;
 pub mod types;
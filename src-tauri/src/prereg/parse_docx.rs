@@ -3,25 +3,36 @@ use std::io::Read;
 use zip::ZipArchive;
 
 use super::extract::fill_from_text;
-use super::types::PreregSpec;
+use super::types::{PreregSpec, PREREG_LOSSY_DECODE_WARNING};
+use crate::util::text::{decode_text, normalize_line_endings};
 
 pub fn parse_prereg_docx(path: &str) -> Result<PreregSpec, String> {
     let file = std::fs::File::open(path).map_err(|e| format!("Unable to open DOCX: {e}"))?;
     let mut zip = ZipArchive::new(file).map_err(|e| format!("Invalid DOCX zip: {e}"))?;
-    let mut xml = String::new();
+    let mut raw = Vec::new();
     zip.by_name("word/document.xml")
         .map_err(|e| format!("DOCX missing word/document.xml: {e}"))?
-        .read_to_string(&mut xml)
+        .read_to_end(&mut raw)
         .map_err(|e| format!("Unable to read DOCX XML: {e}"))?;
+    let decoded = decode_text(&raw);
 
+    let mut spec = build_structured_spec(&docx_xml_to_text(&decoded.text))?;
+    if decoded.lossy {
+        spec.warnings.push(PREREG_LOSSY_DECODE_WARNING.to_string());
+    }
+    Ok(spec)
+}
+
+fn docx_xml_to_text(xml: &str) -> String {
     let text = xml
         .replace("</w:p>", "\n")
         .replace("</w:tr>", "\n")
-        .replace("</w:tc>", " ");
+        .replace("</w:tc>", " ")
+        .replace("<w:br/>", "\n")
+        .replace("&#13;", "\n")
+        .replace("&#xD;", "\n");
     let tag_re = Regex::new(r"<[^>]+>").expect("regex");
-    let plain = tag_re.replace_all(&text, " ").to_string();
-
-    build_structured_spec(&plain)
+    normalize_line_endings(&tag_re.replace_all(&text, " "))
 }
 
 pub fn build_structured_spec(plain_text: &str) -> Result<PreregSpec, String> {
@@ -1,10 +1,11 @@
-use super::extract::fill_from_text;
+use super::extract::{fill_from_text, summarize_extraction};
 use super::types::PreregSpec;
 
 pub fn parse_prereg_json(raw: &str) -> Result<PreregSpec, String> {
     let parsed: serde_json::Value =
         serde_json::from_str(raw).map_err(|e| format!("Invalid prereg JSON: {e}"))?;
-    if let Ok(spec) = serde_json::from_value::<PreregSpec>(parsed.clone()) {
+    if let Ok(mut spec) = serde_json::from_value::<PreregSpec>(parsed.clone()) {
+        spec.extraction_summary = summarize_extraction(&spec);
         return Ok(spec);
     }
 
@@ -0,0 +1,374 @@
+use pdf_extract::{Document, MediaBox, OutputDev, OutputError, Transform};
+use regex::Regex;
+use std::panic::{self, AssertUnwindSafe};
+
+use super::parse_docx::build_structured_spec;
+use super::types::PreregSpec;
+
+/// Warning attached when the PDF text looks incomplete or garbled (scans, embedded-font
+/// encodings without a Unicode map, content pdf-extract could not read).
+pub const PDF_TEXT_EXTRACTION_LOSSY: &str = "PDF_TEXT_EXTRACTION_LOSSY";
+/// Share of readable characters below which extraction is reported as lossy.
+const PDF_MIN_READABLE_RATIO: f32 = 0.9;
+/// Horizontal gap (in points) between line starts that is treated as a column break.
+const PDF_MIN_COLUMN_GAP: f32 = 72.0;
+/// Lines a right-hand cluster needs before the page is laid out as two columns.
+const PDF_MIN_COLUMN_LINES: usize = 3;
+
+pub fn parse_prereg_pdf(path: &str) -> Result<PreregSpec, String> {
+    let bytes = std::fs::read(path).map_err(|e| format!("Unable to open PDF: {e}"))?;
+    parse_prereg_pdf_bytes(&bytes)
+}
+
+fn parse_prereg_pdf_bytes(bytes: &[u8]) -> Result<PreregSpec, String> {
+    if !bytes.starts_with(b"%PDF") {
+        return Err("Invalid PDF: missing %PDF header.".to_string());
+    }
+    let doc = Document::load_mem(bytes).map_err(|e| format!("Invalid PDF: {e}"))?;
+    if doc.is_encrypted() {
+        return Err("Encrypted PDFs are not supported; export an unprotected copy.".to_string());
+    }
+    let mut collector = RunCollector::default();
+    // pdf-extract panics on some malformed fonts; keep whatever was read before that.
+    let completed = panic::catch_unwind(AssertUnwindSafe(|| {
+        pdf_extract::output_doc(&doc, &mut collector)
+    }))
+    .is_ok_and(|result| result.is_ok());
+    let text = normalize_pdf_text(&collector.text());
+    let mut spec = build_structured_spec(&text)?;
+    if !completed
+        || collector.decoded_ratio() < PDF_MIN_READABLE_RATIO
+        || readable_ratio(&text) < PDF_MIN_READABLE_RATIO
+    {
+        spec.warnings.push(PDF_TEXT_EXTRACTION_LOSSY.to_string());
+    }
+    Ok(spec)
+}
+
+/// A piece of text drawn on one line; `x`/`y` are in page space.
+struct TextRun {
+    x: f32,
+    y: f32,
+    text: String,
+}
+
+/// Receives glyphs from pdf-extract and joins the ones that continue the same line into
+/// runs, so each page can be laid out by column.
+#[derive(Default)]
+struct RunCollector {
+    pages: Vec<Vec<TextRun>>,
+    runs: Vec<TextRun>,
+    /// Where the previous glyph ended, in page space.
+    last_end: f64,
+    glyphs: usize,
+    /// Glyphs the font could not map to Unicode (e.g. subset fonts without ToUnicode).
+    undecoded: usize,
+}
+
+impl RunCollector {
+    fn text(&mut self) -> String {
+        self.flush_page();
+        let pages: Vec<Vec<TextRun>> = std::mem::take(&mut self.pages);
+        pages
+            .into_iter()
+            .map(layout_runs)
+            .filter(|page| !page.trim().is_empty())
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    fn decoded_ratio(&self) -> f32 {
+        if self.glyphs == 0 {
+            return 1.0;
+        }
+        (self.glyphs - self.undecoded) as f32 / self.glyphs as f32
+    }
+
+    fn flush_page(&mut self) {
+        let mut runs = std::mem::take(&mut self.runs);
+        runs.retain(|run| !run.text.trim().is_empty());
+        if !runs.is_empty() {
+            self.pages.push(runs);
+        }
+    }
+}
+
+impl OutputDev for RunCollector {
+    fn begin_page(
+        &mut self,
+        _page_num: u32,
+        _media_box: &MediaBox,
+        _art_box: Option<(f64, f64, f64, f64)>,
+    ) -> Result<(), OutputError> {
+        self.flush_page();
+        Ok(())
+    }
+
+    fn end_page(&mut self) -> Result<(), OutputError> {
+        self.flush_page();
+        Ok(())
+    }
+
+    fn output_character(
+        &mut self,
+        trm: &Transform,
+        width: f64,
+        _spacing: f64,
+        font_size: f64,
+        char: &str,
+    ) -> Result<(), OutputError> {
+        self.glyphs += 1;
+        if char.is_empty() {
+            self.undecoded += 1;
+        }
+        let size = font_size * (trm.m11 * trm.m22).abs().sqrt();
+        let (x, y) = (trm.m31, trm.m32);
+        let continues = self.runs.last().is_some_and(|run| {
+            (f64::from(run.y) - y).abs() < size * 0.5
+                && x > self.last_end - size * 0.5
+                && x < self.last_end + size * 2.0
+        });
+        if continues {
+            let run = self.runs.last_mut().expect("run");
+            if x > self.last_end + size * 0.1 && !run.text.ends_with(' ') {
+                run.text.push(' ');
+            }
+            run.text.push_str(char);
+        } else {
+            self.runs.push(TextRun {
+                x: x as f32,
+                y: y as f32,
+                text: char.to_string(),
+            });
+        }
+        self.last_end = x + width * size;
+        Ok(())
+    }
+
+    fn begin_word(&mut self) -> Result<(), OutputError> {
+        Ok(())
+    }
+
+    fn end_word(&mut self) -> Result<(), OutputError> {
+        Ok(())
+    }
+
+    fn end_line(&mut self) -> Result<(), OutputError> {
+        Ok(())
+    }
+}
+
+/// Lays runs out top to bottom. When line starts split into a left and a right cluster the
+/// page is read as two columns, left first, so numbered sections in either column stay whole.
+fn layout_runs(mut runs: Vec<TextRun>) -> String {
+    let mut starts: Vec<f32> = runs.iter().map(|run| run.x).collect();
+    starts.sort_by(f32::total_cmp);
+    let split = starts
+        .windows(2)
+        .filter(|pair| pair[1] - pair[0] >= PDF_MIN_COLUMN_GAP)
+        .map(|pair| pair[1])
+        .find(|split| {
+            let right = runs.iter().filter(|run| run.x >= *split).count();
+            let left = runs.len() - right;
+            right >= PDF_MIN_COLUMN_LINES && left >= PDF_MIN_COLUMN_LINES
+        });
+    let column = |run: &TextRun| usize::from(split.is_some_and(|split| run.x >= split));
+    runs.sort_by(|a, b| {
+        column(a)
+            .cmp(&column(b))
+            .then(b.y.total_cmp(&a.y))
+            .then(a.x.total_cmp(&b.x))
+    });
+
+    let mut out = String::new();
+    let mut previous: Option<(usize, f32)> = None;
+    for run in &runs {
+        let key = (column(run), run.y);
+        match previous {
+            Some((col, y)) if col == key.0 && (y - key.1).abs() < 1.0 => out.push(' '),
+            Some(_) => out.push('\n'),
+            None => {}
+        }
+        out.push_str(run.text.trim());
+        previous = Some(key);
+    }
+    out
+}
+
+/// Collapses spacing, rejoins words hyphenated across line breaks and puts every numbered
+/// "N) Heading" on its own line so section detection matches the DOCX path.
+fn normalize_pdf_text(text: &str) -> String {
+    let space_re = Regex::new(r"[ \t\u{a0}]+").expect("regex");
+    let mut lines: Vec<String> = Vec::new();
+    for line in text.lines() {
+        let line = space_re.replace_all(line, " ").trim().to_string();
+        if line.is_empty() {
+            continue;
+        }
+        if let Some(previous) = lines.last_mut() {
+            let hyphenated = previous.len() > 1
+                && previous.ends_with('-')
+                && previous[..previous.len() - 1]
+                    .chars()
+                    .last()
+                    .is_some_and(char::is_alphabetic);
+            if hyphenated && line.starts_with(|c: char| c.is_lowercase()) {
+                previous.pop();
+                previous.push_str(&line);
+                continue;
+            }
+        }
+        lines.push(line);
+    }
+    let heading_re = Regex::new(r"(\S) +(\d{1,2}\) +\p{Lu})").expect("regex");
+    heading_re
+        .replace_all(&lines.join("\n"), "$1\n$2")
+        .to_string()
+}
+
+fn readable_ratio(text: &str) -> f32 {
+    let total = text.chars().filter(|c| !c.is_whitespace()).count();
+    if total == 0 {
+        return 0.0;
+    }
+    let readable = text
+        .chars()
+        .filter(|c| !c.is_whitespace() && (c.is_alphanumeric() || c.is_ascii_punctuation()))
+        .count();
+    readable as f32 / total as f32
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pdf_extract::content::{Content, Operation};
+    use pdf_extract::{dictionary, Object, Stream, StringFormat};
+
+    /// Writes a one-page PDF through lopdf, with compressed streams and a cross-reference
+    /// table as a real exporter produces. `lines` are `(x, y, text)` drawn with `font`.
+    fn write_pdf(font: pdf_extract::Dictionary, lines: &[(i64, i64, Object)]) -> Vec<u8> {
+        let mut doc = Document::with_version("1.5");
+        let pages_id = doc.new_object_id();
+        let font_id = doc.add_object(font);
+        let resources_id = doc.add_object(dictionary! {
+            "Font" => dictionary! { "F1" => font_id },
+        });
+        let mut operations = vec![
+            Operation::new("BT", vec![]),
+            Operation::new("Tf", vec!["F1".into(), 11.into()]),
+        ];
+        for (x, y, text) in lines {
+            let matrix = vec![
+                1.into(),
+                0.into(),
+                0.into(),
+                1.into(),
+                (*x).into(),
+                (*y).into(),
+            ];
+            operations.push(Operation::new("Tm", matrix));
+            operations.push(Operation::new("Tj", vec![text.clone()]));
+        }
+        operations.push(Operation::new("ET", vec![]));
+        let content = Content { operations }.encode().expect("encode content");
+        let content_id = doc.add_object(Stream::new(dictionary! {}, content));
+        let page_id = doc.add_object(dictionary! {
+            "Type" => "Page",
+            "Parent" => pages_id,
+            "Contents" => content_id,
+        });
+        doc.objects.insert(
+            pages_id,
+            Object::Dictionary(dictionary! {
+                "Type" => "Pages",
+                "Kids" => vec![page_id.into()],
+                "Count" => 1,
+                "Resources" => resources_id,
+                "MediaBox" => vec![0.into(), 0.into(), 595.into(), 842.into()],
+            }),
+        );
+        let catalog_id = doc.add_object(dictionary! {
+            "Type" => "Catalog",
+            "Pages" => pages_id,
+        });
+        doc.trailer.set("Root", catalog_id);
+        doc.compress();
+        let mut bytes = Vec::new();
+        doc.save_to(&mut bytes).expect("save pdf");
+        bytes
+    }
+
+    fn text(value: &str) -> Object {
+        Object::String(value.as_bytes().to_vec(), StringFormat::Literal)
+    }
+
+    #[test]
+    fn two_column_pdf_keeps_numbered_sections_and_rejoins_hyphenation() {
+        // Rows are drawn left to right across both columns, as some exporters do.
+        let rows = [
+            ("1) Variables", "3) Exclusions"),
+            ("DV: outcome_y", "We will exclude partici-"),
+            ("IV: treat_x", "pants with duration < 60"),
+            ("2) Analysis", "4) Robustness"),
+            ("outcome_y ~ treat_x + age", "Repeat without controls"),
+        ];
+        let mut lines = Vec::new();
+        for (idx, (left, right)) in rows.iter().enumerate() {
+            let y = 700 - idx as i64 * 14;
+            lines.push((72, y, text(left)));
+            lines.push((320, y, text(right)));
+        }
+        let helvetica = dictionary! {
+            "Type" => "Font",
+            "Subtype" => "Type1",
+            "BaseFont" => "Helvetica",
+            "Encoding" => "WinAnsiEncoding",
+        };
+
+        let spec = parse_prereg_pdf_bytes(&write_pdf(helvetica, &lines)).expect("spec");
+        let headings: Vec<&str> = spec.sections.keys().map(String::as_str).collect();
+        assert_eq!(headings.len(), 4, "{headings:?}");
+        let analysis = &spec.sections["2) Analysis"];
+        assert!(analysis.contains("outcome_y ~ treat_x + age"));
+        assert!(!analysis.contains("Exclusions"));
+        assert!(spec.sections["3) Exclusions"].contains("participants with duration < 60"));
+        assert!(!spec.main_analyses.is_empty());
+        assert!(!spec.exclusion_rules.is_empty());
+        assert!(!spec.warnings.iter().any(|w| w == PDF_TEXT_EXTRACTION_LOSSY));
+    }
+
+    #[test]
+    fn glyph_id_text_is_flagged_as_lossy() {
+        // A subset CID font without a ToUnicode map: the glyph IDs carry no text.
+        let cid_font = dictionary! {
+            "Type" => "Font",
+            "Subtype" => "Type0",
+            "BaseFont" => "ABCDEF+Calibri",
+            "Encoding" => "Identity-H",
+            "DescendantFonts" => vec![Object::Dictionary(dictionary! {
+                "Type" => "Font",
+                "Subtype" => "CIDFontType2",
+                "BaseFont" => "ABCDEF+Calibri",
+                "CIDSystemInfo" => dictionary! {
+                    "Registry" => text("Adobe"),
+                    "Ordering" => text("Identity"),
+                    "Supplement" => 0,
+                },
+                "FontDescriptor" => dictionary! {
+                    "Type" => "FontDescriptor",
+                    "FontName" => "ABCDEF+Calibri",
+                    "Flags" => 32,
+                },
+                "DW" => 500,
+            })],
+        };
+        let glyphs = Object::String(
+            vec![0, 1, 0, 2, 0, 3, 0, 4, 0, 5, 0, 6, 0, 7],
+            StringFormat::Hexadecimal,
+        );
+        let spec =
+            parse_prereg_pdf_bytes(&write_pdf(cid_font, &[(72, 700, glyphs)])).expect("spec");
+        assert!(spec.warnings.iter().any(|w| w == PDF_TEXT_EXTRACTION_LOSSY));
+        assert!(parse_prereg_pdf_bytes(b"not a pdf").is_err());
+    }
+}
@@ -1,6 +1,9 @@
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 
+/// Warning attached when prereg text had to be decoded lossily (non-UTF-8 input).
+pub const PREREG_LOSSY_DECODE_WARNING: &str = "PREREG_TEXT_LOSSY_DECODE";
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct PreregMetadata {
@@ -27,6 +30,26 @@ pub struct AnalysisModelSpec {
     pub controls: Vec<String>,
     pub interaction_terms: Vec<String>,
     pub formula: Option<String>,
+    /// Survey or post-stratification weight column, e.g. from "weighted by <var>".
+    #[serde(default)]
+    pub weight_var: Option<String>,
+    /// GLM family named in the prose ("poisson" for count outcomes, "binomial" for logistic
+    /// regression); the spec builder weighs it against the DV's survey question.
+    #[serde(default)]
+    pub family_hint: Option<String>,
+    /// Wrapper removed from the outcome in the formula, e.g. `log` for `log(y) ~ x`.
+    #[serde(default)]
+    pub dv_transform: Option<String>,
+    /// Wrappers removed from predictors, e.g. `scale` for `scale(age)` or `I(age^2)`.
+    #[serde(default)]
+    pub predictor_transforms: Vec<TermTransform>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TermTransform {
+    pub variable: String,
+    pub transform: String,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -47,6 +70,34 @@ pub struct DerivedScale {
     pub definition: String,
 }
 
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HypothesisSpec {
+    /// Stable label taken from the document ("H1", "H2a"); numbered predictions become "H<n>".
+    pub id: String,
+    pub text: String,
+    /// "positive", "negative" or "null" when the wording makes it clear.
+    pub direction: Option<String>,
+    #[serde(default)]
+    pub linked_model_ids: Vec<String>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExtractionSummary {
+    pub sections: usize,
+    pub variables: usize,
+    pub models: usize,
+    pub exclusions: usize,
+    pub score: usize,
+}
+
+impl ExtractionSummary {
+    pub fn is_empty(&self) -> bool {
+        self.models == 0 && self.variables == 0
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct PreregSpec {
@@ -57,9 +108,13 @@ pub struct PreregSpec {
     pub robustness_checks: Vec<String>,
     pub exclusion_rules: Vec<ExclusionRule>,
     pub derived_scales: Vec<DerivedScale>,
+    #[serde(default)]
+    pub hypotheses: Vec<HypothesisSpec>,
     pub missing_data_plan: Option<String>,
     pub sections: HashMap<String, String>,
     pub warnings: Vec<String>,
+    #[serde(default)]
+    pub extraction_summary: ExtractionSummary,
 }
 
 impl Default for PreregSpec {
@@ -81,9 +136,11 @@ impl Default for PreregSpec {
             robustness_checks: Vec::new(),
             exclusion_rules: Vec::new(),
             derived_scales: Vec::new(),
+            hypotheses: Vec::new(),
             missing_data_plan: None,
             sections: HashMap::new(),
             warnings: Vec::new(),
+            extraction_summary: ExtractionSummary::default(),
         }
     }
 }
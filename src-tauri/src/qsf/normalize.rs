@@ -1,36 +1,158 @@
 use std::collections::HashMap;
 
-use super::types::{QsfEmbeddedData, QsfQuestion, QsfSurveySpec};
+use crate::util::text::clean_names;
 
-const STANDARD_COLUMNS: &[&str] = &[
-    "ResponseId",
-    "Finished",
-    "Progress",
-    "Duration (in seconds)",
-    "RecordedDate",
+use super::types::{
+    ExpectedColumn, QsfEmbeddedData, QsfIssue, QsfLoopIteration, QsfQuestion, QsfSurveySpec,
+    DUPLICATE_EXPORT_TAG,
+};
+
+/// Response metadata Qualtrics adds to every CSV export; none of it appears in the QSF.
+pub const QUALTRICS_META_COLUMNS: &[&str] = &[
     "StartDate",
     "EndDate",
     "Status",
+    "Progress",
+    "Duration (in seconds)",
+    "Finished",
+    "RecordedDate",
+    "ResponseId",
+    "DistributionChannel",
+    "UserLanguage",
 ];
 
+pub const DURATION_COLUMN: &str = "Duration (in seconds)";
+
+/// Renames repeated export tags to `<tag>.1`, `<tag>.2`, ... as Qualtrics does in its CSV
+/// export, keeping the first question as the canonical column, and reports one warning per
+/// reused tag. Tags differing only in case count as repeats, since they collide once the
+/// export's column names are cleaned. A suffix another question already uses is skipped.
+fn disambiguate_export_tags(questions: &mut [QsfQuestion]) -> Vec<QsfIssue> {
+    let mut order: Vec<String> = Vec::new();
+    let mut groups: HashMap<String, Vec<usize>> = HashMap::new();
+    for (idx, q) in questions.iter().enumerate() {
+        let key = q.export_tag.to_lowercase();
+        let group = groups.entry(key.clone()).or_default();
+        if group.is_empty() {
+            order.push(key);
+        }
+        group.push(idx);
+    }
+
+    let mut warnings = Vec::new();
+    for key in order {
+        let indices = &groups[&key];
+        if indices.len() < 2 {
+            continue;
+        }
+        let tag = questions[indices[0]].export_tag.clone();
+        let original_tags: Vec<String> = indices
+            .iter()
+            .map(|&idx| questions[idx].export_tag.clone())
+            .collect();
+        let qids: Vec<String> = indices
+            .iter()
+            .map(|&idx| questions[idx].qualtrics_qid.clone())
+            .collect();
+        let question_texts: Vec<String> = indices
+            .iter()
+            .map(|&idx| clean_label(&questions[idx].question_text))
+            .collect();
+        let mut renamed_to = Vec::new();
+        let mut n = 0;
+        for &idx in indices.iter().skip(1) {
+            let renamed = loop {
+                n += 1;
+                let candidate = format!("{tag}.{n}");
+                if !groups.contains_key(&candidate.to_lowercase()) {
+                    break candidate;
+                }
+            };
+            questions[idx].export_tag = renamed.clone();
+            renamed_to.push(renamed);
+        }
+        warnings.push(QsfIssue {
+            code: DUPLICATE_EXPORT_TAG.to_string(),
+            message: format!(
+                "Export tag '{}' is used by {} questions ({}); '{}' keeps the tag and the others were renamed to {}.",
+                tag,
+                qids.len(),
+                qids.join(", "),
+                qids[0],
+                renamed_to.join(", ")
+            ),
+            details: serde_json::json!({
+                "exportTag": tag,
+                "qids": qids,
+                "originalTags": original_tags,
+                "questionTexts": question_texts,
+                "renamedTo": renamed_to,
+            }),
+        });
+    }
+    warnings
+}
+
 pub fn build_spec(
     survey_name: String,
-    questions: Vec<QsfQuestion>,
+    mut questions: Vec<QsfQuestion>,
     embedded_data_fields: Vec<QsfEmbeddedData>,
 ) -> QsfSurveySpec {
-    let mut expected_columns: Vec<String> =
-        STANDARD_COLUMNS.iter().map(|v| v.to_string()).collect();
+    let warnings = disambiguate_export_tags(&mut questions);
+    let mut expected_columns: Vec<String> = QUALTRICS_META_COLUMNS
+        .iter()
+        .map(|v| v.to_string())
+        .collect();
     let mut label_map: HashMap<String, String> = HashMap::new();
     let embedded_data = embedded_data_fields
         .iter()
         .map(|f| f.name.clone())
         .collect::<Vec<String>>();
 
+    let mut text_entry_columns: Vec<String> = Vec::new();
     for q in &questions {
-        if !expected_columns.iter().any(|c| c == &q.export_tag) {
-            expected_columns.push(q.export_tag.clone());
+        let mut label = match &q.matrix_stem {
+            Some(stem) if !stem.is_empty() => format!("{} - {}", stem, q.question_text),
+            _ => q.question_text.clone(),
+        };
+        match &q.loop_iteration {
+            Some(QsfLoopIteration {
+                index,
+                field_value: Some(value),
+            }) => label.push_str(&format!(" (loop {index}: {value})")),
+            Some(QsfLoopIteration { index, .. }) => label.push_str(&format!(" (loop {index})")),
+            None => {}
+        }
+        let label = clean_label(&label);
+        if q.multi_select {
+            for choice in &q.choices {
+                let column = format!("{}_{}", q.export_tag, choice.value);
+                if !expected_columns.iter().any(|c| c == &column) {
+                    expected_columns.push(column.clone());
+                }
+                label_map.insert(
+                    column,
+                    format!("{label} \u{2014} {}", clean_label(&choice.label)),
+                );
+            }
+        } else {
+            if !expected_columns.iter().any(|c| c == &q.export_tag) {
+                expected_columns.push(q.export_tag.clone());
+            }
+            label_map.insert(q.export_tag.clone(), label);
+        }
+        for choice in q.choices.iter().filter(|c| c.text_entry) {
+            let column = format!("{}_{}_TEXT", q.export_tag, choice.value);
+            if expected_columns.iter().any(|c| c == &column) {
+                continue;
+            }
+            expected_columns.push(column.clone());
+            label_map.insert(
+                column.clone(),
+                format!("Other text for {}", clean_label(&q.question_text)),
+            );
+            text_entry_columns.push(column);
         }
-        label_map.insert(q.export_tag.clone(), clean_label(&q.question_text));
     }
     for ed in &embedded_data {
         if !expected_columns.iter().any(|c| c == ed) {
@@ -38,6 +160,15 @@ pub fn build_spec(
         }
     }
 
+    let columns = expected_columns
+        .iter()
+        .map(|name| ExpectedColumn {
+            name: name.clone(),
+            clean_name: clean_names(name),
+            meta: QUALTRICS_META_COLUMNS.contains(&name.as_str()),
+        })
+        .collect();
+
     QsfSurveySpec {
         survey_name,
         questions,
@@ -45,6 +176,9 @@ pub fn build_spec(
         embedded_data_fields,
         expected_columns,
         label_map,
+        text_entry_columns,
+        columns,
+        warnings,
     }
 }
 
@@ -5,15 +5,22 @@ use strsim::normalized_levenshtein;
 use crate::util::text::normalize_token;
 
 use super::normalize::build_spec;
-use super::types::{QsfChoice, QsfEmbeddedData, QsfQuestion, QsfSurveySpec};
+use super::types::{
+    QsfChoice, QsfEmbeddedData, QsfIssue, QsfLoopIteration, QsfQuestion, QsfSurveySpec,
+    LOOP_MERGE_DYNAMIC,
+};
+
+/// Iterations assumed for a Loop & Merge block whose items come from an earlier answer.
+pub const DEFAULT_LOOP_ITERATIONS: usize = 3;
 
 pub fn parse_qsf_json(raw: &str) -> Result<QsfSurveySpec, String> {
-    parse_qsf_json_with_tokens(raw, &[])
+    parse_qsf_json_with_options(raw, &[], DEFAULT_LOOP_ITERATIONS)
 }
 
-pub fn parse_qsf_json_with_tokens(
+pub fn parse_qsf_json_with_options(
     raw: &str,
     candidate_tokens: &[String],
+    default_loop_iterations: usize,
 ) -> Result<QsfSurveySpec, String> {
     let root: Value = serde_json::from_str(raw).map_err(|e| format!("Invalid QSF JSON: {e}"))?;
     let survey_name = root
@@ -35,29 +42,206 @@ pub fn parse_qsf_json_with_tokens(
 
     let mut questions: Vec<QsfQuestion> = Vec::new();
     let mut embedded_data_fields: Vec<QsfEmbeddedData> = Vec::new();
+    let mut loop_blocks: Vec<LoopBlock> = Vec::new();
 
     for element in elements {
         match element.get("Element").and_then(Value::as_str).unwrap_or("") {
             "SQ" => {
                 if let Some(payload) = element.get("Payload") {
                     if let Some(q) = parse_question(payload, &token_filters) {
-                        questions.push(q);
+                        questions.extend(expand_matrix(payload, q));
                     }
                 }
             }
             "FL" => {
                 if let Some(payload) = element.get("Payload") {
-                    extract_embedded_data(payload, &mut embedded_data_fields);
+                    extract_embedded_data(payload, false, &mut embedded_data_fields);
+                }
+            }
+            "BL" => {
+                if let Some(payload) = element.get("Payload") {
+                    loop_blocks.extend(parse_loop_blocks(payload, default_loop_iterations));
                 }
             }
             _ => {}
         }
     }
 
+    let observed = collect_observed_values(embedded_data_fields.iter());
+    let randomized =
+        collect_observed_values(embedded_data_fields.iter().filter(|f| f.is_randomized));
     embedded_data_fields.sort_by(|a, b| a.name.cmp(&b.name));
     embedded_data_fields.dedup_by(|a, b| a.name.eq_ignore_ascii_case(&b.name));
+    for field in &mut embedded_data_fields {
+        let values_for = |collected: &[(String, Vec<String>)]| {
+            collected
+                .iter()
+                .find(|(name, _)| name.eq_ignore_ascii_case(&field.name))
+                .map(|(_, values)| values.clone())
+        };
+        if let Some(values) = values_for(&observed) {
+            field.observed_values = values;
+        }
+        // Only literal branch values count; a randomizer that pipes values in is not
+        // assigning conditions.
+        field.possible_values = values_for(&randomized).unwrap_or_default();
+        field.is_randomized = !field.possible_values.is_empty();
+    }
+
+    let loop_issues = dynamic_loop_issues(&loop_blocks, &questions);
+    let questions = expand_loops(questions, &loop_blocks);
+    let mut spec = build_spec(survey_name, questions, embedded_data_fields);
+    spec.warnings.extend(loop_issues);
+    Ok(spec)
+}
+
+/// A block with Loop & Merge enabled and the questions it repeats.
+struct LoopBlock {
+    description: String,
+    question_ids: Vec<String>,
+    iterations: Vec<QsfLoopIteration>,
+    /// What a dynamic loop iterates over, e.g. `q://QID5/ChoiceGroup/SelectedChoices`;
+    /// `None` for a static loop whose items are listed in the QSF.
+    dynamic_source: Option<String>,
+}
 
-    Ok(build_spec(survey_name, questions, embedded_data_fields))
+fn parse_loop_blocks(payload: &Value, default_iterations: usize) -> Vec<LoopBlock> {
+    let blocks: Vec<&Value> = match payload {
+        Value::Object(map) => map.values().collect(),
+        Value::Array(items) => items.iter().collect(),
+        _ => Vec::new(),
+    };
+    blocks
+        .into_iter()
+        .filter_map(|block| loop_block(block, default_iterations))
+        .collect()
+}
+
+fn loop_block(block: &Value, default_iterations: usize) -> Option<LoopBlock> {
+    let options = block.get("Options")?;
+    let looping = options
+        .get("Looping")
+        .and_then(Value::as_str)
+        .filter(|mode| !mode.is_empty() && *mode != "None")?;
+    let question_ids: Vec<String> = block
+        .get("BlockElements")
+        .and_then(Value::as_array)?
+        .iter()
+        .filter(|item| item.get("Type").and_then(Value::as_str) == Some("Question"))
+        .filter_map(|item| item.get("QuestionID").and_then(Value::as_str))
+        .map(str::to_string)
+        .collect();
+    if question_ids.is_empty() {
+        return None;
+    }
+    let description = block
+        .get("Description")
+        .and_then(Value::as_str)
+        .or_else(|| block.get("ID").and_then(Value::as_str))
+        .unwrap_or("Loop block")
+        .to_string();
+
+    let static_items = options
+        .pointer("/LoopingOptions/Static")
+        .and_then(Value::as_object)
+        .filter(|items| looping == "Static" && !items.is_empty());
+    let (iterations, dynamic_source) = match static_items {
+        Some(items) => {
+            let mut keys: Vec<&String> = items.keys().collect();
+            keys.sort_by_key(|key| (key.parse::<usize>().unwrap_or(usize::MAX), key.to_string()));
+            let iterations = keys
+                .iter()
+                .enumerate()
+                .map(|(position, key)| QsfLoopIteration {
+                    index: key.parse().unwrap_or(position + 1),
+                    field_value: items[key.as_str()]
+                        .get("1")
+                        .and_then(Value::as_str)
+                        .map(strip_html)
+                        .filter(|value| !value.is_empty()),
+                })
+                .collect();
+            (iterations, None)
+        }
+        None => {
+            let source = options
+                .pointer("/LoopingOptions/ChoiceGroupLocator")
+                .or_else(|| options.pointer("/LoopingOptions/QID"))
+                .and_then(Value::as_str)
+                .unwrap_or(looping)
+                .to_string();
+            let iterations = (1..=default_iterations.max(1))
+                .map(|index| QsfLoopIteration {
+                    index,
+                    field_value: None,
+                })
+                .collect();
+            (iterations, Some(source))
+        }
+    };
+    Some(LoopBlock {
+        description,
+        question_ids,
+        iterations,
+        dynamic_source,
+    })
+}
+
+/// Qualtrics prefixes every looped column with its iteration number, so each question in a
+/// loop block becomes one `<index>_<tag>` question per iteration.
+fn expand_loops(questions: Vec<QsfQuestion>, blocks: &[LoopBlock]) -> Vec<QsfQuestion> {
+    questions
+        .into_iter()
+        .flat_map(|question| {
+            match blocks
+                .iter()
+                .find(|block| block.question_ids.contains(&question.qualtrics_qid))
+            {
+                Some(block) => block
+                    .iterations
+                    .iter()
+                    .map(|iteration| QsfQuestion {
+                        export_tag: format!("{}_{}", iteration.index, question.export_tag),
+                        loop_iteration: Some(iteration.clone()),
+                        ..question.clone()
+                    })
+                    .collect(),
+                None => vec![question],
+            }
+        })
+        .collect()
+}
+
+fn dynamic_loop_issues(blocks: &[LoopBlock], questions: &[QsfQuestion]) -> Vec<QsfIssue> {
+    blocks
+        .iter()
+        .filter_map(|block| {
+            let source = block.dynamic_source.as_ref()?;
+            let tags: Vec<&str> = questions
+                .iter()
+                .filter(|q| block.question_ids.contains(&q.qualtrics_qid))
+                .map(|q| q.export_tag.as_str())
+                .collect();
+            if tags.is_empty() {
+                return None;
+            }
+            Some(QsfIssue {
+                code: LOOP_MERGE_DYNAMIC.to_string(),
+                message: format!(
+                    "Loop & Merge block '{}' repeats once per item of {}; its columns were expanded for {} iterations. Check the export for the actual number.",
+                    block.description,
+                    source,
+                    block.iterations.len()
+                ),
+                details: serde_json::json!({
+                    "block": block.description,
+                    "source": source,
+                    "iterations": block.iterations.len(),
+                    "exportTags": tags,
+                }),
+            })
+        })
+        .collect()
 }
 
 fn parse_question(payload: &Value, token_filters: &[String]) -> Option<QsfQuestion> {
@@ -97,33 +281,147 @@ fn parse_question(payload: &Value, token_filters: &[String]) -> Option<QsfQuesti
         .unwrap_or("unknown")
         .to_string();
 
+    // Matrix rows live in "Choices" and the scale in "Answers"; the scale is what every
+    // row's column holds, so it is read as the choices here.
+    let options_key = if question_type == "Matrix" {
+        "Answers"
+    } else {
+        "Choices"
+    };
+    let multi_select = question_type == "MC"
+        && matches!(
+            payload.get("Selector").and_then(Value::as_str),
+            Some("MAVR" | "MAHR" | "MACOL" | "MSB")
+        );
+    let recode_values = payload.get("RecodeValues").and_then(Value::as_object);
     let mut choices: Vec<QsfChoice> = Vec::new();
-    if let Some(choice_obj) = payload.get("Choices").and_then(Value::as_object) {
+    if let Some(choice_obj) = payload.get(options_key).and_then(Value::as_object) {
         for (value, choice) in choice_obj {
             let label = choice
                 .get("Display")
                 .and_then(Value::as_str)
                 .map(strip_html)
                 .unwrap_or_else(String::new);
+            let text_entry = match choice.get("TextEntry") {
+                Some(Value::Bool(flag)) => *flag,
+                Some(Value::String(flag)) => flag.eq_ignore_ascii_case("true"),
+                _ => false,
+            };
+            let recode_value = recode_values
+                .and_then(|recodes| recodes.get(value))
+                .and_then(|recode| match recode {
+                    Value::String(s) => Some(s.trim().to_string()),
+                    Value::Number(n) => Some(n.to_string()),
+                    _ => None,
+                })
+                .filter(|recode| !recode.is_empty() && recode != value);
             choices.push(QsfChoice {
                 value: value.clone(),
                 label,
+                text_entry,
+                recode_value,
             });
         }
     }
 
+    if multi_select {
+        // The indicator columns follow the display order of the choices.
+        let order = ordered_choice_ids(payload);
+        choices.sort_by_key(|choice| order.iter().position(|id| id == &choice.value));
+    }
+
     Some(QsfQuestion {
         qualtrics_qid: qid,
         export_tag,
         question_text,
         question_type,
         choices,
+        matrix_stem: None,
+        loop_iteration: None,
+        multi_select,
     })
 }
 
-fn extract_embedded_data(node: &Value, out: &mut Vec<QsfEmbeddedData>) {
+/// Ids in `Choices` in display order (a matrix's statements, a multiple-choice question's
+/// options): `ChoiceOrder` when present, otherwise the keys sorted numerically.
+fn ordered_choice_ids(payload: &Value) -> Vec<String> {
+    let Some(rows) = payload.get("Choices").and_then(Value::as_object) else {
+        return Vec::new();
+    };
+    let ordered: Vec<String> = payload
+        .get("ChoiceOrder")
+        .and_then(Value::as_array)
+        .map(|order| {
+            order
+                .iter()
+                .filter_map(|id| match id {
+                    Value::String(s) => Some(s.clone()),
+                    Value::Number(n) => Some(n.to_string()),
+                    _ => None,
+                })
+                .filter(|id| rows.contains_key(id))
+                .collect()
+        })
+        .unwrap_or_default();
+    if !ordered.is_empty() {
+        return ordered;
+    }
+    let mut ids: Vec<String> = rows.keys().cloned().collect();
+    ids.sort_by_key(|id| (id.parse::<u64>().unwrap_or(u64::MAX), id.clone()));
+    ids
+}
+
+/// A single-answer matrix exports one column per statement (`<tag>_<row>`, or the row's
+/// own export tag when one is set), so it becomes one question per statement sharing the
+/// matrix scale. Other question types pass through unchanged.
+fn expand_matrix(payload: &Value, question: QsfQuestion) -> Vec<QsfQuestion> {
+    if question.question_type != "Matrix" {
+        return vec![question];
+    }
+    let row_ids = ordered_choice_ids(payload);
+    if row_ids.is_empty() {
+        return vec![question];
+    }
+    let row_tags = payload
+        .get("ChoiceDataExportTags")
+        .and_then(Value::as_object);
+    row_ids
+        .iter()
+        .map(|row| {
+            let statement = payload
+                .pointer(&format!("/Choices/{row}/Display"))
+                .and_then(Value::as_str)
+                .map(strip_html)
+                .unwrap_or_default();
+            let export_tag = row_tags
+                .and_then(|tags| tags.get(row))
+                .and_then(Value::as_str)
+                .map(str::trim)
+                .filter(|tag| !tag.is_empty())
+                .map(str::to_string)
+                .unwrap_or_else(|| format!("{}_{}", question.export_tag, row));
+            QsfQuestion {
+                qualtrics_qid: question.qualtrics_qid.clone(),
+                export_tag,
+                question_text: statement,
+                question_type: question.question_type.clone(),
+                choices: question.choices.clone(),
+                matrix_stem: Some(question.question_text.clone()),
+                loop_iteration: None,
+                multi_select: false,
+            }
+        })
+        .collect()
+}
+
+/// Collects embedded-data assignments from a flow; `in_randomizer` is set below
+/// `Randomizer`/`BlockRandomizer` nodes, whose branches assign the experimental condition.
+fn extract_embedded_data(node: &Value, in_randomizer: bool, out: &mut Vec<QsfEmbeddedData>) {
     if let Some(obj) = node.as_object() {
-        if obj.get("Type").and_then(Value::as_str) == Some("EmbeddedData") {
+        let node_type = obj.get("Type").and_then(Value::as_str);
+        let in_randomizer =
+            in_randomizer || matches!(node_type, Some("Randomizer" | "BlockRandomizer"));
+        if node_type == Some("EmbeddedData") {
             if let Some(fields) = obj.get("EmbeddedData").and_then(Value::as_array) {
                 for field in fields {
                     if let Some(name) = field.get("Field").and_then(Value::as_str) {
@@ -137,6 +435,9 @@ fn extract_embedded_data(node: &Value, out: &mut Vec<QsfEmbeddedData>) {
                         out.push(QsfEmbeddedData {
                             name: name.to_string(),
                             default_value,
+                            observed_values: Vec::new(),
+                            is_randomized: in_randomizer,
+                            possible_values: Vec::new(),
                         });
                     }
                 }
@@ -144,19 +445,109 @@ fn extract_embedded_data(node: &Value, out: &mut Vec<QsfEmbeddedData>) {
         }
 
         for value in obj.values() {
-            extract_embedded_data(value, out);
+            extract_embedded_data(value, in_randomizer, out);
         }
     } else if let Some(arr) = node.as_array() {
         for item in arr {
-            extract_embedded_data(item, out);
+            extract_embedded_data(item, in_randomizer, out);
         }
     }
 }
 
+fn collect_observed_values<'a>(
+    fields: impl Iterator<Item = &'a QsfEmbeddedData>,
+) -> Vec<(String, Vec<String>)> {
+    let mut out: Vec<(String, Vec<String>)> = Vec::new();
+    for field in fields {
+        let Some(value) = field
+            .default_value
+            .as_deref()
+            .map(str::trim)
+            .filter(|v| !v.is_empty() && !v.starts_with("${"))
+        else {
+            continue;
+        };
+        match out
+            .iter_mut()
+            .find(|(name, _)| name.eq_ignore_ascii_case(&field.name))
+        {
+            Some((_, values)) => {
+                if !values.iter().any(|v| v == value) {
+                    values.push(value.to_string());
+                }
+            }
+            None => out.push((field.name.clone(), vec![value.to_string()])),
+        }
+    }
+    out
+}
+
+const HTML_ENTITIES: &[(&str, &str)] = &[
+    ("amp", "&"),
+    ("lt", "<"),
+    ("gt", ">"),
+    ("quot", "\""),
+    ("apos", "'"),
+    ("nbsp", " "),
+    ("ndash", "-"),
+    ("mdash", "-"),
+    ("lsquo", "'"),
+    ("rsquo", "'"),
+    ("ldquo", "\""),
+    ("rdquo", "\""),
+    ("hellip", "..."),
+];
+
+fn decode_entities(input: &str) -> String {
+    let entity_re = Regex::new(r"&(#[0-9]{1,7}|#[xX][0-9a-fA-F]{1,6}|[a-zA-Z]+);").expect("regex");
+    entity_re
+        .replace_all(input, |caps: &regex::Captures| {
+            let name = &caps[1];
+            let decoded = if let Some(hex) = name.strip_prefix("#x").or(name.strip_prefix("#X")) {
+                u32::from_str_radix(hex, 16)
+                    .ok()
+                    .and_then(char::from_u32)
+                    .map(String::from)
+            } else if let Some(dec) = name.strip_prefix('#') {
+                dec.parse::<u32>()
+                    .ok()
+                    .and_then(char::from_u32)
+                    .map(String::from)
+            } else {
+                HTML_ENTITIES
+                    .iter()
+                    .find(|(entity, _)| *entity == name)
+                    .map(|(_, value)| value.to_string())
+            };
+            decoded.unwrap_or_else(|| caps[0].to_string())
+        })
+        .to_string()
+}
+
+/// Replaces Qualtrics piped text with the name it references, so
+/// `${e://Field/condition}` reads as `condition` and `${q://QID5/ChoiceTextEntryValue}`
+/// as `QID5`.
+fn replace_piped_text(input: &str) -> String {
+    let pipe_re = Regex::new(r"\$\{[a-zA-Z]+://([^}]*)\}").expect("regex");
+    pipe_re
+        .replace_all(input, |caps: &regex::Captures| {
+            let mut parts = caps[1].split('/').filter(|part| !part.is_empty());
+            let reference = match parts.next() {
+                Some("Field") => parts.next(),
+                first => first,
+            };
+            format!(" {} ", reference.unwrap_or(""))
+        })
+        .to_string()
+}
+
+/// Plain-text form of QSF question and choice HTML: tags become spaces, piped text keeps
+/// its field name, entities are decoded and whitespace is collapsed.
 fn strip_html(input: &str) -> String {
     let tag_re = Regex::new(r"<[^>]+>").expect("regex");
     let no_tags = tag_re.replace_all(input, " ");
-    no_tags
+    let decoded = decode_entities(&replace_piped_text(&no_tags));
+    decoded
         .split_whitespace()
         .collect::<Vec<&str>>()
         .join(" ")
@@ -242,7 +633,88 @@ fn canonical_token(token: &str) -> &str {
 
 #[cfg(test)]
 mod tests {
-    use super::{parse_qsf_json, parse_qsf_json_with_tokens};
+    use super::{parse_qsf_json, parse_qsf_json_with_options, strip_html, DEFAULT_LOOP_ITERATIONS};
+    use crate::qsf::normalize::QUALTRICS_META_COLUMNS;
+    use crate::qsf::types::{DUPLICATE_EXPORT_TAG, LOOP_MERGE_DYNAMIC};
+
+    #[test]
+    fn two_rating_tags_export_as_rating_and_rating_1() {
+        let raw = r#"{
+      "SurveyEntry": {"SurveyName": "T"},
+      "SurveyElements": [
+        {"Element":"SQ","Payload":{"QuestionID":"QID1","DataExportTag":"rating","QuestionText":"Rate the product","QuestionType":{"Type":"TE"}}},
+        {"Element":"SQ","Payload":{"QuestionID":"QID2","DataExportTag":"rating","QuestionText":"Rate the service","QuestionType":{"Type":"TE"}}}
+      ]
+    }"#;
+        let spec = parse_qsf_json(raw).expect("parse qsf");
+        let question_columns = &spec.expected_columns[QUALTRICS_META_COLUMNS.len()..];
+        assert_eq!(question_columns, ["rating", "rating.1"]);
+        assert_eq!(spec.label_map["rating"], "Rate the product");
+        assert_eq!(spec.label_map["rating.1"], "Rate the service");
+        assert_eq!(spec.warnings.len(), 1);
+        assert_eq!(spec.warnings[0].code, DUPLICATE_EXPORT_TAG);
+        assert_eq!(
+            spec.warnings[0].message,
+            "Export tag 'rating' is used by 2 questions (QID1, QID2); 'QID1' keeps the tag and the others were renamed to rating.1."
+        );
+        assert_eq!(
+            spec.warnings[0].details,
+            serde_json::json!({
+              "exportTag": "rating",
+              "qids": ["QID1", "QID2"],
+              "originalTags": ["rating", "rating"],
+              "questionTexts": ["Rate the product", "Rate the service"],
+              "renamedTo": ["rating.1"],
+            })
+        );
+    }
+
+    #[test]
+    fn export_tags_repeated_in_any_case_get_suffixed_columns_and_labels() {
+        let raw = r#"{
+      "SurveyEntry": {"SurveyName": "T"},
+      "SurveyElements": [
+        {"Element":"SQ","Payload":{"QuestionID":"QID4","DataExportTag":"rating","QuestionText":"Rate the first product","QuestionType":{"Type":"TE"}}},
+        {"Element":"SQ","Payload":{"QuestionID":"QID5","DataExportTag":"Rating","QuestionText":"Rate the second product","QuestionType":{"Type":"TE"}}},
+        {"Element":"SQ","Payload":{"QuestionID":"QID6","DataExportTag":"rating","QuestionText":"Rate the third product","QuestionType":{"Type":"TE"}}},
+        {"Element":"SQ","Payload":{"QuestionID":"QID7","DataExportTag":"rating.2","QuestionText":"Rate the shop","QuestionType":{"Type":"TE"}}}
+      ]
+    }"#;
+        let spec = parse_qsf_json(raw).expect("parse qsf");
+        let tags = spec
+            .questions
+            .iter()
+            .map(|q| q.export_tag.as_str())
+            .collect::<Vec<&str>>();
+        assert_eq!(tags, vec!["rating", "rating.1", "rating.3", "rating.2"]);
+        for (column, label) in [
+            ("rating", "Rate the first product"),
+            ("rating.1", "Rate the second product"),
+            ("rating.3", "Rate the third product"),
+            ("rating.2", "Rate the shop"),
+        ] {
+            assert_eq!(
+                spec.expected_columns
+                    .iter()
+                    .filter(|c| *c == column)
+                    .count(),
+                1
+            );
+            assert_eq!(spec.label_map.get(column).map(String::as_str), Some(label));
+        }
+        assert_eq!(spec.warnings.len(), 1);
+        assert_eq!(spec.warnings[0].code, DUPLICATE_EXPORT_TAG);
+        assert_eq!(
+            spec.warnings[0].details,
+            serde_json::json!({
+              "exportTag": "rating",
+              "qids": ["QID4", "QID5", "QID6"],
+              "originalTags": ["rating", "Rating", "rating"],
+              "questionTexts": ["Rate the first product", "Rate the second product", "Rate the third product"],
+              "renamedTo": ["rating.1", "rating.3"],
+            })
+        );
+    }
 
     #[test]
     fn parses_sq_and_fl_only_with_embedded_data_defaults() {
@@ -266,6 +738,102 @@ mod tests {
         assert!(!spec.embedded_data.iter().any(|e| e == "ignored"));
     }
 
+    #[test]
+    fn multi_select_question_expands_into_one_indicator_column_per_choice() {
+        let raw = r#"{
+      "SurveyEntry": {"SurveyName": "T"},
+      "SurveyElements": [
+        {"Element":"SQ","Payload":{"QuestionID":"QID3","DataExportTag":"platforms","QuestionText":"Which platforms do you use?","QuestionType":"MC","Selector":"MAVR","SubSelector":"TX",
+          "Choices":{"1":{"Display":"Facebook"},"2":{"Display":"Instagram"},"10":{"Display":"TikTok"},"4":{"Display":"Other","TextEntry":"true"}},
+          "ChoiceOrder":["1","2","10","4"]}},
+        {"Element":"SQ","Payload":{"QuestionID":"QID4","DataExportTag":"favorite","QuestionText":"Favorite?","QuestionType":"MC","Selector":"SAVR",
+          "Choices":{"1":{"Display":"Facebook"},"2":{"Display":"Instagram"}}}}
+      ]
+    }"#;
+        let spec = parse_qsf_json(raw).expect("parse qsf");
+        let platforms = &spec.questions[0];
+        assert!(platforms.multi_select);
+        assert!(!spec.questions[1].multi_select);
+
+        let question_columns: Vec<&str> = spec
+            .expected_columns
+            .iter()
+            .map(String::as_str)
+            .filter(|c| c.starts_with("platforms") || c.starts_with("favorite"))
+            .collect();
+        assert_eq!(
+            question_columns,
+            vec![
+                "platforms_1",
+                "platforms_2",
+                "platforms_10",
+                "platforms_4",
+                "platforms_4_TEXT",
+                "favorite"
+            ]
+        );
+        assert_eq!(
+            spec.label_map.get("platforms_10").map(String::as_str),
+            Some("Which platforms do you use? \u{2014} TikTok")
+        );
+        assert!(!spec.label_map.contains_key("platforms"));
+    }
+
+    #[test]
+    fn text_entry_choice_adds_text_column_with_label() {
+        let raw = r#"{
+      "SurveyEntry": {"SurveyName": "T"},
+      "SurveyElements": [
+        {"Element":"SQ","Payload":{"QuestionID":"QID7","DataExportTag":"gender","QuestionText":"What is your gender?","QuestionType":{"Type":"MC"},
+          "Choices":{"1":{"Display":"Woman"},"2":{"Display":"Man"},"4":{"Display":"Other (please specify)","TextEntry":"true"}}}}
+      ]
+    }"#;
+        let spec = parse_qsf_json(raw).expect("parse qsf");
+        assert!(spec.questions[0]
+            .choices
+            .iter()
+            .any(|c| c.value == "4" && c.text_entry));
+        assert!(spec.expected_columns.iter().any(|c| c == "gender_4_TEXT"));
+        assert_eq!(spec.text_entry_columns, vec!["gender_4_TEXT"]);
+        assert_eq!(
+            spec.label_map.get("gender_4_TEXT").map(String::as_str),
+            Some("Other text for What is your gender?")
+        );
+    }
+
+    #[test]
+    fn collects_randomizer_assigned_values_in_order() {
+        let raw = r#"{
+      "SurveyEntry": {"SurveyName": "T"},
+      "SurveyElements": [
+        {"Element":"FL","Payload":{"Flow":[
+          {"Type":"EmbeddedData","EmbeddedData":[{"Field":"condition","Value":""},{"Field":"pid","Value":"${e://Field/PROLIFIC_PID}"}]},
+          {"Type":"BlockRandomizer","Flow":[
+            {"Type":"EmbeddedData","EmbeddedData":[{"Field":"condition","Value":"control"}]},
+            {"Type":"EmbeddedData","EmbeddedData":[{"Field":"condition","Value":"treat"}]},
+            {"Type":"EmbeddedData","EmbeddedData":[{"Field":"Condition","Value":"control"}]}
+          ]}
+        ]}}
+      ]
+    }"#;
+        let spec = parse_qsf_json(raw).expect("parse qsf");
+        let condition = spec
+            .embedded_data_fields
+            .iter()
+            .find(|f| f.name.eq_ignore_ascii_case("condition"))
+            .expect("condition field");
+        assert_eq!(condition.observed_values, vec!["control", "treat"]);
+        assert!(condition.is_randomized);
+        assert_eq!(condition.possible_values, vec!["control", "treat"]);
+        let pid = spec
+            .embedded_data_fields
+            .iter()
+            .find(|f| f.name == "pid")
+            .expect("pid field");
+        assert!(pid.observed_values.is_empty());
+        assert!(!pid.is_randomized);
+    }
+
     #[test]
     fn targeted_mode_keeps_matching_questions_only() {
         let raw = r#"{
@@ -276,7 +844,8 @@ mod tests {
       ]
     }"#;
         let tokens = vec!["advice".to_string()];
-        let spec = parse_qsf_json_with_tokens(raw, &tokens).expect("parse qsf targeted");
+        let spec = parse_qsf_json_with_options(raw, &tokens, DEFAULT_LOOP_ITERATIONS)
+            .expect("parse qsf targeted");
         assert_eq!(spec.questions.len(), 1);
         assert_eq!(spec.questions[0].export_tag, "advice_choice");
     }
@@ -295,7 +864,8 @@ mod tests {
             "income_condition".to_string(),
             "information_condition".to_string(),
         ];
-        let spec = parse_qsf_json_with_tokens(raw, &tokens).expect("parse qsf targeted");
+        let spec = parse_qsf_json_with_options(raw, &tokens, DEFAULT_LOOP_ITERATIONS)
+            .expect("parse qsf targeted");
         let tags = spec
             .questions
             .iter()
@@ -305,4 +875,143 @@ mod tests {
         assert!(tags.iter().any(|t| t == "info"));
         assert!(!tags.iter().any(|t| t == "unrelated_var"));
     }
+
+    #[test]
+    fn matrix_expands_to_one_column_per_statement() {
+        let raw = r#"{
+      "SurveyEntry": {"SurveyName": "T"},
+      "SurveyElements": [
+        {"Element":"SQ","Payload":{"QuestionID":"QID4","DataExportTag":"wb","QuestionText":"How much do you agree?","QuestionType":"Matrix","Selector":"Likert","SubSelector":"SingleAnswer",
+          "Choices":{"1":{"Display":"I feel calm"},"2":{"Display":"I feel &quot;rested&quot;"},"3":{"Display":"I feel optimistic"},"4":{"Display":"I feel lonely"},"10":{"Display":"I feel useful"}},
+          "Answers":{"1":{"Display":"Disagree"},"2":{"Display":"Neutral"},"3":{"Display":"Agree"}},
+          "ChoiceDataExportTags":{"10":"wb_useful"}}}
+      ]
+    }"#;
+        let spec = parse_qsf_json(raw).expect("parse qsf");
+        let tags: Vec<&str> = spec
+            .questions
+            .iter()
+            .map(|q| q.export_tag.as_str())
+            .collect();
+        assert_eq!(tags, vec!["wb_1", "wb_2", "wb_3", "wb_4", "wb_useful"]);
+        let matrix_columns: Vec<&String> = spec
+            .expected_columns
+            .iter()
+            .filter(|c| c.starts_with("wb"))
+            .collect();
+        assert_eq!(matrix_columns.len(), 5);
+        assert!(!spec.expected_columns.iter().any(|c| c == "wb"));
+        assert_eq!(
+            spec.label_map.get("wb_2").map(String::as_str),
+            Some("How much do you agree? - I feel \"rested\"")
+        );
+        let scale: Vec<&str> = spec.questions[4]
+            .choices
+            .iter()
+            .map(|c| c.label.as_str())
+            .collect();
+        assert_eq!(scale, vec!["Disagree", "Neutral", "Agree"]);
+
+        let mapped = crate::spec::mapping::map_variable("wb_3", &spec);
+        assert_eq!(mapped.resolved_to.as_deref(), Some("wb_3"));
+        let mapped = crate::spec::mapping::map_variable("feel_lonely", &spec);
+        assert_eq!(mapped.resolved_to.as_deref(), Some("wb_4"));
+    }
+
+    #[test]
+    fn loop_and_merge_expands_columns_per_iteration() {
+        let raw = r#"{
+      "SurveyEntry": {"SurveyName": "T"},
+      "SurveyElements": [
+        {"Element":"BL","Payload":{
+          "1":{"Type":"Default","Description":"Intro","ID":"BL_1","BlockElements":[{"Type":"Question","QuestionID":"QID1"}]},
+          "2":{"Type":"Standard","Description":"Products","ID":"BL_2",
+            "BlockElements":[{"Type":"Question","QuestionID":"QID2"},{"Type":"Page Break"},{"Type":"Question","QuestionID":"QID3"}],
+            "Options":{"Looping":"Static","LoopingOptions":{"Static":{"1":{"1":"Apple"},"2":{"1":"Banana"},"3":{"1":"Cherry"}},"Randomization":"None"}}},
+          "3":{"Type":"Standard","Description":"Chosen brands","ID":"BL_3",
+            "BlockElements":[{"Type":"Question","QuestionID":"QID4"}],
+            "Options":{"Looping":"Question","LoopingOptions":{"QID":"QID1","ChoiceGroupLocator":"q://QID1/ChoiceGroup/SelectedChoices"}}}
+        }},
+        {"Element":"SQ","Payload":{"QuestionID":"QID1","DataExportTag":"brands","QuestionText":"Which brands do you know?","QuestionType":{"Type":"MC"}}},
+        {"Element":"SQ","Payload":{"QuestionID":"QID2","DataExportTag":"rating","QuestionText":"Rate ${lm://Field/1}","QuestionType":{"Type":"MC"}}},
+        {"Element":"SQ","Payload":{"QuestionID":"QID3","DataExportTag":"buy","QuestionText":"Would you buy it?","QuestionType":{"Type":"MC"}}},
+        {"Element":"SQ","Payload":{"QuestionID":"QID4","DataExportTag":"trust","QuestionText":"Trust this brand?","QuestionType":{"Type":"MC"}}}
+      ]
+    }"#;
+        let spec = parse_qsf_json(raw).expect("parse qsf");
+        let looped: Vec<&str> = spec
+            .expected_columns
+            .iter()
+            .map(String::as_str)
+            .filter(|c| c.ends_with("_rating") || c.ends_with("_buy"))
+            .collect();
+        assert_eq!(
+            looped,
+            vec!["1_rating", "2_rating", "3_rating", "1_buy", "2_buy", "3_buy"]
+        );
+        assert!(!spec.expected_columns.iter().any(|c| c == "rating"));
+        assert!(spec.expected_columns.iter().any(|c| c == "brands"));
+        assert_eq!(
+            spec.label_map.get("2_buy").map(String::as_str),
+            Some("Would you buy it? (loop 2: Banana)")
+        );
+        assert_eq!(spec.warnings.len(), 1);
+        assert_eq!(spec.warnings[0].code, LOOP_MERGE_DYNAMIC);
+        assert_eq!(spec.warnings[0].details["block"], "Chosen brands");
+        assert_eq!(
+            spec.warnings[0].details["iterations"],
+            DEFAULT_LOOP_ITERATIONS
+        );
+
+        let spec = parse_qsf_json_with_options(raw, &[], 2).expect("parse qsf");
+        let trust: Vec<&str> = spec
+            .expected_columns
+            .iter()
+            .map(String::as_str)
+            .filter(|c| c.ends_with("trust"))
+            .collect();
+        assert_eq!(trust, vec!["1_trust", "2_trust"]);
+        assert_eq!(spec.warnings[0].details["iterations"], 2);
+        assert_eq!(
+            spec.warnings[0].details["source"],
+            "q://QID1/ChoiceGroup/SelectedChoices"
+        );
+        assert_eq!(
+            spec.label_map.get("1_trust").map(String::as_str),
+            Some("Trust this brand? (loop 1)")
+        );
+    }
+
+    #[test]
+    fn strip_html_decodes_entities_and_keeps_piped_field_names() {
+        assert_eq!(
+            strip_html(
+                "Tom&nbsp;&amp;&nbsp;Jerry<br>say &quot;hi&quot;&#33; &lt;3 &#x2014; &bogus;"
+            ),
+            "Tom & Jerry say \"hi\"! <3 \u{2014} &bogus;"
+        );
+        assert_eq!(
+            strip_html("<p>You saw the ${e://Field/condition} video.</p><p>Rate it:</p>"),
+            "You saw the condition video. Rate it:"
+        );
+        assert_eq!(
+            strip_html("You said: ${q://QID5/ChoiceTextEntryValue}"),
+            "You said: QID5"
+        );
+
+        let raw = r#"{
+      "SurveyEntry": {"SurveyName": "T"},
+      "SurveyElements": [
+        {"Element":"SQ","Payload":{"QuestionID":"QID1","DataExportTag":"Q1","QuestionText":"Pick one","QuestionType":{"Type":"MC"},
+          "Choices":{"1":{"Display":"Salt &amp; pepper"},"2":{"Display":"${e://Field/flavor}&nbsp;only"}}}}
+      ]
+    }"#;
+        let spec = parse_qsf_json(raw).expect("parse qsf");
+        let labels: Vec<&str> = spec.questions[0]
+            .choices
+            .iter()
+            .map(|c| c.label.as_str())
+            .collect();
+        assert_eq!(labels, vec!["Salt & pepper", "flavor only"]);
+    }
 }
@@ -6,6 +6,19 @@ use std::collections::HashMap;
 pub struct QsfChoice {
     pub value: String,
     pub label: String,
+    /// "Other (please specify)" style choice that exports an extra `_TEXT` column.
+    #[serde(default)]
+    pub text_entry: bool,
+    /// Exported value from the question's `RecodeValues`, when it differs from the choice id.
+    #[serde(default)]
+    pub recode_value: Option<String>,
+}
+
+impl QsfChoice {
+    /// Value this choice takes in a numeric Qualtrics export.
+    pub fn data_value(&self) -> &str {
+        self.recode_value.as_deref().unwrap_or(&self.value)
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -13,6 +26,17 @@ pub struct QsfChoice {
 pub struct QsfEmbeddedData {
     pub name: String,
     pub default_value: Option<String>,
+    /// Distinct values assigned anywhere in the flow (defaults and randomizer
+    /// branches), in order of appearance.
+    #[serde(default)]
+    pub observed_values: Vec<String>,
+    /// Assigned inside a `Randomizer`/`BlockRandomizer` flow node, as experimental
+    /// conditions usually are.
+    #[serde(default)]
+    pub is_randomized: bool,
+    /// Distinct values the randomizer branches assign, in order of appearance.
+    #[serde(default)]
+    pub possible_values: Vec<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -23,6 +47,63 @@ pub struct QsfQuestion {
     pub question_text: String,
     pub question_type: String,
     pub choices: Vec<QsfChoice>,
+    /// Set on the per-statement questions a matrix expands into: the matrix question text,
+    /// while `question_text` holds the statement and `choices` the shared scale.
+    #[serde(default)]
+    pub matrix_stem: Option<String>,
+    /// Set on the per-iteration copies a Loop & Merge block expands its questions into.
+    #[serde(default)]
+    pub loop_iteration: Option<QsfLoopIteration>,
+    /// "Select all that apply" question: exported as one `<tag>_<choice>` indicator column
+    /// per choice (1 when selected, blank otherwise) instead of a single column.
+    #[serde(default)]
+    pub multi_select: bool,
+}
+
+impl QsfQuestion {
+    /// Columns this question exports its answers to, not counting `_TEXT` columns.
+    pub fn answer_columns(&self) -> Vec<String> {
+        if self.multi_select {
+            self.choices
+                .iter()
+                .map(|choice| format!("{}_{}", self.export_tag, choice.value))
+                .collect()
+        } else {
+            vec![self.export_tag.clone()]
+        }
+    }
+}
+
+/// One pass through a Loop & Merge block. Qualtrics exports it as `<index>_<tag>` columns.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct QsfLoopIteration {
+    pub index: usize,
+    /// First loop field for this iteration; unknown when the loop is driven by an answer.
+    pub field_value: Option<String>,
+}
+
+/// A column the Qualtrics CSV export is expected to contain. `meta` marks the
+/// standard response metadata (timing, progress, ids) that no QSF question defines.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct ExpectedColumn {
+    pub name: String,
+    pub clean_name: String,
+    #[serde(default)]
+    pub meta: bool,
+}
+
+pub const DUPLICATE_EXPORT_TAG: &str = "DUPLICATE_EXPORT_TAG";
+pub const LOOP_MERGE_DYNAMIC: &str = "LOOP_MERGE_DYNAMIC";
+
+/// Problem found while normalizing a QSF; carried into the analysis spec as a warning.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct QsfIssue {
+    pub code: String,
+    pub message: String,
+    pub details: serde_json::Value,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -34,4 +115,32 @@ pub struct QsfSurveySpec {
     pub embedded_data_fields: Vec<QsfEmbeddedData>,
     pub expected_columns: Vec<String>,
     pub label_map: HashMap<String, String>,
+    #[serde(default)]
+    pub text_entry_columns: Vec<String>,
+    #[serde(default)]
+    pub columns: Vec<ExpectedColumn>,
+    /// Duplicate export tags and dynamic loops; stored specs from before the rename call
+    /// this `issues`.
+    #[serde(default, alias = "issues")]
+    pub warnings: Vec<QsfIssue>,
+}
+
+impl QsfSurveySpec {
+    pub fn is_meta_column(&self, name: &str) -> bool {
+        self.columns
+            .iter()
+            .any(|c| c.meta && c.name.eq_ignore_ascii_case(name))
+    }
+
+    /// True for columns of questions renamed to `<tag>.N` because an earlier question
+    /// already used the export tag, including their `_TEXT` columns.
+    pub fn is_renamed_duplicate(&self, name: &str) -> bool {
+        self.warnings
+            .iter()
+            .filter(|issue| issue.code == DUPLICATE_EXPORT_TAG)
+            .filter_map(|issue| issue.details.get("renamedTo").and_then(|v| v.as_array()))
+            .flatten()
+            .filter_map(|value| value.as_str())
+            .any(|renamed| name == renamed || name.starts_with(&format!("{renamed}_")))
+    }
 }
@@ -0,0 +1,94 @@
+use reqwest::blocking::{Client, Response};
+use reqwest::header::{ACCEPT, RETRY_AFTER, USER_AGENT};
+use reqwest::StatusCode;
+use serde_json::Value;
+use std::thread;
+use std::time::Duration;
+
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(60);
+/// Longest `Retry-After` honoured before the single retry of a rate-limited request.
+const MAX_RETRY_WAIT: Duration = Duration::from_secs(30);
+
+/// Survey IDs look like `SV_0123456789abcde`.
+pub fn validate_survey_id(survey_id: &str) -> Result<&str, String> {
+    let id = survey_id.trim();
+    let valid = id
+        .strip_prefix("SV_")
+        .is_some_and(|rest| !rest.is_empty() && rest.chars().all(|c| c.is_ascii_alphanumeric()));
+    if valid {
+        Ok(id)
+    } else {
+        Err(format!("Invalid Qualtrics survey ID: {survey_id}"))
+    }
+}
+
+fn retry_wait(response: &Response) -> Duration {
+    response
+        .headers()
+        .get(RETRY_AFTER)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.trim().parse::<u64>().ok())
+        .map(Duration::from_secs)
+        .unwrap_or(Duration::from_secs(1))
+        .min(MAX_RETRY_WAIT)
+}
+
+/// Downloads the survey definition in QSF format
+/// (`GET {base_url}/API/v3/survey-definitions/{id}?format=qsf`) and returns the QSF document
+/// from the response's `result`. A 429 is retried once after `Retry-After`.
+pub fn fetch_survey_qsf(base_url: &str, token: &str, survey_id: &str) -> Result<Value, String> {
+    let survey_id = validate_survey_id(survey_id)?;
+    let client = Client::builder()
+        .timeout(REQUEST_TIMEOUT)
+        .build()
+        .map_err(|e| e.to_string())?;
+    let url = format!(
+        "{}/API/v3/survey-definitions/{survey_id}?format=qsf",
+        base_url.trim_end_matches('/')
+    );
+    let mut retried = false;
+    let response = loop {
+        let response = client
+            .get(&url)
+            .header("X-API-TOKEN", token)
+            .header(ACCEPT, "application/json")
+            .header(USER_AGENT, "research-workflow/0.1")
+            .send()
+            .map_err(|e| format!("Qualtrics request failed: {e}"))?;
+        if response.status() == StatusCode::TOO_MANY_REQUESTS && !retried {
+            retried = true;
+            thread::sleep(retry_wait(&response));
+            continue;
+        }
+        break response;
+    };
+    match response.status() {
+        status if status.is_success() => {}
+        StatusCode::UNAUTHORIZED | StatusCode::FORBIDDEN => {
+            return Err(format!(
+                "Qualtrics rejected the request ({}); check your API token and that it can access survey {survey_id}.",
+                response.status()
+            ))
+        }
+        StatusCode::NOT_FOUND => {
+            return Err(format!(
+                "Qualtrics survey {survey_id} was not found; check the survey ID and datacenter."
+            ))
+        }
+        StatusCode::TOO_MANY_REQUESTS => {
+            return Err("Qualtrics rate limit reached; try again in a minute.".to_string())
+        }
+        status => return Err(format!("Qualtrics request failed with status {status}")),
+    }
+    let body = response
+        .json::<Value>()
+        .map_err(|e| format!("Unable to parse Qualtrics response: {e}"))?;
+    let qsf = body
+        .get("result")
+        .filter(|result| result.get("SurveyElements").is_some())
+        .cloned()
+        .ok_or_else(|| {
+            "Qualtrics response does not contain a QSF survey definition.".to_string()
+        })?;
+    Ok(qsf)
+}
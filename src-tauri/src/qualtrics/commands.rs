@@ -0,0 +1,258 @@
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::fs;
+use std::path::Path;
+use tauri::AppHandle;
+
+use super::client::{fetch_survey_qsf, validate_survey_id};
+use super::settings::{
+    load_qualtrics_settings, project_token, save_qualtrics_settings_for, QualtricsSettings,
+};
+use crate::store::activity::record_activity;
+use crate::store::files::{import_files, ImportDestination};
+use crate::store::storage::app_data_root;
+use crate::store::{read_projects_store, resolve_study_root};
+
+/// Canonical study folder fetched surveys are written to.
+const QSF_FOLDER: &str = "02_build";
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FetchQsfArgs {
+    project_id: String,
+    study_id: String,
+    survey_id: String,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FetchedQsf {
+    /// Absolute path of the written `.qsf`, as listed by `list_build_assets`.
+    pub path: String,
+    /// Project-relative path registered on the study.
+    pub relative_path: String,
+    pub survey_id: String,
+    pub survey_name: Option<String>,
+}
+
+#[tauri::command]
+pub fn qualtrics_get_settings(app: AppHandle) -> Result<QualtricsSettings, String> {
+    load_qualtrics_settings(&app_data_root(&app)?)
+}
+
+/// An entered API token is stored in `project_id`'s secrets store, not in the settings file.
+#[tauri::command]
+pub fn qualtrics_save_settings(
+    app: AppHandle,
+    settings: QualtricsSettings,
+    project_id: Option<String>,
+) -> Result<QualtricsSettings, String> {
+    save_qualtrics_settings_for(&app_data_root(&app)?, settings, project_id.as_deref())
+}
+
+/// Runs off the main thread so the request and any rate-limit wait do not freeze the UI.
+#[tauri::command(async)]
+pub fn fetch_qsf_from_qualtrics(app: AppHandle, args: FetchQsfArgs) -> Result<FetchedQsf, String> {
+    let app_root = app_data_root(&app)?;
+    let settings = load_qualtrics_settings(&app_root)?;
+    let token = project_token(&app_root, &args.project_id)?;
+    fetch_qsf_at(&app_root, &settings.base_url()?, &token, args)
+}
+
+/// Fetches the survey from `base_url`, writes it to `02_build/<survey_id>_<timestamp>.qsf`
+/// and registers it on the study so it shows up as a build asset.
+pub(crate) fn fetch_qsf_at(
+    app_root: &Path,
+    base_url: &str,
+    token: &str,
+    args: FetchQsfArgs,
+) -> Result<FetchedQsf, String> {
+    let survey_id = validate_survey_id(&args.survey_id)?.to_string();
+    let store = read_projects_store(app_root)?;
+    let project = store
+        .projects
+        .iter()
+        .find(|project| project.id == args.project_id)
+        .ok_or_else(|| "Project not found.".to_string())?;
+    let study_root = project
+        .studies
+        .iter()
+        .find(|study| study.id == args.study_id)
+        .map(|study| resolve_study_root(project, study))
+        .ok_or_else(|| "Study not found.".to_string())?;
+
+    let qsf = fetch_survey_qsf(base_url, token, &survey_id)?;
+    let survey_name = qsf
+        .pointer("/SurveyEntry/SurveyName")
+        .and_then(Value::as_str)
+        .map(str::to_string);
+
+    let dir = study_root.join(QSF_FOLDER);
+    fs::create_dir_all(&dir).map_err(|e| e.to_string())?;
+    let stamp = Utc::now().format("%Y%m%dT%H%M%SZ");
+    let path = dir.join(format!("{survey_id}_{stamp}.qsf"));
+    let payload = serde_json::to_string_pretty(&qsf).map_err(|e| e.to_string())?;
+    fs::write(&path, payload).map_err(|e| format!("Unable to write {}: {e}", path.display()))?;
+
+    let report = import_files(
+        app_root,
+        args.project_id.clone(),
+        args.study_id.clone(),
+        vec![path.to_string_lossy().to_string()],
+        false,
+        Some(ImportDestination::canonical(QSF_FOLDER)),
+    )?;
+    let relative_path = report
+        .results
+        .into_iter()
+        .find(|result| result.status == "imported")
+        .and_then(|result| result.path)
+        .ok_or_else(|| format!("Unable to register {}", path.display()))?;
+    record_activity(
+        app_root,
+        "fetch_qsf_from_qualtrics",
+        Some(&args.project_id),
+        Some(&args.study_id),
+        &format!("Fetched QSF for {survey_id} from Qualtrics"),
+    );
+    Ok(FetchedQsf {
+        path: path.to_string_lossy().to_string(),
+        relative_path,
+        survey_id,
+        survey_name,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commands::assets::list_build_assets_at;
+    use crate::store::projects::{add_study, create_project};
+    use std::io::{Read, Write};
+    use std::net::TcpListener;
+    use std::sync::mpsc;
+    use std::thread;
+    use uuid::Uuid;
+
+    /// Serves one canned `(status line, extra headers, body)` per connection and sends each
+    /// request head back through the returned channel.
+    fn serve(
+        responses: Vec<(&'static str, &'static str, String)>,
+    ) -> (String, mpsc::Receiver<String>) {
+        let listener = TcpListener::bind("127.0.0.1:0").expect("bind");
+        let base_url = format!("http://{}", listener.local_addr().expect("addr"));
+        let (tx, rx) = mpsc::channel();
+        thread::spawn(move || {
+            for (status, headers, body) in responses {
+                let (mut stream, _) = listener.accept().expect("accept");
+                let mut buf = [0u8; 4096];
+                let read = stream.read(&mut buf).unwrap_or(0);
+                let _ = tx.send(String::from_utf8_lossy(&buf[..read]).to_string());
+                let reply = format!(
+                    "HTTP/1.1 {status}\r\nContent-Type: application/json\r\n{headers}Content-Length: {}\r\nConnection: close\r\n\r\n{body}",
+                    body.len()
+                );
+                let _ = stream.write_all(reply.as_bytes());
+            }
+        });
+        (base_url, rx)
+    }
+
+    fn demo_study(base: &Path) -> (std::path::PathBuf, String) {
+        let app_root = base.join("app");
+        fs::create_dir_all(&app_root).expect("app root");
+        let project = create_project(
+            &app_root,
+            serde_json::from_value(serde_json::json!({
+                "name": "Demo",
+                "rootDir": base.to_string_lossy(),
+                "googleDriveUrl": null
+            }))
+            .expect("project args"),
+        )
+        .expect("project should be created");
+        add_study(
+            &app_root,
+            serde_json::from_value(serde_json::json!({
+                "projectId": project.id,
+                "folderName": "S-ABC123",
+                "title": null
+            }))
+            .expect("study args"),
+        )
+        .expect("study should be added");
+        (app_root, project.id)
+    }
+
+    fn args(project_id: &str) -> FetchQsfArgs {
+        serde_json::from_value(serde_json::json!({
+            "projectId": project_id,
+            "studyId": "S-ABC123",
+            "surveyId": "SV_abc123XYZ"
+        }))
+        .expect("fetch args")
+    }
+
+    #[test]
+    fn fetched_qsf_lands_in_build_folder_after_a_rate_limited_retry() {
+        let base = std::env::temp_dir().join(format!("qualtrics-fetch-{}", Uuid::new_v4()));
+        let (app_root, project_id) = demo_study(&base);
+        let body = serde_json::json!({
+            "result": {
+                "SurveyEntry": {"SurveyID": "SV_abc123XYZ", "SurveyName": "Wellbeing"},
+                "SurveyElements": []
+            },
+            "meta": {"httpStatus": "200 - OK"}
+        })
+        .to_string();
+        let (base_url, requests) = serve(vec![
+            (
+                "429 Too Many Requests",
+                "Retry-After: 0\r\n",
+                "{}".to_string(),
+            ),
+            ("200 OK", "", body),
+        ]);
+
+        let fetched =
+            fetch_qsf_at(&app_root, &base_url, "secret-token", args(&project_id)).expect("fetch");
+        let first = requests.recv().expect("first request");
+        assert!(first.starts_with("GET /API/v3/survey-definitions/SV_abc123XYZ?format=qsf "));
+        assert!(first.to_lowercase().contains("x-api-token: secret-token"));
+        assert!(requests.recv().is_ok(), "429 should be retried once");
+
+        assert_eq!(fetched.survey_name.as_deref(), Some("Wellbeing"));
+        assert!(fetched
+            .relative_path
+            .starts_with("studies/S-ABC123/02_build/SV_abc123XYZ_"));
+        let written: Value =
+            serde_json::from_str(&fs::read_to_string(&fetched.path).expect("read qsf"))
+                .expect("qsf json");
+        assert_eq!(written["SurveyEntry"]["SurveyName"], "Wellbeing");
+
+        let assets = list_build_assets_at(&app_root, &project_id, "S-ABC123").expect("assets");
+        assert_eq!(assets.len(), 1);
+        assert_eq!(assets[0].path, fetched.path);
+        let _ = fs::remove_dir_all(base);
+    }
+
+    #[test]
+    fn rejected_tokens_report_a_clear_error_and_write_nothing() {
+        let base = std::env::temp_dir().join(format!("qualtrics-denied-{}", Uuid::new_v4()));
+        let (app_root, project_id) = demo_study(&base);
+        let (base_url, _requests) = serve(vec![(
+            "401 Unauthorized",
+            "",
+            r#"{"meta":{"httpStatus":"401 - Unauthorized"}}"#.to_string(),
+        )]);
+
+        let err = fetch_qsf_at(&app_root, &base_url, "stale", args(&project_id))
+            .expect_err("401 should fail");
+        assert!(err.contains("check your API token"), "{err}");
+        assert!(list_build_assets_at(&app_root, &project_id, "S-ABC123")
+            .expect("assets")
+            .is_empty());
+        let _ = fs::remove_dir_all(base);
+    }
+}
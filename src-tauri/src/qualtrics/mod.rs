@@ -0,0 +1,3 @@
+pub mod client;
+pub mod commands;
+pub mod settings;
@@ -0,0 +1,192 @@
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::store::secrets::{get_secret_at, project_root_for, set_secret_at};
+
+/// Project secret holding the Qualtrics API token.
+pub const QUALTRICS_TOKEN_SECRET: &str = "QUALTRICS_API_TOKEN";
+
+/// Qualtrics account settings. Only the datacenter is written to `qualtrics.json`; the API
+/// token lives in each project's encrypted secrets store under `QUALTRICS_TOKEN_SECRET`.
+#[derive(Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct QualtricsSettings {
+    /// Datacenter ID from Account Settings > Qualtrics IDs, e.g. `iad1` or `fra1`.
+    #[serde(default)]
+    pub datacenter: String,
+    /// Accepted from the settings form, and read from files written before the token moved
+    /// to the secrets store; never written back or returned.
+    #[serde(default, skip_serializing)]
+    pub api_token: Option<String>,
+}
+
+impl fmt::Debug for QualtricsSettings {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("QualtricsSettings")
+            .field("datacenter", &self.datacenter)
+            .field("api_token", &self.api_token.as_ref().map(|_| "<redacted>"))
+            .finish()
+    }
+}
+
+impl QualtricsSettings {
+    /// `https://{datacenter}.qualtrics.com`, after checking the datacenter is a bare host label.
+    pub fn base_url(&self) -> Result<String, String> {
+        let datacenter = self.datacenter.trim();
+        if datacenter.is_empty() {
+            return Err("Set your Qualtrics datacenter ID first.".to_string());
+        }
+        if !datacenter
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '.')
+        {
+            return Err(format!("Invalid Qualtrics datacenter ID: {datacenter}"));
+        }
+        Ok(format!("https://{datacenter}.qualtrics.com"))
+    }
+
+    /// The token entered in the settings form, if any.
+    fn entered_token(&self) -> Option<&str> {
+        self.api_token
+            .as_deref()
+            .map(str::trim)
+            .filter(|token| !token.is_empty())
+    }
+}
+
+pub fn settings_path(app_root: &Path) -> PathBuf {
+    app_root.join("settings").join("qualtrics.json")
+}
+
+pub fn load_qualtrics_settings(app_root: &Path) -> Result<QualtricsSettings, String> {
+    let path = settings_path(app_root);
+    if !path.exists() {
+        return Ok(QualtricsSettings::default());
+    }
+    let raw =
+        fs::read_to_string(&path).map_err(|e| format!("Unable to read {}: {e}", path.display()))?;
+    if raw.trim().is_empty() {
+        return Ok(QualtricsSettings::default());
+    }
+    serde_json::from_str(&raw).map_err(|e| format!("Invalid qualtrics settings JSON: {e}"))
+}
+
+pub fn save_qualtrics_settings(
+    app_root: &Path,
+    settings: &QualtricsSettings,
+) -> Result<(), String> {
+    let path = settings_path(app_root);
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+    }
+    let payload = serde_json::to_string_pretty(settings).map_err(|e| e.to_string())?;
+    fs::write(&path, payload).map_err(|e| format!("Unable to write {}: {e}", path.display()))
+}
+
+/// Saves the datacenter and, when one was entered, stores the token in the project's secrets
+/// store (which must be unlocked). Returns the saved settings without the token.
+pub fn save_qualtrics_settings_for(
+    app_root: &Path,
+    settings: QualtricsSettings,
+    project_id: Option<&str>,
+) -> Result<QualtricsSettings, String> {
+    if let Some(token) = settings.entered_token() {
+        let project_id = project_id
+            .ok_or_else(|| "Choose a project to store the Qualtrics API token in.".to_string())?;
+        set_secret_at(
+            &project_root_for(app_root, project_id)?,
+            QUALTRICS_TOKEN_SECRET,
+            token,
+        )?;
+    }
+    save_qualtrics_settings(app_root, &settings)?;
+    Ok(QualtricsSettings {
+        api_token: None,
+        ..settings
+    })
+}
+
+/// The project's Qualtrics token from its secrets store. A token an older version left in
+/// `qualtrics.json` is moved into the store on first use.
+pub fn project_token(app_root: &Path, project_id: &str) -> Result<String, String> {
+    let project_root = project_root_for(app_root, project_id)?;
+    if let Some(token) = get_secret_at(&project_root, QUALTRICS_TOKEN_SECRET)? {
+        return Ok(token);
+    }
+    let legacy = load_qualtrics_settings(app_root)?;
+    let token = legacy
+        .entered_token()
+        .map(str::to_string)
+        .ok_or_else(|| "Set your Qualtrics API token first.".to_string())?;
+    set_secret_at(&project_root, QUALTRICS_TOKEN_SECRET, &token)?;
+    save_qualtrics_settings(app_root, &legacy)?;
+    Ok(token)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::store::projects::create_project;
+    use crate::store::secrets::unlock_secrets_at;
+    use uuid::Uuid;
+
+    #[test]
+    fn token_goes_to_the_project_secrets_store_and_never_to_disk_or_debug() {
+        let base = std::env::temp_dir().join(format!("qualtrics-settings-{}", Uuid::new_v4()));
+        let app_root = base.join("app");
+        fs::create_dir_all(&app_root).expect("app root");
+        let project = create_project(
+            &app_root,
+            serde_json::from_value(serde_json::json!({
+                "name": "Demo",
+                "rootDir": base.to_string_lossy(),
+                "googleDriveUrl": null
+            }))
+            .expect("project args"),
+        )
+        .expect("project should be created");
+        let settings = QualtricsSettings {
+            datacenter: "iad1".to_string(),
+            api_token: Some("super-secret-token".to_string()),
+        };
+        assert!(!format!("{settings:?}").contains("super-secret-token"));
+
+        // Locked secrets store: nothing is saved.
+        assert!(
+            save_qualtrics_settings_for(&app_root, settings.clone(), Some(&project.id)).is_err()
+        );
+        unlock_secrets_at(Path::new(&project.root_path), "passphrase").expect("unlock");
+        let saved =
+            save_qualtrics_settings_for(&app_root, settings, Some(&project.id)).expect("save");
+        assert_eq!(saved.api_token, None);
+        let raw = fs::read_to_string(settings_path(&app_root)).expect("settings file");
+        assert!(raw.contains("iad1") && !raw.contains("super-secret-token"));
+        assert_eq!(
+            project_token(&app_root, &project.id).expect("token"),
+            "super-secret-token"
+        );
+
+        // A plain-text token from an older settings file moves into the store.
+        fs::write(
+            settings_path(&app_root),
+            r#"{"datacenter":"iad1","apiToken":"legacy-token"}"#,
+        )
+        .expect("legacy settings");
+        let root = Path::new(&project.root_path);
+        crate::store::secrets::delete_secret_at(root, QUALTRICS_TOKEN_SECRET).expect("delete");
+        assert_eq!(
+            project_token(&app_root, &project.id).expect("legacy token"),
+            "legacy-token"
+        );
+        let raw = fs::read_to_string(settings_path(&app_root)).expect("settings file");
+        assert!(!raw.contains("legacy-token"));
+        assert_eq!(
+            get_secret_at(root, QUALTRICS_TOKEN_SECRET).expect("secret"),
+            Some("legacy-token".to_string())
+        );
+        crate::store::secrets::lock_secrets_at(root).expect("lock");
+        let _ = fs::remove_dir_all(base);
+    }
+}
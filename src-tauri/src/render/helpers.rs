@@ -1,6 +1,13 @@
 use std::fs;
 use std::path::{Path, PathBuf};
 
+use std::collections::{BTreeMap, HashMap};
+use tera::{Tera, Value};
+
+use crate::prereg::types::HypothesisSpec;
+use crate::spec::builder::sanitize_identifier;
+use crate::spec::types::DerivedVariableSpec;
+
 pub fn ensure_dir(path: &Path) -> Result<(), String> {
     fs::create_dir_all(path)
         .map_err(|e| format!("Unable to create directory {}: {e}", path.display()))
@@ -13,6 +20,80 @@ pub fn write_string(path: &Path, content: &str) -> Result<(), String> {
     fs::write(path, content).map_err(|e| format!("Unable to write {}: {e}", path.display()))
 }
 
+/// Writes every file to a hidden sibling first and only renames once all of them are staged,
+/// so a failed write leaves the previous set of files untouched.
+pub fn write_files_atomically(files: &[(PathBuf, String)]) -> Result<(), String> {
+    let mut staged: Vec<(PathBuf, &Path)> = Vec::new();
+    for (path, content) in files {
+        let name = path
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .ok_or_else(|| format!("Invalid output path {}", path.display()))?;
+        let staging = path.with_file_name(format!(".{name}.tmp"));
+        if let Err(err) = write_string(&staging, content) {
+            for (written, _) in &staged {
+                let _ = fs::remove_file(written);
+            }
+            return Err(err);
+        }
+        staged.push((staging, path));
+    }
+    for (staging, path) in staged {
+        fs::rename(&staging, path)
+            .map_err(|e| format!("Unable to write {}: {e}", path.display()))?;
+    }
+    Ok(())
+}
+
+/// Setup-chunk stanza for analyses pinned with renv: restore from `renv.lock` when it exists,
+/// otherwise start a project library next to the document.
+pub const RENV_SETUP_R: &str = r#"if (requireNamespace("renv", quietly = TRUE)) {
+  if (file.exists("renv.lock")) {
+    renv::restore(prompt = FALSE)
+  } else {
+    renv::init(bare = TRUE, restart = FALSE)
+  }
+} else {
+  message("Install renv to use the pinned package library: install.packages(\"renv\")")
+}
+"#;
+
+pub const RENV_SNAPSHOT_CHUNK: &str = r#"# Reproducibility
+
+```{r renv_snapshot, eval=FALSE}
+# After adding or updating packages, record their versions in renv.lock:
+renv::snapshot()
+```
+"#;
+
+const RENV_RPROFILE: &str = "source(\"renv/activate.R\")\n";
+
+// Stand-in until renv::init() or renv::restore() writes renv's own activate script.
+const RENV_ACTIVATE_R: &str = r#"local({
+  if (requireNamespace("renv", quietly = TRUE)) {
+    renv::load(getwd())
+  } else {
+    message("renv is not installed; packages load from the user library.")
+  }
+})
+"#;
+
+/// Writes `.Rprofile` and `renv/activate.R` into `dir` where missing and returns the files it
+/// created. Existing files, and `renv.lock` in particular, are never touched.
+pub fn write_renv_scaffolding(dir: &Path) -> Result<Vec<PathBuf>, String> {
+    let mut created = Vec::new();
+    for (path, content) in [
+        (dir.join(".Rprofile"), RENV_RPROFILE),
+        (dir.join("renv").join("activate.R"), RENV_ACTIVATE_R),
+    ] {
+        if !path.exists() {
+            write_string(&path, content)?;
+            created.push(path);
+        }
+    }
+    Ok(created)
+}
+
 pub fn analysis_paths(base: &Path) -> (PathBuf, PathBuf, PathBuf) {
     (
         base.join("analysis").join("spec.json"),
@@ -20,3 +101,212 @@ pub fn analysis_paths(base: &Path) -> (PathBuf, PathBuf, PathBuf) {
         base.join("analysis").join("analysis.R"),
     )
 }
+
+/// R reserved words, which are never syntactic names.
+const R_RESERVED: &[&str] = &[
+    "if",
+    "else",
+    "repeat",
+    "while",
+    "function",
+    "for",
+    "in",
+    "next",
+    "break",
+    "TRUE",
+    "FALSE",
+    "NULL",
+    "Inf",
+    "NaN",
+    "NA",
+    "NA_integer_",
+    "NA_real_",
+    "NA_character_",
+    "NA_complex_",
+];
+
+/// `value` as a double-quoted R string literal.
+pub fn r_string(value: &str) -> String {
+    let mut out = String::with_capacity(value.len() + 2);
+    out.push('"');
+    for ch in value.chars() {
+        match ch {
+            '\\' => out.push_str("\\\\"),
+            '"' => out.push_str("\\\""),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if c.is_control() => out.push_str(&format!("\\u{{{:04x}}}", c as u32)),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+/// A file path as an R string literal, with forward slashes so it also works on Windows.
+pub fn r_path(path: &str) -> String {
+    r_string(&path.replace('\\', "/"))
+}
+
+/// `name` as an R name, backquoted unless it is an ASCII syntactic name.
+pub fn r_name(name: &str) -> String {
+    let syntactic = name
+        .chars()
+        .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '.')
+        && name.chars().next().is_some_and(|c| c.is_ascii_alphabetic())
+        && !R_RESERVED.contains(&name);
+    if syntactic {
+        name.to_string()
+    } else {
+        format!("`{}`", name.replace('\\', "\\\\").replace('`', "\\`"))
+    }
+}
+
+fn r_string_vector(values: &[String]) -> String {
+    values
+        .iter()
+        .map(|value| r_string(value))
+        .collect::<Vec<String>>()
+        .join(", ")
+}
+
+fn filter_text(value: &Value, filter: &str) -> tera::Result<String> {
+    match value {
+        Value::String(text) => Ok(text.clone()),
+        Value::Number(number) => Ok(number.to_string()),
+        Value::Bool(flag) => Ok(flag.to_string()),
+        _ => Err(tera::Error::msg(format!(
+            "Filter `{filter}` expects a string, got {value}"
+        ))),
+    }
+}
+
+fn text_filter(
+    name: &'static str,
+    convert: fn(&str) -> String,
+) -> impl Fn(&Value, &HashMap<String, Value>) -> tera::Result<Value> {
+    move |value, _| Ok(Value::String(convert(&filter_text(value, name)?)))
+}
+
+/// Registers `r_string`, `r_name`, `r_path` and `snake` so templates quote spec fields the
+/// same way the hand-written renderers do.
+pub fn register_r_filters(tera: &mut Tera) {
+    tera.register_filter("r_string", text_filter("r_string", r_string));
+    tera.register_filter("r_name", text_filter("r_name", r_name));
+    tera.register_filter("r_path", text_filter("r_path", r_path));
+    tera.register_filter("snake", text_filter("snake", sanitize_identifier));
+}
+
+/// R statement coercing `column` to a factor with explicit `levels`, or a TODO
+/// comment listing what was observed when fewer than two levels are known.
+pub fn factor_coercion_r(column: &str, levels: &[String]) -> String {
+    let quoted = r_string_vector(levels);
+    if levels.len() < 2 {
+        let observed = if quoted.is_empty() {
+            "none".to_string()
+        } else {
+            quoted
+        };
+        return format!(
+            "# TODO: set factor levels for {column} explicitly (observed defaults: {observed})"
+        );
+    }
+    let name = r_name(column);
+    format!("df <- df %>% dplyr::mutate({name} = factor({name}, levels = c({quoted})))")
+}
+
+/// R assertion that `column` holds only `levels` (or NA); run before factor coercion, which
+/// would silently turn any other value into NA.
+pub fn factor_levels_assertion_r(column: &str, levels: &[String]) -> String {
+    format!(
+        "stopifnot({} = all(df${} %in% c({}, NA)))",
+        r_string(&format!(
+            "{column} has values outside its randomizer levels"
+        )),
+        r_name(column),
+        r_string_vector(levels)
+    )
+}
+
+/// Row mean over already-cleaned item columns; the definition of a fully resolved scale.
+pub fn scale_mean_r(columns: &[String]) -> String {
+    format!(
+        "rowMeans(dplyr::select(df, dplyr::all_of(c({}))), na.rm = TRUE)",
+        columns
+            .iter()
+            .map(|c| r_string(c))
+            .collect::<Vec<String>>()
+            .join(", ")
+    )
+}
+
+/// Scales whose items were resolved to columns, i.e. whose definition is the generated row
+/// mean.
+pub fn resolved_scales(derived: &[DerivedVariableSpec]) -> Vec<&DerivedVariableSpec> {
+    derived
+        .iter()
+        .filter(|d| {
+            d.derived_type == "scale"
+                && !d.depends_on.is_empty()
+                && d.definition == scale_mean_r(&d.depends_on)
+        })
+        .collect()
+}
+
+/// Resolved scales with enough items for an internal-consistency estimate.
+pub fn reliability_scales(derived: &[DerivedVariableSpec]) -> Vec<&DerivedVariableSpec> {
+    resolved_scales(derived)
+        .into_iter()
+        .filter(|d| d.depends_on.len() >= 2)
+        .collect()
+}
+
+/// Model id -> "H1: <hypothesis text>" for titling model chunks; a model linked to several
+/// hypotheses gets them joined with "; ".
+pub fn model_hypothesis_titles(hypotheses: &[HypothesisSpec]) -> BTreeMap<String, String> {
+    let mut out: BTreeMap<String, String> = BTreeMap::new();
+    for hypothesis in hypotheses {
+        let title = format!("{}: {}", hypothesis.id, hypothesis.text.replace('\n', " "));
+        for model_id in &hypothesis.linked_model_ids {
+            out.entry(model_id.clone())
+                .and_modify(|t| {
+                    t.push_str("; ");
+                    t.push_str(&title);
+                })
+                .or_insert_with(|| title.clone());
+        }
+    }
+    out
+}
+
+/// File extensions for model summary tables given a `model_table_format` of
+/// "html" (default), "docx" or "both".
+pub fn model_table_extensions(format: Option<&str>) -> Vec<&'static str> {
+    match format.map(|f| f.trim().to_ascii_lowercase()).as_deref() {
+        Some("docx") => vec!["docx"],
+        Some("both") => vec!["html", "docx"],
+        _ => vec!["html"],
+    }
+}
+
+pub const MODEL_TABLE_DOCX_R: &str = r#"save_model_table_docx <- function(models, output_path) {
+  ft <- modelsummary::modelsummary(
+    models,
+    estimate = "{estimate}{stars}",
+    statistic = "({std.error})",
+    stars = c("*" = .05, "**" = .01, "***" = .001),
+    output = "flextable"
+  )
+  ft <- flextable::font(ft, fontname = "Times New Roman", part = "all")
+  ft <- flextable::fontsize(ft, size = 12, part = "all")
+  ft <- flextable::border_remove(ft)
+  ft <- flextable::hline_top(ft, border = officer::fp_border(width = 1), part = "header")
+  ft <- flextable::hline_bottom(ft, border = officer::fp_border(width = 1), part = "header")
+  ft <- flextable::hline_bottom(ft, border = officer::fp_border(width = 1), part = "body")
+  ft <- flextable::bold(ft, part = "header")
+  ft <- flextable::autofit(ft)
+  flextable::save_as_docx(ft, path = output_path)
+  invisible(ft)
+}
+"#;
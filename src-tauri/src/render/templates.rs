@@ -1,14 +1,21 @@
+use serde::Serialize;
 use std::path::{Path, PathBuf};
 
 use tera::{Context, Tera};
 
-use crate::render::helpers::write_string;
+use crate::render::helpers::{
+    model_hypothesis_titles, model_table_extensions, r_path, register_r_filters,
+    reliability_scales, resolved_scales, write_files_atomically, write_renv_scaffolding,
+    MODEL_TABLE_DOCX_R, RENV_SETUP_R, RENV_SNAPSHOT_CHUNK,
+};
 use crate::spec::types::AnalysisSpec;
+use crate::template::models_manifest::{manifest_json, manifest_path, spec_models_manifest};
 
 const ORDERED_PARTIALS: &[&str] = &[
     "00_header.Rmd.tera",
     "01_packages.R.tera",
     "02_import_clean.R.tera",
+    "02b_reliability.R.tera",
     "03_main_models.R.tera",
     "04_robustness.R.tera",
     "05_exploratory.R.tera",
@@ -16,21 +23,112 @@ const ORDERED_PARTIALS: &[&str] = &[
     "99_appendix.R.tera",
 ];
 
+/// The default template set compiled into the binary, for installs that ship no templates folder.
+const EMBEDDED_TEMPLATE_SET: &str = "apa_v1";
+const EMBEDDED_PARTIALS: &[(&str, &str)] = &[
+    (
+        "00_header.Rmd.tera",
+        include_str!("../../../templates/analysis/apa_v1/00_header.Rmd.tera"),
+    ),
+    (
+        "01_packages.R.tera",
+        include_str!("../../../templates/analysis/apa_v1/01_packages.R.tera"),
+    ),
+    (
+        "02_import_clean.R.tera",
+        include_str!("../../../templates/analysis/apa_v1/02_import_clean.R.tera"),
+    ),
+    (
+        "02b_reliability.R.tera",
+        include_str!("../../../templates/analysis/apa_v1/02b_reliability.R.tera"),
+    ),
+    (
+        "03_main_models.R.tera",
+        include_str!("../../../templates/analysis/apa_v1/03_main_models.R.tera"),
+    ),
+    (
+        "04_robustness.R.tera",
+        include_str!("../../../templates/analysis/apa_v1/04_robustness.R.tera"),
+    ),
+    (
+        "05_exploratory.R.tera",
+        include_str!("../../../templates/analysis/apa_v1/05_exploratory.R.tera"),
+    ),
+    (
+        "06_tables_figures.R.tera",
+        include_str!("../../../templates/analysis/apa_v1/06_tables_figures.R.tera"),
+    ),
+    (
+        "99_appendix.R.tera",
+        include_str!("../../../templates/analysis/apa_v1/99_appendix.R.tera"),
+    ),
+];
+
+/// Where the partials of a render were loaded from; recorded in the render provenance.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "kind", content = "path", rename_all = "lowercase")]
+pub enum TemplateSource {
+    Embedded,
+    Path(PathBuf),
+}
+
+fn load_templates(
+    template_set: &str,
+    template_root: Option<&Path>,
+) -> Result<(Tera, TemplateSource), String> {
+    if let Some(root) = template_root {
+        let set_dir = root.join("analysis").join(template_set);
+        if set_dir.is_dir() {
+            let pattern = format!("{}/**/*", set_dir.display());
+            let mut tera = Tera::new(&pattern).map_err(|e| format!("Template load failed: {e}"))?;
+            register_r_filters(&mut tera);
+            return Ok((tera, TemplateSource::Path(set_dir)));
+        }
+    }
+    if template_set != EMBEDDED_TEMPLATE_SET {
+        return Err(format!(
+            "Template set '{template_set}' was not found and has no built-in copy."
+        ));
+    }
+    let mut tera = Tera::default();
+    tera.add_raw_templates(EMBEDDED_PARTIALS.iter().copied())
+        .map_err(|e| format!("Template load failed: {e}"))?;
+    register_r_filters(&mut tera);
+    Ok((tera, TemplateSource::Embedded))
+}
+
+/// Renders the spec with the partials under `template_root` when it holds the spec's template
+/// set, otherwise with the embedded default set.
 pub fn render_from_spec(
     spec: &AnalysisSpec,
-    template_root: &Path,
+    template_root: Option<&Path>,
     out_rmd: &Path,
     out_r: &Path,
-) -> Result<(), String> {
-    let pattern = format!(
-        "{}/analysis/{}/**/*",
-        template_root.display(),
-        spec.template_bindings.template_set
-    );
-    let tera = Tera::new(&pattern).map_err(|e| format!("Template load failed: {e}"))?;
+) -> Result<TemplateSource, String> {
+    let (tera, source) = load_templates(&spec.template_bindings.template_set, template_root)?;
 
     let mut ctx = Context::new();
     ctx.insert("spec", spec);
+    ctx.insert(
+        "model_table_extensions",
+        &model_table_extensions(spec.outputs.model_table_format.as_deref()),
+    );
+    ctx.insert("model_table_docx_helper", MODEL_TABLE_DOCX_R);
+    let derived = &spec.data_contract.derived_variables;
+    ctx.insert(
+        "resolved_scale_names",
+        &resolved_scales(derived)
+            .iter()
+            .map(|d| d.name.as_str())
+            .collect::<Vec<&str>>(),
+    );
+    ctx.insert("reliability_scales", &reliability_scales(derived));
+    ctx.insert(
+        "model_hypotheses",
+        &model_hypothesis_titles(&spec.hypotheses),
+    );
+    ctx.insert("renv_setup", RENV_SETUP_R);
+    ctx.insert("renv_snapshot_chunk", RENV_SNAPSHOT_CHUNK);
 
     let mut rendered = String::new();
     for partial in ORDERED_PARTIALS {
@@ -46,43 +144,144 @@ pub fn render_from_spec(
         rendered.push_str("\n\n");
     }
 
-    write_string(out_rmd, &rendered)?;
+    let rmd_name = out_rmd
+        .file_name()
+        .and_then(|name| name.to_str())
+        .unwrap_or("analysis.Rmd");
+    let helper = r_helper_script(spec, rmd_name);
+    let manifest = manifest_json(out_rmd, spec_models_manifest(spec))?;
+    write_files_atomically(&[
+        (out_rmd.to_path_buf(), rendered),
+        (out_r.to_path_buf(), helper),
+        (manifest_path(out_rmd), manifest),
+    ])?;
+    if spec.template_bindings.use_renv {
+        if let Some(dir) = out_rmd.parent() {
+            write_renv_scaffolding(dir)?;
+        }
+    }
+    Ok(source)
+}
+
+/// Directories the render writes into, relative to the Rmd: `*_dir` bindings themselves and
+/// the parent of every other file binding except the raw data input.
+fn output_dirs(spec: &AnalysisSpec) -> Vec<String> {
+    let mut dirs: Vec<String> = Vec::new();
+    for (key, path) in &spec.template_bindings.paths {
+        let dir = if key.ends_with("_dir") {
+            path.trim().to_string()
+        } else if key == "data_raw" {
+            continue;
+        } else {
+            match Path::new(path.trim()).parent() {
+                Some(parent) => parent.to_string_lossy().replace('\\', "/"),
+                None => continue,
+            }
+        };
+        if !dir.is_empty() && !dirs.contains(&dir) {
+            dirs.push(dir);
+        }
+    }
+    dirs
+}
+
+/// `analysis.R` written next to the rendered Rmd. It finds its own location, so
+/// `Rscript path/to/analysis.R [output_format]` works from any working directory.
+pub fn r_helper_script(spec: &AnalysisSpec, rmd_name: &str) -> String {
+    let mut out = String::new();
+    out.push_str("# Auto-generated helper script; re-rendering the spec overwrites this file.\n");
+    out.push_str(&format!(
+        "# Research Workflow {} | analysis_id: {}\n",
+        env!("CARGO_PKG_VERSION"),
+        spec.analysis_id
+    ));
+    out.push_str(
+        "# Usage: Rscript analysis.R [output_format], e.g. Rscript analysis.R word_document\n\n",
+    );
+
+    out.push_str("script_path <- local({\n");
+    out.push_str(
+        "  file_arg <- grep(\"^--file=\", commandArgs(trailingOnly = FALSE), value = TRUE)\n",
+    );
+    out.push_str("  if (length(file_arg) > 0) {\n");
+    out.push_str("    sub(\"^--file=\", \"\", file_arg[1])\n");
+    out.push_str("  } else if (!is.null(sys.frame(1)$ofile)) {\n");
+    out.push_str("    sys.frame(1)$ofile\n");
+    out.push_str("  } else {\n");
+    out.push_str(
+        "    stop(\"Run this script with Rscript or source() so its location can be detected.\")\n",
+    );
+    out.push_str("  }\n");
+    out.push_str("})\n");
+    out.push_str("script_dir <- dirname(normalizePath(script_path))\n\n");
+
+    out.push_str("args <- commandArgs(trailingOnly = TRUE)\n");
+    out.push_str("output_format <- if (length(args) > 0 && nzchar(args[1])) args[1] else NULL\n\n");
 
-    let mut r_helper = String::new();
-    r_helper.push_str("# Auto-generated helper script\n");
-    r_helper.push_str("rmarkdown::render('analysis/analysis.Rmd')\n");
-    write_string(out_r, &r_helper)?;
+    let dirs = output_dirs(spec);
+    if !dirs.is_empty() {
+        out.push_str("output_dirs <- c(\n");
+        out.push_str(
+            &dirs
+                .iter()
+                .map(|dir| format!("  {}", r_path(dir)))
+                .collect::<Vec<String>>()
+                .join(",\n"),
+        );
+        out.push_str("\n)\n");
+        out.push_str("for (dir in output_dirs) {\n");
+        out.push_str(
+            "  dir.create(file.path(script_dir, dir), recursive = TRUE, showWarnings = FALSE)\n",
+        );
+        out.push_str("}\n\n");
+    }
+
+    out.push_str("output_file <- rmarkdown::render(\n");
+    out.push_str(&format!("  file.path(script_dir, {}),\n", r_path(rmd_name)));
+    out.push_str("  output_format = output_format,\n");
+    out.push_str("  knit_root_dir = script_dir\n");
+    out.push_str(")\n");
+    out.push_str("cat(\"Rendered:\", output_file, \"\\n\")\n");
+    out
+}
 
-    Ok(())
+/// First `templates` folder holding analysis templates: the project's own override, then the
+/// working directory and its parent (the dev layout). `None` means the embedded set is used.
+fn find_template_root(project_root: Option<&Path>, cwd: &Path) -> Option<PathBuf> {
+    project_root
+        .map(|root| root.join("templates"))
+        .into_iter()
+        .chain([
+            cwd.join("templates"),
+            cwd.parent()
+                .map(|p| p.join("templates"))
+                .unwrap_or_else(|| cwd.join("templates")),
+        ])
+        .find(|candidate| candidate.join("analysis").is_dir())
 }
 
-pub fn template_root_from_cwd() -> Result<PathBuf, String> {
-    let cwd = std::env::current_dir().map_err(|e| e.to_string())?;
-    let local = cwd.join("templates");
-    if local.exists() {
-        return Ok(local);
-    }
-    let parent = cwd
-        .parent()
-        .map(|p| p.join("templates"))
-        .unwrap_or_else(|| cwd.join("templates"));
-    Ok(parent)
+pub fn template_root_from_cwd(project_root: Option<&Path>) -> Option<PathBuf> {
+    let cwd = std::env::current_dir().ok()?;
+    find_template_root(project_root, &cwd)
 }
 
 #[cfg(test)]
 mod tests {
-    use super::render_from_spec;
+    use super::{find_template_root, r_helper_script, render_from_spec, TemplateSource};
+    use crate::prereg::types::HypothesisSpec;
+    use crate::render::helpers::{
+        factor_coercion_r, factor_levels_assertion_r, r_name, scale_mean_r,
+    };
     use crate::spec::types::{
-        AnalysisSpec, DataContractSpec, InputRef, InputsSpec, ModelsSpec, OutputsSpec,
-        TemplateBindingsSpec,
+        AnalysisSpec, DataContractSpec, DerivedVariableSpec, ExclusionSpec, FactorLevelSpec,
+        InputRef, InputsSpec, ModelSpec, ModelsSpec, OutputsSpec, TemplateBindingsSpec,
     };
-    use std::collections::HashMap;
+    use std::collections::{BTreeMap, HashMap};
     use std::path::PathBuf;
     use uuid::Uuid;
 
-    #[test]
-    fn renders_rmd_with_style_sources() {
-        let spec = AnalysisSpec {
+    fn test_spec() -> AnalysisSpec {
+        AnalysisSpec {
             project_id: "p".to_string(),
             study_id: "s".to_string(),
             analysis_id: "a".to_string(),
@@ -98,12 +297,19 @@ mod tests {
             },
             data_contract: DataContractSpec {
                 source: "qualtrics_csv".to_string(),
-                id_columns: HashMap::new(),
+                id_columns: BTreeMap::new(),
                 expected_columns: vec![],
-                label_map: HashMap::new(),
+                label_map: BTreeMap::new(),
                 exclusions: vec![],
                 missingness: None,
                 derived_variables: vec![],
+                expected_values: Default::default(),
+                factor_levels: vec![],
+                randomized_levels: vec![],
+                indicator_columns: vec![],
+                free_text_columns: vec![],
+                columns: vec![],
+                value_labels: BTreeMap::new(),
             },
             variable_mappings: vec![],
             models: ModelsSpec {
@@ -111,26 +317,31 @@ mod tests {
                 exploratory: vec![],
                 robustness: vec![],
             },
+            hypotheses: vec![],
             outputs: OutputsSpec {
                 tables: vec![],
                 figures: vec![],
+                model_table_format: None,
             },
             template_bindings: TemplateBindingsSpec {
                 template_set: "apa_v1".to_string(),
                 style_profile: "apa_flextable_ggpubr".to_string(),
-                paths: HashMap::from([
+                paths: BTreeMap::from([
                     ("data_raw".to_string(), "x.csv".to_string()),
                     ("data_clean".to_string(), "y.csv".to_string()),
                     ("tables_dir".to_string(), "tables".to_string()),
                     ("figures_dir".to_string(), "figures".to_string()),
                 ]),
                 packages: vec!["tidyverse".to_string()],
+                use_renv: false,
             },
             model_provenance: None,
             model_lock: None,
             warnings: vec![],
-        };
+        }
+    }
 
+    fn render_to_string(spec: &AnalysisSpec) -> String {
         let root = std::env::current_dir().expect("cwd");
         let tmp = std::env::temp_dir().join(format!("render-test-{}", Uuid::new_v4()));
         std::fs::create_dir_all(&tmp).expect("tmp");
@@ -141,9 +352,403 @@ mod tests {
         } else {
             root.parent().expect("parent").join("templates")
         };
-        render_from_spec(&spec, &template_root, &out_rmd, &out_r).expect("render");
+        render_from_spec(spec, Some(&template_root), &out_rmd, &out_r).expect("render");
         let rendered = std::fs::read_to_string(&out_rmd).expect("read");
+        let _ = std::fs::remove_dir_all(tmp);
+        rendered
+    }
+
+    #[test]
+    fn renders_rmd_with_style_sources() {
+        let rendered = render_to_string(&test_spec());
         assert!(rendered.contains("source(\"styles/apa_flextable_ggpubr/style.R\")"));
+    }
+
+    #[test]
+    fn import_clean_emits_factor_levels_and_free_text_types() {
+        let mut spec = test_spec();
+        spec.data_contract.factor_levels = vec![FactorLevelSpec {
+            column: "condition".to_string(),
+            levels: vec!["control".to_string(), "treat".to_string()],
+            r_code: factor_coercion_r("condition", &["control".to_string(), "treat".to_string()]),
+        }];
+        spec.data_contract.value_labels = BTreeMap::from([(
+            "condition".to_string(),
+            BTreeMap::from([("1".to_string(), "Control \"A\"".to_string())]),
+        )]);
+        spec.data_contract.randomized_levels = vec![FactorLevelSpec {
+            column: "condition".to_string(),
+            levels: vec!["control".to_string(), "treat".to_string()],
+            r_code: factor_levels_assertion_r(
+                "condition",
+                &["control".to_string(), "treat".to_string()],
+            ),
+        }];
+        let rendered = render_to_string(&spec);
+        assert!(rendered.contains("factor(condition, levels = c(\"control\", \"treat\"))"));
+        let assertion = rendered
+            .find("stopifnot(\"condition has values outside")
+            .expect("level assertion");
+        assert!(assertion < rendered.find("factor(condition, levels").expect("coercion"));
+        assert!(rendered.contains("  condition = c(\"1\" = \"Control \\\"A\\\"\")\n"));
+        assert!(rendered.contains("raw <- readr::read_csv(paths$data_raw, show_col_types = FALSE)"));
+
+        spec.data_contract.free_text_columns = vec!["gender_4_TEXT".to_string()];
+        let rendered = render_to_string(&spec);
+        assert!(rendered.contains("    gender_4_TEXT = readr::col_character(),"));
+        assert!(!rendered.contains("Multi-select choices"));
+
+        spec.data_contract.indicator_columns =
+            vec!["platforms_1".to_string(), "platforms_2".to_string()];
+        let rendered = render_to_string(&spec);
+        assert!(rendered.contains(
+            "dplyr::across(dplyr::any_of(c(\"platforms_1\", \"platforms_2\")), ~ as.integer(!is.na(.x) & .x != \"\"))"
+        ));
+    }
+
+    /// Reads the R string literal at the start of `text`, undoing `\\`, `\"` and `\n`.
+    fn read_r_string(text: &str) -> String {
+        let mut chars = text.strip_prefix('"').expect("opening quote").chars();
+        let mut out = String::new();
+        while let Some(c) = chars.next() {
+            match c {
+                '"' => return out,
+                '\\' => match chars.next().expect("escape") {
+                    'n' => out.push('\n'),
+                    other => out.push(other),
+                },
+                c => out.push(c),
+            }
+        }
+        panic!("unterminated R string in {text}");
+    }
+
+    #[test]
+    fn spec_fields_are_interpolated_as_r_literals() {
+        let mut spec = test_spec();
+        spec.template_bindings.paths.insert(
+            "data_raw".to_string(),
+            "C:\\Users\\ana\\05_data\\raw \"final\".csv".to_string(),
+        );
+        spec.data_contract.free_text_columns = vec!["näive score".to_string()];
+        spec.data_contract.value_labels = BTreeMap::from([(
+            "näive score".to_string(),
+            BTreeMap::from([("1".to_string(), "Back\\slash \"quoted\"\nlabel".to_string())]),
+        )]);
+        let rendered = render_to_string(&spec);
+
+        let literal = |prefix: &str| {
+            let start = rendered.find(prefix).unwrap_or_else(|| panic!("{prefix}")) + prefix.len();
+            read_r_string(&rendered[start..])
+        };
+        assert_eq!(
+            literal("data_raw = "),
+            "C:/Users/ana/05_data/raw \"final\".csv"
+        );
+        assert_eq!(
+            literal("`näive score` = c(\"1\" = "),
+            "Back\\slash \"quoted\"\nlabel"
+        );
+        assert!(rendered.contains("    `näive score` = readr::col_character(),\n"));
+        assert_eq!(r_name("if"), "`if`");
+        assert_eq!(r_name("odd`name"), "`odd\\`name`");
+        assert_eq!(r_name("wb_1"), "wb_1");
+    }
+
+    #[test]
+    fn resolved_scales_get_row_means_and_reliability_chunks() {
+        let mut spec = test_spec();
+        let rendered = render_to_string(&spec);
+        assert!(!rendered.contains("psych::alpha"));
+
+        let items = vec!["wb_1".to_string(), "wb_2".to_string()];
+        spec.data_contract.derived_variables = vec![
+            DerivedVariableSpec {
+                name: "wellbeing_scale".to_string(),
+                derived_type: "scale".to_string(),
+                depends_on: items.clone(),
+                definition: scale_mean_r(&items),
+            },
+            DerivedVariableSpec {
+                name: "anxiety_scale".to_string(),
+                derived_type: "scale".to_string(),
+                depends_on: vec![],
+                definition: "rowMeans(cbind(/* items for anxiety */), na.rm = TRUE)".to_string(),
+            },
+        ];
+        let rendered = render_to_string(&spec);
+        assert!(rendered.contains(
+            "df <- df %>% dplyr::mutate(wellbeing_scale = rowMeans(dplyr::select(df, dplyr::all_of(c(\"wb_1\", \"wb_2\"))), na.rm = TRUE))"
+        ));
+        assert!(rendered.contains("# TODO: rowMeans(cbind(/* items for anxiety */)"));
+        assert!(rendered.contains("print(summary(df$wellbeing_scale))\n"));
+        assert!(!rendered.contains("summary(df$anxiety_scale)"));
+        assert!(rendered.contains("```{r reliability_1}"));
+        assert!(rendered.contains("psych::alpha(items, check.keys = FALSE, warnings = FALSE)"));
+        assert!(rendered.contains("reliability_rows[[\"wellbeing_scale\"]]"));
+        assert!(!rendered.contains("reliability_rows[[\"anxiety_scale\"]]"));
+        assert!(rendered.contains(
+            "save_apa_table(ft_apa(reliability_tbl), file.path(paths$tables_dir, \"reliability.docx\"))"
+        ));
+    }
+
+    #[test]
+    fn model_table_format_reaches_spec_template_context() {
+        let mut spec = test_spec();
+        spec.models.main = vec![ModelSpec {
+            id: "H1".to_string(),
+            family: "gaussian".to_string(),
+            dv: "wellbeing".to_string(),
+            iv: vec!["condition".to_string()],
+            controls: vec![],
+            interactions: vec![],
+            formula: "wellbeing ~ condition".to_string(),
+            unresolved_variables: vec![],
+            weight_var: None,
+        }];
+
+        let rendered = render_to_string(&spec);
+        assert!(rendered.contains("models_main[c(\"H1\")]"));
+        assert!(rendered.contains("\"models_wellbeing.html\""));
+        assert!(!rendered.contains("save_model_table_docx"));
+
+        spec.outputs.model_table_format = Some("docx".to_string());
+        let rendered = render_to_string(&spec);
+        assert!(rendered.contains("save_model_table_docx <- function"));
+        assert!(rendered.contains("\"models_wellbeing.docx\""));
+        assert!(!rendered.contains("\"models_wellbeing.html\""));
+
+        spec.outputs.model_table_format = Some("both".to_string());
+        let rendered = render_to_string(&spec);
+        assert!(rendered.contains("\"models_wellbeing.docx\""));
+        assert!(rendered.contains("\"models_wellbeing.html\""));
+    }
+
+    #[test]
+    fn exclusions_render_in_order_with_dropped_row_counts() {
+        let mut spec = test_spec();
+        spec.data_contract.exclusions = vec![
+            ExclusionSpec {
+                id: "exclusion_1".to_string(),
+                criterion: "finished in under 60 seconds".to_string(),
+                r_filter: "df <- df %>% dplyr::filter(duration_in_seconds >= 60)".to_string(),
+            },
+            ExclusionSpec {
+                id: "exclusion_2".to_string(),
+                criterion: "suspected bots".to_string(),
+                r_filter: "# TODO: apply exclusion: suspected bots".to_string(),
+            },
+        ];
+        let rendered = render_to_string(&spec);
+        assert!(rendered.contains(
+            "# exclusion_1: finished in under 60 seconds\n\
+             n_before <- nrow(df)\n\
+             df <- df %>% dplyr::filter(duration_in_seconds >= 60)\n\
+             cat(sprintf(\"%s: dropped %d of %d rows\\n\", \"exclusion_1\", n_before - nrow(df), n_before))\n"
+        ));
+        assert!(rendered
+            .contains("# exclusion_2: suspected bots\n# TODO: apply exclusion: suspected bots\n"));
+        assert_eq!(rendered.matches("n_before <- nrow(df)").count(), 1);
+    }
+
+    #[test]
+    fn model_family_selects_the_fitting_call() {
+        let mut spec = test_spec();
+        spec.models.main = vec![ModelSpec {
+            id: "H1".to_string(),
+            family: "gaussian".to_string(),
+            dv: "purchased".to_string(),
+            iv: vec!["condition".to_string()],
+            controls: vec![],
+            interactions: vec![],
+            formula: "purchased ~ condition".to_string(),
+            unresolved_variables: vec![],
+            weight_var: None,
+        }];
+        let rendered = render_to_string(&spec);
+        assert!(
+            rendered.contains("models_main[[\"H1\"]] <- lm(purchased ~ condition, data = df)\n")
+        );
+
+        spec.models.main[0].family = "binomial".to_string();
+        let rendered = render_to_string(&spec);
+        assert!(rendered.contains(
+            "models_main[[\"H1\"]] <- glm(purchased ~ condition, family = binomial(), data = dplyr::mutate(df, purchased = factor(purchased)))\n"
+        ));
+        assert!(!rendered.contains("<- lm("));
+
+        spec.models.main[0].family = "poisson".to_string();
+        assert!(render_to_string(&spec)
+            .contains("glm(purchased ~ condition, family = poisson(), data = df)"));
+    }
+
+    #[test]
+    fn linked_hypotheses_title_their_models() {
+        let mut spec = test_spec();
+        let model = |id: &str, dv: &str| ModelSpec {
+            id: id.to_string(),
+            family: "gaussian".to_string(),
+            dv: dv.to_string(),
+            iv: vec!["condition".to_string()],
+            controls: vec![],
+            interactions: vec![],
+            formula: format!("{dv} ~ condition"),
+            unresolved_variables: vec![],
+            weight_var: None,
+        };
+        spec.models.main = vec![model("main_1", "loneliness"), model("main_2", "wellbeing")];
+        spec.hypotheses = vec![HypothesisSpec {
+            id: "H1".to_string(),
+            text: "Gratitude lowers loneliness.".to_string(),
+            direction: Some("negative".to_string()),
+            linked_model_ids: vec!["main_1".to_string()],
+        }];
+
+        let rendered = render_to_string(&spec);
+        assert!(rendered.contains(
+            "# H1: Gratitude lowers loneliness.\nmodels_main[[\"main_1\"]] <- lm(loneliness ~ condition"
+        ));
+        assert_eq!(rendered.matches("# H1:").count(), 1);
+    }
+
+    #[test]
+    fn rendering_the_same_spec_twice_is_byte_identical() {
+        let mut spec = test_spec();
+        spec.template_bindings.packages = vec![
+            "tidyverse".to_string(),
+            "modelsummary".to_string(),
+            "car".to_string(),
+        ];
+        spec.data_contract.label_map = BTreeMap::from([
+            ("q2".to_string(), "Second".to_string()),
+            ("q1".to_string(), "First".to_string()),
+        ]);
+        assert_eq!(render_to_string(&spec), render_to_string(&spec));
+    }
+
+    #[test]
+    fn hash_map_insertion_order_does_not_change_output() {
+        let entries: Vec<(String, String)> = (0..32)
+            .map(|idx| (format!("q{idx}"), format!("Question {idx}")))
+            .collect();
+        let forward: HashMap<String, String> = entries.iter().cloned().collect();
+        let backward: HashMap<String, String> = entries.iter().rev().cloned().collect();
+
+        let mut first = test_spec();
+        first.data_contract.label_map = forward.into_iter().collect();
+        let mut paths: Vec<(String, String)> =
+            first.template_bindings.paths.clone().into_iter().collect();
+        paths.reverse();
+        let mut second = test_spec();
+        second.data_contract.label_map = backward.into_iter().collect();
+        second.template_bindings.paths = paths.into_iter().collect();
+
+        assert_eq!(render_to_string(&first), render_to_string(&second));
+        assert_eq!(
+            serde_json::to_string(&first).expect("json"),
+            serde_json::to_string(&second).expect("json")
+        );
+    }
+
+    #[test]
+    fn r_helper_renders_sibling_rmd_from_any_working_directory() {
+        let mut spec = test_spec();
+        spec.template_bindings.paths = BTreeMap::from([
+            ("data_raw".to_string(), "05_data/raw/data.csv".to_string()),
+            (
+                "data_clean".to_string(),
+                "05_data/clean/data_clean.csv".to_string(),
+            ),
+            ("tables_dir".to_string(), "07_outputs/tables".to_string()),
+            ("figures_dir".to_string(), "07_outputs/figures".to_string()),
+        ]);
+        let script = r_helper_script(&spec, "analysis.Rmd");
+
+        assert!(script.contains("commandArgs(trailingOnly = FALSE)"));
+        assert!(script.contains("script_dir <- dirname(normalizePath(script_path))"));
+        assert!(script.contains("file.path(script_dir, \"analysis.Rmd\")"));
+        assert!(script.contains("knit_root_dir = script_dir"));
+        assert!(!script.contains("'analysis/analysis.Rmd'"));
+        assert!(script.contains("output_format <- if (length(args) > 0"));
+        assert!(script.contains("  output_format = output_format,\n"));
+        assert!(script.contains("\"05_data/clean\""));
+        assert!(script.contains("\"07_outputs/figures\""));
+        assert!(script.contains("\"07_outputs/tables\""));
+        assert!(!script.contains("05_data/raw"));
+        assert!(script.contains("cat(\"Rendered:\", output_file"));
+    }
+
+    #[test]
+    fn r_helper_header_records_app_version_and_analysis_id() {
+        let mut spec = test_spec();
+        spec.analysis_id = "A-7F3K2Q".to_string();
+        let script = r_helper_script(&spec, "analysis.Rmd");
+        let header = script.lines().nth(1).expect("header line");
+        assert_eq!(
+            header,
+            format!(
+                "# Research Workflow {} | analysis_id: A-7F3K2Q",
+                env!("CARGO_PKG_VERSION")
+            )
+        );
+
+        let tmp = std::env::temp_dir().join(format!("render-helper-{}", Uuid::new_v4()));
+        std::fs::create_dir_all(&tmp).expect("tmp");
+        let root = std::env::current_dir().expect("cwd");
+        let template_root = if root.join("templates").exists() {
+            root.join("templates")
+        } else {
+            root.parent().expect("parent").join("templates")
+        };
+        let out_r = tmp.join("analysis.R");
+        render_from_spec(
+            &spec,
+            Some(&template_root),
+            &tmp.join("analysis.Rmd"),
+            &out_r,
+        )
+        .expect("render");
+        assert_eq!(std::fs::read_to_string(&out_r).expect("read"), script);
+        let _ = std::fs::remove_dir_all(tmp);
+    }
+
+    #[test]
+    fn missing_templates_folder_falls_back_to_embedded_set() {
+        let spec = test_spec();
+        let cwd = std::env::temp_dir().join(format!("render-no-templates-{}", Uuid::new_v4()));
+        let project = cwd.join("project");
+        std::fs::create_dir_all(project.join("templates")).expect("mkdir");
+        assert_eq!(find_template_root(Some(&project), &cwd), None);
+
+        let out_rmd = cwd.join("analysis.Rmd");
+        let source =
+            render_from_spec(&spec, None, &out_rmd, &cwd.join("analysis.R")).expect("render");
+        assert_eq!(source, TemplateSource::Embedded);
+        let embedded = std::fs::read_to_string(&out_rmd).expect("read");
+        assert_eq!(embedded, render_to_string(&spec));
+
+        std::fs::create_dir_all(project.join("templates").join("analysis").join("apa_v1"))
+            .expect("mkdir");
+        assert_eq!(
+            find_template_root(Some(&project), &cwd),
+            Some(project.join("templates"))
+        );
+        let _ = std::fs::remove_dir_all(cwd);
+    }
+
+    #[test]
+    fn use_renv_adds_restore_stanza_snapshot_chunk_and_scaffolding() {
+        let mut spec = test_spec();
+        assert!(!render_to_string(&spec).contains("renv"));
+        spec.template_bindings.use_renv = true;
+        let tmp = std::env::temp_dir().join(format!("render-renv-{}", Uuid::new_v4()));
+        let out_rmd = tmp.join("analysis.Rmd");
+        render_from_spec(&spec, None, &out_rmd, &tmp.join("analysis.R")).expect("render");
+        let rmd = std::fs::read_to_string(&out_rmd).expect("read");
+        assert!(rmd.contains("renv::restore(prompt = FALSE)"));
+        assert!(rmd.trim_end().ends_with("renv::snapshot()\n```"));
+        assert!(tmp.join(".Rprofile").exists());
+        assert!(tmp.join("renv").join("activate.R").exists());
         let _ = std::fs::remove_dir_all(tmp);
     }
 }
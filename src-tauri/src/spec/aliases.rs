@@ -0,0 +1,152 @@
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::store::now_string;
+
+/// A project-wide answer to "which column is this prereg variable?", learned from manual
+/// mapping resolutions and applied before fuzzy matching in later analyses.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct VariableAlias {
+    pub prereg_var: String,
+    pub column: String,
+    pub updated_at: String,
+}
+
+pub fn aliases_file_path(project_root: &Path) -> PathBuf {
+    project_root
+        .join(".researchapp")
+        .join("variable_aliases.json")
+}
+
+pub fn read_variable_aliases(project_root: &Path) -> Result<Vec<VariableAlias>, String> {
+    let path = aliases_file_path(project_root);
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+    let raw =
+        fs::read_to_string(&path).map_err(|e| format!("Unable to read {}: {e}", path.display()))?;
+    serde_json::from_str(&raw).map_err(|e| format!("Invalid {}: {e}", path.display()))
+}
+
+fn write_variable_aliases(project_root: &Path, aliases: &[VariableAlias]) -> Result<(), String> {
+    let path = aliases_file_path(project_root);
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+    }
+    let payload = serde_json::to_string_pretty(aliases).map_err(|e| e.to_string())?;
+    fs::write(&path, payload).map_err(|e| format!("Unable to write {}: {e}", path.display()))
+}
+
+/// Alias for `prereg_var`, matched case-insensitively.
+pub fn find_alias<'a>(aliases: &'a [VariableAlias], prereg_var: &str) -> Option<&'a VariableAlias> {
+    aliases
+        .iter()
+        .find(|alias| alias.prereg_var.eq_ignore_ascii_case(prereg_var.trim()))
+}
+
+/// Adds or replaces aliases; a prereg variable keeps a single alias whatever its casing.
+pub fn upsert_variable_aliases(
+    project_root: &Path,
+    entries: &[(String, String)],
+) -> Result<Vec<VariableAlias>, String> {
+    let mut aliases = read_variable_aliases(project_root)?;
+    for (prereg_var, column) in entries {
+        let (prereg_var, column) = (prereg_var.trim(), column.trim());
+        if prereg_var.is_empty() || column.is_empty() {
+            return Err("Aliases need both a prereg variable and a column.".to_string());
+        }
+        aliases.retain(|alias| !alias.prereg_var.eq_ignore_ascii_case(prereg_var));
+        aliases.push(VariableAlias {
+            prereg_var: prereg_var.to_string(),
+            column: column.to_string(),
+            updated_at: now_string(),
+        });
+    }
+    aliases.sort_by_key(|alias| alias.prereg_var.to_lowercase());
+    write_variable_aliases(project_root, &aliases)?;
+    Ok(aliases)
+}
+
+pub fn remove_variable_alias(
+    project_root: &Path,
+    prereg_var: &str,
+) -> Result<Vec<VariableAlias>, String> {
+    let mut aliases = read_variable_aliases(project_root)?;
+    let before = aliases.len();
+    aliases.retain(|alias| !alias.prereg_var.eq_ignore_ascii_case(prereg_var.trim()));
+    if aliases.len() == before {
+        return Err(format!("No alias is stored for '{}'.", prereg_var.trim()));
+    }
+    write_variable_aliases(project_root, &aliases)?;
+    Ok(aliases)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::prereg::types::PreregSpec;
+    use crate::qsf::parse::parse_qsf_json;
+    use crate::spec::builder::build_analysis_spec;
+    use crate::spec::types::ResolutionKind;
+    use uuid::Uuid;
+
+    #[test]
+    fn stored_alias_resolves_later_specs_case_insensitively() {
+        let project_root = std::env::temp_dir().join(format!("aliases-{}", Uuid::new_v4()));
+        upsert_variable_aliases(
+            &project_root,
+            &[("outcome".to_string(), "dv_old".to_string())],
+        )
+        .expect("add");
+        let aliases = upsert_variable_aliases(
+            &project_root,
+            &[("Outcome".to_string(), "dv_final_1".to_string())],
+        )
+        .expect("replace");
+        assert_eq!(aliases.len(), 1);
+        assert_eq!(read_variable_aliases(&project_root).expect("read"), aliases);
+
+        let qsf = parse_qsf_json(
+            r#"{"SurveyEntry":{"SurveyName":"T"},"SurveyElements":[
+              {"Element":"SQ","Payload":{"QuestionID":"QID1","DataExportTag":"dv_final_1","QuestionText":"Rating","QuestionType":{"Type":"TE"}}}
+            ]}"#,
+        )
+        .expect("qsf");
+        let mut prereg = PreregSpec::default();
+        prereg.variables.dv = vec!["OUTCOME".to_string()];
+        let spec = build_analysis_spec(
+            "p", "s", "a", "q", "p", "q", "p", &qsf, &prereg, "apa_v1", "apa", &aliases,
+        );
+        let mapping = &spec.variable_mappings[0];
+        assert_eq!(mapping.resolved_to.as_deref(), Some("dv_final_1"));
+        assert_eq!(mapping.resolution_kind, ResolutionKind::Alias);
+        assert_eq!(mapping.candidates[0].score, 1.0);
+        assert!(spec
+            .warnings
+            .iter()
+            .any(|w| w.code == "VARIABLE_ALIASES_APPLIED"));
+        let unaliased = build_analysis_spec(
+            "p",
+            "s",
+            "a",
+            "q",
+            "p",
+            "q",
+            "p",
+            &qsf,
+            &prereg,
+            "apa_v1",
+            "apa",
+            &[],
+        );
+        assert_eq!(unaliased.variable_mappings[0].resolved_to, None);
+
+        assert!(remove_variable_alias(&project_root, "outcome")
+            .expect("remove")
+            .is_empty());
+        assert!(remove_variable_alias(&project_root, "outcome").is_err());
+        let _ = fs::remove_dir_all(project_root);
+    }
+}
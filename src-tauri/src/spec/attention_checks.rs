@@ -0,0 +1,215 @@
+use regex::Regex;
+
+use crate::qsf::types::{QsfChoice, QsfQuestion, QsfSurveySpec};
+use crate::render::helpers::r_string;
+use crate::util::text::clean_names;
+
+use super::types::{WarningItem, WarningSeverity};
+
+pub const ATTENTION_CHECK_ASSUMED: &str = "ATTENTION_CHECK_ASSUMED";
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum CheckKind {
+    Attention,
+    Manipulation,
+}
+
+struct DetectedCheck<'a> {
+    question: &'a QsfQuestion,
+    column: String,
+    answer: Option<&'a QsfChoice>,
+}
+
+fn check_kind(question: &QsfQuestion) -> Option<CheckKind> {
+    let tag = Regex::new(r"(?i)(^|[^a-z])(attn|attention|imc|ac|instructed|bogus|trap)([^a-z]|$)")
+        .expect("regex");
+    let text = Regex::new(
+        r"(?i)attention check|paying attention|show (?:that )?you are (?:reading|paying)|instructed response",
+    )
+    .expect("regex");
+    if question.export_tag.to_lowercase().contains("manip") {
+        Some(CheckKind::Manipulation)
+    } else if tag.is_match(&question.export_tag) || text.is_match(&question.question_text) {
+        Some(CheckKind::Attention)
+    } else {
+        None
+    }
+}
+
+/// Kinds of check an exclusion criterion refers to; empty when it mentions neither.
+fn criterion_kinds(criterion: &str) -> Vec<CheckKind> {
+    let attention =
+        Regex::new(r"(?i)attention|\bimc\b|instructed.response|trap question").expect("regex");
+    let mut kinds = Vec::new();
+    if attention.is_match(criterion) {
+        kinds.push(CheckKind::Attention);
+    }
+    if criterion.to_lowercase().contains("manipulation") {
+        kinds.push(CheckKind::Manipulation);
+    }
+    kinds
+}
+
+/// The choice the question text tells respondents to pick ("Please select 'Strongly agree'").
+/// The longest matching label wins so "Strongly agree" beats "Agree".
+fn instructed_answer(question: &QsfQuestion) -> Option<&QsfChoice> {
+    question
+        .choices
+        .iter()
+        .filter(|choice| !choice.label.trim().is_empty())
+        .filter(|choice| {
+            let pattern = format!(
+                r#"(?i)\b(?:select|choose|click|pick|mark|answer|respond with)\s+(?:the\s+)?(?:option|answer|response|number|choice)?\s*["“'‘]?{}["”'’]?(?:\W|$)"#,
+                regex::escape(choice.label.trim())
+            );
+            Regex::new(&pattern)
+                .map(|re| re.is_match(&question.question_text))
+                .unwrap_or(false)
+        })
+        .max_by_key(|choice| choice.label.trim().len())
+}
+
+pub fn r_value(value: &str) -> String {
+    if value.parse::<f64>().is_ok() {
+        value.to_string()
+    } else {
+        r_string(value)
+    }
+}
+
+fn detect_checks<'a>(qsf: &'a QsfSurveySpec, kinds: &[CheckKind]) -> Vec<DetectedCheck<'a>> {
+    qsf.questions
+        .iter()
+        .filter(|q| !qsf.is_renamed_duplicate(&q.export_tag))
+        .filter(|q| check_kind(q).is_some_and(|kind| kinds.contains(&kind)))
+        .map(|question| DetectedCheck {
+            question,
+            column: qsf
+                .columns
+                .iter()
+                .find(|c| c.name == question.export_tag)
+                .map(|c| c.clean_name.clone())
+                .unwrap_or_else(|| clean_names(&question.export_tag)),
+            answer: instructed_answer(question),
+        })
+        .collect()
+}
+
+/// Executable filter for an exclusion that refers to attention or manipulation checks, keeping
+/// respondents who picked the instructed answer on every detected check question. Each
+/// assumed answer is reported as an `ATTENTION_CHECK_ASSUMED` warning. `None` when the
+/// criterion is not about checks or the survey has no recognisable check question.
+pub fn attention_check_filter(
+    exclusion_id: &str,
+    criterion: &str,
+    qsf: &QsfSurveySpec,
+    warnings: &mut Vec<WarningItem>,
+) -> Option<String> {
+    let kinds = criterion_kinds(criterion);
+    if kinds.is_empty() {
+        return None;
+    }
+    let checks = detect_checks(qsf, &kinds);
+    if checks.is_empty() {
+        return None;
+    }
+
+    let mut lines = Vec::new();
+    let mut conditions = Vec::new();
+    for check in &checks {
+        let value = check.answer.map(|choice| choice.data_value().to_string());
+        match check.answer {
+            Some(choice) => {
+                lines.push(format!(
+                    "# Assumed correct answer for {}: {} (\"{}\"); verify against the survey.",
+                    check.column,
+                    choice.data_value(),
+                    choice.label.trim()
+                ));
+                conditions.push(format!(
+                    "{} == {}",
+                    check.column,
+                    r_value(choice.data_value())
+                ));
+            }
+            None => lines.push(format!(
+                "# TODO: set the correct answer for attention check {}.",
+                check.column
+            )),
+        }
+        warnings.push(WarningItem {
+            code: ATTENTION_CHECK_ASSUMED.to_string(),
+            message: match &value {
+                Some(value) => format!(
+                    "Exclusion {exclusion_id} keeps respondents who answered {value} on {}; verify this is the correct answer.",
+                    check.column
+                ),
+                None => format!(
+                    "Exclusion {exclusion_id} uses attention check {}, but its correct answer could not be identified.",
+                    check.column
+                ),
+            },
+            details: serde_json::json!({
+              "exclusionId": exclusion_id,
+              "column": check.column,
+              "questionText": check.question.question_text,
+              "value": value,
+              "label": check.answer.map(|choice| choice.label.trim()),
+            }),
+            severity: WarningSeverity::Warning,
+        });
+    }
+    if !conditions.is_empty() {
+        lines.push(format!(
+            "df <- df %>% dplyr::filter({})",
+            conditions.join(" & ")
+        ));
+    }
+    Some(lines.join("\n"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::qsf::parse::parse_qsf_json;
+
+    #[test]
+    fn instructed_answer_becomes_filter_and_assumption_warning() {
+        let raw = r#"{"SurveyEntry":{"SurveyName":"T"},"SurveyElements":[
+          {"Element":"SQ","Payload":{"QuestionID":"QID1","DataExportTag":"attn_check","QuestionText":"To show you are paying attention, please select 'Strongly agree'.","QuestionType":{"Type":"MC"},
+            "Choices":{"1":{"Display":"Strongly disagree"},"2":{"Display":"Disagree"},"3":{"Display":"Agree"},"4":{"Display":"Strongly agree"}}}},
+          {"Element":"SQ","Payload":{"QuestionID":"QID2","DataExportTag":"imc_1","QuestionText":"What is your favourite colour?","QuestionType":{"Type":"MC"},
+            "Choices":{"1":{"Display":"Red"},"2":{"Display":"Blue"}}}},
+          {"Element":"SQ","Payload":{"QuestionID":"QID3","DataExportTag":"wellbeing","QuestionText":"How are you?","QuestionType":{"Type":"MC"}}}
+        ]}"#;
+        let qsf = parse_qsf_json(raw).expect("parse");
+        let mut warnings = Vec::new();
+
+        let filter = attention_check_filter(
+            "E1",
+            "Exclude participants who fail the attention check",
+            &qsf,
+            &mut warnings,
+        )
+        .expect("filter");
+        assert_eq!(
+            filter,
+            "# Assumed correct answer for attn_check: 4 (\"Strongly agree\"); verify against the survey.\n\
+             # TODO: set the correct answer for attention check imc_1.\n\
+             df <- df %>% dplyr::filter(attn_check == 4)"
+        );
+        assert_eq!(warnings.len(), 2);
+        assert_eq!(warnings[0].code, ATTENTION_CHECK_ASSUMED);
+        assert_eq!(warnings[0].details["value"], "4");
+        assert!(warnings[0].details["questionText"]
+            .as_str()
+            .unwrap()
+            .contains("Strongly agree"));
+        assert!(warnings[1].details["value"].is_null());
+
+        assert!(
+            attention_check_filter("E2", "Duration under 120 seconds", &qsf, &mut warnings)
+                .is_none()
+        );
+    }
+}
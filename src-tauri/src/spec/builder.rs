@@ -1,14 +1,22 @@
-use std::collections::HashMap;
+use std::collections::BTreeMap;
 
-use crate::prereg::types::{AnalysisModelSpec, PreregSpec};
-use crate::qsf::types::QsfSurveySpec;
-use crate::spec::mapping::{map_variable, unresolved_warning};
-use crate::util::hash::sha256_hex;
+use crate::prereg::types::{AnalysisModelSpec, DerivedScale, PreregSpec};
+use crate::qsf::types::{QsfChoice, QsfSurveySpec};
+use crate::render::helpers::{
+    factor_coercion_r, factor_levels_assertion_r, reliability_scales, scale_mean_r,
+};
+use crate::spec::mapping::{map_variable, map_variable_with_aliases, unresolved_warning};
+use crate::spec::value_labels::qsf_value_labels;
+use crate::util::text::{clean_names, normalize_token};
 
+use super::aliases::VariableAlias;
+use super::exclusions::exclusion_filter;
 use super::types::{
-    AnalysisSpec, DataContractSpec, DerivedVariableSpec, ExclusionSpec, InputRef, InputsSpec,
-    MappingResult, ModelSpec, ModelsSpec, OutputsSpec, TemplateBindingsSpec, WarningItem,
+    AnalysisSpec, DataContractSpec, DerivedVariableSpec, ExclusionSpec, FactorLevelSpec, InputRef,
+    InputsSpec, MappingResult, ModelSpec, ModelsSpec, OutputsSpec, ResolutionKind,
+    TemplateBindingsSpec, WarningItem, WarningSeverity,
 };
+use super::warnings::default_severity;
 
 pub fn build_analysis_spec(
     project_id: &str,
@@ -16,59 +24,87 @@ pub fn build_analysis_spec(
     analysis_id: &str,
     qsf_path: &str,
     prereg_path: &str,
-    qsf_bytes: &[u8],
-    prereg_bytes: &[u8],
+    qsf_sha256: &str,
+    prereg_sha256: &str,
     qsf: &QsfSurveySpec,
     prereg: &PreregSpec,
     template_set: &str,
     style_profile: &str,
+    aliases: &[VariableAlias],
 ) -> AnalysisSpec {
-    let mappings = collect_mappings(qsf, prereg);
+    let mappings = collect_mappings(qsf, prereg, aliases);
     let mut warnings = collect_warnings(&mappings, prereg);
-    let auto_merge_derived = build_counterbalance_derived_variables(&mappings, qsf);
+    warnings.extend(alias_warning(&mappings));
+    warnings.extend(qsf.warnings.iter().map(|issue| WarningItem {
+        code: issue.code.clone(),
+        message: issue.message.clone(),
+        details: issue.details.clone(),
+        severity: default_severity(&issue.code),
+    }));
+    let auto_merge_derived = build_counterbalance_derived_variables(&mappings);
 
-    let data_contract = DataContractSpec {
+    let mut data_contract = DataContractSpec {
         source: "qualtrics_csv".to_string(),
-        id_columns: HashMap::from([
+        id_columns: BTreeMap::from([
             ("response_id".to_string(), "ResponseId".to_string()),
             ("participant_id".to_string(), "participant_id".to_string()),
         ]),
         expected_columns: qsf.expected_columns.clone(),
-        label_map: qsf.label_map.clone(),
+        label_map: qsf
+            .label_map
+            .iter()
+            .map(|(key, label)| (key.clone(), label.clone()))
+            .collect(),
         exclusions: prereg
             .exclusion_rules
             .iter()
             .map(|e| ExclusionSpec {
                 id: e.id.clone(),
                 criterion: e.criterion.clone(),
-                r_filter: format!("# TODO: apply exclusion: {}", e.criterion),
+                r_filter: exclusion_filter(e, qsf, &mut warnings),
             })
             .collect(),
         missingness: prereg.missing_data_plan.clone(),
         derived_variables: prereg
             .derived_scales
             .iter()
-            .map(|d| DerivedVariableSpec {
-                name: d.name.clone(),
-                derived_type: d.derived_type.clone(),
-                depends_on: d.depends_on.clone(),
-                definition: d.definition.clone(),
-            })
+            .map(|d| derived_scale_spec(d, qsf, &mut warnings))
             .chain(auto_merge_derived.into_iter())
             .collect(),
+        expected_values: collect_expected_values(qsf),
+        factor_levels: Vec::new(),
+        randomized_levels: collect_randomized_levels(qsf),
+        indicator_columns: qsf
+            .questions
+            .iter()
+            .filter(|q| q.multi_select && !qsf.is_renamed_duplicate(&q.export_tag))
+            .flat_map(|q| q.answer_columns())
+            .map(|column| clean_names(&column))
+            .collect(),
+        free_text_columns: qsf.text_entry_columns.clone(),
+        columns: qsf.columns.clone(),
+        value_labels: qsf_value_labels(qsf),
     };
 
-    let models = ModelsSpec {
+    let mut models = ModelsSpec {
         main: map_models(&prereg.main_analyses, &mappings),
         exploratory: map_models(&prereg.exploratory_analyses, &mappings),
         robustness: build_robustness_models(prereg, &mappings),
     };
+    infer_model_families(&mut models, prereg, qsf, &mut warnings);
+
+    data_contract.factor_levels = build_factor_levels(
+        &data_contract.expected_values,
+        &data_contract.randomized_levels,
+        &models.main,
+    );
 
     if models.main.is_empty() {
         warnings.push(WarningItem {
             code: "NO_MAIN_MODELS".to_string(),
             message: "No main models were extracted from prereg.".to_string(),
             details: serde_json::json!({}),
+            severity: WarningSeverity::Error,
         });
     }
 
@@ -83,12 +119,13 @@ pub fn build_analysis_spec(
             "box_by_condition".to_string(),
             "coefplots".to_string(),
         ],
+        model_table_format: None,
     };
 
-    let template_bindings = TemplateBindingsSpec {
+    let mut template_bindings = TemplateBindingsSpec {
         template_set: template_set.to_string(),
         style_profile: style_profile.to_string(),
-        paths: HashMap::from([
+        paths: BTreeMap::from([
             ("data_raw".to_string(), "05_data/raw/data.csv".to_string()),
             (
                 "data_clean".to_string(),
@@ -106,7 +143,11 @@ pub fn build_analysis_spec(
             "ggpubr".to_string(),
             "modelsummary".to_string(),
         ],
+        use_renv: false,
     };
+    if !reliability_scales(&data_contract.derived_variables).is_empty() {
+        template_bindings.packages.push("psych".to_string());
+    }
 
     AnalysisSpec {
         project_id: project_id.to_string(),
@@ -115,16 +156,17 @@ pub fn build_analysis_spec(
         inputs: InputsSpec {
             qsf: InputRef {
                 path: qsf_path.to_string(),
-                sha256: sha256_hex(qsf_bytes),
+                sha256: qsf_sha256.to_string(),
             },
             prereg: InputRef {
                 path: prereg_path.to_string(),
-                sha256: sha256_hex(prereg_bytes),
+                sha256: prereg_sha256.to_string(),
             },
         },
         data_contract,
         variable_mappings: mappings,
         models,
+        hypotheses: prereg.hypotheses.clone(),
         outputs,
         template_bindings,
         model_provenance: None,
@@ -133,14 +175,205 @@ pub fn build_analysis_spec(
     }
 }
 
-fn collect_mappings(qsf: &QsfSurveySpec, prereg: &PreregSpec) -> Vec<MappingResult> {
+/// Info note listing the mappings taken from the project alias dictionary.
+fn alias_warning(mappings: &[MappingResult]) -> Option<WarningItem> {
+    let aliased: Vec<serde_json::Value> = mappings
+        .iter()
+        .filter(|m| m.resolution_kind == ResolutionKind::Alias)
+        .map(|m| serde_json::json!({ "preregVar": m.prereg_var, "column": m.resolved_to }))
+        .collect();
+    if aliased.is_empty() {
+        return None;
+    }
+    Some(WarningItem {
+        code: "VARIABLE_ALIASES_APPLIED".to_string(),
+        message: format!(
+            "{} variable(s) were mapped from the project alias dictionary.",
+            aliased.len()
+        ),
+        details: serde_json::json!({ "aliases": aliased }),
+        severity: WarningSeverity::Info,
+    })
+}
+
+/// Answer choices that record a non-answer rather than a level of the outcome.
+fn is_substantive_choice(choice: &QsfChoice) -> bool {
+    let nonresponse = regex::Regex::new(
+        r"(?i)prefer not|rather not|don'?t know|do not know|not sure|unsure|^n/?a$|^other\b",
+    )
+    .expect("regex");
+    let label = choice.label.trim();
+    !choice.text_entry && !label.is_empty() && !nonresponse.is_match(label)
+}
+
+/// GLM family for `model` and the warning recording why. A multiple-choice DV with exactly
+/// two substantive choices is binomial; otherwise a family named in the prereg prose is used;
+/// otherwise gaussian, which needs no explanation.
+fn infer_family(
+    model: &ModelSpec,
+    hint: Option<&str>,
+    qsf: &QsfSurveySpec,
+) -> (&'static str, Option<WarningItem>) {
+    let question = qsf
+        .questions
+        .iter()
+        .filter(|q| q.question_type == "MC")
+        .find(|q| q.export_tag == model.dv || clean_names(&q.export_tag) == model.dv);
+    if let Some(question) = question {
+        let levels: Vec<&str> = question
+            .choices
+            .iter()
+            .filter(|choice| is_substantive_choice(choice))
+            .map(|choice| choice.label.trim())
+            .collect();
+        if levels.len() == 2 {
+            let warning = WarningItem {
+                code: "FAMILY_INFERRED".to_string(),
+                message: format!(
+                    "Model {} is fit with glm(family = binomial) because {} has two answer choices ({} / {}).",
+                    model.id, model.dv, levels[0], levels[1]
+                ),
+                details: serde_json::json!({
+                    "modelId": model.id,
+                    "dv": model.dv,
+                    "family": "binomial",
+                    "source": "qsf_choices",
+                    "question": qsf.label_map.get(&question.export_tag).unwrap_or(&question.question_text),
+                    "choices": levels,
+                }),
+                severity: default_severity("FAMILY_INFERRED"),
+            };
+            return ("binomial", Some(warning));
+        }
+    }
+    let family = match hint {
+        Some("poisson") => "poisson",
+        Some("binomial") => "binomial",
+        _ => return ("gaussian", None),
+    };
+    let warning = WarningItem {
+        code: "FAMILY_INFERRED".to_string(),
+        message: format!(
+            "Model {} is fit with glm(family = {family}) as described in the preregistration.",
+            model.id
+        ),
+        details: serde_json::json!({
+            "modelId": model.id,
+            "dv": model.dv,
+            "family": family,
+            "source": "prereg_text",
+        }),
+        severity: default_severity("FAMILY_INFERRED"),
+    };
+    (family, Some(warning))
+}
+
+/// Sets each model's family from its DV's survey question and the prereg family hints.
+/// Robustness variants (`<id>_with_controls`, ...) follow the hint of the model they vary.
+fn infer_model_families(
+    models: &mut ModelsSpec,
+    prereg: &PreregSpec,
+    qsf: &QsfSurveySpec,
+    warnings: &mut Vec<WarningItem>,
+) {
+    let hints: Vec<(&str, &str)> = prereg
+        .main_analyses
+        .iter()
+        .chain(prereg.exploratory_analyses.iter())
+        .filter_map(|m| Some((m.id.as_str(), m.family_hint.as_deref()?)))
+        .collect();
+    let hint_for = |id: &str| {
+        hints
+            .iter()
+            .filter(|(model_id, _)| id == *model_id || id.starts_with(&format!("{model_id}_")))
+            .max_by_key(|(model_id, _)| model_id.len())
+            .map(|(_, hint)| *hint)
+    };
+    for model in models.main.iter_mut().chain(models.exploratory.iter_mut()) {
+        let (family, warning) = infer_family(model, hint_for(&model.id), qsf);
+        model.family = family.to_string();
+        warnings.extend(warning);
+    }
+    for model in &mut models.robustness {
+        model.family = infer_family(model, hint_for(&model.id), qsf).0.to_string();
+    }
+}
+
+fn collect_mappings(
+    qsf: &QsfSurveySpec,
+    prereg: &PreregSpec,
+    aliases: &[VariableAlias],
+) -> Vec<MappingResult> {
     let mut vars = Vec::new();
     vars.extend(prereg.variables.dv.clone());
     vars.extend(prereg.variables.iv.clone());
     vars.extend(prereg.variables.controls.clone());
+    vars.extend(
+        prereg
+            .main_analyses
+            .iter()
+            .chain(prereg.exploratory_analyses.iter())
+            .filter_map(|m| m.weight_var.clone()),
+    );
     vars.sort();
     vars.dedup();
-    vars.into_iter().map(|v| map_variable(&v, qsf)).collect()
+    vars.into_iter()
+        .map(|v| map_variable_with_aliases(&v, qsf, aliases))
+        .collect()
+}
+
+fn collect_expected_values(qsf: &QsfSurveySpec) -> BTreeMap<String, Vec<String>> {
+    qsf.embedded_data_fields
+        .iter()
+        .filter(|f| !f.observed_values.is_empty())
+        .map(|f| (f.name.clone(), f.observed_values.clone()))
+        .collect()
+}
+
+fn collect_randomized_levels(qsf: &QsfSurveySpec) -> Vec<FactorLevelSpec> {
+    qsf.embedded_data_fields
+        .iter()
+        .filter(|f| f.is_randomized)
+        .map(|f| FactorLevelSpec {
+            column: f.name.clone(),
+            levels: f.possible_values.clone(),
+            r_code: factor_levels_assertion_r(&f.name, &f.possible_values),
+        })
+        .collect()
+}
+
+/// Explicit factor levels for embedded-data columns used as predictors, so R does
+/// not silently pick the alphabetically first value as the reference level. Randomizer
+/// levels win over values observed anywhere in the flow.
+fn build_factor_levels(
+    expected_values: &BTreeMap<String, Vec<String>>,
+    randomized_levels: &[FactorLevelSpec],
+    models: &[ModelSpec],
+) -> Vec<FactorLevelSpec> {
+    let mut out: Vec<FactorLevelSpec> = Vec::new();
+    for iv in models.iter().flat_map(|m| m.iv.iter()) {
+        if out.iter().any(|f| f.column.eq_ignore_ascii_case(iv)) {
+            continue;
+        }
+        let randomized = randomized_levels
+            .iter()
+            .find(|f| f.column.eq_ignore_ascii_case(iv))
+            .map(|f| f.levels.clone());
+        let Some(levels) = randomized.or_else(|| {
+            expected_values
+                .iter()
+                .find(|(column, _)| column.eq_ignore_ascii_case(iv))
+                .map(|(_, values)| values.clone())
+        }) else {
+            continue;
+        };
+        out.push(FactorLevelSpec {
+            column: iv.clone(),
+            r_code: factor_coercion_r(iv, &levels),
+            levels,
+        });
+    }
+    out
 }
 
 fn collect_warnings(mappings: &[MappingResult], prereg: &PreregSpec) -> Vec<WarningItem> {
@@ -149,6 +382,7 @@ fn collect_warnings(mappings: &[MappingResult], prereg: &PreregSpec) -> Vec<Warn
         code: w.clone(),
         message: w.clone(),
         details: serde_json::json!({}),
+        severity: default_severity(w),
     }));
     warnings
 }
@@ -166,7 +400,7 @@ fn resolved_or_todo(var: &str, mappings: &[MappingResult], unresolved: &mut Vec<
     format!("TODO_{}", sanitize_identifier(var))
 }
 
-fn sanitize_identifier(value: &str) -> String {
+pub(crate) fn sanitize_identifier(value: &str) -> String {
     let mut out = String::new();
     for ch in value.chars() {
         if ch.is_ascii_alphanumeric() {
@@ -198,6 +432,10 @@ fn map_models(models: &[AnalysisModelSpec], mappings: &[MappingResult]) -> Vec<M
                 .iter()
                 .map(|v| resolved_or_todo(v, mappings, &mut unresolved))
                 .collect::<Vec<String>>();
+            let weight_var = m
+                .weight_var
+                .as_ref()
+                .map(|v| resolved_or_todo(v, mappings, &mut unresolved));
             let rhs = iv
                 .iter()
                 .chain(controls.iter())
@@ -213,11 +451,67 @@ fn map_models(models: &[AnalysisModelSpec], mappings: &[MappingResult]) -> Vec<M
                 interactions: m.interaction_terms.clone(),
                 formula: format!("{} ~ {}", dv, rhs),
                 unresolved_variables: unresolved,
+                weight_var,
             }
         })
         .collect()
 }
 
+/// Recomputes model terms and formulas after mapping resolutions change. Each term is traced
+/// back to its prereg variable (via its `TODO_` token or the previous resolution) and then
+/// re-mapped through `map_models` with the current mappings.
+pub fn remap_models(
+    models: &ModelsSpec,
+    previous: &[MappingResult],
+    current: &[MappingResult],
+) -> ModelsSpec {
+    let remap = |specs: &[ModelSpec]| -> Vec<ModelSpec> {
+        specs
+            .iter()
+            .map(|model| {
+                let source = |term: &str| -> String {
+                    if term.starts_with("TODO_") {
+                        if let Some(var) = model
+                            .unresolved_variables
+                            .iter()
+                            .find(|var| format!("TODO_{}", sanitize_identifier(var)) == term)
+                        {
+                            return var.clone();
+                        }
+                    }
+                    previous
+                        .iter()
+                        .find(|m| m.resolved_to.as_deref() == Some(term))
+                        .map(|m| m.prereg_var.clone())
+                        .unwrap_or_else(|| term.to_string())
+                };
+                let prereg_model = AnalysisModelSpec {
+                    id: model.id.clone(),
+                    dv: source(&model.dv),
+                    iv: model.iv.iter().map(|v| source(v)).collect(),
+                    controls: model.controls.iter().map(|v| source(v)).collect(),
+                    interaction_terms: model.interactions.clone(),
+                    formula: None,
+                    weight_var: model.weight_var.as_deref().map(source),
+                    family_hint: None,
+                    dv_transform: None,
+                    predictor_transforms: Vec::new(),
+                };
+                let mut remapped = map_models(std::slice::from_ref(&prereg_model), current)
+                    .pop()
+                    .expect("one model in, one model out");
+                remapped.family = model.family.clone();
+                remapped
+            })
+            .collect()
+    };
+    ModelsSpec {
+        main: remap(&models.main),
+        exploratory: remap(&models.exploratory),
+        robustness: remap(&models.robustness),
+    }
+}
+
 fn build_robustness_models(prereg: &PreregSpec, mappings: &[MappingResult]) -> Vec<ModelSpec> {
     let mut out = map_models(&prereg.exploratory_analyses, mappings);
     if prereg
@@ -235,6 +529,7 @@ fn build_robustness_models(prereg: &PreregSpec, mappings: &[MappingResult]) -> V
                 interactions: main.interactions.clone(),
                 formula: main.formula.clone(),
                 unresolved_variables: main.unresolved_variables.clone(),
+                weight_var: main.weight_var.clone(),
             });
             out.push(ModelSpec {
                 id: format!("{}_without_controls", main.id),
@@ -245,104 +540,235 @@ fn build_robustness_models(prereg: &PreregSpec, mappings: &[MappingResult]) -> V
                 interactions: main.interactions.clone(),
                 formula: format!("{} ~ {}", main.dv, main.iv.join(" + ")),
                 unresolved_variables: main.unresolved_variables.clone(),
+                weight_var: main.weight_var.clone(),
             });
         }
     }
     out
 }
 
-fn build_counterbalance_derived_variables(
-    mappings: &[MappingResult],
-    qsf: &QsfSurveySpec,
-) -> Vec<DerivedVariableSpec> {
-    let expected = qsf
+/// Survey columns named after the scale's stem plus an item number (`trust_1`, `Trust2`, ...)
+/// in item order; `None` unless there are at least two.
+fn stem_item_columns(scale_name: &str, qsf: &QsfSurveySpec) -> Option<Vec<String>> {
+    let stem = normalize_token(scale_name.strip_suffix("_scale").unwrap_or(scale_name));
+    if stem.is_empty() {
+        return None;
+    }
+    let mut items: Vec<(u32, String)> = qsf
         .expected_columns
         .iter()
-        .map(|v| v.to_lowercase())
-        .collect::<Vec<String>>();
-    let mut out = Vec::new();
-    for m in mappings {
-        let Some(resolved) = &m.resolved_to else {
-            continue;
-        };
-        // This indicates map_variable auto-resolved to prereg var rather than a raw column.
-        if !resolved.eq_ignore_ascii_case(&m.prereg_var) {
-            continue;
-        }
-        if expected
-            .iter()
-            .any(|col| col.eq_ignore_ascii_case(resolved))
-        {
-            continue;
-        }
-        let sources = candidate_pair_sources(&m.candidates, &m.prereg_var);
-        if sources.len() < 2 {
-            continue;
+        .filter(|col| !qsf.is_meta_column(col) && !qsf.text_entry_columns.contains(col))
+        .filter_map(|col| {
+            let normalized = normalize_token(col);
+            let number = normalized.strip_prefix(&stem)?;
+            let number = number.strip_prefix('_').unwrap_or(number);
+            if number.is_empty() || !number.chars().all(|c| c.is_ascii_digit()) {
+                return None;
+            }
+            Some((number.parse().ok()?, col.clone()))
+        })
+        .collect();
+    items.sort();
+    (items.len() >= 2).then(|| items.into_iter().map(|(_, col)| col).collect())
+}
+
+/// Resolves a prereg scale's items to cleaned data columns. When every item maps to a QSF
+/// column the placeholder definition becomes a real row mean; otherwise it stays a TODO and a
+/// `SCALE_ITEMS_UNRESOLVED` warning lists the items that did not map. A scale listing no items
+/// takes the numbered columns sharing its stem, flagged with `SCALE_ITEMS_ASSUMED`.
+fn derived_scale_spec(
+    scale: &DerivedScale,
+    qsf: &QsfSurveySpec,
+    warnings: &mut Vec<WarningItem>,
+) -> DerivedVariableSpec {
+    let mut spec = DerivedVariableSpec {
+        name: scale.name.clone(),
+        derived_type: scale.derived_type.clone(),
+        depends_on: scale.depends_on.clone(),
+        definition: scale.definition.clone(),
+    };
+    if scale.derived_type != "scale" {
+        return spec;
+    }
+    if scale.depends_on.is_empty() {
+        if let Some(items) = stem_item_columns(&scale.name, qsf) {
+            warnings.push(WarningItem {
+                code: "SCALE_ITEMS_ASSUMED".to_string(),
+                message: format!(
+                    "Scale '{}' lists no items; assumed {} from the survey columns. Check they are the preregistered items.",
+                    scale.name,
+                    items.join(", ")
+                ),
+                details: serde_json::json!({
+                  "scale": scale.name,
+                  "columns": items,
+                }),
+                severity: WarningSeverity::Warning,
+            });
+            let columns = items.iter().map(|col| clean_names(col)).collect::<Vec<_>>();
+            spec.definition = scale_mean_r(&columns);
+            spec.depends_on = columns;
+            return spec;
         }
-        let definition = format!(
-            "dplyr::coalesce({})",
-            sources
+    }
+    let mut columns = Vec::new();
+    let mut unresolved = Vec::new();
+    for item in &scale.depends_on {
+        let column = map_variable(item, qsf).resolved_to.filter(|resolved| {
+            qsf.expected_columns
                 .iter()
-                .map(|s| format!("`{}`", s))
-                .collect::<Vec<String>>()
-                .join(", ")
-        );
-        out.push(DerivedVariableSpec {
-            name: resolved.clone(),
-            derived_type: "counterbalance_merge".to_string(),
-            depends_on: sources,
-            definition,
+                .any(|col| col.eq_ignore_ascii_case(resolved))
         });
-    }
-    out
-}
-
-fn candidate_pair_sources(
-    candidates: &[crate::spec::types::MappingCandidate],
-    prereg_var: &str,
-) -> Vec<String> {
-    let prereg_norm = crate::util::text::normalize_token(prereg_var);
-    let mut filtered = candidates
-        .iter()
-        .filter(|c| c.score >= 0.70)
-        .map(|c| c.key.clone())
-        .collect::<Vec<String>>();
-    filtered.sort();
-    filtered.dedup();
-    for i in 0..filtered.len() {
-        for j in (i + 1)..filtered.len() {
-            let a = &filtered[i];
-            let b = &filtered[j];
-            let a_norm = crate::util::text::normalize_token(a);
-            let b_norm = crate::util::text::normalize_token(b);
-            let a_base = strip_order_suffix(&a_norm);
-            let b_base = strip_order_suffix(&b_norm);
-            if a_base.is_empty() || a_base != b_base {
-                continue;
-            }
-            if a_base == prereg_norm
-                || a_base.contains(&prereg_norm)
-                || prereg_norm.contains(&a_base)
-            {
-                return vec![a.clone(), b.clone()];
-            }
+        match column {
+            Some(column) => columns.push(clean_names(&column)),
+            None => unresolved.push(item.clone()),
         }
     }
-    Vec::new()
+    if columns.is_empty() || !unresolved.is_empty() {
+        let message = if scale.depends_on.is_empty() {
+            format!(
+                "Scale '{}' lists no items; its definition is left as a TODO.",
+                scale.name
+            )
+        } else {
+            format!(
+                "Items of scale '{}' could not be matched to QSF columns; its definition is left as a TODO.",
+                scale.name
+            )
+        };
+        warnings.push(WarningItem {
+            code: "SCALE_ITEMS_UNRESOLVED".to_string(),
+            message,
+            details: serde_json::json!({
+              "scale": scale.name,
+              "unresolvedItems": unresolved,
+            }),
+            severity: WarningSeverity::Warning,
+        });
+        return spec;
+    }
+    spec.definition = scale_mean_r(&columns);
+    spec.depends_on = columns;
+    spec
 }
 
-fn strip_order_suffix(value: &str) -> String {
-    let re = regex::Regex::new(r"(?i)(?:_)?[ab]\d+$").expect("regex");
-    re.replace(value, "").to_string()
+/// Coalesced variables for mappings `map_variable` resolved to a counterbalanced column pair.
+fn build_counterbalance_derived_variables(mappings: &[MappingResult]) -> Vec<DerivedVariableSpec> {
+    mappings
+        .iter()
+        .filter(|m| {
+            m.resolution_kind == ResolutionKind::DerivedMerge && m.derived_sources.len() >= 2
+        })
+        .filter_map(|m| {
+            let name = m.resolved_to.clone()?;
+            let definition = format!(
+                "dplyr::coalesce({})",
+                m.derived_sources
+                    .iter()
+                    .map(|s| format!("`{}`", s))
+                    .collect::<Vec<String>>()
+                    .join(", ")
+            );
+            Some(DerivedVariableSpec {
+                name,
+                derived_type: "counterbalance_merge".to_string(),
+                depends_on: m.derived_sources.clone(),
+                definition,
+            })
+        })
+        .collect()
 }
 
 #[cfg(test)]
 mod tests {
     use super::build_analysis_spec;
-    use crate::prereg::types::{AnalysisModelSpec, PreregSpec};
-    use crate::qsf::types::{QsfQuestion, QsfSurveySpec};
+    use crate::prereg::extract::fill_from_text;
+    use crate::prereg::types::{AnalysisModelSpec, DerivedScale, ExclusionRule, PreregSpec};
+    use crate::qsf::normalize::build_spec;
+    use crate::qsf::parse::parse_qsf_json;
+    use crate::qsf::types::{QsfEmbeddedData, QsfQuestion, QsfSurveySpec, DUPLICATE_EXPORT_TAG};
+    use crate::spec::types::{MappingResult, ResolutionKind};
     use std::collections::HashMap;
 
+    /// Condition assigned as embedded data by a two-branch randomizer, one branch wrapped
+    /// in a group the way the Qualtrics flow editor exports it.
+    const RANDOMIZER_QSF: &str = r#"{
+      "SurveyEntry": {"SurveyName": "Randomized"},
+      "SurveyElements": [
+        {"Element":"SQ","Payload":{"QuestionID":"QID1","DataExportTag":"wellbeing","QuestionText":"How satisfied are you?","QuestionType":{"Type":"TE"}}},
+        {"Element":"SQ","Payload":{"QuestionID":"QID2","DataExportTag":"condition_check","QuestionText":"Which message did you read?","QuestionType":{"Type":"MC"}}},
+        {"Element":"FL","Payload":{"Flow":[
+          {"Type":"EmbeddedData","EmbeddedData":[{"Field":"version","Value":"control"},{"Field":"pid","Value":"${e://Field/PROLIFIC_PID}"}]},
+          {"Type":"Randomizer","SubSet":1,"EvenPresentation":true,"Flow":[
+            {"Type":"Group","Description":"Gain frame","Flow":[
+              {"Type":"EmbeddedData","EmbeddedData":[{"Field":"version","Value":"gain"}]},
+              {"Type":"Block","ID":"BL_1"}
+            ]},
+            {"Type":"EmbeddedData","EmbeddedData":[{"Field":"version","Value":"loss"}]}
+          ]}
+        ]}}
+      ]
+    }"#;
+
+    #[test]
+    fn randomized_field_resolves_condition_and_fixes_its_levels() {
+        let qsf = parse_qsf_json(RANDOMIZER_QSF).expect("parse qsf");
+        let mut prereg = PreregSpec::default();
+        prereg.variables.dv = vec!["wellbeing".to_string()];
+        prereg.variables.iv = vec!["condition".to_string()];
+        prereg.main_analyses.push(AnalysisModelSpec {
+            id: "H1".to_string(),
+            dv: "wellbeing".to_string(),
+            iv: vec!["condition".to_string()],
+            controls: vec![],
+            interaction_terms: vec![],
+            formula: None,
+            weight_var: None,
+            family_hint: None,
+            dv_transform: None,
+            predictor_transforms: Vec::new(),
+        });
+        let spec = build_analysis_spec(
+            "p",
+            "s",
+            "a",
+            "qsf",
+            "prereg",
+            "q",
+            "p",
+            &qsf,
+            &prereg,
+            "apa_v1",
+            "apa",
+            &[],
+        );
+        let mapping = spec
+            .variable_mappings
+            .iter()
+            .find(|m| m.prereg_var == "condition")
+            .expect("condition mapping");
+        assert_eq!(mapping.resolved_to.as_deref(), Some("version"));
+        assert_eq!(spec.models.main[0].iv, vec!["version".to_string()]);
+
+        // The pre-randomizer default is observed but is not a level the randomizer assigns.
+        let contract = &spec.data_contract;
+        assert_eq!(
+            contract.expected_values.get("version"),
+            Some(&vec![
+                "control".to_string(),
+                "gain".to_string(),
+                "loss".to_string()
+            ])
+        );
+        assert_eq!(contract.randomized_levels.len(), 1);
+        assert_eq!(contract.randomized_levels[0].levels, vec!["gain", "loss"]);
+        assert_eq!(
+            contract.randomized_levels[0].r_code,
+            "stopifnot(\"version has values outside its randomizer levels\" = all(df$version %in% c(\"gain\", \"loss\", NA)))"
+        );
+        assert_eq!(contract.factor_levels[0].levels, vec!["gain", "loss"]);
+    }
+
     #[test]
     fn builds_spec_and_emits_unresolved_warning() {
         let qsf = QsfSurveySpec {
@@ -353,11 +779,17 @@ mod tests {
                 question_text: "Known".to_string(),
                 question_type: "MC".to_string(),
                 choices: vec![],
+                matrix_stem: None,
+                loop_iteration: None,
+                multi_select: false,
             }],
             embedded_data: vec![],
             embedded_data_fields: vec![],
             expected_columns: vec!["known_x".to_string()],
             label_map: HashMap::new(),
+            text_entry_columns: vec![],
+            columns: vec![],
+            warnings: vec![],
         };
         let mut prereg = PreregSpec::default();
         prereg.variables.dv = vec!["missing_y".to_string()];
@@ -369,6 +801,10 @@ mod tests {
             controls: vec![],
             interaction_terms: vec![],
             formula: Some("missing_y ~ known_x".to_string()),
+            weight_var: None,
+            family_hint: None,
+            dv_transform: None,
+            predictor_transforms: Vec::new(),
         });
         let spec = build_analysis_spec(
             "p",
@@ -376,12 +812,13 @@ mod tests {
             "a",
             "qsf",
             "prereg",
-            b"q",
-            b"p",
+            "q",
+            "p",
             &qsf,
             &prereg,
             "apa_v1",
             "apa_flextable_ggpubr",
+            &[],
         );
         assert!(!spec.models.main.is_empty());
         assert!(spec
@@ -389,4 +826,553 @@ mod tests {
             .iter()
             .any(|w| w.code == "UNRESOLVED_VARIABLE"));
     }
+
+    #[test]
+    fn prereg_weight_is_mapped_and_unresolved_weight_is_reported() {
+        let raw = r#"{
+      "SurveyEntry": {"SurveyName": "T"},
+      "SurveyElements": [
+        {"Element":"SQ","Payload":{"QuestionID":"QID1","DataExportTag":"wellbeing","QuestionText":"Wellbeing","QuestionType":{"Type":"TE"}}},
+        {"Element":"SQ","Payload":{"QuestionID":"QID2","DataExportTag":"condition","QuestionText":"Condition","QuestionType":{"Type":"MC"}}}
+      ]
+    }"#;
+        let qsf = parse_qsf_json(raw).expect("parse qsf");
+        let mut prereg = PreregSpec::default();
+        fill_from_text(
+            &mut prereg,
+            "wellbeing ~ condition\nEstimates are weighted by poststrat_weight.\n",
+        );
+        assert_eq!(
+            prereg.main_analyses[0].weight_var.as_deref(),
+            Some("poststrat_weight")
+        );
+
+        let spec = build_analysis_spec(
+            "p",
+            "s",
+            "a",
+            "qsf",
+            "prereg",
+            "q",
+            "p",
+            &qsf,
+            &prereg,
+            "apa_v1",
+            "apa",
+            &[],
+        );
+        let model = &spec.models.main[0];
+        assert_eq!(model.weight_var.as_deref(), Some("TODO_poststrat_weight"));
+        assert!(model
+            .unresolved_variables
+            .contains(&"poststrat_weight".to_string()));
+        assert!(spec.warnings.iter().any(
+            |w| w.code == "UNRESOLVED_VARIABLE" && w.details["preregVar"] == "poststrat_weight"
+        ));
+    }
+
+    #[test]
+    fn same_formula_gets_family_from_dv_choices_and_prereg_prose() {
+        let qsf_with = |choices: &str| {
+            parse_qsf_json(&format!(
+                r#"{{"SurveyEntry":{{"SurveyName":"T"}},"SurveyElements":[
+                  {{"Element":"SQ","Payload":{{"QuestionID":"QID1","DataExportTag":"purchased","QuestionText":"Did you buy it?","QuestionType":{{"Type":"MC"}},"Choices":{choices}}}}},
+                  {{"Element":"SQ","Payload":{{"QuestionID":"QID2","DataExportTag":"condition","QuestionText":"Condition","QuestionType":{{"Type":"MC"}}}}}}
+                ]}}"#
+            ))
+            .expect("parse qsf")
+        };
+        let binary = qsf_with(
+            r#"{"1":{"Display":"Yes"},"2":{"Display":"No"},"3":{"Display":"Prefer not to say"}}"#,
+        );
+        let scale = qsf_with(
+            r#"{"1":{"Display":"Never"},"2":{"Display":"Once"},"3":{"Display":"Twice"},"4":{"Display":"More"}}"#,
+        );
+        let build = |text: &str, qsf: &QsfSurveySpec| {
+            let mut prereg = PreregSpec::default();
+            fill_from_text(&mut prereg, text);
+            build_analysis_spec(
+                "p",
+                "s",
+                "a",
+                "q",
+                "p",
+                "q",
+                "p",
+                qsf,
+                &prereg,
+                "apa_v1",
+                "apa",
+                &[],
+            )
+        };
+
+        let spec = build("purchased ~ condition\n", &binary);
+        assert_eq!(spec.models.main[0].formula, "purchased ~ condition");
+        assert_eq!(spec.models.main[0].family, "binomial");
+        let warning = spec
+            .warnings
+            .iter()
+            .find(|w| w.code == "FAMILY_INFERRED")
+            .expect("family warning");
+        assert_eq!(warning.details["source"], "qsf_choices");
+        assert_eq!(warning.details["choices"], serde_json::json!(["Yes", "No"]));
+        assert_eq!(warning.details["question"], "Did you buy it?");
+
+        let spec = build("purchased ~ condition\n", &scale);
+        assert_eq!(spec.models.main[0].formula, "purchased ~ condition");
+        assert_eq!(spec.models.main[0].family, "gaussian");
+        assert!(!spec.warnings.iter().any(|w| w.code == "FAMILY_INFERRED"));
+
+        let spec = build(
+            "purchased ~ condition\nWe fit a Poisson regression on the purchase counts.\n",
+            &scale,
+        );
+        assert_eq!(spec.models.main[0].family, "poisson");
+        assert!(spec
+            .warnings
+            .iter()
+            .any(|w| w.code == "FAMILY_INFERRED" && w.details["source"] == "prereg_text"));
+    }
+
+    #[test]
+    fn duplicate_export_tags_are_renamed_and_reported() {
+        let raw = r#"{
+      "SurveyEntry": {"SurveyName": "T"},
+      "SurveyElements": [
+        {"Element":"SQ","Payload":{"QuestionID":"QID1","DataExportTag":"wellbeing","QuestionText":"How satisfied are you?","QuestionType":{"Type":"TE"}}},
+        {"Element":"SQ","Payload":{"QuestionID":"QID9","DataExportTag":"wellbeing","QuestionText":"How satisfied are you (copy)?","QuestionType":{"Type":"TE"}}}
+      ]
+    }"#;
+        let qsf = parse_qsf_json(raw).expect("parse qsf");
+        assert_eq!(qsf.questions[0].export_tag, "wellbeing");
+        assert_eq!(qsf.questions[1].export_tag, "wellbeing.1");
+        assert!(qsf.expected_columns.iter().any(|c| c == "wellbeing.1"));
+        assert_eq!(
+            qsf.label_map.get("wellbeing").map(String::as_str),
+            Some("How satisfied are you?")
+        );
+        assert_eq!(qsf.warnings.len(), 1);
+        assert_eq!(qsf.warnings[0].code, DUPLICATE_EXPORT_TAG);
+        assert_eq!(
+            qsf.warnings[0].details["qids"],
+            serde_json::json!(["QID1", "QID9"])
+        );
+
+        let mut prereg = PreregSpec::default();
+        prereg.variables.dv = vec!["wellbeing".to_string()];
+        let spec = build_analysis_spec(
+            "p",
+            "s",
+            "a",
+            "qsf",
+            "prereg",
+            "q",
+            "p",
+            &qsf,
+            &prereg,
+            "apa_v1",
+            "apa",
+            &[],
+        );
+        let warning = spec
+            .warnings
+            .iter()
+            .find(|w| w.code == DUPLICATE_EXPORT_TAG)
+            .expect("duplicate tag warning");
+        assert!(warning.message.contains("QID1, QID9"));
+        assert_eq!(
+            warning.details["questionTexts"],
+            serde_json::json!(["How satisfied are you?", "How satisfied are you (copy)?"])
+        );
+        let mapping = &spec.variable_mappings[0];
+        assert_eq!(mapping.resolved_to.as_deref(), Some("wellbeing"));
+        assert!(mapping
+            .candidates
+            .iter()
+            .all(|c| !c.key.starts_with("wellbeing.")));
+    }
+
+    #[test]
+    fn embedded_condition_values_become_factor_levels() {
+        let qsf = QsfSurveySpec {
+            survey_name: "Survey".to_string(),
+            questions: vec![QsfQuestion {
+                qualtrics_qid: "QID1".to_string(),
+                export_tag: "wellbeing".to_string(),
+                question_text: "Wellbeing".to_string(),
+                question_type: "TE".to_string(),
+                choices: vec![],
+                matrix_stem: None,
+                loop_iteration: None,
+                multi_select: false,
+            }],
+            embedded_data: vec!["condition".to_string()],
+            embedded_data_fields: vec![QsfEmbeddedData {
+                name: "condition".to_string(),
+                default_value: None,
+                observed_values: vec!["control".to_string(), "treat".to_string()],
+                is_randomized: false,
+                possible_values: vec![],
+            }],
+            expected_columns: vec!["wellbeing".to_string(), "condition".to_string()],
+            label_map: HashMap::new(),
+            text_entry_columns: vec![],
+            columns: vec![],
+            warnings: vec![],
+        };
+        let mut prereg = PreregSpec::default();
+        prereg.variables.dv = vec!["wellbeing".to_string()];
+        prereg.variables.iv = vec!["condition".to_string()];
+        prereg.main_analyses.push(AnalysisModelSpec {
+            id: "H1".to_string(),
+            dv: "wellbeing".to_string(),
+            iv: vec!["condition".to_string()],
+            controls: vec![],
+            interaction_terms: vec![],
+            formula: None,
+            weight_var: None,
+            family_hint: None,
+            dv_transform: None,
+            predictor_transforms: Vec::new(),
+        });
+        let spec = build_analysis_spec(
+            "p",
+            "s",
+            "a",
+            "qsf",
+            "prereg",
+            "q",
+            "p",
+            &qsf,
+            &prereg,
+            "apa_v1",
+            "apa",
+            &[],
+        );
+        assert_eq!(
+            spec.data_contract.expected_values.get("condition"),
+            Some(&vec!["control".to_string(), "treat".to_string()])
+        );
+        assert_eq!(spec.data_contract.factor_levels.len(), 1);
+        assert_eq!(
+            spec.data_contract.factor_levels[0].r_code,
+            "df <- df %>% dplyr::mutate(condition = factor(condition, levels = c(\"control\", \"treat\")))"
+        );
+    }
+
+    #[test]
+    fn meta_columns_are_documented_and_duration_exclusion_uses_clean_name() {
+        let qsf = build_spec(
+            "Survey".to_string(),
+            vec![QsfQuestion {
+                qualtrics_qid: "QID1".to_string(),
+                export_tag: "wellbeing".to_string(),
+                question_text: "Wellbeing".to_string(),
+                question_type: "TE".to_string(),
+                choices: vec![],
+                matrix_stem: None,
+                loop_iteration: None,
+                multi_select: false,
+            }],
+            vec![],
+        );
+        let prereg = PreregSpec {
+            exclusion_rules: vec![
+                ExclusionRule {
+                    id: "exclusion_1".to_string(),
+                    rule_type: "filter".to_string(),
+                    variable: None,
+                    criterion: "participants who finish in under 2 minutes".to_string(),
+                },
+                ExclusionRule {
+                    id: "exclusion_2".to_string(),
+                    rule_type: "filter".to_string(),
+                    variable: None,
+                    criterion: "participants who fail the attention check".to_string(),
+                },
+            ],
+            ..PreregSpec::default()
+        };
+        let spec = build_analysis_spec(
+            "p",
+            "s",
+            "a",
+            "qsf",
+            "prereg",
+            "q",
+            "p",
+            &qsf,
+            &prereg,
+            "apa_v1",
+            "apa",
+            &[],
+        );
+        let duration = spec
+            .data_contract
+            .columns
+            .iter()
+            .find(|c| c.name == "Duration (in seconds)")
+            .expect("duration column");
+        assert!(duration.meta);
+        assert_eq!(duration.clean_name, "duration_in_seconds");
+        assert!(spec
+            .data_contract
+            .expected_columns
+            .iter()
+            .any(|c| c == "UserLanguage"));
+        assert!(spec
+            .data_contract
+            .columns
+            .iter()
+            .any(|c| c.name == "wellbeing" && !c.meta));
+        assert_eq!(
+            spec.data_contract.exclusions[0].r_filter,
+            "df <- df %>% dplyr::filter(duration_in_seconds >= 120)"
+        );
+        assert!(spec.data_contract.exclusions[1]
+            .r_filter
+            .starts_with("# TODO"));
+    }
+
+    #[test]
+    fn exclusion_prose_becomes_concrete_filters_on_clean_columns() {
+        let qsf = parse_qsf_json(
+            r#"{"SurveyEntry":{"SurveyName":"T"},"SurveyElements":[
+              {"Element":"SQ","Payload":{"QuestionID":"QID1","DataExportTag":"consent","QuestionText":"Do you agree to take part?","QuestionType":{"Type":"MC"},"Choices":{"1":{"Display":"I agree"},"2":{"Display":"I do not agree"}}}},
+              {"Element":"SQ","Payload":{"QuestionID":"QID2","DataExportTag":"wellbeing","QuestionText":"Wellbeing","QuestionType":{"Type":"TE"}}}
+            ]}"#,
+        )
+        .expect("parse qsf");
+        let mut prereg = PreregSpec::default();
+        fill_from_text(
+            &mut prereg,
+            "We will exclude participants who finished the survey in under 60 seconds. \
+             Participants who do not consent will be excluded. \
+             We will also exclude duplicate responses from the same IP address. \
+             We will remove duplicate submissions by the same worker_code.",
+        );
+        let spec = build_analysis_spec(
+            "p",
+            "s",
+            "a",
+            "q",
+            "p",
+            "q",
+            "p",
+            &qsf,
+            &prereg,
+            "apa_v1",
+            "apa",
+            &[],
+        );
+        let filters = spec
+            .data_contract
+            .exclusions
+            .iter()
+            .map(|e| e.r_filter.as_str())
+            .collect::<Vec<&str>>();
+        assert_eq!(
+            filters,
+            vec![
+                "df <- df %>% dplyr::filter(duration_in_seconds >= 60)",
+                "df <- df %>% dplyr::filter(consent == 1)",
+                "df <- df %>% dplyr::filter(!duplicated(ip_address))",
+                "# TODO: apply exclusion (duplicate, no column for worker_code): duplicate submissions by the same worker_code",
+            ]
+        );
+        let unresolved = spec
+            .warnings
+            .iter()
+            .filter(|w| w.code == "EXCLUSION_VARIABLE_UNRESOLVED")
+            .collect::<Vec<_>>();
+        assert_eq!(unresolved.len(), 1);
+        assert_eq!(unresolved[0].details["exclusionId"], "exclusion_4");
+    }
+
+    #[test]
+    fn counterbalanced_columns_resolve_to_a_merge_listing_both_sources() {
+        let qsf = parse_qsf_json(
+            r#"{"SurveyEntry":{"SurveyName":"T"},"SurveyElements":[
+              {"Element":"SQ","Payload":{"QuestionID":"QID1","DataExportTag":"consent_a1","QuestionText":"Order A","QuestionType":{"Type":"MC"}}},
+              {"Element":"SQ","Payload":{"QuestionID":"QID2","DataExportTag":"consent_b1","QuestionText":"Order B","QuestionType":{"Type":"MC"}}}
+            ]}"#,
+        )
+        .expect("parse qsf");
+        let mut prereg = PreregSpec::default();
+        prereg.variables.dv = vec!["consent".to_string()];
+
+        let spec = build_analysis_spec(
+            "p",
+            "s",
+            "a",
+            "qsf",
+            "prereg",
+            "q",
+            "p",
+            &qsf,
+            &prereg,
+            "apa_v1",
+            "apa",
+            &[],
+        );
+        let mapping = &spec.variable_mappings[0];
+        assert_eq!(mapping.resolved_to.as_deref(), Some("consent"));
+        assert_eq!(mapping.resolution_kind, ResolutionKind::DerivedMerge);
+        assert_eq!(mapping.derived_sources, vec!["consent_a1", "consent_b1"]);
+        let merge = &spec.data_contract.derived_variables[0];
+        assert_eq!(merge.derived_type, "counterbalance_merge");
+        assert_eq!(
+            merge.definition,
+            "dplyr::coalesce(`consent_a1`, `consent_b1`)"
+        );
+
+        let legacy: MappingResult = serde_json::from_str(
+            r#"{"preregVar":"consent","resolvedTo":"consent_a1","candidates":[]}"#,
+        )
+        .expect("legacy mapping");
+        assert_eq!(legacy.resolution_kind, ResolutionKind::Direct);
+        assert!(legacy.derived_sources.is_empty());
+    }
+
+    #[test]
+    fn resolved_scale_items_become_row_mean_and_add_psych() {
+        let raw = r#"{
+      "SurveyEntry": {"SurveyName": "T"},
+      "SurveyElements": [
+        {"Element":"SQ","Payload":{"QuestionID":"QID1","DataExportTag":"WB_1","QuestionText":"Calm","QuestionType":{"Type":"MC"}}},
+        {"Element":"SQ","Payload":{"QuestionID":"QID2","DataExportTag":"WB_2","QuestionText":"Content","QuestionType":{"Type":"MC"}}},
+        {"Element":"SQ","Payload":{"QuestionID":"QID3","DataExportTag":"WB_3","QuestionText":"Rested","QuestionType":{"Type":"MC"}}}
+      ]
+    }"#;
+        let qsf = parse_qsf_json(raw).expect("parse qsf");
+        let scale = |name: &str, items: &[&str]| DerivedScale {
+            name: name.to_string(),
+            derived_type: "scale".to_string(),
+            depends_on: items.iter().map(|i| i.to_string()).collect(),
+            definition: format!("rowMeans(cbind(/* items for {name} */), na.rm = TRUE)"),
+        };
+        let mut prereg = PreregSpec {
+            derived_scales: vec![
+                scale("wellbeing_scale", &["WB_1", "WB_2", "WB_3"]),
+                scale("anxiety_scale", &[]),
+            ],
+            ..PreregSpec::default()
+        };
+
+        let spec = build_analysis_spec(
+            "p",
+            "s",
+            "a",
+            "qsf",
+            "prereg",
+            "q",
+            "p",
+            &qsf,
+            &prereg,
+            "apa_v1",
+            "apa",
+            &[],
+        );
+        let derived = &spec.data_contract.derived_variables;
+        assert_eq!(derived[0].depends_on, vec!["wb_1", "wb_2", "wb_3"]);
+        assert_eq!(
+            derived[0].definition,
+            "rowMeans(dplyr::select(df, dplyr::all_of(c(\"wb_1\", \"wb_2\", \"wb_3\"))), na.rm = TRUE)"
+        );
+        assert!(derived[1]
+            .definition
+            .contains("/* items for anxiety_scale */"));
+        let unresolved: Vec<_> = spec
+            .warnings
+            .iter()
+            .filter(|w| w.code == "SCALE_ITEMS_UNRESOLVED")
+            .collect();
+        assert_eq!(unresolved.len(), 1);
+        assert_eq!(unresolved[0].details["scale"], "anxiety_scale");
+        assert!(spec
+            .template_bindings
+            .packages
+            .contains(&"psych".to_string()));
+
+        prereg.derived_scales = vec![scale("wellbeing_scale", &["WB_1", "missing_item"])];
+        let spec = build_analysis_spec(
+            "p",
+            "s",
+            "a",
+            "qsf",
+            "prereg",
+            "q",
+            "p",
+            &qsf,
+            &prereg,
+            "apa_v1",
+            "apa",
+            &[],
+        );
+        assert!(spec
+            .warnings
+            .iter()
+            .any(|w| w.code == "SCALE_ITEMS_UNRESOLVED"
+                && w.details["unresolvedItems"] == serde_json::json!(["missing_item"])));
+        assert!(!spec
+            .template_bindings
+            .packages
+            .contains(&"psych".to_string()));
+    }
+
+    #[test]
+    fn scale_without_items_takes_numbered_columns_sharing_its_stem() {
+        let raw = r#"{
+      "SurveyEntry": {"SurveyName": "T"},
+      "SurveyElements": [
+        {"Element":"SQ","Payload":{"QuestionID":"QID1","DataExportTag":"trust_2","QuestionText":"Reliable","QuestionType":{"Type":"MC"}}},
+        {"Element":"SQ","Payload":{"QuestionID":"QID2","DataExportTag":"trust_1","QuestionText":"Honest","QuestionType":{"Type":"MC"}}},
+        {"Element":"SQ","Payload":{"QuestionID":"QID3","DataExportTag":"Trust_3","QuestionText":"Competent","QuestionType":{"Type":"MC"}}},
+        {"Element":"SQ","Payload":{"QuestionID":"QID4","DataExportTag":"trust_check","QuestionText":"Attention","QuestionType":{"Type":"MC"}}},
+        {"Element":"SQ","Payload":{"QuestionID":"QID5","DataExportTag":"distrust_1","QuestionText":"Suspicious","QuestionType":{"Type":"MC"}}}
+      ]
+    }"#;
+        let qsf = parse_qsf_json(raw).expect("parse qsf");
+        let mut prereg = PreregSpec::default();
+        fill_from_text(
+            &mut prereg,
+            "Trust is measured with a 3-item trust scale averaged into one score.",
+        );
+        assert_eq!(prereg.derived_scales[0].name, "trust_scale");
+        let spec = build_analysis_spec(
+            "p",
+            "s",
+            "a",
+            "qsf",
+            "prereg",
+            "q",
+            "p",
+            &qsf,
+            &prereg,
+            "apa_v1",
+            "apa",
+            &[],
+        );
+
+        let trust = &spec.data_contract.derived_variables[0];
+        assert_eq!(trust.depends_on, vec!["trust_1", "trust_2", "trust_3"]);
+        assert_eq!(
+            trust.definition,
+            "rowMeans(dplyr::select(df, dplyr::all_of(c(\"trust_1\", \"trust_2\", \"trust_3\"))), na.rm = TRUE)"
+        );
+        let assumed = spec
+            .warnings
+            .iter()
+            .find(|w| w.code == "SCALE_ITEMS_ASSUMED")
+            .expect("assumed items warning");
+        assert_eq!(
+            assumed.details["columns"],
+            serde_json::json!(["trust_1", "trust_2", "Trust_3"])
+        );
+        assert!(!spec
+            .warnings
+            .iter()
+            .any(|w| w.code == "SCALE_ITEMS_UNRESOLVED"));
+    }
 }
@@ -0,0 +1,286 @@
+use serde::Serialize;
+use std::path::Path;
+
+use crate::render::helpers::{ensure_dir, write_files_atomically};
+use crate::util::text::clean_names;
+
+use super::types::{AnalysisSpec, ResolutionKind};
+
+/// Study-relative folder the codebook is written to.
+pub const CODEBOOK_DIR: &str = "07_outputs/reports";
+const CODEBOOK_COLUMNS: [&str; 6] = [
+    "variable",
+    "clean_name",
+    "type",
+    "label",
+    "values",
+    "used_by",
+];
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CodebookOutput {
+    pub csv_path: String,
+    pub markdown_path: String,
+    pub columns: usize,
+    /// Preregistered variables with no survey column behind them.
+    pub unresolved: Vec<String>,
+}
+
+struct CodebookRow {
+    variable: String,
+    clean_name: String,
+    kind: &'static str,
+    label: String,
+    values: String,
+    used_by: String,
+}
+
+fn codebook_rows(spec: &AnalysisSpec) -> Vec<CodebookRow> {
+    let contract = &spec.data_contract;
+    contract
+        .expected_columns
+        .iter()
+        .map(|variable| {
+            let column = contract.columns.iter().find(|c| &c.name == variable);
+            let kind = if column.is_some_and(|c| c.meta) {
+                "metadata"
+            } else if contract.free_text_columns.contains(variable) {
+                "free_text"
+            } else {
+                "question"
+            };
+            let values = contract
+                .value_labels
+                .get(variable)
+                .map(|labels| {
+                    labels
+                        .iter()
+                        .map(|(value, label)| format!("{value} = {label}"))
+                        .collect::<Vec<_>>()
+                        .join("; ")
+                })
+                .unwrap_or_default();
+            let used_by = contract
+                .derived_variables
+                .iter()
+                .filter(|derived| derived.depends_on.contains(variable))
+                .map(|derived| derived.name.as_str())
+                .collect::<Vec<_>>()
+                .join("; ");
+            CodebookRow {
+                variable: variable.clone(),
+                clean_name: column
+                    .map(|c| c.clean_name.clone())
+                    .unwrap_or_else(|| clean_names(variable)),
+                kind,
+                label: contract
+                    .label_map
+                    .get(variable)
+                    .cloned()
+                    .unwrap_or_default(),
+                values,
+                used_by,
+            }
+        })
+        .collect()
+}
+
+fn unresolved_variables(spec: &AnalysisSpec) -> Vec<String> {
+    spec.variable_mappings
+        .iter()
+        .filter(|m| m.resolution_kind == ResolutionKind::Unresolved)
+        .map(|m| m.prereg_var.clone())
+        .collect()
+}
+
+fn codebook_csv(rows: &[CodebookRow]) -> Result<String, String> {
+    let mut writer = csv::Writer::from_writer(Vec::new());
+    writer
+        .write_record(CODEBOOK_COLUMNS)
+        .map_err(|err| err.to_string())?;
+    for row in rows {
+        writer
+            .write_record([
+                row.variable.as_str(),
+                &row.clean_name,
+                row.kind,
+                &row.label,
+                &row.values,
+                &row.used_by,
+            ])
+            .map_err(|err| err.to_string())?;
+    }
+    let bytes = writer.into_inner().map_err(|err| err.to_string())?;
+    String::from_utf8(bytes).map_err(|err| err.to_string())
+}
+
+/// Escapes text for a single Markdown table cell.
+fn cell(value: &str) -> String {
+    value
+        .replace('|', "\\|")
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn codebook_markdown(spec: &AnalysisSpec, rows: &[CodebookRow], unresolved: &[String]) -> String {
+    let contract = &spec.data_contract;
+    let mut out = format!(
+        "# Codebook: {} / {}\n\nGenerated from the analysis spec; survey labels and choices come from the QSF.\n\n",
+        spec.study_id, spec.analysis_id
+    );
+    out.push_str("## Survey columns\n\n");
+    out.push_str("| Variable | Clean name | Type | Label | Values |\n");
+    out.push_str("| --- | --- | --- | --- | --- |\n");
+    for row in rows {
+        out.push_str(&format!(
+            "| {} | `{}` | {} | {} | {} |\n",
+            cell(&row.variable),
+            row.clean_name,
+            row.kind,
+            cell(&row.label),
+            cell(&row.values)
+        ));
+    }
+    if !contract.derived_variables.is_empty() {
+        out.push_str("\n## Derived variables\n\n");
+        for derived in &contract.derived_variables {
+            out.push_str(&format!(
+                "- **{}** ({}): {}\n  - Depends on: {}\n",
+                derived.name,
+                derived.derived_type,
+                cell(&derived.definition),
+                if derived.depends_on.is_empty() {
+                    "none".to_string()
+                } else {
+                    derived.depends_on.join(", ")
+                }
+            ));
+        }
+    }
+    if !contract.exclusions.is_empty() {
+        out.push_str("\n## Exclusions\n\n");
+        for exclusion in &contract.exclusions {
+            out.push_str(&format!(
+                "- **{}**: {}\n  - `{}`\n",
+                exclusion.id,
+                cell(&exclusion.criterion),
+                exclusion.r_filter
+            ));
+        }
+    }
+    if !unresolved.is_empty() {
+        out.push_str("\n## Not located in survey\n\n");
+        out.push_str("These preregistered variables have no survey column yet.\n\n");
+        for variable in unresolved {
+            out.push_str(&format!("- {variable}\n"));
+        }
+    }
+    out
+}
+
+/// Writes `codebook.csv` (one row per expected column) and `codebook.md` into `out_dir`.
+pub fn write_codebook(spec: &AnalysisSpec, out_dir: &Path) -> Result<CodebookOutput, String> {
+    let rows = codebook_rows(spec);
+    let unresolved = unresolved_variables(spec);
+    let csv_path = out_dir.join("codebook.csv");
+    let markdown_path = out_dir.join("codebook.md");
+    ensure_dir(out_dir)?;
+    write_files_atomically(&[
+        (csv_path.clone(), codebook_csv(&rows)?),
+        (
+            markdown_path.clone(),
+            codebook_markdown(spec, &rows, &unresolved),
+        ),
+    ])?;
+    Ok(CodebookOutput {
+        csv_path: csv_path.to_string_lossy().to_string(),
+        markdown_path: markdown_path.to_string_lossy().to_string(),
+        columns: rows.len(),
+        unresolved,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::prereg::types::PreregSpec;
+    use crate::qsf::parse::parse_qsf_json;
+    use crate::spec::builder::build_analysis_spec;
+    use crate::spec::types::{DerivedVariableSpec, MappingResult};
+    use std::fs;
+    use uuid::Uuid;
+
+    #[test]
+    fn codebook_has_one_row_per_expected_column_with_labels() {
+        let raw = r#"{
+      "SurveyEntry": {"SurveyName": "T"},
+      "SurveyElements": [
+        {"Element":"SQ","Payload":{"QuestionID":"QID1","DataExportTag":"condition","QuestionText":"Which condition?","QuestionType":{"Type":"MC"},
+          "Choices":{"1":{"Display":"Control"},"2":{"Display":"Treatment"}}}},
+        {"Element":"SQ","Payload":{"QuestionID":"QID2","DataExportTag":"wellbeing","QuestionText":"How satisfied are you | overall?","QuestionType":{"Type":"TE"}}}
+      ]
+    }"#;
+        let qsf = parse_qsf_json(raw).expect("parse qsf");
+        let mut spec = build_analysis_spec(
+            "p",
+            "s",
+            "a",
+            "qsf",
+            "prereg",
+            "q",
+            "p",
+            &qsf,
+            &PreregSpec::default(),
+            "apa_v1",
+            "apa",
+            &[],
+        );
+        spec.data_contract
+            .derived_variables
+            .push(DerivedVariableSpec {
+                name: "treated".to_string(),
+                derived_type: "indicator".to_string(),
+                depends_on: vec!["condition".to_string()],
+                definition: "condition == 2".to_string(),
+            });
+        spec.variable_mappings.push(MappingResult {
+            prereg_var: "income".to_string(),
+            resolved_to: None,
+            candidates: Vec::new(),
+            resolution_kind: ResolutionKind::Unresolved,
+            derived_sources: Vec::new(),
+            reason: None,
+        });
+
+        let dir = std::env::temp_dir().join(format!("codebook-{}", Uuid::new_v4()));
+        let output = write_codebook(&spec, &dir).expect("write codebook");
+        assert_eq!(output.columns, spec.data_contract.expected_columns.len());
+        assert_eq!(output.unresolved, vec!["income".to_string()]);
+
+        let csv = fs::read_to_string(&output.csv_path).expect("read csv");
+        let mut reader = csv::Reader::from_reader(csv.as_bytes());
+        let records: Vec<csv::StringRecord> =
+            reader.records().map(|r| r.expect("record")).collect();
+        assert_eq!(records.len(), spec.data_contract.expected_columns.len());
+        let row = |name: &str| {
+            records
+                .iter()
+                .find(|r| &r[0] == name)
+                .unwrap_or_else(|| panic!("row for {name}"))
+        };
+        assert_eq!(&row("condition")[3], "Which condition?");
+        assert_eq!(&row("condition")[4], "1 = Control; 2 = Treatment");
+        assert_eq!(&row("condition")[5], "treated");
+        assert_eq!(&row("wellbeing")[3], "How satisfied are you | overall?");
+
+        let markdown = fs::read_to_string(&output.markdown_path).expect("read markdown");
+        assert!(markdown.contains("How satisfied are you \\| overall?"));
+        assert!(markdown
+            .contains("- **treated** (indicator): condition == 2\n  - Depends on: condition\n"));
+        assert!(markdown.contains("## Not located in survey\n"));
+        assert!(markdown.contains("- income\n"));
+        let _ = fs::remove_dir_all(dir);
+    }
+}
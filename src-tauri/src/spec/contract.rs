@@ -0,0 +1,334 @@
+use serde::Serialize;
+use std::collections::BTreeSet;
+
+use super::types::{AnalysisSpec, WarningItem, WarningSeverity};
+
+pub const DATA_CONTRACT_MISMATCH: &str = "DATA_CONTRACT_MISMATCH";
+
+#[derive(Debug, Clone, Serialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct ColumnNearMatch {
+    pub expected: String,
+    pub found: String,
+    /// "case" when only letter case differs, "suffix" when one name is the other plus `_N`.
+    pub kind: String,
+}
+
+#[derive(Debug, Clone, Serialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct ModelColumnCheck {
+    pub model_id: String,
+    /// dv/iv/control/weight columns absent from the header (near matches count as absent).
+    pub missing: Vec<String>,
+    pub all_present: bool,
+}
+
+#[derive(Debug, Clone, Serialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct DataContractReport {
+    pub csv_path: String,
+    pub missing_columns: Vec<String>,
+    pub extra_columns: Vec<String>,
+    pub near_matches: Vec<ColumnNearMatch>,
+    pub models: Vec<ModelColumnCheck>,
+    pub ok: bool,
+}
+
+/// Whether `name` is `base` followed by a Qualtrics-style `_N` suffix.
+fn has_numeric_suffix(name: &str, base: &str) -> bool {
+    let (Some(head), Some(tail)) = (name.get(..base.len()), name.get(base.len()..)) else {
+        return false;
+    };
+    head.eq_ignore_ascii_case(base)
+        && tail
+            .strip_prefix('_')
+            .is_some_and(|rest| !rest.is_empty() && rest.chars().all(|c| c.is_ascii_digit()))
+}
+
+fn near_match(expected: &str, header: &[String]) -> Option<ColumnNearMatch> {
+    let make = |found: &String, kind: &str| ColumnNearMatch {
+        expected: expected.to_string(),
+        found: found.clone(),
+        kind: kind.to_string(),
+    };
+    if let Some(found) = header.iter().find(|h| h.eq_ignore_ascii_case(expected)) {
+        return Some(make(found, "case"));
+    }
+    header
+        .iter()
+        .find(|h| has_numeric_suffix(h, expected) || has_numeric_suffix(expected, h))
+        .map(|found| make(found, "suffix"))
+}
+
+/// Columns the analysis reads: the QSF's expected columns plus every resolved mapping target
+/// that is a real column rather than a derived variable or `TODO_` placeholder.
+fn required_columns(spec: &AnalysisSpec) -> Vec<String> {
+    let derived: BTreeSet<String> = spec
+        .data_contract
+        .derived_variables
+        .iter()
+        .map(|d| d.name.to_lowercase())
+        .collect();
+    let mut out: Vec<String> = Vec::new();
+    let targets = spec
+        .variable_mappings
+        .iter()
+        .filter_map(|m| m.resolved_to.as_ref())
+        .filter(|col| !col.starts_with("TODO_") && !derived.contains(&col.to_lowercase()));
+    for col in spec.data_contract.expected_columns.iter().chain(targets) {
+        if !out.contains(col) {
+            out.push(col.clone());
+        }
+    }
+    out
+}
+
+/// Compares a data file's header row with what the spec expects.
+pub fn check_data_contract(
+    spec: &AnalysisSpec,
+    csv_path: &str,
+    header: &[String],
+) -> DataContractReport {
+    let required = required_columns(spec);
+    let mut missing_columns = Vec::new();
+    let mut near_matches = Vec::new();
+    for col in &required {
+        if header.contains(col) {
+            continue;
+        }
+        match near_match(col, header) {
+            Some(near) => near_matches.push(near),
+            None => missing_columns.push(col.clone()),
+        }
+    }
+
+    let mut known: BTreeSet<&str> = required.iter().map(String::as_str).collect();
+    known.extend(spec.data_contract.columns.iter().map(|c| c.name.as_str()));
+    known.extend(spec.data_contract.id_columns.values().map(String::as_str));
+    known.extend(near_matches.iter().map(|n| n.found.as_str()));
+    let extra_columns = header
+        .iter()
+        .filter(|h| !known.contains(h.as_str()))
+        .cloned()
+        .collect::<Vec<String>>();
+
+    let derived: BTreeSet<&str> = spec
+        .data_contract
+        .derived_variables
+        .iter()
+        .map(|d| d.name.as_str())
+        .collect();
+    let models = spec
+        .models
+        .main
+        .iter()
+        .chain(spec.models.exploratory.iter())
+        .chain(spec.models.robustness.iter())
+        .map(|model| {
+            let mut missing: Vec<String> = Vec::new();
+            let columns = std::iter::once(&model.dv)
+                .chain(model.iv.iter())
+                .chain(model.controls.iter())
+                .chain(model.weight_var.iter());
+            for col in columns {
+                if !header.contains(col)
+                    && !derived.contains(col.as_str())
+                    && !missing.contains(col)
+                {
+                    missing.push(col.clone());
+                }
+            }
+            ModelColumnCheck {
+                model_id: model.id.clone(),
+                all_present: missing.is_empty(),
+                missing,
+            }
+        })
+        .collect::<Vec<ModelColumnCheck>>();
+
+    let ok = missing_columns.is_empty()
+        && near_matches.is_empty()
+        && models.iter().all(|m| m.all_present);
+    DataContractReport {
+        csv_path: csv_path.to_string(),
+        missing_columns,
+        extra_columns,
+        near_matches,
+        models,
+        ok,
+    }
+}
+
+/// Replaces any earlier `DATA_CONTRACT_MISMATCH` warning with one for `report` (none when the
+/// file matches). Returns whether the warnings changed.
+pub fn apply_contract_warning(spec: &mut AnalysisSpec, report: &DataContractReport) -> bool {
+    let before = spec.warnings.len();
+    let previous = spec
+        .warnings
+        .iter()
+        .find(|w| w.code == DATA_CONTRACT_MISMATCH)
+        .map(|w| w.details.clone());
+    spec.warnings.retain(|w| w.code != DATA_CONTRACT_MISMATCH);
+    if report.ok {
+        return spec.warnings.len() != before;
+    }
+    let incomplete_models = report
+        .models
+        .iter()
+        .filter(|m| !m.all_present)
+        .map(|m| m.model_id.clone())
+        .collect::<Vec<String>>();
+    let details = serde_json::json!({
+      "csvPath": report.csv_path,
+      "missingColumns": report.missing_columns,
+      "nearMatches": report.near_matches,
+      "incompleteModels": incomplete_models,
+    });
+    let changed = previous.as_ref() != Some(&details);
+    spec.warnings.push(WarningItem {
+        code: DATA_CONTRACT_MISMATCH.to_string(),
+        message: format!(
+            "{} expected column(s) missing and {} differing only by case or suffix in {}.",
+            report.missing_columns.len(),
+            report.near_matches.len(),
+            report.csv_path
+        ),
+        details,
+        severity: WarningSeverity::Warning,
+    });
+    changed
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::spec::types::{
+        DataContractSpec, InputRef, InputsSpec, MappingResult, ModelSpec, ModelsSpec, OutputsSpec,
+        ResolutionKind, TemplateBindingsSpec,
+    };
+    use std::collections::BTreeMap;
+
+    fn spec_expecting(columns: &[&str]) -> AnalysisSpec {
+        let input = InputRef {
+            path: String::new(),
+            sha256: String::new(),
+        };
+        AnalysisSpec {
+            project_id: "p".to_string(),
+            study_id: "s".to_string(),
+            analysis_id: "a".to_string(),
+            inputs: InputsSpec {
+                qsf: input.clone(),
+                prereg: input,
+            },
+            data_contract: DataContractSpec {
+                source: "qualtrics_csv".to_string(),
+                id_columns: BTreeMap::from([("response_id".to_string(), "ResponseId".to_string())]),
+                expected_columns: columns.iter().map(|c| c.to_string()).collect(),
+                label_map: BTreeMap::new(),
+                exclusions: vec![],
+                missingness: None,
+                derived_variables: vec![],
+                expected_values: Default::default(),
+                factor_levels: vec![],
+                randomized_levels: vec![],
+                indicator_columns: vec![],
+                free_text_columns: vec![],
+                columns: vec![],
+                value_labels: BTreeMap::new(),
+            },
+            variable_mappings: vec![
+                MappingResult {
+                    prereg_var: "loneliness".to_string(),
+                    resolved_to: Some("UCLA_total".to_string()),
+                    candidates: vec![],
+                    resolution_kind: ResolutionKind::Direct,
+                    derived_sources: vec![],
+                    reason: None,
+                },
+                MappingResult {
+                    prereg_var: "income".to_string(),
+                    resolved_to: Some("TODO_income".to_string()),
+                    candidates: vec![],
+                    resolution_kind: ResolutionKind::Unresolved,
+                    derived_sources: vec![],
+                    reason: None,
+                },
+            ],
+            models: ModelsSpec {
+                main: vec![ModelSpec {
+                    id: "H1".to_string(),
+                    family: "gaussian".to_string(),
+                    dv: "UCLA_total".to_string(),
+                    iv: vec!["Condition".to_string()],
+                    controls: vec!["age".to_string()],
+                    interactions: vec![],
+                    formula: "UCLA_total ~ Condition + age".to_string(),
+                    unresolved_variables: vec![],
+                    weight_var: None,
+                }],
+                exploratory: vec![],
+                robustness: vec![],
+            },
+            hypotheses: vec![],
+            outputs: OutputsSpec {
+                tables: vec![],
+                figures: vec![],
+                model_table_format: None,
+            },
+            template_bindings: TemplateBindingsSpec {
+                template_set: "apa_v1".to_string(),
+                style_profile: "apa".to_string(),
+                paths: BTreeMap::new(),
+                packages: vec![],
+                use_renv: false,
+            },
+            model_provenance: None,
+            model_lock: None,
+            warnings: vec![],
+        }
+    }
+
+    #[test]
+    fn header_differences_by_case_and_suffix_are_near_matches() {
+        let spec = spec_expecting(&["Condition", "age", "Q7", "attention_check"]);
+        // Header row of a Qualtrics export whose columns drifted from the QSF.
+        let header: Vec<String> = "ResponseId,condition,age,Q7_1,UCLA_TOTAL,Q12"
+            .split(',')
+            .map(str::to_string)
+            .collect();
+        let report = check_data_contract(&spec, "data.csv", &header);
+
+        assert_eq!(report.missing_columns, vec!["attention_check"]);
+        assert_eq!(
+            report.near_matches,
+            vec![
+                ColumnNearMatch {
+                    expected: "Condition".to_string(),
+                    found: "condition".to_string(),
+                    kind: "case".to_string(),
+                },
+                ColumnNearMatch {
+                    expected: "Q7".to_string(),
+                    found: "Q7_1".to_string(),
+                    kind: "suffix".to_string(),
+                },
+                ColumnNearMatch {
+                    expected: "UCLA_total".to_string(),
+                    found: "UCLA_TOTAL".to_string(),
+                    kind: "case".to_string(),
+                },
+            ]
+        );
+        assert_eq!(report.extra_columns, vec!["Q12"]);
+        assert_eq!(report.models[0].missing, vec!["UCLA_total", "Condition"]);
+        assert!(!report.ok);
+        assert!(!has_numeric_suffix("Q7_1a", "Q7"));
+        assert!(!has_numeric_suffix("Q7_", "Q7"));
+
+        let mut spec = spec;
+        assert!(apply_contract_warning(&mut spec, &report));
+        assert!(!apply_contract_warning(&mut spec, &report));
+        assert_eq!(spec.warnings.len(), 1);
+    }
+}
@@ -0,0 +1,358 @@
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::BTreeSet;
+
+use super::types::{AnalysisSpec, InputRef, ModelSpec, WarningItem};
+
+#[derive(Debug, Clone, Serialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct ModelRef {
+    /// `main`, `exploratory` or `robustness`.
+    pub group: String,
+    pub id: String,
+    pub formula: String,
+}
+
+#[derive(Debug, Clone, Serialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct FieldChange {
+    /// Spec JSON field name, e.g. `controls`.
+    pub field: String,
+    pub before: Value,
+    pub after: Value,
+}
+
+#[derive(Debug, Clone, Serialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct ModelChange {
+    pub group: String,
+    pub id: String,
+    pub changes: Vec<FieldChange>,
+}
+
+#[derive(Debug, Clone, Serialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct MappingDiff {
+    pub prereg_var: String,
+    /// `None` when the variable was unresolved or not in that spec.
+    pub before: Option<String>,
+    pub after: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct InputChange {
+    /// `qsf` or `prereg`.
+    pub input: String,
+    pub before_path: String,
+    pub after_path: String,
+    pub before_sha256: String,
+    pub after_sha256: String,
+}
+
+/// What changed from `before` (usually the saved spec.json) to `after` (a regeneration).
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SpecDiff {
+    pub models_added: Vec<ModelRef>,
+    pub models_removed: Vec<ModelRef>,
+    pub models_modified: Vec<ModelChange>,
+    pub mapping_changes: Vec<MappingDiff>,
+    pub warnings_added: Vec<WarningItem>,
+    pub warnings_resolved: Vec<WarningItem>,
+    pub columns_added: Vec<String>,
+    pub columns_removed: Vec<String>,
+    pub input_changes: Vec<InputChange>,
+    /// True when every list above is empty.
+    pub unchanged: bool,
+}
+
+impl SpecDiff {
+    fn is_empty(&self) -> bool {
+        self.models_added.is_empty()
+            && self.models_removed.is_empty()
+            && self.models_modified.is_empty()
+            && self.mapping_changes.is_empty()
+            && self.warnings_added.is_empty()
+            && self.warnings_resolved.is_empty()
+            && self.columns_added.is_empty()
+            && self.columns_removed.is_empty()
+            && self.input_changes.is_empty()
+    }
+}
+
+fn model_groups(spec: &AnalysisSpec) -> Vec<(&'static str, &ModelSpec)> {
+    let models = &spec.models;
+    [
+        ("main", &models.main),
+        ("exploratory", &models.exploratory),
+        ("robustness", &models.robustness),
+    ]
+    .into_iter()
+    .flat_map(|(group, list)| list.iter().map(move |model| (group, model)))
+    .collect()
+}
+
+fn model_ref(group: &str, model: &ModelSpec) -> ModelRef {
+    ModelRef {
+        group: group.to_string(),
+        id: model.id.clone(),
+        formula: model.formula.clone(),
+    }
+}
+
+/// Field-by-field comparison over the serialized models, so new `ModelSpec` fields are
+/// picked up without touching this function.
+fn model_field_changes(before: &ModelSpec, after: &ModelSpec) -> Vec<FieldChange> {
+    let before = serde_json::to_value(before).unwrap_or(Value::Null);
+    let after = serde_json::to_value(after).unwrap_or(Value::Null);
+    let empty = serde_json::Map::new();
+    let before = before.as_object().unwrap_or(&empty);
+    let after = after.as_object().unwrap_or(&empty);
+    let fields: BTreeSet<&String> = before.keys().chain(after.keys()).collect();
+    fields
+        .into_iter()
+        .filter_map(|field| {
+            let old = before.get(field).cloned().unwrap_or(Value::Null);
+            let new = after.get(field).cloned().unwrap_or(Value::Null);
+            (old != new).then(|| FieldChange {
+                field: field.clone(),
+                before: old,
+                after: new,
+            })
+        })
+        .collect()
+}
+
+fn find_model<'a>(
+    models: &[(&str, &'a ModelSpec)],
+    group: &str,
+    id: &str,
+) -> Option<&'a ModelSpec> {
+    models
+        .iter()
+        .find(|(g, model)| *g == group && model.id == id)
+        .map(|(_, model)| *model)
+}
+
+fn diff_models(before: &AnalysisSpec, after: &AnalysisSpec, diff: &mut SpecDiff) {
+    let old = model_groups(before);
+    let new = model_groups(after);
+    for (group, model) in &new {
+        match find_model(&old, group, &model.id) {
+            None => diff.models_added.push(model_ref(group, model)),
+            Some(previous) => {
+                let changes = model_field_changes(previous, model);
+                if !changes.is_empty() {
+                    diff.models_modified.push(ModelChange {
+                        group: group.to_string(),
+                        id: model.id.clone(),
+                        changes,
+                    });
+                }
+            }
+        }
+    }
+    for (group, model) in &old {
+        if find_model(&new, group, &model.id).is_none() {
+            diff.models_removed.push(model_ref(group, model));
+        }
+    }
+}
+
+fn diff_mappings(before: &AnalysisSpec, after: &AnalysisSpec, diff: &mut SpecDiff) {
+    let resolved = |spec: &AnalysisSpec, var: &str| {
+        spec.variable_mappings
+            .iter()
+            .find(|m| m.prereg_var == var)
+            .and_then(|m| m.resolved_to.clone())
+    };
+    let mut seen = BTreeSet::new();
+    for mapping in after
+        .variable_mappings
+        .iter()
+        .chain(&before.variable_mappings)
+    {
+        if !seen.insert(mapping.prereg_var.as_str()) {
+            continue;
+        }
+        let old = resolved(before, &mapping.prereg_var);
+        let new = resolved(after, &mapping.prereg_var);
+        if old != new {
+            diff.mapping_changes.push(MappingDiff {
+                prereg_var: mapping.prereg_var.clone(),
+                before: old,
+                after: new,
+            });
+        }
+    }
+}
+
+/// Warnings are matched on code and message; details may carry incidental values.
+fn warnings_missing_from(warnings: &[WarningItem], other: &[WarningItem]) -> Vec<WarningItem> {
+    warnings
+        .iter()
+        .filter(|w| {
+            !other
+                .iter()
+                .any(|o| o.code == w.code && o.message == w.message)
+        })
+        .cloned()
+        .collect()
+}
+
+fn columns_missing_from(columns: &[String], other: &[String]) -> Vec<String> {
+    columns
+        .iter()
+        .filter(|column| !other.contains(column))
+        .cloned()
+        .collect()
+}
+
+fn input_change(input: &str, before: &InputRef, after: &InputRef) -> Option<InputChange> {
+    (before.sha256 != after.sha256).then(|| InputChange {
+        input: input.to_string(),
+        before_path: before.path.clone(),
+        after_path: after.path.clone(),
+        before_sha256: before.sha256.clone(),
+        after_sha256: after.sha256.clone(),
+    })
+}
+
+pub fn diff_specs(before: &AnalysisSpec, after: &AnalysisSpec) -> SpecDiff {
+    let mut diff = SpecDiff::default();
+    diff_models(before, after, &mut diff);
+    diff_mappings(before, after, &mut diff);
+    diff.warnings_added = warnings_missing_from(&after.warnings, &before.warnings);
+    diff.warnings_resolved = warnings_missing_from(&before.warnings, &after.warnings);
+    let old_columns = &before.data_contract.expected_columns;
+    let new_columns = &after.data_contract.expected_columns;
+    diff.columns_added = columns_missing_from(new_columns, old_columns);
+    diff.columns_removed = columns_missing_from(old_columns, new_columns);
+    diff.input_changes = [
+        input_change("qsf", &before.inputs.qsf, &after.inputs.qsf),
+        input_change("prereg", &before.inputs.prereg, &after.inputs.prereg),
+    ]
+    .into_iter()
+    .flatten()
+    .collect();
+    diff.unchanged = diff.is_empty();
+    diff
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::prereg::types::PreregSpec;
+    use crate::qsf::parse::parse_qsf_json;
+    use crate::spec::builder::build_analysis_spec;
+    use crate::spec::types::{MappingResult, ResolutionKind};
+    use serde_json::json;
+
+    fn base_spec() -> AnalysisSpec {
+        let raw = r#"{
+      "SurveyEntry": {"SurveyName": "T"},
+      "SurveyElements": [
+        {"Element":"SQ","Payload":{"QuestionID":"QID1","DataExportTag":"wellbeing","QuestionText":"Wellbeing","QuestionType":{"Type":"TE"}}},
+        {"Element":"SQ","Payload":{"QuestionID":"QID2","DataExportTag":"age","QuestionText":"Age","QuestionType":{"Type":"TE"}}}
+      ]
+    }"#;
+        let qsf = parse_qsf_json(raw).expect("parse qsf");
+        let mut spec = build_analysis_spec(
+            "p",
+            "s",
+            "a",
+            "qsf",
+            "prereg",
+            "q",
+            "p",
+            &qsf,
+            &PreregSpec::default(),
+            "apa_v1",
+            "apa",
+            &[],
+        );
+        for (id, controls) in [("m1", vec!["age".to_string()]), ("m2", Vec::new())] {
+            spec.models.main.push(ModelSpec {
+                id: id.to_string(),
+                family: "gaussian".to_string(),
+                dv: "wellbeing".to_string(),
+                iv: vec!["condition".to_string()],
+                formula: format!(
+                    "wellbeing ~ {}",
+                    ["condition".to_string()]
+                        .iter()
+                        .chain(&controls)
+                        .cloned()
+                        .collect::<Vec<_>>()
+                        .join(" + ")
+                ),
+                controls,
+                interactions: Vec::new(),
+                unresolved_variables: Vec::new(),
+                weight_var: None,
+            });
+        }
+        for (var, resolved) in [("wellbeing", "wellbeing"), ("age", "age")] {
+            spec.variable_mappings.push(MappingResult {
+                prereg_var: var.to_string(),
+                resolved_to: Some(resolved.to_string()),
+                candidates: Vec::new(),
+                resolution_kind: ResolutionKind::Direct,
+                derived_sources: Vec::new(),
+                reason: None,
+            });
+        }
+        spec
+    }
+
+    #[test]
+    fn identical_specs_have_an_empty_diff() {
+        let spec = base_spec();
+        assert!(diff_specs(&spec, &spec.clone()).unchanged);
+    }
+
+    #[test]
+    fn diff_reports_only_the_changed_model_fields_and_mapping() {
+        let before = base_spec();
+        let mut after = before.clone();
+        let m1 = &mut after.models.main[0];
+        m1.controls = vec!["age".to_string(), "gender".to_string()];
+        m1.formula = "wellbeing ~ condition + age + gender".to_string();
+        after.variable_mappings[1].resolved_to = Some("age_years".to_string());
+
+        let diff = diff_specs(&before, &after);
+        assert!(!diff.unchanged);
+        assert!(diff.models_added.is_empty() && diff.models_removed.is_empty());
+        assert_eq!(
+            diff.models_modified,
+            vec![ModelChange {
+                group: "main".to_string(),
+                id: "m1".to_string(),
+                changes: vec![
+                    FieldChange {
+                        field: "controls".to_string(),
+                        before: json!(["age"]),
+                        after: json!(["age", "gender"]),
+                    },
+                    FieldChange {
+                        field: "formula".to_string(),
+                        before: json!("wellbeing ~ condition + age"),
+                        after: json!("wellbeing ~ condition + age + gender"),
+                    },
+                ],
+            }]
+        );
+        assert_eq!(
+            diff.mapping_changes,
+            vec![MappingDiff {
+                prereg_var: "age".to_string(),
+                before: Some("age".to_string()),
+                after: Some("age_years".to_string()),
+            }]
+        );
+        assert!(diff.warnings_added.is_empty() && diff.warnings_resolved.is_empty());
+        assert!(diff.columns_added.is_empty() && diff.columns_removed.is_empty());
+        assert!(diff.input_changes.is_empty());
+    }
+}
@@ -0,0 +1,129 @@
+use regex::Regex;
+
+use crate::prereg::extract::classify_exclusion;
+use crate::prereg::types::ExclusionRule;
+use crate::qsf::normalize::DURATION_COLUMN;
+use crate::qsf::types::QsfSurveySpec;
+use crate::util::text::clean_names;
+
+use super::attention_checks::{attention_check_filter, r_value};
+use super::mapping::map_variable;
+use super::types::{WarningItem, WarningSeverity};
+
+pub const EXCLUSION_VARIABLE_UNRESOLVED: &str = "EXCLUSION_VARIABLE_UNRESOLVED";
+
+/// Executable `dplyr::filter()` step for one preregistered exclusion, written against the
+/// cleaned column names. Rules whose column is not in the survey become a TODO and an
+/// `EXCLUSION_VARIABLE_UNRESOLVED` warning; unrecognised prose stays a plain TODO.
+pub fn exclusion_filter(
+    rule: &ExclusionRule,
+    qsf: &QsfSurveySpec,
+    warnings: &mut Vec<WarningItem>,
+) -> String {
+    // Specs saved before rules were classified carry the generic `filter` type.
+    let (rule_type, variable) = if rule.rule_type == "filter" {
+        let (kind, variable) = classify_exclusion(&rule.criterion);
+        (kind.to_string(), rule.variable.clone().or(variable))
+    } else {
+        (rule.rule_type.clone(), rule.variable.clone())
+    };
+    let filter = match rule_type.as_str() {
+        "duration" => duration_filter(&rule.criterion, qsf),
+        "attention_check" => attention_check_filter(&rule.id, &rule.criterion, qsf, warnings),
+        "duplicate" => variable
+            .as_deref()
+            .and_then(|v| survey_column(v, qsf))
+            .map(|column| format!("df <- df %>% dplyr::filter(!duplicated({column}))")),
+        "consent" => consent_filter(variable.as_deref().unwrap_or("consent"), qsf),
+        "completion" => meta_column(variable.as_deref().unwrap_or("Finished"), qsf)
+            .map(|column| format!("df <- df %>% dplyr::filter({column} == 1)")),
+        _ => return format!("# TODO: apply exclusion: {}", rule.criterion),
+    };
+    filter.unwrap_or_else(|| {
+        let variable = variable.unwrap_or_else(|| "attention check question".to_string());
+        warnings.push(WarningItem {
+            code: EXCLUSION_VARIABLE_UNRESOLVED.to_string(),
+            message: format!(
+                "Exclusion {} ({rule_type}) needs '{variable}', which was not found in the survey; the filter is left as a TODO.",
+                rule.id
+            ),
+            details: serde_json::json!({
+              "exclusionId": rule.id,
+              "ruleType": rule_type,
+              "variable": variable,
+              "criterion": rule.criterion,
+            }),
+            severity: WarningSeverity::Warning,
+        });
+        format!(
+            "# TODO: apply exclusion ({rule_type}, no column for {variable}): {}",
+            rule.criterion
+        )
+    })
+}
+
+/// Cleaned name of a Qualtrics response-metadata column present in this export.
+fn meta_column(name: &str, qsf: &QsfSurveySpec) -> Option<String> {
+    qsf.columns
+        .iter()
+        .find(|c| c.meta && c.name == name)
+        .map(|c| c.clean_name.clone())
+}
+
+/// Cleaned name of the survey column for `name`: metadata, the IP address Qualtrics adds to
+/// non-anonymised exports, or a question/embedded field resolved by the variable mapper.
+fn survey_column(name: &str, qsf: &QsfSurveySpec) -> Option<String> {
+    if let Some(column) = meta_column(name, qsf) {
+        return Some(column);
+    }
+    if name == "IPAddress" {
+        return Some(clean_names(name));
+    }
+    map_variable(name, qsf)
+        .resolved_to
+        .map(|key| clean_names(&key))
+}
+
+/// Keeps respondents at or above the duration threshold ("finished in under 2 minutes").
+fn duration_filter(criterion: &str, qsf: &QsfSurveySpec) -> Option<String> {
+    let column = meta_column(DURATION_COLUMN, qsf)?;
+    let re = Regex::new(
+        r"(?i)\b(?:duration|time|finish\w*|complet\w*|respon\w*)\b.*?(<=|<|under|less than|fewer than|faster than|shorter than|below)\s*(\d+(?:\.\d+)?)\s*(seconds?|secs?|s|minutes?|mins?|m)?\b",
+    )
+    .expect("regex");
+    let cap = re.captures(criterion)?;
+    let mut threshold = cap[2].parse::<f64>().ok()?;
+    let unit = cap
+        .get(3)
+        .map(|u| u.as_str().to_lowercase())
+        .unwrap_or_default();
+    if unit.starts_with('m') {
+        threshold *= 60.0;
+    }
+    let op = if &cap[1] == "<=" { ">" } else { ">=" };
+    Some(format!(
+        "df <- df %>% dplyr::filter({column} {op} {threshold})"
+    ))
+}
+
+/// Keeps respondents who picked the agreeing choice on the consent question.
+fn consent_filter(variable: &str, qsf: &QsfSurveySpec) -> Option<String> {
+    let agrees = Regex::new(r"(?i)^\s*(?:yes|i (?:agree|consent)|agree|consent)\b").expect("regex");
+    let question = map_variable(variable, qsf)
+        .resolved_to
+        .and_then(|key| qsf.questions.iter().find(|q| q.export_tag == key))
+        .or_else(|| {
+            qsf.questions
+                .iter()
+                .find(|q| q.export_tag.to_lowercase().contains("consent"))
+        })?;
+    let choice = question
+        .choices
+        .iter()
+        .find(|c| agrees.is_match(&c.label))?;
+    Some(format!(
+        "df <- df %>% dplyr::filter({} == {})",
+        clean_names(&question.export_tag),
+        r_value(choice.data_value())
+    ))
+}
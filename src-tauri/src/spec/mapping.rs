@@ -1,22 +1,97 @@
 use strsim::normalized_levenshtein;
 
+use std::collections::BTreeSet;
+
 use crate::qsf::types::QsfSurveySpec;
-use crate::util::text::normalize_token;
+use crate::util::text::{is_concept_stopword, normalize_token};
 
-use super::types::{MappingCandidate, MappingResult, WarningItem};
+use super::aliases::{find_alias, VariableAlias};
+use super::types::{
+    MappingCandidate, MappingResult, MatchedOn, ResolutionKind, ScoreBreakdown, WarningItem,
+    WarningSeverity,
+};
 
 const RESOLVE_THRESHOLD: f64 = 0.95;
 const CANDIDATE_MIN_SCORE: f64 = 0.75;
+// Free-text "Other" columns rarely hold the analysed variable.
+const TEXT_ENTRY_PENALTY: f64 = 0.5;
+// Ceiling for a pure question-text match: enough to suggest, never enough to auto-resolve.
+const QUESTION_TEXT_WEIGHT: f64 = 0.85;
+// Shortest token that may match another by prefix ("fair" ~ "fairness").
+const STEM_PREFIX_MIN: usize = 4;
+// Lift for randomizer-assigned embedded data when the variable names a condition.
+const RANDOMIZED_CONDITION_BOOST: f64 = 0.2;
+const LEVENSHTEIN_WEIGHT: f64 = 0.55;
+const TOKEN_OVERLAP_WEIGHT: f64 = 0.45;
+const CONTAINS_BOOST: f64 = 0.1;
+// A name equal to the variable only after canonicalizing tokens scores just under an exact match.
+const CANONICAL_MATCH_SCORE: f64 = 0.99;
+// Largest score gap between the two columns of a counterbalanced pair.
+const COUNTERBALANCE_SCORE_GAP: f64 = 0.08;
+// Longest question text quoted in a candidate's reason.
+const REASON_TEXT_MAX: usize = 60;
 
 pub fn map_variable(prereg_var: &str, qsf: &QsfSurveySpec) -> MappingResult {
+    map_variable_with_aliases(prereg_var, qsf, &[])
+}
+
+/// Like [`map_variable`], but a project alias naming a column of this survey wins outright.
+pub fn map_variable_with_aliases(
+    prereg_var: &str,
+    qsf: &QsfSurveySpec,
+    aliases: &[VariableAlias],
+) -> MappingResult {
     let all_candidates = build_candidates(prereg_var, qsf);
-    let mut resolved = all_candidates
-        .iter()
-        .find(|c| c.score >= RESOLVE_THRESHOLD)
-        .map(|c| c.key.clone());
-    if resolved.is_none() && has_counterbalanced_pair(prereg_var, &all_candidates) {
-        // Auto-resolve to a derived variable keyed by prereg variable name.
-        resolved = Some(prereg_var.to_string());
+    let aliased = find_alias(aliases, prereg_var).and_then(|alias| {
+        all_candidates
+            .iter()
+            .find(|c| c.key.eq_ignore_ascii_case(&alias.column))
+    });
+    if let Some(column) = aliased {
+        let reason = format!(
+            "project alias dictionary maps '{prereg_var}' to '{}'",
+            column.key
+        );
+        return MappingResult {
+            prereg_var: prereg_var.to_string(),
+            resolved_to: Some(column.key.clone()),
+            candidates: vec![MappingCandidate {
+                key: column.key.clone(),
+                score: 1.0,
+                matched_on: MatchedOn::ExportTag,
+                breakdown: ScoreBreakdown {
+                    adjustment: 1.0,
+                    ..ScoreBreakdown::default()
+                },
+                reason: reason.clone(),
+            }],
+            resolution_kind: ResolutionKind::Alias,
+            derived_sources: Vec::new(),
+            reason: Some(reason),
+        };
+    }
+    let direct = all_candidates.iter().find(|c| c.score >= RESOLVE_THRESHOLD);
+    let mut resolved = direct.map(|c| c.key.clone());
+    let mut reason = direct.map(|c| c.reason.clone());
+    let mut resolution_kind = if resolved.is_some() {
+        ResolutionKind::Direct
+    } else {
+        ResolutionKind::Unresolved
+    };
+    let mut derived_sources = Vec::new();
+    if resolved.is_none() {
+        if let Some(sources) = counterbalanced_pair_sources(prereg_var, &all_candidates) {
+            // Auto-resolve to a derived variable keyed by prereg variable name.
+            resolved = Some(prereg_var.to_string());
+            resolution_kind = ResolutionKind::DerivedMerge;
+            reason = Some(format!(
+                "counterbalanced columns '{}' and '{}' share the base name '{}' and score within {COUNTERBALANCE_SCORE_GAP} of each other, so they are merged into '{prereg_var}'",
+                sources[0],
+                sources[1],
+                strip_order_suffix(&normalize_token(&sources[0]))
+            ));
+            derived_sources = sources;
+        }
     }
     let mut candidates = all_candidates
         .iter()
@@ -33,6 +108,9 @@ pub fn map_variable(prereg_var: &str, qsf: &QsfSurveySpec) -> MappingResult {
         prereg_var: prereg_var.to_string(),
         resolved_to: resolved,
         candidates: candidates.into_iter().take(5).collect(),
+        resolution_kind,
+        derived_sources,
+        reason,
     }
 }
 
@@ -40,16 +118,27 @@ pub fn unresolved_warning(mapping: &MappingResult) -> Option<WarningItem> {
     if mapping.resolved_to.is_some() {
         return None;
     }
+    let top = mapping.candidates.first();
+    let closest = top
+        .map(|c| {
+            format!(
+                " Closest candidate: '{}' ({:.2}), {}.",
+                c.key, c.score, c.reason
+            )
+        })
+        .unwrap_or_default();
     Some(WarningItem {
         code: "UNRESOLVED_VARIABLE".to_string(),
         message: format!(
-            "Unable to map prereg variable '{}' to QSF column.",
+            "Unable to map prereg variable '{}' to QSF column.{closest}",
             mapping.prereg_var
         ),
         details: serde_json::json!({
           "preregVar": mapping.prereg_var,
           "candidates": mapping.candidates,
+          "topCandidateReason": top.map(|c| c.reason.as_str()),
         }),
+        severity: WarningSeverity::Error,
     })
 }
 
@@ -59,25 +148,125 @@ fn build_candidates(prereg_var: &str, qsf: &QsfSurveySpec) -> Vec<MappingCandida
     // Score each stable output column (export_tag / embedded data), using aliases
     // (QID + question text) only for matching, never as returned keys.
     let mut out: Vec<MappingCandidate> = Vec::new();
-    for q in &qsf.questions {
-        let aliases = vec![
-            q.export_tag.clone(),
-            q.qualtrics_qid.clone(),
-            q.question_text.clone(),
+    let question_words = qsf
+        .questions
+        .iter()
+        .map(|q| content_words(&q.question_text))
+        .collect::<Vec<BTreeSet<String>>>();
+    let weights = idf_weights(&content_words(prereg_var), &question_words);
+    for (q, words) in qsf.questions.iter().zip(&question_words) {
+        if qsf.is_renamed_duplicate(&q.export_tag) {
+            continue;
+        }
+        if q.multi_select {
+            // Only the per-choice indicator columns exist in the export.
+            for (column, choice) in q.answer_columns().into_iter().zip(&q.choices) {
+                let by_tag = alias_score(prereg_var, &n_prereg, &column);
+                let by_label = alias_score(prereg_var, &n_prereg, &choice.label);
+                out.push(if by_label.0 > by_tag.0 {
+                    candidate(&column, by_label, MatchedOn::QuestionText, &choice.label)
+                } else {
+                    candidate(&column, by_tag, MatchedOn::ExportTag, &column)
+                });
+            }
+            continue;
+        }
+        let lexical = QUESTION_TEXT_WEIGHT * weighted_text_overlap(&weights, words);
+        let by_text = alias_score(prereg_var, &n_prereg, &q.question_text);
+        let mut best = candidate(
+            &q.export_tag,
+            alias_score(prereg_var, &n_prereg, &q.export_tag),
+            MatchedOn::ExportTag,
+            &q.export_tag,
+        );
+        let others = [
+            candidate(
+                &q.export_tag,
+                alias_score(prereg_var, &n_prereg, &q.qualtrics_qid),
+                MatchedOn::Qid,
+                &q.qualtrics_qid,
+            ),
+            if lexical > by_text.0 {
+                MappingCandidate {
+                    key: q.export_tag.clone(),
+                    score: lexical,
+                    matched_on: MatchedOn::QuestionText,
+                    breakdown: ScoreBreakdown {
+                        token_overlap: lexical,
+                        ..ScoreBreakdown::default()
+                    },
+                    reason: format!(
+                        "question text '{}' shares key words with the variable name",
+                        quoted(&q.question_text)
+                    ),
+                }
+            } else {
+                candidate(
+                    &q.export_tag,
+                    by_text,
+                    MatchedOn::QuestionText,
+                    &q.question_text,
+                )
+            },
         ];
-        let score = best_alias_score(prereg_var, &n_prereg, &aliases);
-        out.push(MappingCandidate {
-            key: q.export_tag.clone(),
-            score,
-        });
+        for other in others {
+            if other.score > best.score {
+                best = other;
+            }
+        }
+        out.push(best);
     }
+    let randomized = if names_condition(&n_prereg) {
+        qsf.embedded_data_fields
+            .iter()
+            .filter(|f| f.is_randomized)
+            .map(|f| f.name.as_str())
+            .collect::<Vec<&str>>()
+    } else {
+        Vec::new()
+    };
     for ed in &qsf.embedded_data {
-        let aliases = vec![ed.clone()];
-        let score = best_alias_score(prereg_var, &n_prereg, &aliases);
-        out.push(MappingCandidate {
-            key: ed.clone(),
-            score,
-        });
+        let mut c = candidate(
+            ed,
+            alias_score(prereg_var, &n_prereg, ed),
+            MatchedOn::ExportTag,
+            ed,
+        );
+        if randomized.iter().any(|name| name.eq_ignore_ascii_case(ed)) {
+            // A lone randomized field is the condition whatever it is called; with several,
+            // they only outrank similarly named questions.
+            let (score, note) = if randomized.len() == 1 {
+                (
+                    c.score.max(RESOLVE_THRESHOLD),
+                    "the survey's only randomizer-assigned field",
+                )
+            } else {
+                (
+                    (c.score + RANDOMIZED_CONDITION_BOOST).clamp(CANDIDATE_MIN_SCORE, 1.0),
+                    "boosted as a randomizer-assigned field",
+                )
+            };
+            adjust(&mut c, score, note);
+        }
+        out.push(c);
+    }
+    for column in &qsf.text_entry_columns {
+        if qsf.is_renamed_duplicate(column) {
+            continue;
+        }
+        out.push(candidate(
+            column,
+            alias_score(prereg_var, &n_prereg, column),
+            MatchedOn::ExportTag,
+            column,
+        ));
+    }
+    let wants_text = n_prereg.ends_with("_text");
+    for c in out.iter_mut() {
+        if !wants_text && c.key.to_ascii_lowercase().ends_with("_text") {
+            let score = c.score * TEXT_ENTRY_PENALTY;
+            adjust(c, score, "penalized as a free-text entry column");
+        }
     }
 
     // Keep highest score per key.
@@ -85,13 +274,16 @@ fn build_candidates(prereg_var: &str, qsf: &QsfSurveySpec) -> Vec<MappingCandida
     for c in out {
         if let Some(existing) = deduped.iter_mut().find(|x| x.key == c.key) {
             if c.score > existing.score {
-                existing.score = c.score;
+                *existing = c;
             }
         } else {
             deduped.push(c);
         }
     }
 
+    // Qualtrics response metadata (Progress, Status, ...) is never a preregistered measure.
+    deduped.retain(|c| !qsf.is_meta_column(&c.key));
+
     deduped.sort_by(|a, b| {
         b.score
             .partial_cmp(&a.score)
@@ -100,34 +292,151 @@ fn build_candidates(prereg_var: &str, qsf: &QsfSurveySpec) -> Vec<MappingCandida
     deduped
 }
 
-fn best_alias_score(prereg_var: &str, n_prereg: &str, aliases: &[String]) -> f64 {
+/// Question text cut down for quoting in a reason.
+fn quoted(text: &str) -> String {
+    let text = text.split_whitespace().collect::<Vec<&str>>().join(" ");
+    if text.chars().count() <= REASON_TEXT_MAX {
+        return text;
+    }
+    let cut = text.chars().take(REASON_TEXT_MAX).collect::<String>();
+    format!("{}…", cut.trim_end())
+}
+
+fn candidate(
+    key: &str,
+    (score, breakdown): (f64, ScoreBreakdown),
+    matched_on: MatchedOn,
+    matched_text: &str,
+) -> MappingCandidate {
+    let field = match matched_on {
+        MatchedOn::ExportTag => "export tag",
+        MatchedOn::QuestionText => "question text",
+        MatchedOn::Qid => "question ID",
+    };
+    let mut reason = format!("matched {field} '{}'", quoted(matched_text));
+    if !breakdown.alias_used.is_empty() {
+        reason.push_str(&format!(
+            " after canonicalizing {}",
+            breakdown.alias_used.join(", ")
+        ));
+    }
+    MappingCandidate {
+        key: key.to_string(),
+        score,
+        matched_on,
+        breakdown,
+        reason,
+    }
+}
+
+/// Moves a candidate to `score`, booking the change as an adjustment and noting why.
+fn adjust(c: &mut MappingCandidate, score: f64, note: &str) {
+    if score == c.score {
+        return;
+    }
+    c.breakdown.adjustment += score - c.score;
+    c.score = score;
+    c.reason.push_str(&format!("; {note}"));
+}
+
+/// Canonical substitutions (`label→condition`) made in either normalized name.
+fn canonical_substitutions(names: [&str; 2]) -> Vec<String> {
+    let mut out: Vec<String> = Vec::new();
+    for token in names.iter().flat_map(|name| name.split('_')) {
+        let canonical = canonical_token(token);
+        let note = format!("{token}→{canonical}");
+        if !token.is_empty() && canonical != token && !out.contains(&note) {
+            out.push(note);
+        }
+    }
+    out
+}
+
+/// Name similarity of `alias` to the variable, with the parts it is made of.
+fn alias_score(prereg_var: &str, n_prereg: &str, alias: &str) -> (f64, ScoreBreakdown) {
+    let exact = ScoreBreakdown {
+        levenshtein: LEVENSHTEIN_WEIGHT,
+        token_overlap: TOKEN_OVERLAP_WEIGHT,
+        ..ScoreBreakdown::default()
+    };
+    if alias.eq_ignore_ascii_case(prereg_var) {
+        return (1.0, exact);
+    }
+    let n_alias = normalize_token(alias);
     let c_prereg = canonicalize_norm(n_prereg);
-    let mut best = 0.0_f64;
-    for alias in aliases {
-        let score = if alias.eq_ignore_ascii_case(prereg_var) {
-            1.0
+    let c_alias = canonicalize_norm(&n_alias);
+    let alias_used = canonical_substitutions([n_prereg, &n_alias]);
+    if c_alias == c_prereg {
+        return (
+            CANONICAL_MATCH_SCORE,
+            ScoreBreakdown {
+                alias_used,
+                adjustment: CANONICAL_MATCH_SCORE - 1.0,
+                ..exact
+            },
+        );
+    }
+    let mut breakdown = ScoreBreakdown {
+        levenshtein: LEVENSHTEIN_WEIGHT * normalized_levenshtein(&c_alias, &c_prereg),
+        token_overlap: TOKEN_OVERLAP_WEIGHT * token_overlap(&c_alias, &c_prereg),
+        contains_boost: if c_alias.contains(&c_prereg) || c_prereg.contains(&c_alias) {
+            CONTAINS_BOOST
         } else {
-            let n_alias = normalize_token(alias);
-            let c_alias = canonicalize_norm(&n_alias);
-            if c_alias == c_prereg {
-                0.99
-            } else {
-                let lev = normalized_levenshtein(&c_alias, &c_prereg);
-                let overlap = token_overlap(&c_alias, &c_prereg);
-                let contains_boost = if c_alias.contains(&c_prereg) || c_prereg.contains(&c_alias) {
-                    0.1
-                } else {
-                    0.0
-                };
-                let prefix_boost = token_prefix_boost(&c_alias, &c_prereg);
-                (0.55 * lev + 0.45 * overlap + contains_boost + prefix_boost).min(1.0)
-            }
-        };
-        if score > best {
-            best = score;
-        }
+            0.0
+        },
+        prefix_boost: token_prefix_boost(&c_alias, &c_prereg),
+        alias_used,
+        adjustment: 0.0,
+    };
+    let raw = breakdown.total();
+    if raw > 1.0 {
+        breakdown.adjustment = 1.0 - raw;
+    }
+    (raw.min(1.0), breakdown)
+}
+
+/// Content words of an identifier or question text, minus concept stopwords.
+fn content_words(text: &str) -> BTreeSet<String> {
+    normalize_token(text)
+        .split('_')
+        .filter(|w| w.len() > 1 && !w.chars().all(|c| c.is_ascii_digit()))
+        .filter(|w| !is_concept_stopword(w))
+        .map(str::to_string)
+        .collect()
+}
+
+fn words_match(a: &str, b: &str) -> bool {
+    a == b || (a.len().min(b.len()) >= STEM_PREFIX_MIN && (a.starts_with(b) || b.starts_with(a)))
+}
+
+/// Smoothed IDF weight of each variable word across the survey's question texts (prefix
+/// matches count), so words many questions share ("please", "rate") count for little.
+fn idf_weights(prereg_words: &BTreeSet<String>, docs: &[BTreeSet<String>]) -> Vec<(String, f64)> {
+    let n = docs.len() as f64;
+    prereg_words
+        .iter()
+        .map(|word| {
+            let df = docs
+                .iter()
+                .filter(|doc| doc.iter().any(|w| words_match(w, word)))
+                .count() as f64;
+            (word.clone(), ((1.0 + n) / (1.0 + df)).ln() + 1.0)
+        })
+        .collect()
+}
+
+/// Share of the variable's IDF weight whose words appear in one question's text.
+fn weighted_text_overlap(weights: &[(String, f64)], question_words: &BTreeSet<String>) -> f64 {
+    let total = weights.iter().map(|(_, w)| w).sum::<f64>();
+    if total == 0.0 {
+        return 0.0;
     }
-    best
+    let matched = weights
+        .iter()
+        .filter(|(word, _)| question_words.iter().any(|w| words_match(w, word)))
+        .map(|(_, w)| w)
+        .sum::<f64>();
+    matched / total
 }
 
 fn token_overlap(a: &str, b: &str) -> f64 {
@@ -174,6 +483,13 @@ fn canonicalize_norm(norm: &str) -> String {
         .join("_")
 }
 
+/// True when a token of the normalized variable name is in the "condition" family.
+fn names_condition(n_prereg: &str) -> bool {
+    n_prereg
+        .split('_')
+        .any(|token| canonical_token(token) == "condition")
+}
+
 fn canonical_token(token: &str) -> &str {
     match token {
         "cond" | "condition" | "group" | "assignment" | "arm" | "label" | "lbl" => "condition",
@@ -186,9 +502,14 @@ fn canonical_token(token: &str) -> &str {
     }
 }
 
-fn has_counterbalanced_pair(prereg_var: &str, candidates: &[MappingCandidate]) -> bool {
+/// The two order-suffixed columns (e.g. `consent_a1`/`consent_b1`) that together hold
+/// `prereg_var`, sorted; `None` unless a close-scoring pair shares the variable's base name.
+fn counterbalanced_pair_sources(
+    prereg_var: &str,
+    candidates: &[MappingCandidate],
+) -> Option<Vec<String>> {
     if candidates.len() < 2 {
-        return false;
+        return None;
     }
     let prereg_norm = normalize_token(prereg_var);
     let top = candidates
@@ -197,14 +518,14 @@ fn has_counterbalanced_pair(prereg_var: &str, candidates: &[MappingCandidate]) -
         .take(4)
         .collect::<Vec<&MappingCandidate>>();
     if top.len() < 2 {
-        return false;
+        return None;
     }
 
     for i in 0..top.len() {
         for j in (i + 1)..top.len() {
             let a = top[i];
             let b = top[j];
-            if (a.score - b.score).abs() > 0.08 {
+            if (a.score - b.score).abs() > COUNTERBALANCE_SCORE_GAP {
                 continue;
             }
             let a_norm = normalize_token(&a.key);
@@ -218,11 +539,13 @@ fn has_counterbalanced_pair(prereg_var: &str, candidates: &[MappingCandidate]) -
                 || a_base.contains(&prereg_norm)
                 || prereg_norm.contains(&a_base)
             {
-                return true;
+                let mut pair = vec![a.key.clone(), b.key.clone()];
+                pair.sort();
+                return Some(pair);
             }
         }
     }
-    false
+    None
 }
 
 fn strip_order_suffix(value: &str) -> String {
@@ -232,8 +555,9 @@ fn strip_order_suffix(value: &str) -> String {
 
 #[cfg(test)]
 mod tests {
-    use super::map_variable;
+    use super::{map_variable, unresolved_warning, CANDIDATE_MIN_SCORE};
     use crate::qsf::types::{QsfChoice, QsfEmbeddedData, QsfQuestion, QsfSurveySpec};
+    use crate::spec::types::MatchedOn;
     use std::collections::HashMap;
 
     #[test]
@@ -248,17 +572,191 @@ mod tests {
                 choices: vec![QsfChoice {
                     value: "1".to_string(),
                     label: "Low".to_string(),
+                    text_entry: false,
+                    recode_value: None,
                 }],
+                matrix_stem: None,
+                loop_iteration: None,
+                multi_select: false,
             }],
             embedded_data: vec![],
             embedded_data_fields: vec![QsfEmbeddedData {
                 name: "participant_id".to_string(),
                 default_value: None,
+                observed_values: vec![],
+                is_randomized: false,
+                possible_values: vec![],
             }],
             expected_columns: vec!["income_label".to_string(), "participant_id".to_string()],
             label_map: HashMap::new(),
+            text_entry_columns: vec![],
+            columns: vec![],
+            warnings: vec![],
         };
         let result = map_variable("income_condition", &qsf);
         assert!(result.candidates.iter().any(|c| c.key == "income_label"));
+        for c in &result.candidates {
+            assert!((c.breakdown.total() - c.score).abs() < 1e-9, "{c:?}");
+        }
+        let top = &result.candidates[0];
+        assert_eq!(top.key, "income_label");
+        assert_eq!(
+            top.breakdown.alias_used,
+            vec!["label→condition".to_string()]
+        );
+        assert!(top.reason.contains("after canonicalizing label→condition"));
+        assert_eq!(result.reason.as_deref(), Some(top.reason.as_str()));
+
+        let unresolved = map_variable("household_size", &qsf);
+        assert!(unresolved.resolved_to.is_none());
+        let warning = unresolved_warning(&unresolved).expect("warning");
+        match unresolved.candidates.first() {
+            Some(top) => {
+                assert_eq!(warning.details["topCandidateReason"], top.reason.as_str());
+                assert!(warning.message.contains("Closest candidate"));
+            }
+            None => assert!(warning.details["topCandidateReason"].is_null()),
+        }
+    }
+
+    #[test]
+    fn question_text_words_suggest_opaque_export_tags() {
+        let question = |qid: &str, tag: &str, text: &str| QsfQuestion {
+            qualtrics_qid: qid.to_string(),
+            export_tag: tag.to_string(),
+            question_text: text.to_string(),
+            question_type: "MC".to_string(),
+            choices: vec![],
+            matrix_stem: None,
+            loop_iteration: None,
+            multi_select: false,
+        };
+        let qsf = crate::qsf::normalize::build_spec(
+            "S".to_string(),
+            vec![
+                question("QID21", "Q21", "Please indicate your age."),
+                question("QID22", "Q22", "Please rate how you feel right now."),
+                question("QID23", "Q23", "How fair did you perceive the offer to be?"),
+                question("QID24", "Q24", "Please describe how you perceive the task."),
+            ],
+            vec![],
+        );
+
+        let fairness = map_variable("perceived_fairness", &qsf);
+        let top = &fairness.candidates[0];
+        assert_eq!(top.key, "Q23");
+        assert_eq!(top.matched_on, MatchedOn::QuestionText);
+        assert!(top.score > CANDIDATE_MIN_SCORE);
+        // A text match is a suggestion only; the user still confirms it.
+        assert_eq!(fairness.resolved_to, None);
+        assert!(fairness
+            .candidates
+            .iter()
+            .all(|c| c.key != "Q24" || c.score < top.score));
+
+        let by_tag = map_variable("Q21", &qsf);
+        assert_eq!(by_tag.resolved_to.as_deref(), Some("Q21"));
+        assert_eq!(by_tag.candidates[0].matched_on, MatchedOn::ExportTag);
+        let by_qid = map_variable("QID22", &qsf);
+        assert_eq!(by_qid.candidates[0].key, "Q22");
+        assert_eq!(by_qid.candidates[0].matched_on, MatchedOn::Qid);
+    }
+
+    #[test]
+    fn text_entry_columns_are_penalized_unless_requested() {
+        let qsf = crate::qsf::normalize::build_spec(
+            "S".to_string(),
+            vec![QsfQuestion {
+                qualtrics_qid: "QID1".to_string(),
+                export_tag: "gender".to_string(),
+                question_text: "Gender".to_string(),
+                question_type: "MC".to_string(),
+                choices: vec![QsfChoice {
+                    value: "4".to_string(),
+                    label: "Other".to_string(),
+                    text_entry: true,
+                    recode_value: None,
+                }],
+                matrix_stem: None,
+                loop_iteration: None,
+                multi_select: false,
+            }],
+            vec![],
+        );
+        let gender = map_variable("gender", &qsf);
+        assert_eq!(gender.resolved_to.as_deref(), Some("gender"));
+
+        let other = map_variable("gender_4", &qsf);
+        assert_ne!(other.resolved_to.as_deref(), Some("gender_4_TEXT"));
+
+        let explicit = map_variable("gender_4_TEXT", &qsf);
+        assert_eq!(explicit.resolved_to.as_deref(), Some("gender_4_TEXT"));
+    }
+
+    #[test]
+    fn meta_columns_are_not_mapping_candidates() {
+        let qsf = crate::qsf::normalize::build_spec(
+            "S".to_string(),
+            vec![QsfQuestion {
+                qualtrics_qid: "QID1".to_string(),
+                export_tag: "task_progress".to_string(),
+                question_text: "How far along are you with the task?".to_string(),
+                question_type: "TE".to_string(),
+                choices: vec![],
+                matrix_stem: None,
+                loop_iteration: None,
+                multi_select: false,
+            }],
+            vec![],
+        );
+        let result = map_variable("progress", &qsf);
+        assert!(result.candidates.iter().all(|c| c.key != "Progress"));
+        assert!(result.candidates.iter().any(|c| c.key == "task_progress"));
+    }
+
+    #[test]
+    fn piped_field_names_in_question_text_improve_scores() {
+        let raw_text = "<b>${e://Field/advisor&#95;type}</b>&nbsp;";
+        let raw = format!(
+            r#"{{
+      "SurveyEntry": {{"SurveyName": "T"}},
+      "SurveyElements": [
+        {{"Element":"SQ","Payload":{{"QuestionID":"QID12","DataExportTag":"Q12","QuestionText":"{}","QuestionType":{{"Type":"MC"}}}}}}
+      ]
+    }}"#,
+            raw_text
+        );
+        let cleaned = crate::qsf::parse::parse_qsf_json(&raw).expect("parse qsf");
+        let uncleaned = crate::qsf::normalize::build_spec(
+            "T".to_string(),
+            vec![QsfQuestion {
+                qualtrics_qid: "QID12".to_string(),
+                export_tag: "Q12".to_string(),
+                question_text: raw_text.to_string(),
+                question_type: "MC".to_string(),
+                choices: vec![],
+                matrix_stem: None,
+                loop_iteration: None,
+                multi_select: false,
+            }],
+            vec![],
+        );
+        let score = |qsf: &QsfSurveySpec| {
+            map_variable("advisor_type", qsf)
+                .candidates
+                .iter()
+                .find(|c| c.key == "Q12")
+                .map(|c| c.score)
+                .unwrap_or(0.0)
+        };
+        assert_eq!(cleaned.questions[0].question_text, "advisor_type");
+        assert!(score(&cleaned) > score(&uncleaned));
+        assert_eq!(
+            map_variable("advisor_type", &cleaned)
+                .resolved_to
+                .as_deref(),
+            Some("Q12")
+        );
+        assert_eq!(map_variable("advisor_type", &uncleaned).resolved_to, None);
     }
 }
@@ -1,4 +1,13 @@
+pub mod aliases;
+pub mod attention_checks;
 pub mod builder;
+pub mod codebook;
+pub mod contract;
+pub mod diff;
+pub mod exclusions;
 pub mod mapping;
+pub mod persist;
 pub mod types;
 pub mod validate;
+pub mod value_labels;
+pub mod warnings;
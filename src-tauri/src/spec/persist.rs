@@ -0,0 +1,259 @@
+use chrono::Utc;
+use serde::Serialize;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::render::helpers::{ensure_dir, write_files_atomically};
+
+use super::types::{AnalysisSpec, WarningItem, WarningSeverity};
+
+pub const SPEC_BACKUP_EXTENSION: &str = "bak";
+/// Folder next to spec.json holding timestamped copies of earlier saves.
+pub const SPEC_HISTORY_DIR: &str = ".spec_history";
+/// Oldest history entries beyond this count are pruned on save.
+pub const SPEC_HISTORY_LIMIT: usize = 20;
+pub const SPEC_RECOVERED_FROM_BACKUP: &str = "SPEC_RECOVERED_FROM_BACKUP";
+
+#[derive(Debug, Serialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct SpecHistoryEntry {
+    /// File name inside `.spec_history`, passed back to `restore_spec_version`.
+    pub version: String,
+    pub saved_at: String,
+    pub size_bytes: u64,
+}
+
+fn backup_path(spec_path: &Path) -> PathBuf {
+    spec_path.with_extension(format!("json.{SPEC_BACKUP_EXTENSION}"))
+}
+
+fn history_dir(spec_path: &Path) -> PathBuf {
+    spec_path.with_file_name(SPEC_HISTORY_DIR)
+}
+
+fn parse_spec(raw: &str) -> Result<AnalysisSpec, String> {
+    serde_json::from_str(raw).map_err(|e| format!("Invalid spec.json: {e}"))
+}
+
+fn unique_path(dir: &Path, stem: &str, ext: &str) -> PathBuf {
+    let mut path = dir.join(format!("{stem}.{ext}"));
+    let mut suffix = 1;
+    while path.exists() {
+        suffix += 1;
+        // Zero-padded so same-millisecond saves still sort in order.
+        path = dir.join(format!("{stem}_{suffix:03}.{ext}"));
+    }
+    path
+}
+
+/// Versions in `.spec_history`, newest first. Names sort by their save timestamp.
+fn history_files(spec_path: &Path) -> Vec<PathBuf> {
+    let mut files: Vec<PathBuf> = fs::read_dir(history_dir(spec_path))
+        .map(|entries| {
+            entries
+                .filter_map(|entry| entry.ok())
+                .map(|entry| entry.path())
+                .filter(|path| {
+                    path.is_file() && path.extension().and_then(|ext| ext.to_str()) == Some("json")
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+    files.sort();
+    files.reverse();
+    files
+}
+
+/// Copies the current spec to `spec.json.bak` and into the history ring. A spec that no
+/// longer parses is not copied, so a good backup is never replaced by a corrupt one.
+fn back_up_current(spec_path: &Path) -> Result<(), String> {
+    let Ok(raw) = fs::read_to_string(spec_path) else {
+        return Ok(());
+    };
+    if parse_spec(&raw).is_err() {
+        return Ok(());
+    }
+    let history = history_dir(spec_path);
+    ensure_dir(&history)?;
+    let stamp = Utc::now().format("spec_%Y%m%dT%H%M%S%3fZ").to_string();
+    write_files_atomically(&[
+        (backup_path(spec_path), raw.clone()),
+        (unique_path(&history, &stamp, "json"), raw),
+    ])?;
+    for stale in history_files(spec_path)
+        .into_iter()
+        .skip(SPEC_HISTORY_LIMIT)
+    {
+        let _ = fs::remove_file(stale);
+    }
+    Ok(())
+}
+
+/// Saves `spec` through a temp file and rename, keeping the previous version as a backup.
+pub fn write_spec(spec_path: &Path, spec: &AnalysisSpec) -> Result<(), String> {
+    let json = serde_json::to_string_pretty(spec).map_err(|e| e.to_string())?;
+    back_up_current(spec_path)?;
+    write_files_atomically(&[(spec_path.to_path_buf(), json)])
+}
+
+/// Reads spec.json, falling back to `spec.json.bak` when it does not parse. The corrupt file
+/// is renamed aside for inspection and the recovered spec carries a warning saying so.
+pub fn read_spec_file(spec_path: &Path) -> Result<AnalysisSpec, String> {
+    let raw = fs::read_to_string(spec_path).map_err(|e| format!("Unable to read spec: {e}"))?;
+    let parse_error = match parse_spec(&raw) {
+        Ok(spec) => return Ok(spec),
+        Err(err) => err,
+    };
+    let backup = backup_path(spec_path);
+    let Some(mut spec) = fs::read_to_string(&backup)
+        .ok()
+        .and_then(|raw| parse_spec(&raw).ok())
+    else {
+        return Err(parse_error);
+    };
+
+    let dir = spec_path.parent().unwrap_or(Path::new("."));
+    let stamp = Utc::now().format("spec.corrupt_%Y%m%dT%H%M%S").to_string();
+    let corrupt = unique_path(dir, &stamp, "json");
+    fs::rename(spec_path, &corrupt)
+        .map_err(|e| format!("Unable to move corrupt spec aside: {e}"))?;
+    fs::copy(&backup, spec_path).map_err(|e| format!("Unable to restore spec backup: {e}"))?;
+
+    spec.warnings.push(WarningItem {
+        code: SPEC_RECOVERED_FROM_BACKUP.to_string(),
+        message: "spec.json could not be read and was restored from the last backup; changes since that save are lost.".to_string(),
+        details: serde_json::json!({
+            "error": parse_error,
+            "corruptFile": corrupt.to_string_lossy(),
+        }),
+        severity: WarningSeverity::Warning,
+    });
+    Ok(spec)
+}
+
+pub fn list_spec_history(spec_path: &Path) -> Vec<SpecHistoryEntry> {
+    history_files(spec_path)
+        .into_iter()
+        .filter_map(|path| {
+            let meta = fs::metadata(&path).ok()?;
+            let saved_at = meta
+                .modified()
+                .map(|time| chrono::DateTime::<Utc>::from(time).to_rfc3339())
+                .unwrap_or_default();
+            Some(SpecHistoryEntry {
+                version: path.file_name()?.to_string_lossy().to_string(),
+                saved_at,
+                size_bytes: meta.len(),
+            })
+        })
+        .collect()
+}
+
+/// Makes a history entry the current spec. The spec being replaced is backed up first, so a
+/// restore can itself be undone.
+pub fn restore_spec_version(spec_path: &Path, version: &str) -> Result<AnalysisSpec, String> {
+    let version = version.trim();
+    if version.is_empty()
+        || version.contains('/')
+        || version.contains('\\')
+        || version.contains("..")
+    {
+        return Err("Spec version must be a history file name.".to_string());
+    }
+    let path = history_dir(spec_path).join(version);
+    let raw =
+        fs::read_to_string(&path).map_err(|_| format!("Spec version {version} not found."))?;
+    let spec = parse_spec(&raw)?;
+    write_spec(spec_path, &spec)?;
+    Ok(spec)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::prereg::types::PreregSpec;
+    use crate::qsf::parse::parse_qsf_json;
+    use crate::spec::builder::build_analysis_spec;
+    use uuid::Uuid;
+
+    fn spec(analysis_id: &str) -> AnalysisSpec {
+        build_analysis_spec(
+            "p",
+            "s",
+            analysis_id,
+            "qsf",
+            "prereg",
+            "q",
+            "p",
+            &parse_qsf_json(r#"{"SurveyEntry": {"SurveyName": "T"}, "SurveyElements": []}"#)
+                .expect("qsf"),
+            &PreregSpec::default(),
+            "apa_v1",
+            "apa",
+            &[],
+        )
+    }
+
+    fn temp_spec_path(label: &str) -> (PathBuf, PathBuf) {
+        let dir = std::env::temp_dir().join(format!("spec-{label}-{}", Uuid::new_v4()));
+        let spec_path = dir.join("analysis").join("spec.json");
+        (dir, spec_path)
+    }
+
+    #[test]
+    fn corrupt_spec_is_recovered_from_backup_and_moved_aside() {
+        let (dir, spec_path) = temp_spec_path("recover");
+        write_spec(&spec_path, &spec("first")).expect("first save");
+        assert!(!backup_path(&spec_path).exists());
+        write_spec(&spec_path, &spec("second")).expect("second save");
+        assert!(backup_path(&spec_path).exists());
+
+        fs::write(&spec_path, "{\"analysisId\": \"sec").expect("truncate");
+        let recovered = read_spec_file(&spec_path).expect("recovered");
+        assert_eq!(recovered.analysis_id, "first");
+        let warning = recovered
+            .warnings
+            .iter()
+            .find(|w| w.code == SPEC_RECOVERED_FROM_BACKUP)
+            .expect("recovery warning");
+        let corrupt = PathBuf::from(warning.details["corruptFile"].as_str().expect("path"));
+        assert_eq!(
+            fs::read_to_string(corrupt).expect("corrupt kept"),
+            "{\"analysisId\": \"sec"
+        );
+        assert_eq!(
+            read_spec_file(&spec_path).expect("restored").analysis_id,
+            "first"
+        );
+
+        fs::write(&spec_path, "not json").expect("corrupt");
+        fs::remove_file(backup_path(&spec_path)).expect("drop backup");
+        let err = read_spec_file(&spec_path).expect_err("no backup");
+        assert!(err.contains("Invalid spec.json"));
+        let _ = fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn history_is_capped_and_older_version_can_be_restored() {
+        let (dir, spec_path) = temp_spec_path("history");
+        for n in 0..SPEC_HISTORY_LIMIT + 3 {
+            write_spec(&spec_path, &spec(&format!("v{n}"))).expect("save");
+        }
+        let history = list_spec_history(&spec_path);
+        assert_eq!(history.len(), SPEC_HISTORY_LIMIT);
+
+        let oldest = history.last().expect("oldest").version.clone();
+        let restored = restore_spec_version(&spec_path, &oldest).expect("restore");
+        assert_eq!(restored.analysis_id, "v2");
+        assert_eq!(read_spec_file(&spec_path).expect("read").analysis_id, "v2");
+        let newest = &list_spec_history(&spec_path)[0].version;
+        let raw = fs::read_to_string(history_dir(&spec_path).join(newest)).expect("newest");
+        assert_eq!(
+            parse_spec(&raw).expect("parse").analysis_id,
+            format!("v{}", SPEC_HISTORY_LIMIT + 2)
+        );
+
+        assert!(restore_spec_version(&spec_path, "../spec.json").is_err());
+        let _ = fs::remove_dir_all(dir);
+    }
+}
@@ -1,7 +1,11 @@
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::BTreeMap;
 
 use crate::llm::types::{LlmModelLock, ModelProvenance};
+use crate::prereg::types::HypothesisSpec;
+use crate::qsf::types::ExpectedColumn;
+
+use super::warnings::default_severity;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
@@ -34,16 +38,44 @@ pub struct DerivedVariableSpec {
     pub definition: String,
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FactorLevelSpec {
+    pub column: String,
+    pub levels: Vec<String>,
+    pub r_code: String,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct DataContractSpec {
     pub source: String,
-    pub id_columns: HashMap<String, String>,
+    pub id_columns: BTreeMap<String, String>,
     pub expected_columns: Vec<String>,
-    pub label_map: HashMap<String, String>,
+    pub label_map: BTreeMap<String, String>,
     pub exclusions: Vec<ExclusionSpec>,
     pub missingness: Option<String>,
     pub derived_variables: Vec<DerivedVariableSpec>,
+    #[serde(default)]
+    pub expected_values: BTreeMap<String, Vec<String>>,
+    #[serde(default)]
+    pub factor_levels: Vec<FactorLevelSpec>,
+    /// Levels of randomizer-assigned embedded-data columns; `r_code` asserts the data holds
+    /// nothing else.
+    #[serde(default)]
+    pub randomized_levels: Vec<FactorLevelSpec>,
+    /// Cleaned names of multi-select indicator columns, whose blanks mean "not selected".
+    #[serde(default)]
+    pub indicator_columns: Vec<String>,
+    /// Free-text columns (e.g. "Other, please specify"), read as character data.
+    #[serde(default)]
+    pub free_text_columns: Vec<String>,
+    /// Expected columns with their `clean_names()` form; meta columns are Qualtrics metadata.
+    #[serde(default)]
+    pub columns: Vec<ExpectedColumn>,
+    /// Per-column value → label maps from QSF choices, with saved overrides merged in.
+    #[serde(default)]
+    pub value_labels: BTreeMap<String, BTreeMap<String, String>>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -57,6 +89,9 @@ pub struct ModelSpec {
     pub interactions: Vec<String>,
     pub formula: String,
     pub unresolved_variables: Vec<String>,
+    /// Resolved weight column (or its `TODO_` token) passed as `weights =` to the fit.
+    #[serde(default)]
+    pub weight_var: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -72,6 +107,8 @@ pub struct ModelsSpec {
 pub struct OutputsSpec {
     pub tables: Vec<String>,
     pub figures: Vec<String>,
+    #[serde(default)]
+    pub model_table_format: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -79,31 +116,177 @@ pub struct OutputsSpec {
 pub struct TemplateBindingsSpec {
     pub template_set: String,
     pub style_profile: String,
-    pub paths: HashMap<String, String>,
+    pub paths: BTreeMap<String, String>,
     pub packages: Vec<String>,
+    /// Restore/init renv in the setup chunk and scaffold `.Rprofile` + `renv/activate.R`.
+    #[serde(default)]
+    pub use_renv: bool,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct MappingCandidate {
     pub key: String,
     pub score: f64,
+    /// Which survey field produced the score, so the UI can explain the suggestion.
+    #[serde(default)]
+    pub matched_on: MatchedOn,
+    #[serde(default)]
+    pub breakdown: ScoreBreakdown,
+    /// Plain-language account of the match, e.g. "matched question text 'Income condition'".
+    #[serde(default)]
+    pub reason: String,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+/// The parts a candidate's score is made of; they add up to `score`.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
+pub struct ScoreBreakdown {
+    /// Weighted edit-distance similarity of the canonicalized names.
+    #[serde(default)]
+    pub levenshtein: f64,
+    /// Weighted share of name tokens in common; for question-text matches, the IDF-weighted
+    /// share of the variable's words found in the text.
+    #[serde(default)]
+    pub token_overlap: f64,
+    /// Added when one canonicalized name contains the other.
+    #[serde(default)]
+    pub contains_boost: f64,
+    /// Added when a token of one name starts with a token of the other.
+    #[serde(default)]
+    pub prefix_boost: f64,
+    /// Canonical token substitutions made before comparing, e.g. `label→condition`.
+    #[serde(default)]
+    pub alias_used: Vec<String>,
+    /// Everything else that moved the score: the cap at 1.0, the free-text penalty, the
+    /// randomized-condition boost and project aliases.
+    #[serde(default)]
+    pub adjustment: f64,
+}
+
+impl ScoreBreakdown {
+    pub fn total(&self) -> f64 {
+        self.levenshtein
+            + self.token_overlap
+            + self.contains_boost
+            + self.prefix_boost
+            + self.adjustment
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MatchedOn {
+    #[default]
+    ExportTag,
+    QuestionText,
+    Qid,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ResolutionKind {
+    /// Matched a single survey column.
+    Direct,
+    /// Resolved to a derived variable that coalesces counterbalanced columns (`derived_sources`).
+    DerivedMerge,
+    /// Set by the user through `resolve_mappings`.
+    Manual,
+    /// Taken from the project's variable alias dictionary.
+    Alias,
+    Unresolved,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", from = "StoredMappingResult")]
 pub struct MappingResult {
     pub prereg_var: String,
     pub resolved_to: Option<String>,
     pub candidates: Vec<MappingCandidate>,
+    pub resolution_kind: ResolutionKind,
+    pub derived_sources: Vec<String>,
+    /// Why `resolved_to` was chosen; unset for unresolved and manual mappings.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+/// On-disk form of [`MappingResult`]; specs written before resolution kinds existed are read
+/// as direct or unresolved from `resolved_to`.
+#[derive(Deserialize)]
 #[serde(rename_all = "camelCase")]
+struct StoredMappingResult {
+    prereg_var: String,
+    resolved_to: Option<String>,
+    #[serde(default)]
+    candidates: Vec<MappingCandidate>,
+    #[serde(default)]
+    resolution_kind: Option<ResolutionKind>,
+    #[serde(default)]
+    derived_sources: Vec<String>,
+    #[serde(default)]
+    reason: Option<String>,
+}
+
+impl From<StoredMappingResult> for MappingResult {
+    fn from(stored: StoredMappingResult) -> Self {
+        let resolution_kind = stored.resolution_kind.unwrap_or(match &stored.resolved_to {
+            Some(r) if !r.starts_with("TODO_") => ResolutionKind::Direct,
+            _ => ResolutionKind::Unresolved,
+        });
+        MappingResult {
+            prereg_var: stored.prereg_var,
+            resolved_to: stored.resolved_to,
+            candidates: stored.candidates,
+            resolution_kind,
+            derived_sources: stored.derived_sources,
+            reason: stored.reason,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum WarningSeverity {
+    /// Blocks rendering until resolved.
+    Error,
+    Warning,
+    Info,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", from = "StoredWarningItem")]
 pub struct WarningItem {
     pub code: String,
     pub message: String,
     pub details: serde_json::Value,
+    pub severity: WarningSeverity,
+}
+
+/// On-disk form of [`WarningItem`]; specs written before severities existed take the
+/// catalog default for their code.
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct StoredWarningItem {
+    code: String,
+    message: String,
+    #[serde(default)]
+    details: serde_json::Value,
+    #[serde(default)]
+    severity: Option<WarningSeverity>,
+}
+
+impl From<StoredWarningItem> for WarningItem {
+    fn from(stored: StoredWarningItem) -> Self {
+        let severity = stored
+            .severity
+            .unwrap_or_else(|| default_severity(&stored.code));
+        WarningItem {
+            code: stored.code,
+            message: stored.message,
+            details: stored.details,
+            severity,
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -116,6 +299,9 @@ pub struct AnalysisSpec {
     pub data_contract: DataContractSpec,
     pub variable_mappings: Vec<MappingResult>,
     pub models: ModelsSpec,
+    /// Preregistered hypotheses, linked to `models` by id.
+    #[serde(default)]
+    pub hypotheses: Vec<HypothesisSpec>,
     pub outputs: OutputsSpec,
     pub template_bindings: TemplateBindingsSpec,
     #[serde(default)]
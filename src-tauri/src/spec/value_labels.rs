@@ -0,0 +1,198 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::qsf::types::QsfSurveySpec;
+use crate::render::helpers::write_string;
+
+use super::types::{AnalysisSpec, WarningItem, WarningSeverity};
+
+pub const VALUE_LABEL_OVERRIDES_FILE: &str = "value_label_overrides.json";
+pub const VALUE_LABEL_OVERRIDES_APPLIED: &str = "VALUE_LABEL_OVERRIDES_APPLIED";
+pub const VALUE_LABEL_OVERRIDE_UNKNOWN_COLUMN: &str = "VALUE_LABEL_OVERRIDE_UNKNOWN_COLUMN";
+
+/// Column → (exported value → label).
+pub type ValueLabels = BTreeMap<String, BTreeMap<String, String>>;
+
+/// Value labels for every question with choices, keyed by export tag and by the value the
+/// choice takes in a numeric export (its recode value when one is set).
+pub fn qsf_value_labels(qsf: &QsfSurveySpec) -> ValueLabels {
+    qsf.questions
+        .iter()
+        .filter(|q| !q.export_tag.trim().is_empty() && !q.choices.is_empty())
+        // Multi-select indicator columns hold 1 or blank, not choice values.
+        .filter(|q| !q.multi_select)
+        .map(|q| {
+            let labels = q
+                .choices
+                .iter()
+                .map(|c| (c.data_value().to_string(), c.label.clone()))
+                .collect();
+            (q.export_tag.clone(), labels)
+        })
+        .collect()
+}
+
+/// Overrides live next to `spec.json` so they follow the analysis they were made for.
+pub fn overrides_path(analysis_root: &Path) -> PathBuf {
+    analysis_root
+        .join("analysis")
+        .join(VALUE_LABEL_OVERRIDES_FILE)
+}
+
+pub fn read_overrides(analysis_root: &Path) -> Result<ValueLabels, String> {
+    let path = overrides_path(analysis_root);
+    if !path.exists() {
+        return Ok(ValueLabels::new());
+    }
+    let raw =
+        fs::read_to_string(&path).map_err(|e| format!("Unable to read {}: {e}", path.display()))?;
+    serde_json::from_str(&raw).map_err(|e| format!("Invalid {VALUE_LABEL_OVERRIDES_FILE}: {e}"))
+}
+
+/// Persists `overrides`, dropping columns without any entries.
+pub fn write_overrides(analysis_root: &Path, overrides: &ValueLabels) -> Result<PathBuf, String> {
+    let cleaned: ValueLabels = overrides
+        .iter()
+        .filter(|(column, labels)| !column.trim().is_empty() && !labels.is_empty())
+        .map(|(column, labels)| (column.trim().to_string(), labels.clone()))
+        .collect();
+    let path = overrides_path(analysis_root);
+    write_string(
+        &path,
+        &serde_json::to_string_pretty(&cleaned).map_err(|e| e.to_string())?,
+    )?;
+    Ok(path)
+}
+
+/// Merges saved overrides over the QSF value labels. A blank label removes that value.
+/// Columns the spec does not know are reported in a warning instead of being dropped quietly.
+pub fn apply_value_label_overrides(spec: &mut AnalysisSpec, overrides: &ValueLabels) {
+    if overrides.is_empty() {
+        return;
+    }
+    let contract = &mut spec.data_contract;
+    let mut applied: Vec<String> = Vec::new();
+    let mut unknown: Vec<String> = Vec::new();
+    for (column, labels) in overrides {
+        let known = contract
+            .value_labels
+            .keys()
+            .chain(contract.expected_columns.iter())
+            .find(|known| known.eq_ignore_ascii_case(column.trim()))
+            .cloned();
+        let Some(known) = known else {
+            unknown.push(column.clone());
+            continue;
+        };
+        let target = contract.value_labels.entry(known.clone()).or_default();
+        for (value, label) in labels {
+            if label.trim().is_empty() {
+                target.remove(value.trim());
+            } else {
+                target.insert(value.trim().to_string(), label.trim().to_string());
+            }
+        }
+        if target.is_empty() {
+            contract.value_labels.remove(&known);
+        }
+        applied.push(known);
+    }
+
+    if !applied.is_empty() {
+        spec.warnings.push(WarningItem {
+            code: VALUE_LABEL_OVERRIDES_APPLIED.to_string(),
+            message: format!(
+                "Saved value label overrides applied to {} column(s).",
+                applied.len()
+            ),
+            details: serde_json::json!({ "columns": applied, "file": VALUE_LABEL_OVERRIDES_FILE }),
+            severity: WarningSeverity::Info,
+        });
+    }
+    if !unknown.is_empty() {
+        spec.warnings.push(WarningItem {
+            code: VALUE_LABEL_OVERRIDE_UNKNOWN_COLUMN.to_string(),
+            message: format!(
+                "Value label overrides reference columns not in the survey: {}.",
+                unknown.join(", ")
+            ),
+            details: serde_json::json!({ "columns": unknown }),
+            severity: WarningSeverity::Warning,
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::prereg::types::PreregSpec;
+    use crate::qsf::parse::parse_qsf_json;
+    use crate::spec::builder::build_analysis_spec;
+    use uuid::Uuid;
+
+    #[test]
+    fn overrides_replace_qsf_labels_and_report_unknown_columns() {
+        let raw = r#"{
+      "SurveyEntry": {"SurveyName": "T"},
+      "SurveyElements": [
+        {"Element":"SQ","Payload":{"QuestionID":"QID1","DataExportTag":"condition","QuestionText":"Condition","QuestionType":{"Type":"MC"},
+          "Choices":{"1":{"Display":"Treatment A"},"2":{"Display":"Treatment B"}}}},
+        {"Element":"SQ","Payload":{"QuestionID":"QID2","DataExportTag":"agree","QuestionText":"Agree?","QuestionType":{"Type":"MC"},
+          "Choices":{"1":{"Display":"No"},"2":{"Display":"Yes"}},"RecodeValues":{"1":"0","2":"1"}}}
+      ]
+    }"#;
+        let qsf = parse_qsf_json(raw).expect("parse qsf");
+        let mut spec = build_analysis_spec(
+            "p",
+            "s",
+            "a",
+            "qsf",
+            "prereg",
+            "q",
+            "p",
+            &qsf,
+            &PreregSpec::default(),
+            "apa_v1",
+            "apa",
+            &[],
+        );
+        let labels = &spec.data_contract.value_labels;
+        assert_eq!(labels["condition"]["1"], "Treatment A");
+        assert_eq!(labels["agree"]["0"], "No");
+        assert_eq!(labels["agree"]["1"], "Yes");
+
+        let overrides: ValueLabels = BTreeMap::from([
+            (
+                "condition".to_string(),
+                BTreeMap::from([("1".to_string(), "Control".to_string())]),
+            ),
+            (
+                "conditon".to_string(),
+                BTreeMap::from([("2".to_string(), "Treatment".to_string())]),
+            ),
+        ]);
+        let root = std::env::temp_dir().join(format!("value-labels-{}", Uuid::new_v4()));
+        write_overrides(&root, &overrides).expect("write overrides");
+        let saved = read_overrides(&root).expect("read overrides");
+        assert_eq!(saved, overrides);
+
+        apply_value_label_overrides(&mut spec, &saved);
+        let condition = &spec.data_contract.value_labels["condition"];
+        assert_eq!(condition["1"], "Control");
+        assert_eq!(condition["2"], "Treatment B");
+        let applied = spec
+            .warnings
+            .iter()
+            .find(|w| w.code == VALUE_LABEL_OVERRIDES_APPLIED)
+            .expect("applied note");
+        assert_eq!(applied.details["columns"], serde_json::json!(["condition"]));
+        let unknown = spec
+            .warnings
+            .iter()
+            .find(|w| w.code == VALUE_LABEL_OVERRIDE_UNKNOWN_COLUMN)
+            .expect("unknown column report");
+        assert_eq!(unknown.details["columns"], serde_json::json!(["conditon"]));
+        let _ = fs::remove_dir_all(root);
+    }
+}
@@ -0,0 +1,223 @@
+use serde::Serialize;
+
+use super::types::WarningSeverity::{Error, Info, Warning};
+use super::types::{WarningItem, WarningSeverity};
+
+/// Error returned by `render_analysis_from_spec` while error-severity warnings remain.
+pub const BLOCKING_WARNINGS_UNRESOLVED: &str = "BLOCKING_WARNINGS_UNRESOLVED";
+
+#[derive(Debug, Clone, Serialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct WarningCodeInfo {
+    pub code: &'static str,
+    pub default_severity: WarningSeverity,
+    pub description: &'static str,
+}
+
+const fn entry(
+    code: &'static str,
+    default_severity: WarningSeverity,
+    description: &'static str,
+) -> WarningCodeInfo {
+    WarningCodeInfo {
+        code,
+        default_severity,
+        description,
+    }
+}
+
+/// Every warning and structured error code the backend emits, for UI help text.
+pub const WARNING_CATALOG: &[WarningCodeInfo] = &[
+    entry(
+        "UNRESOLVED_VARIABLE",
+        Error,
+        "A preregistered variable has no matching survey column; its model terms are TODO_ placeholders.",
+    ),
+    entry(
+        "NO_MAIN_MODELS",
+        Error,
+        "No main models were extracted from the preregistration.",
+    ),
+    entry(
+        "PREREG_EXTRACTION_EMPTY",
+        Error,
+        "No models or variables were found in the preregistration file.",
+    ),
+    entry(
+        BLOCKING_WARNINGS_UNRESOLVED,
+        Error,
+        "Rendering was refused because error-severity warnings remain on the spec.",
+    ),
+    entry(
+        "SCALE_ITEMS_UNRESOLVED",
+        Warning,
+        "Some items of a preregistered scale could not be matched to columns; the scale is left as a TODO.",
+    ),
+    entry(
+        "SCALE_ITEMS_ASSUMED",
+        Warning,
+        "A preregistered scale listed no items, so numbered columns sharing its name were assumed to be its items.",
+    ),
+    entry(
+        "ATTENTION_CHECK_ASSUMED",
+        Warning,
+        "An attention-check exclusion filter assumes which answer is correct; verify the value.",
+    ),
+    entry(
+        "EXCLUSION_VARIABLE_UNRESOLVED",
+        Warning,
+        "An exclusion rule names a column that is not in the survey; its filter is left as a TODO.",
+    ),
+    entry(
+        "DATA_CONTRACT_MISMATCH",
+        Warning,
+        "The data file header is missing expected columns or has columns differing only by case or suffix.",
+    ),
+    entry(
+        "DUPLICATE_EXPORT_TAG",
+        Warning,
+        "Several QSF questions share an export tag; all but the first were renamed.",
+    ),
+    entry(
+        "LOOP_MERGE_DYNAMIC",
+        Warning,
+        "A Loop & Merge block repeats per answer, so its columns were expanded for an assumed number of iterations.",
+    ),
+    entry(
+        "VARIABLES_UNCLEAR_IN_PREREG",
+        Warning,
+        "The preregistration does not clearly name dependent and independent variables.",
+    ),
+    entry(
+        "NO_MAIN_ANALYSIS_EXTRACTED",
+        Warning,
+        "No analysis formula or regression sentence was found in the preregistration text.",
+    ),
+    entry(
+        "PREREG_TEXT_LOSSY_DECODE",
+        Warning,
+        "The preregistration was not valid UTF-8 and some characters were replaced.",
+    ),
+    entry(
+        "PDF_TEXT_EXTRACTION_LOSSY",
+        Warning,
+        "Text extracted from the PDF is partly unreadable; check the extracted variables.",
+    ),
+    entry(
+        "VALUE_LABEL_OVERRIDE_UNKNOWN_COLUMN",
+        Warning,
+        "Saved value label overrides name columns that are not in the survey.",
+    ),
+    entry(
+        "SPEC_RECOVERED_FROM_BACKUP",
+        Warning,
+        "spec.json was unreadable and was restored from its last backup.",
+    ),
+    entry(
+        "PROJECTS_RECOVERED_FROM_BACKUP",
+        Warning,
+        "projects.json was unreadable and was restored from its last backup.",
+    ),
+    entry(
+        "DOCX_SECTIONS_NOT_DETECTED",
+        Info,
+        "No numbered sections were found in the docx; the whole text was scanned.",
+    ),
+    entry(
+        "LLM_ENRICHMENT_APPLIED",
+        Info,
+        "The local model was used to enrich preregistration parsing.",
+    ),
+    entry(
+        "LLM_UNAVAILABLE",
+        Info,
+        "The local model was unavailable; only heuristic extraction was used.",
+    ),
+    entry(
+        "VARIABLE_ALIASES_APPLIED",
+        Info,
+        "Some variables were mapped from the project's variable alias dictionary.",
+    ),
+    entry(
+        "FAMILY_INFERRED",
+        Info,
+        "A model is fit as a binomial or Poisson GLM based on its outcome's answer choices or the preregistration.",
+    ),
+    entry(
+        "VALUE_LABEL_OVERRIDES_APPLIED",
+        Info,
+        "Saved value label overrides were applied.",
+    ),
+    entry(
+        "GIT_NO_REPO",
+        Error,
+        "The project root is not a git repository.",
+    ),
+    entry("GIT_NO_REMOTE", Error, "The repository has no origin remote."),
+    entry(
+        "GIT_NO_UPSTREAM",
+        Error,
+        "The current branch has no upstream to push to.",
+    ),
+    entry("GIT_AUTH_FAILED", Error, "Authentication with the remote failed."),
+    entry(
+        "GIT_PUSH_REJECTED",
+        Error,
+        "The remote rejected the push; pull and retry.",
+    ),
+    entry("GIT_PUSH_FAILED", Error, "git push failed."),
+    entry("GIT_COMMIT_FAILED", Error, "git commit failed."),
+    entry(
+        "PROJECT_SECRETS_LOCKED",
+        Error,
+        "Project secrets are locked; unlock them with the passphrase first.",
+    ),
+    entry(
+        "PROJECT_SECRETS_WRONG_PASSPHRASE",
+        Error,
+        "The passphrase does not unlock the project secrets.",
+    ),
+];
+
+/// Severity for `code` from the catalog; codes it does not know are plain warnings.
+pub fn default_severity(code: &str) -> WarningSeverity {
+    WARNING_CATALOG
+        .iter()
+        .find(|entry| entry.code == code)
+        .map_or(WarningSeverity::Warning, |entry| entry.default_severity)
+}
+
+/// Distinct codes of error-severity warnings, sorted.
+pub fn blocking_codes(warnings: &[WarningItem]) -> Vec<String> {
+    let mut codes = warnings
+        .iter()
+        .filter(|w| w.severity == WarningSeverity::Error)
+        .map(|w| w.code.clone())
+        .collect::<Vec<String>>();
+    codes.sort();
+    codes.dedup();
+    codes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn catalog_codes_are_unique_and_unknown_codes_default_to_warning() {
+        let mut codes = WARNING_CATALOG.iter().map(|e| e.code).collect::<Vec<_>>();
+        codes.sort();
+        let total = codes.len();
+        codes.dedup();
+        assert_eq!(codes.len(), total);
+        assert_eq!(
+            default_severity("UNRESOLVED_VARIABLE"),
+            WarningSeverity::Error
+        );
+        assert_eq!(
+            default_severity("LLM_ENRICHMENT_APPLIED"),
+            WarningSeverity::Info
+        );
+        assert_eq!(default_severity("SOMETHING_NEW"), WarningSeverity::Warning);
+    }
+}
@@ -0,0 +1,252 @@
+use serde::{Deserialize, Serialize};
+use std::fs::{self, File, OpenOptions};
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use super::now_string;
+
+pub const ACTIVITY_FILE: &str = "activity.jsonl";
+/// Previous feed, kept after rotation so the tail stays readable across the boundary.
+pub const ROTATED_ACTIVITY_FILE: &str = "activity.1.jsonl";
+/// Feed size at which the next append rotates the file.
+pub const MAX_FEED_BYTES: u64 = 512 * 1024;
+pub const DEFAULT_ACTIVITY_LIMIT: usize = 50;
+pub const MAX_ACTIVITY_LIMIT: usize = 500;
+
+/// Bytes read per backwards step when scanning the tail of the feed.
+const TAIL_CHUNK_BYTES: u64 = 8 * 1024;
+
+static FEED_LOCK: Mutex<()> = Mutex::new(());
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct ActivityEntry {
+    pub timestamp: String,
+    pub command: String,
+    pub project_id: Option<String>,
+    pub study_id: Option<String>,
+    pub summary: String,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GetRecentActivityArgs {
+    #[serde(default)]
+    limit: Option<usize>,
+    #[serde(default)]
+    project_id: Option<String>,
+}
+
+pub fn activity_path(app_root: &Path) -> PathBuf {
+    app_root.join(ACTIVITY_FILE)
+}
+
+fn rotated_path(feed: &Path) -> PathBuf {
+    feed.with_file_name(ROTATED_ACTIVITY_FILE)
+}
+
+fn append_entry(feed: &Path, entry: &ActivityEntry, max_bytes: u64) -> Result<(), String> {
+    let mut line = serde_json::to_string(entry).map_err(|err| err.to_string())?;
+    line.push('\n');
+
+    let _guard = FEED_LOCK
+        .lock()
+        .map_err(|_| "Activity feed lock poisoned.".to_string())?;
+    let size = fs::metadata(feed).map(|meta| meta.len()).unwrap_or(0);
+    if size > 0 && size + line.len() as u64 > max_bytes {
+        fs::rename(feed, rotated_path(feed))
+            .map_err(|err| format!("Unable to rotate {}: {err}", feed.display()))?;
+    }
+    let mut file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(feed)
+        .map_err(|err| format!("Unable to open {}: {err}", feed.display()))?;
+    file.write_all(line.as_bytes())
+        .map_err(|err| format!("Unable to write {}: {err}", feed.display()))
+}
+
+/// Appends an entry to the workspace activity feed. Failures are logged and never reach
+/// the command that triggered them.
+pub fn record_activity(
+    app_root: &Path,
+    command: &str,
+    project_id: Option<&str>,
+    study_id: Option<&str>,
+    summary: &str,
+) {
+    let entry = ActivityEntry {
+        timestamp: now_string(),
+        command: command.to_string(),
+        project_id: project_id.map(str::to_string),
+        study_id: study_id.map(str::to_string),
+        summary: summary.to_string(),
+    };
+    if let Err(err) = append_entry(&activity_path(app_root), &entry, MAX_FEED_BYTES) {
+        println!("activity: unable to record {command}: {err}");
+    }
+}
+
+/// Walks `feed` backwards from its end, pushing matching entries newest first until
+/// `limit` is reached. Unparseable lines are skipped.
+fn read_tail(
+    feed: &Path,
+    limit: usize,
+    project_id: Option<&str>,
+    out: &mut Vec<ActivityEntry>,
+) -> Result<(), String> {
+    let mut file = match File::open(feed) {
+        Ok(file) => file,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(()),
+        Err(err) => return Err(format!("Unable to open {}: {err}", feed.display())),
+    };
+    let mut pos = file
+        .metadata()
+        .map_err(|err| format!("Unable to read {}: {err}", feed.display()))?
+        .len();
+
+    let keep = |line: &[u8], out: &mut Vec<ActivityEntry>| {
+        let Ok(entry) = serde_json::from_slice::<ActivityEntry>(line) else {
+            return;
+        };
+        if project_id.is_none_or(|id| entry.project_id.as_deref() == Some(id)) {
+            out.push(entry);
+        }
+    };
+
+    // Bytes before the first newline of the last chunk read; completed by the next chunk.
+    let mut carry: Vec<u8> = Vec::new();
+    while pos > 0 && out.len() < limit {
+        let step = pos.min(TAIL_CHUNK_BYTES);
+        pos -= step;
+        let mut chunk = vec![0u8; step as usize];
+        file.seek(SeekFrom::Start(pos))
+            .and_then(|_| file.read_exact(&mut chunk))
+            .map_err(|err| format!("Unable to read {}: {err}", feed.display()))?;
+        chunk.extend_from_slice(&carry);
+
+        let mut lines = chunk.split(|byte| *byte == b'\n');
+        let first = lines.next().unwrap_or_default().to_vec();
+        let rest: Vec<&[u8]> = lines.collect();
+        for line in rest.into_iter().rev() {
+            if out.len() >= limit {
+                return Ok(());
+            }
+            keep(line, out);
+        }
+        carry = first;
+    }
+    if pos == 0 && out.len() < limit {
+        keep(&carry, out);
+    }
+    Ok(())
+}
+
+fn recent_activity_at(
+    feed: &Path,
+    limit: usize,
+    project_id: Option<&str>,
+) -> Result<Vec<ActivityEntry>, String> {
+    let mut out = Vec::new();
+    read_tail(feed, limit, project_id, &mut out)?;
+    if out.len() < limit {
+        read_tail(&rotated_path(feed), limit, project_id, &mut out)?;
+    }
+    Ok(out)
+}
+
+/// Most recent activity across the workspace, newest first, optionally for one project.
+pub fn get_recent_activity(
+    app_root: &Path,
+    args: GetRecentActivityArgs,
+) -> Result<Vec<ActivityEntry>, String> {
+    let limit = args
+        .limit
+        .unwrap_or(DEFAULT_ACTIVITY_LIMIT)
+        .clamp(1, MAX_ACTIVITY_LIMIT);
+    let project_id = args
+        .project_id
+        .as_deref()
+        .map(str::trim)
+        .filter(|id| !id.is_empty());
+    recent_activity_at(&activity_path(app_root), limit, project_id)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use uuid::Uuid;
+
+    fn entry(n: usize, project_id: &str) -> ActivityEntry {
+        ActivityEntry {
+            timestamp: format!("2026-01-01T00:00:{:02}Z", n % 60),
+            command: "create_analysis_template".to_string(),
+            project_id: Some(project_id.to_string()),
+            study_id: Some("S-ABC123".to_string()),
+            summary: format!("entry {n} {}", "x".repeat(40)),
+        }
+    }
+
+    fn temp_feed() -> (PathBuf, PathBuf) {
+        let dir = std::env::temp_dir().join(format!("activity-test-{}", Uuid::new_v4()));
+        fs::create_dir_all(&dir).expect("temp dir");
+        let feed = dir.join(ACTIVITY_FILE);
+        (dir, feed)
+    }
+
+    #[test]
+    fn tail_reads_newest_first_across_chunks_and_filters_by_project() {
+        let (dir, feed) = temp_feed();
+        for n in 0..400 {
+            let project = if n % 2 == 0 { "p-even" } else { "p-odd" };
+            append_entry(&feed, &entry(n, project), u64::MAX).expect("append");
+        }
+        let mut file = OpenOptions::new().append(true).open(&feed).expect("open");
+        file.write_all(b"not json\n").expect("garbage line");
+        append_entry(&feed, &entry(400, "p-even"), u64::MAX).expect("append");
+        assert!(fs::metadata(&feed).expect("meta").len() > TAIL_CHUNK_BYTES * 2);
+
+        let recent = recent_activity_at(&feed, 3, None).expect("tail");
+        let summaries: Vec<&str> = recent
+            .iter()
+            .map(|e| e.summary.split(' ').nth(1).expect("n"))
+            .collect();
+        assert_eq!(summaries, vec!["400", "399", "398"]);
+
+        let all = recent_activity_at(&feed, 1000, None).expect("all");
+        assert_eq!(all.len(), 401);
+        assert_eq!(all.last(), Some(&entry(0, "p-even")));
+
+        let odd = recent_activity_at(&feed, 150, Some("p-odd")).expect("filtered");
+        assert_eq!(odd.len(), 150);
+        assert!(odd.iter().all(|e| e.project_id.as_deref() == Some("p-odd")));
+        assert_eq!(odd[0], entry(399, "p-odd"));
+        let _ = fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn rotation_moves_full_feed_aside_and_tail_spans_both_files() {
+        let (dir, feed) = temp_feed();
+        let line_len = serde_json::to_string(&entry(0, "p")).expect("json").len() as u64 + 1;
+        let max_bytes = line_len * 5;
+        for n in 0..8 {
+            append_entry(&feed, &entry(n, "p"), max_bytes).expect("append");
+        }
+        let rotated = dir.join(ROTATED_ACTIVITY_FILE);
+        assert!(rotated.exists());
+        assert!(fs::metadata(&feed).expect("meta").len() <= max_bytes);
+
+        let recent = recent_activity_at(&feed, 8, None).expect("tail");
+        let expected: Vec<ActivityEntry> = (0..8).rev().map(|n| entry(n, "p")).collect();
+        assert_eq!(recent, expected);
+
+        for n in 8..20 {
+            append_entry(&feed, &entry(n, "p"), max_bytes).expect("append");
+        }
+        let recent = recent_activity_at(&feed, 100, None).expect("tail");
+        assert!(recent.len() <= 10);
+        assert_eq!(recent[0], entry(19, "p"));
+        let _ = fs::remove_dir_all(dir);
+    }
+}
@@ -0,0 +1,601 @@
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io::{Read, Write};
+use std::path::{Component, Path, PathBuf};
+use uuid::Uuid;
+use zip::write::FileOptions;
+use zip::{CompressionMethod, ZipArchive, ZipWriter};
+
+use super::activity::record_activity;
+use super::files::should_skip;
+use super::structure::ensure_study_scaffold;
+use super::{
+    ensure_folders, generate_study_code, now_string, read_projects_store, resolve_study_root,
+    write_projects_store, Project, Study, PROJECT_FOLDERS,
+};
+
+/// Archive entry holding the manifest; project files live under `ARCHIVE_FILES_DIR/`.
+pub const ARCHIVE_MANIFEST: &str = "research-workflow-export.json";
+const ARCHIVE_FILES_DIR: &str = "project";
+const ARCHIVE_FORMAT_VERSION: u32 = 1;
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExportProjectArgs {
+    project_id: String,
+    /// Zip file to write, or an existing folder to write `<project name>.zip` into.
+    destination: String,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ImportProjectArgs {
+    archive_path: String,
+    /// Folder the project folder is unpacked into.
+    parent_dir: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ArchivedStudy {
+    pub id: String,
+    pub title: String,
+    /// Study folder inside the archived project folder, `/`-separated.
+    pub relative_folder: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProjectArchiveManifest {
+    pub format_version: u32,
+    pub exported_at: String,
+    pub project: Project,
+    pub studies: Vec<ArchivedStudy>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProjectExportReport {
+    pub archive_path: String,
+    pub file_count: usize,
+}
+
+fn to_entry_name(rel: &Path) -> String {
+    rel.to_string_lossy().replace('\\', "/")
+}
+
+/// Files under `dir` that belong in an export, as (absolute path, path relative to `dir`).
+/// Symlinks are skipped, so link cycles and links out of the project are not followed.
+fn collect_files(dir: &Path, excluded: &[PathBuf]) -> Result<Vec<(PathBuf, PathBuf)>, String> {
+    let mut out = Vec::new();
+    let mut pending = vec![dir.to_path_buf()];
+    while let Some(current) = pending.pop() {
+        let entries = fs::read_dir(&current)
+            .map_err(|e| format!("Unable to read {}: {e}", current.display()))?;
+        for entry in entries {
+            let entry = entry.map_err(|e| e.to_string())?;
+            let path = entry.path();
+            if should_skip(&path, excluded) {
+                continue;
+            }
+            let file_type = entry.file_type().map_err(|e| e.to_string())?;
+            if file_type.is_dir() {
+                pending.push(path);
+            } else if !file_type.is_file() {
+                continue;
+            } else if let Ok(rel) = path.strip_prefix(dir) {
+                out.push((path.clone(), rel.to_path_buf()));
+            }
+        }
+    }
+    out.sort_by(|a, b| a.1.cmp(&b.1));
+    Ok(out)
+}
+
+/// Where each study's folder goes in the archive. Folders under the project root keep their
+/// place; folders kept elsewhere are packed under `studies/<folder name>`.
+fn archived_study_folder(root: &Path, project: &Project, study: &Study) -> PathBuf {
+    let folder = resolve_study_root(project, study);
+    match folder.strip_prefix(root) {
+        Ok(rel) => rel.to_path_buf(),
+        Err(_) => Path::new("studies").join(folder.file_name().unwrap_or(study.id.as_ref())),
+    }
+}
+
+/// Zips the project folder (without `.git`, `node_modules` or stored secrets) together with
+/// a manifest of its projects.json record, so `import_project` can register it elsewhere.
+pub fn export_project(
+    app_root: &Path,
+    args: ExportProjectArgs,
+) -> Result<ProjectExportReport, String> {
+    let store = read_projects_store(app_root)?;
+    let project = store
+        .projects
+        .iter()
+        .find(|project| project.id == args.project_id)
+        .ok_or_else(|| "Project not found.".to_string())?;
+    let root = PathBuf::from(&project.root_path);
+    if !root.is_dir() {
+        return Err(format!("Project folder not found: {}", root.display()));
+    }
+    let mut destination = PathBuf::from(args.destination.trim());
+    if destination.is_dir() {
+        destination = destination.join(format!("{}.zip", project.name.trim()));
+    }
+
+    let excluded = vec![destination.clone()];
+    let mut files: Vec<(PathBuf, String)> = collect_files(&root, &excluded)?
+        .into_iter()
+        .map(|(path, rel)| (path, to_entry_name(&rel)))
+        .collect();
+    let mut studies = Vec::new();
+    for study in &project.studies {
+        let rel = archived_study_folder(&root, project, study);
+        let folder = resolve_study_root(project, study);
+        if !folder.starts_with(&root) && folder.is_dir() {
+            files.extend(
+                collect_files(&folder, &excluded)?
+                    .into_iter()
+                    .map(|(path, inner)| (path, to_entry_name(&rel.join(inner)))),
+            );
+        }
+        studies.push(ArchivedStudy {
+            id: study.id.clone(),
+            title: study.title.clone(),
+            relative_folder: to_entry_name(&rel),
+        });
+    }
+    let manifest = ProjectArchiveManifest {
+        format_version: ARCHIVE_FORMAT_VERSION,
+        exported_at: now_string(),
+        project: project.clone(),
+        studies,
+    };
+
+    if let Some(parent) = destination.parent() {
+        fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+    }
+    let file = fs::File::create(&destination)
+        .map_err(|e| format!("Unable to create {}: {e}", destination.display()))?;
+    let mut zip = ZipWriter::new(file);
+    let options = FileOptions::default().compression_method(CompressionMethod::Deflated);
+    zip.start_file(ARCHIVE_MANIFEST, options)
+        .map_err(|e| e.to_string())?;
+    zip.write_all(
+        serde_json::to_string_pretty(&manifest)
+            .map_err(|e| e.to_string())?
+            .as_bytes(),
+    )
+    .map_err(|e| e.to_string())?;
+    for (path, name) in &files {
+        zip.start_file(format!("{ARCHIVE_FILES_DIR}/{name}"), options)
+            .map_err(|e| e.to_string())?;
+        let mut source =
+            fs::File::open(path).map_err(|e| format!("Unable to read {}: {e}", path.display()))?;
+        std::io::copy(&mut source, &mut zip).map_err(|e| e.to_string())?;
+    }
+    zip.finish().map_err(|e| e.to_string())?;
+
+    record_activity(
+        app_root,
+        "export_project",
+        Some(&project.id),
+        None,
+        &format!(
+            "Exported {} file(s) to {}",
+            files.len(),
+            destination.display()
+        ),
+    );
+    Ok(ProjectExportReport {
+        archive_path: destination.to_string_lossy().to_string(),
+        file_count: files.len(),
+    })
+}
+
+fn unique_folder(parent: &Path, name: &str) -> PathBuf {
+    let candidate = parent.join(name);
+    if !candidate.exists() {
+        return candidate;
+    }
+    (2..)
+        .map(|n| parent.join(format!("{name} ({n})")))
+        .find(|candidate| !candidate.exists())
+        .expect("unbounded range")
+}
+
+/// Folder name used when the archived project's own name is not a plain folder name.
+const DEFAULT_IMPORT_FOLDER: &str = "imported-project";
+
+/// A single plain folder name from the manifest, so the import cannot leave `parent_dir`.
+fn plain_folder_name(name: &str) -> Option<String> {
+    let name = name.trim();
+    let mut components = Path::new(name).components();
+    let single = matches!(components.next(), Some(Component::Normal(_)))
+        && components.next().is_none()
+        && !name.contains(['/', '\\']);
+    single.then(|| name.to_string())
+}
+
+/// A manifest study folder, accepted only when it stays inside the project folder.
+fn enclosed_study_folder(relative_folder: &str) -> Option<PathBuf> {
+    let rel = Path::new(relative_folder);
+    let enclosed = rel.components().next().is_some()
+        && rel
+            .components()
+            .all(|component| matches!(component, Component::Normal(_)));
+    enclosed.then(|| rel.to_path_buf())
+}
+
+/// Unpacks an `export_project` archive under `parent_dir` and adds the project to the
+/// store with its paths pointing at the new folder. The project and its studies get new ids
+/// only when the archived ones are already registered.
+pub fn import_project(app_root: &Path, args: ImportProjectArgs) -> Result<Project, String> {
+    let parent = PathBuf::from(args.parent_dir.trim());
+    if !parent.is_dir() {
+        return Err("Import location must be an existing folder.".to_string());
+    }
+    let file = fs::File::open(args.archive_path.trim())
+        .map_err(|e| format!("Unable to open archive: {e}"))?;
+    let mut zip = ZipArchive::new(file).map_err(|e| format!("Invalid project archive: {e}"))?;
+    let manifest: ProjectArchiveManifest = {
+        let mut raw = String::new();
+        zip.by_name(ARCHIVE_MANIFEST)
+            .map_err(|_| format!("Archive is missing {ARCHIVE_MANIFEST}."))?
+            .read_to_string(&mut raw)
+            .map_err(|e| e.to_string())?;
+        serde_json::from_str(&raw).map_err(|e| format!("Invalid {ARCHIVE_MANIFEST}: {e}"))?
+    };
+    if manifest.format_version > ARCHIVE_FORMAT_VERSION {
+        return Err(format!(
+            "Archive format {} is newer than this app supports.",
+            manifest.format_version
+        ));
+    }
+
+    let folder_name = Path::new(&manifest.project.root_path)
+        .file_name()
+        .and_then(|name| plain_folder_name(&name.to_string_lossy()))
+        .or_else(|| plain_folder_name(&manifest.project.name))
+        .unwrap_or_else(|| DEFAULT_IMPORT_FOLDER.to_string());
+    let new_root = unique_folder(&parent, &folder_name);
+    fs::create_dir_all(&new_root).map_err(|e| e.to_string())?;
+
+    for idx in 0..zip.len() {
+        let mut entry = zip.by_index(idx).map_err(|e| e.to_string())?;
+        // enclosed_name rejects absolute paths and `..`, so nothing lands outside new_root.
+        let Some(rel) = entry
+            .enclosed_name()
+            .and_then(|name| name.strip_prefix(ARCHIVE_FILES_DIR).ok())
+            .map(Path::to_path_buf)
+        else {
+            continue;
+        };
+        let target = new_root.join(rel);
+        if entry.is_dir() {
+            fs::create_dir_all(&target).map_err(|e| e.to_string())?;
+            continue;
+        }
+        if let Some(dir) = target.parent() {
+            fs::create_dir_all(dir).map_err(|e| e.to_string())?;
+        }
+        let mut out = fs::File::create(&target)
+            .map_err(|e| format!("Unable to write {}: {e}", target.display()))?;
+        std::io::copy(&mut entry, &mut out).map_err(|e| e.to_string())?;
+    }
+
+    let mut store = read_projects_store(app_root)?;
+    let mut project = manifest.project;
+    if store
+        .projects
+        .iter()
+        .any(|existing| existing.id == project.id)
+    {
+        project.id = Uuid::new_v4().to_string();
+    }
+    project.root_path = new_root.to_string_lossy().to_string();
+    project.updated_at = now_string();
+    let mut taken: Vec<String> = store
+        .projects
+        .iter()
+        .flat_map(|existing| existing.studies.iter().map(|study| study.id.clone()))
+        .collect();
+    for study in project.studies.iter_mut() {
+        let archived_id = study.id.clone();
+        if taken.contains(&study.id) {
+            study.id = (0..20)
+                .map(|_| generate_study_code())
+                .find(|code| !taken.contains(code))
+                .ok_or_else(|| "Unable to generate a unique study code.".to_string())?;
+        }
+        taken.push(study.id.clone());
+        // A study without a folder path lives at studies/<id>, which follows the archived id.
+        if study.folder_path.trim().is_empty() && study.id == archived_id {
+            continue;
+        }
+        let rel = manifest
+            .studies
+            .iter()
+            .find(|archived| archived.id == archived_id)
+            .and_then(|archived| enclosed_study_folder(&archived.relative_folder))
+            .unwrap_or_else(|| Path::new("studies").join(&archived_id));
+        study.folder_path = new_root.join(rel).to_string_lossy().to_string();
+    }
+    // Empty scaffold folders are not stored in the archive.
+    ensure_folders(&new_root, PROJECT_FOLDERS)?;
+    for study in &project.studies {
+        ensure_study_scaffold(&new_root, &resolve_study_root(&project, study))?;
+    }
+    store.projects.push(project.clone());
+    write_projects_store(app_root, &store)?;
+
+    record_activity(
+        app_root,
+        "import_project",
+        Some(&project.id),
+        None,
+        &format!("Imported project into {}", new_root.display()),
+    );
+    Ok(project)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::store::{ProjectsStore, STUDY_FOLDERS};
+
+    #[test]
+    fn export_then_import_round_trips_studies_and_paths() {
+        let base = std::env::temp_dir().join(format!("project-archive-{}", Uuid::new_v4()));
+        let app_root = base.join("app");
+        let root = base.join("office").join("Lab");
+        let elsewhere = base.join("elsewhere").join("S-OUT001");
+        fs::create_dir_all(&app_root).expect("app root");
+        ensure_folders(&root.join("studies").join("S-IN0001"), STUDY_FOLDERS).expect("study");
+        fs::create_dir_all(&elsewhere).expect("outside study");
+        fs::write(root.join("studies/S-IN0001/04_prereg/prereg.md"), "1) Plan").expect("prereg");
+        fs::write(elsewhere.join("notes.txt"), "outside").expect("notes");
+        fs::create_dir_all(root.join(".git")).expect("git");
+        fs::write(root.join(".git").join("HEAD"), "ref").expect("head");
+        fs::create_dir_all(root.join("node_modules").join("x")).expect("modules");
+        fs::write(root.join("node_modules/x/index.js"), "").expect("module");
+
+        let store: ProjectsStore = serde_json::from_value(serde_json::json!({
+            "projects": [{
+                "id": "p1",
+                "name": "Lab",
+                "rootPath": root.to_string_lossy(),
+                "createdAt": "2026-01-01T00:00:00Z",
+                "studies": [
+                    { "id": "S-IN0001", "title": "Inside", "createdAt": "2026-01-01T00:00:00Z",
+                      "folderPath": root.join("studies").join("S-IN0001").to_string_lossy(),
+                      "files": [{ "path": "studies/S-IN0001/04_prereg/prereg.md", "name": "prereg.md", "kind": "md" }] },
+                    { "id": "S-OUT001", "title": "Outside", "createdAt": "2026-01-01T00:00:00Z",
+                      "folderPath": elsewhere.to_string_lossy() },
+                    { "id": "S-DEF001", "title": "Default", "createdAt": "2026-01-01T00:00:00Z" }
+                ]
+            }]
+        }))
+        .expect("store");
+        write_projects_store(&app_root, &store).expect("write store");
+
+        let report = export_project(
+            &app_root,
+            ExportProjectArgs {
+                project_id: "p1".to_string(),
+                destination: root.to_string_lossy().to_string(),
+            },
+        )
+        .expect("export");
+        assert!(report.archive_path.ends_with("Lab.zip"));
+        let names: Vec<String> = ZipArchive::new(fs::File::open(&report.archive_path).unwrap())
+            .expect("zip")
+            .file_names()
+            .map(str::to_string)
+            .collect();
+        assert!(names
+            .iter()
+            .any(|n| n == "project/studies/S-OUT001/notes.txt"));
+        assert!(!names
+            .iter()
+            .any(|n| n.contains(".git") || n.contains("node_modules") || n.ends_with(".zip")));
+
+        let laptop = base.join("laptop");
+        fs::create_dir_all(&laptop).expect("laptop");
+        let imported = import_project(
+            &app_root,
+            ImportProjectArgs {
+                archive_path: report.archive_path.clone(),
+                parent_dir: laptop.to_string_lossy().to_string(),
+            },
+        )
+        .expect("import");
+        assert_ne!(imported.id, "p1");
+        assert_eq!(imported.root_path, laptop.join("Lab").to_string_lossy());
+
+        let original = &read_projects_store(&app_root).expect("store").projects[0];
+        let summary = |project: &Project| -> Vec<(String, usize)> {
+            project
+                .studies
+                .iter()
+                .map(|s| (s.title.clone(), s.files.len()))
+                .collect()
+        };
+        assert_eq!(summary(original), summary(&imported));
+        // Same store, so every study id collides and is regenerated.
+        for (before, after) in original.studies.iter().zip(&imported.studies) {
+            assert_ne!(before.id, after.id);
+        }
+        for study in &imported.studies {
+            let folder = resolve_study_root(&imported, study);
+            assert!(
+                folder.starts_with(laptop.join("Lab")),
+                "{}",
+                folder.display()
+            );
+        }
+        let inside = &imported.studies[0];
+        assert!(resolve_study_root(&imported, inside)
+            .join("04_prereg")
+            .join("prereg.md")
+            .is_file());
+        assert!(Path::new(&imported.root_path)
+            .join(&inside.files[0].path)
+            .is_file());
+        assert!(resolve_study_root(&imported, &imported.studies[1])
+            .join("notes.txt")
+            .is_file());
+        assert_eq!(
+            read_projects_store(&app_root)
+                .expect("store")
+                .projects
+                .len(),
+            2
+        );
+        let _ = fs::remove_dir_all(base);
+    }
+
+    #[test]
+    fn import_keeps_manifest_study_folders_inside_the_project() {
+        let base = std::env::temp_dir().join(format!("project-archive-{}", Uuid::new_v4()));
+        let app_root = base.join("app");
+        let parent = base.join("imports");
+        fs::create_dir_all(&app_root).expect("app root");
+        fs::create_dir_all(&parent).expect("parent");
+        let existing: ProjectsStore = serde_json::from_value(serde_json::json!({
+            "projects": [{
+                "id": "p1", "name": "Other", "rootPath": base.join("other").to_string_lossy(),
+                "createdAt": "2026-01-01T00:00:00Z",
+                "studies": [{ "id": "S-DUP001", "title": "Taken", "createdAt": "2026-01-01T00:00:00Z" }]
+            }]
+        }))
+        .expect("store");
+        write_projects_store(&app_root, &existing).expect("write store");
+
+        let absolute = base.join("absolute").to_string_lossy().to_string();
+        let manifest = serde_json::json!({
+            "formatVersion": 1,
+            "exportedAt": "2026-01-01T00:00:00Z",
+            "project": {
+                "id": "p1", "name": "Lab", "rootPath": "/old/Lab", "createdAt": "2026-01-01T00:00:00Z",
+                "studies": [
+                    { "id": "S-ESC001", "title": "Escape", "createdAt": "2026-01-01T00:00:00Z",
+                      "folderPath": "/old/escape" },
+                    { "id": "S-ABS001", "title": "Absolute", "createdAt": "2026-01-01T00:00:00Z",
+                      "folderPath": absolute },
+                    { "id": "S-DUP001", "title": "Duplicate", "createdAt": "2026-01-01T00:00:00Z" }
+                ]
+            },
+            "studies": [
+                { "id": "S-ESC001", "title": "Escape", "relativeFolder": "../escape" },
+                { "id": "S-ABS001", "title": "Absolute", "relativeFolder": absolute },
+                { "id": "S-DUP001", "title": "Duplicate", "relativeFolder": "studies/S-DUP001" }
+            ]
+        });
+        let import = |manifest: &serde_json::Value| {
+            let archive_path = base.join(format!("crafted-{}.zip", Uuid::new_v4()));
+            let mut zip = ZipWriter::new(fs::File::create(&archive_path).expect("zip"));
+            zip.start_file(ARCHIVE_MANIFEST, FileOptions::default())
+                .expect("manifest entry");
+            zip.write_all(manifest.to_string().as_bytes())
+                .expect("manifest");
+            zip.finish().expect("finish");
+            import_project(
+                &app_root,
+                ImportProjectArgs {
+                    archive_path: archive_path.to_string_lossy().to_string(),
+                    parent_dir: parent.to_string_lossy().to_string(),
+                },
+            )
+        };
+
+        let imported = import(&manifest).expect("import");
+        assert_ne!(imported.id, "p1");
+        let new_root = parent.join("Lab");
+        assert_eq!(
+            imported.studies[0].folder_path,
+            new_root.join("studies/S-ESC001").to_string_lossy()
+        );
+        assert_eq!(
+            imported.studies[1].folder_path,
+            new_root.join("studies/S-ABS001").to_string_lossy()
+        );
+        assert!(!base.join("escape").exists());
+        assert!(!base.join("absolute").exists());
+
+        let duplicate = &imported.studies[2];
+        assert_ne!(duplicate.id, "S-DUP001");
+        assert_eq!(
+            resolve_study_root(&imported, duplicate),
+            new_root.join("studies/S-DUP001")
+        );
+        assert!(new_root.join("studies/S-DUP001/06_analysis").is_dir());
+
+        // Without a usable root folder name the project name must be a plain name too.
+        let absolute_name = base.join("planted").to_string_lossy().to_string();
+        for name in ["../escape", absolute_name.as_str()] {
+            let mut manifest = manifest.clone();
+            manifest["project"]["rootPath"] = serde_json::json!("/");
+            manifest["project"]["name"] = serde_json::json!(name);
+            manifest["project"]["studies"] = serde_json::json!([]);
+            manifest["studies"] = serde_json::json!([]);
+            let imported = import(&manifest).expect("import");
+            assert!(
+                Path::new(&imported.root_path).starts_with(&parent),
+                "{}",
+                imported.root_path
+            );
+            assert!(Path::new(&imported.root_path)
+                .file_name()
+                .is_some_and(|folder| folder.to_string_lossy().starts_with(DEFAULT_IMPORT_FOLDER)));
+        }
+        assert!(!base.join("escape").exists());
+        assert!(!base.join("planted").exists());
+        let _ = fs::remove_dir_all(base);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn export_skips_symlinks() {
+        use std::os::unix::fs::symlink;
+
+        let base = std::env::temp_dir().join(format!("project-archive-{}", Uuid::new_v4()));
+        let app_root = base.join("app");
+        let root = base.join("Lab");
+        let outside = base.join("outside");
+        fs::create_dir_all(&app_root).expect("app root");
+        fs::create_dir_all(root.join("studies")).expect("studies");
+        fs::create_dir_all(&outside).expect("outside");
+        fs::write(outside.join("private.txt"), "x").expect("outside file");
+        fs::write(root.join("notes.md"), "notes").expect("notes");
+        symlink(&root, root.join("studies").join("loop")).expect("cycle");
+        symlink(&outside, root.join("outside_link")).expect("outside link");
+        let store: ProjectsStore = serde_json::from_value(serde_json::json!({
+            "projects": [{
+                "id": "p1", "name": "Lab", "rootPath": root.to_string_lossy(),
+                "createdAt": "2026-01-01T00:00:00Z", "studies": []
+            }]
+        }))
+        .expect("store");
+        write_projects_store(&app_root, &store).expect("write store");
+
+        let report = export_project(
+            &app_root,
+            ExportProjectArgs {
+                project_id: "p1".to_string(),
+                destination: base.join("Lab.zip").to_string_lossy().to_string(),
+            },
+        )
+        .expect("export");
+        let names: Vec<String> = ZipArchive::new(fs::File::open(&report.archive_path).unwrap())
+            .expect("zip")
+            .file_names()
+            .map(str::to_string)
+            .collect();
+        assert!(names.iter().any(|n| n == "project/notes.md"));
+        assert!(!names
+            .iter()
+            .any(|n| n.contains("loop") || n.contains("private.txt")));
+        let _ = fs::remove_dir_all(base);
+    }
+}
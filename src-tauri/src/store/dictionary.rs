@@ -0,0 +1,508 @@
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use super::{read_projects_store, resolve_study_root};
+use crate::render::helpers::{analysis_paths, write_string};
+use crate::spec::types::DataContractSpec;
+use crate::util::csv_stream::{stream_rows, CsvReadOptions, DEFAULT_SAMPLE_ROWS, MAX_SAMPLE_ROWS};
+
+pub const CLEAN_DATA_DIR: &str = "05_data/clean";
+pub const DICTIONARY_SUFFIX: &str = "_dictionary.csv";
+pub const DICTIONARY_COLUMNS: &[&str] = &[
+    "variable",
+    "label",
+    "source",
+    "type",
+    "pct_missing",
+    "example_values",
+    "description",
+];
+
+/// Non-missing values quoted per variable in `example_values`.
+const EXAMPLE_VALUE_COUNT: usize = 3;
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GenerateDataDictionaryArgs {
+    pub project_id: String,
+    pub study_id: String,
+    /// Data file name, relative to `05_data/clean`.
+    pub data_file: String,
+    /// Data rows scanned for types and missingness; defaults to `DEFAULT_SAMPLE_ROWS`.
+    #[serde(default)]
+    pub sample_rows: Option<usize>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DataDictionaryReport {
+    /// Study-relative path of the written dictionary.
+    pub path: String,
+    pub variables: usize,
+    pub rows_scanned: u64,
+    /// Variables whose description was carried over from the previous dictionary.
+    pub preserved_descriptions: usize,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct DictionaryRow {
+    variable: String,
+    label: String,
+    source: String,
+    kind: String,
+    pct_missing: String,
+    example_values: String,
+    description: String,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SavedContract {
+    data_contract: DataContractSpec,
+}
+
+/// Label and source for every variable the saved analysis specs know about, keyed by
+/// both the export name and its `clean_names()` form.
+#[derive(Default)]
+struct ContractIndex {
+    entries: HashMap<String, (String, &'static str)>,
+}
+
+impl ContractIndex {
+    fn add(&mut self, names: &[&str], label: &str, source: &'static str) {
+        for name in names {
+            self.entries
+                .entry(name.to_string())
+                .or_insert_with(|| (label.to_string(), source));
+        }
+    }
+
+    fn from_contracts(contracts: &[DataContractSpec]) -> Self {
+        let mut index = ContractIndex::default();
+        for contract in contracts {
+            for derived in &contract.derived_variables {
+                index.add(&[&derived.name], &derived.definition, "derived");
+            }
+            for column in &contract.columns {
+                let label = contract
+                    .label_map
+                    .get(&column.name)
+                    .cloned()
+                    .unwrap_or_default();
+                let source = if contract.label_map.contains_key(&column.name) {
+                    "qsf_question"
+                } else if column.meta {
+                    "qualtrics_metadata"
+                } else {
+                    "embedded"
+                };
+                index.add(&[&column.name, &column.clean_name], &label, source);
+            }
+            for name in &contract.expected_columns {
+                let label = contract.label_map.get(name).cloned().unwrap_or_default();
+                let source = if label.is_empty() {
+                    "embedded"
+                } else {
+                    "qsf_question"
+                };
+                index.add(&[name], &label, source);
+            }
+        }
+        index
+    }
+
+    fn lookup(&self, variable: &str) -> (String, String) {
+        match self.entries.get(variable) {
+            Some((label, source)) => (label.clone(), source.to_string()),
+            None => (String::new(), "unknown".to_string()),
+        }
+    }
+}
+
+/// Data contracts from every saved spec under `06_analysis`, in folder name order.
+fn load_contracts(study_root: &Path) -> Vec<DataContractSpec> {
+    let mut specs: Vec<PathBuf> = fs::read_dir(study_root.join("06_analysis"))
+        .map(|entries| {
+            entries
+                .filter_map(|entry| entry.ok())
+                .map(|entry| analysis_paths(&entry.path()).0)
+                .filter(|spec_path| spec_path.is_file())
+                .collect()
+        })
+        .unwrap_or_default();
+    specs.sort();
+    specs
+        .iter()
+        .filter_map(|spec_path| fs::read_to_string(spec_path).ok())
+        .filter_map(|raw| serde_json::from_str::<SavedContract>(&raw).ok())
+        .map(|saved| saved.data_contract)
+        .collect()
+}
+
+struct ColumnScan {
+    missing: u64,
+    examples: Vec<String>,
+    integer: bool,
+    numeric: bool,
+    logical: bool,
+}
+
+impl ColumnScan {
+    fn new() -> Self {
+        Self {
+            missing: 0,
+            examples: Vec::new(),
+            integer: true,
+            numeric: true,
+            logical: true,
+        }
+    }
+
+    fn observe(&mut self, value: &str) {
+        let value = value.trim();
+        if value.is_empty() || value == "NA" {
+            self.missing += 1;
+            return;
+        }
+        self.integer &= value.parse::<i64>().is_ok();
+        self.numeric &= value.parse::<f64>().is_ok();
+        self.logical &= matches!(value, "TRUE" | "FALSE" | "true" | "false");
+        if self.examples.len() < EXAMPLE_VALUE_COUNT && !self.examples.iter().any(|v| v == value) {
+            self.examples.push(value.to_string());
+        }
+    }
+
+    fn kind(&self, rows: u64) -> &'static str {
+        if rows == self.missing {
+            "empty"
+        } else if self.logical {
+            "logical"
+        } else if self.integer {
+            "integer"
+        } else if self.numeric {
+            "numeric"
+        } else {
+            "character"
+        }
+    }
+
+    fn pct_missing(&self, rows: u64) -> String {
+        if rows == 0 {
+            return String::new();
+        }
+        format!("{:.1}", self.missing as f64 * 100.0 / rows as f64)
+    }
+}
+
+/// Resolves `data_file` inside `05_data/clean`, rejecting paths that leave it.
+fn clean_data_path(study_root: &Path, data_file: &str) -> Result<PathBuf, String> {
+    let trimmed = data_file.trim().replace('\\', "/");
+    let relative = trimmed
+        .strip_prefix(&format!("{CLEAN_DATA_DIR}/"))
+        .unwrap_or(&trimmed);
+    if relative.is_empty()
+        || relative
+            .split('/')
+            .any(|part| part.is_empty() || part == "." || part == "..")
+    {
+        return Err(format!(
+            "Data file must be a file name under {CLEAN_DATA_DIR}."
+        ));
+    }
+    if relative.to_lowercase().ends_with(DICTIONARY_SUFFIX) {
+        return Err("Data file is already a data dictionary.".to_string());
+    }
+    let path = study_root.join(CLEAN_DATA_DIR).join(relative);
+    if !path.is_file() {
+        return Err(format!(
+            "Data file {CLEAN_DATA_DIR}/{relative} does not exist."
+        ));
+    }
+    Ok(path)
+}
+
+fn dictionary_path(data_path: &Path) -> PathBuf {
+    let stem = data_path
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().to_string())
+        .unwrap_or_else(|| "data".to_string());
+    data_path.with_file_name(format!("{stem}{DICTIONARY_SUFFIX}"))
+}
+
+/// Descriptions from an existing dictionary, keyed by variable name.
+fn existing_descriptions(path: &Path) -> Result<BTreeMap<String, String>, String> {
+    if !path.exists() {
+        return Ok(BTreeMap::new());
+    }
+    let mut reader = csv::ReaderBuilder::new()
+        .flexible(true)
+        .from_path(path)
+        .map_err(|err| format!("Unable to read {}: {err}", path.display()))?;
+    let headers = reader
+        .headers()
+        .map_err(|err| format!("Unable to read {}: {err}", path.display()))?
+        .clone();
+    let position = |name: &str| {
+        headers
+            .iter()
+            .position(|header| header.trim_start_matches('\u{feff}').trim() == name)
+    };
+    let (Some(variable_col), Some(description_col)) =
+        (position("variable"), position("description"))
+    else {
+        return Ok(BTreeMap::new());
+    };
+    let mut out = BTreeMap::new();
+    for record in reader.records() {
+        let record = record.map_err(|err| format!("Unable to read {}: {err}", path.display()))?;
+        let variable = record.get(variable_col).unwrap_or("").trim();
+        let description = record.get(description_col).unwrap_or("");
+        if !variable.is_empty() && !description.trim().is_empty() {
+            out.insert(variable.to_string(), description.to_string());
+        }
+    }
+    Ok(out)
+}
+
+fn render_dictionary(rows: &[DictionaryRow]) -> Result<String, String> {
+    let mut writer = csv::Writer::from_writer(Vec::new());
+    writer
+        .write_record(DICTIONARY_COLUMNS)
+        .map_err(|err| err.to_string())?;
+    for row in rows {
+        writer
+            .write_record([
+                &row.variable,
+                &row.label,
+                &row.source,
+                &row.kind,
+                &row.pct_missing,
+                &row.example_values,
+                &row.description,
+            ])
+            .map_err(|err| err.to_string())?;
+    }
+    let bytes = writer.into_inner().map_err(|err| err.to_string())?;
+    String::from_utf8(bytes).map_err(|err| err.to_string())
+}
+
+/// Writes `<file>_dictionary.csv` next to a cleaned data file, keeping descriptions
+/// users already filled in.
+pub fn generate_data_dictionary_at(
+    study_root: &Path,
+    data_file: &str,
+    sample_rows: usize,
+) -> Result<DataDictionaryReport, String> {
+    let data_path = clean_data_path(study_root, data_file)?;
+    let out_path = dictionary_path(&data_path);
+    let previous = existing_descriptions(&out_path)?;
+    let index = ContractIndex::from_contracts(&load_contracts(study_root));
+
+    let limit = sample_rows.clamp(1, MAX_SAMPLE_ROWS) as u64;
+    let mut scans: Vec<ColumnScan> = Vec::new();
+    let mut rows_scanned = 0u64;
+    let (header, _) = stream_rows(&data_path, &CsvReadOptions::default(), |record| {
+        if rows_scanned >= limit {
+            return Ok(false);
+        }
+        rows_scanned += 1;
+        if scans.len() < record.len() {
+            scans.resize_with(record.len(), ColumnScan::new);
+        }
+        for (value, scan) in record.iter().zip(scans.iter_mut()) {
+            scan.observe(value);
+        }
+        Ok(true)
+    })?;
+    scans.resize_with(header.columns.len(), ColumnScan::new);
+
+    let mut preserved_descriptions = 0;
+    let rows: Vec<DictionaryRow> = header
+        .columns
+        .iter()
+        .zip(&scans)
+        .map(|(variable, scan)| {
+            let (label, source) = index.lookup(variable);
+            let description = previous.get(variable).cloned().unwrap_or_default();
+            if !description.is_empty() {
+                preserved_descriptions += 1;
+            }
+            DictionaryRow {
+                variable: variable.clone(),
+                label,
+                source,
+                kind: scan.kind(rows_scanned).to_string(),
+                pct_missing: scan.pct_missing(rows_scanned),
+                example_values: scan.examples.join("; "),
+                description,
+            }
+        })
+        .collect();
+
+    write_string(&out_path, &render_dictionary(&rows)?)?;
+    Ok(DataDictionaryReport {
+        path: out_path
+            .strip_prefix(study_root)
+            .unwrap_or(&out_path)
+            .to_string_lossy()
+            .replace('\\', "/"),
+        variables: rows.len(),
+        rows_scanned,
+        preserved_descriptions,
+    })
+}
+
+pub fn generate_data_dictionary(
+    app_root: &Path,
+    args: GenerateDataDictionaryArgs,
+) -> Result<DataDictionaryReport, String> {
+    let store = read_projects_store(app_root)?;
+    let project = store
+        .projects
+        .iter()
+        .find(|project| project.id == args.project_id)
+        .ok_or_else(|| "Project not found.".to_string())?;
+    let study = project
+        .studies
+        .iter()
+        .find(|study| study.id == args.study_id)
+        .ok_or_else(|| "Study not found.".to_string())?;
+    let study_root = resolve_study_root(project, study);
+    if !study_root.exists() {
+        return Err("Study folder does not exist.".to_string());
+    }
+    generate_data_dictionary_at(
+        &study_root,
+        &args.data_file,
+        args.sample_rows.unwrap_or(DEFAULT_SAMPLE_ROWS),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::qsf::types::ExpectedColumn;
+    use crate::spec::types::DerivedVariableSpec;
+    use uuid::Uuid;
+
+    fn seeded_study() -> PathBuf {
+        let study_root = std::env::temp_dir().join(format!("dictionary-test-{}", Uuid::new_v4()));
+        let contract = DataContractSpec {
+            source: "qualtrics_csv".to_string(),
+            id_columns: BTreeMap::new(),
+            expected_columns: vec!["Q1".to_string(), "condition".to_string()],
+            label_map: BTreeMap::from([("Q1".to_string(), "How satisfied are you?".to_string())]),
+            exclusions: vec![],
+            missingness: None,
+            derived_variables: vec![DerivedVariableSpec {
+                name: "sat_z".to_string(),
+                derived_type: "standardize".to_string(),
+                depends_on: vec!["q1".to_string()],
+                definition: "scale(q1)".to_string(),
+            }],
+            expected_values: BTreeMap::new(),
+            factor_levels: vec![],
+            randomized_levels: vec![],
+            indicator_columns: vec![],
+            free_text_columns: vec![],
+            columns: vec![
+                ExpectedColumn {
+                    name: "Q1".to_string(),
+                    clean_name: "q1".to_string(),
+                    meta: false,
+                },
+                ExpectedColumn {
+                    name: "condition".to_string(),
+                    clean_name: "condition".to_string(),
+                    meta: false,
+                },
+            ],
+            value_labels: BTreeMap::new(),
+        };
+        let spec_path = analysis_paths(&study_root.join("06_analysis").join("a1")).0;
+        write_string(
+            &spec_path,
+            &serde_json::json!({ "dataContract": contract }).to_string(),
+        )
+        .expect("write spec");
+        study_root
+    }
+
+    fn read_rows(path: &Path) -> Vec<Vec<String>> {
+        csv::Reader::from_path(path)
+            .expect("open dictionary")
+            .records()
+            .map(|record| record.expect("row").iter().map(str::to_string).collect())
+            .collect()
+    }
+
+    #[test]
+    fn generates_dictionary_from_contract_and_data_scan() {
+        let study_root = seeded_study();
+        write_string(
+            &study_root.join("05_data/clean/data_clean.csv"),
+            "q1,condition,sat_z,extra\n4,treat,0.5,\n,control,-1.2,x\n5,treat,NA,\n",
+        )
+        .expect("write data");
+
+        let report =
+            generate_data_dictionary_at(&study_root, "data_clean.csv", 1000).expect("generate");
+        assert_eq!(report.path, "05_data/clean/data_clean_dictionary.csv");
+        assert_eq!(report.variables, 4);
+        assert_eq!(report.rows_scanned, 3);
+
+        let rows = read_rows(&study_root.join(&report.path));
+        assert_eq!(
+            rows[0],
+            vec![
+                "q1",
+                "How satisfied are you?",
+                "qsf_question",
+                "integer",
+                "33.3",
+                "4; 5",
+                ""
+            ]
+        );
+        assert_eq!(rows[1][2], "embedded");
+        assert_eq!(rows[1][3], "character");
+        assert_eq!(rows[2][..4], ["sat_z", "scale(q1)", "derived", "numeric"]);
+        assert_eq!(rows[3][2], "unknown");
+        assert_eq!(rows[3][4], "66.7");
+
+        let err = generate_data_dictionary_at(&study_root, "../raw/data.csv", 1000)
+            .expect_err("path outside clean folder");
+        assert!(err.contains("05_data/clean"));
+        let _ = fs::remove_dir_all(study_root);
+    }
+
+    #[test]
+    fn regeneration_preserves_user_descriptions() {
+        let study_root = seeded_study();
+        let data_path = study_root.join("05_data/clean/data_clean.csv");
+        write_string(&data_path, "q1,condition\n4,treat\n,control\n").expect("write data");
+        let report =
+            generate_data_dictionary_at(&study_root, "data_clean.csv", 1000).expect("generate");
+        let dictionary = study_root.join(&report.path);
+
+        let edited = fs::read_to_string(&dictionary).expect("read").replace(
+            "q1,How satisfied are you?,qsf_question,integer,50.0,4,",
+            "q1,How satisfied are you?,qsf_question,integer,50.0,4,\"Satisfaction, 1-7\"",
+        );
+        fs::write(&dictionary, edited).expect("edit description");
+
+        write_string(&data_path, "q1,condition\n4,treat\n6,control\n").expect("rewrite data");
+        let report = generate_data_dictionary_at(&study_root, "05_data/clean/data_clean.csv", 1000)
+            .expect("regenerate");
+        assert_eq!(report.preserved_descriptions, 1);
+
+        let rows = read_rows(&dictionary);
+        assert_eq!(rows[0][4], "0.0");
+        assert_eq!(rows[0][5], "4; 6");
+        assert_eq!(rows[0][6], "Satisfaction, 1-7");
+        assert_eq!(rows[1][6], "");
+        let _ = fs::remove_dir_all(study_root);
+    }
+}
@@ -0,0 +1,1136 @@
+use pathdiff::diff_paths;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::ffi::OsStr;
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+use std::process::Command;
+
+use super::activity::record_activity;
+use super::osf_manifest::collect_files;
+use super::release_rules::ReleaseRules;
+use super::secrets::{SECRETS_DIR, SECRETS_FILE_NAME};
+use super::{
+    now_string, read_projects_store, resolve_study_root, write_projects_store, FileRef, Study,
+    STUDY_FOLDERS,
+};
+use crate::util::hash::sha256_file;
+
+pub(super) fn kind_from_ext(ext: Option<&OsStr>) -> String {
+    let value = ext
+        .and_then(|value| value.to_str())
+        .unwrap_or("")
+        .to_lowercase();
+    match value.as_str() {
+        "pdf" => "pdf".to_string(),
+        "md" | "markdown" => "md".to_string(),
+        "txt" => "txt".to_string(),
+        "doc" | "docx" => "docx".to_string(),
+        "csv" => "csv".to_string(),
+        "json" => "json".to_string(),
+        "qsf" => "qsf".to_string(),
+        "rmd" => "rmd".to_string(),
+        "r" => "r".to_string(),
+        "sav" => "sav".to_string(),
+        "dta" => "dta".to_string(),
+        "xlsx" | "xls" => "xlsx".to_string(),
+        "png" => "png".to_string(),
+        "jpg" | "jpeg" => "jpg".to_string(),
+        _ => "other".to_string(),
+    }
+}
+
+fn unique_dest_path(dest_dir: &Path, filename: &OsStr) -> PathBuf {
+    let candidate = dest_dir.join(filename);
+    if !candidate.exists() {
+        return candidate;
+    }
+
+    let filename_str = filename.to_string_lossy();
+    let path = Path::new(&*filename_str);
+    let stem = path
+        .file_stem()
+        .and_then(|value| value.to_str())
+        .unwrap_or("file");
+    let ext = path
+        .extension()
+        .and_then(|value| value.to_str())
+        .unwrap_or("");
+    let ext_suffix = if ext.is_empty() {
+        String::new()
+    } else {
+        format!(".{ext}")
+    };
+
+    for index in 1..=10_000 {
+        let next = format!("{stem} ({index}){ext_suffix}");
+        let candidate = dest_dir.join(next);
+        if !candidate.exists() {
+            return candidate;
+        }
+    }
+
+    candidate
+}
+
+fn move_file_cross_device(src: &Path, dst: &Path) -> Result<(), String> {
+    if src == dst {
+        return Ok(());
+    }
+    match fs::rename(src, dst) {
+        Ok(()) => Ok(()),
+        Err(_) => {
+            fs::copy(src, dst).map_err(|err| err.to_string())?;
+            fs::remove_file(src).map_err(|err| err.to_string())?;
+            Ok(())
+        }
+    }
+}
+
+/// Paths never copied or archived: excluded roots, VCS folders, node_modules and the stored
+/// secrets file.
+pub(super) fn should_skip(path: &Path, excluded: &[PathBuf]) -> bool {
+    if excluded.iter().any(|root| path.starts_with(root)) {
+        return true;
+    }
+    if path
+        .components()
+        .any(|part| matches!(part.as_os_str().to_str(), Some(".git" | "node_modules")))
+    {
+        return true;
+    }
+    path.ends_with(Path::new(SECRETS_DIR).join(SECRETS_FILE_NAME))
+}
+
+/// Which parts of a study tree an OSF package copy takes along.
+pub struct CopyFilter<'a> {
+    /// Paths never copied (the release folder and the package being written).
+    pub excluded: &'a [PathBuf],
+    pub include_pilots: bool,
+    pub condensed: bool,
+    /// Pilot and per-package exclusions, matched against paths relative to the copied root.
+    pub rules: &'a ReleaseRules,
+    /// When set, symlinks resolving inside this root are copied as regular files/folders.
+    pub follow_links_within: Option<&'a Path>,
+}
+
+/// A symlink left out of a copy, with the target it points to.
+#[derive(Debug, Serialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct SkippedLink {
+    pub source: String,
+    pub target: String,
+}
+
+/// A path the release rules left out of a copy, relative to the copied root.
+#[derive(Debug, Serialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct ExcludedPath {
+    pub path: String,
+    pub reason: String,
+}
+
+#[derive(Debug, Default)]
+pub struct CopySummary {
+    pub files: u64,
+    pub skipped_links: Vec<SkippedLink>,
+    /// Top-most paths dropped by `CopyFilter::rules`; their contents are not listed.
+    pub excluded: Vec<ExcludedPath>,
+}
+
+/// Copies `src` into `dst`, skipping excluded and VCS paths plus whatever `filter.rules`
+/// leave out of the package. Symlinks are not followed unless they resolve inside `follow_links_within`;
+/// every skipped link is recorded, and directories already visited are never walked twice.
+pub fn copy_dir_filtered(
+    src: &Path,
+    dst: &Path,
+    filter: &CopyFilter,
+) -> Result<CopySummary, String> {
+    let link_root = filter
+        .follow_links_within
+        .and_then(|root| fs::canonicalize(root).ok());
+    let mut summary = CopySummary::default();
+    let mut visited = HashSet::new();
+    copy_dir_walk(
+        src,
+        src,
+        dst,
+        filter,
+        link_root.as_deref(),
+        &mut visited,
+        &mut summary,
+    )?;
+    Ok(summary)
+}
+
+fn rule_exclusion(root: &Path, path: &Path, filter: &CopyFilter) -> Option<&'static str> {
+    path.strip_prefix(root).ok().and_then(|rel| {
+        filter
+            .rules
+            .exclusion_reason(rel, filter.include_pilots, filter.condensed)
+    })
+}
+
+fn is_filtered(root: &Path, path: &Path, filter: &CopyFilter) -> bool {
+    should_skip(path, filter.excluded)
+        || path.strip_prefix(root).is_ok_and(|rel| {
+            filter
+                .rules
+                .excludes(rel, filter.include_pilots, filter.condensed)
+        })
+}
+
+fn copy_dir_walk(
+    root: &Path,
+    src: &Path,
+    dst: &Path,
+    filter: &CopyFilter,
+    link_root: Option<&Path>,
+    visited: &mut HashSet<PathBuf>,
+    summary: &mut CopySummary,
+) -> Result<(), String> {
+    if is_filtered(root, src, filter) {
+        return Ok(());
+    }
+    let canonical = fs::canonicalize(src).map_err(|err| err.to_string())?;
+    if !visited.insert(canonical) {
+        return Ok(());
+    }
+
+    if !dst.exists() {
+        fs::create_dir_all(dst).map_err(|err| err.to_string())?;
+    }
+
+    for entry in fs::read_dir(src).map_err(|err| err.to_string())? {
+        let entry = entry.map_err(|err| err.to_string())?;
+        let path = entry.path();
+        if should_skip(&path, filter.excluded) {
+            continue;
+        }
+        if let Some(reason) = rule_exclusion(root, &path, filter) {
+            let rel = path.strip_prefix(root).unwrap_or(&path);
+            summary.excluded.push(ExcludedPath {
+                path: rel.to_string_lossy().replace('\\', "/"),
+                reason: reason.to_string(),
+            });
+            continue;
+        }
+        let target = dst.join(entry.file_name());
+        let metadata = fs::symlink_metadata(&path).map_err(|err| err.to_string())?;
+        if metadata.file_type().is_symlink() {
+            let resolved = fs::canonicalize(&path).ok();
+            let follow = match (&resolved, link_root) {
+                (Some(resolved), Some(root)) => resolved.starts_with(root),
+                _ => false,
+            };
+            if !follow {
+                let link_target = fs::read_link(&path)
+                    .map(|value| value.to_string_lossy().to_string())
+                    .unwrap_or_default();
+                summary.skipped_links.push(SkippedLink {
+                    source: path.to_string_lossy().to_string(),
+                    target: link_target,
+                });
+                continue;
+            }
+        }
+        if path.is_dir() {
+            copy_dir_walk(root, &path, &target, filter, link_root, visited, summary)?;
+        } else if path.is_file() {
+            if let Some(parent) = target.parent() {
+                fs::create_dir_all(parent).map_err(|err| err.to_string())?;
+            }
+            fs::copy(&path, &target).map_err(|err| err.to_string())?;
+            summary.files += 1;
+        }
+    }
+    Ok(())
+}
+
+/// Outcome for one requested path: "imported", "duplicate" (identical content is already
+/// registered at `path`) or "skipped" (missing, not a file, or already registered).
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ImportedFile {
+    pub source: String,
+    pub status: String,
+    pub path: Option<String>,
+}
+
+/// Where imported files go: a canonical study folder, optionally with a relative subfolder
+/// inside it (e.g. `05_data` + `raw/wave1`).
+#[derive(Debug, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ImportDestination {
+    folder: String,
+    #[serde(default)]
+    subpath: Option<String>,
+}
+
+impl ImportDestination {
+    /// The top level of a canonical study folder.
+    pub fn canonical(folder: &str) -> Self {
+        Self {
+            folder: folder.to_string(),
+            subpath: None,
+        }
+    }
+}
+
+/// Checks the destination against `STUDY_FOLDERS` and rejects subpaths that are absolute or
+/// step outside the folder. Returns the folder name and the directory inside `study_root`.
+fn import_destination_dir(
+    study_root: &Path,
+    destination: &ImportDestination,
+) -> Result<(String, PathBuf), String> {
+    let folder = destination.folder.trim();
+    if !STUDY_FOLDERS.contains(&folder) {
+        return Err(format!("Unknown study folder: {folder}"));
+    }
+    let mut dir = study_root.join(folder);
+    let subpath = destination.subpath.as_deref().unwrap_or("").trim();
+    if !subpath.is_empty() {
+        let subpath = Path::new(subpath);
+        if !subpath
+            .components()
+            .all(|component| matches!(component, Component::Normal(_)))
+        {
+            return Err(format!(
+                "Import subfolder must stay inside {folder}: {}",
+                subpath.display()
+            ));
+        }
+        dir = dir.join(subpath);
+    }
+    Ok((folder.to_string(), dir))
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ImportFilesReport {
+    pub study: Study,
+    pub results: Vec<ImportedFile>,
+}
+
+/// Registered file whose content hash matches `hash`. Entries imported before hashes were
+/// recorded are hashed on the way and keep the result.
+fn find_duplicate(files: &mut [FileRef], project_root: &Path, hash: &str) -> Option<String> {
+    for file in files.iter_mut() {
+        if file.sha256.is_none() {
+            file.sha256 = sha256_file(&project_root.join(&file.path)).ok();
+        }
+        if file.sha256.as_deref() == Some(hash) {
+            return Some(file.path.clone());
+        }
+    }
+    None
+}
+
+/// Moves files into `destination`, or the study's `sources` folder when none is given. Files
+/// whose content is already registered are left in place and reported as duplicates unless
+/// `force_copy` is set.
+pub fn import_files(
+    app_root: &Path,
+    project_id: String,
+    study_id: String,
+    paths: Vec<String>,
+    force_copy: bool,
+    destination: Option<ImportDestination>,
+) -> Result<ImportFilesReport, String> {
+    let mut store = read_projects_store(app_root)?;
+    let project = store
+        .projects
+        .iter_mut()
+        .find(|project| project.id == project_id)
+        .ok_or_else(|| "Project not found.".to_string())?;
+    let project_root = PathBuf::from(project.root_path.clone());
+    let study_root = project
+        .studies
+        .iter()
+        .find(|study| study.id == study_id)
+        .map(|study| resolve_study_root(project, study))
+        .ok_or_else(|| "Study not found.".to_string())?;
+    let (folder, dest_dir) = match &destination {
+        Some(destination) => {
+            let (folder, dir) = import_destination_dir(&study_root, destination)?;
+            (Some(folder), dir)
+        }
+        None => (
+            None,
+            project_root.join("studies").join(&study_id).join("sources"),
+        ),
+    };
+
+    let study = project
+        .studies
+        .iter_mut()
+        .find(|study| study.id == study_id)
+        .ok_or_else(|| "Study not found.".to_string())?;
+
+    fs::create_dir_all(&dest_dir).map_err(|err| err.to_string())?;
+
+    let mut known_paths: HashSet<String> =
+        study.files.iter().map(|file| file.path.clone()).collect();
+    let mut imported = 0usize;
+    let mut results: Vec<ImportedFile> = Vec::new();
+    let mut report = |source: &str, status: &str, path: Option<String>| {
+        results.push(ImportedFile {
+            source: source.to_string(),
+            status: status.to_string(),
+            path,
+        });
+    };
+
+    for source in paths {
+        let trimmed = source.trim();
+        if trimmed.is_empty() {
+            continue;
+        }
+        let src = PathBuf::from(trimmed);
+        if !src.exists() || !src.is_file() {
+            report(trimmed, "skipped", None);
+            continue;
+        }
+        let filename = match src.file_name() {
+            Some(value) => value,
+            None => continue,
+        };
+
+        let hash = sha256_file(&src)?;
+        if !force_copy && !src.starts_with(&dest_dir) {
+            if let Some(existing) = find_duplicate(&mut study.files, &project_root, &hash) {
+                report(trimmed, "duplicate", Some(existing));
+                continue;
+            }
+        }
+
+        let dest_path = if src.starts_with(&dest_dir) {
+            src.clone()
+        } else {
+            unique_dest_path(&dest_dir, filename)
+        };
+
+        let rel_path = diff_paths(&dest_path, &project_root).unwrap_or(dest_path.clone());
+        let mut rel_string = rel_path.to_string_lossy().to_string();
+        if rel_string.contains('\\') {
+            rel_string = rel_string.replace('\\', "/");
+        }
+
+        if known_paths.contains(&rel_string) {
+            report(trimmed, "skipped", Some(rel_string));
+            continue;
+        }
+
+        if src != dest_path {
+            move_file_cross_device(&src, &dest_path)?;
+        }
+
+        let name = dest_path
+            .file_name()
+            .and_then(|value| value.to_str())
+            .unwrap_or("file")
+            .to_string();
+        let kind = kind_from_ext(dest_path.extension());
+
+        study.files.push(FileRef {
+            path: rel_string.clone(),
+            name,
+            kind,
+            sha256: Some(hash),
+            folder: folder.clone(),
+        });
+        report(trimmed, "imported", Some(rel_string.clone()));
+        known_paths.insert(rel_string);
+        imported += 1;
+    }
+
+    project.updated_at = now_string();
+    let updated = study.clone();
+    write_projects_store(app_root, &store)?;
+    if imported > 0 {
+        record_activity(
+            app_root,
+            "import_files",
+            Some(&project_id),
+            Some(&study_id),
+            &format!("Imported {imported} file(s)"),
+        );
+    }
+    Ok(ImportFilesReport {
+        study: updated,
+        results,
+    })
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RemoveFileArgs {
+    project_id: String,
+    study_id: String,
+    path: String,
+}
+
+pub fn remove_file_ref(app_root: &Path, args: RemoveFileArgs) -> Result<Study, String> {
+    let mut store = read_projects_store(app_root)?;
+    let project = store
+        .projects
+        .iter_mut()
+        .find(|project| project.id == args.project_id)
+        .ok_or_else(|| "Project not found.".to_string())?;
+    let project_root = PathBuf::from(project.root_path.clone());
+
+    let study = project
+        .studies
+        .iter_mut()
+        .find(|study| study.id == args.study_id)
+        .ok_or_else(|| "Study not found.".to_string())?;
+
+    let rel = args.path.trim();
+    if !rel.is_empty() {
+        let candidate = project_root.join(rel);
+        let candidate = fs::canonicalize(&candidate).unwrap_or(candidate);
+        let root = fs::canonicalize(&project_root).unwrap_or(project_root.clone());
+        if candidate.starts_with(&root) && candidate.is_file() {
+            let _ = fs::remove_file(&candidate);
+        }
+    }
+
+    study.files.retain(|file| file.path != rel);
+    project.updated_at = now_string();
+    let updated = study.clone();
+    write_projects_store(app_root, &store)?;
+    Ok(updated)
+}
+
+/// Study folders files are imported into; generated folders (analysis, outputs, release) hold
+/// files the app writes without registering them, so they are not reconciled.
+pub(super) const RECONCILED_FOLDERS: &[&str] = &[
+    "sources",
+    "00_admin",
+    "01_design",
+    "02_build",
+    "03_pilots",
+    "04_prereg",
+    "05_data",
+];
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReconcileFileRefsArgs {
+    project_id: String,
+}
+
+#[derive(Debug, Serialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct FileRefDrift {
+    pub study_id: String,
+    /// Project-relative, like `FileRef::path`.
+    pub path: String,
+    pub folder: Option<String>,
+}
+
+#[derive(Debug, Serialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct FileRefReconciliation {
+    /// Registered files that are no longer on disk.
+    pub missing: Vec<FileRefDrift>,
+    /// Files in a study's import folders that no file ref points at.
+    pub orphaned: Vec<FileRefDrift>,
+}
+
+pub(super) fn project_relative(path: &Path, project_root: &Path) -> String {
+    diff_paths(path, project_root)
+        .unwrap_or_else(|| path.to_path_buf())
+        .to_string_lossy()
+        .replace('\\', "/")
+}
+
+/// Compares each study's file refs with its folders on disk. Hidden files (`.DS_Store`, sync
+/// clients' temporary files) are never reported as orphaned.
+pub fn reconcile_file_refs(
+    app_root: &Path,
+    args: ReconcileFileRefsArgs,
+) -> Result<FileRefReconciliation, String> {
+    let store = read_projects_store(app_root)?;
+    let project = store
+        .projects
+        .iter()
+        .find(|project| project.id == args.project_id)
+        .ok_or_else(|| "Project not found.".to_string())?;
+    let project_root = PathBuf::from(&project.root_path);
+
+    let mut report = FileRefReconciliation::default();
+    for study in &project.studies {
+        let study_root = resolve_study_root(project, study);
+        let mut registered = HashSet::new();
+        for file in &study.files {
+            registered.insert(file.path.clone());
+            if !project_root.join(&file.path).is_file() {
+                report.missing.push(FileRefDrift {
+                    study_id: study.id.clone(),
+                    path: file.path.clone(),
+                    folder: file.folder.clone(),
+                });
+            }
+        }
+        for folder in RECONCILED_FOLDERS {
+            let dir = study_root.join(folder);
+            if !dir.is_dir() {
+                continue;
+            }
+            let mut found = Vec::new();
+            collect_files(&dir, &mut found)?;
+            found.sort();
+            for path in found {
+                let hidden = path
+                    .file_name()
+                    .is_some_and(|name| name.to_string_lossy().starts_with('.'));
+                let rel = project_relative(&path, &project_root);
+                if !hidden && !registered.contains(&rel) {
+                    report.orphaned.push(FileRefDrift {
+                        study_id: study.id.clone(),
+                        path: rel,
+                        folder: Some(folder.to_string()),
+                    });
+                }
+            }
+        }
+    }
+    Ok(report)
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OpenPathArgs {
+    project_id: String,
+    #[serde(default)]
+    study_id: Option<String>,
+    /// Relative to the study folder when a study is given and to the project root otherwise,
+    /// or absolute. Without it the study folder (or the project root) is opened.
+    #[serde(default)]
+    path: Option<String>,
+}
+
+/// Existing target of `args`, checked (after canonicalizing) to lie inside the study folder,
+/// or the project root when no study is given. Study folders may live outside the project
+/// root. The path is returned as joined: canonical paths are verbatim (`\\?\C:\...`) on
+/// Windows, which explorer does not accept.
+fn resolve_open_target(app_root: &Path, args: &OpenPathArgs) -> Result<PathBuf, String> {
+    let store = read_projects_store(app_root)?;
+    let project = store
+        .projects
+        .iter()
+        .find(|project| project.id == args.project_id)
+        .ok_or_else(|| "Project not found.".to_string())?;
+    let (base, scope) = match &args.study_id {
+        Some(study_id) => {
+            let study = project
+                .studies
+                .iter()
+                .find(|study| &study.id == study_id)
+                .ok_or_else(|| "Study not found.".to_string())?;
+            (resolve_study_root(project, study), "study")
+        }
+        None => (PathBuf::from(project.root_path.clone()), "project"),
+    };
+
+    let target = match args
+        .path
+        .as_deref()
+        .map(str::trim)
+        .filter(|p| !p.is_empty())
+    {
+        Some(path) => base.join(path),
+        None => base.clone(),
+    };
+    let resolved = fs::canonicalize(&target)
+        .map_err(|_| format!("Path does not exist: {}", target.display()))?;
+    let root = fs::canonicalize(&base).unwrap_or(base);
+    if !resolved.starts_with(&root) {
+        return Err(format!(
+            "Path is outside the {scope} folder: {}",
+            target.display()
+        ));
+    }
+    Ok(target)
+}
+
+/// explorer.exe arguments, already quoted: it only understands `/select,"<path>"`, not the
+/// fully quoted argument `Command::arg` would produce for a path with spaces. Verbatim
+/// prefixes are dropped since explorer ignores such paths and opens the default folder.
+fn explorer_args(path: &str, reveal: bool) -> Vec<String> {
+    let path = match path.strip_prefix(r"\\?\") {
+        Some(rest) => match rest.strip_prefix(r"UNC\") {
+            Some(share) => format!(r"\\{share}"),
+            None => rest.to_string(),
+        },
+        None => path.to_string(),
+    };
+    if reveal {
+        vec![format!("/select,\"{path}\"")]
+    } else {
+        vec![format!("\"{path}\"")]
+    }
+}
+
+/// Platform opener for `target`. Revealing selects the item in its folder; xdg-open cannot
+/// select, so on Linux a file's parent folder is opened instead.
+fn opener_command(target: &Path, reveal: bool) -> (&'static str, Vec<String>) {
+    let path = target.to_string_lossy().to_string();
+    if cfg!(target_os = "windows") {
+        ("explorer", explorer_args(&path, reveal))
+    } else if cfg!(target_os = "macos") {
+        if reveal {
+            ("open", vec!["-R".to_string(), path])
+        } else {
+            ("open", vec![path])
+        }
+    } else {
+        let folder = match target.parent() {
+            Some(parent) if reveal && !target.is_dir() => parent.to_string_lossy().to_string(),
+            _ => path,
+        };
+        ("xdg-open", vec![folder])
+    }
+}
+
+fn spawn_opener(program: &str, args: &[String]) -> Result<(), String> {
+    let mut command = Command::new(program);
+    #[cfg(target_os = "windows")]
+    {
+        use std::os::windows::process::CommandExt;
+        // explorer arguments come pre-quoted from `explorer_args`.
+        for arg in args {
+            command.raw_arg(arg);
+        }
+    }
+    #[cfg(not(target_os = "windows"))]
+    command.args(args);
+    command
+        .spawn()
+        .map(|_| ())
+        .map_err(|err| format!("Unable to launch {program}: {err}"))
+}
+
+/// Resolves and validates the target, then hands the opener command to `run`. Returns the
+/// opened path.
+pub fn open_in_file_manager_with(
+    app_root: &Path,
+    args: &OpenPathArgs,
+    reveal: bool,
+    run: impl FnOnce(&str, &[String]) -> Result<(), String>,
+) -> Result<String, String> {
+    let target = resolve_open_target(app_root, args)?;
+    let (program, opener_args) = opener_command(&target, reveal);
+    run(program, &opener_args)?;
+    Ok(target.to_string_lossy().to_string())
+}
+
+pub fn reveal_in_file_manager(app_root: &Path, args: OpenPathArgs) -> Result<String, String> {
+    open_in_file_manager_with(app_root, &args, true, spawn_opener)
+}
+
+pub fn open_path(app_root: &Path, args: OpenPathArgs) -> Result<String, String> {
+    open_in_file_manager_with(app_root, &args, false, spawn_opener)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use uuid::Uuid;
+
+    #[cfg(unix)]
+    #[test]
+    fn copy_skips_symlinks_and_terminates_on_cycles() {
+        use std::os::unix::fs::symlink;
+
+        let base = std::env::temp_dir().join(format!("copy-links-{}", Uuid::new_v4()));
+        let study = base.join("study");
+        let outside = base.join("outside.txt");
+        fs::create_dir_all(study.join("06_analysis")).expect("failed to create study");
+        fs::write(study.join("06_analysis").join("analysis.Rmd"), "x")
+            .expect("failed to write analysis");
+        fs::write(&outside, "unrelated").expect("failed to write outside file");
+        symlink(&study, study.join("06_analysis").join("loop")).expect("failed to link cycle");
+        symlink(&outside, study.join("outside_link.txt")).expect("failed to link outside");
+        symlink(
+            study.join("06_analysis").join("analysis.Rmd"),
+            study.join("analysis_link.Rmd"),
+        )
+        .expect("failed to link inside");
+
+        let skipped = copy_dir_filtered(
+            &study,
+            &base.join("skip"),
+            &CopyFilter {
+                excluded: &[],
+                include_pilots: false,
+                condensed: false,
+                rules: &ReleaseRules::default(),
+                follow_links_within: None,
+            },
+        )
+        .expect("copy should finish");
+        assert_eq!(skipped.files, 1);
+        assert_eq!(skipped.skipped_links.len(), 3);
+        assert!(skipped.skipped_links.iter().any(|link| {
+            link.source.ends_with("loop") && link.target == study.to_string_lossy()
+        }));
+
+        let followed = copy_dir_filtered(
+            &study,
+            &base.join("follow"),
+            &CopyFilter {
+                excluded: &[],
+                include_pilots: false,
+                condensed: false,
+                rules: &ReleaseRules::default(),
+                follow_links_within: Some(&study),
+            },
+        )
+        .expect("copy should finish");
+        assert_eq!(followed.files, 2);
+        assert!(base.join("follow").join("analysis_link.Rmd").is_file());
+        assert_eq!(followed.skipped_links.len(), 1);
+        assert!(followed.skipped_links[0]
+            .source
+            .ends_with("outside_link.txt"));
+        let _ = fs::remove_dir_all(base);
+    }
+
+    #[test]
+    fn reimporting_identical_content_reports_duplicate_instead_of_copying() {
+        use crate::store::projects::{add_study, create_project};
+
+        let base = std::env::temp_dir().join(format!("import-dedup-{}", Uuid::new_v4()));
+        let app_root = base.join("app");
+        let downloads = base.join("downloads");
+        fs::create_dir_all(&app_root).expect("failed to create app root");
+        fs::create_dir_all(downloads.join("again")).expect("failed to create downloads");
+        let project = create_project(
+            &app_root,
+            serde_json::from_value(serde_json::json!({
+                "name": "Demo",
+                "rootDir": base.to_string_lossy(),
+                "googleDriveUrl": null
+            }))
+            .expect("project args"),
+        )
+        .expect("project should be created");
+        add_study(
+            &app_root,
+            serde_json::from_value(serde_json::json!({
+                "projectId": project.id,
+                "folderName": "S-ABC123",
+                "title": null
+            }))
+            .expect("study args"),
+        )
+        .expect("study should be added");
+        let import = |path: &Path, force_copy: bool| {
+            import_files(
+                &app_root,
+                project.id.clone(),
+                "S-ABC123".to_string(),
+                vec![path.to_string_lossy().to_string()],
+                force_copy,
+                None,
+            )
+            .expect("import should succeed")
+        };
+
+        let first = downloads.join("data.csv");
+        fs::write(&first, "id,score\n1,4\n").expect("write first");
+        let report = import(&first, false);
+        assert_eq!(report.results[0].status, "imported");
+        assert!(report.study.files[0].sha256.is_some());
+
+        let again = downloads.join("again").join("data.csv");
+        fs::write(&again, "id,score\n1,4\n").expect("write copy");
+        let report = import(&again, false);
+        assert_eq!(report.results[0].status, "duplicate");
+        assert_eq!(
+            report.results[0].path.as_deref(),
+            Some(report.study.files[0].path.as_str())
+        );
+        assert_eq!(report.study.files.len(), 1);
+        assert!(again.is_file());
+        let sources = base
+            .join("Demo")
+            .join("studies")
+            .join("S-ABC123")
+            .join("sources");
+        assert_eq!(fs::read_dir(&sources).expect("sources").count(), 1);
+
+        let changed = downloads.join("again").join("data.csv");
+        fs::write(&changed, "id,score\n1,5\n").expect("write changed");
+        let report = import(&changed, false);
+        assert_eq!(report.results[0].status, "imported");
+        assert!(report.study.files[1].path.ends_with("data (1).csv"));
+
+        fs::write(&again, "id,score\n1,4\n").expect("write forced copy");
+        let report = import(&again, true);
+        assert_eq!(report.results[0].status, "imported");
+        assert!(report.study.files[2].path.ends_with("data (2).csv"));
+        let _ = fs::remove_dir_all(base);
+    }
+
+    #[test]
+    fn imports_into_a_canonical_study_folder_are_registered_as_build_assets() {
+        use crate::commands::assets::list_build_assets_at;
+        use crate::store::projects::{add_study, create_project};
+
+        let base = std::env::temp_dir().join(format!("import-destination-{}", Uuid::new_v4()));
+        let app_root = base.join("app");
+        fs::create_dir_all(&app_root).expect("failed to create app root");
+        let project = create_project(
+            &app_root,
+            serde_json::from_value(serde_json::json!({
+                "name": "Demo",
+                "rootDir": base.to_string_lossy(),
+                "googleDriveUrl": null
+            }))
+            .expect("project args"),
+        )
+        .expect("project should be created");
+        add_study(
+            &app_root,
+            serde_json::from_value(serde_json::json!({
+                "projectId": project.id,
+                "folderName": "S-ABC123",
+                "title": null
+            }))
+            .expect("study args"),
+        )
+        .expect("study should be added");
+        let survey = base.join("Survey Export.qsf");
+        fs::write(&survey, "{}").expect("write qsf");
+        let import = |folder: &str, subpath: Option<&str>| {
+            import_files(
+                &app_root,
+                project.id.clone(),
+                "S-ABC123".to_string(),
+                vec![survey.to_string_lossy().to_string()],
+                false,
+                Some(
+                    serde_json::from_value(serde_json::json!({
+                        "folder": folder,
+                        "subpath": subpath
+                    }))
+                    .expect("destination"),
+                ),
+            )
+        };
+
+        assert!(import("sources", None).is_err());
+        assert!(import("02_build", Some("../../escape")).is_err());
+        let report = import("02_build", Some("qualtrics")).expect("import should succeed");
+        assert_eq!(report.results[0].status, "imported");
+        let file = &report.study.files[0];
+        assert_eq!(
+            file.path,
+            "studies/S-ABC123/02_build/qualtrics/Survey Export.qsf"
+        );
+        assert_eq!(file.kind, "qsf");
+        assert_eq!(file.folder.as_deref(), Some("02_build"));
+
+        let assets = list_build_assets_at(&app_root, &project.id, "S-ABC123").expect("assets");
+        assert_eq!(assets.len(), 1);
+        assert_eq!(assets[0].name, "Survey Export.qsf");
+        assert!(Path::new(&assets[0].path).ends_with("02_build/qualtrics/Survey Export.qsf"));
+        let _ = fs::remove_dir_all(base);
+    }
+
+    #[test]
+    fn open_targets_must_exist_inside_the_project() {
+        use crate::store::projects::{add_study, create_project};
+
+        let base = std::env::temp_dir().join(format!("open-path-{}", Uuid::new_v4()));
+        let app_root = base.join("app");
+        fs::create_dir_all(&app_root).expect("failed to create app root");
+        fs::write(base.join("outside.txt"), "x").expect("write outside file");
+        let project = create_project(
+            &app_root,
+            serde_json::from_value(serde_json::json!({
+                "name": "Demo",
+                "rootDir": base.to_string_lossy(),
+                "googleDriveUrl": null
+            }))
+            .expect("project args"),
+        )
+        .expect("project should be created");
+        add_study(
+            &app_root,
+            serde_json::from_value(serde_json::json!({
+                "projectId": project.id,
+                "folderName": "S-ABC123",
+                "title": null
+            }))
+            .expect("study args"),
+        )
+        .expect("study should be added");
+        let open = |study_id: Option<&str>, path: Option<String>| {
+            let args = OpenPathArgs {
+                project_id: project.id.clone(),
+                study_id: study_id.map(str::to_string),
+                path,
+            };
+            let mut launched = None;
+            let result = open_in_file_manager_with(&app_root, &args, true, |program, args| {
+                launched = Some((program.to_string(), args.to_vec()));
+                Ok(())
+            });
+            (result, launched)
+        };
+
+        let (study_folder, launched) = open(Some("S-ABC123"), None);
+        let study_folder = PathBuf::from(study_folder.expect("study folder opens"));
+        assert!(study_folder.ends_with("S-ABC123"));
+        assert!(launched.is_some());
+
+        let rmd = study_folder.join("06_analysis").join("analysis.Rmd");
+        fs::create_dir_all(rmd.parent().unwrap()).expect("create analysis folder");
+        fs::write(&rmd, "x").expect("write rmd");
+        let (opened, _) = open(None, Some(rmd.to_string_lossy().to_string()));
+        assert_eq!(
+            opened.expect("absolute path inside the project"),
+            rmd.to_string_lossy()
+        );
+
+        let (relative, _) = open(
+            Some("S-ABC123"),
+            Some("06_analysis/analysis.Rmd".to_string()),
+        );
+        assert_eq!(
+            relative.expect("path relative to the study folder"),
+            rmd.to_string_lossy()
+        );
+
+        for (study_id, escaping, scope) in [
+            (None, "../outside.txt".to_string(), "project"),
+            (
+                Some("S-ABC123"),
+                "../../../outside.txt".to_string(),
+                "study",
+            ),
+            (
+                Some("S-ABC123"),
+                base.join("outside.txt").to_string_lossy().to_string(),
+                "study",
+            ),
+        ] {
+            let (result, launched) = open(study_id, Some(escaping));
+            assert!(result
+                .expect_err("outside")
+                .contains(&format!("outside the {scope} folder")));
+            assert!(launched.is_none());
+        }
+
+        // A study kept outside the project root still opens.
+        let elsewhere = base.join("elsewhere");
+        fs::create_dir_all(elsewhere.join("05_data")).expect("outside study");
+        let mut store = read_projects_store(&app_root).expect("store");
+        store.projects[0].studies[0].folder_path = elsewhere.to_string_lossy().to_string();
+        write_projects_store(&app_root, &store).expect("write store");
+        let (data, _) = open(Some("S-ABC123"), Some("05_data".to_string()));
+        assert!(PathBuf::from(data.expect("outside study folder")).ends_with("elsewhere/05_data"));
+        assert_eq!(
+            explorer_args(r"C:\a b\c.csv", true),
+            vec![r#"/select,"C:\a b\c.csv""#.to_string()]
+        );
+        // What fs::canonicalize returns on Windows.
+        assert_eq!(
+            explorer_args(r"\\?\C:\a b\c.csv", true),
+            vec![r#"/select,"C:\a b\c.csv""#.to_string()]
+        );
+        assert_eq!(
+            explorer_args(r"\\?\UNC\server\share\data", false),
+            vec![r#""\\server\share\data""#.to_string()]
+        );
+        let (missing, launched) = open(None, Some("studies/S-ABC123/missing.csv".to_string()));
+        assert!(missing.expect_err("missing").contains("does not exist"));
+        assert!(launched.is_none());
+        let _ = fs::remove_dir_all(base);
+    }
+
+    #[test]
+    fn reconcile_reports_missing_refs_and_unregistered_files() {
+        use crate::store::projects::{add_study, create_project};
+
+        let base = std::env::temp_dir().join(format!("reconcile-{}", Uuid::new_v4()));
+        let app_root = base.join("app");
+        fs::create_dir_all(&app_root).expect("failed to create app root");
+        let project = create_project(
+            &app_root,
+            serde_json::from_value(serde_json::json!({
+                "name": "Demo",
+                "rootDir": base.to_string_lossy(),
+                "googleDriveUrl": null
+            }))
+            .expect("project args"),
+        )
+        .expect("project should be created");
+        add_study(
+            &app_root,
+            serde_json::from_value(serde_json::json!({
+                "projectId": project.id,
+                "folderName": "S-ABC123",
+                "title": null
+            }))
+            .expect("study args"),
+        )
+        .expect("study should be added");
+        let kept = base.join("kept.csv");
+        let gone = base.join("gone.csv");
+        fs::write(&kept, "a").expect("write kept");
+        fs::write(&gone, "b").expect("write gone");
+        import_files(
+            &app_root,
+            project.id.clone(),
+            "S-ABC123".to_string(),
+            vec![
+                kept.to_string_lossy().to_string(),
+                gone.to_string_lossy().to_string(),
+            ],
+            false,
+            Some(ImportDestination::canonical("05_data")),
+        )
+        .expect("import should succeed");
+
+        let study_root = base.join("Demo").join("studies").join("S-ABC123");
+        fs::remove_file(study_root.join("05_data").join("gone.csv")).expect("delete");
+        // Dropped in by a collaborator; generated and hidden files are not reported.
+        fs::create_dir_all(study_root.join("05_data").join("raw")).expect("mkdir");
+        fs::write(
+            study_root.join("05_data").join("raw").join("wave2.csv"),
+            "c",
+        )
+        .expect("write");
+        fs::write(study_root.join("05_data").join(".DS_Store"), "").expect("write");
+        fs::create_dir_all(study_root.join("07_outputs")).expect("mkdir");
+        fs::write(study_root.join("07_outputs").join("report.html"), "").expect("write");
+
+        let report = reconcile_file_refs(
+            &app_root,
+            serde_json::from_value(serde_json::json!({ "projectId": project.id })).expect("args"),
+        )
+        .expect("reconcile");
+        assert_eq!(
+            report.missing,
+            vec![FileRefDrift {
+                study_id: "S-ABC123".to_string(),
+                path: "studies/S-ABC123/05_data/gone.csv".to_string(),
+                folder: Some("05_data".to_string()),
+            }]
+        );
+        assert_eq!(
+            report.orphaned,
+            vec![FileRefDrift {
+                study_id: "S-ABC123".to_string(),
+                path: "studies/S-ABC123/05_data/raw/wave2.csv".to_string(),
+                folder: Some("05_data".to_string()),
+            }]
+        );
+        let _ = fs::remove_dir_all(base);
+    }
+}
@@ -0,0 +1,572 @@
+pub mod activity;
+pub mod archive;
+pub mod dictionary;
+pub mod files;
+pub mod osf_manifest;
+pub mod projects;
+pub mod readiness;
+pub mod release_rules;
+pub mod search;
+pub mod secrets;
+pub mod sqlite;
+pub mod storage;
+pub mod structure;
+pub mod trash;
+
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use uuid::Uuid;
+
+use crate::spec::types::{WarningItem, WarningSeverity};
+
+pub const PROJECTS_RECOVERED_FROM_BACKUP: &str = "PROJECTS_RECOVERED_FROM_BACKUP";
+pub const PROJECT_FOLDERS: &[&str] = &["studies", "paper", "templates"];
+pub const STUDY_FOLDERS: &[&str] = &[
+    "00_admin",
+    "01_design",
+    "02_build",
+    "03_pilots",
+    "04_prereg",
+    "05_data",
+    "06_analysis",
+    "07_outputs",
+    "08_osf_release",
+];
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct Project {
+    pub id: String,
+    pub name: String,
+    #[serde(alias = "root_path")]
+    pub root_path: String,
+    #[serde(alias = "created_at")]
+    pub created_at: String,
+    #[serde(default)]
+    #[serde(alias = "updated_at")]
+    pub updated_at: String,
+    #[serde(default)]
+    #[serde(alias = "google_drive_url")]
+    pub google_drive_url: Option<String>,
+    #[serde(default)]
+    #[serde(alias = "analysis_package_defaults")]
+    pub analysis_package_defaults: Option<AnalysisPackages>,
+    #[serde(default)]
+    pub studies: Vec<Study>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct ProjectsStore {
+    pub projects: Vec<Project>,
+    /// Set when the store was just recovered from its backup; never written to disk.
+    #[serde(skip)]
+    pub warning: Option<WarningItem>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct Study {
+    pub id: String,
+    pub title: String,
+    #[serde(alias = "created_at")]
+    pub created_at: String,
+    #[serde(default)]
+    #[serde(alias = "folder_path")]
+    pub folder_path: String,
+    #[serde(default)]
+    pub files: Vec<FileRef>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct FileRef {
+    pub path: String,
+    pub name: String,
+    pub kind: String,
+    /// Content hash recorded on import; older entries are backfilled when first compared.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sha256: Option<String>,
+    /// Canonical study folder (one of `STUDY_FOLDERS`) the file was imported into; unset for
+    /// files in the legacy `sources` folder.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub folder: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct AnalysisPackages {
+    pub cleaning: Vec<String>,
+    pub plot: Vec<String>,
+    pub table: Vec<String>,
+    pub analysis: Vec<String>,
+}
+
+pub fn projects_path(app_root: &Path) -> PathBuf {
+    app_root.join("projects.json")
+}
+
+pub fn now_string() -> String {
+    Utc::now().to_rfc3339()
+}
+
+pub fn is_valid_study_folder(value: &str) -> bool {
+    let mut chars = value.chars();
+    if chars.next() != Some('S') || chars.next() != Some('-') {
+        return false;
+    }
+    let rest: Vec<char> = chars.collect();
+    if rest.len() != 6 {
+        return false;
+    }
+    rest.iter().all(|ch| ch.is_ascii_alphanumeric())
+}
+
+pub fn find_entry_case_insensitive(dir: &Path, name: &str) -> Result<Option<String>, String> {
+    if !dir.exists() {
+        return Ok(None);
+    }
+    for entry in fs::read_dir(dir).map_err(|err| err.to_string())? {
+        let entry = entry.map_err(|err| err.to_string())?;
+        let entry_name = entry.file_name().to_string_lossy().to_string();
+        if entry_name.eq_ignore_ascii_case(name) {
+            return Ok(Some(entry_name));
+        }
+    }
+    Ok(None)
+}
+
+pub fn ensure_study_folder_available(
+    studies: &[Study],
+    studies_dir: &Path,
+    candidate: &str,
+    current_study_id: Option<&str>,
+) -> Result<(), String> {
+    let is_current = |id: &str| current_study_id.is_some_and(|current| current == id);
+    if let Some(existing) = studies
+        .iter()
+        .find(|study| study.id.eq_ignore_ascii_case(candidate) && !is_current(&study.id))
+    {
+        return Err(format!(
+            "Study code already exists: '{}' conflicts with existing study '{}' ({}).",
+            candidate, existing.id, existing.title
+        ));
+    }
+
+    let Some(on_disk) = find_entry_case_insensitive(studies_dir, candidate)? else {
+        return Ok(());
+    };
+    let current_folder = current_study_id
+        .and_then(|id| studies.iter().find(|study| study.id == id))
+        .map(|study| {
+            let path = if study.folder_path.trim().is_empty() {
+                studies_dir.join(&study.id)
+            } else {
+                PathBuf::from(study.folder_path.clone())
+            };
+            path.file_name()
+                .map(|name| name.to_string_lossy().to_string())
+                .unwrap_or_default()
+        });
+    if current_folder.as_deref() == Some(on_disk.as_str()) {
+        return Ok(());
+    }
+    let owner = studies.iter().find(|study| {
+        study.id.eq_ignore_ascii_case(&on_disk)
+            || Path::new(&study.folder_path)
+                .file_name()
+                .map(|name| name.to_string_lossy().eq_ignore_ascii_case(&on_disk))
+                .unwrap_or(false)
+    });
+    Err(match owner {
+        Some(study) => format!(
+            "Study folder already exists: '{}' conflicts with existing study '{}' ({}) on disk.",
+            candidate, study.id, study.title
+        ),
+        None => format!(
+            "Study folder already exists: '{}' conflicts with folder '{}' on disk.",
+            candidate, on_disk
+        ),
+    })
+}
+
+pub fn generate_study_code() -> String {
+    let raw = Uuid::new_v4().simple().to_string().to_uppercase();
+    format!("S-{}", &raw[..6])
+}
+
+fn projects_backup_path(app_root: &Path) -> PathBuf {
+    app_root.join("projects.json.bak")
+}
+
+fn parse_projects_store(raw: &str) -> Result<ProjectsStore, String> {
+    if raw.trim().is_empty() {
+        return Ok(ProjectsStore::default());
+    }
+    let mut store: ProjectsStore =
+        serde_json::from_str(raw).map_err(|err| format!("Invalid projects.json: {err}"))?;
+    for project in &mut store.projects {
+        if project.updated_at.is_empty() {
+            project.updated_at = project.created_at.clone();
+        }
+    }
+    Ok(store)
+}
+
+/// Reads projects.json, falling back to `projects.json.bak` when it does not parse (or was
+/// left empty by an interrupted write). The bad file is moved aside, the backup restored, and
+/// the returned store carries a `PROJECTS_RECOVERED_FROM_BACKUP` warning.
+pub fn read_projects_store(app_root: &Path) -> Result<ProjectsStore, String> {
+    let path = projects_path(app_root);
+    if !path.exists() {
+        return Ok(ProjectsStore::default());
+    }
+    let raw = fs::read_to_string(&path).map_err(|err| err.to_string())?;
+    let backup = projects_backup_path(app_root);
+    let parse_error = match parse_projects_store(&raw) {
+        Ok(_) if raw.trim().is_empty() && backup.exists() => "projects.json is empty".to_string(),
+        Ok(store) => return Ok(store),
+        Err(err) => err,
+    };
+    let Some(mut store) = fs::read_to_string(&backup)
+        .ok()
+        .and_then(|raw| parse_projects_store(&raw).ok())
+    else {
+        return Err(parse_error);
+    };
+
+    let stamp = Utc::now().format("projects.corrupt_%Y%m%dT%H%M%S%3f.json");
+    let corrupt = app_root.join(stamp.to_string());
+    fs::rename(&path, &corrupt)
+        .map_err(|err| format!("Unable to move corrupt projects.json aside: {err}"))?;
+    fs::copy(&backup, &path).map_err(|err| format!("Unable to restore projects backup: {err}"))?;
+
+    let message = "projects.json could not be read and was restored from the last backup; changes since that save are lost.";
+    activity::record_activity(app_root, "recover_projects_store", None, None, message);
+    store.warning = Some(WarningItem {
+        code: PROJECTS_RECOVERED_FROM_BACKUP.to_string(),
+        message: message.to_string(),
+        details: serde_json::json!({
+            "error": parse_error,
+            "corruptFile": corrupt.to_string_lossy(),
+        }),
+        severity: WarningSeverity::Warning,
+    });
+    Ok(store)
+}
+
+/// Writes through a synced temp file and a rename, so a crash leaves either the old or the
+/// new contents in place, never a truncated file.
+fn write_synced(path: &Path, payload: &[u8]) -> Result<(), String> {
+    let name = path
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .ok_or_else(|| format!("Invalid path {}", path.display()))?;
+    let staging = path.with_file_name(format!(".{name}.tmp"));
+    let mut file = fs::File::create(&staging)
+        .map_err(|err| format!("Unable to write {}: {err}", staging.display()))?;
+    file.write_all(payload)
+        .and_then(|_| file.sync_all())
+        .map_err(|err| format!("Unable to write {}: {err}", staging.display()))?;
+    drop(file);
+    fs::rename(&staging, path).map_err(|err| format!("Unable to write {}: {err}", path.display()))
+}
+
+/// Saves the store atomically after rotating the current file into `projects.json.bak`. A
+/// current file that no longer parses is not rotated, so a good backup is never replaced.
+pub fn write_projects_store(app_root: &Path, store: &ProjectsStore) -> Result<(), String> {
+    let path = projects_path(app_root);
+    let payload = serde_json::to_string_pretty(store).map_err(|err| err.to_string())?;
+    if let Ok(current) = fs::read_to_string(&path) {
+        if !current.trim().is_empty() && parse_projects_store(&current).is_ok() {
+            write_synced(&projects_backup_path(app_root), current.as_bytes())?;
+        }
+    }
+    write_synced(&path, payload.as_bytes())
+}
+
+pub fn migrate_sqlite_projects(app_root: &Path) -> Result<(), String> {
+    let db = sqlite::db_path(app_root);
+    if !db.exists() {
+        return Ok(());
+    }
+
+    let conn = sqlite::connection(app_root)?;
+    let table_exists: i64 = conn
+        .query_row(
+            "SELECT COUNT(1) FROM sqlite_master WHERE type='table' AND name='projects'",
+            [],
+            |row| row.get(0),
+        )
+        .map_err(|err| err.to_string())?;
+    if table_exists == 0 {
+        return Ok(());
+    }
+
+    let mut stmt = conn
+        .prepare("SELECT id, name, root_path, created_at FROM projects")
+        .map_err(|err| err.to_string())?;
+    let rows = stmt
+        .query_map([], |row| {
+            Ok(Project {
+                id: row.get(0)?,
+                name: row.get(1)?,
+                root_path: row.get(2)?,
+                created_at: row.get(3)?,
+                updated_at: row.get(3)?,
+                google_drive_url: None,
+                analysis_package_defaults: None,
+                studies: Vec::new(),
+            })
+        })
+        .map_err(|err| err.to_string())?;
+
+    let mut sqlite_projects = Vec::new();
+    for row in rows {
+        sqlite_projects.push(row.map_err(|err| err.to_string())?);
+    }
+    if sqlite_projects.is_empty() {
+        return Ok(());
+    }
+
+    let mut store = read_projects_store(app_root)?;
+    let mut added = 0;
+    for project in sqlite_projects {
+        if !store.projects.iter().any(|p| p.id == project.id) {
+            store.projects.push(project);
+            added += 1;
+        }
+    }
+    if added > 0 {
+        write_projects_store(app_root, &store)?;
+        println!("migration: imported {} project(s) from sqlite", added);
+    } else {
+        println!("migration: no new projects to import from sqlite");
+    }
+
+    Ok(())
+}
+
+pub fn ensure_folders(root: &Path, folders: &[&str]) -> Result<(), String> {
+    for folder in folders {
+        fs::create_dir_all(root.join(folder)).map_err(|err| err.to_string())?;
+    }
+    Ok(())
+}
+
+/// A path rewritten (or planned to be rewritten) when a project root moves.
+#[derive(Debug, Serialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct PathRewrite {
+    pub kind: String,
+    pub id: String,
+    pub from: String,
+    pub to: String,
+}
+
+/// Re-roots `path` from `old_root` onto `new_root`; `None` when it lies outside `old_root`.
+pub fn rebase_path(path: &Path, old_root: &Path, new_root: &Path) -> Option<PathBuf> {
+    path.strip_prefix(old_root)
+        .ok()
+        .map(|rest| new_root.join(rest))
+}
+
+pub fn resolve_study_root(project: &Project, study: &Study) -> PathBuf {
+    if study.folder_path.trim().is_empty() {
+        PathBuf::from(project.root_path.clone())
+            .join("studies")
+            .join(&study.id)
+    } else {
+        PathBuf::from(study.folder_path.clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_study(id: &str, folder_path: &Path) -> Study {
+        Study {
+            id: id.to_string(),
+            title: format!("Study {id}"),
+            created_at: now_string(),
+            folder_path: folder_path.to_string_lossy().to_string(),
+            files: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn study_folder_check_rejects_case_variant_folder_on_disk() {
+        let base = std::env::temp_dir().join(format!("study-case-test-{}", Uuid::new_v4()));
+        let studies_dir = base.join("studies");
+        fs::create_dir_all(studies_dir.join("s-abc123")).expect("failed to seed lowercase folder");
+
+        let err = ensure_study_folder_available(&[], &studies_dir, "S-ABC123", None)
+            .expect_err("case variant folder should conflict");
+        assert!(err.contains("s-abc123"));
+
+        let owner = test_study("s-abc123", &studies_dir.join("s-abc123"));
+        let err = ensure_study_folder_available(
+            std::slice::from_ref(&owner),
+            &studies_dir,
+            "S-ABC123",
+            None,
+        )
+        .expect_err("case variant study id should conflict");
+        assert!(err.contains("existing study 's-abc123'"));
+
+        assert!(ensure_study_folder_available(&[], &studies_dir, "S-XYZ789", None).is_ok());
+        let _ = fs::remove_dir_all(base);
+    }
+
+    #[test]
+    fn study_folder_check_allows_case_only_rename_of_same_study() {
+        let base = std::env::temp_dir().join(format!("study-case-rename-{}", Uuid::new_v4()));
+        let studies_dir = base.join("studies");
+        fs::create_dir_all(studies_dir.join("s-abc123")).expect("failed to seed study folder");
+        fs::create_dir_all(studies_dir.join("S-DEF456")).expect("failed to seed other folder");
+        let studies = vec![
+            test_study("s-abc123", &studies_dir.join("s-abc123")),
+            test_study("S-DEF456", &studies_dir.join("S-DEF456")),
+        ];
+
+        assert!(ensure_study_folder_available(
+            &studies,
+            &studies_dir,
+            "S-ABC123",
+            Some("s-abc123")
+        )
+        .is_ok());
+        let err =
+            ensure_study_folder_available(&studies, &studies_dir, "s-def456", Some("s-abc123"))
+                .expect_err("renaming onto another study should fail");
+        assert!(err.contains("S-DEF456"));
+        let _ = fs::remove_dir_all(base);
+    }
+
+    fn temp_root(prefix: &str) -> PathBuf {
+        let root = std::env::temp_dir().join(format!("{prefix}-{}", Uuid::new_v4()));
+        fs::create_dir_all(&root).expect("failed to create temp app root");
+        root
+    }
+
+    #[test]
+    fn read_store_treats_missing_and_blank_file_as_empty() {
+        let root = temp_root("store-empty");
+        assert!(read_projects_store(&root)
+            .expect("missing file should read")
+            .projects
+            .is_empty());
+
+        fs::write(projects_path(&root), "  \n").expect("failed to write blank store");
+        assert!(read_projects_store(&root)
+            .expect("blank file should read")
+            .projects
+            .is_empty());
+        let _ = fs::remove_dir_all(root);
+    }
+
+    #[test]
+    fn read_store_accepts_snake_case_and_backfills_updated_at() {
+        let root = temp_root("store-legacy");
+        fs::write(
+            projects_path(&root),
+            r#"{"projects":[{"id":"p1","name":"Legacy","root_path":"/tmp/legacy","created_at":"2024-01-01T00:00:00Z"}]}"#,
+        )
+        .expect("failed to seed store");
+
+        let store = read_projects_store(&root).expect("legacy store should read");
+        assert_eq!(store.projects.len(), 1);
+        assert_eq!(store.projects[0].root_path, "/tmp/legacy");
+        assert_eq!(store.projects[0].updated_at, "2024-01-01T00:00:00Z");
+
+        write_projects_store(&root, &store).expect("store should write");
+        let raw = fs::read_to_string(projects_path(&root)).expect("store should be readable");
+        assert!(raw.contains("\"rootPath\""));
+        assert!(raw.contains("\"updatedAt\""));
+        let reread = read_projects_store(&root).expect("written store should read");
+        assert_eq!(reread.projects[0].name, "Legacy");
+        let _ = fs::remove_dir_all(root);
+    }
+
+    #[test]
+    fn migrate_sqlite_projects_imports_each_project_once() {
+        let root = temp_root("store-migrate");
+        migrate_sqlite_projects(&root).expect("missing db should be a no-op");
+        assert!(!projects_path(&root).exists());
+
+        let conn = sqlite::connection(&root).expect("db should open");
+        sqlite::init_schema(&conn).expect("schema should apply");
+        conn.execute(
+            "INSERT INTO projects (id, name, root_path, created_at) VALUES ('p1', 'From DB', '/tmp/db', '2024-02-02T00:00:00Z')",
+            [],
+        )
+        .expect("failed to seed sqlite project");
+        drop(conn);
+
+        migrate_sqlite_projects(&root).expect("migration should succeed");
+        migrate_sqlite_projects(&root).expect("second migration should succeed");
+        let store = read_projects_store(&root).expect("store should read");
+        assert_eq!(store.projects.len(), 1);
+        assert_eq!(store.projects[0].id, "p1");
+        assert_eq!(store.projects[0].updated_at, "2024-02-02T00:00:00Z");
+        let _ = fs::remove_dir_all(root);
+    }
+
+    #[test]
+    fn corrupt_projects_json_is_recovered_from_backup() {
+        let app_root = std::env::temp_dir().join(format!("projects-store-{}", Uuid::new_v4()));
+        fs::create_dir_all(&app_root).expect("app root");
+        let project = |id: &str| Project {
+            id: id.to_string(),
+            name: format!("Project {id}"),
+            root_path: app_root.join(id).to_string_lossy().to_string(),
+            created_at: now_string(),
+            updated_at: now_string(),
+            google_drive_url: None,
+            analysis_package_defaults: None,
+            studies: Vec::new(),
+        };
+        let mut store = ProjectsStore::default();
+        store.projects.push(project("p1"));
+        write_projects_store(&app_root, &store).expect("first write");
+        assert!(!projects_backup_path(&app_root).exists());
+        store.projects.push(project("p2"));
+        write_projects_store(&app_root, &store).expect("second write");
+        assert!(read_projects_store(&app_root)
+            .expect("read")
+            .warning
+            .is_none());
+
+        let path = projects_path(&app_root);
+        fs::write(&path, "{\"projects\": [{\"id\": \"p1\", \"na").expect("corrupt");
+        let recovered = read_projects_store(&app_root).expect("recovered");
+        let ids: Vec<&str> = recovered.projects.iter().map(|p| p.id.as_str()).collect();
+        assert_eq!(ids, vec!["p1"]);
+        let warning = recovered.warning.expect("recovery warning");
+        assert_eq!(warning.code, PROJECTS_RECOVERED_FROM_BACKUP);
+        let corrupt = warning.details["corruptFile"]
+            .as_str()
+            .expect("corrupt file");
+        assert!(fs::read_to_string(corrupt).expect("kept").contains("\"na"));
+        // The restored primary reads cleanly from now on.
+        assert!(read_projects_store(&app_root)
+            .expect("read")
+            .warning
+            .is_none());
+
+        // An interrupted write that left the file empty is treated the same way.
+        fs::write(&path, "").expect("truncate");
+        assert!(read_projects_store(&app_root)
+            .expect("read")
+            .warning
+            .is_some());
+        fs::write(&path, "not json").expect("corrupt");
+        fs::remove_file(projects_backup_path(&app_root)).expect("drop backup");
+        let err = read_projects_store(&app_root).expect_err("no backup");
+        assert!(err.starts_with("Invalid projects.json"));
+        let _ = fs::remove_dir_all(app_root);
+    }
+}
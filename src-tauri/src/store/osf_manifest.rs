@@ -0,0 +1,203 @@
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use super::files::ExcludedPath;
+use super::{now_string, STUDY_FOLDERS};
+use crate::util::hash::sha256_file;
+
+pub const MANIFEST_FILE_NAME: &str = "MANIFEST.json";
+pub const README_FILE_NAME: &str = "README.md";
+/// Used instead of `README.md` when the study already ships its own.
+const FALLBACK_README_FILE_NAME: &str = "README_OSF_PACKAGE.md";
+
+const FOLDER_DESCRIPTIONS: &[(&str, &str)] = &[
+    (
+        "00_admin",
+        "Administrative records: approvals, consent forms, correspondence",
+    ),
+    ("01_design", "Study design documents and materials"),
+    (
+        "02_build",
+        "Survey and experiment build files, e.g. Qualtrics QSF exports",
+    ),
+    ("03_pilots", "Pilot runs and their data"),
+    ("04_prereg", "Preregistration documents"),
+    ("05_data", "Collected data"),
+    ("06_analysis", "Analysis scripts, templates and specs"),
+    ("07_outputs", "Generated tables, figures and reports"),
+    (
+        "08_osf_release",
+        "OSF packages (never copied into a package)",
+    ),
+];
+
+#[derive(Debug, Serialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct ManifestFile {
+    /// Package-relative path with `/` separators.
+    pub path: String,
+    pub bytes: u64,
+    pub sha256: String,
+    /// Modification time of the study file the entry was copied from.
+    pub source_modified: Option<String>,
+}
+
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct PackageManifestSummary {
+    pub file_count: usize,
+    pub total_bytes: u64,
+    pub generated_at: String,
+    pub include_pilots: bool,
+    pub app_version: String,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct PackageManifest<'a> {
+    kind: &'a str,
+    study_id: &'a str,
+    #[serde(flatten)]
+    summary: &'a PackageManifestSummary,
+    files: &'a [ManifestFile],
+}
+
+pub(super) fn collect_files(dir: &Path, out: &mut Vec<PathBuf>) -> Result<(), String> {
+    for entry in fs::read_dir(dir).map_err(|err| err.to_string())? {
+        let entry = entry.map_err(|err| err.to_string())?;
+        let path = entry.path();
+        let file_type = entry.file_type().map_err(|err| err.to_string())?;
+        if file_type.is_dir() {
+            collect_files(&path, out)?;
+        } else if file_type.is_file() {
+            out.push(path);
+        }
+    }
+    Ok(())
+}
+
+fn manifest_files(package_root: &Path, study_root: &Path) -> Result<Vec<ManifestFile>, String> {
+    let mut paths = Vec::new();
+    collect_files(package_root, &mut paths)?;
+    let mut files = paths
+        .iter()
+        .map(|path| {
+            let rel = path.strip_prefix(package_root).unwrap_or(path);
+            let bytes = fs::metadata(path).map_err(|err| err.to_string())?.len();
+            let source_modified = fs::metadata(study_root.join(rel))
+                .and_then(|metadata| metadata.modified())
+                .ok()
+                .map(|modified| DateTime::<Utc>::from(modified).to_rfc3339());
+            Ok(ManifestFile {
+                path: rel.to_string_lossy().replace('\\', "/"),
+                bytes,
+                sha256: sha256_file(path)?,
+                source_modified,
+            })
+        })
+        .collect::<Result<Vec<_>, String>>()?;
+    files.sort_by(|a, b| a.path.cmp(&b.path));
+    Ok(files)
+}
+
+fn package_readme(
+    kind: &str,
+    study_id: &str,
+    summary: &PackageManifestSummary,
+    files: &[ManifestFile],
+    excluded: &[ExcludedPath],
+) -> String {
+    let mut out = format!(
+        "# {study_id}: OSF package ({kind})\n\nGenerated {} by Research Workflow {}: {} files, {} bytes.\n\n",
+        summary.generated_at, summary.app_version, summary.file_count, summary.total_bytes
+    );
+    out.push_str(&format!(
+        "{MANIFEST_FILE_NAME} lists every file with its size and SHA-256 checksum. Compare them after uploading to find files that were changed in transit.\n\n"
+    ));
+    out.push_str("## Folder layout\n\n");
+    out.push_str("| Folder | Contents | Files |\n");
+    out.push_str("| --- | --- | --- |\n");
+    let in_folder = |folder: &str| {
+        files
+            .iter()
+            .filter(|file| file.path.split('/').next() == Some(folder))
+            .count()
+    };
+    for folder in STUDY_FOLDERS {
+        let description = FOLDER_DESCRIPTIONS
+            .iter()
+            .find(|(name, _)| name == folder)
+            .map(|(_, description)| *description)
+            .unwrap_or("");
+        out.push_str(&format!(
+            "| `{folder}/` | {description} | {} |\n",
+            in_folder(folder)
+        ));
+    }
+    let other = files
+        .iter()
+        .filter(|file| {
+            !STUDY_FOLDERS
+                .iter()
+                .any(|folder| file.path.split('/').next() == Some(*folder))
+        })
+        .count();
+    if other > 0 {
+        out.push_str(&format!(
+            "| Other | Files outside the standard folders | {other} |\n"
+        ));
+    }
+    out.push_str("\n## Excluded\n\n");
+    if excluded.is_empty() {
+        out.push_str("Nothing was left out by the release rules.\n");
+    }
+    for item in excluded {
+        out.push_str(&format!("- `{}`: {}\n", item.path, item.reason));
+    }
+    out
+}
+
+/// Hashes every file copied into `package_root` and writes `MANIFEST.json` and a README
+/// describing the layout and the `excluded` paths. Both generated files stay out of the
+/// manifest, which therefore lists exactly the copied files.
+pub fn write_package_manifest(
+    package_root: &Path,
+    study_root: &Path,
+    study_id: &str,
+    kind: &str,
+    include_pilots: bool,
+    excluded: &[ExcludedPath],
+) -> Result<PackageManifestSummary, String> {
+    let files = manifest_files(package_root, study_root)?;
+    let summary = PackageManifestSummary {
+        file_count: files.len(),
+        total_bytes: files.iter().map(|file| file.bytes).sum(),
+        generated_at: now_string(),
+        include_pilots,
+        app_version: env!("CARGO_PKG_VERSION").to_string(),
+    };
+    let readme_name = if package_root.join(README_FILE_NAME).exists() {
+        FALLBACK_README_FILE_NAME
+    } else {
+        README_FILE_NAME
+    };
+    let readme_path = package_root.join(readme_name);
+    fs::write(
+        &readme_path,
+        package_readme(kind, study_id, &summary, &files, excluded),
+    )
+    .map_err(|err| format!("Unable to write {}: {err}", readme_path.display()))?;
+    let manifest = PackageManifest {
+        kind,
+        study_id,
+        summary: &summary,
+        files: &files,
+    };
+    let manifest_path = package_root.join(MANIFEST_FILE_NAME);
+    let payload = serde_json::to_string_pretty(&manifest).map_err(|err| err.to_string())?;
+    fs::write(&manifest_path, payload)
+        .map_err(|err| format!("Unable to write {}: {err}", manifest_path.display()))?;
+    Ok(summary)
+}
@@ -0,0 +1,1452 @@
+use pathdiff::diff_paths;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use uuid::Uuid;
+
+use super::activity::record_activity;
+use super::files::{
+    copy_dir_filtered, kind_from_ext, project_relative, CopyFilter, SkippedLink, RECONCILED_FOLDERS,
+};
+use super::osf_manifest::collect_files;
+use super::release_rules::ReleaseRules;
+use super::sqlite::{rebase_study_artifacts, relocate_project_rows};
+use super::structure::{ensure_study_scaffold, resolve_study_folders};
+use super::trash::{
+    check_deletable_root, home_dir, os_trash, record_tombstone, tombstone_for, trash_project_root,
+    TrashLocation,
+};
+use super::{
+    ensure_folders, ensure_study_folder_available, find_entry_case_insensitive,
+    generate_study_code, is_valid_study_folder, migrate_sqlite_projects, now_string,
+    read_projects_store, rebase_path, resolve_study_root, write_projects_store, AnalysisPackages,
+    FileRef, PathRewrite, Project, Study, PROJECT_FOLDERS,
+};
+
+/// Study folder never carried into a duplicate: release packages belong to their source study.
+const DUPLICATE_EXCLUDED_FOLDER: &str = "08_osf_release";
+
+pub fn list_projects(app_root: &Path) -> Result<Vec<Project>, String> {
+    migrate_sqlite_projects(app_root)?;
+    let mut store = read_projects_store(app_root)?;
+    store
+        .projects
+        .sort_by(|a, b| b.created_at.cmp(&a.created_at));
+    Ok(store.projects)
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CreateProjectArgs {
+    name: String,
+    root_dir: String,
+    #[serde(default)]
+    use_existing_root: bool,
+    google_drive_url: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UpdateProjectRootArgs {
+    project_id: String,
+    root_dir: String,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DeleteProjectArgs {
+    project_id: String,
+    #[serde(default)]
+    delete_on_disk: bool,
+}
+
+pub fn create_project(app_root: &Path, args: CreateProjectArgs) -> Result<Project, String> {
+    let id = Uuid::new_v4().to_string();
+    let trimmed_name = args.name.trim();
+    if trimmed_name.is_empty() {
+        return Err("Project name is required.".to_string());
+    }
+    let root_dir_path = PathBuf::from(args.root_dir.trim());
+    if !root_dir_path.exists() || !root_dir_path.is_dir() {
+        return Err("Project root location must be an existing folder.".to_string());
+    }
+
+    let root = if args.use_existing_root {
+        root_dir_path
+    } else {
+        let root = root_dir_path.join(trimmed_name);
+        if root.exists() {
+            return Err("Project folder already exists.".to_string());
+        }
+        root
+    };
+    ensure_folders(&root, PROJECT_FOLDERS)?;
+
+    let project = Project {
+        id: id.clone(),
+        name: trimmed_name.to_string(),
+        root_path: root.to_string_lossy().to_string(),
+        created_at: now_string(),
+        updated_at: now_string(),
+        google_drive_url: drive_url(args.google_drive_url),
+        analysis_package_defaults: None,
+        studies: Vec::new(),
+    };
+
+    let mut store = read_projects_store(app_root)?;
+    store.projects.push(project.clone());
+    write_projects_store(app_root, &store)?;
+
+    Ok(project)
+}
+
+fn drive_url(value: Option<String>) -> Option<String> {
+    value
+        .map(|value| value.trim().to_string())
+        .filter(|value| !value.is_empty())
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AdoptExistingProjectArgs {
+    name: String,
+    root_dir: String,
+    google_drive_url: Option<String>,
+    /// Report what would be adopted without creating folders or touching the store.
+    #[serde(default)]
+    dry_run: bool,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AdoptedStudy {
+    pub study_id: String,
+    pub title: String,
+    pub files: usize,
+    /// Study folders that were (or, in a dry run, would be) created because they were missing.
+    pub created_folders: Vec<String>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SkippedStudyFolder {
+    pub folder: String,
+    pub reason: String,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AdoptionReport {
+    pub dry_run: bool,
+    pub studies: Vec<AdoptedStudy>,
+    /// Entries under `studies/` that are not study folders.
+    pub skipped: Vec<SkippedStudyFolder>,
+    /// The project as it is (adopted) or would be stored (dry run).
+    pub project: Project,
+}
+
+/// Title from the first Markdown heading of `00_admin/README.md`, if there is one.
+fn readme_title(study_root: &Path) -> Option<String> {
+    let admin = study_root.join("00_admin");
+    let readme = find_entry_case_insensitive(&admin, "README.md").ok()??;
+    let text = fs::read_to_string(admin.join(readme)).ok()?;
+    text.lines()
+        .map(str::trim)
+        .find(|line| line.starts_with('#'))
+        .map(|line| line.trim_start_matches('#').trim().to_string())
+        .filter(|title| !title.is_empty())
+}
+
+/// File refs for the files already sitting in a study's import folders; hidden files are left out.
+fn existing_file_refs(study_root: &Path, project_root: &Path) -> Result<Vec<FileRef>, String> {
+    let mut refs = Vec::new();
+    for folder in RECONCILED_FOLDERS {
+        let dir = study_root.join(folder);
+        if !dir.is_dir() {
+            continue;
+        }
+        let mut found = Vec::new();
+        collect_files(&dir, &mut found)?;
+        found.sort();
+        for path in found {
+            let name = path
+                .file_name()
+                .map(|name| name.to_string_lossy().to_string())
+                .unwrap_or_default();
+            if name.starts_with('.') {
+                continue;
+            }
+            refs.push(FileRef {
+                path: project_relative(&path, project_root),
+                name,
+                kind: kind_from_ext(path.extension()),
+                sha256: None,
+                folder: (*folder != "sources").then(|| folder.to_string()),
+            });
+        }
+    }
+    Ok(refs)
+}
+
+/// Registers a folder that already follows the `studies/S-XXXXXX` layout as a project. Every
+/// study folder becomes a study with its existing files registered, and missing study
+/// subfolders are created. Folders with other names are reported and left alone.
+pub fn adopt_existing_project(
+    app_root: &Path,
+    args: AdoptExistingProjectArgs,
+) -> Result<AdoptionReport, String> {
+    let name = args.name.trim();
+    if name.is_empty() {
+        return Err("Project name is required.".to_string());
+    }
+    let root = PathBuf::from(args.root_dir.trim());
+    if !root.is_dir() {
+        return Err("Project root location must be an existing folder.".to_string());
+    }
+    let mut store = read_projects_store(app_root)?;
+    if let Some(existing) = store
+        .projects
+        .iter()
+        .find(|project| Path::new(&project.root_path) == root)
+    {
+        return Err(format!(
+            "This folder is already registered as project '{}'.",
+            existing.name
+        ));
+    }
+
+    let studies_dir = root.join("studies");
+    let mut entries = Vec::new();
+    if studies_dir.is_dir() {
+        for entry in fs::read_dir(&studies_dir).map_err(|err| err.to_string())? {
+            let entry = entry.map_err(|err| err.to_string())?;
+            if entry.file_type().map_err(|err| err.to_string())?.is_dir() {
+                entries.push(entry.file_name().to_string_lossy().to_string());
+            }
+        }
+    }
+    entries.sort();
+
+    let scaffold = resolve_study_folders(&root)?;
+    let mut studies: Vec<Study> = Vec::new();
+    let mut adopted = Vec::new();
+    let mut skipped = Vec::new();
+    for folder in entries {
+        if folder.starts_with('.') {
+            continue;
+        }
+        if !is_valid_study_folder(&folder) {
+            skipped.push(SkippedStudyFolder {
+                folder,
+                reason: "Folder name does not match S-XXXXXX.".to_string(),
+            });
+            continue;
+        }
+        if let Some(existing) = studies
+            .iter()
+            .find(|study| study.id.eq_ignore_ascii_case(&folder))
+        {
+            skipped.push(SkippedStudyFolder {
+                reason: format!("Study code conflicts with folder '{}'.", existing.id),
+                folder,
+            });
+            continue;
+        }
+        let study_root = studies_dir.join(&folder);
+        let files = existing_file_refs(&study_root, &root)?;
+        let title = readme_title(&study_root).unwrap_or_else(|| folder.clone());
+        adopted.push(AdoptedStudy {
+            study_id: folder.clone(),
+            title: title.clone(),
+            files: files.len(),
+            created_folders: scaffold
+                .iter()
+                .filter(|sub| !study_root.join(sub).is_dir())
+                .cloned()
+                .collect(),
+        });
+        studies.push(Study {
+            id: folder,
+            title,
+            created_at: now_string(),
+            folder_path: study_root.to_string_lossy().to_string(),
+            files,
+        });
+    }
+
+    let project = Project {
+        id: Uuid::new_v4().to_string(),
+        name: name.to_string(),
+        root_path: root.to_string_lossy().to_string(),
+        created_at: now_string(),
+        updated_at: now_string(),
+        google_drive_url: drive_url(args.google_drive_url),
+        analysis_package_defaults: None,
+        studies,
+    };
+
+    if !args.dry_run {
+        ensure_folders(&root, PROJECT_FOLDERS)?;
+        for study in &project.studies {
+            ensure_study_scaffold(&root, Path::new(&study.folder_path))?;
+        }
+        store.projects.push(project.clone());
+        write_projects_store(app_root, &store)?;
+        record_activity(
+            app_root,
+            "adopt_existing_project",
+            Some(&project.id),
+            None,
+            &format!(
+                "Adopted {} with {} study folder(s)",
+                project.name,
+                project.studies.len()
+            ),
+        );
+    }
+
+    Ok(AdoptionReport {
+        dry_run: args.dry_run,
+        studies: adopted,
+        skipped,
+        project,
+    })
+}
+
+pub fn update_project_root(
+    app_root: &Path,
+    args: UpdateProjectRootArgs,
+) -> Result<Project, String> {
+    let report = relocate_project(
+        app_root,
+        RelocateProjectArgs {
+            project_id: args.project_id,
+            root_dir: args.root_dir,
+            dry_run: false,
+        },
+    )?;
+    Ok(report.project)
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RelocateProjectArgs {
+    project_id: String,
+    root_dir: String,
+    #[serde(default)]
+    dry_run: bool,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RelocationReport {
+    pub from_root: String,
+    pub to_root: String,
+    pub dry_run: bool,
+    pub rewrites: Vec<PathRewrite>,
+    /// Studies whose folder does not exist under the new root.
+    pub missing_studies: Vec<String>,
+    /// `study_id: path` for file references that do not resolve under the new root.
+    pub missing_files: Vec<String>,
+    /// The project as it is (dry run) or will be stored after the move.
+    pub project: Project,
+}
+
+/// Points a project at a new root folder and rewrites every absolute study path that lived
+/// under the old root, in projects.json and sqlite. Studies and files that cannot be found
+/// under the new root are reported rather than failing the move.
+pub fn relocate_project(
+    app_root: &Path,
+    args: RelocateProjectArgs,
+) -> Result<RelocationReport, String> {
+    let new_root = PathBuf::from(args.root_dir.trim());
+    if !new_root.exists() || !new_root.is_dir() {
+        return Err("Project root location must be an existing folder.".to_string());
+    }
+
+    let mut store = read_projects_store(app_root)?;
+    let project = store
+        .projects
+        .iter_mut()
+        .find(|project| project.id == args.project_id)
+        .ok_or_else(|| "Project not found.".to_string())?;
+    let old_root = PathBuf::from(project.root_path.clone());
+
+    let mut relocated = project.clone();
+    relocated.root_path = new_root.to_string_lossy().to_string();
+    relocated.updated_at = now_string();
+
+    let mut rewrites = Vec::new();
+    let mut missing_studies = Vec::new();
+    let mut missing_files = Vec::new();
+    for study in relocated.studies.iter_mut() {
+        let study_root = if study.folder_path.trim().is_empty() {
+            new_root.join("studies").join(&study.id)
+        } else {
+            let current = PathBuf::from(study.folder_path.clone());
+            let moved = rebase_path(&current, &old_root, &new_root).or_else(|| {
+                // Folder outside the old root: accept a same-named study folder under the new one.
+                current
+                    .file_name()
+                    .map(|name| new_root.join("studies").join(name))
+                    .filter(|candidate| candidate.is_dir())
+            });
+            match moved {
+                Some(moved) if moved != current => {
+                    rewrites.push(PathRewrite {
+                        kind: "study".to_string(),
+                        id: study.id.clone(),
+                        from: study.folder_path.clone(),
+                        to: moved.to_string_lossy().to_string(),
+                    });
+                    study.folder_path = moved.to_string_lossy().to_string();
+                    moved
+                }
+                _ => current,
+            }
+        };
+        if !study_root.is_dir() {
+            missing_studies.push(study.id.clone());
+        }
+        for file in &study.files {
+            if !new_root.join(&file.path).exists() {
+                missing_files.push(format!("{}: {}", study.id, file.path));
+            }
+        }
+    }
+
+    rewrites.extend(relocate_project_rows(
+        app_root,
+        &relocated.id,
+        &old_root,
+        &new_root,
+        args.dry_run,
+    )?);
+
+    if !args.dry_run {
+        ensure_folders(&new_root, PROJECT_FOLDERS)?;
+        *project = relocated.clone();
+        write_projects_store(app_root, &store)?;
+    }
+
+    Ok(RelocationReport {
+        from_root: old_root.to_string_lossy().to_string(),
+        to_root: new_root.to_string_lossy().to_string(),
+        dry_run: args.dry_run,
+        rewrites,
+        missing_studies,
+        missing_files,
+        project: relocated,
+    })
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UpdateProjectAnalysisDefaultsArgs {
+    project_id: String,
+    packages: AnalysisPackages,
+}
+
+pub fn update_project_analysis_defaults(
+    app_root: &Path,
+    args: UpdateProjectAnalysisDefaultsArgs,
+) -> Result<Project, String> {
+    let mut store = read_projects_store(app_root)?;
+    let project = store
+        .projects
+        .iter_mut()
+        .find(|project| project.id == args.project_id)
+        .ok_or_else(|| "Project not found.".to_string())?;
+
+    project.analysis_package_defaults = Some(args.packages);
+    project.updated_at = now_string();
+
+    let updated = project.clone();
+    write_projects_store(app_root, &store)?;
+    Ok(updated)
+}
+
+pub fn delete_project(app_root: &Path, args: DeleteProjectArgs) -> Result<(), String> {
+    delete_project_with(app_root, args, home_dir().as_deref(), os_trash)
+}
+
+/// Removes a project from the list and, with `delete_on_disk`, moves its folder to the trash.
+/// A tombstone is kept either way so `restore_deleted_project` can undo the deletion.
+pub fn delete_project_with(
+    app_root: &Path,
+    args: DeleteProjectArgs,
+    home: Option<&Path>,
+    os_trash: impl FnOnce(&Path) -> Result<(), String>,
+) -> Result<(), String> {
+    let mut store = read_projects_store(app_root)?;
+    let index = store
+        .projects
+        .iter()
+        .position(|project| project.id == args.project_id)
+        .ok_or_else(|| "Project not found.".to_string())?;
+    let project = store.projects.remove(index);
+
+    let root = PathBuf::from(&project.root_path);
+    let (location, trashed_path) = if args.delete_on_disk && root.is_dir() {
+        check_deletable_root(&root, home)?;
+        trash_project_root(&root, os_trash)?
+    } else {
+        (TrashLocation::Kept, None)
+    };
+    record_tombstone(app_root, tombstone_for(project, location, trashed_path))?;
+    write_projects_store(app_root, &store)?;
+    Ok(())
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AddStudyArgs {
+    project_id: String,
+    folder_name: Option<String>,
+    title: Option<String>,
+}
+
+pub fn add_study(app_root: &Path, args: AddStudyArgs) -> Result<Project, String> {
+    println!(
+        "add_study called with project_id={}, folder_name={:?}, title={:?}",
+        args.project_id, args.folder_name, args.title
+    );
+    let mut store = read_projects_store(app_root)?;
+    let project = store
+        .projects
+        .iter_mut()
+        .find(|project| project.id == args.project_id)
+        .ok_or_else(|| "Project not found.".to_string())?;
+    println!(
+        "add_study resolved project root_path={} existing studies={}",
+        project.root_path,
+        project.studies.len()
+    );
+
+    let studies_dir = PathBuf::from(project.root_path.clone()).join("studies");
+    let mut trimmed_folder = args.folder_name.unwrap_or_default().trim().to_uppercase();
+    if trimmed_folder.is_empty() {
+        for _ in 0..20 {
+            let candidate = generate_study_code();
+            if ensure_study_folder_available(&project.studies, &studies_dir, &candidate, None)
+                .is_ok()
+            {
+                trimmed_folder = candidate;
+                break;
+            }
+        }
+        if trimmed_folder.is_empty() {
+            return Err("Unable to generate a unique study code.".to_string());
+        }
+    }
+    if !is_valid_study_folder(&trimmed_folder) {
+        return Err("Study folder name must match S-XXXXXX (letters/numbers).".to_string());
+    }
+    if trimmed_folder.contains('/')
+        || trimmed_folder.contains('\\')
+        || trimmed_folder.contains("..")
+    {
+        return Err("Study folder name must be a single folder name.".to_string());
+    }
+    ensure_study_folder_available(&project.studies, &studies_dir, &trimmed_folder, None)?;
+
+    let trimmed_title = args.title.unwrap_or_else(|| "Untitled Study".to_string());
+    let study_root = studies_dir.join(&trimmed_folder);
+    if study_root.exists() {
+        return Err("Study folder already exists.".to_string());
+    }
+    ensure_study_scaffold(Path::new(&project.root_path), &study_root)?;
+
+    let new_study = Study {
+        id: trimmed_folder.to_string(),
+        title: if trimmed_title.trim().is_empty() {
+            "Untitled Study".to_string()
+        } else {
+            trimmed_title
+        },
+        created_at: now_string(),
+        folder_path: study_root.to_string_lossy().to_string(),
+        files: Vec::new(),
+    };
+
+    let summary = format!("Created study {} ({})", new_study.id, new_study.title);
+    let study_id = new_study.id.clone();
+    project.studies.push(new_study);
+    project.updated_at = now_string();
+    let updated = project.clone();
+    write_projects_store(app_root, &store)?;
+    record_activity(
+        app_root,
+        "add_study",
+        Some(&updated.id),
+        Some(&study_id),
+        &summary,
+    );
+    Ok(updated)
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DuplicateStudyArgs {
+    project_id: String,
+    study_id: String,
+    title: Option<String>,
+    /// Study folders whose content is copied, e.g. `["01_design", "02_build"]`; every other
+    /// folder is created empty.
+    #[serde(default)]
+    copy_folders: Vec<String>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DuplicateStudyReport {
+    pub study_id: String,
+    pub copied_folders: Vec<String>,
+    pub files_copied: u64,
+    pub file_refs_cloned: usize,
+    pub skipped_links: Vec<SkippedLink>,
+    /// Copied R/Rmd files (relative to the new study) that still mention the source study ID;
+    /// they are left as they are for the user to review.
+    pub stale_study_references: Vec<String>,
+    pub project: Project,
+}
+
+/// Relative paths of `.R`/`.Rmd` files under `dir` whose text contains `needle`.
+fn scripts_mentioning(dir: &Path, base: &Path, needle: &str, out: &mut Vec<String>) {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return;
+    };
+    for entry in entries.flatten() {
+        let path = entry.path();
+        let Ok(file_type) = entry.file_type() else {
+            continue;
+        };
+        if file_type.is_dir() {
+            scripts_mentioning(&path, base, needle, out);
+            continue;
+        }
+        let is_script = path
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .is_some_and(|ext| ext.eq_ignore_ascii_case("r") || ext.eq_ignore_ascii_case("rmd"));
+        if is_script && fs::read_to_string(&path).is_ok_and(|text| text.contains(needle)) {
+            let rel = path.strip_prefix(base).unwrap_or(&path);
+            out.push(rel.to_string_lossy().replace('\\', "/"));
+        }
+    }
+}
+
+/// Creates a new study with a fresh code, copying the content of the selected study folders
+/// from `study_id` and cloning the file references that were copied along.
+pub fn duplicate_study(
+    app_root: &Path,
+    args: DuplicateStudyArgs,
+) -> Result<DuplicateStudyReport, String> {
+    let mut store = read_projects_store(app_root)?;
+    let project = store
+        .projects
+        .iter_mut()
+        .find(|project| project.id == args.project_id)
+        .ok_or_else(|| "Project not found.".to_string())?;
+    let project_root = PathBuf::from(&project.root_path);
+    let scaffold = resolve_study_folders(&project_root)?;
+
+    let mut copy_folders: Vec<String> = Vec::new();
+    for folder in &args.copy_folders {
+        let folder = folder.trim();
+        if folder == DUPLICATE_EXCLUDED_FOLDER {
+            return Err(format!(
+                "{DUPLICATE_EXCLUDED_FOLDER} cannot be copied into a duplicate study."
+            ));
+        }
+        if !scaffold.iter().any(|f| f == folder) {
+            return Err(format!("Unknown study folder: {folder}"));
+        }
+        if !copy_folders.iter().any(|f| f == folder) {
+            copy_folders.push(folder.to_string());
+        }
+    }
+    let source = project
+        .studies
+        .iter()
+        .find(|study| study.id == args.study_id)
+        .cloned()
+        .ok_or_else(|| "Study not found.".to_string())?;
+    let source_root = resolve_study_root(project, &source);
+    let studies_dir = PathBuf::from(project.root_path.clone()).join("studies");
+
+    let mut new_id = String::new();
+    for _ in 0..20 {
+        let candidate = generate_study_code();
+        if ensure_study_folder_available(&project.studies, &studies_dir, &candidate, None).is_ok() {
+            new_id = candidate;
+            break;
+        }
+    }
+    if new_id.is_empty() {
+        return Err("Unable to generate a unique study code.".to_string());
+    }
+    let study_root = studies_dir.join(&new_id);
+    if study_root.exists() {
+        return Err("Study folder already exists.".to_string());
+    }
+    ensure_study_scaffold(&project_root, &study_root)?;
+
+    let rules = ReleaseRules::default();
+    let filter = CopyFilter {
+        excluded: &[],
+        include_pilots: true,
+        condensed: false,
+        rules: &rules,
+        follow_links_within: Some(&source_root),
+    };
+    let mut files_copied = 0u64;
+    let mut skipped_links = Vec::new();
+    for folder in &copy_folders {
+        let src = source_root.join(folder);
+        if !src.is_dir() {
+            continue;
+        }
+        let summary = copy_dir_filtered(&src, &study_root.join(folder), &filter)?;
+        files_copied += summary.files;
+        skipped_links.extend(summary.skipped_links);
+    }
+
+    let old_prefix = format!("studies/{}/", source.id);
+    let files = source
+        .files
+        .iter()
+        .filter_map(|file| {
+            let rest = file.path.strip_prefix(&old_prefix)?;
+            let folder = rest.split('/').next().unwrap_or_default();
+            if !copy_folders.iter().any(|f| f == folder) || !study_root.join(rest).is_file() {
+                return None;
+            }
+            Some(FileRef {
+                path: format!("studies/{new_id}/{rest}"),
+                ..file.clone()
+            })
+        })
+        .collect::<Vec<FileRef>>();
+    let file_refs_cloned = files.len();
+
+    let mut stale_study_references = Vec::new();
+    for folder in &copy_folders {
+        scripts_mentioning(
+            &study_root.join(folder),
+            &study_root,
+            &source.id,
+            &mut stale_study_references,
+        );
+    }
+    stale_study_references.sort();
+
+    let title = args
+        .title
+        .map(|value| value.trim().to_string())
+        .filter(|value| !value.is_empty())
+        .unwrap_or_else(|| format!("{} (copy)", source.title));
+    let summary = format!(
+        "Duplicated study {} as {} ({}), copied {}",
+        source.id,
+        new_id,
+        title,
+        if copy_folders.is_empty() {
+            "no folders".to_string()
+        } else {
+            copy_folders.join(", ")
+        }
+    );
+    project.studies.push(Study {
+        id: new_id.clone(),
+        title,
+        created_at: now_string(),
+        folder_path: study_root.to_string_lossy().to_string(),
+        files,
+    });
+    project.updated_at = now_string();
+    let updated = project.clone();
+    write_projects_store(app_root, &store)?;
+    record_activity(
+        app_root,
+        "duplicate_study",
+        Some(&updated.id),
+        Some(&new_id),
+        &summary,
+    );
+    Ok(DuplicateStudyReport {
+        study_id: new_id,
+        copied_folders: copy_folders,
+        files_copied,
+        file_refs_cloned,
+        skipped_links,
+        stale_study_references,
+        project: updated,
+    })
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RenameStudyJsonArgs {
+    project_id: String,
+    study_id: String,
+    title: String,
+}
+
+pub fn rename_study_json(app_root: &Path, args: RenameStudyJsonArgs) -> Result<Project, String> {
+    let mut store = read_projects_store(app_root)?;
+    let project = store
+        .projects
+        .iter_mut()
+        .find(|project| project.id == args.project_id)
+        .ok_or_else(|| "Project not found.".to_string())?;
+
+    let study = project
+        .studies
+        .iter_mut()
+        .find(|study| study.id == args.study_id)
+        .ok_or_else(|| "Study not found.".to_string())?;
+
+    let trimmed = args.title.trim();
+    if trimmed.is_empty() {
+        return Err("Study title is required.".to_string());
+    }
+
+    study.title = trimmed.to_string();
+    project.updated_at = now_string();
+    let updated = project.clone();
+    write_projects_store(app_root, &store)?;
+    Ok(updated)
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RenameStudyFolderArgs {
+    project_id: String,
+    study_id: String,
+    folder_name: String,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StudyFolderRenameReport {
+    pub project: Project,
+    pub file_refs_rewritten: usize,
+    pub artifacts_rewritten: usize,
+    /// Analysis scripts (relative to the study) whose `here::here(...)` paths now use the new code.
+    pub rewritten_scripts: Vec<String>,
+    /// Analysis scripts that still mention the old study code elsewhere; left for the user.
+    pub stale_study_references: Vec<String>,
+}
+
+/// Replaces `old_code` with `new_code` inside the `here::here(...)` calls of the `.R`/`.Rmd`
+/// files under `dir`, collecting the files that changed relative to `base`.
+fn rewrite_here_paths(
+    dir: &Path,
+    base: &Path,
+    old_code: &str,
+    new_code: &str,
+    out: &mut Vec<String>,
+) {
+    let here = Regex::new(r"here::here\([^)]*\)").expect("regex");
+    let Ok(entries) = fs::read_dir(dir) else {
+        return;
+    };
+    for entry in entries.flatten() {
+        let path = entry.path();
+        let Ok(file_type) = entry.file_type() else {
+            continue;
+        };
+        if file_type.is_dir() {
+            rewrite_here_paths(&path, base, old_code, new_code, out);
+            continue;
+        }
+        let is_script = path
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .is_some_and(|ext| ext.eq_ignore_ascii_case("r") || ext.eq_ignore_ascii_case("rmd"));
+        let Some(text) = is_script.then(|| fs::read_to_string(&path).ok()).flatten() else {
+            continue;
+        };
+        let rewritten = here.replace_all(&text, |caps: &regex::Captures| {
+            caps[0].replace(old_code, new_code)
+        });
+        if rewritten != text && fs::write(&path, rewritten.as_bytes()).is_ok() {
+            let rel = path.strip_prefix(base).unwrap_or(&path);
+            out.push(rel.to_string_lossy().replace('\\', "/"));
+        }
+    }
+}
+
+/// Renames a study folder and its code, then follows the move: file references and sqlite
+/// artifact paths are rebased, and `here::here(...)` paths in the analysis scripts rewritten.
+pub fn rename_study_folder_json(
+    app_root: &Path,
+    args: RenameStudyFolderArgs,
+) -> Result<StudyFolderRenameReport, String> {
+    let mut store = read_projects_store(app_root)?;
+    let project = store
+        .projects
+        .iter_mut()
+        .find(|project| project.id == args.project_id)
+        .ok_or_else(|| "Project not found.".to_string())?;
+
+    let trimmed_folder = args.folder_name.trim();
+    if trimmed_folder.is_empty() {
+        return Err("Study folder name is required.".to_string());
+    }
+    if !is_valid_study_folder(trimmed_folder) {
+        return Err("Study folder name must match S-XXXXXX (letters/numbers).".to_string());
+    }
+    if trimmed_folder.contains('/')
+        || trimmed_folder.contains('\\')
+        || trimmed_folder.contains("..")
+    {
+        return Err("Study folder name must be a single folder name.".to_string());
+    }
+    let project_root = PathBuf::from(project.root_path.clone());
+    let base = project_root.join("studies");
+    ensure_study_folder_available(
+        &project.studies,
+        &base,
+        trimmed_folder,
+        Some(&args.study_id),
+    )?;
+
+    let study = project
+        .studies
+        .iter_mut()
+        .find(|study| study.id == args.study_id)
+        .ok_or_else(|| "Study not found.".to_string())?;
+
+    let old_root = if study.folder_path.trim().is_empty() {
+        base.join(&study.id)
+    } else {
+        PathBuf::from(study.folder_path.clone())
+    };
+    let new_root = base.join(trimmed_folder);
+
+    if old_root != new_root {
+        let case_only_rename = old_root
+            .file_name()
+            .map(|name| name.to_string_lossy().eq_ignore_ascii_case(trimmed_folder))
+            .unwrap_or(false);
+        if new_root.exists() && !case_only_rename {
+            return Err("Study folder already exists.".to_string());
+        }
+        if !old_root.exists() {
+            return Err("Study folder does not exist.".to_string());
+        }
+        fs::rename(&old_root, &new_root).map_err(|err| err.to_string())?;
+    }
+
+    let old_code = study.id.clone();
+    let mut file_refs_rewritten = 0;
+    if let (Some(old_relative), Some(new_relative)) = (
+        diff_paths(&old_root, &project_root),
+        diff_paths(&new_root, &project_root),
+    ) {
+        for file in &mut study.files {
+            if let Some(path) = rebase_path(Path::new(&file.path), &old_relative, &new_relative) {
+                file.path = path.to_string_lossy().replace('\\', "/");
+                file_refs_rewritten += 1;
+            }
+        }
+    }
+
+    let mut rewritten_scripts = Vec::new();
+    let mut stale_study_references = Vec::new();
+    if old_code != trimmed_folder {
+        let analysis_dir = new_root.join("06_analysis");
+        rewrite_here_paths(
+            &analysis_dir,
+            &new_root,
+            &old_code,
+            trimmed_folder,
+            &mut rewritten_scripts,
+        );
+        scripts_mentioning(
+            &analysis_dir,
+            &new_root,
+            &old_code,
+            &mut stale_study_references,
+        );
+        rewritten_scripts.sort();
+        stale_study_references.sort();
+    }
+
+    study.id = trimmed_folder.to_string();
+    study.folder_path = new_root.to_string_lossy().to_string();
+    project.updated_at = now_string();
+
+    let updated = project.clone();
+    write_projects_store(app_root, &store)?;
+    let artifacts_rewritten = if old_root != new_root {
+        rebase_study_artifacts(app_root, &project_root, &old_root, &new_root)?
+    } else {
+        0
+    };
+    Ok(StudyFolderRenameReport {
+        project: updated,
+        file_refs_rewritten,
+        artifacts_rewritten,
+        rewritten_scripts,
+        stale_study_references,
+    })
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DeleteStudyArgs {
+    project_id: String,
+    study_id: String,
+    #[serde(default)]
+    delete_on_disk: bool,
+}
+
+pub fn delete_study(app_root: &Path, args: DeleteStudyArgs) -> Result<Project, String> {
+    let mut store = read_projects_store(app_root)?;
+    let project = store
+        .projects
+        .iter_mut()
+        .find(|project| project.id == args.project_id)
+        .ok_or_else(|| "Project not found.".to_string())?;
+
+    let mut removed_path: Option<PathBuf> = None;
+    let before = project.studies.len();
+    project.studies.retain(|study| {
+        if study.id == args.study_id {
+            if args.delete_on_disk {
+                if !study.folder_path.trim().is_empty() {
+                    removed_path = Some(PathBuf::from(study.folder_path.clone()));
+                } else {
+                    removed_path = Some(
+                        PathBuf::from(project.root_path.clone())
+                            .join("studies")
+                            .join(&study.id),
+                    );
+                }
+            }
+            return false;
+        }
+        true
+    });
+
+    if project.studies.len() == before {
+        return Err("Study not found.".to_string());
+    }
+
+    if let Some(folder) = removed_path {
+        let root = fs::canonicalize(PathBuf::from(project.root_path.clone()))
+            .unwrap_or_else(|_| PathBuf::from(project.root_path.clone()));
+        let target = fs::canonicalize(&folder).unwrap_or(folder);
+        if target.starts_with(&root) && target.is_dir() {
+            fs::remove_dir_all(&target).map_err(|err| err.to_string())?;
+        }
+    }
+
+    project.updated_at = now_string();
+    let updated = project.clone();
+    write_projects_store(app_root, &store)?;
+    Ok(updated)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn create_project_and_add_study_persist_under_app_root() {
+        let base = std::env::temp_dir().join(format!("store-projects-{}", Uuid::new_v4()));
+        let app_root = base.join("app");
+        let parent = base.join("work");
+        fs::create_dir_all(&app_root).expect("failed to create app root");
+        fs::create_dir_all(&parent).expect("failed to create project parent");
+
+        let project = create_project(
+            &app_root,
+            CreateProjectArgs {
+                name: "  Demo  ".to_string(),
+                root_dir: parent.to_string_lossy().to_string(),
+                use_existing_root: false,
+                google_drive_url: Some("   ".to_string()),
+            },
+        )
+        .expect("project should be created");
+        assert_eq!(project.name, "Demo");
+        assert!(project.google_drive_url.is_none());
+        assert!(parent.join("Demo").join("studies").is_dir());
+
+        let updated = add_study(
+            &app_root,
+            AddStudyArgs {
+                project_id: project.id.clone(),
+                folder_name: Some("s-abc123".to_string()),
+                title: None,
+            },
+        )
+        .expect("study should be added");
+        assert_eq!(updated.studies[0].id, "S-ABC123");
+        assert_eq!(updated.studies[0].title, "Untitled Study");
+        assert!(parent
+            .join("Demo")
+            .join("studies")
+            .join("S-ABC123")
+            .join("06_analysis")
+            .is_dir());
+
+        let listed = list_projects(&app_root).expect("projects should list");
+        assert_eq!(listed.len(), 1);
+        assert_eq!(listed[0].studies.len(), 1);
+        let _ = fs::remove_dir_all(base);
+    }
+
+    #[test]
+    fn adopting_an_existing_root_registers_valid_study_folders_only() {
+        let base = std::env::temp_dir().join(format!("store-adopt-{}", Uuid::new_v4()));
+        let app_root = base.join("app");
+        let root = base.join("old-projects");
+        let studies = root.join("studies");
+        fs::create_dir_all(&app_root).expect("app root");
+        fs::create_dir_all(studies.join("S-OLD001").join("00_admin")).expect("study one");
+        fs::write(
+            studies.join("S-OLD001").join("00_admin").join("README.md"),
+            "\n# Loss aversion replication\n\nNotes.\n",
+        )
+        .expect("readme");
+        fs::create_dir_all(studies.join("S-OLD002").join("02_build")).expect("study two");
+        fs::write(
+            studies.join("S-OLD002").join("02_build").join("survey.qsf"),
+            "{}",
+        )
+        .expect("qsf");
+        fs::write(
+            studies.join("S-OLD002").join("02_build").join(".DS_Store"),
+            "",
+        )
+        .expect("hidden");
+        fs::create_dir_all(studies.join("pilot-notes")).expect("invalid folder");
+        let args = |dry_run| AdoptExistingProjectArgs {
+            name: "Old projects".to_string(),
+            root_dir: root.to_string_lossy().to_string(),
+            google_drive_url: None,
+            dry_run,
+        };
+
+        let preview = adopt_existing_project(&app_root, args(true)).expect("dry run");
+        let titles = preview
+            .studies
+            .iter()
+            .map(|study| (study.study_id.as_str(), study.title.as_str(), study.files))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            titles,
+            vec![
+                ("S-OLD001", "Loss aversion replication", 1),
+                ("S-OLD002", "S-OLD002", 1)
+            ]
+        );
+        assert_eq!(preview.skipped.len(), 1);
+        assert_eq!(preview.skipped[0].folder, "pilot-notes");
+        assert!(preview.studies[1]
+            .created_folders
+            .contains(&"06_analysis".to_string()));
+        assert!(list_projects(&app_root).expect("list").is_empty());
+        assert!(!studies.join("S-OLD002").join("06_analysis").exists());
+
+        let report = adopt_existing_project(&app_root, args(false)).expect("adopt");
+        let stored = list_projects(&app_root).expect("list");
+        assert_eq!(stored.len(), 1);
+        assert_eq!(stored[0].studies.len(), 2);
+        let qsf = &stored[0].studies[1].files[0];
+        assert_eq!(qsf.path, "studies/S-OLD002/02_build/survey.qsf");
+        assert_eq!(
+            (qsf.kind.as_str(), qsf.folder.as_deref()),
+            ("qsf", Some("02_build"))
+        );
+        assert!(studies.join("S-OLD002").join("06_analysis").is_dir());
+        assert!(!studies.join("pilot-notes").join("06_analysis").exists());
+        assert_eq!(report.project.id, stored[0].id);
+        assert!(adopt_existing_project(&app_root, args(true))
+            .expect_err("root already registered")
+            .contains("already registered"));
+        let _ = fs::remove_dir_all(base);
+    }
+
+    #[test]
+    fn renamed_study_keeps_file_refs_and_analysis_paths_resolvable() {
+        use crate::store::files::{import_files, remove_file_ref, RemoveFileArgs};
+
+        let base = std::env::temp_dir().join(format!("store-rename-{}", Uuid::new_v4()));
+        let app_root = base.join("app");
+        fs::create_dir_all(&app_root).expect("failed to create app root");
+        let project = create_project(
+            &app_root,
+            CreateProjectArgs {
+                name: "Demo".to_string(),
+                root_dir: base.to_string_lossy().to_string(),
+                use_existing_root: false,
+                google_drive_url: None,
+            },
+        )
+        .expect("project should be created");
+        add_study(
+            &app_root,
+            AddStudyArgs {
+                project_id: project.id.clone(),
+                folder_name: Some("S-ABC123".to_string()),
+                title: None,
+            },
+        )
+        .expect("study should be added");
+        let incoming = base.join("survey.qsf");
+        fs::write(&incoming, "{}").expect("write source");
+        import_files(
+            &app_root,
+            project.id.clone(),
+            "S-ABC123".to_string(),
+            vec![incoming.to_string_lossy().to_string()],
+            false,
+            None,
+        )
+        .expect("import");
+        let analysis = base
+            .join("Demo")
+            .join("studies")
+            .join("S-ABC123")
+            .join("06_analysis")
+            .join("analysis.Rmd");
+        fs::write(
+            &analysis,
+            "title: S-ABC123\nout <- here::here(\"studies\", \"S-ABC123\", \"07_outputs\")\n",
+        )
+        .expect("rmd");
+
+        let report = rename_study_folder_json(
+            &app_root,
+            RenameStudyFolderArgs {
+                project_id: project.id.clone(),
+                study_id: "S-ABC123".to_string(),
+                folder_name: "S-XYZ789".to_string(),
+            },
+        )
+        .expect("rename");
+        assert_eq!(report.file_refs_rewritten, 1);
+        let file_path = report.project.studies[0].files[0].path.clone();
+        assert_eq!(file_path, "studies/S-XYZ789/sources/survey.qsf");
+        assert_eq!(report.rewritten_scripts, vec!["06_analysis/analysis.Rmd"]);
+        assert_eq!(
+            report.stale_study_references,
+            vec!["06_analysis/analysis.Rmd"]
+        );
+        let rmd = fs::read_to_string(
+            base.join("Demo")
+                .join("studies")
+                .join("S-XYZ789")
+                .join("06_analysis")
+                .join("analysis.Rmd"),
+        )
+        .expect("read rmd");
+        assert!(rmd.contains("here::here(\"studies\", \"S-XYZ789\", \"07_outputs\")"));
+
+        let remove: RemoveFileArgs = serde_json::from_value(serde_json::json!({
+            "projectId": project.id,
+            "studyId": "S-XYZ789",
+            "path": file_path,
+        }))
+        .expect("args");
+        let study = remove_file_ref(&app_root, remove).expect("remove");
+        assert!(study.files.is_empty());
+        assert!(!base.join("Demo").join(&file_path).exists());
+        let _ = fs::remove_dir_all(base);
+    }
+
+    #[test]
+    fn duplicate_study_copies_selected_folders_and_flags_stale_ids() {
+        let base = std::env::temp_dir().join(format!("store-duplicate-{}", Uuid::new_v4()));
+        let app_root = base.join("app");
+        fs::create_dir_all(&app_root).expect("failed to create app root");
+        fs::create_dir_all(&base).expect("failed to create project parent");
+        let project = create_project(
+            &app_root,
+            CreateProjectArgs {
+                name: "Demo".to_string(),
+                root_dir: base.to_string_lossy().to_string(),
+                use_existing_root: false,
+                google_drive_url: None,
+            },
+        )
+        .expect("project should be created");
+        add_study(
+            &app_root,
+            AddStudyArgs {
+                project_id: project.id.clone(),
+                folder_name: Some("S-ABC123".to_string()),
+                title: Some("Study 1".to_string()),
+            },
+        )
+        .expect("study should be added");
+        let source = base.join("Demo").join("studies").join("S-ABC123");
+        fs::write(source.join("01_design").join("design.md"), "design").expect("design");
+        fs::write(source.join("05_data").join("data.csv"), "a,b").expect("data");
+        fs::write(
+            source.join("06_analysis").join("analysis.Rmd"),
+            "title: S-ABC123 analysis",
+        )
+        .expect("rmd");
+        let mut store = read_projects_store(&app_root).expect("store");
+        store.projects[0].studies[0].files = ["01_design/design.md", "05_data/data.csv"]
+            .iter()
+            .map(|rel| FileRef {
+                path: format!("studies/S-ABC123/{rel}"),
+                name: rel.rsplit('/').next().unwrap_or_default().to_string(),
+                kind: "other".to_string(),
+                sha256: None,
+                folder: None,
+            })
+            .collect();
+        write_projects_store(&app_root, &store).expect("write store");
+
+        let args = |folders: &[&str]| DuplicateStudyArgs {
+            project_id: project.id.clone(),
+            study_id: "S-ABC123".to_string(),
+            title: None,
+            copy_folders: folders.iter().map(|f| f.to_string()).collect(),
+        };
+        let err = duplicate_study(&app_root, args(&["01_design", "08_osf_release"]))
+            .expect_err("release folder refused");
+        assert!(err.contains("08_osf_release"));
+
+        let report = duplicate_study(&app_root, args(&["01_design", "06_analysis"]))
+            .expect("study should duplicate");
+        assert_ne!(report.study_id, "S-ABC123");
+        assert_eq!(report.files_copied, 2);
+        assert_eq!(
+            report.stale_study_references,
+            vec!["06_analysis/analysis.Rmd"]
+        );
+        let copy = report
+            .project
+            .studies
+            .iter()
+            .find(|study| study.id == report.study_id)
+            .expect("new study");
+        assert_eq!(copy.title, "Study 1 (copy)");
+        assert_eq!(copy.files.len(), 1);
+        assert_eq!(
+            copy.files[0].path,
+            format!("studies/{}/01_design/design.md", report.study_id)
+        );
+        let copy_root = PathBuf::from(&copy.folder_path);
+        assert!(copy_root.join("05_data").is_dir());
+        assert!(!copy_root.join("05_data").join("data.csv").exists());
+        assert!(copy_root.join("08_osf_release").is_dir());
+        let _ = fs::remove_dir_all(base);
+    }
+
+    #[test]
+    fn relocate_project_rewrites_paths_and_reports_missing_studies() {
+        let base = std::env::temp_dir().join(format!("store-relocate-{}", Uuid::new_v4()));
+        let app_root = base.join("app");
+        let parent = base.join("old-drive");
+        let moved_parent = base.join("new-drive");
+        fs::create_dir_all(&app_root).expect("failed to create app root");
+        fs::create_dir_all(&parent).expect("failed to create project parent");
+        fs::create_dir_all(&moved_parent).expect("failed to create new parent");
+
+        let project = create_project(
+            &app_root,
+            CreateProjectArgs {
+                name: "Demo".to_string(),
+                root_dir: parent.to_string_lossy().to_string(),
+                use_existing_root: false,
+                google_drive_url: None,
+            },
+        )
+        .expect("project should be created");
+        for folder in ["S-ABC123", "S-XYZ789"] {
+            add_study(
+                &app_root,
+                AddStudyArgs {
+                    project_id: project.id.clone(),
+                    folder_name: Some(folder.to_string()),
+                    title: None,
+                },
+            )
+            .expect("study should be added");
+        }
+        let source = base.join("notes.txt");
+        fs::write(&source, "notes").expect("failed to write source file");
+        crate::store::files::import_files(
+            &app_root,
+            project.id.clone(),
+            "S-ABC123".to_string(),
+            vec![source.to_string_lossy().to_string()],
+            false,
+            None,
+        )
+        .expect("file should import");
+        crate::store::sqlite::migrate_json_to_sqlite(&app_root).expect("sqlite mirror");
+        let old_root = parent.join("Demo");
+        let conn = crate::store::sqlite::connection(&app_root).expect("db should open");
+        conn.execute(
+            "INSERT INTO artifacts (id, study_id, kind, value, label, created_at) \
+      VALUES ('a1', 'S-ABC123', 'file', ?1, NULL, ?2)",
+            rusqlite::params![
+                old_root
+                    .join("paper")
+                    .join("draft.docx")
+                    .to_string_lossy()
+                    .to_string(),
+                now_string()
+            ],
+        )
+        .expect("failed to seed artifact");
+
+        let new_root = moved_parent.join("Demo");
+        fs::rename(&old_root, &new_root).expect("failed to move project");
+        fs::remove_dir_all(new_root.join("studies").join("S-XYZ789"))
+            .expect("failed to drop study folder");
+
+        let args = |dry_run| RelocateProjectArgs {
+            project_id: project.id.clone(),
+            root_dir: new_root.to_string_lossy().to_string(),
+            dry_run,
+        };
+        let preview = relocate_project(&app_root, args(true)).expect("dry run");
+        assert!(preview.rewrites.iter().any(|r| r.kind == "study"));
+        assert!(preview.rewrites.iter().any(|r| r.kind == "sqlite_study"));
+        assert!(preview.rewrites.iter().any(|r| r.kind == "artifact"));
+        assert_eq!(preview.missing_studies, vec!["S-XYZ789".to_string()]);
+        assert!(preview.missing_files.is_empty());
+        let stored = read_projects_store(&app_root).expect("store");
+        assert_eq!(stored.projects[0].root_path, old_root.to_string_lossy());
+
+        let report = relocate_project(&app_root, args(false)).expect("relocate");
+        let stored = read_projects_store(&app_root).expect("store");
+        let relocated = &stored.projects[0];
+        assert_eq!(relocated.root_path, new_root.to_string_lossy());
+        let study = &relocated.studies[0];
+        assert!(Path::new(&study.folder_path).starts_with(&new_root));
+        assert!(Path::new(&study.folder_path).is_dir());
+        assert!(new_root.join(&study.files[0].path).is_file());
+        assert_eq!(report.missing_studies, vec!["S-XYZ789".to_string()]);
+
+        let folder: String = conn
+            .query_row(
+                "SELECT folder_path FROM studies WHERE id = 'S-ABC123'",
+                [],
+                |row| row.get(0),
+            )
+            .expect("study row");
+        assert!(Path::new(&folder).is_dir());
+        let artifact: String = conn
+            .query_row("SELECT value FROM artifacts WHERE id = 'a1'", [], |row| {
+                row.get(0)
+            })
+            .expect("artifact row");
+        assert!(Path::new(&artifact).starts_with(&new_root));
+        let _ = fs::remove_dir_all(base);
+    }
+}
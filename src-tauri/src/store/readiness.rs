@@ -0,0 +1,526 @@
+use rusqlite::{params, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use super::sqlite::{connection, db_path, init_schema};
+use super::{read_projects_store, resolve_study_root};
+use crate::render::helpers::analysis_paths;
+use crate::spec::types::WarningItem;
+
+pub const READINESS_CONFIG_PATH: &str = "config/readiness.json";
+pub const RELEASE_GATE: &str = "release";
+
+const DEFAULT_PREREG_GATE: &[&str] = &["design_doc", "qsf", "prereg_doc"];
+const DEFAULT_RELEASE_GATE: &[&str] = &[
+    "design_doc",
+    "qsf",
+    "prereg_doc",
+    "data_dictionary",
+    "analysis_spec",
+    "outputs",
+];
+const POST_COLLECTION_STATUSES: &[&str] =
+    &["analyzing", "writing", "submitted", "published", "archived"];
+
+#[derive(Debug, Serialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum ReadinessStatus {
+    Pass,
+    Warn,
+    Fail,
+}
+
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ReadinessItem {
+    pub id: String,
+    pub label: String,
+    pub status: ReadinessStatus,
+    /// Study-relative paths found, warning counts, or the reason a check failed.
+    pub evidence: Vec<String>,
+}
+
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct GateResult {
+    pub name: String,
+    pub passed: bool,
+    /// Ids of required items with status `fail`; warnings do not block a gate.
+    pub failing: Vec<String>,
+}
+
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct StudyReadiness {
+    pub study_id: String,
+    pub items: Vec<ReadinessItem>,
+    pub gates: Vec<GateResult>,
+}
+
+impl StudyReadiness {
+    pub fn gate(&self, name: &str) -> Option<&GateResult> {
+        self.gates.iter().find(|gate| gate.name == name)
+    }
+}
+
+/// Project-level `config/readiness.json`: gate name to the item ids that must pass.
+#[derive(Debug, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ReadinessConfig {
+    #[serde(default)]
+    pub gates: BTreeMap<String, Vec<String>>,
+}
+
+impl Default for ReadinessConfig {
+    fn default() -> Self {
+        let gate = |ids: &[&str]| ids.iter().map(|id| id.to_string()).collect();
+        Self {
+            gates: BTreeMap::from([
+                ("prereg".to_string(), gate(DEFAULT_PREREG_GATE)),
+                (RELEASE_GATE.to_string(), gate(DEFAULT_RELEASE_GATE)),
+            ]),
+        }
+    }
+}
+
+pub fn load_readiness_config(project_root: &Path) -> Result<ReadinessConfig, String> {
+    let path = project_root.join(READINESS_CONFIG_PATH);
+    if !path.exists() {
+        return Ok(ReadinessConfig::default());
+    }
+    let raw = fs::read_to_string(&path).map_err(|err| err.to_string())?;
+    let config: ReadinessConfig = serde_json::from_str(&raw)
+        .map_err(|err| format!("Invalid {READINESS_CONFIG_PATH}: {err}"))?;
+    if config.gates.is_empty() {
+        return Ok(ReadinessConfig::default());
+    }
+    Ok(config)
+}
+
+fn collect_files(dir: &Path, out: &mut Vec<PathBuf>) {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return;
+    };
+    for entry in entries.filter_map(|entry| entry.ok()) {
+        if entry.file_name().to_string_lossy().starts_with('.') {
+            continue;
+        }
+        let path = entry.path();
+        if path.is_dir() {
+            collect_files(&path, out);
+        } else if path.is_file() {
+            out.push(path);
+        }
+    }
+}
+
+fn files_in(study_root: &Path, folder: &str, keep: impl Fn(&str) -> bool) -> Vec<String> {
+    let mut files = Vec::new();
+    collect_files(&study_root.join(folder), &mut files);
+    let mut out: Vec<String> = files
+        .iter()
+        .filter(|path| {
+            path.file_name()
+                .map(|name| keep(&name.to_string_lossy().to_lowercase()))
+                .unwrap_or(false)
+        })
+        .map(|path| relative(study_root, path))
+        .collect();
+    out.sort();
+    out
+}
+
+fn relative(study_root: &Path, path: &Path) -> String {
+    path.strip_prefix(study_root)
+        .unwrap_or(path)
+        .to_string_lossy()
+        .replace('\\', "/")
+}
+
+fn file_item(id: &str, label: &str, found: Vec<String>, missing: &str) -> ReadinessItem {
+    let (status, evidence) = if found.is_empty() {
+        (ReadinessStatus::Fail, vec![missing.to_string()])
+    } else {
+        (ReadinessStatus::Pass, found)
+    };
+    ReadinessItem {
+        id: id.to_string(),
+        label: label.to_string(),
+        status,
+        evidence,
+    }
+}
+
+#[derive(Deserialize)]
+struct SavedSpecWarnings {
+    #[serde(default)]
+    warnings: Vec<WarningItem>,
+}
+
+fn analysis_spec_item(study_root: &Path) -> ReadinessItem {
+    let mut specs: Vec<PathBuf> = fs::read_dir(study_root.join("06_analysis"))
+        .map(|entries| {
+            entries
+                .filter_map(|entry| entry.ok())
+                .map(|entry| analysis_paths(&entry.path()).0)
+                .filter(|spec_path| spec_path.is_file())
+                .collect()
+        })
+        .unwrap_or_default();
+    specs.sort();
+
+    let mut item = ReadinessItem {
+        id: "analysis_spec".to_string(),
+        label: "Analysis spec saved without unresolved warnings".to_string(),
+        status: ReadinessStatus::Pass,
+        evidence: Vec::new(),
+    };
+    if specs.is_empty() {
+        item.status = ReadinessStatus::Fail;
+        item.evidence
+            .push("No saved analysis spec in 06_analysis.".to_string());
+        return item;
+    }
+    for spec_path in specs {
+        let path = relative(study_root, &spec_path);
+        let parsed = fs::read_to_string(&spec_path)
+            .map_err(|err| err.to_string())
+            .and_then(|raw| {
+                serde_json::from_str::<SavedSpecWarnings>(&raw).map_err(|err| err.to_string())
+            });
+        let warnings = match parsed {
+            Ok(spec) => spec.warnings,
+            Err(err) => {
+                item.status = ReadinessStatus::Fail;
+                item.evidence.push(format!("{path}: unreadable ({err})"));
+                continue;
+            }
+        };
+        let unresolved = warnings
+            .iter()
+            .filter(|warning| warning.code == "UNRESOLVED_VARIABLE")
+            .count();
+        if unresolved > 0 {
+            item.status = ReadinessStatus::Fail;
+        } else if !warnings.is_empty() && item.status == ReadinessStatus::Pass {
+            item.status = ReadinessStatus::Warn;
+        }
+        item.evidence.push(format!(
+            "{path}: {} warning(s), {unresolved} unresolved variable(s)",
+            warnings.len()
+        ));
+    }
+    item
+}
+
+fn study_status_item(app_root: &Path, study_id: &str) -> Result<ReadinessItem, String> {
+    let status: Option<String> = if db_path(app_root).exists() {
+        let conn = connection(app_root)?;
+        init_schema(&conn)?;
+        conn.query_row(
+            "SELECT status FROM studies WHERE id = ?1",
+            params![study_id],
+            |row| row.get(0),
+        )
+        .optional()
+        .map_err(|err| err.to_string())?
+    } else {
+        None
+    };
+    let (status, evidence) = match status {
+        None => (
+            ReadinessStatus::Warn,
+            "Study is not tracked in the study database.".to_string(),
+        ),
+        Some(value) if POST_COLLECTION_STATUSES.contains(&value.as_str()) => {
+            (ReadinessStatus::Pass, format!("status: {value}"))
+        }
+        Some(value) => (ReadinessStatus::Warn, format!("status: {value}")),
+    };
+    Ok(ReadinessItem {
+        id: "study_status".to_string(),
+        label: "Study status past data collection".to_string(),
+        status,
+        evidence: vec![evidence],
+    })
+}
+
+/// Computes every readiness item for a study folder and evaluates the configured gates.
+pub fn compute_readiness(
+    app_root: &Path,
+    project_root: &Path,
+    study_root: &Path,
+    study_id: &str,
+) -> Result<StudyReadiness, String> {
+    let config = load_readiness_config(project_root)?;
+    let mut dictionary = Vec::new();
+    for folder in ["01_design", "02_build", "05_data"] {
+        dictionary.extend(files_in(study_root, folder, |name| {
+            name.contains("dictionary") || name.contains("codebook")
+        }));
+    }
+    let items = vec![
+        file_item(
+            "design_doc",
+            "Design document in 01_design",
+            files_in(study_root, "01_design", |_| true),
+            "No files in 01_design.",
+        ),
+        file_item(
+            "qsf",
+            "Qualtrics survey (.qsf) in 02_build",
+            files_in(study_root, "02_build", |name| name.ends_with(".qsf")),
+            "No .qsf file in 02_build.",
+        ),
+        file_item(
+            "prereg_doc",
+            "Preregistration in 04_prereg",
+            files_in(study_root, "04_prereg", |_| true),
+            "No files in 04_prereg.",
+        ),
+        file_item(
+            "data_dictionary",
+            "Data dictionary or codebook",
+            dictionary,
+            "No file named *dictionary* or *codebook* in 01_design, 02_build or 05_data.",
+        ),
+        analysis_spec_item(study_root),
+        file_item(
+            "outputs",
+            "Generated outputs in 07_outputs",
+            files_in(study_root, "07_outputs", |_| true),
+            "No files in 07_outputs.",
+        ),
+        study_status_item(app_root, study_id)?,
+    ];
+
+    let mut gates = Vec::new();
+    for (name, required) in &config.gates {
+        let mut failing = Vec::new();
+        for id in required {
+            let item = items.iter().find(|item| &item.id == id).ok_or_else(|| {
+                format!("Unknown readiness item '{id}' in gate '{name}' ({READINESS_CONFIG_PATH}).")
+            })?;
+            if item.status == ReadinessStatus::Fail {
+                failing.push(id.clone());
+            }
+        }
+        gates.push(GateResult {
+            name: name.clone(),
+            passed: failing.is_empty(),
+            failing,
+        });
+    }
+
+    Ok(StudyReadiness {
+        study_id: study_id.to_string(),
+        items,
+        gates,
+    })
+}
+
+/// Error listing the failing release gate items, or `None` when the gate passes.
+pub fn release_gate_refusal(readiness: &StudyReadiness) -> Option<String> {
+    let gate = readiness.gate(RELEASE_GATE)?;
+    if gate.passed {
+        return None;
+    }
+    let labels: Vec<&str> = gate
+        .failing
+        .iter()
+        .filter_map(|id| readiness.items.iter().find(|item| &item.id == id))
+        .map(|item| item.label.as_str())
+        .collect();
+    Some(format!(
+        "Release gate failed: {}. Fix these items or force OSF package generation.",
+        labels.join("; ")
+    ))
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GetStudyReadinessArgs {
+    project_id: String,
+    study_id: String,
+}
+
+pub fn get_study_readiness(
+    app_root: &Path,
+    args: GetStudyReadinessArgs,
+) -> Result<StudyReadiness, String> {
+    let store = read_projects_store(app_root)?;
+    let project = store
+        .projects
+        .iter()
+        .find(|project| project.id == args.project_id)
+        .ok_or_else(|| "Project not found.".to_string())?;
+    let study = project
+        .studies
+        .iter()
+        .find(|study| study.id == args.study_id)
+        .ok_or_else(|| "Study not found.".to_string())?;
+    let study_root = resolve_study_root(project, study);
+    if !study_root.exists() {
+        return Err("Study folder does not exist.".to_string());
+    }
+    compute_readiness(
+        app_root,
+        Path::new(&project.root_path),
+        &study_root,
+        &study.id,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::store::now_string;
+    use crate::store::sqlite::{generate_osf_packages, GenerateOsfPackagesArgs};
+    use uuid::Uuid;
+
+    fn seeded_study(prefix: &str, status: &str) -> (PathBuf, PathBuf, PathBuf, PathBuf) {
+        let base = std::env::temp_dir().join(format!("{prefix}-{}", Uuid::new_v4()));
+        let app_root = base.join("app");
+        let project_root = base.join("project");
+        let study_root = project_root.join("studies").join("S-ABC123");
+        fs::create_dir_all(&app_root).expect("app root");
+        fs::create_dir_all(&study_root).expect("study root");
+        let conn = connection(&app_root).expect("db should open");
+        init_schema(&conn).expect("schema should apply");
+        conn.execute(
+            "INSERT INTO projects (id, name, root_path, created_at) VALUES ('p1', 'P', ?1, ?2)",
+            params![project_root.to_string_lossy().to_string(), now_string()],
+        )
+        .expect("seed project");
+        conn.execute(
+            "INSERT INTO studies (id, project_id, internal_name, paper_label, status, folder_path, created_at) \
+      VALUES ('S-ABC123', 'p1', 'Study', NULL, ?1, ?2, ?3)",
+            params![status, study_root.to_string_lossy().to_string(), now_string()],
+        )
+        .expect("seed study");
+        (base, app_root, project_root, study_root)
+    }
+
+    fn write(path: PathBuf, contents: &str) {
+        fs::create_dir_all(path.parent().expect("parent")).expect("mkdir");
+        fs::write(path, contents).expect("write");
+    }
+
+    fn status_of(readiness: &StudyReadiness, id: &str) -> ReadinessStatus {
+        readiness
+            .items
+            .iter()
+            .find(|item| item.id == id)
+            .map(|item| item.status)
+            .expect("item present")
+    }
+
+    #[test]
+    fn checklist_reports_found_files_and_spec_warnings() {
+        let (base, app_root, project_root, study_root) = seeded_study("readiness", "analyzing");
+        write(study_root.join("01_design/design.docx"), "d");
+        write(study_root.join("02_build/survey.qsf"), "{}");
+        write(study_root.join("04_prereg/prereg.md"), "p");
+        write(study_root.join("05_data/codebook.csv"), "c");
+        write(study_root.join("07_outputs/tables/main.docx"), "t");
+        write(
+            study_root.join("06_analysis/a1/analysis/spec.json"),
+            r#"{"warnings":[{"code":"LOW_CONFIDENCE","message":"m","details":{}}]}"#,
+        );
+
+        let readiness =
+            compute_readiness(&app_root, &project_root, &study_root, "S-ABC123").expect("computed");
+        for id in [
+            "design_doc",
+            "qsf",
+            "prereg_doc",
+            "data_dictionary",
+            "outputs",
+        ] {
+            assert_eq!(status_of(&readiness, id), ReadinessStatus::Pass, "{id}");
+        }
+        assert_eq!(
+            status_of(&readiness, "analysis_spec"),
+            ReadinessStatus::Warn
+        );
+        assert_eq!(status_of(&readiness, "study_status"), ReadinessStatus::Pass);
+        let qsf = readiness
+            .items
+            .iter()
+            .find(|item| item.id == "qsf")
+            .expect("qsf");
+        assert_eq!(qsf.evidence, vec!["02_build/survey.qsf".to_string()]);
+        assert!(readiness.gate("prereg").expect("prereg gate").passed);
+        assert!(readiness.gate(RELEASE_GATE).expect("release gate").passed);
+        assert!(release_gate_refusal(&readiness).is_none());
+
+        write(
+            study_root.join("06_analysis/a2/analysis/spec.json"),
+            r#"{"warnings":[{"code":"UNRESOLVED_VARIABLE","message":"m","details":{}}]}"#,
+        );
+        let readiness =
+            compute_readiness(&app_root, &project_root, &study_root, "S-ABC123").expect("computed");
+        assert_eq!(
+            status_of(&readiness, "analysis_spec"),
+            ReadinessStatus::Fail
+        );
+        assert_eq!(
+            readiness.gate(RELEASE_GATE).expect("release gate").failing,
+            vec!["analysis_spec".to_string()]
+        );
+        let _ = fs::remove_dir_all(base);
+    }
+
+    #[test]
+    fn failing_release_gate_blocks_osf_generation_unless_forced() {
+        let (base, app_root, project_root, study_root) = seeded_study("readiness-gate", "planning");
+        write(study_root.join("02_build/survey.qsf"), "{}");
+
+        let readiness =
+            compute_readiness(&app_root, &project_root, &study_root, "S-ABC123").expect("computed");
+        assert_eq!(status_of(&readiness, "design_doc"), ReadinessStatus::Fail);
+        assert_eq!(
+            status_of(&readiness, "analysis_spec"),
+            ReadinessStatus::Fail
+        );
+        assert_eq!(status_of(&readiness, "study_status"), ReadinessStatus::Warn);
+        assert!(!readiness.gate("prereg").expect("prereg gate").passed);
+
+        let args = |force: bool| -> GenerateOsfPackagesArgs {
+            serde_json::from_value(serde_json::json!({
+                "studyId": "S-ABC123",
+                "includePilots": false,
+                "enforceReleaseGate": true,
+                "force": force
+            }))
+            .expect("args")
+        };
+        let err = generate_osf_packages(&app_root, args(false)).expect_err("gate should refuse");
+        assert!(err.starts_with("Release gate failed"));
+        assert!(err.contains("Design document in 01_design"));
+        assert!(!study_root.join("08_osf_release").exists());
+        generate_osf_packages(&app_root, args(true)).expect("forced generation");
+        assert!(study_root.join("08_osf_release/COMPLETE").exists());
+
+        write(
+            project_root.join(READINESS_CONFIG_PATH),
+            r#"{"gates":{"release":["qsf"]}}"#,
+        );
+        let readiness =
+            compute_readiness(&app_root, &project_root, &study_root, "S-ABC123").expect("computed");
+        assert!(readiness.gate("prereg").is_none());
+        assert!(readiness.gate(RELEASE_GATE).expect("release gate").passed);
+        generate_osf_packages(&app_root, args(false)).expect("configured gate passes");
+
+        write(
+            project_root.join(READINESS_CONFIG_PATH),
+            r#"{"gates":{"release":["ethics_approval"]}}"#,
+        );
+        let err = compute_readiness(&app_root, &project_root, &study_root, "S-ABC123")
+            .expect_err("unknown item");
+        assert!(err.contains("'ethics_approval'"));
+        let _ = fs::remove_dir_all(base);
+    }
+}
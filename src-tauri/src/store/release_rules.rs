@@ -0,0 +1,197 @@
+use globset::{GlobBuilder, GlobSet, GlobSetBuilder};
+use serde::Deserialize;
+use std::fs;
+use std::path::Path;
+
+pub const OSF_RELEASE_CONFIG_PATH: &str = "config/osf_release.json";
+
+const DEFAULT_PILOT_PATTERNS: &[&str] = &["03_pilots"];
+const DEFAULT_COMPLETE_PATTERNS: &[&str] = &[];
+const DEFAULT_CONDENSED_PATTERNS: &[&str] = &["raw", "raw_data", "raw_data.*"];
+
+fn patterns(values: &[&str]) -> Vec<String> {
+    values.iter().map(|value| value.to_string()).collect()
+}
+
+fn default_pilots() -> Vec<String> {
+    patterns(DEFAULT_PILOT_PATTERNS)
+}
+
+fn default_complete() -> Vec<String> {
+    patterns(DEFAULT_COMPLETE_PATTERNS)
+}
+
+fn default_condensed() -> Vec<String> {
+    patterns(DEFAULT_CONDENSED_PATTERNS)
+}
+
+/// Project-level `config/osf_release.json`: glob patterns for study paths left out of OSF
+/// packages. A pattern without `/` matches a single file or folder name anywhere in the
+/// study; one with `/` matches the study-relative path. Matching ignores case.
+#[derive(Debug, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct OsfReleaseConfig {
+    /// Excluded unless pilots are included.
+    #[serde(default = "default_pilots")]
+    pub pilots: Vec<String>,
+    /// Excluded from complete packages.
+    #[serde(default = "default_complete")]
+    pub complete: Vec<String>,
+    /// Excluded from condensed packages.
+    #[serde(default = "default_condensed")]
+    pub condensed: Vec<String>,
+}
+
+impl Default for OsfReleaseConfig {
+    fn default() -> Self {
+        Self {
+            pilots: default_pilots(),
+            complete: default_complete(),
+            condensed: default_condensed(),
+        }
+    }
+}
+
+/// Compiled form of [`OsfReleaseConfig`].
+#[derive(Debug, Clone)]
+pub struct ReleaseRules {
+    pilots: GlobSet,
+    complete: GlobSet,
+    condensed: GlobSet,
+}
+
+impl Default for ReleaseRules {
+    fn default() -> Self {
+        ReleaseRules::compile(&OsfReleaseConfig::default()).expect("default OSF release rules")
+    }
+}
+
+fn compile_set(section: &str, values: &[String]) -> Result<GlobSet, String> {
+    let mut builder = GlobSetBuilder::new();
+    for value in values {
+        let trimmed = value.trim().trim_matches('/');
+        if trimmed.is_empty() {
+            continue;
+        }
+        let pattern = if trimmed.contains('/') {
+            trimmed.to_string()
+        } else {
+            format!("**/{trimmed}")
+        };
+        let glob = GlobBuilder::new(&pattern)
+            .case_insensitive(true)
+            .literal_separator(true)
+            .build()
+            .map_err(|err| {
+                format!("Invalid {OSF_RELEASE_CONFIG_PATH} pattern in {section}: '{value}': {err}")
+            })?;
+        builder.add(glob);
+    }
+    builder
+        .build()
+        .map_err(|err| format!("Invalid {OSF_RELEASE_CONFIG_PATH}: {err}"))
+}
+
+impl ReleaseRules {
+    pub fn compile(config: &OsfReleaseConfig) -> Result<Self, String> {
+        Ok(Self {
+            pilots: compile_set("pilots", &config.pilots)?,
+            complete: compile_set("complete", &config.complete)?,
+            condensed: compile_set("condensed", &config.condensed)?,
+        })
+    }
+
+    /// Whether the study-relative path `rel` is left out of a package. Folders are checked
+    /// before they are entered, so a matching folder drops everything inside it.
+    pub fn excludes(&self, rel: &Path, include_pilots: bool, condensed: bool) -> bool {
+        self.exclusion_reason(rel, include_pilots, condensed)
+            .is_some()
+    }
+
+    /// Why `rel` is left out of a package, for the package README; `None` when it is kept.
+    pub fn exclusion_reason(
+        &self,
+        rel: &Path,
+        include_pilots: bool,
+        condensed: bool,
+    ) -> Option<&'static str> {
+        if rel.as_os_str().is_empty() {
+            return None;
+        }
+        let rel = rel.to_string_lossy().replace('\\', "/");
+        if !include_pilots && self.pilots.is_match(&rel) {
+            return Some("pilot material; pilots were not included");
+        }
+        if condensed {
+            self.condensed
+                .is_match(&rel)
+                .then_some("raw or private data left out of the condensed package")
+        } else {
+            self.complete
+                .is_match(&rel)
+                .then_some("excluded from the complete package by the project's release rules")
+        }
+    }
+}
+
+pub fn load_release_rules(project_root: &Path) -> Result<ReleaseRules, String> {
+    let path = project_root.join(OSF_RELEASE_CONFIG_PATH);
+    if !path.exists() {
+        return Ok(ReleaseRules::default());
+    }
+    let raw = fs::read_to_string(&path).map_err(|err| err.to_string())?;
+    let config: OsfReleaseConfig = serde_json::from_str(&raw)
+        .map_err(|err| format!("Invalid {OSF_RELEASE_CONFIG_PATH}: {err}"))?;
+    ReleaseRules::compile(&config)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn default_rules_match_path_segments_not_substrings() {
+        let rules = ReleaseRules::default();
+        let excluded = |rel: &str, pilots: bool, condensed: bool| {
+            rules.excludes(Path::new(rel), pilots, condensed)
+        };
+        assert!(!excluded("02_build/pilot_light_stimuli.docx", false, true));
+        assert!(excluded("03_pilots", false, false));
+        assert!(!excluded("03_pilots", true, false));
+        assert!(!excluded("05_data/pilots_summary.csv", false, false));
+
+        assert!(excluded("05_data/raw", true, true));
+        assert!(excluded("05_data/RAW_DATA.csv", true, true));
+        assert!(!excluded("05_data/raw", true, false));
+        assert!(!excluded("05_data/drawings.csv", true, true));
+        assert!(!excluded("", false, true));
+    }
+
+    #[test]
+    fn custom_config_excludes_sav_files_from_condensed_only() {
+        let root = std::env::temp_dir().join(format!("osf-rules-{}", uuid::Uuid::new_v4()));
+        fs::create_dir_all(root.join("config")).expect("config dir");
+        fs::write(
+            root.join(OSF_RELEASE_CONFIG_PATH),
+            r#"{ "condensed": ["*.sav", "05_data/private"] }"#,
+        )
+        .expect("config");
+        let rules = load_release_rules(&root).expect("rules");
+        assert!(rules.excludes(Path::new("05_data/export.SAV"), false, true));
+        assert!(!rules.excludes(Path::new("05_data/export.sav"), false, false));
+        assert!(rules.excludes(Path::new("05_data/private"), false, true));
+        assert!(!rules.excludes(Path::new("06_analysis/private"), false, true));
+        // A section in the file replaces its defaults; sections left out keep them.
+        assert!(rules.excludes(Path::new("03_pilots"), false, false));
+        assert!(!rules.excludes(Path::new("05_data/raw"), false, true));
+
+        fs::write(
+            root.join(OSF_RELEASE_CONFIG_PATH),
+            r#"{ "complete": ["a[b"] }"#,
+        )
+        .expect("bad config");
+        let err = load_release_rules(&root).expect_err("invalid glob");
+        assert!(err.contains("complete"));
+        let _ = fs::remove_dir_all(root);
+    }
+}
@@ -0,0 +1,421 @@
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+use super::sqlite::{connection, db_path, init_schema, write_transaction};
+use super::{now_string, read_projects_store, ProjectsStore};
+
+const DEFAULT_LIMIT: usize = 50;
+/// Row in `search_index_state` recorded once the FTS tables have been filled.
+const FTS_STATE: &str = "fts";
+
+#[derive(Debug, Serialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum SearchHitKind {
+    Study,
+    Artifact,
+    File,
+}
+
+#[derive(Debug, Serialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct SearchHit {
+    pub kind: SearchHitKind,
+    pub project_id: String,
+    pub study_id: String,
+    /// Set for artifact hits.
+    pub artifact_id: Option<String>,
+    /// Set for file hits: the file's path as stored on the study.
+    pub path: Option<String>,
+    /// Which field matched: "title", "internalName", "paperLabel", "kind", "value", "label"
+    /// or "fileName".
+    pub field: String,
+    /// Matched text; artifact hits mark the match with `[` `]`.
+    pub snippet: String,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SearchProjectArgs {
+    project_id: String,
+    query: String,
+    #[serde(default)]
+    limit: Option<usize>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SearchAllProjectsArgs {
+    query: String,
+    #[serde(default)]
+    limit: Option<usize>,
+}
+
+/// Query words, split the way the `unicode61` tokenizer splits indexed text.
+fn query_terms(query: &str) -> Vec<String> {
+    query
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|term| !term.is_empty())
+        .map(str::to_lowercase)
+        .collect()
+}
+
+/// Every term as a quoted prefix query, so "trust 7-item" finds "Trust (7-item scale)".
+fn fts_query(terms: &[String]) -> String {
+    terms
+        .iter()
+        .map(|term| format!("\"{}\"*", term.replace('"', "\"\"")))
+        .collect::<Vec<String>>()
+        .join(" ")
+}
+
+fn matches_all(text: &str, terms: &[String]) -> bool {
+    let lower = text.to_lowercase();
+    terms.iter().all(|term| lower.contains(term.as_str()))
+}
+
+fn matches_any(text: &str, terms: &[String]) -> bool {
+    let lower = text.to_lowercase();
+    terms.iter().any(|term| lower.contains(term.as_str()))
+}
+
+/// Fills the FTS tables from their content tables the first time a search runs; the
+/// triggers keep them current after that.
+fn ensure_search_index(conn: &Connection) -> Result<(), String> {
+    let built: Option<String> = conn
+        .query_row(
+            "SELECT built_at FROM search_index_state WHERE name = ?1",
+            params![FTS_STATE],
+            |row| row.get(0),
+        )
+        .optional()
+        .map_err(|err| err.to_string())?;
+    if built.is_some() {
+        return Ok(());
+    }
+    let tx = write_transaction(conn)?;
+    // Another search may have built the index while this one waited for the lock.
+    let built: i64 = tx
+        .query_row(
+            "SELECT COUNT(1) FROM search_index_state WHERE name = ?1",
+            params![FTS_STATE],
+            |row| row.get(0),
+        )
+        .map_err(|err| err.to_string())?;
+    if built > 0 {
+        return Ok(());
+    }
+    tx.execute_batch(
+        "INSERT INTO artifacts_fts (artifacts_fts) VALUES ('rebuild');
+      INSERT INTO studies_fts (studies_fts) VALUES ('rebuild');",
+    )
+    .map_err(|err| format!("Unable to build the search index: {err}"))?;
+    tx.execute(
+        "INSERT INTO search_index_state (name, built_at) VALUES (?1, ?2)",
+        params![FTS_STATE, now_string()],
+    )
+    .map_err(|err| err.to_string())?;
+    tx.commit().map_err(|err| err.to_string())
+}
+
+/// Titles and file names from the JSON store.
+fn store_hits(store: &ProjectsStore, project_id: Option<&str>, terms: &[String]) -> Vec<SearchHit> {
+    let mut hits = Vec::new();
+    for project in &store.projects {
+        if project_id.is_some_and(|id| id != project.id) {
+            continue;
+        }
+        for study in &project.studies {
+            if matches_all(&study.title, terms) {
+                hits.push(SearchHit {
+                    kind: SearchHitKind::Study,
+                    project_id: project.id.clone(),
+                    study_id: study.id.clone(),
+                    artifact_id: None,
+                    path: None,
+                    field: "title".to_string(),
+                    snippet: study.title.clone(),
+                });
+            }
+            for file in &study.files {
+                if matches_all(&file.name, terms) {
+                    hits.push(SearchHit {
+                        kind: SearchHitKind::File,
+                        project_id: project.id.clone(),
+                        study_id: study.id.clone(),
+                        artifact_id: None,
+                        path: Some(file.path.clone()),
+                        field: "fileName".to_string(),
+                        snippet: file.name.clone(),
+                    });
+                }
+            }
+        }
+    }
+    hits
+}
+
+/// Study names/labels and artifacts from the SQLite FTS index.
+fn database_hits(
+    conn: &Connection,
+    project_id: Option<&str>,
+    terms: &[String],
+    limit: usize,
+) -> Result<Vec<SearchHit>, String> {
+    ensure_search_index(conn)?;
+    let query = fts_query(terms);
+    let mut hits = Vec::new();
+
+    let mut stmt = conn
+        .prepare(
+            "SELECT studies.id, studies.project_id, studies.internal_name, studies.paper_label \
+      FROM studies_fts JOIN studies ON studies.rowid = studies_fts.rowid \
+      WHERE studies_fts MATCH ?1 AND (?2 IS NULL OR studies.project_id = ?2) \
+      ORDER BY rank LIMIT ?3",
+        )
+        .map_err(|err| err.to_string())?;
+    let rows = stmt
+        .query_map(params![query, project_id, limit as i64], |row| {
+            Ok((
+                row.get::<_, String>(0)?,
+                row.get::<_, String>(1)?,
+                row.get::<_, String>(2)?,
+                row.get::<_, Option<String>>(3)?,
+            ))
+        })
+        .map_err(|err| err.to_string())?;
+    for row in rows {
+        let (study_id, project_id, internal_name, paper_label) =
+            row.map_err(|err| err.to_string())?;
+        let (field, snippet) = match paper_label {
+            Some(label) if !matches_any(&internal_name, terms) => ("paperLabel", label),
+            _ => ("internalName", internal_name),
+        };
+        hits.push(SearchHit {
+            kind: SearchHitKind::Study,
+            project_id,
+            study_id,
+            artifact_id: None,
+            path: None,
+            field: field.to_string(),
+            snippet,
+        });
+    }
+
+    let mut stmt = conn
+        .prepare(
+            "SELECT artifacts.id, artifacts.study_id, studies.project_id, artifacts.value, \
+      artifacts.label, snippet(artifacts_fts, -1, '[', ']', '…', 12) \
+      FROM artifacts_fts JOIN artifacts ON artifacts.rowid = artifacts_fts.rowid \
+      JOIN studies ON studies.id = artifacts.study_id \
+      WHERE artifacts_fts MATCH ?1 AND (?2 IS NULL OR studies.project_id = ?2) \
+      ORDER BY rank LIMIT ?3",
+        )
+        .map_err(|err| err.to_string())?;
+    let rows = stmt
+        .query_map(params![query, project_id, limit as i64], |row| {
+            Ok((
+                row.get::<_, String>(0)?,
+                row.get::<_, String>(1)?,
+                row.get::<_, String>(2)?,
+                row.get::<_, String>(3)?,
+                row.get::<_, Option<String>>(4)?,
+                row.get::<_, String>(5)?,
+            ))
+        })
+        .map_err(|err| err.to_string())?;
+    for row in rows {
+        let (artifact_id, study_id, project_id, value, label, snippet) =
+            row.map_err(|err| err.to_string())?;
+        let field = if label.as_deref().is_some_and(|l| matches_any(l, terms)) {
+            "label"
+        } else if matches_any(&value, terms) {
+            "value"
+        } else {
+            "kind"
+        };
+        hits.push(SearchHit {
+            kind: SearchHitKind::Artifact,
+            project_id,
+            study_id,
+            artifact_id: Some(artifact_id),
+            path: None,
+            field: field.to_string(),
+            snippet,
+        });
+    }
+    Ok(hits)
+}
+
+/// Case-insensitive search over study titles and file names (JSON store) and study names,
+/// paper labels and artifacts (SQLite), limited to `project_id` when given. Every query word
+/// must match; words match as prefixes in the database and as substrings in the store.
+pub fn search(
+    app_root: &Path,
+    project_id: Option<&str>,
+    query: &str,
+    limit: Option<usize>,
+) -> Result<Vec<SearchHit>, String> {
+    let terms = query_terms(query);
+    if terms.is_empty() {
+        return Ok(Vec::new());
+    }
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).max(1);
+    let mut hits = store_hits(&read_projects_store(app_root)?, project_id, &terms);
+    if db_path(app_root).exists() {
+        let conn = connection(app_root)?;
+        init_schema(&conn)?;
+        for hit in database_hits(&conn, project_id, &terms, limit)? {
+            // The database mirrors store titles into internal_name; keep one hit per study.
+            let duplicate = hit.kind == SearchHitKind::Study
+                && hits.iter().any(|existing| {
+                    existing.kind == SearchHitKind::Study
+                        && existing.study_id == hit.study_id
+                        && existing.snippet.eq_ignore_ascii_case(&hit.snippet)
+                });
+            if !duplicate {
+                hits.push(hit);
+            }
+        }
+    }
+    hits.truncate(limit);
+    Ok(hits)
+}
+
+pub fn search_project(app_root: &Path, args: SearchProjectArgs) -> Result<Vec<SearchHit>, String> {
+    search(app_root, Some(&args.project_id), &args.query, args.limit)
+}
+
+pub fn search_all_projects(
+    app_root: &Path,
+    args: SearchAllProjectsArgs,
+) -> Result<Vec<SearchHit>, String> {
+    search(app_root, None, &args.query, args.limit)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::store::sqlite::migrate_json_to_sqlite;
+    use crate::store::{write_projects_store, FileRef, Project, Study};
+    use std::fs;
+    use std::path::PathBuf;
+    use uuid::Uuid;
+
+    fn project(id: &str, studies: Vec<Study>) -> Project {
+        Project {
+            id: id.to_string(),
+            name: id.to_string(),
+            root_path: format!("/tmp/{id}"),
+            created_at: now_string(),
+            updated_at: now_string(),
+            google_drive_url: None,
+            analysis_package_defaults: None,
+            studies,
+        }
+    }
+
+    fn study(id: &str, title: &str, files: &[&str]) -> Study {
+        Study {
+            id: id.to_string(),
+            title: title.to_string(),
+            created_at: now_string(),
+            folder_path: String::new(),
+            files: files
+                .iter()
+                .map(|name| FileRef {
+                    path: format!("02_build/{name}"),
+                    name: name.to_string(),
+                    kind: "file".to_string(),
+                    sha256: None,
+                    folder: Some("02_build".to_string()),
+                })
+                .collect(),
+        }
+    }
+
+    fn seeded_app_root() -> PathBuf {
+        let app_root = std::env::temp_dir().join(format!("search-{}", Uuid::new_v4()));
+        fs::create_dir_all(&app_root).expect("mkdir");
+        let store = ProjectsStore {
+            projects: vec![
+                project(
+                    "p1",
+                    vec![
+                        study("S-TRUST1", "Trust in Science", &["TRUST_items.csv"]),
+                        study("S-OTHER1", "Pilot", &["notes.txt"]),
+                    ],
+                ),
+                project("p2", vec![study("S-TRUST2", "Institutional trust", &[])]),
+            ],
+            warning: None,
+        };
+        write_projects_store(&app_root, &store).expect("write store");
+        migrate_json_to_sqlite(&app_root).expect("mirror to sqlite");
+        app_root
+    }
+
+    fn add(conn: &Connection, id: &str, study_id: &str, label: &str) {
+        conn.execute(
+            "INSERT INTO artifacts (id, study_id, kind, value, label, created_at) \
+      VALUES (?1, ?2, 'url', 'https://osf.io/abc', ?3, ?4)",
+            params![id, study_id, label, now_string()],
+        )
+        .expect("insert artifact");
+    }
+
+    #[test]
+    fn search_is_case_insensitive_and_scoped_to_the_project() {
+        let app_root = seeded_app_root();
+        let hits = search(&app_root, Some("p1"), "trust", None).expect("search");
+        let found = hits
+            .iter()
+            .map(|hit| (hit.kind, hit.study_id.as_str(), hit.field.as_str()))
+            .collect::<Vec<_>>();
+        // The mirrored internal_name duplicates the title and is dropped.
+        assert_eq!(
+            found,
+            vec![
+                (SearchHitKind::Study, "S-TRUST1", "title"),
+                (SearchHitKind::File, "S-TRUST1", "fileName"),
+            ]
+        );
+        assert_eq!(hits[1].path.as_deref(), Some("02_build/TRUST_items.csv"));
+
+        let everywhere = search(&app_root, None, "TRUST", None).expect("search");
+        assert!(everywhere.iter().any(|hit| hit.study_id == "S-TRUST2"));
+        assert!(search(&app_root, None, "  -- ", None)
+            .expect("search")
+            .is_empty());
+        let _ = fs::remove_dir_all(app_root);
+    }
+
+    #[test]
+    fn artifact_labels_are_indexed_lazily_and_kept_current() {
+        let app_root = seeded_app_root();
+        let conn = connection(&app_root).expect("db");
+        add(&conn, "a1", "S-OTHER1", "Trust scale (7-item) wording");
+
+        let hits = search(&app_root, Some("p1"), "7-item TRUST", None).expect("search");
+        let artifact = hits
+            .iter()
+            .find(|hit| hit.kind == SearchHitKind::Artifact)
+            .expect("artifact hit");
+        assert_eq!(artifact.artifact_id.as_deref(), Some("a1"));
+        assert_eq!(artifact.study_id, "S-OTHER1");
+        assert_eq!(artifact.field, "label");
+        assert!(artifact.snippet.contains("[Trust]"));
+
+        // Mutations after the first build reach the index through the triggers.
+        add(&conn, "a2", "S-OTHER1", "Codebook for the trust battery");
+        conn.execute("DELETE FROM artifacts WHERE id = 'a1'", [])
+            .expect("delete");
+        let ids = search(&app_root, Some("p1"), "trust", None)
+            .expect("search")
+            .into_iter()
+            .filter_map(|hit| hit.artifact_id)
+            .collect::<Vec<_>>();
+        assert_eq!(ids, vec!["a2".to_string()]);
+        let _ = fs::remove_dir_all(app_root);
+    }
+}
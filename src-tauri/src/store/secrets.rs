@@ -0,0 +1,403 @@
+use argon2::Argon2;
+use chacha20poly1305::aead::rand_core::RngCore;
+use chacha20poly1305::aead::{Aead, AeadCore, KeyInit, OsRng, Payload};
+use chacha20poly1305::{ChaCha20Poly1305, Key, Nonce};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, OnceLock};
+
+use super::read_projects_store;
+use crate::spec::types::{WarningItem, WarningSeverity};
+
+pub const SECRETS_DIR: &str = ".researchapp";
+pub const SECRETS_FILE_NAME: &str = "secrets.json";
+pub const SECRETS_LOCKED: &str = "PROJECT_SECRETS_LOCKED";
+pub const SECRETS_WRONG_PASSPHRASE: &str = "PROJECT_SECRETS_WRONG_PASSPHRASE";
+
+const KDF_NAME: &str = "argon2id";
+const SALT_LEN: usize = 16;
+const CHECK_NAME: &str = "__check__";
+const CHECK_PLAINTEXT: &[u8] = b"research-workflow-secrets";
+
+/// Derived keys of unlocked projects, keyed by project root. Never persisted.
+static UNLOCKED_KEYS: OnceLock<Mutex<HashMap<PathBuf, [u8; 32]>>> = OnceLock::new();
+
+fn unlocked_keys() -> &'static Mutex<HashMap<PathBuf, [u8; 32]>> {
+    UNLOCKED_KEYS.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+pub fn secrets_path(project_root: &Path) -> PathBuf {
+    project_root.join(SECRETS_DIR).join(SECRETS_FILE_NAME)
+}
+
+/// Structured error (JSON `WarningItem`) the UI can parse; never includes secret values.
+fn secrets_error(code: &str, message: &str) -> String {
+    let error = WarningItem {
+        code: code.to_string(),
+        message: message.to_string(),
+        details: serde_json::Value::Null,
+        severity: WarningSeverity::Error,
+    };
+    serde_json::to_string(&error).unwrap_or_else(|_| message.to_string())
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+struct SealedValue {
+    nonce: String,
+    ciphertext: String,
+}
+
+#[derive(Serialize, Deserialize)]
+struct SecretsFile {
+    version: u32,
+    kdf: String,
+    salt: String,
+    /// Known plaintext sealed with the key, so a wrong passphrase fails on unlock.
+    check: SealedValue,
+    #[serde(default)]
+    secrets: BTreeMap<String, SealedValue>,
+}
+
+fn read_secrets_file(project_root: &Path) -> Result<Option<SecretsFile>, String> {
+    let path = secrets_path(project_root);
+    if !path.exists() {
+        return Ok(None);
+    }
+    let raw =
+        fs::read_to_string(&path).map_err(|e| format!("Unable to read {}: {e}", path.display()))?;
+    let file: SecretsFile =
+        serde_json::from_str(&raw).map_err(|e| format!("Invalid {}: {e}", path.display()))?;
+    Ok(Some(file))
+}
+
+fn write_secrets_file(project_root: &Path, file: &SecretsFile) -> Result<(), String> {
+    let path = secrets_path(project_root);
+    let dir = project_root.join(SECRETS_DIR);
+    fs::create_dir_all(&dir).map_err(|e| e.to_string())?;
+    let ignore = dir.join(".gitignore");
+    if !ignore.exists() {
+        fs::write(&ignore, format!("{SECRETS_FILE_NAME}\n")).map_err(|e| e.to_string())?;
+    }
+    let payload = serde_json::to_string_pretty(file).map_err(|e| e.to_string())?;
+    fs::write(&path, payload).map_err(|e| format!("Unable to write {}: {e}", path.display()))
+}
+
+fn derive_key(passphrase: &str, salt: &[u8]) -> Result<[u8; 32], String> {
+    let mut key = [0u8; 32];
+    Argon2::default()
+        .hash_password_into(passphrase.as_bytes(), salt, &mut key)
+        .map_err(|e| format!("Unable to derive secrets key: {e}"))?;
+    Ok(key)
+}
+
+fn seal(key: &[u8; 32], name: &str, plaintext: &[u8]) -> Result<SealedValue, String> {
+    let cipher = ChaCha20Poly1305::new(Key::from_slice(key));
+    let nonce = ChaCha20Poly1305::generate_nonce(&mut OsRng);
+    let ciphertext = cipher
+        .encrypt(
+            &nonce,
+            Payload {
+                msg: plaintext,
+                aad: name.as_bytes(),
+            },
+        )
+        .map_err(|_| "Unable to encrypt secret.".to_string())?;
+    Ok(SealedValue {
+        nonce: hex::encode(nonce),
+        ciphertext: hex::encode(ciphertext),
+    })
+}
+
+/// Decrypts a sealed value; `None` when authentication fails (wrong key or tampered data).
+fn open(key: &[u8; 32], name: &str, sealed: &SealedValue) -> Result<Option<Vec<u8>>, String> {
+    let nonce = hex::decode(&sealed.nonce).map_err(|e| e.to_string())?;
+    let ciphertext = hex::decode(&sealed.ciphertext).map_err(|e| e.to_string())?;
+    if nonce.len() != 12 {
+        return Err("Invalid secret nonce.".to_string());
+    }
+    let cipher = ChaCha20Poly1305::new(Key::from_slice(key));
+    Ok(cipher
+        .decrypt(
+            Nonce::from_slice(&nonce),
+            Payload {
+                msg: &ciphertext,
+                aad: name.as_bytes(),
+            },
+        )
+        .ok())
+}
+
+/// Derives the project key from `passphrase` and keeps it in memory for this session. The
+/// first unlock of a project creates its secrets file with that passphrase.
+pub fn unlock_secrets_at(project_root: &Path, passphrase: &str) -> Result<(), String> {
+    if passphrase.is_empty() {
+        return Err("Passphrase is required.".to_string());
+    }
+    let key = match read_secrets_file(project_root)? {
+        Some(file) => {
+            let salt = hex::decode(&file.salt).map_err(|e| e.to_string())?;
+            let key = derive_key(passphrase, &salt)?;
+            if open(&key, CHECK_NAME, &file.check)?.as_deref() != Some(CHECK_PLAINTEXT) {
+                return Err(secrets_error(
+                    SECRETS_WRONG_PASSPHRASE,
+                    "The passphrase does not match this project's secrets.",
+                ));
+            }
+            key
+        }
+        None => {
+            let mut salt = [0u8; SALT_LEN];
+            OsRng.fill_bytes(&mut salt);
+            let key = derive_key(passphrase, &salt)?;
+            write_secrets_file(
+                project_root,
+                &SecretsFile {
+                    version: 1,
+                    kdf: KDF_NAME.to_string(),
+                    salt: hex::encode(salt),
+                    check: seal(&key, CHECK_NAME, CHECK_PLAINTEXT)?,
+                    secrets: BTreeMap::new(),
+                },
+            )?;
+            key
+        }
+    };
+    unlocked_keys()
+        .lock()
+        .map_err(|_| "Unable to acquire secrets lock".to_string())?
+        .insert(project_root.to_path_buf(), key);
+    Ok(())
+}
+
+pub fn lock_secrets_at(project_root: &Path) -> Result<(), String> {
+    unlocked_keys()
+        .lock()
+        .map_err(|_| "Unable to acquire secrets lock".to_string())?
+        .remove(project_root);
+    Ok(())
+}
+
+fn session_key(project_root: &Path) -> Result<[u8; 32], String> {
+    unlocked_keys()
+        .lock()
+        .map_err(|_| "Unable to acquire secrets lock".to_string())?
+        .get(project_root)
+        .copied()
+        .ok_or_else(|| {
+            secrets_error(
+                SECRETS_LOCKED,
+                "Project secrets are locked. Unlock them with the project passphrase first.",
+            )
+        })
+}
+
+fn validate_secret_name(name: &str) -> Result<&str, String> {
+    let name = name.trim();
+    if name.is_empty() || name == CHECK_NAME {
+        return Err("Secret name is required.".to_string());
+    }
+    Ok(name)
+}
+
+fn unlocked_file(project_root: &Path) -> Result<([u8; 32], SecretsFile), String> {
+    let key = session_key(project_root)?;
+    let file = read_secrets_file(project_root)?.ok_or_else(|| {
+        secrets_error(
+            SECRETS_LOCKED,
+            "Project secrets file is missing. Unlock the project again to recreate it.",
+        )
+    })?;
+    Ok((key, file))
+}
+
+pub fn set_secret_at(project_root: &Path, name: &str, value: &str) -> Result<(), String> {
+    let name = validate_secret_name(name)?;
+    let (key, mut file) = unlocked_file(project_root)?;
+    let sealed = seal(&key, name, value.as_bytes())?;
+    file.secrets.insert(name.to_string(), sealed);
+    write_secrets_file(project_root, &file)
+}
+
+pub fn get_secret_at(project_root: &Path, name: &str) -> Result<Option<String>, String> {
+    let name = validate_secret_name(name)?;
+    let (key, file) = unlocked_file(project_root)?;
+    let Some(sealed) = file.secrets.get(name) else {
+        return Ok(None);
+    };
+    let plaintext = open(&key, name, sealed)?.ok_or_else(|| {
+        secrets_error(
+            SECRETS_WRONG_PASSPHRASE,
+            "Secret could not be decrypted with the unlocked passphrase.",
+        )
+    })?;
+    String::from_utf8(plaintext)
+        .map(Some)
+        .map_err(|_| "Secret is not valid UTF-8.".to_string())
+}
+
+pub fn delete_secret_at(project_root: &Path, name: &str) -> Result<bool, String> {
+    let name = validate_secret_name(name)?;
+    let (_, mut file) = unlocked_file(project_root)?;
+    let removed = file.secrets.remove(name).is_some();
+    if removed {
+        write_secrets_file(project_root, &file)?;
+    }
+    Ok(removed)
+}
+
+/// Names of stored secrets; readable while locked since names are not encrypted.
+pub fn list_secret_names_at(project_root: &Path) -> Result<Vec<String>, String> {
+    Ok(read_secrets_file(project_root)?
+        .map(|file| file.secrets.into_keys().collect())
+        .unwrap_or_default())
+}
+
+pub(crate) fn project_root_for(app_root: &Path, project_id: &str) -> Result<PathBuf, String> {
+    let store = read_projects_store(app_root)?;
+    store
+        .projects
+        .iter()
+        .find(|project| project.id == project_id)
+        .map(|project| PathBuf::from(project.root_path.clone()))
+        .ok_or_else(|| "Project not found.".to_string())
+}
+
+// Secret-bearing args deliberately do not derive Debug so values cannot end up in logs.
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UnlockProjectSecretsArgs {
+    project_id: String,
+    passphrase: String,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProjectSecretArgs {
+    project_id: String,
+    name: String,
+    #[serde(default)]
+    value: Option<String>,
+}
+
+pub fn unlock_project_secrets(
+    app_root: &Path,
+    args: UnlockProjectSecretsArgs,
+) -> Result<(), String> {
+    unlock_secrets_at(
+        &project_root_for(app_root, &args.project_id)?,
+        &args.passphrase,
+    )
+}
+
+pub fn lock_project_secrets(app_root: &Path, project_id: &str) -> Result<(), String> {
+    lock_secrets_at(&project_root_for(app_root, project_id)?)
+}
+
+pub fn set_project_secret(app_root: &Path, args: ProjectSecretArgs) -> Result<(), String> {
+    let value = args
+        .value
+        .ok_or_else(|| "Secret value is required.".to_string())?;
+    set_secret_at(
+        &project_root_for(app_root, &args.project_id)?,
+        &args.name,
+        &value,
+    )
+}
+
+pub fn get_project_secret(
+    app_root: &Path,
+    args: ProjectSecretArgs,
+) -> Result<Option<String>, String> {
+    get_secret_at(&project_root_for(app_root, &args.project_id)?, &args.name)
+}
+
+pub fn delete_project_secret(app_root: &Path, args: ProjectSecretArgs) -> Result<bool, String> {
+    delete_secret_at(&project_root_for(app_root, &args.project_id)?, &args.name)
+}
+
+pub fn list_project_secrets(app_root: &Path, project_id: &str) -> Result<Vec<String>, String> {
+    list_secret_names_at(&project_root_for(app_root, project_id)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::store::files::{copy_dir_filtered, CopyFilter};
+    use crate::store::release_rules::ReleaseRules;
+    use uuid::Uuid;
+
+    fn error_code(err: &str) -> String {
+        let parsed: serde_json::Value = serde_json::from_str(err).expect("structured error");
+        parsed["code"].as_str().unwrap_or_default().to_string()
+    }
+
+    #[test]
+    fn secrets_round_trip_and_reject_wrong_passphrase() {
+        let root = std::env::temp_dir().join(format!("secrets-{}", Uuid::new_v4()));
+        fs::create_dir_all(&root).expect("project root");
+
+        let err = get_secret_at(&root, "prolific_api_key").expect_err("locked");
+        assert_eq!(error_code(&err), SECRETS_LOCKED);
+
+        unlock_secrets_at(&root, "correct horse").expect("first unlock creates file");
+        set_secret_at(&root, "prolific_api_key", "pk_live_123").expect("set");
+        assert_eq!(
+            get_secret_at(&root, "prolific_api_key").expect("get"),
+            Some("pk_live_123".to_string())
+        );
+        let stored = fs::read_to_string(secrets_path(&root)).expect("secrets file");
+        assert!(!stored.contains("pk_live_123"));
+        assert!(!stored.contains("correct horse"));
+        assert_eq!(
+            fs::read_to_string(root.join(SECRETS_DIR).join(".gitignore")).expect("gitignore"),
+            "secrets.json\n"
+        );
+
+        lock_secrets_at(&root).expect("lock");
+        let err = get_secret_at(&root, "prolific_api_key").expect_err("locked again");
+        assert_eq!(error_code(&err), SECRETS_LOCKED);
+        let err = unlock_secrets_at(&root, "wrong horse").expect_err("wrong passphrase");
+        assert_eq!(error_code(&err), SECRETS_WRONG_PASSPHRASE);
+        assert!(get_secret_at(&root, "prolific_api_key").is_err());
+
+        unlock_secrets_at(&root, "correct horse").expect("unlock");
+        assert_eq!(
+            list_secret_names_at(&root).expect("names"),
+            vec!["prolific_api_key".to_string()]
+        );
+        assert!(delete_secret_at(&root, "prolific_api_key").expect("delete"));
+        assert_eq!(get_secret_at(&root, "prolific_api_key").expect("get"), None);
+        lock_secrets_at(&root).expect("lock");
+        let _ = fs::remove_dir_all(root);
+    }
+
+    #[test]
+    fn osf_copies_leave_out_secrets_file() {
+        let base = std::env::temp_dir().join(format!("secrets-osf-{}", Uuid::new_v4()));
+        let study_root = base.join("S-ABC123");
+        fs::create_dir_all(study_root.join(SECRETS_DIR)).expect("mkdir");
+        fs::write(secrets_path(&study_root), "{}").expect("secrets");
+        fs::write(study_root.join(SECRETS_DIR).join("llm_lock.json"), "{}").expect("lock");
+        fs::write(study_root.join("notes.md"), "n").expect("notes");
+
+        let package = base.join("package");
+        copy_dir_filtered(
+            &study_root,
+            &package,
+            &CopyFilter {
+                excluded: &[],
+                include_pilots: false,
+                condensed: false,
+                rules: &ReleaseRules::default(),
+                follow_links_within: None,
+            },
+        )
+        .expect("copy");
+        assert!(package.join("notes.md").exists());
+        assert!(package.join(SECRETS_DIR).join("llm_lock.json").exists());
+        assert!(!secrets_path(&package).exists());
+        let _ = fs::remove_dir_all(base);
+    }
+}
@@ -0,0 +1,1599 @@
+use pathdiff::diff_paths;
+use rusqlite::{params, Connection, OptionalExtension, Transaction, TransactionBehavior};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use uuid::Uuid;
+
+use super::activity::record_activity;
+use super::files::{copy_dir_filtered, CopyFilter, ExcludedPath, SkippedLink};
+use super::osf_manifest::{write_package_manifest, PackageManifestSummary};
+use super::readiness::{compute_readiness, release_gate_refusal};
+use super::release_rules::{load_release_rules, ReleaseRules};
+use super::structure::ensure_study_scaffold;
+use super::{now_string, read_projects_store, rebase_path, PathRewrite};
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct DbStudy {
+    pub id: String,
+    #[serde(alias = "project_id")]
+    pub project_id: String,
+    #[serde(alias = "internal_name")]
+    pub internal_name: String,
+    #[serde(alias = "paper_label")]
+    pub paper_label: Option<String>,
+    pub status: String,
+    #[serde(alias = "folder_path")]
+    pub folder_path: String,
+    #[serde(alias = "created_at")]
+    pub created_at: String,
+    #[serde(default)]
+    #[serde(alias = "updated_at")]
+    pub updated_at: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct Artifact {
+    pub id: String,
+    #[serde(alias = "study_id")]
+    pub study_id: String,
+    pub kind: String,
+    pub value: String,
+    pub label: Option<String>,
+    #[serde(alias = "created_at")]
+    pub created_at: String,
+    #[serde(default)]
+    #[serde(alias = "was_auto_added")]
+    pub was_auto_added: bool,
+    #[serde(default)]
+    #[serde(alias = "updated_at")]
+    pub updated_at: String,
+}
+
+/// One row of `study_status_history`; `from_status` is `None` when nothing was recorded before.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct StudyStatusChange {
+    pub from_status: Option<String>,
+    pub to_status: String,
+    pub changed_at: String,
+    pub note: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct StudyDetail {
+    pub study: DbStudy,
+    pub artifacts: Vec<Artifact>,
+    /// Status transitions, oldest first.
+    #[serde(default)]
+    pub status_history: Vec<StudyStatusChange>,
+}
+
+/// Lifecycle statuses `update_study_status` accepts, in order.
+pub const STUDY_STATUSES: &[&str] = &[
+    "planning",
+    "building",
+    "piloting",
+    "collecting",
+    "analyzing",
+    "writing",
+    "released",
+];
+
+/// How long a connection waits on another connection's write lock before failing.
+const BUSY_TIMEOUT: Duration = Duration::from_secs(5);
+
+pub fn db_path(app_root: &Path) -> PathBuf {
+    app_root.join("db.sqlite3")
+}
+
+/// Opens the database in WAL mode, so reads do not block on a concurrent write, with a busy
+/// timeout and foreign keys enforced.
+pub fn connection(app_root: &Path) -> Result<Connection, String> {
+    let path = db_path(app_root);
+    let conn = Connection::open(path).map_err(|err| err.to_string())?;
+    conn.busy_timeout(BUSY_TIMEOUT)
+        .map_err(|err| err.to_string())?;
+    conn.query_row("PRAGMA journal_mode = WAL", [], |row| {
+        row.get::<_, String>(0)
+    })
+    .map_err(|err| format!("Unable to enable WAL mode: {err}"))?;
+    conn.pragma_update(None, "foreign_keys", true)
+        .map_err(|err| err.to_string())?;
+    Ok(conn)
+}
+
+/// Starts a transaction that takes the write lock up front. Read-then-write sequences need
+/// it: a deferred transaction that reads first cannot wait for the lock once it must write.
+pub(crate) fn write_transaction(conn: &Connection) -> Result<Transaction<'_>, String> {
+    Transaction::new_unchecked(conn, TransactionBehavior::Immediate).map_err(|err| err.to_string())
+}
+
+/// Schema changes applied in order on top of the base tables. Migration `n` (1-based) brings
+/// the database to version `n`, recorded in `schema_version`; only ever append to this list.
+const MIGRATIONS: &[&str] = &[
+    "ALTER TABLE studies ADD COLUMN updated_at TEXT;
+    UPDATE studies SET updated_at = created_at;
+    ALTER TABLE artifacts ADD COLUMN updated_at TEXT;
+    UPDATE artifacts SET updated_at = created_at;",
+    "CREATE INDEX IF NOT EXISTS idx_artifacts_kind ON artifacts(kind);",
+    "CREATE TABLE IF NOT EXISTS study_status_history (
+        id INTEGER PRIMARY KEY AUTOINCREMENT,
+        study_id TEXT NOT NULL,
+        from_status TEXT,
+        to_status TEXT NOT NULL,
+        changed_at TEXT NOT NULL,
+        note TEXT
+      );
+    CREATE INDEX IF NOT EXISTS idx_status_history_study ON study_status_history(study_id);",
+    // Full-text indexes over artifacts and studies, kept in step by triggers. They start empty
+    // and are filled by `store::search` on the first search.
+    "CREATE VIRTUAL TABLE IF NOT EXISTS artifacts_fts USING fts5(
+        kind, value, label, content = 'artifacts', tokenize = 'unicode61'
+      );
+    CREATE TRIGGER IF NOT EXISTS artifacts_fts_insert AFTER INSERT ON artifacts BEGIN
+      INSERT INTO artifacts_fts (rowid, kind, value, label)
+        VALUES (new.rowid, new.kind, new.value, new.label);
+    END;
+    CREATE TRIGGER IF NOT EXISTS artifacts_fts_delete AFTER DELETE ON artifacts BEGIN
+      INSERT INTO artifacts_fts (artifacts_fts, rowid, kind, value, label)
+        VALUES ('delete', old.rowid, old.kind, old.value, old.label);
+    END;
+    CREATE TRIGGER IF NOT EXISTS artifacts_fts_update AFTER UPDATE OF kind, value, label ON artifacts BEGIN
+      INSERT INTO artifacts_fts (artifacts_fts, rowid, kind, value, label)
+        VALUES ('delete', old.rowid, old.kind, old.value, old.label);
+      INSERT INTO artifacts_fts (rowid, kind, value, label)
+        VALUES (new.rowid, new.kind, new.value, new.label);
+    END;
+    CREATE VIRTUAL TABLE IF NOT EXISTS studies_fts USING fts5(
+        internal_name, paper_label, content = 'studies', tokenize = 'unicode61'
+      );
+    CREATE TRIGGER IF NOT EXISTS studies_fts_insert AFTER INSERT ON studies BEGIN
+      INSERT INTO studies_fts (rowid, internal_name, paper_label)
+        VALUES (new.rowid, new.internal_name, new.paper_label);
+    END;
+    CREATE TRIGGER IF NOT EXISTS studies_fts_delete AFTER DELETE ON studies BEGIN
+      INSERT INTO studies_fts (studies_fts, rowid, internal_name, paper_label)
+        VALUES ('delete', old.rowid, old.internal_name, old.paper_label);
+    END;
+    CREATE TRIGGER IF NOT EXISTS studies_fts_update AFTER UPDATE OF internal_name, paper_label ON studies BEGIN
+      INSERT INTO studies_fts (studies_fts, rowid, internal_name, paper_label)
+        VALUES ('delete', old.rowid, old.internal_name, old.paper_label);
+      INSERT INTO studies_fts (rowid, internal_name, paper_label)
+        VALUES (new.rowid, new.internal_name, new.paper_label);
+    END;
+    CREATE TABLE IF NOT EXISTS search_index_state (
+        name TEXT PRIMARY KEY,
+        built_at TEXT NOT NULL
+      );",
+];
+
+pub const SCHEMA_VERSION: usize = MIGRATIONS.len();
+
+pub fn schema_version(conn: &Connection) -> Result<usize, String> {
+    conn.execute_batch("CREATE TABLE IF NOT EXISTS schema_version (version INTEGER NOT NULL);")
+        .map_err(|err| err.to_string())?;
+    let version: i64 = conn
+        .query_row(
+            "SELECT COALESCE(MAX(version), 0) FROM schema_version",
+            [],
+            |row| row.get(0),
+        )
+        .map_err(|err| err.to_string())?;
+    Ok(version.max(0) as usize)
+}
+
+/// Applies pending migrations with their version bumps, inside the caller's transaction.
+/// Refuses to touch a database written by a newer app.
+fn run_migrations(conn: &Connection) -> Result<(), String> {
+    let current = schema_version(conn)?;
+    if current > SCHEMA_VERSION {
+        return Err(format!(
+            "The database schema (version {current}) is newer than this app supports (version {SCHEMA_VERSION}). Update the app to open it."
+        ));
+    }
+    for (index, migration) in MIGRATIONS.iter().enumerate().skip(current) {
+        let version = index + 1;
+        conn.execute_batch(migration)
+            .map_err(|err| format!("Database migration {version} failed: {err}"))?;
+        conn.execute(
+            "INSERT INTO schema_version (version) VALUES (?1)",
+            params![version as i64],
+        )
+        .map_err(|err| err.to_string())?;
+    }
+    Ok(())
+}
+
+/// Creates the base tables and applies pending migrations in one write transaction, so
+/// concurrent callers cannot both apply the same migration. Returns without locking when
+/// the schema is already current.
+pub fn init_schema(conn: &Connection) -> Result<(), String> {
+    if schema_version(conn)? == SCHEMA_VERSION {
+        return Ok(());
+    }
+    let tx = write_transaction(conn)?;
+    tx.execute_batch(
+        "CREATE TABLE IF NOT EXISTS projects (
+        id TEXT PRIMARY KEY,
+        name TEXT NOT NULL,
+        root_path TEXT NOT NULL,
+        created_at TEXT NOT NULL
+      );
+      CREATE TABLE IF NOT EXISTS studies (
+        id TEXT PRIMARY KEY,
+        project_id TEXT NOT NULL,
+        internal_name TEXT NOT NULL,
+        paper_label TEXT,
+        status TEXT NOT NULL,
+        folder_path TEXT NOT NULL,
+        created_at TEXT NOT NULL,
+        FOREIGN KEY(project_id) REFERENCES projects(id)
+      );
+      CREATE INDEX IF NOT EXISTS idx_studies_project ON studies(project_id);
+      CREATE TABLE IF NOT EXISTS artifacts (
+        id TEXT PRIMARY KEY,
+        study_id TEXT NOT NULL,
+        kind TEXT NOT NULL,
+        value TEXT NOT NULL,
+        label TEXT,
+        created_at TEXT NOT NULL,
+        FOREIGN KEY(study_id) REFERENCES studies(id)
+      );
+      CREATE INDEX IF NOT EXISTS idx_artifacts_study ON artifacts(study_id);",
+    )
+    .map_err(|err| err.to_string())?;
+    if !has_column(&tx, "artifacts", "was_auto_added")? {
+        tx.execute(
+            "ALTER TABLE artifacts ADD COLUMN was_auto_added INTEGER NOT NULL DEFAULT 0",
+            [],
+        )
+        .map_err(|err| err.to_string())?;
+    }
+    run_migrations(&tx)?;
+    tx.commit().map_err(|err| err.to_string())
+}
+
+fn has_column(conn: &Connection, table: &str, column: &str) -> Result<bool, String> {
+    let mut stmt = conn
+        .prepare(&format!("PRAGMA table_info({table})"))
+        .map_err(|err| err.to_string())?;
+    let names = stmt
+        .query_map([], |row| row.get::<_, String>(1))
+        .map_err(|err| err.to_string())?;
+    for name in names {
+        if name.map_err(|err| err.to_string())? == column {
+            return Ok(true);
+        }
+    }
+    Ok(false)
+}
+
+/// Upserts an automatically generated output into the artifacts table, keyed by study and
+/// project-relative path. Does nothing when there is no database or the study is not in it.
+pub fn record_generated_artifact(
+    app_root: &Path,
+    study_id: &str,
+    kind: &str,
+    path: &Path,
+    label: Option<&str>,
+) -> Result<(), String> {
+    if !db_path(app_root).exists() {
+        return Ok(());
+    }
+    let conn = connection(app_root)?;
+    init_schema(&conn)?;
+    let tx = write_transaction(&conn)?;
+
+    let project_root: Option<Option<String>> = tx
+        .query_row(
+            "SELECT projects.root_path FROM studies \
+      LEFT JOIN projects ON projects.id = studies.project_id WHERE studies.id = ?1",
+            params![study_id],
+            |row| row.get(0),
+        )
+        .optional()
+        .map_err(|err| err.to_string())?;
+    let Some(project_root) = project_root else {
+        return Ok(());
+    };
+
+    let relative = project_root
+        .and_then(|root| diff_paths(path, root))
+        .unwrap_or_else(|| path.to_path_buf());
+    let value = relative.to_string_lossy().replace('\\', "/");
+
+    let updated = tx
+        .execute(
+            "UPDATE artifacts SET kind = ?1, label = COALESCE(?2, label), created_at = ?3, \
+      updated_at = ?3 WHERE study_id = ?4 AND value = ?5",
+            params![kind, label, now_string(), study_id, value],
+        )
+        .map_err(|err| err.to_string())?;
+    if updated == 0 {
+        tx.execute(
+            "INSERT INTO artifacts (id, study_id, kind, value, label, created_at, updated_at, \
+      was_auto_added) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?6, 1)",
+            params![
+                Uuid::new_v4().to_string(),
+                study_id,
+                kind,
+                value,
+                label,
+                now_string()
+            ],
+        )
+        .map_err(|err| err.to_string())?;
+    }
+    tx.commit().map_err(|err| err.to_string())
+}
+
+/// Best-effort variant of [`record_generated_artifact`] for generation paths, where a
+/// bookkeeping failure should not fail the output that was already written.
+pub fn track_generated_artifact(
+    app_root: &Path,
+    study_id: &str,
+    kind: &str,
+    path: &Path,
+    label: Option<&str>,
+) {
+    if let Err(err) = record_generated_artifact(app_root, study_id, kind, path, label) {
+        println!(
+            "artifacts: unable to record {} for study {}: {}",
+            path.to_string_lossy(),
+            study_id,
+            err
+        );
+    }
+}
+
+/// Rewrites the sqlite rows of a project that moved from `old_root` to `new_root`: the project
+/// root, study folder paths, and absolute artifact paths under the old root. Relative artifact
+/// paths already follow the project. On a dry run the planned rewrites are returned unapplied.
+pub fn relocate_project_rows(
+    app_root: &Path,
+    project_id: &str,
+    old_root: &Path,
+    new_root: &Path,
+    dry_run: bool,
+) -> Result<Vec<PathRewrite>, String> {
+    if !db_path(app_root).exists() {
+        return Ok(Vec::new());
+    }
+    let mut conn = connection(app_root)?;
+    init_schema(&conn)?;
+
+    let mut rewrites = Vec::new();
+    {
+        let mut stmt = conn
+            .prepare("SELECT id, folder_path FROM studies WHERE project_id = ?1")
+            .map_err(|err| err.to_string())?;
+        let rows = stmt
+            .query_map(params![project_id], |row| {
+                Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?))
+            })
+            .map_err(|err| err.to_string())?;
+        for row in rows {
+            let (id, folder_path) = row.map_err(|err| err.to_string())?;
+            if let Some(to) = rebase_path(Path::new(&folder_path), old_root, new_root) {
+                rewrites.push(PathRewrite {
+                    kind: "sqlite_study".to_string(),
+                    id,
+                    from: folder_path,
+                    to: to.to_string_lossy().to_string(),
+                });
+            }
+        }
+
+        let mut stmt = conn
+            .prepare(
+                "SELECT artifacts.id, artifacts.value FROM artifacts \
+      JOIN studies ON studies.id = artifacts.study_id WHERE studies.project_id = ?1",
+            )
+            .map_err(|err| err.to_string())?;
+        let rows = stmt
+            .query_map(params![project_id], |row| {
+                Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?))
+            })
+            .map_err(|err| err.to_string())?;
+        for row in rows {
+            let (id, value) = row.map_err(|err| err.to_string())?;
+            if let Some(to) = rebase_path(Path::new(&value), old_root, new_root) {
+                rewrites.push(PathRewrite {
+                    kind: "artifact".to_string(),
+                    id,
+                    from: value,
+                    to: to.to_string_lossy().to_string(),
+                });
+            }
+        }
+    }
+    if dry_run {
+        return Ok(rewrites);
+    }
+
+    let tx = conn
+        .transaction_with_behavior(TransactionBehavior::Immediate)
+        .map_err(|err| err.to_string())?;
+    tx.execute(
+        "UPDATE projects SET root_path = ?1 WHERE id = ?2",
+        params![new_root.to_string_lossy().to_string(), project_id],
+    )
+    .map_err(|err| err.to_string())?;
+    for rewrite in &rewrites {
+        let sql = if rewrite.kind == "artifact" {
+            "UPDATE artifacts SET value = ?1 WHERE id = ?2"
+        } else {
+            "UPDATE studies SET folder_path = ?1 WHERE id = ?2"
+        };
+        tx.execute(sql, params![rewrite.to, rewrite.id])
+            .map_err(|err| err.to_string())?;
+    }
+    tx.commit().map_err(|err| err.to_string())?;
+    Ok(rewrites)
+}
+
+/// Rewrites artifact values, absolute or project-relative, that point under a study folder
+/// renamed from `old_root` to `new_root`. Returns how many rows changed.
+pub fn rebase_study_artifacts(
+    app_root: &Path,
+    project_root: &Path,
+    old_root: &Path,
+    new_root: &Path,
+) -> Result<usize, String> {
+    if !db_path(app_root).exists() {
+        return Ok(0);
+    }
+    let mut conn = connection(app_root)?;
+    init_schema(&conn)?;
+    let old_relative = diff_paths(old_root, project_root);
+    let new_relative = diff_paths(new_root, project_root);
+
+    let mut rewrites = Vec::new();
+    {
+        let mut stmt = conn
+            .prepare("SELECT id, value FROM artifacts")
+            .map_err(|err| err.to_string())?;
+        let rows = stmt
+            .query_map([], |row| {
+                Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?))
+            })
+            .map_err(|err| err.to_string())?;
+        for row in rows {
+            let (id, value) = row.map_err(|err| err.to_string())?;
+            let path = Path::new(&value);
+            let rebased = if path.is_absolute() {
+                rebase_path(path, old_root, new_root).map(|p| p.to_string_lossy().to_string())
+            } else if let (Some(old), Some(new)) = (&old_relative, &new_relative) {
+                rebase_path(path, old, new).map(|p| p.to_string_lossy().replace('\\', "/"))
+            } else {
+                None
+            };
+            if let Some(to) = rebased {
+                rewrites.push((id, to));
+            }
+        }
+    }
+
+    let tx = conn
+        .transaction_with_behavior(TransactionBehavior::Immediate)
+        .map_err(|err| err.to_string())?;
+    for (id, to) in &rewrites {
+        tx.execute(
+            "UPDATE artifacts SET value = ?1 WHERE id = ?2",
+            params![to, id],
+        )
+        .map_err(|err| err.to_string())?;
+    }
+    tx.commit().map_err(|err| err.to_string())?;
+    Ok(rewrites.len())
+}
+
+pub fn init_db(app_root: &Path) -> Result<(), String> {
+    let conn = connection(app_root)?;
+    init_schema(&conn)?;
+    Ok(())
+}
+
+pub fn migrate_json_to_sqlite(app_root: &Path) -> Result<String, String> {
+    let conn = connection(app_root)?;
+    init_schema(&conn)?;
+    let store = read_projects_store(app_root)?;
+    let tx = write_transaction(&conn)?;
+
+    let mut projects_added = 0;
+    let mut studies_added = 0;
+
+    for project in store.projects {
+        let project_id = project.id.clone();
+        let project_name = project.name.clone();
+        let project_root = project.root_path.clone();
+        let project_created = project.created_at.clone();
+        let exists: i64 = tx
+            .query_row(
+                "SELECT COUNT(1) FROM projects WHERE id = ?1",
+                params![&project_id],
+                |row| row.get(0),
+            )
+            .map_err(|err| err.to_string())?;
+
+        if exists == 0 {
+            tx.execute(
+                "INSERT INTO projects (id, name, root_path, created_at) VALUES (?1, ?2, ?3, ?4)",
+                params![&project_id, &project_name, &project_root, &project_created],
+            )
+            .map_err(|err| err.to_string())?;
+            projects_added += 1;
+        }
+
+        for study in project.studies {
+            let study_exists: i64 = tx
+                .query_row(
+                    "SELECT COUNT(1) FROM studies WHERE id = ?1",
+                    params![study.id],
+                    |row| row.get(0),
+                )
+                .map_err(|err| err.to_string())?;
+            if study_exists > 0 {
+                continue;
+            }
+
+            let folder_path = if !study.folder_path.trim().is_empty() {
+                study.folder_path
+            } else {
+                PathBuf::from(project_root.clone())
+                    .join("studies")
+                    .join(&study.id)
+                    .to_string_lossy()
+                    .to_string()
+            };
+
+            tx
+        .execute(
+          "INSERT INTO studies (id, project_id, internal_name, paper_label, status, folder_path, created_at, updated_at) \
+          VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?7)",
+          params![
+            study.id,
+            &project_id,
+            study.title,
+            Option::<String>::None,
+            "planning",
+            folder_path,
+            study.created_at
+          ]
+        )
+        .map_err(|err| err.to_string())?;
+            studies_added += 1;
+        }
+    }
+
+    tx.commit().map_err(|err| err.to_string())?;
+    Ok(format!(
+        "Migration complete. Projects added: {projects_added}. Studies added: {studies_added}."
+    ))
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ListStudiesArgs {
+    project_id: String,
+}
+
+pub fn list_studies(app_root: &Path, args: ListStudiesArgs) -> Result<Vec<DbStudy>, String> {
+    let conn = connection(app_root)?;
+    init_schema(&conn)?;
+    let mut stmt = conn
+        .prepare(
+            "SELECT id, project_id, internal_name, paper_label, status, folder_path, created_at, \
+      COALESCE(updated_at, created_at) FROM studies WHERE project_id = ?1 ORDER BY created_at DESC",
+        )
+        .map_err(|err| err.to_string())?;
+    let rows = stmt
+        .query_map(params![args.project_id], |row| {
+            Ok(DbStudy {
+                id: row.get(0)?,
+                project_id: row.get(1)?,
+                internal_name: row.get(2)?,
+                paper_label: row.get(3)?,
+                status: row.get(4)?,
+                folder_path: row.get(5)?,
+                created_at: row.get(6)?,
+                updated_at: row.get(7)?,
+            })
+        })
+        .map_err(|err| err.to_string())?;
+
+    let mut studies: Vec<DbStudy> = Vec::new();
+    for row in rows {
+        studies.push(row.map_err(|err| err.to_string())?);
+    }
+    Ok(studies)
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CreateStudyArgs {
+    project_id: String,
+    internal_name: String,
+    paper_label: Option<String>,
+}
+
+pub fn create_study(app_root: &Path, args: CreateStudyArgs) -> Result<DbStudy, String> {
+    let conn = connection(app_root)?;
+    init_schema(&conn)?;
+
+    let store = read_projects_store(app_root)?;
+    let project = store
+        .projects
+        .iter()
+        .find(|project| project.id == args.project_id)
+        .ok_or_else(|| "Project not found.".to_string())?;
+
+    let id = Uuid::new_v4().to_string();
+    let folder = PathBuf::from(&project.root_path).join("studies").join(&id);
+    ensure_study_scaffold(Path::new(&project.root_path), &folder)?;
+
+    let created_at = now_string();
+    let study = DbStudy {
+        id: id.clone(),
+        project_id: args.project_id,
+        internal_name: args.internal_name,
+        paper_label: args.paper_label,
+        status: "planning".to_string(),
+        folder_path: folder.to_string_lossy().to_string(),
+        created_at: created_at.clone(),
+        updated_at: created_at,
+    };
+
+    let tx = write_transaction(&conn)?;
+    // Projects reach the database through the JSON migration; mirror this one if it has not,
+    // so the study's foreign key holds.
+    tx.execute(
+        "INSERT OR IGNORE INTO projects (id, name, root_path, created_at) VALUES (?1, ?2, ?3, ?4)",
+        params![
+            project.id,
+            project.name,
+            project.root_path,
+            project.created_at
+        ],
+    )
+    .map_err(|err| err.to_string())?;
+    tx
+    .execute(
+      "INSERT INTO studies (id, project_id, internal_name, paper_label, status, folder_path, created_at, updated_at) \
+      VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+      params![
+        study.id,
+        study.project_id,
+        study.internal_name,
+        study.paper_label,
+        study.status,
+        study.folder_path,
+        study.created_at,
+        study.updated_at
+      ]
+    )
+    .map_err(|err| err.to_string())?;
+    tx.commit().map_err(|err| err.to_string())?;
+
+    record_activity(
+        app_root,
+        "create_study",
+        Some(&study.project_id),
+        Some(&study.id),
+        &format!("Created study {}", study.internal_name),
+    );
+    Ok(study)
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RenameStudyArgs {
+    study_id: String,
+    internal_name: String,
+    paper_label: Option<String>,
+}
+
+pub fn rename_study(app_root: &Path, args: RenameStudyArgs) -> Result<(), String> {
+    let conn = connection(app_root)?;
+    init_schema(&conn)?;
+    conn.execute(
+        "UPDATE studies SET internal_name = ?1, paper_label = ?2, updated_at = ?3 WHERE id = ?4",
+        params![
+            args.internal_name,
+            args.paper_label,
+            now_string(),
+            args.study_id
+        ],
+    )
+    .map_err(|err| err.to_string())?;
+    Ok(())
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UpdateStudyStatusArgs {
+    study_id: String,
+    status: String,
+    /// Recorded with the transition in the status history.
+    #[serde(default)]
+    note: Option<String>,
+}
+
+pub fn list_study_statuses() -> Vec<String> {
+    STUDY_STATUSES
+        .iter()
+        .map(|status| status.to_string())
+        .collect()
+}
+
+/// Sets the study's status and logs the transition in `study_status_history` within the same
+/// transaction. Setting the current status again changes nothing.
+pub fn update_study_status(app_root: &Path, args: UpdateStudyStatusArgs) -> Result<(), String> {
+    let status = args.status.trim();
+    if !STUDY_STATUSES.contains(&status) {
+        return Err(format!(
+            "Unknown study status '{status}'. Use one of: {}.",
+            STUDY_STATUSES.join(", ")
+        ));
+    }
+    let conn = connection(app_root)?;
+    init_schema(&conn)?;
+    let tx = write_transaction(&conn)?;
+    let current: String = tx
+        .query_row(
+            "SELECT status FROM studies WHERE id = ?1",
+            params![args.study_id],
+            |row| row.get(0),
+        )
+        .optional()
+        .map_err(|err| err.to_string())?
+        .ok_or_else(|| "Study not found.".to_string())?;
+    if current == status {
+        return Ok(());
+    }
+    let now = now_string();
+    tx.execute(
+        "UPDATE studies SET status = ?1, updated_at = ?2 WHERE id = ?3",
+        params![status, now, args.study_id],
+    )
+    .map_err(|err| err.to_string())?;
+    let note = args
+        .note
+        .as_deref()
+        .map(str::trim)
+        .filter(|note| !note.is_empty());
+    tx.execute(
+        "INSERT INTO study_status_history (study_id, from_status, to_status, changed_at, note) \
+      VALUES (?1, ?2, ?3, ?4, ?5)",
+        params![args.study_id, current, status, now, note],
+    )
+    .map_err(|err| err.to_string())?;
+    tx.commit().map_err(|err| err.to_string())
+}
+
+fn study_status_history(
+    conn: &Connection,
+    study_id: &str,
+) -> Result<Vec<StudyStatusChange>, String> {
+    let mut stmt = conn
+        .prepare(
+            "SELECT from_status, to_status, changed_at, note FROM study_status_history \
+      WHERE study_id = ?1 ORDER BY changed_at, id",
+        )
+        .map_err(|err| err.to_string())?;
+    let rows = stmt
+        .query_map(params![study_id], |row| {
+            Ok(StudyStatusChange {
+                from_status: row.get(0)?,
+                to_status: row.get(1)?,
+                changed_at: row.get(2)?,
+                note: row.get(3)?,
+            })
+        })
+        .map_err(|err| err.to_string())?;
+    rows.map(|row| row.map_err(|err| err.to_string())).collect()
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GetStudyDetailArgs {
+    study_id: String,
+}
+
+pub fn get_study_detail(app_root: &Path, args: GetStudyDetailArgs) -> Result<StudyDetail, String> {
+    let conn = connection(app_root)?;
+    init_schema(&conn)?;
+
+    let study: DbStudy = conn
+        .query_row(
+            "SELECT id, project_id, internal_name, paper_label, status, folder_path, created_at, \
+      COALESCE(updated_at, created_at) FROM studies WHERE id = ?1",
+            params![args.study_id],
+            |row| {
+                Ok(DbStudy {
+                    id: row.get(0)?,
+                    project_id: row.get(1)?,
+                    internal_name: row.get(2)?,
+                    paper_label: row.get(3)?,
+                    status: row.get(4)?,
+                    folder_path: row.get(5)?,
+                    created_at: row.get(6)?,
+                    updated_at: row.get(7)?,
+                })
+            },
+        )
+        .map_err(|err| err.to_string())?;
+
+    let mut stmt = conn
+        .prepare(
+            "SELECT id, study_id, kind, value, label, created_at, was_auto_added, \
+      COALESCE(updated_at, created_at) FROM artifacts WHERE study_id = ?1 ORDER BY created_at DESC",
+        )
+        .map_err(|err| err.to_string())?;
+
+    let rows = stmt
+        .query_map(params![args.study_id], |row| {
+            Ok(Artifact {
+                id: row.get(0)?,
+                study_id: row.get(1)?,
+                kind: row.get(2)?,
+                value: row.get(3)?,
+                label: row.get(4)?,
+                created_at: row.get(5)?,
+                was_auto_added: row.get(6)?,
+                updated_at: row.get(7)?,
+            })
+        })
+        .map_err(|err| err.to_string())?;
+
+    let mut artifacts = Vec::new();
+    for row in rows {
+        artifacts.push(row.map_err(|err| err.to_string())?);
+    }
+
+    let status_history = study_status_history(&conn, &args.study_id)?;
+    Ok(StudyDetail {
+        study,
+        artifacts,
+        status_history,
+    })
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AddArtifactArgs {
+    study_id: String,
+    kind: String,
+    value: String,
+    label: Option<String>,
+}
+
+pub fn add_artifact(app_root: &Path, args: AddArtifactArgs) -> Result<(), String> {
+    let conn = connection(app_root)?;
+    init_schema(&conn)?;
+    let id = Uuid::new_v4().to_string();
+    conn.execute(
+        "INSERT INTO artifacts (id, study_id, kind, value, label, created_at, updated_at) \
+      VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?6)",
+        params![
+            id,
+            args.study_id,
+            args.kind,
+            args.value,
+            args.label,
+            now_string()
+        ],
+    )
+    .map_err(|err| err.to_string())?;
+    Ok(())
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RemoveArtifactArgs {
+    artifact_id: String,
+}
+
+pub fn remove_artifact(app_root: &Path, args: RemoveArtifactArgs) -> Result<(), String> {
+    let conn = connection(app_root)?;
+    init_schema(&conn)?;
+    conn.execute(
+        "DELETE FROM artifacts WHERE id = ?1",
+        params![args.artifact_id],
+    )
+    .map_err(|err| err.to_string())?;
+    Ok(())
+}
+
+pub const OSF_RELEASE_FOLDER: &str = "08_osf_release";
+
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum PackageKind {
+    Complete,
+    Condensed,
+}
+
+impl PackageKind {
+    fn default_folder(self) -> &'static str {
+        match self {
+            PackageKind::Complete => "COMPLETE",
+            PackageKind::Condensed => "CONDENSED",
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct PackageRequest {
+    kind: PackageKind,
+    #[serde(default)]
+    folder_name: Option<String>,
+}
+
+fn default_package_requests() -> Vec<PackageRequest> {
+    vec![
+        PackageRequest {
+            kind: PackageKind::Complete,
+            folder_name: None,
+        },
+        PackageRequest {
+            kind: PackageKind::Condensed,
+            folder_name: None,
+        },
+    ]
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GenerateOsfPackagesArgs {
+    study_id: String,
+    include_pilots: bool,
+    #[serde(default = "default_package_requests")]
+    packages: Vec<PackageRequest>,
+    /// Copy symlinks that resolve inside the study folder instead of skipping them.
+    #[serde(default)]
+    follow_internal_links: bool,
+    /// Refuse to generate when items of the study's release gate fail.
+    #[serde(default)]
+    enforce_release_gate: bool,
+    /// Generate despite a failing release gate.
+    #[serde(default)]
+    force: bool,
+}
+
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct OsfPackageResult {
+    pub kind: String,
+    pub folder_name: String,
+    pub path: String,
+    pub files: u64,
+    pub skipped_links: Vec<SkippedLink>,
+    /// Paths the release rules left out, as listed in the package README.
+    pub excluded: Vec<ExcludedPath>,
+    /// Totals from the package's MANIFEST.json.
+    pub manifest: PackageManifestSummary,
+}
+
+fn package_folder_name(request: &PackageRequest) -> Result<String, String> {
+    let name = request
+        .folder_name
+        .as_deref()
+        .map(str::trim)
+        .filter(|name| !name.is_empty())
+        .unwrap_or(request.kind.default_folder());
+    let single_component = Path::new(name).components().count() == 1
+        && !name.contains(['/', '\\', ':'])
+        && name != "."
+        && name != "..";
+    if !single_component {
+        return Err(format!(
+            "OSF package folder name must be a single folder name: '{}'.",
+            name
+        ));
+    }
+    Ok(name.to_string())
+}
+
+/// Rebuilds the requested OSF packages inside the study's release folder. Only the requested
+/// package folders are replaced; anything else already in the release folder is left alone.
+pub fn generate_osf_packages(
+    app_root: &Path,
+    args: GenerateOsfPackagesArgs,
+) -> Result<Vec<OsfPackageResult>, String> {
+    if args.packages.is_empty() {
+        return Err("Select at least one OSF package to generate.".to_string());
+    }
+    let mut targets: Vec<(PackageKind, String)> = Vec::new();
+    for request in &args.packages {
+        let folder_name = package_folder_name(request)?;
+        if targets
+            .iter()
+            .any(|(_, existing)| existing.eq_ignore_ascii_case(&folder_name))
+        {
+            return Err(format!(
+                "OSF package folder '{}' was requested more than once.",
+                folder_name
+            ));
+        }
+        targets.push((request.kind, folder_name));
+    }
+
+    let conn = connection(app_root)?;
+    init_schema(&conn)?;
+
+    let (folder_path, project_id, project_root): (String, String, Option<String>) = conn
+        .query_row(
+            "SELECT s.folder_path, s.project_id, p.root_path FROM studies s \
+      LEFT JOIN projects p ON p.id = s.project_id WHERE s.id = ?1",
+            params![args.study_id],
+            |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+        )
+        .map_err(|err| err.to_string())?;
+    drop(conn);
+
+    let study_root = PathBuf::from(folder_path);
+    if !study_root.exists() {
+        return Err("Study folder does not exist".to_string());
+    }
+    if args.enforce_release_gate && !args.force {
+        let project_root = project_root
+            .as_deref()
+            .map(PathBuf::from)
+            .unwrap_or_default();
+        let readiness = compute_readiness(app_root, &project_root, &study_root, &args.study_id)?;
+        if let Some(refusal) = release_gate_refusal(&readiness) {
+            return Err(refusal);
+        }
+    }
+
+    let rules = match &project_root {
+        Some(root) => load_release_rules(Path::new(root))?,
+        None => ReleaseRules::default(),
+    };
+    let osf_root = study_root.join(OSF_RELEASE_FOLDER);
+    let mut excluded = vec![osf_root.clone()];
+    excluded.extend(targets.iter().map(|(_, name)| osf_root.join(name)));
+
+    let mut results = Vec::new();
+    for (kind, folder_name) in targets {
+        let package_root = osf_root.join(&folder_name);
+        if package_root.exists() {
+            fs::remove_dir_all(&package_root).map_err(|err| err.to_string())?;
+        }
+        let summary = copy_dir_filtered(
+            &study_root,
+            &package_root,
+            &CopyFilter {
+                excluded: &excluded,
+                include_pilots: args.include_pilots,
+                condensed: kind == PackageKind::Condensed,
+                rules: &rules,
+                follow_links_within: args.follow_internal_links.then_some(study_root.as_path()),
+            },
+        )?;
+        let kind_label = kind.default_folder();
+        let manifest = write_package_manifest(
+            &package_root,
+            &study_root,
+            &args.study_id,
+            &kind_label.to_lowercase(),
+            args.include_pilots,
+            &summary.excluded,
+        )?;
+        track_generated_artifact(
+            app_root,
+            &args.study_id,
+            "osf_package",
+            &package_root,
+            Some(&format!("OSF package ({kind_label})")),
+        );
+        results.push(OsfPackageResult {
+            kind: kind_label.to_lowercase(),
+            folder_name,
+            path: package_root.to_string_lossy().to_string(),
+            files: summary.files,
+            skipped_links: summary.skipped_links,
+            excluded: summary.excluded,
+            manifest,
+        });
+    }
+    record_activity(
+        app_root,
+        "generate_osf_packages",
+        Some(&project_id),
+        Some(&args.study_id),
+        &format!(
+            "Built {} OSF package(s): {}",
+            results.len(),
+            results
+                .iter()
+                .map(|result| result.folder_name.as_str())
+                .collect::<Vec<&str>>()
+                .join(", ")
+        ),
+    );
+    Ok(results)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn seeded_app_root(prefix: &str) -> (PathBuf, PathBuf) {
+        let base = std::env::temp_dir().join(format!("{prefix}-{}", Uuid::new_v4()));
+        let app_root = base.join("app");
+        let project_root = base.join("project");
+        fs::create_dir_all(&app_root).expect("failed to create app root");
+        fs::create_dir_all(project_root.join("studies").join("S-ABC123"))
+            .expect("failed to create study folder");
+        let conn = connection(&app_root).expect("db should open");
+        init_schema(&conn).expect("schema should apply");
+        conn.execute(
+            "INSERT INTO projects (id, name, root_path, created_at) VALUES ('p1', 'P', ?1, ?2)",
+            params![project_root.to_string_lossy().to_string(), now_string()],
+        )
+        .expect("failed to seed project");
+        conn.execute(
+            "INSERT INTO studies (id, project_id, internal_name, paper_label, status, folder_path, created_at) \
+      VALUES ('S-ABC123', 'p1', 'Study', NULL, 'planning', ?1, ?2)",
+            params![
+                project_root
+                    .join("studies")
+                    .join("S-ABC123")
+                    .to_string_lossy()
+                    .to_string(),
+                now_string()
+            ],
+        )
+        .expect("failed to seed study");
+        (base, project_root)
+    }
+
+    fn artifact_rows(app_root: &Path) -> Vec<(String, String, String, bool)> {
+        let conn = connection(app_root).expect("db should open");
+        let mut stmt = conn
+            .prepare("SELECT kind, value, created_at, was_auto_added FROM artifacts ORDER BY value")
+            .expect("query should prepare");
+        let rows = stmt
+            .query_map([], |row| {
+                Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?))
+            })
+            .expect("query should run");
+        rows.map(|row| row.expect("row should decode")).collect()
+    }
+
+    #[test]
+    fn generated_artifacts_upsert_by_relative_path() {
+        let (base, project_root) = seeded_app_root("artifacts-upsert");
+        let app_root = base.join("app");
+        let rmd = project_root
+            .join("studies")
+            .join("S-ABC123")
+            .join("06_analysis")
+            .join("analysis.Rmd");
+
+        record_generated_artifact(&app_root, "S-ABC123", "analysis_template", &rmd, None)
+            .expect("first record should succeed");
+        let first = artifact_rows(&app_root);
+        assert_eq!(first.len(), 1);
+        assert_eq!(first[0].0, "analysis_template");
+        assert_eq!(first[0].1, "studies/S-ABC123/06_analysis/analysis.Rmd");
+        assert!(first[0].3);
+
+        std::thread::sleep(std::time::Duration::from_millis(5));
+        record_generated_artifact(&app_root, "S-ABC123", "analysis_template", &rmd, None)
+            .expect("second record should succeed");
+        let second = artifact_rows(&app_root);
+        assert_eq!(second.len(), 1);
+        assert!(second[0].2 > first[0].2);
+
+        let detail = get_study_detail(
+            &app_root,
+            GetStudyDetailArgs {
+                study_id: "S-ABC123".to_string(),
+            },
+        )
+        .expect("detail should load");
+        assert!(detail.artifacts[0].was_auto_added);
+        let _ = fs::remove_dir_all(base);
+    }
+
+    #[test]
+    fn generated_artifacts_skip_unknown_studies_and_missing_db() {
+        let (base, project_root) = seeded_app_root("artifacts-skip");
+        let app_root = base.join("app");
+        record_generated_artifact(
+            &app_root,
+            "S-ZZZ999",
+            "osf_package",
+            &project_root.join("studies").join("S-ZZZ999"),
+            None,
+        )
+        .expect("unknown study should be skipped");
+        assert!(artifact_rows(&app_root).is_empty());
+
+        let empty_root = base.join("empty");
+        fs::create_dir_all(&empty_root).expect("failed to create empty app root");
+        record_generated_artifact(&empty_root, "S-ABC123", "osf_package", &project_root, None)
+            .expect("missing db should be skipped");
+        assert!(!db_path(&empty_root).exists());
+        let _ = fs::remove_dir_all(base);
+    }
+
+    #[test]
+    fn init_schema_adds_auto_flag_to_existing_artifacts_table() {
+        let base = std::env::temp_dir().join(format!("artifacts-migrate-{}", Uuid::new_v4()));
+        fs::create_dir_all(&base).expect("failed to create app root");
+        let conn = connection(&base).expect("db should open");
+        conn.execute_batch(
+            "CREATE TABLE artifacts (id TEXT PRIMARY KEY, study_id TEXT NOT NULL, kind TEXT NOT NULL, \
+      value TEXT NOT NULL, label TEXT, created_at TEXT NOT NULL);
+      INSERT INTO artifacts VALUES ('a1', 's1', 'url', 'https://osf.io', NULL, '2024-01-01');",
+        )
+        .expect("failed to seed legacy table");
+        init_schema(&conn).expect("schema should migrate");
+        init_schema(&conn).expect("schema migration should be idempotent");
+        let flag: bool = conn
+            .query_row(
+                "SELECT was_auto_added FROM artifacts WHERE id = 'a1'",
+                [],
+                |row| row.get(0),
+            )
+            .expect("column should exist");
+        assert!(!flag);
+        let _ = fs::remove_dir_all(base);
+    }
+
+    #[test]
+    fn migrations_upgrade_existing_databases_and_refuse_newer_ones() {
+        let base = std::env::temp_dir().join(format!("schema-migrate-{}", Uuid::new_v4()));
+        fs::create_dir_all(&base).expect("failed to create app root");
+        let conn = connection(&base).expect("db should open");
+        conn.execute_batch(
+            "CREATE TABLE projects (id TEXT PRIMARY KEY, name TEXT NOT NULL, root_path TEXT NOT NULL, \
+      created_at TEXT NOT NULL);
+      CREATE TABLE studies (id TEXT PRIMARY KEY, project_id TEXT NOT NULL, internal_name TEXT NOT NULL, \
+      paper_label TEXT, status TEXT NOT NULL, folder_path TEXT NOT NULL, created_at TEXT NOT NULL);
+      CREATE TABLE artifacts (id TEXT PRIMARY KEY, study_id TEXT NOT NULL, kind TEXT NOT NULL, \
+      value TEXT NOT NULL, label TEXT, created_at TEXT NOT NULL, was_auto_added INTEGER NOT NULL DEFAULT 0);
+      INSERT INTO studies VALUES ('s1', 'p1', 'Study', NULL, 'planning', '/tmp/s1', '2024-01-01');
+      INSERT INTO artifacts VALUES ('a1', 's1', 'url', 'https://osf.io', NULL, '2024-02-01', 0);",
+        )
+        .expect("failed to seed current schema");
+        init_schema(&conn).expect("migrations should apply");
+        init_schema(&conn).expect("migrations should be idempotent");
+        assert_eq!(schema_version(&conn).expect("version"), SCHEMA_VERSION);
+        let backfilled: (String, String) = conn
+            .query_row(
+                "SELECT studies.updated_at, artifacts.updated_at FROM studies, artifacts",
+                [],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .expect("updated_at columns should exist");
+        assert_eq!(
+            backfilled,
+            ("2024-01-01".to_string(), "2024-02-01".to_string())
+        );
+        let kind_index: i64 = conn
+            .query_row(
+                "SELECT COUNT(1) FROM sqlite_master WHERE type = 'index' AND name = 'idx_artifacts_kind'",
+                [],
+                |row| row.get(0),
+            )
+            .expect("index lookup");
+        assert_eq!(kind_index, 1);
+        let history_rows: i64 = conn
+            .query_row("SELECT COUNT(1) FROM study_status_history", [], |row| {
+                row.get(0)
+            })
+            .expect("status history table should exist");
+        assert_eq!(history_rows, 0);
+
+        conn.execute(
+            "INSERT INTO schema_version (version) VALUES (?1)",
+            params![SCHEMA_VERSION as i64 + 1],
+        )
+        .expect("bump version");
+        assert!(init_schema(&conn)
+            .expect_err("newer schema")
+            .contains("newer than this app supports"));
+        let _ = fs::remove_dir_all(base);
+    }
+
+    #[test]
+    fn status_updates_are_validated_and_logged_in_order() {
+        let (base, _) = seeded_app_root("status-history");
+        let app_root = base.join("app");
+        let update = |status: &str, note: Option<&str>| {
+            update_study_status(
+                &app_root,
+                UpdateStudyStatusArgs {
+                    study_id: "S-ABC123".to_string(),
+                    status: status.to_string(),
+                    note: note.map(str::to_string),
+                },
+            )
+        };
+
+        let err = update("submitted", None).expect_err("unknown status");
+        assert!(err.contains("Unknown study status 'submitted'"));
+        update("building", None).expect("building");
+        update("building", None).expect("unchanged status");
+        update("collecting", Some("Prolific launch")).expect("collecting");
+        update("analyzing", None).expect("analyzing");
+
+        let detail = get_study_detail(
+            &app_root,
+            GetStudyDetailArgs {
+                study_id: "S-ABC123".to_string(),
+            },
+        )
+        .expect("detail should load");
+        assert_eq!(detail.study.status, "analyzing");
+        let transitions: Vec<(Option<&str>, &str)> = detail
+            .status_history
+            .iter()
+            .map(|change| (change.from_status.as_deref(), change.to_status.as_str()))
+            .collect();
+        assert_eq!(
+            transitions,
+            vec![
+                (Some("planning"), "building"),
+                (Some("building"), "collecting"),
+                (Some("collecting"), "analyzing"),
+            ]
+        );
+        assert_eq!(
+            detail.status_history[1].note.as_deref(),
+            Some("Prolific launch")
+        );
+        let _ = fs::remove_dir_all(base);
+    }
+
+    #[test]
+    fn concurrent_commands_do_not_hit_lock_errors() {
+        const THREADS: usize = 6;
+        const ROUNDS: usize = 15;
+        let fresh = std::env::temp_dir().join(format!("stress-fresh-{}", Uuid::new_v4()));
+        fs::create_dir_all(&fresh).expect("mkdir");
+        std::thread::scope(|scope| {
+            let handles = (0..THREADS)
+                .map(|_| scope.spawn(|| init_db(&fresh)))
+                .collect::<Vec<_>>();
+            for handle in handles {
+                handle.join().expect("thread").expect("concurrent init");
+            }
+        });
+        let conn = connection(&fresh).expect("db should open");
+        assert_eq!(schema_version(&conn).expect("version"), SCHEMA_VERSION);
+        let _ = fs::remove_dir_all(fresh);
+
+        let (base, _) = seeded_app_root("stress");
+        let app_root = base.join("app");
+        std::thread::scope(|scope| {
+            let handles = (0..THREADS)
+                .map(|thread| {
+                    let app_root = &app_root;
+                    scope.spawn(move || -> Result<(), String> {
+                        for round in 0..ROUNDS {
+                            add_artifact(
+                                app_root,
+                                AddArtifactArgs {
+                                    study_id: "S-ABC123".to_string(),
+                                    kind: "url".to_string(),
+                                    value: format!("https://osf.io/{thread}-{round}"),
+                                    label: None,
+                                },
+                            )?;
+                            update_study_status(
+                                app_root,
+                                UpdateStudyStatusArgs {
+                                    study_id: "S-ABC123".to_string(),
+                                    status: STUDY_STATUSES[(thread + round) % 3].to_string(),
+                                    note: None,
+                                },
+                            )?;
+                            list_studies(
+                                app_root,
+                                ListStudiesArgs {
+                                    project_id: "p1".to_string(),
+                                },
+                            )?;
+                            get_study_detail(
+                                app_root,
+                                GetStudyDetailArgs {
+                                    study_id: "S-ABC123".to_string(),
+                                },
+                            )?;
+                        }
+                        Ok(())
+                    })
+                })
+                .collect::<Vec<_>>();
+            for handle in handles {
+                handle.join().expect("thread").expect("no lock errors");
+            }
+        });
+        let detail = get_study_detail(
+            &app_root,
+            GetStudyDetailArgs {
+                study_id: "S-ABC123".to_string(),
+            },
+        )
+        .expect("detail should load");
+        assert_eq!(detail.artifacts.len(), THREADS * ROUNDS);
+        let _ = fs::remove_dir_all(base);
+    }
+
+    #[test]
+    fn fresh_database_is_migrated_and_study_changes_touch_updated_at() {
+        let (base, _) = seeded_app_root("schema-fresh");
+        let app_root = base.join("app");
+        let conn = connection(&app_root).expect("db should open");
+        assert_eq!(schema_version(&conn).expect("version"), SCHEMA_VERSION);
+        conn.execute(
+            "UPDATE studies SET created_at = '2024-01-01', updated_at = '2024-01-01'",
+            [],
+        )
+        .expect("age study");
+
+        update_study_status(
+            &app_root,
+            UpdateStudyStatusArgs {
+                study_id: "S-ABC123".to_string(),
+                status: "collecting".to_string(),
+                note: None,
+            },
+        )
+        .expect("status update");
+        add_artifact(
+            &app_root,
+            AddArtifactArgs {
+                study_id: "S-ABC123".to_string(),
+                kind: "url".to_string(),
+                value: "https://osf.io".to_string(),
+                label: None,
+            },
+        )
+        .expect("artifact");
+        let detail = get_study_detail(
+            &app_root,
+            GetStudyDetailArgs {
+                study_id: "S-ABC123".to_string(),
+            },
+        )
+        .expect("detail should load");
+        assert_eq!(detail.study.created_at, "2024-01-01");
+        assert!(detail.study.updated_at.as_str() > "2024-01-01");
+        assert_eq!(
+            detail.artifacts[0].updated_at,
+            detail.artifacts[0].created_at
+        );
+        let _ = fs::remove_dir_all(base);
+    }
+
+    #[test]
+    fn package_manifest_lists_exactly_the_copied_files_with_checksums() {
+        use sha2::{Digest, Sha256};
+
+        let (base, project_root) = seeded_app_root("osf-manifest");
+        let app_root = base.join("app");
+        let study_root = project_root.join("studies").join("S-ABC123");
+        for (rel, content) in [
+            ("06_analysis/analysis.Rmd", "x"),
+            ("05_data/clean.csv", "id,score\n1,5\n"),
+            ("05_data/raw/responses.csv", "id,score,email\n"),
+            ("03_pilots/pilot.csv", "id\n"),
+        ] {
+            let path = study_root.join(rel);
+            fs::create_dir_all(path.parent().expect("parent")).expect("mkdir");
+            fs::write(path, content).expect("seed file");
+        }
+
+        let results = generate_osf_packages(
+            &app_root,
+            GenerateOsfPackagesArgs {
+                study_id: "S-ABC123".to_string(),
+                include_pilots: false,
+                packages: vec![PackageRequest {
+                    kind: PackageKind::Condensed,
+                    folder_name: None,
+                }],
+                follow_internal_links: false,
+                enforce_release_gate: false,
+                force: false,
+            },
+        )
+        .expect("condensed package should build");
+        let result = &results[0];
+        assert_eq!(result.manifest.file_count, 2);
+        assert_eq!(result.manifest.total_bytes, 1 + 13);
+        assert!(!result.manifest.include_pilots);
+
+        let package = PathBuf::from(&result.path);
+        let manifest: serde_json::Value = serde_json::from_str(
+            &fs::read_to_string(package.join("MANIFEST.json")).expect("read manifest"),
+        )
+        .expect("manifest json");
+        let paths: Vec<&str> = manifest["files"]
+            .as_array()
+            .expect("files")
+            .iter()
+            .map(|file| file["path"].as_str().expect("path"))
+            .collect();
+        assert_eq!(paths, vec!["05_data/clean.csv", "06_analysis/analysis.Rmd"]);
+        let expected = format!("{:x}", Sha256::digest(b"id,score\n1,5\n"));
+        assert_eq!(manifest["files"][0]["sha256"], expected.as_str());
+        assert_eq!(manifest["fileCount"], 2);
+        assert_eq!(manifest["appVersion"], env!("CARGO_PKG_VERSION"));
+
+        let readme = fs::read_to_string(package.join("README.md")).expect("read readme");
+        assert!(readme.contains("| `05_data/` | Collected data | 1 |"));
+        assert!(readme.contains("- `03_pilots`: pilot material; pilots were not included"));
+        assert!(readme.contains("- `05_data/raw`: raw or private data left out"));
+        let _ = fs::remove_dir_all(base);
+    }
+
+    #[test]
+    fn condensed_only_run_keeps_other_release_folders() {
+        let (base, project_root) = seeded_app_root("osf-selective");
+        let app_root = base.join("app");
+        let study_root = project_root.join("studies").join("S-ABC123");
+        fs::create_dir_all(study_root.join("06_analysis")).expect("failed to create analysis");
+        fs::write(study_root.join("06_analysis").join("analysis.Rmd"), "x")
+            .expect("failed to write analysis");
+        let complete = study_root.join(OSF_RELEASE_FOLDER).join("COMPLETE");
+        fs::create_dir_all(&complete).expect("failed to create complete package");
+        fs::write(complete.join("keep.txt"), "keep").expect("failed to seed complete package");
+
+        let results = generate_osf_packages(
+            &app_root,
+            GenerateOsfPackagesArgs {
+                study_id: "S-ABC123".to_string(),
+                include_pilots: false,
+                packages: vec![PackageRequest {
+                    kind: PackageKind::Condensed,
+                    folder_name: Some("S2_public_materials".to_string()),
+                }],
+                follow_internal_links: false,
+                enforce_release_gate: false,
+                force: false,
+            },
+        )
+        .expect("condensed package should build");
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].kind, "condensed");
+        assert_eq!(results[0].files, 1);
+        let public = study_root
+            .join(OSF_RELEASE_FOLDER)
+            .join("S2_public_materials");
+        assert!(public.join("06_analysis").join("analysis.Rmd").is_file());
+        assert!(!public.join(OSF_RELEASE_FOLDER).exists());
+        assert!(complete.join("keep.txt").is_file());
+
+        let err = generate_osf_packages(
+            &app_root,
+            GenerateOsfPackagesArgs {
+                study_id: "S-ABC123".to_string(),
+                include_pilots: false,
+                packages: vec![PackageRequest {
+                    kind: PackageKind::Complete,
+                    folder_name: Some("../escape".to_string()),
+                }],
+                follow_internal_links: false,
+                enforce_release_gate: false,
+                force: false,
+            },
+        )
+        .expect_err("nested folder names should be rejected");
+        assert!(err.contains("single folder name"));
+        let _ = fs::remove_dir_all(base);
+    }
+}
@@ -0,0 +1,258 @@
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+use tauri::AppHandle;
+
+pub const APP_DIR_NAME: &str = "research-workflow";
+/// Data folder override, checked before the config file next to the executable.
+pub const DATA_DIR_ENV: &str = "RESEARCH_WORKFLOW_DATA_DIR";
+pub const STORAGE_CONFIG_FILE: &str = "research-workflow.storage.json";
+const EMERGENCY_DIR_NAME: &str = "research-workflow-emergency";
+const PROBE_FILE: &str = ".write-probe";
+const EMERGENCY_WARNING: &str = "The app data folder is not writable, so data is being stored in a temporary folder that may be cleared. Set RESEARCH_WORKFLOW_DATA_DIR or choose a storage folder, then restart.";
+
+/// Resolved once per process so every command sees the same location.
+static STORAGE: OnceLock<StorageHealth> = OnceLock::new();
+
+#[derive(Debug, Serialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum StorageSource {
+    Standard,
+    Override,
+    Emergency,
+}
+
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct StorageHealth {
+    pub active_path: String,
+    pub source: StorageSource,
+    pub writable: bool,
+    /// Why earlier locations in the chain were skipped; None when the standard dir is used.
+    pub fallback_reason: Option<String>,
+    pub warning: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SetStorageOverrideArgs {
+    /// None (or blank) clears the override.
+    #[serde(default)]
+    pub data_dir: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct StorageConfig {
+    data_dir: String,
+}
+
+fn probe_writable(dir: &Path) -> Result<(), String> {
+    fs::create_dir_all(dir).map_err(|e| format!("Unable to create {}: {e}", dir.display()))?;
+    let probe = dir.join(PROBE_FILE);
+    fs::write(&probe, b"ok").map_err(|e| format!("{} is not writable: {e}", dir.display()))?;
+    let _ = fs::remove_file(&probe);
+    Ok(())
+}
+
+/// Walks the fallback chain: `<standard>/research-workflow`, then the override folder, then
+/// `emergency` (a temp folder). Each candidate must accept a probe write to be chosen.
+pub fn select_storage(
+    standard: Option<&Path>,
+    override_dir: Option<&Path>,
+    emergency: &Path,
+) -> StorageHealth {
+    let mut reasons: Vec<String> = Vec::new();
+    match standard {
+        Some(base) => {
+            let root = base.join(APP_DIR_NAME);
+            match probe_writable(&root) {
+                Ok(()) => {
+                    return StorageHealth {
+                        active_path: root.to_string_lossy().to_string(),
+                        source: StorageSource::Standard,
+                        writable: true,
+                        fallback_reason: None,
+                        warning: None,
+                    }
+                }
+                Err(err) => reasons.push(format!("app data dir: {err}")),
+            }
+        }
+        None => reasons.push("app data dir: unable to resolve".to_string()),
+    }
+
+    if let Some(dir) = override_dir {
+        match probe_writable(dir) {
+            Ok(()) => {
+                return StorageHealth {
+                    active_path: dir.to_string_lossy().to_string(),
+                    source: StorageSource::Override,
+                    writable: true,
+                    fallback_reason: Some(reasons.join("; ")),
+                    warning: None,
+                }
+            }
+            Err(err) => reasons.push(format!("override: {err}")),
+        }
+    }
+
+    let writable = match probe_writable(emergency) {
+        Ok(()) => true,
+        Err(err) => {
+            reasons.push(format!("temp dir: {err}"));
+            false
+        }
+    };
+    StorageHealth {
+        active_path: emergency.to_string_lossy().to_string(),
+        source: StorageSource::Emergency,
+        writable,
+        fallback_reason: Some(reasons.join("; ")),
+        warning: Some(EMERGENCY_WARNING.to_string()),
+    }
+}
+
+fn storage_config_path() -> Option<PathBuf> {
+    let exe = std::env::current_exe().ok()?;
+    Some(exe.parent()?.join(STORAGE_CONFIG_FILE))
+}
+
+fn read_storage_config(path: &Path) -> Option<PathBuf> {
+    let raw = fs::read_to_string(path).ok()?;
+    let config: StorageConfig = serde_json::from_str(&raw).ok()?;
+    let dir = config.data_dir.trim();
+    (!dir.is_empty()).then(|| PathBuf::from(dir))
+}
+
+/// Saves (or clears, for `None`) the override folder in the config next to the executable.
+fn write_storage_config(path: &Path, data_dir: Option<&str>) -> Result<(), String> {
+    match data_dir.map(str::trim).filter(|dir| !dir.is_empty()) {
+        Some(dir) => {
+            let raw = serde_json::to_string_pretty(&StorageConfig {
+                data_dir: dir.to_string(),
+            })
+            .map_err(|e| e.to_string())?;
+            fs::write(path, raw).map_err(|e| format!("Unable to write {}: {e}", path.display()))
+        }
+        None if path.exists() => {
+            fs::remove_file(path).map_err(|e| format!("Unable to remove {}: {e}", path.display()))
+        }
+        None => Ok(()),
+    }
+}
+
+fn override_dir() -> Option<PathBuf> {
+    if let Some(dir) = std::env::var_os(DATA_DIR_ENV).filter(|dir| !dir.is_empty()) {
+        return Some(PathBuf::from(dir));
+    }
+    read_storage_config(&storage_config_path()?)
+}
+
+pub fn storage_health(app: &AppHandle) -> StorageHealth {
+    STORAGE
+        .get_or_init(|| {
+            let standard = tauri::api::path::app_data_dir(&app.config());
+            select_storage(
+                standard.as_deref(),
+                override_dir().as_deref(),
+                &std::env::temp_dir().join(EMERGENCY_DIR_NAME),
+            )
+        })
+        .clone()
+}
+
+/// Root for app-level data (projects store, database, settings, models). Every command
+/// resolves it through here so they all agree on the fallback location.
+pub fn app_data_root(app: &AppHandle) -> Result<PathBuf, String> {
+    let health = storage_health(app);
+    if !health.writable {
+        return Err(format!(
+            "No writable data folder is available: {}",
+            health.fallback_reason.unwrap_or_default()
+        ));
+    }
+    let root = PathBuf::from(health.active_path);
+    fs::create_dir_all(&root).map_err(|e| e.to_string())?;
+    Ok(root)
+}
+
+/// Persists the override folder; it is picked up on the next launch.
+pub fn set_storage_override(data_dir: Option<&str>) -> Result<String, String> {
+    let path = storage_config_path()
+        .ok_or_else(|| "Unable to locate the application folder.".to_string())?;
+    write_storage_config(&path, data_dir)?;
+    Ok(path.to_string_lossy().to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use uuid::Uuid;
+
+    fn temp_base(label: &str) -> PathBuf {
+        let base = std::env::temp_dir().join(format!("storage-{label}-{}", Uuid::new_v4()));
+        fs::create_dir_all(&base).expect("temp base");
+        base
+    }
+
+    #[test]
+    fn unwritable_primary_falls_back_to_override_then_temp() {
+        let base = temp_base("fallback");
+        // A regular file where the app data dir should be cannot hold the data folder,
+        // which behaves like a read-only redirected profile even when tests run as root.
+        let blocked = base.join("roaming");
+        fs::write(&blocked, "not a directory").expect("blocker");
+        let override_dir = base.join("override");
+        let emergency = base.join("emergency");
+
+        let health = select_storage(Some(&base.join("ok")), Some(&override_dir), &emergency);
+        assert_eq!(health.source, StorageSource::Standard);
+        assert!(health.active_path.ends_with(APP_DIR_NAME));
+        assert!(health.writable && health.fallback_reason.is_none());
+
+        let health = select_storage(Some(&blocked), Some(&override_dir), &emergency);
+        assert_eq!(health.source, StorageSource::Override);
+        assert_eq!(health.active_path, override_dir.to_string_lossy());
+        assert!(health.writable);
+        assert!(health
+            .fallback_reason
+            .as_deref()
+            .is_some_and(|reason| reason.starts_with("app data dir:")));
+        assert!(health.warning.is_none());
+        assert!(!override_dir.join(PROBE_FILE).exists());
+
+        let health = select_storage(Some(&blocked), Some(&blocked.join("x")), &emergency);
+        assert_eq!(health.source, StorageSource::Emergency);
+        assert!(health.writable);
+        assert!(health.warning.is_some());
+        let reason = health.fallback_reason.expect("reason");
+        assert!(reason.contains("app data dir:") && reason.contains("override:"));
+
+        let health = select_storage(None, None, &blocked.join("tmp"));
+        assert_eq!(health.source, StorageSource::Emergency);
+        assert!(!health.writable);
+        assert!(health
+            .fallback_reason
+            .expect("reason")
+            .contains("temp dir:"));
+        let _ = fs::remove_dir_all(base);
+    }
+
+    #[test]
+    fn storage_config_round_trips_and_clears() {
+        let base = temp_base("config");
+        let config = base.join(STORAGE_CONFIG_FILE);
+        assert_eq!(read_storage_config(&config), None);
+
+        write_storage_config(&config, Some(" D:\\research-data ")).expect("write");
+        assert_eq!(
+            read_storage_config(&config),
+            Some(PathBuf::from("D:\\research-data"))
+        );
+        write_storage_config(&config, None).expect("clear");
+        assert!(!config.exists());
+        let _ = fs::remove_dir_all(base);
+    }
+}
@@ -0,0 +1,277 @@
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+
+use super::{read_projects_store, STUDY_FOLDERS};
+
+pub const PROJECT_STRUCTURE_CONFIG_PATH: &str = "config/project_structure.json";
+
+/// Study folders the analysis generator writes into; every scaffold has them.
+pub const REQUIRED_STUDY_FOLDERS: &[&str] = &["06_analysis", "07_outputs"];
+
+/// Project-level `config/project_structure.json`. Without a `studyFolderTemplate` new studies
+/// get the default `STUDY_FOLDERS`.
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct ProjectStructureConfig {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub study_folder_template: Option<Vec<String>>,
+}
+
+#[derive(Debug, Serialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct ProjectStructure {
+    /// The configured template, if the project has one.
+    pub study_folder_template: Option<Vec<String>>,
+    /// Folders a new study is created with: the template (or the defaults) plus the required
+    /// folders.
+    pub study_folders: Vec<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProjectStructureArgs {
+    project_id: String,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UpdateProjectStructureArgs {
+    project_id: String,
+    /// `None` goes back to the default folders.
+    #[serde(default)]
+    study_folder_template: Option<Vec<String>>,
+}
+
+fn project_root(app_root: &Path, project_id: &str) -> Result<PathBuf, String> {
+    let store = read_projects_store(app_root)?;
+    store
+        .projects
+        .iter()
+        .find(|project| project.id == project_id)
+        .map(|project| PathBuf::from(&project.root_path))
+        .ok_or_else(|| "Project not found.".to_string())
+}
+
+/// A study folder name must be a single relative path segment.
+fn validate_folder_name(name: &str) -> Result<String, String> {
+    let name = name.trim();
+    if name.is_empty() {
+        return Err("Study folder names cannot be empty.".to_string());
+    }
+    let mut components = Path::new(name).components();
+    let single = matches!(components.next(), Some(Component::Normal(_)))
+        && components.next().is_none()
+        && !name.contains(['/', '\\']);
+    if !single {
+        return Err(format!(
+            "Study folder '{name}' must be a single folder name, not a path."
+        ));
+    }
+    Ok(name.to_string())
+}
+
+/// Trimmed, validated template with duplicates (ignoring case) removed.
+fn normalize_template(template: &[String]) -> Result<Vec<String>, String> {
+    let mut folders: Vec<String> = Vec::new();
+    for name in template {
+        let name = validate_folder_name(name)?;
+        if !folders
+            .iter()
+            .any(|folder| folder.eq_ignore_ascii_case(&name))
+        {
+            folders.push(name);
+        }
+    }
+    Ok(folders)
+}
+
+pub fn load_project_structure(project_root: &Path) -> Result<ProjectStructureConfig, String> {
+    let path = project_root.join(PROJECT_STRUCTURE_CONFIG_PATH);
+    if !path.exists() {
+        return Ok(ProjectStructureConfig::default());
+    }
+    let raw = fs::read_to_string(&path).map_err(|err| err.to_string())?;
+    serde_json::from_str(&raw)
+        .map_err(|err| format!("Invalid {PROJECT_STRUCTURE_CONFIG_PATH}: {err}"))
+}
+
+/// Folders a new study under `project_root` is scaffolded with. The project's template (or
+/// `STUDY_FOLDERS`) comes first; required folders it leaves out are appended.
+pub fn resolve_study_folders(project_root: &Path) -> Result<Vec<String>, String> {
+    let mut folders = match load_project_structure(project_root)?.study_folder_template {
+        Some(template) => normalize_template(&template)
+            .map_err(|err| format!("Invalid {PROJECT_STRUCTURE_CONFIG_PATH}: {err}"))?,
+        None => STUDY_FOLDERS
+            .iter()
+            .map(|folder| folder.to_string())
+            .collect(),
+    };
+    for required in REQUIRED_STUDY_FOLDERS {
+        if !folders.iter().any(|folder| folder == required) {
+            folders.push(required.to_string());
+        }
+    }
+    Ok(folders)
+}
+
+/// Creates the project's study folders under `study_root`.
+pub fn ensure_study_scaffold(project_root: &Path, study_root: &Path) -> Result<(), String> {
+    for folder in resolve_study_folders(project_root)? {
+        fs::create_dir_all(study_root.join(folder)).map_err(|err| err.to_string())?;
+    }
+    Ok(())
+}
+
+pub fn project_structure_at(project_root: &Path) -> Result<ProjectStructure, String> {
+    Ok(ProjectStructure {
+        study_folder_template: load_project_structure(project_root)?.study_folder_template,
+        study_folders: resolve_study_folders(project_root)?,
+    })
+}
+
+/// Replaces the project's study folder template; `config/` is created when needed. Existing
+/// studies are left as they are.
+pub fn update_project_structure_at(
+    project_root: &Path,
+    template: Option<Vec<String>>,
+) -> Result<ProjectStructure, String> {
+    let mut config = load_project_structure(project_root)?;
+    config.study_folder_template = template
+        .map(|template| normalize_template(&template))
+        .transpose()?;
+    let path = project_root.join(PROJECT_STRUCTURE_CONFIG_PATH);
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).map_err(|err| err.to_string())?;
+    }
+    let payload = serde_json::to_string_pretty(&config).map_err(|err| err.to_string())?;
+    fs::write(&path, payload).map_err(|err| err.to_string())?;
+    project_structure_at(project_root)
+}
+
+pub fn get_project_structure(
+    app_root: &Path,
+    args: ProjectStructureArgs,
+) -> Result<ProjectStructure, String> {
+    project_structure_at(&project_root(app_root, &args.project_id)?)
+}
+
+pub fn update_project_structure(
+    app_root: &Path,
+    args: UpdateProjectStructureArgs,
+) -> Result<ProjectStructure, String> {
+    update_project_structure_at(
+        &project_root(app_root, &args.project_id)?,
+        args.study_folder_template,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::store::projects::{add_study, create_project};
+    use crate::template::create_analysis_template;
+    use uuid::Uuid;
+
+    #[test]
+    fn custom_template_scaffolds_studies_and_keeps_required_folders() {
+        let base = std::env::temp_dir().join(format!("store-structure-{}", Uuid::new_v4()));
+        let app_root = base.join("app");
+        let root = base.join("dept");
+        fs::create_dir_all(&app_root).expect("app root");
+        fs::create_dir_all(&root).expect("project root");
+        let args = serde_json::from_value(serde_json::json!({
+            "name": "Dept",
+            "rootDir": root.to_string_lossy(),
+            "useExistingRoot": true,
+        }))
+        .expect("args");
+        let project = create_project(&app_root, args).expect("project");
+        assert_eq!(
+            project_structure_at(&root).expect("defaults").study_folders,
+            STUDY_FOLDERS.to_vec()
+        );
+
+        let template = [
+            "00_admin",
+            "01_design",
+            "02_build",
+            "04_prereg",
+            " 09_irb ",
+            "09_IRB",
+        ];
+        let structure = update_project_structure_at(
+            &root,
+            Some(template.iter().map(|name| name.to_string()).collect()),
+        )
+        .expect("update");
+        assert_eq!(
+            structure.study_folders,
+            vec![
+                "00_admin",
+                "01_design",
+                "02_build",
+                "04_prereg",
+                "09_irb",
+                "06_analysis",
+                "07_outputs"
+            ]
+        );
+        assert!(root.join(PROJECT_STRUCTURE_CONFIG_PATH).is_file());
+
+        let args = serde_json::from_value(serde_json::json!({
+            "projectId": project.id,
+            "folderName": "S-IRB001",
+        }))
+        .expect("args");
+        let updated = add_study(&app_root, args).expect("study");
+        let study_root = root.join("studies").join("S-IRB001");
+        assert!(study_root.join("09_irb").is_dir());
+        assert!(study_root.join("06_analysis").is_dir());
+        assert!(!study_root.join("03_pilots").exists());
+        assert!(!study_root.join("05_data").exists());
+
+        create_analysis_template(
+            &app_root,
+            project.id.clone(),
+            updated.studies[0].id.clone(),
+            serde_json::json!({}),
+            None,
+        )
+        .expect("analysis template without optional folders");
+        let generated = fs::read_dir(study_root.join("06_analysis"))
+            .expect("analysis dir")
+            .filter_map(|entry| entry.ok())
+            .any(|entry| entry.file_name().to_string_lossy().ends_with(".Rmd"));
+        assert!(generated);
+        assert!(root.join("R").join("style").join("style_init.R").is_file());
+        let _ = fs::remove_dir_all(base);
+    }
+
+    #[test]
+    fn template_names_must_be_single_folder_segments() {
+        let root = std::env::temp_dir().join(format!("store-structure-{}", Uuid::new_v4()));
+        fs::create_dir_all(&root).expect("root");
+        for bad in ["raw/wave1", "..", "/abs", " ", "a\\b"] {
+            let err = update_project_structure_at(&root, Some(vec![bad.to_string()]))
+                .expect_err("invalid folder name");
+            assert!(err.contains("Study folder"), "{bad}: {err}");
+        }
+        assert!(!root.join(PROJECT_STRUCTURE_CONFIG_PATH).exists());
+
+        // A hand-edited config is validated too, and clearing the template restores defaults.
+        fs::create_dir_all(root.join("config")).expect("config");
+        fs::write(
+            root.join(PROJECT_STRUCTURE_CONFIG_PATH),
+            r#"{"studyFolderTemplate": ["../outside"]}"#,
+        )
+        .expect("write");
+        assert!(resolve_study_folders(&root)
+            .expect_err("invalid config")
+            .starts_with("Invalid config/project_structure.json"));
+        let structure = update_project_structure_at(&root, None).expect("reset");
+        assert_eq!(structure.study_folder_template, None);
+        assert_eq!(structure.study_folders, STUDY_FOLDERS.to_vec());
+        let _ = fs::remove_dir_all(root);
+    }
+}
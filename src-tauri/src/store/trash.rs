@@ -0,0 +1,404 @@
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use super::{now_string, read_projects_store, write_projects_store, Project};
+
+/// App-managed trash created next to a project folder when the OS trash is unavailable.
+pub const APP_TRASH_DIR: &str = ".research-workflow-trash";
+const TOMBSTONES_FILE: &str = "deleted_projects.json";
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum TrashLocation {
+    /// Only removed from the project list; the folder was left in place.
+    Kept,
+    /// Moved to the operating system's trash.
+    OsTrash,
+    /// Renamed into [`APP_TRASH_DIR`] beside the original folder.
+    AppTrash,
+}
+
+/// Record of a deleted project, kept until it is restored or purged.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ProjectTombstone {
+    pub project: Project,
+    pub original_path: String,
+    pub deleted_at: String,
+    pub location: TrashLocation,
+    /// Folder inside the app-managed trash, for [`TrashLocation::AppTrash`].
+    #[serde(default)]
+    pub trashed_path: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RestoreDeletedProjectArgs {
+    project_id: String,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PurgeDeletedProjectsArgs {
+    /// Tombstones to purge; all of them when empty.
+    #[serde(default)]
+    project_ids: Vec<String>,
+}
+
+fn tombstones_path(app_root: &Path) -> PathBuf {
+    app_root.join(TOMBSTONES_FILE)
+}
+
+pub fn list_deleted_projects(app_root: &Path) -> Result<Vec<ProjectTombstone>, String> {
+    let path = tombstones_path(app_root);
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+    let raw = fs::read_to_string(&path).map_err(|err| err.to_string())?;
+    serde_json::from_str(&raw).map_err(|err| format!("Invalid {TOMBSTONES_FILE}: {err}"))
+}
+
+fn write_tombstones(app_root: &Path, tombstones: &[ProjectTombstone]) -> Result<(), String> {
+    let payload = serde_json::to_string_pretty(tombstones).map_err(|err| err.to_string())?;
+    fs::write(tombstones_path(app_root), payload).map_err(|err| err.to_string())
+}
+
+pub fn home_dir() -> Option<PathBuf> {
+    std::env::var_os("HOME")
+        .or_else(|| std::env::var_os("USERPROFILE"))
+        .filter(|value| !value.is_empty())
+        .map(PathBuf::from)
+}
+
+/// Refuses folders whose removal would take unrelated files along: filesystem and drive
+/// roots, and the user's home directory or any folder containing it.
+pub fn check_deletable_root(path: &Path, home: Option<&Path>) -> Result<(), String> {
+    let resolved = path.canonicalize().unwrap_or_else(|_| path.to_path_buf());
+    if resolved.parent().is_none() || resolved.components().count() < 2 {
+        return Err(format!(
+            "Refusing to delete a filesystem or drive root: {}",
+            path.display()
+        ));
+    }
+    if let Some(home) = home {
+        let home = home.canonicalize().unwrap_or_else(|_| home.to_path_buf());
+        if home.starts_with(&resolved) {
+            return Err(format!(
+                "Refusing to delete a home directory or a folder containing one: {}",
+                path.display()
+            ));
+        }
+    }
+    Ok(())
+}
+
+pub fn os_trash(path: &Path) -> Result<(), String> {
+    trash::delete(path).map_err(|err| err.to_string())
+}
+
+/// Puts the most recently trashed item that came from `path` back in place.
+#[cfg(any(
+    target_os = "windows",
+    all(
+        unix,
+        not(target_os = "macos"),
+        not(target_os = "ios"),
+        not(target_os = "android")
+    )
+))]
+pub fn os_restore(path: &Path) -> Result<(), String> {
+    let item = trash::os_limited::list()
+        .map_err(|err| err.to_string())?
+        .into_iter()
+        .filter(|item| item.original_path() == path)
+        .max_by_key(|item| item.time_deleted)
+        .ok_or_else(|| format!("{} is no longer in the trash.", path.display()))?;
+    trash::os_limited::restore_all([item]).map_err(|err| err.to_string())
+}
+
+#[cfg(not(any(
+    target_os = "windows",
+    all(
+        unix,
+        not(target_os = "macos"),
+        not(target_os = "ios"),
+        not(target_os = "android")
+    )
+)))]
+pub fn os_restore(path: &Path) -> Result<(), String> {
+    Err(format!(
+        "Put {} back from the Trash, then restore the project again.",
+        path.display()
+    ))
+}
+
+/// Moves a project folder to the OS trash, or into [`APP_TRASH_DIR`] under a timestamped
+/// name when `os_trash` fails.
+pub fn trash_project_root(
+    root: &Path,
+    os_trash: impl FnOnce(&Path) -> Result<(), String>,
+) -> Result<(TrashLocation, Option<PathBuf>), String> {
+    if os_trash(root).is_ok() {
+        return Ok((TrashLocation::OsTrash, None));
+    }
+    let parent = root
+        .parent()
+        .ok_or_else(|| format!("Cannot trash {}", root.display()))?;
+    let name = root
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_else(|| "project".to_string());
+    let trash_dir = parent.join(APP_TRASH_DIR);
+    fs::create_dir_all(&trash_dir).map_err(|err| err.to_string())?;
+    let target = trash_dir.join(format!(
+        "{name}-{}",
+        Utc::now().format("%Y%m%dT%H%M%S%.3fZ")
+    ));
+    fs::rename(root, &target)
+        .map_err(|err| format!("Unable to move {} to the trash: {err}", root.display()))?;
+    Ok((TrashLocation::AppTrash, Some(target)))
+}
+
+pub fn record_tombstone(app_root: &Path, tombstone: ProjectTombstone) -> Result<(), String> {
+    let mut tombstones = list_deleted_projects(app_root)?;
+    tombstones.push(tombstone);
+    write_tombstones(app_root, &tombstones)
+}
+
+/// Tombstone for `project`, stamped now.
+pub fn tombstone_for(
+    project: Project,
+    location: TrashLocation,
+    trashed_path: Option<PathBuf>,
+) -> ProjectTombstone {
+    ProjectTombstone {
+        original_path: project.root_path.clone(),
+        project,
+        deleted_at: now_string(),
+        location,
+        trashed_path: trashed_path.map(|path| path.to_string_lossy().to_string()),
+    }
+}
+
+pub fn restore_deleted_project(
+    app_root: &Path,
+    args: RestoreDeletedProjectArgs,
+) -> Result<Project, String> {
+    restore_deleted_project_with(app_root, args, os_restore)
+}
+
+/// Moves the project folder back from the trash and re-adds the project to the list.
+pub fn restore_deleted_project_with(
+    app_root: &Path,
+    args: RestoreDeletedProjectArgs,
+    os_restore: impl FnOnce(&Path) -> Result<(), String>,
+) -> Result<Project, String> {
+    let mut tombstones = list_deleted_projects(app_root)?;
+    let index = tombstones
+        .iter()
+        .rposition(|tombstone| tombstone.project.id == args.project_id)
+        .ok_or_else(|| "Deleted project not found.".to_string())?;
+    let mut store = read_projects_store(app_root)?;
+    if store
+        .projects
+        .iter()
+        .any(|project| project.id == args.project_id)
+    {
+        return Err("This project is already in the project list.".to_string());
+    }
+
+    let tombstone = &tombstones[index];
+    let original = PathBuf::from(&tombstone.original_path);
+    match tombstone.location {
+        TrashLocation::Kept => {}
+        TrashLocation::OsTrash => {
+            if !original.exists() {
+                os_restore(&original)?;
+            }
+        }
+        TrashLocation::AppTrash => {
+            if original.exists() {
+                return Err(format!(
+                    "Cannot restore: {} already exists.",
+                    original.display()
+                ));
+            }
+            let trashed = tombstone
+                .trashed_path
+                .as_deref()
+                .map(PathBuf::from)
+                .ok_or_else(|| "Trashed folder is not recorded.".to_string())?;
+            fs::rename(&trashed, &original)
+                .map_err(|err| format!("Unable to restore {}: {err}", original.display()))?;
+        }
+    }
+    if !original.is_dir() {
+        return Err(format!(
+            "Project folder does not exist: {}",
+            original.display()
+        ));
+    }
+
+    let project = tombstones.remove(index).project;
+    store.projects.push(project.clone());
+    write_projects_store(app_root, &store)?;
+    write_tombstones(app_root, &tombstones)?;
+    Ok(project)
+}
+
+/// Forgets tombstones and permanently removes their folders from the app-managed trash.
+/// Folders in the OS trash are left for the OS to empty. Returns the number purged.
+pub fn purge_deleted_projects(
+    app_root: &Path,
+    args: PurgeDeletedProjectsArgs,
+) -> Result<usize, String> {
+    let (purge, keep): (Vec<ProjectTombstone>, Vec<ProjectTombstone>) =
+        list_deleted_projects(app_root)?
+            .into_iter()
+            .partition(|tombstone| {
+                args.project_ids.is_empty() || args.project_ids.contains(&tombstone.project.id)
+            });
+    for tombstone in &purge {
+        let Some(trashed) = tombstone.trashed_path.as_deref().map(PathBuf::from) else {
+            continue;
+        };
+        let in_app_trash = trashed
+            .parent()
+            .and_then(|parent| parent.file_name())
+            .is_some_and(|name| name == APP_TRASH_DIR);
+        if in_app_trash && trashed.is_dir() {
+            fs::remove_dir_all(&trashed).map_err(|err| err.to_string())?;
+        }
+    }
+    write_tombstones(app_root, &keep)?;
+    Ok(purge.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::store::projects::{
+        create_project, delete_project_with, list_projects, CreateProjectArgs, DeleteProjectArgs,
+    };
+    use uuid::Uuid;
+
+    fn setup() -> (PathBuf, PathBuf, Project) {
+        let base = std::env::temp_dir().join(format!("store-trash-{}", Uuid::new_v4()));
+        let app_root = base.join("app");
+        let parent = base.join("work");
+        fs::create_dir_all(&app_root).expect("app root");
+        fs::create_dir_all(&parent).expect("project parent");
+        let args: CreateProjectArgs = serde_json::from_value(serde_json::json!({
+          "name": "Demo",
+          "rootDir": parent.to_string_lossy(),
+        }))
+        .expect("create args");
+        let project = create_project(&app_root, args).expect("create project");
+        (base, app_root, project)
+    }
+
+    fn delete_args(project: &Project) -> DeleteProjectArgs {
+        serde_json::from_value(serde_json::json!({
+          "projectId": project.id,
+          "deleteOnDisk": true,
+        }))
+        .expect("delete args")
+    }
+
+    #[test]
+    fn deleted_project_is_tombstoned_and_restored_to_its_folder() {
+        let (base, app_root, project) = setup();
+        let root = PathBuf::from(&project.root_path);
+        fs::write(root.join("notes.txt"), "keep me").expect("write file");
+        let home = base.join("home");
+
+        delete_project_with(&app_root, delete_args(&project), Some(&home), |_| {
+            Err("no trash".to_string())
+        })
+        .expect("delete project");
+        assert!(!root.exists());
+        assert!(list_projects(&app_root).expect("list").is_empty());
+        let tombstones = list_deleted_projects(&app_root).expect("tombstones");
+        assert_eq!(tombstones.len(), 1);
+        assert_eq!(tombstones[0].original_path, project.root_path);
+        assert_eq!(tombstones[0].location, TrashLocation::AppTrash);
+        assert_eq!(tombstones[0].project.name, "Demo");
+        let trashed = PathBuf::from(tombstones[0].trashed_path.as_deref().expect("trashed"));
+        assert_eq!(
+            trashed.parent(),
+            Some(base.join("work").join(APP_TRASH_DIR).as_path())
+        );
+        assert!(trashed.join("notes.txt").is_file());
+
+        let restored = restore_deleted_project_with(
+            &app_root,
+            RestoreDeletedProjectArgs {
+                project_id: project.id.clone(),
+            },
+            |_| panic!("app-trash restores never touch the OS trash"),
+        )
+        .expect("restore project");
+        assert_eq!(restored.root_path, project.root_path);
+        let projects = list_projects(&app_root).expect("list");
+        assert_eq!(projects.len(), 1);
+        assert_eq!(projects[0].id, project.id);
+        assert_eq!(projects[0].root_path, project.root_path);
+        assert_eq!(
+            fs::read_to_string(root.join("notes.txt")).expect("restored file"),
+            "keep me"
+        );
+        assert!(list_deleted_projects(&app_root)
+            .expect("tombstones")
+            .is_empty());
+
+        delete_project_with(&app_root, delete_args(&project), Some(&home), |_| {
+            Err("no trash".to_string())
+        })
+        .expect("delete again");
+        let purged = purge_deleted_projects(
+            &app_root,
+            PurgeDeletedProjectsArgs {
+                project_ids: Vec::new(),
+            },
+        )
+        .expect("purge");
+        assert_eq!(purged, 1);
+        assert!(list_deleted_projects(&app_root)
+            .expect("tombstones")
+            .is_empty());
+        assert_eq!(
+            fs::read_dir(base.join("work").join(APP_TRASH_DIR))
+                .expect("trash dir")
+                .count(),
+            0
+        );
+
+        let _ = fs::remove_dir_all(base);
+    }
+
+    #[test]
+    fn home_directories_and_roots_are_never_trashed() {
+        let (base, app_root, project) = setup();
+        let root = PathBuf::from(&project.root_path);
+
+        assert!(check_deletable_root(Path::new("/"), None).is_err());
+        assert!(check_deletable_root(&root, Some(&root)).is_err());
+        assert!(check_deletable_root(&base, Some(&root.join("nested"))).is_err());
+        assert!(check_deletable_root(&root, Some(&base.join("home"))).is_ok());
+
+        let err = delete_project_with(&app_root, delete_args(&project), Some(&root), |_| {
+            panic!("unsafe roots must not reach the trash")
+        })
+        .expect_err("home directory refused");
+        assert!(err.contains("home directory"));
+        assert!(root.is_dir());
+        assert_eq!(list_projects(&app_root).expect("list").len(), 1);
+        assert!(list_deleted_projects(&app_root)
+            .expect("tombstones")
+            .is_empty());
+
+        let _ = fs::remove_dir_all(base);
+    }
+}
@@ -0,0 +1,575 @@
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::store::now_string;
+
+const BEGIN_PREFIX: &str = "<!-- rw:begin:";
+const END_PREFIX: &str = "<!-- rw:end:";
+const MANIFEST_PREFIX: &str = "<!-- rw:manifest:";
+const COMMENT_SUFFIX: &str = "-->";
+
+/// Suffix of the sidecar holding the hash of each generated region: `analysis.Rmd` gets
+/// `analysis.rw_meta.json`.
+pub const REGION_META_SUFFIX: &str = "rw_meta.json";
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RegionMeta {
+    pub source_file: String,
+    pub generated_at: String,
+    /// Region id to the SHA-256 of its generated body, `rw:manual` blocks excluded.
+    pub regions: BTreeMap<String, String>,
+}
+
+pub fn region_meta_path(template_path: &Path) -> PathBuf {
+    let stem = template_path
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().to_string())
+        .unwrap_or_default();
+    template_path.with_file_name(format!("{stem}.{REGION_META_SUFFIX}"))
+}
+
+fn body_hash(body: &[String]) -> String {
+    format!("{:x}", Sha256::digest(body.join("\n").as_bytes()))
+}
+
+pub fn region_meta_json(template_path: &Path, rendered: &str) -> Result<String, String> {
+    let regions = parse_template(rendered)?
+        .segments
+        .iter()
+        .filter_map(|segment| match segment {
+            Segment::Region { id, body, .. } => Some((id.clone(), body_hash(body))),
+            Segment::Text(_) => None,
+        })
+        .collect();
+    let meta = RegionMeta {
+        source_file: template_path
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_default(),
+        generated_at: now_string(),
+        regions,
+    };
+    serde_json::to_string_pretty(&meta).map_err(|err| err.to_string())
+}
+
+/// Region hashes stored next to `template_path`; `None` for templates generated before the
+/// sidecar existed, or when it is unreadable.
+pub fn read_region_hashes(template_path: &Path) -> Option<BTreeMap<String, String>> {
+    let raw = fs::read_to_string(region_meta_path(template_path)).ok()?;
+    serde_json::from_str::<RegionMeta>(&raw)
+        .ok()
+        .map(|meta| meta.regions)
+}
+
+/// Appends a generated section, wrapped in `rw:begin`/`rw:end` markers when enabled.
+/// Blank sections are skipped so they never show up as regions.
+pub fn push_region(out: &mut String, id: &str, body: &str, markers: bool) {
+    if body.trim().is_empty() {
+        return;
+    }
+    if !markers {
+        out.push_str(body);
+        return;
+    }
+    out.push_str(&format!("{BEGIN_PREFIX}{id} {COMMENT_SUFFIX}\n"));
+    out.push_str(body.trim_end_matches('\n'));
+    out.push_str(&format!("\n{END_PREFIX}{id} {COMMENT_SUFFIX}\n\n"));
+}
+
+/// Trailing comment listing every region in `text`, used to tell deleted regions from new ones.
+pub fn manifest_line(text: &str) -> String {
+    let ids: Vec<&str> = text.lines().filter_map(begin_id).collect();
+    format!("{MANIFEST_PREFIX} {} {COMMENT_SUFFIX}\n", ids.join(" "))
+}
+
+fn marker_id<'a>(line: &'a str, prefix: &str) -> Option<&'a str> {
+    line.trim()
+        .strip_prefix(prefix)?
+        .strip_suffix(COMMENT_SUFFIX)
+        .map(str::trim)
+        .filter(|id| !id.is_empty())
+}
+
+fn begin_id(line: &str) -> Option<&str> {
+    marker_id(line, BEGIN_PREFIX)
+}
+
+fn end_id(line: &str) -> Option<&str> {
+    marker_id(line, END_PREFIX)
+}
+
+fn manifest_ids(line: &str) -> Option<Vec<String>> {
+    let rest = line
+        .trim()
+        .strip_prefix(MANIFEST_PREFIX)?
+        .strip_suffix(COMMENT_SUFFIX)?;
+    Some(rest.split_whitespace().map(str::to_string).collect())
+}
+
+/// `Some(true)` for a manual-block start, `Some(false)` for its end. Accepts both the HTML
+/// comment form (prose) and the `#` form (inside R chunks).
+fn manual_marker(line: &str) -> Option<bool> {
+    let trimmed = line.trim();
+    let inner = match trimmed.strip_prefix("<!--") {
+        Some(rest) => rest.strip_suffix(COMMENT_SUFFIX)?,
+        None => trimmed.strip_prefix('#')?,
+    };
+    match inner.trim() {
+        "rw:manual:begin" => Some(true),
+        "rw:manual:end" => Some(false),
+        _ => None,
+    }
+}
+
+#[derive(Debug)]
+struct ManualBlock {
+    /// Last non-blank generated line before the block; the block is re-inserted after it.
+    anchor: Option<String>,
+    lines: Vec<String>,
+}
+
+#[derive(Debug)]
+enum Segment {
+    Text(Vec<String>),
+    Region {
+        id: String,
+        body: Vec<String>,
+        manual: Vec<ManualBlock>,
+        /// Every line between the markers, manual blocks included, as the user left them.
+        raw: Vec<String>,
+    },
+}
+
+struct ParsedTemplate {
+    segments: Vec<Segment>,
+    manifest: Option<Vec<String>>,
+}
+
+fn parse_template(text: &str) -> Result<ParsedTemplate, String> {
+    let mut segments: Vec<Segment> = Vec::new();
+    let mut manifest = None;
+    let mut pending_text: Vec<String> = Vec::new();
+    let mut region: Option<(String, Vec<String>, Vec<ManualBlock>)> = None;
+    let mut raw: Vec<String> = Vec::new();
+    let mut manual: Option<ManualBlock> = None;
+
+    for line in text.lines() {
+        let Some((id, body, blocks)) = region.as_mut() else {
+            if let Some(ids) = manifest_ids(line) {
+                manifest = Some(ids);
+            } else if let Some(id) = begin_id(line) {
+                if !pending_text.is_empty() {
+                    segments.push(Segment::Text(std::mem::take(&mut pending_text)));
+                }
+                region = Some((id.to_string(), Vec::new(), Vec::new()));
+                raw.clear();
+            } else if let Some(id) = end_id(line) {
+                return Err(format!(
+                    "End marker for region '{id}' has no matching begin marker."
+                ));
+            } else {
+                pending_text.push(line.to_string());
+            }
+            continue;
+        };
+
+        if end_id(line).is_none() {
+            raw.push(line.to_string());
+        }
+        if let Some(block) = manual.as_mut() {
+            if end_id(line).is_some() || begin_id(line).is_some() {
+                return Err(format!(
+                    "Manual block in region '{id}' is missing its rw:manual:end marker."
+                ));
+            }
+            block.lines.push(line.to_string());
+            if manual_marker(line) == Some(false) {
+                blocks.extend(manual.take());
+            }
+            continue;
+        }
+        if manual_marker(line) == Some(true) {
+            manual = Some(ManualBlock {
+                anchor: body.iter().rev().find(|l| !l.trim().is_empty()).cloned(),
+                lines: vec![line.to_string()],
+            });
+            continue;
+        }
+        if let Some(end) = end_id(line) {
+            if end != id.as_str() {
+                return Err(format!(
+                    "Managed region '{id}' is closed by the end marker for '{end}'."
+                ));
+            }
+            if let Some((id, body, manual)) = region.take() {
+                segments.push(Segment::Region {
+                    id,
+                    body,
+                    manual,
+                    raw: std::mem::take(&mut raw),
+                });
+            }
+            continue;
+        }
+        if begin_id(line).is_some() {
+            return Err(format!("Managed region '{id}' is missing its end marker."));
+        }
+        body.push(line.to_string());
+    }
+
+    if let Some((id, _, _)) = region {
+        return Err(format!("Managed region '{id}' is missing its end marker."));
+    }
+    if !pending_text.is_empty() {
+        segments.push(Segment::Text(pending_text));
+    }
+    Ok(ParsedTemplate { segments, manifest })
+}
+
+/// Generated body with the user's manual blocks placed back after their anchor lines.
+fn with_manual_blocks(body: &[String], blocks: &[ManualBlock]) -> Vec<String> {
+    let positions: Vec<usize> = blocks
+        .iter()
+        .map(|block| match &block.anchor {
+            None => 0,
+            Some(anchor) => body
+                .iter()
+                .position(|line| line.trim() == anchor.trim())
+                .map(|index| index + 1)
+                .unwrap_or(body.len()),
+        })
+        .collect();
+    let mut out = Vec::new();
+    for index in 0..=body.len() {
+        for (block, position) in blocks.iter().zip(&positions) {
+            if *position == index {
+                out.extend(block.lines.iter().cloned());
+            }
+        }
+        if let Some(line) = body.get(index) {
+            out.push(line.clone());
+        }
+    }
+    out
+}
+
+#[derive(Debug, Default)]
+pub struct MergeOutcome {
+    pub text: String,
+    pub updated_regions: Vec<String>,
+    pub added_regions: Vec<String>,
+    pub removed_regions: Vec<String>,
+    /// Regions kept as they are: unchanged since the last generation, or edited by the user
+    /// with nothing new to pick up.
+    pub preserved_regions: Vec<String>,
+    /// Regions edited by the user whose generated content changed too. The edited version is
+    /// kept and the new one is written below it as a comment.
+    pub conflicted_regions: Vec<String>,
+    /// Regions generated previously but deleted by the user; they are not re-added.
+    pub conflicts: Vec<String>,
+}
+
+/// Commented-out copy of a regenerated region, placed after the user's edited version.
+fn conflict_block(id: &str, fresh: &[String]) -> Vec<String> {
+    let mut lines = vec![
+        String::new(),
+        format!("<!-- rw:regenerated:{id}"),
+        format!(
+            "Region '{id}' was edited by hand and its generated content has changed since; the edited version above was kept."
+        ),
+        "The regenerated version follows. Merge what you need, then delete this comment.".to_string(),
+        String::new(),
+    ];
+    lines.extend(fresh.iter().map(|line| line.replace("-->", "-- >")));
+    lines.push(COMMENT_SUFFIX.to_string());
+    lines
+}
+
+enum Item {
+    Text(Vec<String>),
+    Region(String, Vec<String>),
+}
+
+/// Replaces the managed regions of `previous` with those of `rendered`. Text outside markers
+/// and `rw:manual` blocks inside them are kept in place; new regions follow the closest
+/// preceding region (and any text after it) that still exists.
+///
+/// With the hashes recorded at the last generation, only regions whose generated content
+/// changed are replaced, and a region the user edited is never overwritten. Without them
+/// every region is replaced.
+pub fn merge_regenerated(
+    previous: &str,
+    rendered: &str,
+    generated_hashes: Option<&BTreeMap<String, String>>,
+) -> Result<MergeOutcome, String> {
+    let old = parse_template(previous)?;
+    if !old
+        .segments
+        .iter()
+        .any(|segment| matches!(segment, Segment::Region { .. }))
+    {
+        return Err(
+            "Template has no managed regions; regenerating it would discard manual edits."
+                .to_string(),
+        );
+    }
+    let new_regions: Vec<(String, Vec<String>)> = parse_template(rendered)?
+        .segments
+        .into_iter()
+        .filter_map(|segment| match segment {
+            Segment::Region { id, body, .. } => Some((id, body)),
+            Segment::Text(_) => None,
+        })
+        .collect();
+    if new_regions.is_empty() {
+        return Err("Regeneration requires managed region markers in the new render.".to_string());
+    }
+
+    let mut outcome = MergeOutcome::default();
+    let mut items: Vec<Item> = Vec::new();
+    for segment in old.segments {
+        match segment {
+            Segment::Text(lines) => items.push(Item::Text(lines)),
+            Segment::Region {
+                id,
+                body,
+                manual,
+                raw,
+            } => match new_regions.iter().find(|(new_id, _)| *new_id == id) {
+                Some((_, fresh)) => {
+                    let stored = generated_hashes.and_then(|hashes| hashes.get(&id));
+                    let edited = stored.is_some_and(|hash| *hash != body_hash(&body));
+                    let changed = stored.is_none_or(|hash| *hash != body_hash(fresh));
+                    match (edited, changed) {
+                        (false, true) => {
+                            items
+                                .push(Item::Region(id.clone(), with_manual_blocks(fresh, &manual)));
+                            outcome.updated_regions.push(id);
+                        }
+                        (true, true) => {
+                            items.push(Item::Region(id.clone(), raw));
+                            items.push(Item::Text(conflict_block(&id, fresh)));
+                            outcome.conflicted_regions.push(id);
+                        }
+                        (_, false) => {
+                            items.push(Item::Region(id.clone(), raw));
+                            outcome.preserved_regions.push(id);
+                        }
+                    }
+                }
+                None => {
+                    for block in manual {
+                        items.push(Item::Text(block.lines));
+                    }
+                    outcome.removed_regions.push(id);
+                }
+            },
+        }
+    }
+
+    let generated_before: BTreeSet<String> = old.manifest.unwrap_or_default().into_iter().collect();
+    let region_index = |items: &[Item], wanted: &str| {
+        items
+            .iter()
+            .position(|item| matches!(item, Item::Region(id, _) if id == wanted))
+    };
+    for (index, (id, body)) in new_regions.iter().enumerate() {
+        if region_index(&items, id).is_some() {
+            continue;
+        }
+        if generated_before.contains(id) {
+            outcome.conflicts.push(id.clone());
+            continue;
+        }
+        let position = new_regions[..index]
+            .iter()
+            .rev()
+            .find_map(|(previous_id, _)| region_index(&items, previous_id).map(|at| at + 1))
+            .unwrap_or(0);
+        let position = items[position..]
+            .iter()
+            .position(|item| matches!(item, Item::Region(..)))
+            .map(|offset| position + offset)
+            .unwrap_or(items.len());
+        items.insert(position, Item::Text(vec![String::new()]));
+        items.insert(position, Item::Region(id.clone(), body.clone()));
+        outcome.added_regions.push(id.clone());
+    }
+
+    let mut text = String::new();
+    for item in &items {
+        match item {
+            Item::Text(lines) => {
+                for line in lines {
+                    text.push_str(line);
+                    text.push('\n');
+                }
+            }
+            Item::Region(id, body) => {
+                text.push_str(&format!("{BEGIN_PREFIX}{id} {COMMENT_SUFFIX}\n"));
+                for line in body {
+                    text.push_str(line);
+                    text.push('\n');
+                }
+                text.push_str(&format!("{END_PREFIX}{id} {COMMENT_SUFFIX}\n"));
+            }
+        }
+    }
+    text.push_str(&manifest_line(rendered));
+    outcome.text = text;
+    Ok(outcome)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn render(sections: &[(&str, &str)]) -> String {
+        let mut out = String::from("---\ntitle: \"Demo\"\n---\n\n");
+        for (id, body) in sections {
+            push_region(&mut out, id, body, true);
+        }
+        out.push_str(&manifest_line(&out.clone()));
+        out
+    }
+
+    #[test]
+    fn merge_keeps_manual_blocks_and_reports_deleted_regions() {
+        let original = render(&[
+            ("setup", "```{r setup}\nlibrary(here)\n```\n\n"),
+            ("models", "```{r m}\nm_1 <- lm(y ~ x, data = df)\n```\n\n"),
+            ("exports", "```{r exports}\nsave()\n```\n\n"),
+        ]);
+        let edited = original
+            .replace(
+                "library(here)\n",
+                "library(here)\n# rw:manual:begin\nlibrary(sandwich)\n# rw:manual:end\n",
+            )
+            .replace(
+                "<!-- rw:end:setup -->\n",
+                "<!-- rw:end:setup -->\n\nNotes kept between regions.\n",
+            );
+        let edited = {
+            let start = edited
+                .find("<!-- rw:begin:exports")
+                .expect("exports region");
+            let end = edited.find("<!-- rw:manifest").expect("manifest");
+            format!("{}{}", &edited[..start], &edited[end..])
+        };
+
+        let rerendered = render(&[
+            ("setup", "```{r setup}\nlibrary(here)\nlibrary(gt)\n```\n\n"),
+            ("descriptives", "```{r desc}\nsummary(df)\n```\n\n"),
+            (
+                "models",
+                "```{r m}\nm_1 <- lm(y ~ x + z, data = df)\n```\n\n",
+            ),
+            ("exports", "```{r exports}\nsave()\n```\n\n"),
+        ]);
+        let outcome = merge_regenerated(&edited, &rerendered, None).expect("merge");
+
+        assert!(outcome.text.contains(
+            "library(here)\n# rw:manual:begin\nlibrary(sandwich)\n# rw:manual:end\nlibrary(gt)\n"
+        ));
+        assert!(outcome.text.contains("Notes kept between regions."));
+        assert!(outcome.text.contains("m_1 <- lm(y ~ x + z, data = df)"));
+        assert!(!outcome.text.contains("save()"));
+        let notes = outcome.text.find("Notes kept").expect("notes");
+        let desc = outcome
+            .text
+            .find("<!-- rw:begin:descriptives")
+            .expect("desc");
+        assert!(notes < desc);
+        assert_eq!(outcome.added_regions, vec!["descriptives".to_string()]);
+        assert_eq!(outcome.conflicts, vec!["exports".to_string()]);
+        assert!(outcome
+            .text
+            .ends_with("<!-- rw:manifest: setup descriptives models exports -->\n"));
+    }
+
+    #[test]
+    fn merge_rejects_unmarked_or_broken_templates() {
+        let rendered = render(&[("setup", "x\n")]);
+        assert!(merge_regenerated("plain text\n", &rendered, None)
+            .expect_err("no regions")
+            .contains("no managed regions"));
+        assert!(
+            merge_regenerated("<!-- rw:begin:setup -->\nx\n", &rendered, None)
+                .expect_err("unterminated")
+                .contains("missing its end marker")
+        );
+    }
+
+    #[test]
+    fn edited_region_is_kept_with_regenerated_version_commented_below() {
+        let original = render(&[
+            ("setup", "```{r setup}\nlibrary(here)\n```\n\n"),
+            ("models", "```{r m}\nm_1 <- lm(y ~ x, data = df)\n```\n\n"),
+            ("plots", "```{r p}\nplot(df)\n```\n\n"),
+            ("exports", "```{r exports}\nsave()\n```\n\n"),
+        ]);
+        let meta: RegionMeta = serde_json::from_str(
+            &region_meta_json(Path::new("analysis.Rmd"), &original).expect("meta"),
+        )
+        .expect("parse meta");
+        let edited = original
+            .replace(
+                "m_1 <- lm(y ~ x, data = df)",
+                "m_1 <- lm(log(y) ~ x, data = df)",
+            )
+            .replace("plot(df)", "plot(df, main = \"Mine\")");
+
+        let rerendered = render(&[
+            ("setup", "```{r setup}\nlibrary(here)\nlibrary(gt)\n```\n\n"),
+            (
+                "models",
+                "```{r m}\nm_1 <- lm(y ~ x + z, data = df)\n```\n\n",
+            ),
+            ("plots", "```{r p}\nplot(df)\n```\n\n"),
+            ("exports", "```{r exports}\nsave()\n```\n\n"),
+        ]);
+        let outcome = merge_regenerated(&edited, &rerendered, Some(&meta.regions)).expect("merge");
+
+        assert_eq!(outcome.updated_regions, vec!["setup".to_string()]);
+        assert_eq!(outcome.conflicted_regions, vec!["models".to_string()]);
+        assert_eq!(
+            outcome.preserved_regions,
+            vec!["plots".to_string(), "exports".to_string()]
+        );
+        assert!(outcome.text.contains("library(gt)"));
+        assert!(outcome.text.contains("plot(df, main = \"Mine\")"));
+        let kept = outcome
+            .text
+            .find("m_1 <- lm(log(y) ~ x, data = df)")
+            .expect("edited model kept");
+        let end = outcome.text.find("<!-- rw:end:models").expect("end marker");
+        let block = outcome
+            .text
+            .find("<!-- rw:regenerated:models")
+            .expect("regenerated block");
+        let fresh = outcome
+            .text
+            .find("m_1 <- lm(y ~ x + z, data = df)")
+            .expect("fresh model");
+        assert!(kept < end && end < block && block < fresh);
+
+        let again = merge_regenerated(
+            &outcome.text,
+            &rerendered,
+            Some(
+                &serde_json::from_str::<RegionMeta>(
+                    &region_meta_json(Path::new("analysis.Rmd"), &rerendered).expect("meta"),
+                )
+                .expect("parse meta")
+                .regions,
+            ),
+        )
+        .expect("merge again");
+        assert!(again.conflicted_regions.is_empty());
+        assert_eq!(again.text.matches("rw:regenerated:models").count(), 1);
+    }
+}
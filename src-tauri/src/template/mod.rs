@@ -0,0 +1,4569 @@
+pub mod managed;
+pub mod models_manifest;
+pub mod presets;
+pub mod style_kit;
+pub mod style_profile;
+
+use chrono::Utc;
+use pathdiff::diff_paths;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::render::helpers::{
+    factor_coercion_r, model_table_extensions, r_path, r_string, write_files_atomically,
+    write_renv_scaffolding, MODEL_TABLE_DOCX_R, RENV_SETUP_R, RENV_SNAPSHOT_CHUNK,
+};
+use crate::store::activity::record_activity;
+use crate::store::sqlite::track_generated_artifact;
+use crate::store::{read_projects_store, resolve_study_root};
+use managed::{
+    manifest_line, merge_regenerated, push_region, read_region_hashes, region_meta_json,
+    region_meta_path,
+};
+use models_manifest::{manifest_json, manifest_path, ModelManifestEntry};
+use style_kit::{default_style_profile, ensure_project_style_kit, STYLE_PACKAGE_NAME};
+use style_profile::{StyleProfile, MINIMAL_GT_HELPERS_R, MODEL_TABLE_GT_DOCX_R};
+
+pub const ANALYSIS_FOLDER: &str = "06_analysis";
+/// Folder inside the analysis directory holding templates replaced by regeneration.
+const TRASH_FOLDER: &str = ".trash";
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ModelLayout {
+    name: String,
+    model_type: String,
+    outcome_var: String,
+    #[serde(default)]
+    treatment_var: Option<String>,
+    layout: String,
+    #[serde(default)]
+    interaction_var: Option<String>,
+    #[serde(default)]
+    covariates: Option<String>,
+    #[serde(default)]
+    id_var: Option<String>,
+    #[serde(default)]
+    time_var: Option<String>,
+    #[serde(default)]
+    figures: Vec<String>,
+    #[serde(default)]
+    include_in_main_table: bool,
+    /// "frequentist" (default) or "bayesian".
+    #[serde(default)]
+    estimation: Option<String>,
+    #[serde(default)]
+    contrasts: Vec<ContrastSpec>,
+    /// R logical expression restricting the model to a subset of rows.
+    #[serde(default)]
+    subset_filter: Option<String>,
+    /// Column of sampling or post-stratification weights passed to the fit.
+    #[serde(default, alias = "weightsVar")]
+    weight_var: Option<String>,
+    /// Mediator for "mediation" models: treatment -> mediator -> outcome.
+    #[serde(default)]
+    mediator_var: Option<String>,
+    /// Columns whose levels the model is refit within, as exploratory subgroup analyses.
+    #[serde(default)]
+    subgroup_vars: Vec<String>,
+    /// Excluded instruments for "iv" models.
+    #[serde(default)]
+    instrument_vars: Vec<String>,
+    /// Endogenous regressor for "iv" models; defaults to the treatment variable.
+    #[serde(default)]
+    endogenous_var: Option<String>,
+    /// Column standard errors are clustered on, e.g. participant or session.
+    #[serde(default)]
+    cluster_var: Option<String>,
+}
+
+/// A planned comparison over treatment levels; weights must align with levels and sum to zero.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ContrastSpec {
+    name: String,
+    levels: Vec<String>,
+    weights: Vec<f64>,
+}
+
+fn validate_contrast(contrast: &ContrastSpec) -> Result<(), String> {
+    let name = contrast.name.trim();
+    if name.is_empty() {
+        return Err("Contrast name is required.".to_string());
+    }
+    if contrast.levels.is_empty() || contrast.levels.len() != contrast.weights.len() {
+        return Err(format!(
+            "Contrast '{name}' must have one weight per level ({} levels, {} weights).",
+            contrast.levels.len(),
+            contrast.weights.len()
+        ));
+    }
+    let sum: f64 = contrast.weights.iter().sum();
+    if sum.abs() > 1e-9 {
+        return Err(format!(
+            "Contrast '{name}' weights must sum to zero (sum is {sum})."
+        ));
+    }
+    Ok(())
+}
+
+/// Superficial checks on a subset expression before it is pasted into R code.
+fn validate_subset_filter(expr: &str) -> Result<(), String> {
+    if expr.contains(';') || expr.contains('\n') || expr.contains('\r') {
+        return Err(
+            "Subset filter must be a single expression (no ';' or line breaks).".to_string(),
+        );
+    }
+    let shell_call = regex::Regex::new(r"\bsystem2?\s*\(").expect("regex");
+    if shell_call.is_match(expr) {
+        return Err("Subset filter must not call system().".to_string());
+    }
+    let mut depth: i32 = 0;
+    for ch in expr.chars() {
+        match ch {
+            '(' => depth += 1,
+            ')' => {
+                depth -= 1;
+                if depth < 0 {
+                    break;
+                }
+            }
+            _ => {}
+        }
+    }
+    if depth != 0 {
+        return Err("Subset filter has unbalanced parentheses.".to_string());
+    }
+    Ok(())
+}
+
+fn subset_filter(layout: &ModelLayout) -> Option<String> {
+    layout
+        .subset_filter
+        .as_ref()
+        .map(|value| value.trim().to_string())
+        .filter(|value| !value.is_empty())
+}
+
+/// Model types whose fits take a weight column. `rd` is accepted but only gets a TODO,
+/// since rdrobust expects the weights as a vector.
+const WEIGHTED_MODEL_TYPES: &[&str] = &[
+    "ols",
+    "logit",
+    "poisson",
+    "negbin",
+    "mixed_effects",
+    "fixed_effects",
+    "survival",
+    "rd",
+    "did",
+    "event_study",
+    "iv",
+];
+
+fn weight_var(layout: &ModelLayout) -> Option<String> {
+    layout
+        .weight_var
+        .as_ref()
+        .map(|value| value.trim().to_string())
+        .filter(|value| !value.is_empty())
+}
+
+/// Model types that report cluster-robust standard errors: `lm`/`glm` fits through
+/// `sandwich::vcovCL`, fixest fits through their own `vcov` argument.
+const CLUSTERED_MODEL_TYPES: &[&str] = &[
+    "ols",
+    "logit",
+    "poisson",
+    "fixed_effects",
+    "did",
+    "event_study",
+    "iv",
+];
+
+fn cluster_var(layout: &ModelLayout) -> Option<String> {
+    layout
+        .cluster_var
+        .as_ref()
+        .map(|value| value.trim().to_string())
+        .filter(|value| !value.is_empty())
+}
+
+fn is_plain_column(name: &str) -> bool {
+    name.chars().next().is_some_and(|c| c.is_ascii_alphabetic())
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '.')
+}
+
+fn subgroup_vars(layout: &ModelLayout) -> Vec<String> {
+    layout
+        .subgroup_vars
+        .iter()
+        .map(|value| value.trim().to_string())
+        .filter(|value| !value.is_empty())
+        .collect()
+}
+
+fn instrument_vars(layout: &ModelLayout) -> Vec<String> {
+    layout
+        .instrument_vars
+        .iter()
+        .map(|value| value.trim().to_string())
+        .filter(|value| !value.is_empty())
+        .collect()
+}
+
+fn validate_weight_var(layout: &ModelLayout, weight: &str) -> Result<(), String> {
+    if !is_plain_column(weight) {
+        return Err(format!(
+            "Weight variable '{weight}' must be a plain column name."
+        ));
+    }
+    let model_type = layout.model_type.trim();
+    if !WEIGHTED_MODEL_TYPES.contains(&model_type) {
+        return Err(format!(
+            "Model type '{model_type}' does not support a weight variable."
+        ));
+    }
+    Ok(())
+}
+
+fn validate_cluster_var(layout: &ModelLayout, cluster: &str) -> Result<(), String> {
+    if !is_plain_column(cluster) {
+        return Err(format!(
+            "Cluster variable '{cluster}' must be a plain column name."
+        ));
+    }
+    let model_type = layout.model_type.trim();
+    if !CLUSTERED_MODEL_TYPES.contains(&model_type) {
+        return Err(format!(
+            "Model type '{model_type}' does not support clustered standard errors."
+        ));
+    }
+    Ok(())
+}
+
+fn validate_model_layouts(options: &AnalysisTemplateOptions) -> Result<(), String> {
+    for layout in &options.model_layouts {
+        if let Some(cluster) = cluster_var(layout) {
+            validate_cluster_var(layout, &cluster)
+                .map_err(|err| format!("Model layout '{}': {err}", layout.name.trim()))?;
+        }
+        if let Some(weight) = weight_var(layout) {
+            validate_weight_var(layout, &weight)
+                .map_err(|err| format!("Model layout '{}': {err}", layout.name.trim()))?;
+        }
+        if let Some(expr) = subset_filter(layout) {
+            validate_subset_filter(&expr)
+                .map_err(|err| format!("Model layout '{}': {err}", layout.name.trim()))?;
+        }
+        for contrast in &layout.contrasts {
+            validate_contrast(contrast)
+                .map_err(|err| format!("Model layout '{}': {err}", layout.name.trim()))?;
+        }
+        if let Some(column) = subgroup_vars(layout)
+            .into_iter()
+            .find(|column| !is_plain_column(column))
+        {
+            return Err(format!(
+                "Model layout '{}': Subgroup variable '{column}' must be a plain column name.",
+                layout.name.trim()
+            ));
+        }
+        if let Some(column) = instrument_vars(layout)
+            .into_iter()
+            .chain(layout.endogenous_var.iter().map(|v| v.trim().to_string()))
+            .find(|column| !column.is_empty() && !is_plain_column(column))
+        {
+            return Err(format!(
+                "Model layout '{}': Instrument variable '{column}' must be a plain column name.",
+                layout.name.trim()
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// Cut points and columns for the `winsorize` robustness check.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct WinsorizeParams {
+    lower: f64,
+    upper: f64,
+    /// Columns to winsorize; empty means every model outcome.
+    variables: Vec<String>,
+}
+
+impl Default for WinsorizeParams {
+    fn default() -> Self {
+        Self {
+            lower: 0.01,
+            upper: 0.99,
+            variables: Vec::new(),
+        }
+    }
+}
+
+/// Parameters for robustness checks that apply across all model layouts.
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct RobustnessParams {
+    winsorize: WinsorizeParams,
+}
+
+fn validate_robustness_params(options: &AnalysisTemplateOptions) -> Result<(), String> {
+    let winsorize = &options.robustness_params.winsorize;
+    if selected(&options.robustness, "winsorize")
+        && !(0.0 <= winsorize.lower && winsorize.lower < winsorize.upper && winsorize.upper <= 1.0)
+    {
+        return Err(format!(
+            "Winsorize cut points must satisfy 0 <= lower < upper <= 1 (got {} and {}).",
+            winsorize.lower, winsorize.upper
+        ));
+    }
+    Ok(())
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct BayesOptions {
+    chains: u32,
+    iter: u32,
+    seed: u64,
+}
+
+impl Default for BayesOptions {
+    fn default() -> Self {
+        Self {
+            chains: 4,
+            iter: 2000,
+            seed: 1234,
+        }
+    }
+}
+
+/// Missing fields take their defaults, so options merged from a saved preset always parse.
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct AnalysisTemplateOptions {
+    analysis_file_name: Option<String>,
+    data_source_paths: Option<Vec<String>>,
+    /// Sheet name or 1-based number read from `.xlsx`/`.xls` data sources.
+    #[serde(default)]
+    data_source_sheet: Option<String>,
+    dataset_path_hint: Option<String>,
+    outcome_var_hint: Option<String>,
+    treatment_var_hint: Option<String>,
+    id_var_hint: Option<String>,
+    time_var_hint: Option<String>,
+    group_var_hint: Option<String>,
+    descriptives: Vec<String>,
+    plots: Vec<String>,
+    balance_checks: Vec<String>,
+    models: Vec<String>,
+    diagnostics: Vec<String>,
+    tables: Vec<String>,
+    robustness: Vec<String>,
+    #[serde(default)]
+    robustness_params: RobustnessParams,
+    #[serde(default)]
+    model_layouts: Vec<ModelLayout>,
+    #[serde(default)]
+    model_table_format: Option<String>,
+    /// Values observed per column (e.g. QSF embedded-data defaults), used for factor levels.
+    #[serde(default)]
+    expected_values: BTreeMap<String, Vec<String>>,
+    #[serde(default)]
+    bayes_options: BayesOptions,
+    /// Quick-template style profile ("apa" or "minimal_gt"); falls back to the project default.
+    #[serde(default)]
+    style_profile: Option<String>,
+    /// Skips the `rw:begin`/`rw:end` comments around generated sections; such templates
+    /// cannot be regenerated in place.
+    #[serde(default)]
+    omit_region_markers: bool,
+    /// Adds a post-model chunk with standardized effect sizes from the `effectsize` package.
+    #[serde(default)]
+    effect_sizes: bool,
+    /// "rmd" (default) or "qmd" for a Quarto document.
+    #[serde(default)]
+    output_format: Option<String>,
+    /// Restores packages from `renv.lock` in the setup chunk and scaffolds `.Rprofile` and
+    /// `renv/activate.R` next to the template.
+    #[serde(default)]
+    use_renv: bool,
+    exploratory: bool,
+    export_artifacts: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TemplateFormat {
+    Rmd,
+    Qmd,
+}
+
+impl TemplateFormat {
+    pub const ALL: [TemplateFormat; 2] = [TemplateFormat::Rmd, TemplateFormat::Qmd];
+
+    /// Parses a format name; a missing or blank name selects R Markdown.
+    pub fn parse(value: Option<&str>) -> Result<Self, String> {
+        match value.map(|v| v.trim().to_lowercase()).as_deref() {
+            None | Some("") | Some("rmd") => Ok(TemplateFormat::Rmd),
+            Some("qmd") => Ok(TemplateFormat::Qmd),
+            Some(other) => Err(format!(
+                "Unknown output format '{other}'. Available formats: rmd, qmd."
+            )),
+        }
+    }
+
+    pub fn extension(self) -> &'static str {
+        match self {
+            TemplateFormat::Rmd => "Rmd",
+            TemplateFormat::Qmd => "qmd",
+        }
+    }
+
+    fn from_path(path: &Path) -> Option<Self> {
+        let ext = path.extension()?.to_str()?;
+        Self::ALL
+            .into_iter()
+            .find(|format| format.extension().eq_ignore_ascii_case(ext))
+    }
+}
+
+/// Format for rendering; names are validated before rendering, so unknown values mean Rmd.
+fn template_format(options: &AnalysisTemplateOptions) -> TemplateFormat {
+    TemplateFormat::parse(options.output_format.as_deref()).unwrap_or(TemplateFormat::Rmd)
+}
+
+/// Splits a template name into its base and the format named by an `.Rmd`/`.qmd` suffix.
+fn split_template_name(name: &str) -> (&str, Option<TemplateFormat>) {
+    match TemplateFormat::from_path(Path::new(name)) {
+        Some(format) => (
+            &name[..name.len() - format.extension().len() - 1],
+            Some(format),
+        ),
+        None => (name, None),
+    }
+}
+
+/// Rewrites knitr chunk headers such as `{r setup, include=FALSE}` as a bare `{r}` followed
+/// by Quarto `#|` option lines.
+fn quarto_chunk_options(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for line in text.split_inclusive('\n') {
+        let Some(inner) = line
+            .trim_end()
+            .strip_prefix("```{r")
+            .and_then(|rest| rest.strip_suffix('}'))
+        else {
+            out.push_str(line);
+            continue;
+        };
+        out.push_str("```{r}\n");
+        for (idx, part) in inner.split(',').map(str::trim).enumerate() {
+            match part.split_once('=') {
+                Some((key, value)) => {
+                    let value = match value.trim() {
+                        "TRUE" => "true".to_string(),
+                        "FALSE" => "false".to_string(),
+                        other => other.to_string(),
+                    };
+                    out.push_str(&format!("#| {}: {value}\n", key.trim().replace('.', "-")));
+                }
+                None if idx == 0 && !part.is_empty() => {
+                    out.push_str(&format!("#| label: {part}\n"));
+                }
+                None => {}
+            }
+        }
+    }
+    out
+}
+
+/// Profile for rendering; names are validated before rendering, so unknown values mean APA.
+fn style_profile(options: &AnalysisTemplateOptions) -> StyleProfile {
+    StyleProfile::parse(options.style_profile.as_deref()).unwrap_or(StyleProfile::Apa)
+}
+
+fn region_markers(options: &AnalysisTemplateOptions) -> bool {
+    !options.omit_region_markers
+}
+
+fn add_package(packages: &mut Vec<String>, value: &str) {
+    if !packages.iter().any(|item| item == value) {
+        packages.push(value.to_string());
+    }
+}
+
+fn selected(values: &[String], key: &str) -> bool {
+    values.iter().any(|value| value == key)
+}
+
+fn collect_model_types(options: &AnalysisTemplateOptions) -> Vec<String> {
+    let mut out: Vec<String> = Vec::new();
+    for layout in &options.model_layouts {
+        if !layout.model_type.trim().is_empty() && !out.iter().any(|m| m == &layout.model_type) {
+            out.push(layout.model_type.clone());
+        }
+    }
+    out
+}
+
+fn selected_model(options: &AnalysisTemplateOptions, key: &str) -> bool {
+    collect_model_types(options)
+        .iter()
+        .any(|value| value == key)
+}
+
+/// brms family for a model type, or `None` when no Bayesian scaffold exists for it.
+fn brms_family(model_type: &str) -> Option<&'static str> {
+    match model_type {
+        "ols" | "mixed_effects" => Some("gaussian()"),
+        "logit" => Some("bernoulli()"),
+        "poisson" => Some("poisson()"),
+        "negbin" => Some("negbinomial()"),
+        _ => None,
+    }
+}
+
+fn is_bayesian(layout: &ModelLayout) -> bool {
+    layout
+        .estimation
+        .as_deref()
+        .is_some_and(|value| value.trim().eq_ignore_ascii_case("bayesian"))
+}
+
+fn uses_bayesian_models(options: &AnalysisTemplateOptions) -> bool {
+    options
+        .model_layouts
+        .iter()
+        .any(|layout| is_bayesian(layout) && brms_family(layout.model_type.trim()).is_some())
+}
+
+fn model_outcomes(options: &AnalysisTemplateOptions, fallback: &str) -> Vec<String> {
+    let mut out: Vec<String> = Vec::new();
+    for layout in &options.model_layouts {
+        let value = layout.outcome_var.trim();
+        if !value.is_empty() && !out.iter().any(|item| item == value) {
+            out.push(value.to_string());
+        }
+    }
+    if out.is_empty() {
+        out.push(fallback.to_string());
+    }
+    out
+}
+
+fn primary_treatment_from_models(options: &AnalysisTemplateOptions, fallback: &str) -> String {
+    for layout in &options.model_layouts {
+        if let Some(value) = &layout.treatment_var {
+            let trimmed = value.trim();
+            if !trimmed.is_empty() {
+                return trimmed.to_string();
+            }
+        }
+    }
+    fallback.to_string()
+}
+
+fn primary_group_from_models(options: &AnalysisTemplateOptions, fallback: &str) -> String {
+    for layout in &options.model_layouts {
+        if let Some(value) = &layout.interaction_var {
+            let trimmed = value.trim();
+            if !trimmed.is_empty() {
+                return trimmed.to_string();
+            }
+        }
+    }
+    fallback.to_string()
+}
+
+fn safe_token(value: &str, fallback: &str) -> String {
+    let mut out = String::new();
+    for c in value.chars() {
+        if c.is_ascii_alphanumeric() || c == '_' {
+            out.push(c);
+        } else {
+            out.push('_');
+        }
+    }
+    let out = out.trim_matches('_').to_string();
+    if out.is_empty() {
+        fallback.to_string()
+    } else {
+        out
+    }
+}
+
+fn hint_or_default(value: &Option<String>, fallback: &str) -> String {
+    value
+        .as_ref()
+        .map(|item| item.trim())
+        .filter(|item| !item.is_empty())
+        .unwrap_or(fallback)
+        .to_string()
+}
+
+fn analysis_output_here_expr(project_root: &Path, study_root: &Path) -> String {
+    let output_root = study_root.join("07_outputs");
+    if let Some(rel) = diff_paths(&output_root, project_root) {
+        let parts: Vec<String> = rel
+            .components()
+            .map(|component| r_string(&component.as_os_str().to_string_lossy()))
+            .collect();
+        if !parts.is_empty() {
+            return format!("here::here({})", parts.join(", "));
+        }
+    }
+    r_path(&output_root.to_string_lossy())
+}
+
+fn normalized_analysis_file_base(value: &Option<String>) -> Result<String, String> {
+    let mut base = value
+        .as_ref()
+        .map(|item| item.trim().to_string())
+        .unwrap_or_else(|| "analysis".to_string());
+    if base.is_empty() {
+        base = "analysis".to_string();
+    }
+    let stem_len = split_template_name(&base).0.len();
+    if stem_len > 0 {
+        base.truncate(stem_len);
+    }
+    if base.trim().is_empty() {
+        return Err("Analysis file name cannot be empty.".to_string());
+    }
+    ensure_single_file_name(&base, "Analysis file name")?;
+    Ok(base)
+}
+
+fn ensure_single_file_name(value: &str, what: &str) -> Result<(), String> {
+    if value.contains('/') || value.contains('\\') || value.contains("..") {
+        return Err(format!("{what} must be a single file name."));
+    }
+    Ok(())
+}
+
+/// Extensions `read_data_source` in the generated import chunk can read.
+const SUPPORTED_DATA_EXTENSIONS: &[&str] = &[
+    "csv", "tsv", "txt", "rds", "sav", "zsav", "dta", "xlsx", "xls",
+];
+
+fn data_source_paths(options: &AnalysisTemplateOptions) -> Vec<String> {
+    options
+        .data_source_paths
+        .as_ref()
+        .map(|values| {
+            values
+                .iter()
+                .map(|value| value.trim())
+                .filter(|value| !value.is_empty())
+                .map(|value| value.replace('\\', "/"))
+                .collect::<Vec<String>>()
+        })
+        .unwrap_or_default()
+}
+
+fn has_data_source_extension(options: &AnalysisTemplateOptions, extensions: &[&str]) -> bool {
+    data_source_paths(options).iter().any(|path| {
+        Path::new(path)
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .is_some_and(|ext| extensions.iter().any(|e| ext.eq_ignore_ascii_case(e)))
+    })
+}
+
+fn uses_labelled_sources(options: &AnalysisTemplateOptions) -> bool {
+    has_data_source_extension(options, &["sav", "zsav", "dta"])
+}
+
+/// `, sheet = ...` for `readxl::read_excel`; numbers stay numeric, names are quoted.
+fn excel_sheet_arg(options: &AnalysisTemplateOptions) -> String {
+    match options
+        .data_source_sheet
+        .as_deref()
+        .map(str::trim)
+        .filter(|sheet| !sheet.is_empty())
+    {
+        Some(sheet) if sheet.parse::<u32>().is_ok() => format!(", sheet = {sheet}"),
+        Some(sheet) => format!(", sheet = {}", r_string(sheet)),
+        None => String::new(),
+    }
+}
+
+/// Packages every generated analysis loads before style profiles and options add or drop any.
+pub const DEFAULT_PACKAGES: &[&str] = &[
+    "tidyverse",
+    "here",
+    "janitor",
+    "ggplot2",
+    "ggpubr",
+    "gganimate",
+    "flextable",
+    "modelsummary",
+    "broom",
+    "gt",
+    "kableExtra",
+];
+
+fn render_packages(options: &AnalysisTemplateOptions) -> String {
+    let profile = style_profile(options);
+    let mut packages: Vec<String> = DEFAULT_PACKAGES.iter().map(|p| p.to_string()).collect();
+    packages.retain(|package| !profile.unused_packages().contains(&package.as_str()));
+
+    // Conditional additions are sorted after the built-ins so that reordering
+    // model layouts or options never reshuffles the package block.
+    let mut extra: Vec<String> = Vec::new();
+
+    if selected(&options.descriptives, "missingness") {
+        add_package(&mut extra, "naniar");
+    }
+    if uses_labelled_sources(options) {
+        add_package(&mut extra, "haven");
+    }
+    if has_data_source_extension(options, &["xlsx", "xls"]) {
+        add_package(&mut extra, "readxl");
+    }
+    if selected(&options.plots, "correlation_heatmap") {
+        add_package(&mut extra, "reshape2");
+    }
+    if selected_model(options, "ols")
+        || selected(&options.diagnostics, "linearity")
+        || selected(&options.diagnostics, "multicollinearity")
+    {
+        add_package(&mut extra, "car");
+    }
+    if selected_model(options, "ols") || selected(&options.diagnostics, "homoskedasticity") {
+        add_package(&mut extra, "lmtest");
+        add_package(&mut extra, "sandwich");
+        add_package(&mut extra, "performance");
+    }
+    if selected_model(options, "logit")
+        || selected_model(options, "poisson")
+        || selected_model(options, "negbin")
+        || selected(&options.diagnostics, "overdispersion")
+    {
+        add_package(&mut extra, "performance");
+        add_package(&mut extra, "pscl");
+    }
+    if selected_model(options, "negbin") {
+        add_package(&mut extra, "MASS");
+    }
+    if selected_model(options, "mixed_effects") {
+        add_package(&mut extra, "lme4");
+        add_package(&mut extra, "broom.mixed");
+    }
+    if selected_model(options, "fixed_effects")
+        || selected_model(options, "iv")
+        || selected_model(options, "did")
+        || selected_model(options, "event_study")
+        || selected(&options.diagnostics, "parallel_trends")
+    {
+        add_package(&mut extra, "fixest");
+    }
+    if selected_model(options, "survival") {
+        add_package(&mut extra, "survival");
+        add_package(&mut extra, "survminer");
+    }
+    if selected_model(options, "rd") || selected(&options.diagnostics, "bandwidth_sensitivity") {
+        add_package(&mut extra, "rdrobust");
+    }
+    if selected_model(options, "mediation") {
+        add_package(&mut extra, "mediation");
+    }
+    if model_table_extensions(options.model_table_format.as_deref()).contains(&"docx") {
+        add_package(&mut extra, profile.docx_package());
+    }
+    if options
+        .model_layouts
+        .iter()
+        .any(|layout| !layout.contrasts.is_empty())
+    {
+        add_package(&mut extra, "emmeans");
+    }
+    if marginal_effects_selected(options) {
+        add_package(&mut extra, "marginaleffects");
+    }
+    if options.effect_sizes && !options.model_layouts.is_empty() {
+        add_package(&mut extra, "effectsize");
+        if selected_model(options, "ols") {
+            add_package(&mut extra, "car");
+        }
+    }
+    if options
+        .model_layouts
+        .iter()
+        .any(|layout| weight_var(layout).is_some())
+    {
+        add_package(&mut extra, "survey");
+    }
+    if selected(&options.robustness, "winsorize") {
+        add_package(&mut extra, "DescTools");
+    }
+    if uses_bayesian_models(options) {
+        add_package(&mut extra, "brms");
+        add_package(&mut extra, "bayesplot");
+    }
+    extra.sort_by_key(|package| package.to_lowercase());
+    for package in &extra {
+        add_package(&mut packages, package);
+    }
+
+    let mut out = String::new();
+    out.push_str("# Packages\n\n");
+    out.push_str("```{r packages, message=FALSE, warning=FALSE}\n");
+    out.push_str("# TODO: install packages as needed.\n");
+    out.push_str("# install.packages(c(");
+    out.push_str(
+        &packages
+            .iter()
+            .map(|item| format!("\"{item}\""))
+            .collect::<Vec<String>>()
+            .join(", "),
+    );
+    out.push_str("))\n");
+    for package in packages {
+        out.push_str(&format!("library({package})\n"));
+    }
+    out.push_str("```\n\n");
+    out
+}
+
+/// Weight for descriptive tables: only used when every weighted layout shares one column.
+fn descriptives_weight(options: &AnalysisTemplateOptions) -> Option<String> {
+    let weights: BTreeSet<String> = options
+        .model_layouts
+        .iter()
+        .filter_map(weight_var)
+        .collect();
+    if weights.len() == 1 {
+        weights.into_iter().next()
+    } else {
+        None
+    }
+}
+
+fn render_descriptives(
+    options: &AnalysisTemplateOptions,
+    outcomes: &[String],
+    treatment: &str,
+    group: &str,
+) -> String {
+    if options.descriptives.is_empty() && options.plots.is_empty() {
+        return String::new();
+    }
+    let profile = style_profile(options);
+    let table_fn = profile.table_fn();
+    let mut out = String::new();
+    out.push_str("# Descriptives\n\n");
+    let weight = descriptives_weight(options);
+    if let Some(weight) = &weight {
+        out.push_str(&format!(
+            "Weighted means use `{weight}`, the weight set on the model layouts; other statistics are unweighted.\n\n"
+        ));
+    }
+
+    if selected(&options.tables, "table1_descriptives") {
+        out.push_str("```{r descriptives_table1}\n");
+        out.push_str("table1_descriptives_df <- modelsummary::datasummary(\n");
+        let statistics = match &weight {
+            Some(weight) => format!(
+                "Mean + SD + Heading(\"Weighted mean\") * weighted.mean * Arguments(w = {weight}, na.rm = TRUE)"
+            ),
+            None => "Mean + SD".to_string(),
+        };
+        out.push_str(&format!(
+            "  as.formula({}),\n",
+            r_string(&format!(
+                "{} ~ {group} * ({statistics})",
+                outcomes.join(" + ")
+            ))
+        ));
+        out.push_str("  df,\n");
+        out.push_str("  output = \"data.frame\"\n");
+        out.push_str(")\n");
+        out.push_str(&format!(
+            "table1_descriptives_ft <- {table_fn}(table1_descriptives_df)\n"
+        ));
+        out.push_str("table1_descriptives_ft\n");
+        out.push_str("```\n\n");
+    }
+
+    if selected(&options.descriptives, "summary_stats") {
+        out.push_str("```{r descriptives_summary_stats}\n");
+        out.push_str(&format!(
+            "summary_stats_ft <- {}(\n",
+            profile.descriptives_table_fn()
+        ));
+        out.push_str("  df,\n");
+        out.push_str("  digits = 2\n");
+        out.push_str(")\n");
+        out.push_str("summary_stats_ft\n");
+        out.push_str("```\n\n");
+    }
+    if selected(&options.descriptives, "counts") {
+        out.push_str("```{r descriptives_counts}\n");
+        out.push_str("n_obs <- nrow(df)\n");
+        out.push_str(&format!("n_ids <- dplyr::n_distinct(df${treatment})\n"));
+        out.push_str(&format!("counts_by_group <- df %>% count({treatment})\n"));
+        out.push_str("counts_tbl <- tibble::tibble(\n");
+        out.push_str("  Metric = c(\"N observations\", \"N IDs\"),\n");
+        out.push_str("  Value = c(n_obs, n_ids)\n");
+        out.push_str(")\n");
+        out.push_str(&format!("{table_fn}(counts_tbl)\n"));
+        out.push_str(&format!("{table_fn}(counts_by_group)\n"));
+        out.push_str("```\n\n");
+    }
+    if selected(&options.descriptives, "missingness") {
+        out.push_str("```{r descriptives_missingness}\n");
+        out.push_str("missing_summary <- naniar::miss_var_summary(df)\n");
+        out.push_str("missing_summary\n");
+        out.push_str("```\n\n");
+    }
+    if selected(&options.descriptives, "group_summary") {
+        out.push_str("```{r descriptives_group_summary}\n");
+        out.push_str(&format!("group_summary <- df %>% group_by({group}) %>%\n"));
+        out.push_str(
+            "  summarise(across(where(is.numeric), ~mean(.x, na.rm = TRUE)), .groups = \"drop\")\n",
+        );
+        out.push_str(&format!("{table_fn}(group_summary)\n"));
+        out.push_str("```\n\n");
+    }
+    if selected(&options.descriptives, "correlations") {
+        out.push_str("```{r descriptives_correlations}\n");
+        out.push_str("cor_matrix <- df %>% select(where(is.numeric)) %>% cor(use = \"pairwise.complete.obs\")\n");
+        out.push_str("cor_matrix\n");
+        out.push_str("```\n\n");
+    }
+
+    if selected(&options.plots, "histogram") {
+        for outcome in outcomes {
+            let token = safe_token(outcome, "outcome");
+            out.push_str(&format!("```{{r descriptives_plot_histogram_{token}}}\n"));
+            out.push_str(&format!(
+                "p_hist_{token} <- apa_hist(df, {outcome}, bins = 30)\n"
+            ));
+            out.push_str(&format!("p_hist_{token}\n"));
+            out.push_str("```\n\n");
+        }
+    }
+    if selected(&options.plots, "boxplot") {
+        for outcome in outcomes {
+            let token = safe_token(outcome, "outcome");
+            out.push_str(&format!("```{{r descriptives_plot_boxplot_{token}}}\n"));
+            out.push_str(&format!(
+                "p_box_{token} <- apa_box(df, {treatment}, {outcome})\n"
+            ));
+            out.push_str(&format!("p_box_{token}\n"));
+            out.push_str("```\n\n");
+        }
+    }
+    if selected(&options.plots, "density") {
+        for outcome in outcomes {
+            let token = safe_token(outcome, "outcome");
+            out.push_str(&format!("```{{r descriptives_plot_density_{token}}}\n"));
+            out.push_str(&format!(
+                "p_density_{token} <- ggplot(df, aes(x = {outcome})) + geom_density()\n"
+            ));
+            out.push_str(&format!("p_density_{token}\n"));
+            out.push_str("```\n\n");
+        }
+    }
+    if selected(&options.plots, "scatter") {
+        for outcome in outcomes {
+            let token = safe_token(outcome, "outcome");
+            out.push_str(&format!("```{{r descriptives_plot_scatter_{token}}}\n"));
+            out.push_str(&format!(
+                "p_scatter_{token} <- apa_scatter(df, {treatment}, {outcome}, add_lm = TRUE)\n"
+            ));
+            out.push_str(&format!("p_scatter_{token}\n"));
+            out.push_str("```\n\n");
+        }
+    }
+    if selected(&options.plots, "qqplot") {
+        for outcome in outcomes {
+            let token = safe_token(outcome, "outcome");
+            out.push_str(&format!("```{{r descriptives_plot_qq_{token}}}\n"));
+            out.push_str(&format!("p_qq_{token} <- ggplot(df, aes(sample = {outcome})) + stat_qq() + stat_qq_line()\n"));
+            out.push_str(&format!("p_qq_{token}\n"));
+            out.push_str("```\n\n");
+        }
+    }
+    if selected(&options.plots, "correlation_heatmap") {
+        out.push_str("```{r descriptives_plot_corr_heatmap}\n");
+        out.push_str("cor_matrix <- df %>% select(where(is.numeric)) %>% cor(use = \"pairwise.complete.obs\")\n");
+        out.push_str("cor_long <- reshape2::melt(cor_matrix)\n");
+        out.push_str("p_corr <- ggplot(cor_long, aes(x = Var1, y = Var2, fill = value)) +\n");
+        out.push_str("  geom_tile() +\n");
+        out.push_str("  scale_fill_gradient2()\n");
+        out.push_str("p_corr\n");
+        out.push_str("```\n\n");
+    }
+
+    out
+}
+
+fn render_balance_checks(options: &AnalysisTemplateOptions, treatment: &str) -> String {
+    if options.balance_checks.is_empty() {
+        return String::new();
+    }
+    let mut out = String::new();
+    out.push_str("# Balance Checks\n\n");
+
+    if selected(&options.balance_checks, "baseline_table") {
+        out.push_str("```{r balance_baseline_table}\n");
+        out.push_str(&format!(
+            "baseline_tbl <- modelsummary::datasummary_balance(~ {treatment}, data = df)\n"
+        ));
+        out.push_str("baseline_tbl\n");
+        out.push_str("```\n\n");
+    }
+    if selected(&options.balance_checks, "std_diff") {
+        out.push_str("```{r balance_std_diff}\n");
+        out.push_str("std_diff <- function(x, g) {\n");
+        out.push_str("  m1 <- mean(x[g == 1], na.rm = TRUE)\n");
+        out.push_str("  m0 <- mean(x[g == 0], na.rm = TRUE)\n");
+        out.push_str("  s <- sd(x, na.rm = TRUE)\n");
+        out.push_str("  (m1 - m0) / s\n");
+        out.push_str("}\n");
+        out.push_str(&format!(
+            "# TODO: apply std_diff across covariates using {treatment}\n"
+        ));
+        out.push_str("```\n\n");
+    }
+    if selected(&options.balance_checks, "randomization_check") {
+        out.push_str("```{r balance_randomization_check}\n");
+        out.push_str(
+            "# TODO: regress baseline covariates on treatment and inspect joint significance.\n",
+        );
+        out.push_str(&format!("# lm(covariate ~ {treatment}, data = df)\n"));
+        out.push_str("```\n\n");
+    }
+
+    out
+}
+
+/// One generated model chunk, with layout fields trimmed and defaults filled in.
+#[derive(Clone)]
+struct ModelPlan {
+    name: String,
+    model_type: String,
+    outcome_var: String,
+    treatment_var: String,
+    layout: String,
+    interaction_var: String,
+    covariates: String,
+    id_var: String,
+    time_var: String,
+    figures: Vec<String>,
+    include_in_main_table: bool,
+    bayesian: bool,
+    contrasts: Vec<ContrastSpec>,
+    subset_filter: Option<String>,
+    weight_var: Option<String>,
+    mediator_var: Option<String>,
+    subgroup_vars: Vec<String>,
+    instrument_vars: Vec<String>,
+    /// Instrumented regressor of an "iv" model: the layout's endogenous variable or the treatment.
+    endogenous_var: String,
+    /// The layout's cluster variable; IV models fall back to the layout's own id variable
+    /// (hints are not used).
+    cluster_var: Option<String>,
+}
+
+fn model_plans(
+    options: &AnalysisTemplateOptions,
+    treatment: &str,
+    id: &str,
+    time: &str,
+) -> Vec<ModelPlan> {
+    let mut plans: Vec<ModelPlan> = Vec::new();
+    for (idx, layout) in options.model_layouts.iter().enumerate() {
+        let outcome_var = layout.outcome_var.trim();
+        if outcome_var.is_empty() {
+            continue;
+        }
+        let model_type = layout.model_type.trim().to_string();
+        if model_type.is_empty() {
+            continue;
+        }
+        let name = if layout.name.trim().is_empty() {
+            format!("model_{}", idx + 1)
+        } else {
+            layout.name.trim().to_string()
+        };
+        let treatment_var = layout
+            .treatment_var
+            .as_ref()
+            .map(|v| v.trim().to_string())
+            .filter(|v| !v.is_empty())
+            .unwrap_or_else(|| treatment.to_string());
+        let layout_id = layout
+            .id_var
+            .as_ref()
+            .map(|v| v.trim().to_string())
+            .filter(|v| !v.is_empty());
+        let cluster_var = cluster_var(layout).or_else(|| {
+            if model_type == "iv" {
+                layout_id.clone()
+            } else {
+                None
+            }
+        });
+        plans.push(ModelPlan {
+            name,
+            model_type,
+            outcome_var: outcome_var.to_string(),
+            endogenous_var: layout
+                .endogenous_var
+                .as_ref()
+                .map(|v| v.trim().to_string())
+                .filter(|v| !v.is_empty())
+                .unwrap_or_else(|| treatment_var.clone()),
+            treatment_var,
+            layout: layout.layout.trim().to_string(),
+            interaction_var: layout.interaction_var.clone().unwrap_or_default(),
+            covariates: layout.covariates.clone().unwrap_or_default(),
+            id_var: layout_id.unwrap_or_else(|| id.to_string()),
+            time_var: layout
+                .time_var
+                .as_ref()
+                .map(|v| v.trim().to_string())
+                .filter(|v| !v.is_empty())
+                .unwrap_or_else(|| time.to_string()),
+            figures: layout.figures.clone(),
+            include_in_main_table: layout.include_in_main_table,
+            bayesian: is_bayesian(layout),
+            contrasts: layout.contrasts.clone(),
+            subset_filter: subset_filter(layout),
+            weight_var: weight_var(layout),
+            mediator_var: layout
+                .mediator_var
+                .as_ref()
+                .map(|v| v.trim().to_string())
+                .filter(|v| !v.is_empty()),
+            subgroup_vars: subgroup_vars(layout),
+            instrument_vars: instrument_vars(layout),
+            cluster_var,
+        });
+    }
+    plans
+}
+
+fn model_chunk_id(idx: usize, plan: &ModelPlan) -> String {
+    safe_token(
+        &format!("model_{}_{}", idx + 1, plan.name.to_lowercase()),
+        &format!("model_{}", idx + 1),
+    )
+}
+
+/// Manifest entries for the model chunks `render_analysis_rmd` generates from `options`.
+fn template_models_manifest(options: &AnalysisTemplateOptions) -> Vec<ModelManifestEntry> {
+    let treatment_hint = hint_or_default(&options.treatment_var_hint, "treat");
+    let treatment = primary_treatment_from_models(options, &treatment_hint);
+    let id = hint_or_default(&options.id_var_hint, "id");
+    let time = hint_or_default(&options.time_var_hint, "time");
+    model_plans(options, &treatment, &id, &time)
+        .iter()
+        .enumerate()
+        .map(|(idx, plan)| ModelManifestEntry {
+            name: plan.name.clone(),
+            model_type: plan.model_type.clone(),
+            outcome: plan.outcome_var.clone(),
+            treatment: plan.treatment_var.clone(),
+            covariates: plan.covariates.trim().to_string(),
+            layout: plan.layout.clone(),
+            figures: plan.figures.clone(),
+            include_in_main_table: plan.include_in_main_table,
+            chunk_id: model_chunk_id(idx, plan),
+        })
+        .collect()
+}
+
+fn render_models(
+    options: &AnalysisTemplateOptions,
+    _outcome: &str,
+    treatment: &str,
+    id: &str,
+    time: &str,
+) -> String {
+    let profile = style_profile(options);
+    let markers = region_markers(options);
+    let mut out = String::new();
+    out.push_str("# Main Analyses\n\n");
+
+    let plans = model_plans(options, treatment, id, time);
+    if plans.is_empty() {
+        out.push_str("```{r model_none}\n");
+        out.push_str(
+            "# TODO: Add at least one Model Layout in the model builder to generate analyses.\n",
+        );
+        out.push_str("```\n\n");
+        let body = std::mem::take(&mut out);
+        push_region(&mut out, "models_setup", &body, markers);
+        return out;
+    }
+
+    out.push_str("```{r model_registry_init}\n");
+    out.push_str("model_registry <- list()\n");
+    out.push_str("model_metadata <- tibble::tibble(\n");
+    out.push_str("  model_name = character(),\n");
+    out.push_str("  model_object = character(),\n");
+    out.push_str("  outcome = character(),\n");
+    out.push_str("  include_main_table = logical(),\n");
+    out.push_str("  main_figure = character(),\n");
+    out.push_str("  subset = character(),\n");
+    out.push_str("  cluster = character()\n");
+    out.push_str(")\n");
+    out.push_str("```\n\n");
+    let body = std::mem::take(&mut out);
+    push_region(&mut out, "models_setup", &body, markers);
+
+    // Outcomes keep the order in which layouts first mention them.
+    // (model name, table object, include in main table, figure, clustered vcov for the table)
+    type TableEntry = (String, String, bool, String, Option<String>);
+    let mut by_outcome: Vec<(String, Vec<TableEntry>)> = Vec::new();
+    let mut figure_plans: Vec<(String, String, String, String, bool)> = Vec::new();
+    let mut effect_plans: Vec<(String, String, String, bool)> = Vec::new();
+    for (idx, plan) in plans.iter().enumerate() {
+        let model_start = out.len();
+        let model_object = format!("m_{}", idx + 1);
+        let chunk_id = model_chunk_id(idx, plan);
+        let outcome_var = plan.outcome_var.clone();
+        let model_name = r_string(&plan.name);
+        let covariates = plan.covariates.trim();
+        let interaction_var = if plan.interaction_var.trim().is_empty() {
+            "moderator_var".to_string()
+        } else {
+            plan.interaction_var.trim().to_string()
+        };
+        let treatment_expr = plan.treatment_var.trim();
+        let mut rhs = if plan.layout == "interaction" {
+            format!("({}) * {}", treatment_expr, interaction_var)
+        } else {
+            treatment_expr.to_string()
+        };
+        if !covariates.is_empty() {
+            rhs.push_str(" + ");
+            rhs.push_str(covariates);
+        }
+
+        out.push_str(&format!(
+            "## {} ({})\n\n```{{r {}}}\n",
+            plan.name, plan.model_type, chunk_id
+        ));
+        let bayes_family = if plan.bayesian {
+            brms_family(&plan.model_type)
+        } else {
+            None
+        };
+        // Subset fits filter df inline; fixest takes the condition through its `subset` argument.
+        let data_expr = match &plan.subset_filter {
+            Some(expr) => format!("dplyr::filter(df, {expr})"),
+            None => "df".to_string(),
+        };
+        let fixest_data = match &plan.subset_filter {
+            Some(expr) => format!("df, subset = ~ ({expr})"),
+            None => "df".to_string(),
+        };
+        if let Some(expr) = &plan.subset_filter {
+            out.push_str(&format!("# Subset: model fit only on rows where {expr}\n"));
+            out.push_str(&format!(
+                "cat(\"N in subset:\", nrow({data_expr}), \"\\n\")\n"
+            ));
+        }
+        let weights = match &plan.weight_var {
+            Some(weight) => format!(", weights = {weight}"),
+            None => String::new(),
+        };
+        let fixest_weights = match &plan.weight_var {
+            Some(weight) => format!(", weights = ~{weight}"),
+            None => String::new(),
+        };
+        let fixest_vcov = match &plan.cluster_var {
+            Some(cluster) => format!(", vcov = ~{cluster}"),
+            None => String::new(),
+        };
+        if let Some(weight) = &plan.weight_var {
+            out.push_str(&format!("# Weighted by {weight}\n"));
+        }
+        if plan.bayesian && bayes_family.is_none() {
+            out.push_str(&format!(
+                "# TODO: Bayesian estimation is not scaffolded for {}; using the frequentist fit.\n",
+                plan.model_type
+            ));
+        }
+        if let Some(family) = bayes_family {
+            let random_effect = if plan.model_type == "mixed_effects" {
+                format!(" + (1|{})", plan.id_var)
+            } else {
+                String::new()
+            };
+            let bayes = &options.bayes_options;
+            let response = match &plan.weight_var {
+                Some(weight) => format!("{outcome_var} | weights({weight})"),
+                None => outcome_var.clone(),
+            };
+            out.push_str("# Note: brms compiles a Stan model before sampling; the first run can take several minutes.\n");
+            out.push_str(&format!(
+                "{} <- brms::brm({} ~ {}{}, data = {}, family = {}, chains = {}, iter = {}, seed = {})\n",
+                model_object, response, rhs, random_effect, data_expr, family, bayes.chains, bayes.iter, bayes.seed
+            ));
+        } else {
+            match plan.model_type.as_str() {
+                "ols" => out.push_str(&format!(
+                    "{} <- lm({} ~ {}, data = {}{})\n",
+                    model_object, outcome_var, rhs, data_expr, weights
+                )),
+                "logit" => out.push_str(&format!(
+                    "{} <- glm({} ~ {}, data = {}{}, family = binomial())\n",
+                    model_object, outcome_var, rhs, data_expr, weights
+                )),
+                "poisson" => out.push_str(&format!(
+                    "{} <- glm({} ~ {}, data = {}{}, family = poisson())\n",
+                    model_object, outcome_var, rhs, data_expr, weights
+                )),
+                "negbin" => out.push_str(&format!(
+                    "{} <- MASS::glm.nb({} ~ {}, data = {}{})\n",
+                    model_object, outcome_var, rhs, data_expr, weights
+                )),
+                "mixed_effects" => out.push_str(&format!(
+                    "{} <- lme4::lmer({} ~ {} + (1|{}), data = {}{})\n",
+                    model_object, outcome_var, rhs, plan.id_var, data_expr, weights
+                )),
+                "fixed_effects" => out.push_str(&format!(
+                    "{} <- fixest::feols({} ~ {} | {} + {}, data = {}{}{})\n",
+                    model_object,
+                    outcome_var,
+                    rhs,
+                    plan.id_var,
+                    plan.time_var,
+                    fixest_data,
+                    fixest_weights,
+                    if fixest_vcov.is_empty() {
+                        ", vcov = \"cluster\""
+                    } else {
+                        &fixest_vcov
+                    }
+                )),
+                "survival" => out.push_str(&format!(
+                    "{} <- survival::coxph(Surv(time_to_event, event) ~ {}, data = {}{})\n",
+                    model_object, rhs, data_expr, weights
+                )),
+                "rd" => {
+                    out.push_str("# TODO: replace running_var and cutoff.\n");
+                    if let Some(weight) = &plan.weight_var {
+                        out.push_str(&format!(
+                            "# TODO: rdrobust takes weights as a vector; add weights = df${weight} once the running variable is set.\n"
+                        ));
+                    }
+                    let rd_data = if plan.subset_filter.is_some() {
+                        out.push_str(&format!("rd_df_{model_object} <- {data_expr}\n"));
+                        format!("rd_df_{model_object}")
+                    } else {
+                        "df".to_string()
+                    };
+                    out.push_str(&format!(
+                        "{} <- rdrobust::rdrobust(y = {}${}, x = {}$running_var, c = 0)\n",
+                        model_object, rd_data, outcome_var, rd_data
+                    ));
+                }
+                "did" => out.push_str(&format!(
+                    "{} <- fixest::feols({} ~ i({}, {}, ref = 0){} | {} + {}, data = {}{}{})\n",
+                    model_object,
+                    outcome_var,
+                    plan.time_var,
+                    plan.treatment_var,
+                    if covariates.is_empty() {
+                        "".to_string()
+                    } else {
+                        format!(" + {covariates}")
+                    },
+                    plan.id_var,
+                    plan.time_var,
+                    fixest_data,
+                    fixest_weights,
+                    fixest_vcov
+                )),
+                "event_study" => {
+                    out.push_str(&format!(
+                        "{} <- fixest::feols({} ~ sunab(cohort_time, {}) | {} + {}, data = {}{}{})\n",
+                        model_object,
+                        outcome_var,
+                        plan.time_var,
+                        plan.id_var,
+                        plan.time_var,
+                        fixest_data,
+                        fixest_weights,
+                        fixest_vcov
+                    ));
+                    out.push_str("# TODO: define cohort_time for adoption timing.\n");
+                }
+                "iv" if plan.instrument_vars.is_empty() => {
+                    out.push_str(&format!(
+                        "# TODO: set instrument variables for this IV model in the model builder; fixest needs {outcome_var} ~ controls | {} ~ instruments.\n",
+                        plan.endogenous_var
+                    ));
+                    out.push_str(&format!("{model_object} <- NULL\n"));
+                }
+                "iv" => {
+                    out.push_str(&format!(
+                        "# 2SLS: {} instrumented by {}; fixed effects go between the bars (controls | fe | endogenous ~ instruments).\n",
+                        plan.endogenous_var,
+                        plan.instrument_vars.join(", ")
+                    ));
+                    out.push_str(&format!(
+                        "{} <- fixest::feols({} ~ {} | {} ~ {}, data = {}{}{})\n",
+                        model_object,
+                        outcome_var,
+                        if covariates.is_empty() {
+                            "1"
+                        } else {
+                            covariates
+                        },
+                        plan.endogenous_var,
+                        plan.instrument_vars.join(" + "),
+                        fixest_data,
+                        fixest_weights,
+                        fixest_vcov
+                    ));
+                }
+                "mediation" => match &plan.mediator_var {
+                    Some(mediator) => {
+                        out.push_str(&format!(
+                            "# Mediation: {treatment_expr} -> {mediator} -> {outcome_var}\n"
+                        ));
+                        out.push_str(&format!(
+                            "{model_object}_mediator <- lm({mediator} ~ {rhs}, data = {data_expr})\n"
+                        ));
+                        out.push_str(&format!(
+                            "{model_object}_outcome <- lm({outcome_var} ~ {rhs} + {mediator}, data = {data_expr})\n"
+                        ));
+                        out.push_str(&format!(
+                            "{model_object} <- mediation::mediate({model_object}_mediator, {model_object}_outcome, treat = \"{treatment_expr}\", mediator = \"{mediator}\", boot = TRUE, sims = 1000)\n"
+                        ));
+                        out.push_str(&format!("print(summary({model_object}))\n"));
+                    }
+                    None => {
+                        out.push_str("# TODO: set a mediator variable for this mediation model in the model builder.\n");
+                        out.push_str(&format!("{model_object} <- NULL\n"));
+                    }
+                },
+                _ => out.push_str(&format!(
+                    "{} <- lm({} ~ {}, data = {}{})\n",
+                    model_object, outcome_var, rhs, data_expr, weights
+                )),
+            }
+        }
+        let sandwich_cluster = plan
+            .cluster_var
+            .as_ref()
+            .filter(|_| bayes_family.is_none())
+            .filter(|_| matches!(plan.model_type.as_str(), "ols" | "logit" | "poisson"));
+        if let Some(cluster) = sandwich_cluster {
+            out.push_str(&format!(
+                "# Standard errors clustered on {cluster}\n{model_object}_vcov <- sandwich::vcovCL({model_object}, cluster = ~{cluster})\nprint(lmtest::coeftest({model_object}, vcov = {model_object}_vcov))\n"
+            ));
+        }
+        out.push_str(&format!(
+            "model_registry[[{model_name}]] <- {model_object}\n"
+        ));
+        let figure_pref = if plan.figures.iter().any(|f| f == "mediation_plot") {
+            "mediation_plot".to_string()
+        } else {
+            plan.figures
+                .first()
+                .cloned()
+                .unwrap_or_else(|| "coef_plot".to_string())
+        };
+        // mediate() results are not regression tables; the outcome model stands in for them.
+        let (table_object, in_table) = match (plan.model_type.as_str(), &plan.mediator_var) {
+            ("mediation", Some(_)) => (
+                format!("{model_object}_outcome"),
+                plan.include_in_main_table,
+            ),
+            ("mediation", None) => (model_object.clone(), false),
+            ("iv", _) if plan.instrument_vars.is_empty() => (model_object.clone(), false),
+            _ => (model_object.clone(), plan.include_in_main_table),
+        };
+        out.push_str("model_metadata <- dplyr::bind_rows(\n");
+        out.push_str("  model_metadata,\n");
+        out.push_str(&format!(
+      "  tibble::tibble(model_name = {}, model_object = \"{}\", outcome = {}, include_main_table = {}, main_figure = {}, subset = {}, cluster = {})\n",
+      model_name,
+      model_object,
+      r_string(&plan.outcome_var),
+      if plan.include_in_main_table { "TRUE" } else { "FALSE" },
+      r_string(&figure_pref),
+      plan.subset_filter
+          .as_ref()
+          .map(|expr| r_string(expr))
+          .unwrap_or_else(|| "NA_character_".to_string()),
+      plan.cluster_var
+          .as_ref()
+          .map(|cluster| r_string(cluster))
+          .unwrap_or_else(|| "NA_character_".to_string())
+    ));
+        out.push_str(")\n");
+        if bayes_family.is_some() {
+            out.push_str(&format!("print(summary(model_registry[[{model_name}]]))\n"));
+        } else {
+            out.push_str("if (inherits(model_registry[[");
+            out.push_str(&model_name);
+            out.push_str("]], c(\"lm\", \"glm\", \"fixest\", \"lmerMod\", \"coxph\"))) {\n");
+            out.push_str("  print(broom::glance(model_registry[[");
+            out.push_str(&model_name);
+            out.push_str("]]))\n");
+            out.push_str("}\n");
+        }
+        out.push_str("```\n\n");
+
+        if !plan.contrasts.is_empty() {
+            out.push_str(&render_contrasts(
+                options,
+                &chunk_id,
+                &model_object,
+                &plan.name,
+                &plan.treatment_var,
+                &plan.contrasts,
+            ));
+        }
+
+        let group = match by_outcome
+            .iter()
+            .position(|(outcome, _)| outcome == &plan.outcome_var)
+        {
+            Some(index) => index,
+            None => {
+                by_outcome.push((plan.outcome_var.clone(), Vec::new()));
+                by_outcome.len() - 1
+            }
+        };
+        let table_vcov = match (plan.cluster_var.as_ref(), plan.model_type.as_str()) {
+            (Some(_), "ols" | "logit" | "poisson") if bayes_family.is_none() => {
+                Some(format!("{model_object}_vcov"))
+            }
+            (Some(cluster), "fixed_effects" | "did" | "event_study" | "iv") => {
+                Some(format!("~{cluster}"))
+            }
+            _ => None,
+        };
+        by_outcome[group].1.push((
+            plan.name.clone(),
+            table_object,
+            in_table,
+            figure_pref.clone(),
+            table_vcov,
+        ));
+        // An IV layout without instruments only has a TODO and a NULL placeholder.
+        if plan.model_type != "iv" || !plan.instrument_vars.is_empty() {
+            figure_plans.push((
+                plan.name.clone(),
+                model_object.clone(),
+                plan.outcome_var.clone(),
+                figure_pref,
+                bayes_family.is_some(),
+            ));
+            effect_plans.push((
+                plan.name.clone(),
+                model_object.clone(),
+                plan.model_type.clone(),
+                bayes_family.is_some(),
+            ));
+        }
+        let body = out.split_off(model_start);
+        push_region(&mut out, &chunk_id, &body, markers);
+    }
+
+    let tables_start = out.len();
+    if selected(&options.tables, "model_table") {
+        out.push_str("## Main Regression Tables (Grouped by Outcome)\n\n");
+        let table_extensions = model_table_extensions(options.model_table_format.as_deref());
+        if table_extensions.contains(&"docx") {
+            out.push_str("```{r model_table_docx_helper}\n");
+            out.push_str(match profile {
+                StyleProfile::Apa => MODEL_TABLE_DOCX_R,
+                StyleProfile::MinimalGt => MODEL_TABLE_GT_DOCX_R,
+            });
+            out.push_str("```\n\n");
+        }
+        for (outcome_name, models) in &by_outcome {
+            let included: Vec<(String, String, Option<String>)> = models
+                .iter()
+                .filter(|(_, _, include, _, _)| *include)
+                .map(|(name, object, _, _, vcov)| (name.clone(), object.clone(), vcov.clone()))
+                .collect();
+            if included.is_empty() {
+                continue;
+            }
+            let file_outcome = safe_token(outcome_name, "outcome");
+            out.push_str(&format!("```{{r model_table_{}}}\n", file_outcome));
+            out.push_str("models_for_outcome <- list(\n");
+            for (idx, (name, object, _)) in included.iter().enumerate() {
+                let suffix = if idx + 1 == included.len() { "" } else { "," };
+                out.push_str(&format!("  {} = {}{}\n", r_string(name), object, suffix));
+            }
+            out.push_str(")\n");
+            // Only the APA style kit forwards extra arguments to modelsummary.
+            let clustered =
+                profile == StyleProfile::Apa && included.iter().any(|(_, _, vcov)| vcov.is_some());
+            let table_vcov = if clustered {
+                let entries = included
+                    .iter()
+                    .map(|(_, object, vcov)| {
+                        vcov.clone()
+                            .unwrap_or_else(|| format!("stats::vcov({object})"))
+                    })
+                    .collect::<Vec<String>>();
+                out.push_str(&format!(
+                    "vcov_for_outcome <- list({})\n",
+                    entries.join(", ")
+                ));
+                ", vcov = vcov_for_outcome"
+            } else {
+                ""
+            };
+            for ext in &table_extensions {
+                if *ext == "docx" {
+                    out.push_str(&format!(
+                        "save_model_table_docx(models_for_outcome, file.path(tables_dir, \"models_{}.docx\"))\n",
+                        file_outcome
+                    ));
+                } else {
+                    out.push_str(&format!(
+                        "style_model_table(models_for_outcome, output_path = file.path(tables_dir, \"models_{}.{}\"){})\n",
+                        file_outcome, ext, table_vcov
+                    ));
+                }
+            }
+            out.push_str("```\n\n");
+        }
+    }
+
+    let tables = out.split_off(tables_start);
+    push_region(&mut out, "model_tables", &tables, markers);
+    if options.effect_sizes {
+        push_region(
+            &mut out,
+            "effect_sizes",
+            &render_effect_sizes(options, &effect_plans),
+            markers,
+        );
+    }
+    if marginal_effects_selected(options) {
+        push_region(
+            &mut out,
+            "marginal_effects",
+            &render_marginal_effects(options, &effect_plans),
+            markers,
+        );
+    }
+
+    let figures_start = out.len();
+    out.push_str("## Main Figures by Model Builder Input\n\n");
+    for (model_name, model_object, outcome_name, figure_pref, bayesian) in &figure_plans {
+        let chunk = safe_token(
+            &format!("main_figure_{}_{}", model_name, outcome_name),
+            "main_figure",
+        );
+        let clean_outcome = safe_token(&format!("{}_{}", model_name, outcome_name), "outcome");
+        out.push_str(&format!("```{{r {}}}\n", chunk));
+        out.push_str(&format!("main_model <- {}\n", model_object));
+        if *bayesian {
+            out.push_str(&format!(
+                "p_main_{} <- brms::mcmc_plot(main_model, type = \"intervals\", prob = 0.5, prob_outer = 0.95) +\n",
+                clean_outcome
+            ));
+            out.push_str(&format!("  {}\n", profile.plot_theme()));
+            out.push_str(&format!("p_main_{}\n", clean_outcome));
+            out.push_str("```\n\n");
+            continue;
+        }
+        match figure_pref.as_str() {
+            "fitted_plot" => {
+                out.push_str("if (inherits(main_model, c(\"lm\", \"glm\"))) {\n");
+                out.push_str(&format!(
+                    "  p_main_{} <- ggplot(df, aes(x = fitted(main_model), y = {})) +\n",
+                    clean_outcome, outcome_name
+                ));
+                out.push_str("    geom_point(alpha = 0.7) +\n");
+                out.push_str(
+                    "    geom_abline(slope = 1, intercept = 0, linetype = \"dashed\") +\n",
+                );
+                out.push_str("    labs(x = \"Fitted\", y = \"Observed\") +\n");
+                out.push_str(&format!("    {}\n", profile.plot_theme()));
+                out.push_str(&format!("  p_main_{}\n", clean_outcome));
+                out.push_str("}\n");
+            }
+            "residual_plot" => {
+                out.push_str("if (inherits(main_model, c(\"lm\", \"glm\"))) {\n");
+                out.push_str("  plot(main_model, which = 1)\n");
+                out.push_str("}\n");
+            }
+            "event_study_plot" => {
+                out.push_str("if (inherits(main_model, \"fixest\")) {\n");
+                out.push_str("  fixest::iplot(main_model)\n");
+                out.push_str("}\n");
+            }
+            "mediation_plot" => {
+                out.push_str("if (inherits(main_model, \"mediate\")) {\n");
+                out.push_str("  mediation_df <- tibble::tibble(\n");
+                out.push_str("    effect = c(\"Indirect (ACME)\", \"Direct (ADE)\", \"Total\"),\n");
+                out.push_str(
+                    "    estimate = c(main_model$d.avg, main_model$z.avg, main_model$tau.coef),\n",
+                );
+                out.push_str("    ci_low = c(main_model$d.avg.ci[[1]], main_model$z.avg.ci[[1]], main_model$tau.ci[[1]]),\n");
+                out.push_str("    ci_high = c(main_model$d.avg.ci[[2]], main_model$z.avg.ci[[2]], main_model$tau.ci[[2]])\n");
+                out.push_str("  )\n");
+                out.push_str(&format!(
+                    "  p_main_{} <- ggplot(mediation_df, aes(x = estimate, y = effect)) +\n",
+                    clean_outcome
+                ));
+                out.push_str("    geom_vline(xintercept = 0, linetype = \"dashed\") +\n");
+                out.push_str("    geom_point() +\n");
+                out.push_str(
+                    "    geom_errorbarh(aes(xmin = ci_low, xmax = ci_high), height = 0.1) +\n",
+                );
+                out.push_str("    labs(x = \"Effect (bootstrap 95% CI)\", y = NULL) +\n");
+                out.push_str(&format!("    {}\n", profile.plot_theme()));
+                out.push_str(&format!("  p_main_{}\n", clean_outcome));
+                out.push_str("}\n");
+            }
+            _ => {
+                out.push_str("if (inherits(main_model, c(\"lm\", \"glm\", \"fixest\", \"lmerMod\", \"coxph\"))) {\n");
+                out.push_str("  coef_df <- broom::tidy(main_model)\n");
+                out.push_str(&format!(
+                    "  p_main_{} <- ggplot(coef_df, aes(x = estimate, y = term)) +\n",
+                    clean_outcome
+                ));
+                out.push_str("    geom_point() +\n");
+                out.push_str("    geom_errorbarh(aes(xmin = estimate - 1.96 * std.error, xmax = estimate + 1.96 * std.error), height = 0.1) +\n");
+                out.push_str(&format!("    {}\n", profile.plot_theme()));
+                out.push_str(&format!("  p_main_{}\n", clean_outcome));
+                out.push_str("}\n");
+            }
+        }
+        out.push_str("```\n\n");
+    }
+    let figures = out.split_off(figures_start);
+    push_region(&mut out, "model_figures", &figures, markers);
+
+    out
+}
+
+/// Model types whose coefficients are not on the response scale, so a marginal effects
+/// table is worth generating for them.
+const MARGINAL_EFFECT_MODEL_TYPES: &[&str] = &["logit", "poisson", "negbin", "mixed_effects"];
+
+fn marginal_effects_selected(options: &AnalysisTemplateOptions) -> bool {
+    selected(&options.tables, "marginal_effects_table")
+        && MARGINAL_EFFECT_MODEL_TYPES
+            .iter()
+            .any(|model_type| selected_model(options, model_type))
+}
+
+fn render_marginal_effects(
+    options: &AnalysisTemplateOptions,
+    effect_plans: &[(String, String, String, bool)],
+) -> String {
+    let profile = style_profile(options);
+    let mut out = String::new();
+    out.push_str("## Marginal Effects\n\n");
+    out.push_str("```{r marginal_effects}\n");
+    out.push_str("# avg_slopes() averages unit-level slopes on the response scale; factor terms are reported as contrasts, as with avg_comparisons().\n");
+    out.push_str("marginal_effect_rows <- function(model_name, effects) {\n");
+    out.push_str("  tibble::tibble(\n");
+    out.push_str("    model = model_name,\n");
+    out.push_str("    term = as.character(effects$term),\n");
+    out.push_str("    contrast = as.character(effects$contrast),\n");
+    out.push_str("    estimate = effects$estimate,\n");
+    out.push_str("    std_error = effects$std.error,\n");
+    out.push_str("    ci_low = effects$conf.low,\n");
+    out.push_str("    ci_high = effects$conf.high,\n");
+    out.push_str("    p_value = effects$p.value\n");
+    out.push_str("  )\n");
+    out.push_str("}\n");
+    out.push_str("marginal_effects <- tibble::tibble()\n");
+    for (name, object, model_type, _) in effect_plans {
+        let quoted = r_string(name);
+        match model_type.as_str() {
+            "survival" | "rd" | "mediation" => {
+                out.push_str(&format!(
+                    "# Note: {name} ({model_type}) is skipped; average marginal effects are not meaningful for this fit.\n"
+                ));
+            }
+            _ => {
+                out.push_str(&format!(
+                    "marginal_effects <- dplyr::bind_rows(marginal_effects, marginal_effect_rows({quoted}, marginaleffects::avg_slopes({object})))\n"
+                ));
+            }
+        }
+    }
+    out.push_str(&format!(
+        "marginal_effects_ft <- {}(marginal_effects)\n",
+        profile.table_fn()
+    ));
+    out.push_str("marginal_effects_ft\n");
+    out.push_str(&profile.save_table_html(
+        "marginal_effects_ft",
+        "file.path(tables_dir, \"marginal_effects.html\")",
+    ));
+    out.push('\n');
+    out.push_str(&profile.save_table_docx(
+        "marginal_effects_ft",
+        "file.path(tables_dir, \"marginal_effects.docx\")",
+    ));
+    out.push('\n');
+    out.push_str("```\n\n");
+    out
+}
+
+/// Consolidated effect-size chunk: standardized betas and partial eta-squared for OLS,
+/// odds ratios for logit, and a note for model classes `effectsize` handles differently.
+fn render_effect_sizes(
+    options: &AnalysisTemplateOptions,
+    effect_plans: &[(String, String, String, bool)],
+) -> String {
+    let profile = style_profile(options);
+    let mut out = String::new();
+    out.push_str("## Effect Sizes\n\n");
+    out.push_str("```{r effect_sizes}\n");
+    out.push_str(
+        "effect_size_rows <- function(model_name, measure, term, estimate, ci_low, ci_high) {\n",
+    );
+    out.push_str("  tibble::tibble(\n");
+    out.push_str("    model = model_name,\n");
+    out.push_str("    measure = measure,\n");
+    out.push_str("    term = as.character(term),\n");
+    out.push_str("    estimate = estimate,\n");
+    out.push_str("    ci_low = ci_low,\n");
+    out.push_str("    ci_high = ci_high\n");
+    out.push_str("  )\n");
+    out.push_str("}\n");
+    out.push_str("effect_sizes <- tibble::tibble()\n");
+    for (name, object, model_type, bayesian) in effect_plans {
+        let quoted = r_string(name);
+        if *bayesian {
+            out.push_str(&format!(
+                "# Note: {name} is a brms fit; report posterior summaries instead of effectsize output.\n"
+            ));
+            continue;
+        }
+        match model_type.as_str() {
+            "ols" => {
+                out.push_str(&format!(
+                    "std_betas_{object} <- effectsize::standardize_parameters({object})\n"
+                ));
+                out.push_str(&format!(
+                    "eta_sq_{object} <- effectsize::eta_squared(car::Anova({object}), partial = TRUE)\n"
+                ));
+                out.push_str("effect_sizes <- dplyr::bind_rows(\n");
+                out.push_str("  effect_sizes,\n");
+                out.push_str(&format!(
+                    "  effect_size_rows({quoted}, \"std_beta\", std_betas_{object}$Parameter, std_betas_{object}$Std_Coefficient, std_betas_{object}$CI_low, std_betas_{object}$CI_high),\n"
+                ));
+                out.push_str(&format!(
+                    "  effect_size_rows({quoted}, \"partial_eta_sq\", eta_sq_{object}$Parameter, eta_sq_{object}$Eta2_partial, eta_sq_{object}$CI_low, eta_sq_{object}$CI_high)\n"
+                ));
+                out.push_str(")\n");
+            }
+            "logit" => {
+                out.push_str(&format!(
+                    "odds_ratios_{object} <- broom::tidy({object}, conf.int = TRUE, exponentiate = TRUE)\n"
+                ));
+                out.push_str("effect_sizes <- dplyr::bind_rows(\n");
+                out.push_str("  effect_sizes,\n");
+                out.push_str(&format!(
+                    "  effect_size_rows({quoted}, \"odds_ratio\", odds_ratios_{object}$term, odds_ratios_{object}$estimate, odds_ratios_{object}$conf.low, odds_ratios_{object}$conf.high)\n"
+                ));
+                out.push_str(")\n");
+            }
+            "fixed_effects" | "did" | "event_study" | "mixed_effects" | "iv" => {
+                out.push_str(&format!(
+                    "# Note: effectsize support for {model_type} fits ({name}) differs; check effectsize::standardize_parameters({object}) before reporting.\n"
+                ));
+            }
+            other => {
+                out.push_str(&format!(
+                    "# TODO: choose an effect size for {name} ({other}).\n"
+                ));
+            }
+        }
+    }
+    out.push_str(&format!(
+        "effect_sizes_ft <- {}(effect_sizes)\n",
+        profile.table_fn()
+    ));
+    out.push_str("effect_sizes_ft\n");
+    if options.export_artifacts {
+        out.push_str(&profile.save_table_docx(
+            "effect_sizes_ft",
+            "file.path(tables_dir, \"effect_sizes.docx\")",
+        ));
+        out.push('\n');
+    }
+    out.push_str("```\n\n");
+    out
+}
+
+fn render_contrasts(
+    options: &AnalysisTemplateOptions,
+    chunk_id: &str,
+    model_object: &str,
+    model_name: &str,
+    treatment_var: &str,
+    contrasts: &[ContrastSpec],
+) -> String {
+    let factor = treatment_var
+        .split(['+', '*', ':'])
+        .map(str::trim)
+        .find(|term| !term.is_empty())
+        .unwrap_or("condition");
+    let file_token = safe_token(&model_name.to_lowercase(), model_object);
+
+    let mut out = String::new();
+    out.push_str(&format!("```{{r {chunk_id}_contrasts}}\n"));
+    out.push_str(&format!(
+        "emm_{model_object} <- emmeans::emmeans({model_object}, specs = ~ {factor})\n"
+    ));
+    out.push_str(&format!("contrast_weights_{model_object} <- list(\n"));
+    for (idx, contrast) in contrasts.iter().enumerate() {
+        let weights = contrast
+            .levels
+            .iter()
+            .zip(&contrast.weights)
+            .map(|(level, weight)| format!("{} = {}", r_string(level), weight))
+            .collect::<Vec<String>>()
+            .join(", ");
+        let suffix = if idx + 1 == contrasts.len() { "" } else { "," };
+        out.push_str(&format!(
+            "  {} = c({}){}\n",
+            r_string(&contrast.name),
+            weights,
+            suffix
+        ));
+    }
+    out.push_str(")\n");
+    out.push_str(&format!(
+        "emm_levels_{model_object} <- as.character(summary(emm_{model_object})[[\"{factor}\"]])\n"
+    ));
+    out.push_str(&format!(
+        "contrast_matrix_{model_object} <- lapply(contrast_weights_{model_object}, function(w) {{\n"
+    ));
+    out.push_str(&format!(
+        "  full <- setNames(rep(0, length(emm_levels_{model_object})), emm_levels_{model_object})\n"
+    ));
+    out.push_str("  full[names(w)] <- w\n");
+    out.push_str("  unname(full)\n");
+    out.push_str("})\n");
+    out.push_str(&format!(
+        "contrasts_{model_object} <- emmeans::contrast(emm_{model_object}, method = contrast_matrix_{model_object})\n"
+    ));
+    out.push_str(&format!(
+        "contrasts_df_{model_object} <- as.data.frame(summary(contrasts_{model_object}, infer = c(TRUE, TRUE)))\n"
+    ));
+    let profile = style_profile(options);
+    out.push_str(&format!(
+        "contrasts_ft_{model_object} <- {}(contrasts_df_{model_object})\n",
+        profile.table_fn()
+    ));
+    out.push_str(&format!("contrasts_ft_{model_object}\n"));
+    if options.export_artifacts {
+        out.push_str(&profile.save_table_docx(
+            &format!("contrasts_ft_{model_object}"),
+            &format!("file.path(tables_dir, \"contrasts_{file_token}.docx\")"),
+        ));
+        out.push('\n');
+    }
+    out.push_str("```\n\n");
+    out
+}
+
+fn render_diagnostics(options: &AnalysisTemplateOptions) -> String {
+    let has_iv = selected_model(options, "iv");
+    if options.diagnostics.is_empty() && !has_iv {
+        return String::new();
+    }
+    let mut out = String::new();
+    out.push_str("# Diagnostics and Assumption Checks\n\n");
+    out.push_str("```{r diagnostics_registry_guard}\n");
+    out.push_str("if (!exists(\"model_registry\")) model_registry <- list()\n");
+    out.push_str("```\n\n");
+
+    if selected(&options.diagnostics, "linearity") {
+        out.push_str("```{r diag_linearity}\n");
+        out.push_str("for (nm in names(model_registry)) {\n");
+        out.push_str("  m <- model_registry[[nm]]\n");
+        out.push_str("  if (inherits(m, \"lm\")) {\n");
+        out.push_str("    message(\"Linearity diagnostics: \", nm)\n");
+        out.push_str("    plot(m, which = 1)\n");
+        out.push_str("    car::crPlots(m)\n");
+        out.push_str("  }\n");
+        out.push_str("}\n");
+        out.push_str("```\n\n");
+    }
+    if selected(&options.diagnostics, "normality_residuals") {
+        out.push_str("```{r diag_normality}\n");
+        out.push_str("for (nm in names(model_registry)) {\n");
+        out.push_str("  m <- model_registry[[nm]]\n");
+        out.push_str("  if (inherits(m, \"lm\")) {\n");
+        out.push_str("    message(\"Normality diagnostics: \", nm)\n");
+        out.push_str("    plot(m, which = 2)\n");
+        out.push_str("  }\n");
+        out.push_str("}\n");
+        out.push_str("# TODO: Shapiro tests can be misleading at large N.\n");
+        out.push_str("```\n\n");
+    }
+    if selected(&options.diagnostics, "homoskedasticity") {
+        out.push_str("```{r diag_homoskedasticity}\n");
+        out.push_str("for (nm in names(model_registry)) {\n");
+        out.push_str("  m <- model_registry[[nm]]\n");
+        out.push_str("  if (inherits(m, \"lm\")) {\n");
+        out.push_str("    message(\"Homoskedasticity diagnostics: \", nm)\n");
+        out.push_str("    print(lmtest::bptest(m))\n");
+        out.push_str(
+            "    print(lmtest::coeftest(m, vcov = sandwich::vcovHC(m, type = \"HC1\")))\n",
+        );
+        out.push_str("  }\n");
+        out.push_str("}\n");
+        out.push_str("```\n\n");
+    }
+    if selected(&options.diagnostics, "multicollinearity") {
+        out.push_str("```{r diag_multicollinearity}\n");
+        out.push_str("for (nm in names(model_registry)) {\n");
+        out.push_str("  m <- model_registry[[nm]]\n");
+        out.push_str("  if (inherits(m, \"lm\")) {\n");
+        out.push_str("    message(\"VIF: \", nm)\n");
+        out.push_str("    print(car::vif(m))\n");
+        out.push_str("  }\n");
+        out.push_str("}\n");
+        out.push_str("```\n\n");
+    }
+    if selected(&options.diagnostics, "influential_points") {
+        out.push_str("```{r diag_influential_points}\n");
+        out.push_str("for (nm in names(model_registry)) {\n");
+        out.push_str("  m <- model_registry[[nm]]\n");
+        out.push_str("  if (inherits(m, \"lm\")) {\n");
+        out.push_str("    message(\"Influence diagnostics: \", nm)\n");
+        out.push_str("    plot(m, which = 4)\n");
+        out.push_str("    plot(m, which = 5)\n");
+        out.push_str("  }\n");
+        out.push_str("}\n");
+        out.push_str("```\n\n");
+    }
+    if selected(&options.diagnostics, "overdispersion") {
+        out.push_str("```{r diag_overdispersion}\n");
+        out.push_str("for (nm in names(model_registry)) {\n");
+        out.push_str("  m <- model_registry[[nm]]\n");
+        out.push_str(
+            "  if (inherits(m, \"glm\") && identical(stats::family(m)$family, \"poisson\")) {\n",
+        );
+        out.push_str("    message(\"Overdispersion check: \", nm)\n");
+        out.push_str("    print(performance::check_overdispersion(m))\n");
+        out.push_str("  }\n");
+        out.push_str("}\n");
+        out.push_str("```\n\n");
+    }
+    if selected(&options.diagnostics, "parallel_trends") {
+        out.push_str(&render_parallel_trends(options));
+    }
+    if has_iv {
+        out.push_str("```{r diag_weak_instruments}\n");
+        out.push_str("for (nm in names(model_registry)) {\n");
+        out.push_str("  m <- model_registry[[nm]]\n");
+        out.push_str("  if (inherits(m, \"fixest\") && isTRUE(m$iv)) {\n");
+        out.push_str("    message(\"First-stage F (weak instruments below ~10): \", nm)\n");
+        out.push_str("    print(fixest::fitstat(m, \"ivf\"))\n");
+        out.push_str("  }\n");
+        out.push_str("}\n");
+        out.push_str("```\n\n");
+    }
+    if selected(&options.diagnostics, "common_support") {
+        out.push_str("```{r diag_common_support}\n");
+        out.push_str("# TODO: estimate propensity scores and plot overlap.\n");
+        out.push_str("```\n\n");
+    }
+    if selected(&options.diagnostics, "placebo_tests") {
+        out.push_str("```{r diag_placebo_tests}\n");
+        out.push_str("# TODO: add placebo outcomes or pseudo-treatment timings.\n");
+        out.push_str("```\n\n");
+    }
+    if selected(&options.diagnostics, "bandwidth_sensitivity") {
+        out.push_str("```{r diag_bandwidth_sensitivity}\n");
+        out.push_str("# TODO: compare RD estimates across multiple bandwidths.\n");
+        out.push_str("```\n\n");
+    }
+
+    out
+}
+
+/// Event-study refits of each DiD / event-study layout with leads and lags, an `iplot()` of the
+/// coefficients and a joint Wald test that the pre-period (lead) coefficients are zero.
+fn render_parallel_trends(options: &AnalysisTemplateOptions) -> String {
+    let treatment_hint = hint_or_default(&options.treatment_var_hint, "treat");
+    let treatment = primary_treatment_from_models(options, &treatment_hint);
+    let id = hint_or_default(&options.id_var_hint, "id");
+    let time = hint_or_default(&options.time_var_hint, "time");
+    let plans: Vec<ModelPlan> = model_plans(options, &treatment, &id, &time)
+        .into_iter()
+        .filter(|plan| plan.model_type == "did" || plan.model_type == "event_study")
+        .collect();
+    let mut out = String::new();
+    out.push_str("```{r diag_parallel_trends}\n");
+    if plans.is_empty() {
+        out.push_str("# Parallel trends checks need a did or event_study model layout; none is configured, so this check is skipped.\n");
+        out.push_str("```\n\n");
+        return out;
+    }
+    out.push_str(
+        "# Joint Wald test of the lead coefficients: a small p-value means the groups already\n",
+    );
+    out.push_str(
+        "# diverged before treatment, so parallel trends is doubtful. A large p-value is\n",
+    );
+    out.push_str("# consistent with parallel trends but does not prove it (pre-tests can be underpowered).\n");
+    for (idx, plan) in plans.iter().enumerate() {
+        let object = format!("pretrend_{}", idx + 1);
+        let outcome = &plan.outcome_var;
+        let (id, time) = (&plan.id_var, &plan.time_var);
+        let title = &plan.name;
+        let main = r_string(&format!("Pre-trends: {title}"));
+        if plan.model_type == "did" {
+            let treatment = &plan.treatment_var;
+            out.push_str(&format!(
+                "# {title}: leads and lags of {treatment} around each unit's first treated {time}\n"
+            ));
+            out.push_str(&format!("{object}_df <- df %>%\n"));
+            out.push_str(&format!("  dplyr::group_by({id}) %>%\n"));
+            out.push_str(&format!(
+                "  dplyr::mutate(first_treated = suppressWarnings(min({time}[{treatment} == 1], na.rm = TRUE))) %>%\n"
+            ));
+            out.push_str("  dplyr::ungroup() %>%\n");
+            out.push_str(&format!(
+                "  dplyr::mutate(rel_time = dplyr::if_else(is.finite(first_treated), {time} - first_treated, -1000))\n"
+            ));
+            out.push_str(&format!(
+                "{object} <- fixest::feols({outcome} ~ i(rel_time, ref = c(-1, -1000)) | {id} + {time}, data = {object}_df, cluster = ~{id})\n"
+            ));
+            out.push_str(&format!(
+                "fixest::iplot({object}, main = {main}, xlab = \"Periods relative to treatment\")\n"
+            ));
+            out.push_str(&format!(
+                "print(fixest::wald({object}, keep = \"^rel_time::-[0-9]+$\"))\n"
+            ));
+        } else {
+            out.push_str(&format!(
+                "# {title}: Sun-Abraham leads and lags by adoption cohort (cohort_time, as in the model)\n"
+            ));
+            out.push_str(&format!(
+                "{object} <- fixest::feols({outcome} ~ sunab(cohort_time, {time}) | {id} + {time}, data = df, cluster = ~{id})\n"
+            ));
+            out.push_str(&format!(
+                "fixest::iplot({object}, main = {main}, xlab = \"Periods relative to treatment\")\n"
+            ));
+            out.push_str(&format!(
+                "print(fixest::wald({object}, keep = \"^{time}::-[0-9]+$\"))\n"
+            ));
+        }
+    }
+    out.push_str("```\n\n");
+    out
+}
+
+/// Refits each weighted main-table model without its weights and sets the coefficients side
+/// by side. Bayesian fits carry the weights in the formula and are left out.
+fn render_weight_sensitivity(options: &AnalysisTemplateOptions) -> String {
+    // An IV layout without instruments registers NULL, so there is nothing to refit.
+    let weighted: Vec<(String, String, String)> = model_plans(options, "", "", "")
+        .into_iter()
+        .filter(|plan| plan.include_in_main_table && !plan.bayesian && plan.model_type != "rd")
+        .filter(|plan| plan.model_type != "iv" || !plan.instrument_vars.is_empty())
+        .filter_map(|plan| {
+            plan.weight_var
+                .map(|weight| (plan.name, weight, plan.model_type))
+        })
+        .collect();
+    if weighted.is_empty() {
+        return String::new();
+    }
+    let mut out = String::new();
+    out.push_str("## WEIGHTED VS UNWEIGHTED\n\n");
+    out.push_str("```{r robustness_weight_sensitivity}\n");
+    out.push_str("weight_sensitivity <- list()\n");
+    for (name, weight, model_type) in &weighted {
+        let quoted = r_string(name);
+        out.push_str(&format!("# {name}: weighted by {weight}\n"));
+        out.push_str(&format!("m_weighted <- model_registry[[{quoted}]]\n"));
+        match model_type.as_str() {
+            // update() does not reliably drop fixest weights; call the estimator again without them.
+            "fixed_effects" | "did" | "event_study" | "iv" => {
+                out.push_str("unweighted_call <- stats::getCall(m_weighted)\n");
+                out.push_str("unweighted_call$weights <- NULL\n");
+                out.push_str("m_unweighted <- eval(unweighted_call)\n");
+            }
+            _ => out.push_str("m_unweighted <- stats::update(m_weighted, weights = NULL)\n"),
+        }
+        out.push_str(&format!(
+            "weight_sensitivity[[{quoted}]] <- dplyr::full_join(\n"
+        ));
+        out.push_str("  broom::tidy(m_weighted) %>% dplyr::select(term, weighted = estimate),\n");
+        out.push_str(
+            "  broom::tidy(m_unweighted) %>% dplyr::select(term, unweighted = estimate),\n",
+        );
+        out.push_str("  by = \"term\"\n");
+        out.push_str(") %>% dplyr::mutate(difference = weighted - unweighted)\n");
+    }
+    out.push_str("print(weight_sensitivity)\n");
+    out.push_str("```\n\n");
+    out
+}
+
+fn render_robustness(options: &AnalysisTemplateOptions, outcomes: &[String]) -> String {
+    let weight_sensitivity = render_weight_sensitivity(options);
+    if options.robustness.is_empty() && weight_sensitivity.is_empty() {
+        return String::new();
+    }
+    let mut out = String::new();
+    out.push_str("# Robustness Checks\n\n");
+    out.push_str(&weight_sensitivity);
+    for check in &options.robustness {
+        out.push_str(&format!(
+            "## {}\n\n",
+            check.replace('_', " ").to_uppercase()
+        ));
+        out.push_str(&format!("```{{r robustness_{check}}}\n"));
+        match check.as_str() {
+            "hc_se" => {
+                out.push_str("for (nm in names(model_registry)) {\n");
+                out.push_str("  m <- model_registry[[nm]]\n");
+                out.push_str("  if (inherits(m, \"lm\")) {\n");
+                out.push_str(
+                    "    print(lmtest::coeftest(m, vcov = sandwich::vcovHC(m, type = \"HC1\")))\n",
+                );
+                out.push_str("  }\n");
+                out.push_str("}\n");
+            }
+            "cluster_se" => {
+                out.push_str(
+                    "# Cluster variables come from each model layout (model_metadata$cluster).\n",
+                );
+                out.push_str("clustered <- if (exists(\"model_metadata\")) {\n");
+                out.push_str("  dplyr::filter(model_metadata, !is.na(cluster))\n");
+                out.push_str("} else {\n");
+                out.push_str("  tibble::tibble(model_name = character(), cluster = character())\n");
+                out.push_str("}\n");
+                out.push_str("if (nrow(clustered) == 0) {\n");
+                out.push_str("  message(\"No model layout sets a cluster variable.\")\n");
+                out.push_str("}\n");
+                out.push_str("for (i in seq_len(nrow(clustered))) {\n");
+                out.push_str("  m <- model_registry[[clustered$model_name[[i]]]]\n");
+                out.push_str("  if (is.null(m)) next\n");
+                out.push_str("  cluster_formula <- stats::reformulate(clustered$cluster[[i]])\n");
+                out.push_str("  cat(\"\\n\", clustered$model_name[[i]], \" clustered by \", clustered$cluster[[i]], \"\\n\", sep = \"\")\n");
+                out.push_str("  if (inherits(m, \"fixest\")) {\n");
+                out.push_str("    print(fixest::etable(m, vcov = cluster_formula))\n");
+                out.push_str("  } else if (inherits(m, \"lm\")) {\n");
+                out.push_str(
+                    "    print(lmtest::coeftest(m, vcov = sandwich::vcovCL(m, cluster = cluster_formula)))\n",
+                );
+                out.push_str("  }\n");
+                out.push_str("}\n");
+            }
+            "winsorize" => out.push_str(&render_winsorize(options, outcomes)),
+            "alt_controls" => {
+                out.push_str("# TODO: refit models with alternative control sets.\n");
+            }
+            "alt_outcome" => {
+                out.push_str("# TODO: define alternative outcomes and refit models.\n");
+            }
+            _ => {
+                out.push_str("# TODO: implement this robustness check.\n");
+            }
+        }
+        out.push_str("```\n\n");
+    }
+    out
+}
+
+/// Winsorizes the configured columns into `df_winsorized`, refits every registered model on
+/// it, and sets original and winsorized estimates side by side.
+fn render_winsorize(options: &AnalysisTemplateOptions, outcomes: &[String]) -> String {
+    let params = &options.robustness_params.winsorize;
+    let variables = if params.variables.is_empty() {
+        outcomes.to_vec()
+    } else {
+        params.variables.clone()
+    };
+    let mut out = String::new();
+    out.push_str(&format!(
+        "winsorize_vars <- c({})\n",
+        variables
+            .iter()
+            .map(|v| r_string(v.trim()))
+            .collect::<Vec<String>>()
+            .join(", ")
+    ));
+    out.push_str(&format!(
+        "winsorize_probs <- c({}, {})\n",
+        params.lower, params.upper
+    ));
+    out.push_str("df_winsorized <- df %>%\n");
+    out.push_str("  dplyr::mutate(dplyr::across(\n");
+    out.push_str("    dplyr::all_of(winsorize_vars),\n");
+    out.push_str(
+        "    ~ DescTools::Winsorize(.x, val = stats::quantile(.x, probs = winsorize_probs, na.rm = TRUE))\n",
+    );
+    out.push_str("  ))\n");
+    out.push_str("winsorized_registry <- list()\n");
+    out.push_str("for (nm in names(model_registry)) {\n");
+    out.push_str("  refit <- tryCatch(\n");
+    out.push_str("    stats::update(model_registry[[nm]], data = df_winsorized),\n");
+    out.push_str("    error = function(e) NULL\n");
+    out.push_str("  )\n");
+    out.push_str("  if (is.null(refit)) {\n");
+    out.push_str("    message(\"Could not refit \", nm, \" on winsorized data.\")\n");
+    out.push_str("    next\n");
+    out.push_str("  }\n");
+    out.push_str("  winsorized_registry[[nm]] <- refit\n");
+    out.push_str("}\n");
+    out.push_str("for (nm in names(winsorized_registry)) {\n");
+    out.push_str("  print(modelsummary::modelsummary(\n");
+    out.push_str(
+        "    list(Original = model_registry[[nm]], Winsorized = winsorized_registry[[nm]]),\n",
+    );
+    out.push_str(
+        "    title = sprintf(\"%s: original vs winsorized at %s/%s\", nm, winsorize_probs[1], winsorize_probs[2])\n",
+    );
+    out.push_str("  ))\n");
+    out.push_str("}\n");
+    out
+}
+
+fn render_exploratory(options: &AnalysisTemplateOptions) -> String {
+    if !options.exploratory {
+        return String::new();
+    }
+    let treatment_hint = hint_or_default(&options.treatment_var_hint, "treat");
+    let treatment = primary_treatment_from_models(options, &treatment_hint);
+    let id = hint_or_default(&options.id_var_hint, "id");
+    let time = hint_or_default(&options.time_var_hint, "time");
+    let plans = model_plans(options, &treatment, &id, &time);
+    let mut out = String::new();
+    out.push_str("# Exploratory Analyses\n\n");
+    if plans.iter().all(|plan| plan.subgroup_vars.is_empty()) {
+        out.push_str("```{r exploratory}\n");
+        out.push_str(
+            "# TODO: add subgroup analyses, heterogeneity checks, and discovery analyses.\n",
+        );
+        out.push_str("```\n\n");
+        return out;
+    }
+    let profile = style_profile(options);
+    // Indices match render_models, so `m_{idx + 1}` is the main fit of the same layout.
+    for (idx, plan) in plans.iter().enumerate() {
+        for column in &plan.subgroup_vars {
+            out.push_str(&render_subgroup(profile, idx, plan, column));
+        }
+    }
+    out
+}
+
+/// Refits model `m_{idx + 1}` with `update()` within each level of `column`, then plots the
+/// per-subgroup coefficients in facets and exports them as a table.
+fn render_subgroup(profile: StyleProfile, idx: usize, plan: &ModelPlan, column: &str) -> String {
+    let model_object = format!("m_{}", idx + 1);
+    let token = safe_token(
+        &format!("{}_{}", model_chunk_id(idx, plan), column.to_lowercase()),
+        &format!("model_{}_subgroup", idx + 1),
+    );
+    let title = &plan.name;
+    let mut out = String::new();
+    out.push_str(&format!("## {title} by {column}\n\n"));
+    out.push_str(&format!("```{{r subgroup_{token}}}\n"));
+    let skip = match plan.model_type.as_str() {
+        "rd" | "mediation" => Some(format!(
+            "{} fits cannot be refit with update()",
+            plan.model_type
+        )),
+        "iv" if plan.instrument_vars.is_empty() => {
+            Some("the IV model has no instruments yet".to_string())
+        }
+        _ if plan.bayesian => Some("Bayesian fits are slow to refit per subgroup".to_string()),
+        _ => None,
+    };
+    if let Some(reason) = skip {
+        out.push_str(&format!(
+            "# Note: subgroup refits of {title} are skipped; {reason}.\n"
+        ));
+        out.push_str("```\n\n");
+        return out;
+    }
+    let data_expr = match &plan.subset_filter {
+        Some(expr) => format!("dplyr::filter(df, {expr})"),
+        None => "df".to_string(),
+    };
+    let results = format!("subgroup_{token}");
+    out.push_str(&format!(
+        "# Refits {title} within each level of {column}, keeping the main model's right-hand side.\n"
+    ));
+    out.push_str(&format!("{results}_base <- {data_expr}\n"));
+    out.push_str(&format!("{results}_rows <- list()\n"));
+    out.push_str(&format!(
+        "for (level in sort(unique(stats::na.omit({results}_base[[\"{column}\"]])))) {{\n"
+    ));
+    out.push_str(&format!(
+        "  level_df <- dplyr::filter({results}_base, .data[[\"{column}\"]] == level)\n"
+    ));
+    out.push_str("  level_rows <- tryCatch(\n");
+    out.push_str(&format!(
+        "    broom::tidy(update({model_object}, data = level_df), conf.int = TRUE),\n"
+    ));
+    out.push_str("    error = function(e) {\n");
+    out.push_str(&format!(
+        "      message(\"Skipping {column} = \", level, \": \", conditionMessage(e))\n"
+    ));
+    out.push_str("      NULL\n");
+    out.push_str("    }\n");
+    out.push_str("  )\n");
+    out.push_str("  if (!is.null(level_rows)) {\n");
+    out.push_str(&format!(
+        "    {results}_rows[[as.character(level)]] <- dplyr::mutate(level_rows, subgroup = as.character(level))\n"
+    ));
+    out.push_str("  }\n");
+    out.push_str("}\n");
+    out.push_str(&format!("{results} <- dplyr::bind_rows({results}_rows)\n"));
+    out.push_str(&format!(
+        "p_{results} <- ggplot(dplyr::filter({results}, term != \"(Intercept)\"), aes(x = estimate, y = term)) +\n"
+    ));
+    out.push_str("  geom_vline(xintercept = 0, linetype = \"dashed\") +\n");
+    out.push_str("  geom_point() +\n");
+    out.push_str("  geom_errorbarh(aes(xmin = conf.low, xmax = conf.high), height = 0.1) +\n");
+    out.push_str("  facet_wrap(~ subgroup) +\n");
+    out.push_str(&format!(
+        "  labs(x = \"Estimate (95% CI)\", y = NULL, title = {}) +\n",
+        r_string(&format!("{title} by {column}"))
+    ));
+    out.push_str(&format!("  {}\n", profile.plot_theme()));
+    out.push_str(&format!("p_{results}\n"));
+    out.push_str(&format!(
+        "{results}_ft <- {}({results})\n",
+        profile.table_fn()
+    ));
+    out.push_str(&format!("{results}_ft\n"));
+    out.push_str(&profile.save_table_docx(
+        &format!("{results}_ft"),
+        &format!("file.path(tables_dir, \"{results}.docx\")"),
+    ));
+    out.push('\n');
+    out.push_str(
+        "# Alternative: test heterogeneity in one model with a treatment x subgroup interaction.\n",
+    );
+    out.push_str(&format!(
+        "# {results}_interaction <- update({model_object}, . ~ . + {} * {column})\n",
+        plan.treatment_var.trim()
+    ));
+    out.push_str(&format!("# summary({results}_interaction)\n"));
+    out.push_str("```\n\n");
+    out
+}
+
+fn render_exports(options: &AnalysisTemplateOptions, outcomes: &[String]) -> String {
+    if !options.export_artifacts {
+        return String::new();
+    }
+    let profile = style_profile(options);
+    let mut out = String::new();
+    out.push_str("# Tables and Figures Export\n\n");
+    out.push_str("```{r export_artifacts}\n");
+    out.push_str("dir.create(tables_dir, recursive = TRUE, showWarnings = FALSE)\n");
+    out.push_str("dir.create(figures_dir, recursive = TRUE, showWarnings = FALSE)\n");
+    out.push_str("dir.create(reports_dir, recursive = TRUE, showWarnings = FALSE)\n");
+    if selected(&options.tables, "model_table") {
+        out.push_str("# Model tables are exported in Main Analyses, grouped by outcome.\n");
+    }
+    if selected(&options.tables, "table1_descriptives") {
+        out.push_str("if (exists(\"table1_descriptives_ft\")) {\n");
+        out.push_str(&format!(
+            "  {}\n",
+            profile.save_table_docx(
+                "table1_descriptives_ft",
+                "file.path(tables_dir, \"table1_descriptives.docx\")"
+            )
+        ));
+        out.push_str("} else if (exists(\"summary_stats_ft\")) {\n");
+        out.push_str(&format!(
+            "  {}\n",
+            profile.save_table_docx(
+                "summary_stats_ft",
+                "file.path(tables_dir, \"table1_summary_stats.docx\")"
+            )
+        ));
+        out.push_str("}\n");
+    }
+    if selected(&options.tables, "balance_table") {
+        out.push_str("# TODO: export balance table object.\n");
+    }
+    if marginal_effects_selected(options) {
+        out.push_str("# Marginal effects tables are exported in Main Analyses.\n");
+    } else if selected(&options.tables, "marginal_effects_table") {
+        out.push_str("# Marginal effects table needs a logit, poisson, negbin, or mixed-effects model layout.\n");
+    }
+    if selected(&options.plots, "histogram") {
+        for outcome in outcomes {
+            let token = safe_token(outcome, "outcome");
+            out.push_str(&format!(
+        "if (exists(\"p_hist_{}\")) ggsave(file.path(figures_dir, \"hist_{}.png\"), plot = p_hist_{}, width = 7, height = 5, dpi = 300)\n",
+        token, token, token
+      ));
+        }
+    }
+    out.push_str("if (exists(\"model_metadata\") && nrow(model_metadata) > 0) {\n");
+    out.push_str("  for (i in seq_len(nrow(model_metadata))) {\n");
+    out.push_str("    mn <- model_metadata$model_name[[i]]\n");
+    out.push_str("    oc <- model_metadata$outcome[[i]]\n");
+    out.push_str("    key <- paste(mn, oc, sep = \"_\")\n");
+    out.push_str("    key_safe <- gsub(\"[^A-Za-z0-9_]+\", \"_\", key)\n");
+    out.push_str("    obj <- get0(paste0(\"p_main_\", key_safe), ifnotfound = NULL)\n");
+    out.push_str("    if (!is.null(obj)) {\n");
+    out.push_str("      ggsave(file.path(figures_dir, paste0(\"main_figure_\", key_safe, \".png\")), plot = obj, width = 7, height = 5, dpi = 300)\n");
+    out.push_str("    }\n");
+    out.push_str("  }\n");
+    out.push_str("}\n");
+    if selected(&options.plots, "coef_plot") {
+        out.push_str("# Coefficient-style figures are generated per outcome in Main Analyses.\n");
+    }
+    out.push_str("# TODO: save knitted reports (html/pdf/docx) to reports_dir when rendering.\n");
+    out.push_str("```\n\n");
+    out
+}
+
+/// Loads the project style package (or its sourced fallback) and binds its APA helpers.
+fn render_style_kit_bindings() -> String {
+    let mut out = String::new();
+    out.push_str("# Load project style package (preferred), fallback to sourced scripts\n");
+    out.push_str("style_pkg_name <- \"");
+    out.push_str(STYLE_PACKAGE_NAME);
+    out.push_str("\"\n");
+    out.push_str("style_pkg_root <- here::here(\"R\", \"");
+    out.push_str(STYLE_PACKAGE_NAME);
+    out.push_str("\")\n");
+    out.push_str("style_pkg_loaded <- requireNamespace(style_pkg_name, quietly = TRUE)\n");
+    out.push_str("if (!style_pkg_loaded && dir.exists(style_pkg_root) && requireNamespace(\"remotes\", quietly = TRUE)) {\n");
+    out.push_str("  tryCatch({\n");
+    out.push_str("    remotes::install_local(style_pkg_root, dependencies = FALSE, upgrade = \"never\", quiet = TRUE)\n");
+    out.push_str("    style_pkg_loaded <- requireNamespace(style_pkg_name, quietly = TRUE)\n");
+    out.push_str("  }, error = function(e) {\n");
+    out.push_str("    message(\"Style package install skipped: \", conditionMessage(e))\n");
+    out.push_str("  })\n");
+    out.push_str("}\n");
+    out.push_str("if (!style_pkg_loaded) {\n");
+    out.push_str("  source(here::here(\"R/style/theme_plots.R\"))\n");
+    out.push_str("  source(here::here(\"R/style/tables_flextable.R\"))\n");
+    out.push_str("  source(here::here(\"R/style/style_init.R\"))\n");
+    out.push_str("  cfg <- init_project_style()\n");
+    out.push_str("} else {\n");
+    out.push_str("  cfg <- getExportedValue(style_pkg_name, \"init_project_style\")()\n");
+    out.push_str("}\n\n");
+    out.push_str("# Bind plotting/table helpers from local style package when available\n");
+    out.push_str("if (style_pkg_loaded) {\n");
+    out.push_str("  style_exports <- c(\n");
+    out.push_str("    \"theme_apa\", \"set_apa_plot_defaults\", \"apa_scatter\", \"apa_hist\", \"apa_box\",\n");
+    out.push_str("    \"theme_study_plot\", \"style_box_plot\", \"style_bar_plot\",\n");
+    out.push_str(
+        "    \"ft_apa\", \"ft_apa_descriptives\", \"ft_apa_regression\", \"style_model_table\"\n",
+    );
+    out.push_str("  )\n");
+    out.push_str("  for (fn in style_exports) {\n");
+    out.push_str("    assign(fn, getExportedValue(style_pkg_name, fn), envir = .GlobalEnv)\n");
+    out.push_str("  }\n");
+    out.push_str("}\n\n");
+    out
+}
+
+fn render_analysis_rmd(
+    project_root: &Path,
+    study_root: &Path,
+    study_id: &str,
+    study_title: &str,
+    options: &AnalysisTemplateOptions,
+) -> String {
+    let dataset_path = hint_or_default(&options.dataset_path_hint, "data/clean/analysis.csv");
+    let data_sources = data_source_paths(options);
+    let hinted_outcome = hint_or_default(&options.outcome_var_hint, "y");
+    let treatment_hint = hint_or_default(&options.treatment_var_hint, "treat");
+    let treatment = primary_treatment_from_models(options, &treatment_hint);
+    let outcomes = model_outcomes(options, &hinted_outcome);
+    let outcome = outcomes
+        .first()
+        .cloned()
+        .unwrap_or_else(|| hinted_outcome.clone());
+    let id = hint_or_default(&options.id_var_hint, "id");
+    let time = hint_or_default(&options.time_var_hint, "time");
+    let group_hint = options
+        .group_var_hint
+        .as_ref()
+        .map(|item| item.trim().to_string())
+        .filter(|item| !item.is_empty())
+        .unwrap_or_else(|| treatment.clone());
+    let group = primary_group_from_models(options, &group_hint);
+    let profile = style_profile(options);
+
+    let mut out = String::new();
+    out.push_str("---\n");
+    out.push_str(&format!(
+        "title: {}\n",
+        r_string(&format!("Analysis: {study_title}"))
+    ));
+    let format = template_format(options);
+    match format {
+        TemplateFormat::Rmd => {
+            out.push_str("output:\n");
+            out.push_str("  html_document:\n");
+            out.push_str("    toc: true\n");
+            out.push_str("    toc_depth: 3\n");
+            out.push_str("    df_print: paged\n");
+        }
+        TemplateFormat::Qmd => {
+            out.push_str("format:\n");
+            out.push_str("  html:\n");
+            out.push_str("    toc: true\n");
+            out.push_str("    toc-depth: 3\n");
+            out.push_str("    df-print: paged\n");
+        }
+    }
+    out.push_str("---\n\n");
+    out.push_str(&format!("Study ID: `{study_id}`\n\n"));
+
+    let markers = region_markers(options);
+    let setup_start = out.len();
+    out.push_str("# Setup\n\n");
+    out.push_str("```{r setup, include=FALSE}\n");
+    if options.use_renv {
+        out.push_str(RENV_SETUP_R);
+        out.push('\n');
+    }
+    out.push_str("knitr::opts_chunk$set(\n");
+    out.push_str("  echo = TRUE,\n");
+    out.push_str("  message = FALSE,\n");
+    out.push_str("  warning = FALSE,\n");
+    out.push_str("  fig.retina = 2,\n");
+    out.push_str("  dpi = 300,\n");
+    out.push_str("  fig.width = 6.5,\n");
+    out.push_str("  fig.height = 4.5\n");
+    out.push_str(")\n\n");
+    out.push_str("suppressPackageStartupMessages({\n");
+    out.push_str("  library(here)\n");
+    out.push_str("  library(tidyverse)\n");
+    out.push_str("  library(ggplot2)\n");
+    if profile == StyleProfile::MinimalGt {
+        out.push_str("  library(gt)\n");
+        out.push_str("})\n\n");
+        out.push_str(MINIMAL_GT_HELPERS_R);
+        out.push('\n');
+    } else {
+        out.push_str("  library(ggpubr)\n");
+        out.push_str("  library(flextable)\n");
+        out.push_str("})\n\n");
+        out.push_str(&render_style_kit_bindings());
+    }
+    out.push_str(&format!(
+        "output_dir <- {}\n",
+        analysis_output_here_expr(project_root, study_root)
+    ));
+    out.push_str("tables_dir <- file.path(output_dir, \"tables\")\n");
+    out.push_str("figures_dir <- file.path(output_dir, \"figures\")\n");
+    out.push_str("reports_dir <- file.path(output_dir, \"reports\")\n");
+    out.push_str("dir.create(tables_dir, recursive = TRUE, showWarnings = FALSE)\n");
+    out.push_str("dir.create(figures_dir, recursive = TRUE, showWarnings = FALSE)\n");
+    out.push_str("dir.create(reports_dir, recursive = TRUE, showWarnings = FALSE)\n");
+    out.push_str("```\n\n");
+    let setup = out.split_off(setup_start);
+    push_region(&mut out, "setup", &setup, markers);
+
+    push_region(&mut out, "packages", &render_packages(options), markers);
+
+    let load_start = out.len();
+    out.push_str("# Data Import and Cleaning\n\n");
+    out.push_str("```{r load_data}\n");
+    if data_sources.is_empty() {
+        out.push_str(&format!(
+            "raw <- readr::read_csv({})\n",
+            r_path(&dataset_path)
+        ));
+    } else {
+        out.push_str("read_data_source <- function(path) {\n");
+        out.push_str("  ext <- tolower(tools::file_ext(path))\n");
+        out.push_str(
+            "  if (ext %in% c(\"csv\")) return(readr::read_csv(path, show_col_types = FALSE))\n",
+        );
+        out.push_str("  if (ext %in% c(\"tsv\", \"txt\")) return(readr::read_tsv(path, show_col_types = FALSE))\n");
+        out.push_str("  if (ext %in% c(\"rds\")) return(readr::read_rds(path))\n");
+        out.push_str("  if (ext %in% c(\"sav\", \"zsav\")) return(haven::read_sav(path))\n");
+        out.push_str("  if (ext %in% c(\"dta\")) return(haven::read_dta(path))\n");
+        out.push_str(&format!(
+            "  if (ext %in% c(\"xlsx\", \"xls\")) return(readxl::read_excel(path{}))\n",
+            excel_sheet_arg(options)
+        ));
+        out.push_str(&format!(
+            "  stop(paste0(\"Unsupported data source extension '\", ext, \"' for: \", path, \". Supported: {}.\"))\n",
+            SUPPORTED_DATA_EXTENSIONS.join(", ")
+        ));
+        out.push_str("}\n");
+        out.push_str("data_sources <- c(\n");
+        for (index, source) in data_sources.iter().enumerate() {
+            let sep = if index + 1 == data_sources.len() {
+                ""
+            } else {
+                ","
+            };
+            out.push_str(&format!("  {}{}\n", r_path(source), sep));
+        }
+        out.push_str(")\n");
+        out.push_str("loaded_data <- purrr::set_names(data_sources, basename(data_sources)) %>%\n");
+        out.push_str("  purrr::map(read_data_source)\n");
+        out.push_str("if (length(loaded_data) == 1) {\n");
+        out.push_str("  raw <- loaded_data[[1]]\n");
+        out.push_str("} else {\n");
+        out.push_str("  # TODO: Replace bind_rows() with study-specific joins if sources differ in structure.\n");
+        out.push_str("  raw <- dplyr::bind_rows(loaded_data, .id = \"source_file\")\n");
+        out.push_str("}\n");
+    }
+    out.push_str("```\n\n");
+    let load_data = out.split_off(load_start);
+    push_region(&mut out, "load_data", &load_data, markers);
+
+    let clean_start = out.len();
+    out.push_str("```{r clean_data}\n");
+    if uses_labelled_sources(options) {
+        out.push_str(
+            "# SPSS/Stata value labels: convert labelled columns to factors where wanted.\n",
+        );
+        out.push_str("# raw <- raw %>% dplyr::mutate(dplyr::across(tidyselect::where(haven::is.labelled), haven::as_factor))\n");
+    }
+    out.push_str("df <- raw %>%\n");
+    out.push_str("  janitor::clean_names() %>%\n");
+    out.push_str("  # TODO: add study-specific cleaning steps\n");
+    out.push_str("  mutate()\n");
+    for line in treatment_factor_lines(options, &treatment) {
+        out.push_str(&line);
+        out.push('\n');
+    }
+    out.push_str("```\n\n");
+    let clean_data = out.split_off(clean_start);
+    push_region(&mut out, "clean_data", &clean_data, markers);
+
+    let descriptives = render_descriptives(options, &outcomes, &treatment, &group);
+    push_region(&mut out, "descriptives", &descriptives, markers);
+    let balance_checks = render_balance_checks(options, &treatment);
+    push_region(&mut out, "balance_checks", &balance_checks, markers);
+    out.push_str(&render_models(options, &outcome, &treatment, &id, &time));
+    push_region(
+        &mut out,
+        "diagnostics",
+        &render_diagnostics(options),
+        markers,
+    );
+    push_region(
+        &mut out,
+        "robustness",
+        &render_robustness(options, &outcomes),
+        markers,
+    );
+    push_region(
+        &mut out,
+        "exploratory",
+        &render_exploratory(options),
+        markers,
+    );
+    push_region(
+        &mut out,
+        "exports",
+        &render_exports(options, &outcomes),
+        markers,
+    );
+    let renv = if options.use_renv {
+        format!("{RENV_SNAPSHOT_CHUNK}\n")
+    } else {
+        String::new()
+    };
+    push_region(&mut out, "renv", &renv, markers);
+    if format == TemplateFormat::Qmd {
+        out = quarto_chunk_options(&out);
+    }
+    if markers {
+        let manifest = manifest_line(&out);
+        out.push_str(&manifest);
+    }
+
+    out
+}
+
+fn treatment_factor_lines(options: &AnalysisTemplateOptions, treatment: &str) -> Vec<String> {
+    let mut columns: Vec<String> = options
+        .model_layouts
+        .iter()
+        .filter_map(|layout| layout.treatment_var.as_ref())
+        .map(|value| value.trim().to_string())
+        .filter(|value| !value.is_empty())
+        .collect();
+    columns.push(treatment.to_string());
+
+    let mut seen: Vec<String> = Vec::new();
+    let mut lines = Vec::new();
+    for column in columns {
+        if seen.iter().any(|item| item.eq_ignore_ascii_case(&column)) {
+            continue;
+        }
+        seen.push(column.clone());
+        if let Some((_, levels)) = options
+            .expected_values
+            .iter()
+            .find(|(name, _)| name.eq_ignore_ascii_case(&column))
+        {
+            lines.push(factor_coercion_r(&column, levels));
+        }
+    }
+    lines
+}
+
+fn create_analysis_template_in_dir(
+    project_root: &Path,
+    study_root: &Path,
+    analysis_dir: &Path,
+    study_id: &str,
+    study_title: &str,
+    options: &AnalysisTemplateOptions,
+) -> Result<PathBuf, String> {
+    fs::create_dir_all(analysis_dir).map_err(|err| err.to_string())?;
+    let output_root = study_root.join("07_outputs");
+    fs::create_dir_all(output_root.join("tables")).map_err(|err| err.to_string())?;
+    fs::create_dir_all(output_root.join("figures")).map_err(|err| err.to_string())?;
+    fs::create_dir_all(output_root.join("reports")).map_err(|err| err.to_string())?;
+
+    validate_model_layouts(options)?;
+    validate_robustness_params(options)?;
+    StyleProfile::parse(options.style_profile.as_deref())?;
+    let ext = TemplateFormat::parse(options.output_format.as_deref())?.extension();
+    let file_base = normalized_analysis_file_base(&options.analysis_file_name)?;
+    // An Rmd and a qmd with the same base would share a name in the template list.
+    let taken = |base: &str| {
+        TemplateFormat::ALL.iter().any(|format| {
+            analysis_dir
+                .join(format!("{base}.{}", format.extension()))
+                .exists()
+        })
+    };
+    let mut template_path = analysis_dir.join(format!("{file_base}.{ext}"));
+    if taken(&file_base) {
+        let stamp = Utc::now().format("%Y%m%d_%H%M%S");
+        template_path = analysis_dir.join(format!("{file_base}_{stamp}.{ext}"));
+    }
+
+    let template = render_analysis_rmd(project_root, study_root, study_id, study_title, options);
+    let manifest = manifest_json(&template_path, template_models_manifest(options))?;
+    let region_meta = if options.omit_region_markers {
+        None
+    } else {
+        Some(region_meta_json(&template_path, &template)?)
+    };
+    let mut files = vec![
+        (template_path.clone(), template),
+        (manifest_path(&template_path), manifest),
+    ];
+    files.extend(region_meta.map(|meta| (region_meta_path(&template_path), meta)));
+    write_files_atomically(&files)?;
+    if options.use_renv {
+        write_renv_scaffolding(analysis_dir)?;
+    }
+    Ok(template_path)
+}
+
+pub fn create_analysis_template(
+    app_root: &Path,
+    project_id: String,
+    study_id: String,
+    options: serde_json::Value,
+    preset_name: Option<String>,
+) -> Result<String, String> {
+    let store = read_projects_store(app_root)?;
+    let project = store
+        .projects
+        .iter()
+        .find(|project| project.id == project_id)
+        .ok_or_else(|| "Project not found.".to_string())?;
+    let study = project
+        .studies
+        .iter()
+        .find(|study| study.id == study_id)
+        .ok_or_else(|| "Study not found.".to_string())?;
+
+    let study_root = resolve_study_root(project, study);
+    if !study_root.exists() {
+        return Err("Study folder does not exist.".to_string());
+    }
+    let project_root = PathBuf::from(project.root_path.clone());
+    let mut options =
+        presets::resolve_template_options(&project_root, preset_name.as_deref(), options)?;
+    ensure_project_style_kit(&project_root)?;
+    if options.style_profile.is_none() {
+        options.style_profile = default_style_profile(&project_root);
+    }
+
+    let analysis_dir = study_root.join(ANALYSIS_FOLDER);
+    let template_path = create_analysis_template_in_dir(
+        &project_root,
+        &study_root,
+        &analysis_dir,
+        &study_id,
+        &study.title,
+        &options,
+    )?;
+
+    track_generated_artifact(
+        app_root,
+        &study_id,
+        "analysis_template",
+        &template_path,
+        template_path.file_name().and_then(|name| name.to_str()),
+    );
+    record_activity(
+        app_root,
+        "create_analysis_template",
+        Some(&project_id),
+        Some(&study_id),
+        &format!(
+            "Generated analysis template {}",
+            template_path
+                .file_name()
+                .map(|name| name.to_string_lossy())
+                .unwrap_or_default()
+        ),
+    );
+
+    Ok(format!(
+        "Created analysis template at {}",
+        template_path.to_string_lossy()
+    ))
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RegenerationReport {
+    path: String,
+    backup_path: String,
+    updated_regions: Vec<String>,
+    added_regions: Vec<String>,
+    removed_regions: Vec<String>,
+    preserved_regions: Vec<String>,
+    /// Regions edited by hand whose generated content changed; the regenerated version is
+    /// written below each as a comment.
+    conflicted_regions: Vec<String>,
+    /// Generated regions the user deleted; they are reported here instead of being re-added.
+    conflicts: Vec<String>,
+}
+
+fn regenerate_analysis_template_in_dir(
+    project_root: &Path,
+    study_root: &Path,
+    analysis_dir: &Path,
+    study_id: &str,
+    study_title: &str,
+    analysis_name: &str,
+    options: &AnalysisTemplateOptions,
+) -> Result<RegenerationReport, String> {
+    if options.omit_region_markers {
+        return Err(
+            "Regenerating a template requires region markers; enable them or create a new template."
+                .to_string(),
+        );
+    }
+    validate_model_layouts(options)?;
+    validate_robustness_params(options)?;
+    StyleProfile::parse(options.style_profile.as_deref())?;
+    let ext = TemplateFormat::parse(options.output_format.as_deref())?.extension();
+    let file_base = normalized_analysis_file_base(&Some(analysis_name.to_string()))?;
+    let template_path = analysis_dir.join(format!("{file_base}.{ext}"));
+    if !template_path.exists() {
+        return Err("Analysis template does not exist.".to_string());
+    }
+
+    let previous = fs::read_to_string(&template_path).map_err(|err| err.to_string())?;
+    let rendered = render_analysis_rmd(project_root, study_root, study_id, study_title, options);
+    let merged = merge_regenerated(
+        &previous,
+        &rendered,
+        read_region_hashes(&template_path).as_ref(),
+    )?;
+
+    let trash_dir = analysis_dir.join(TRASH_FOLDER);
+    fs::create_dir_all(&trash_dir).map_err(|err| err.to_string())?;
+    let stamp = Utc::now().format("%Y%m%d_%H%M%S");
+    let mut backup_path = trash_dir.join(format!("{file_base}_{stamp}.{ext}"));
+    let mut suffix = 1;
+    while backup_path.exists() {
+        suffix += 1;
+        backup_path = trash_dir.join(format!("{file_base}_{stamp}_{suffix}.{ext}"));
+    }
+    fs::write(&backup_path, &previous).map_err(|err| err.to_string())?;
+
+    let manifest = manifest_json(&template_path, template_models_manifest(options))?;
+    let region_meta = region_meta_json(&template_path, &rendered)?;
+    write_files_atomically(&[
+        (template_path.clone(), merged.text),
+        (manifest_path(&template_path), manifest),
+        (region_meta_path(&template_path), region_meta),
+    ])?;
+    if options.use_renv {
+        write_renv_scaffolding(analysis_dir)?;
+    }
+
+    Ok(RegenerationReport {
+        path: template_path.to_string_lossy().to_string(),
+        backup_path: backup_path.to_string_lossy().to_string(),
+        updated_regions: merged.updated_regions,
+        added_regions: merged.added_regions,
+        removed_regions: merged.removed_regions,
+        preserved_regions: merged.preserved_regions,
+        conflicted_regions: merged.conflicted_regions,
+        conflicts: merged.conflicts,
+    })
+}
+
+pub fn regenerate_analysis_template(
+    app_root: &Path,
+    project_id: String,
+    study_id: String,
+    analysis_name: String,
+    mut options: AnalysisTemplateOptions,
+) -> Result<RegenerationReport, String> {
+    let store = read_projects_store(app_root)?;
+    let project = store
+        .projects
+        .iter()
+        .find(|project| project.id == project_id)
+        .ok_or_else(|| "Project not found.".to_string())?;
+    let study = project
+        .studies
+        .iter()
+        .find(|study| study.id == study_id)
+        .ok_or_else(|| "Study not found.".to_string())?;
+
+    let study_root = resolve_study_root(project, study);
+    if !study_root.exists() {
+        return Err("Study folder does not exist.".to_string());
+    }
+    let project_root = PathBuf::from(project.root_path.clone());
+    ensure_project_style_kit(&project_root)?;
+    if options.style_profile.is_none() {
+        options.style_profile = default_style_profile(&project_root);
+    }
+
+    regenerate_analysis_template_in_dir(
+        &project_root,
+        &study_root,
+        &study_root.join(ANALYSIS_FOLDER),
+        &study_id,
+        &study.title,
+        &analysis_name,
+        &options,
+    )
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ListAnalysisTemplatesArgs {
+    project_id: String,
+    study_id: String,
+}
+
+pub fn list_analysis_templates(
+    app_root: &Path,
+    args: ListAnalysisTemplatesArgs,
+) -> Result<Vec<String>, String> {
+    let store = read_projects_store(app_root)?;
+    let project = store
+        .projects
+        .iter()
+        .find(|project| project.id == args.project_id)
+        .ok_or_else(|| "Project not found.".to_string())?;
+    let study = project
+        .studies
+        .iter()
+        .find(|study| study.id == args.study_id)
+        .ok_or_else(|| "Study not found.".to_string())?;
+
+    let study_root = resolve_study_root(project, study);
+    if !study_root.exists() {
+        return Err("Study folder does not exist.".to_string());
+    }
+
+    let analysis_dir = study_root.join(ANALYSIS_FOLDER);
+    if !analysis_dir.exists() {
+        return Ok(Vec::new());
+    }
+
+    let mut names: Vec<String> = Vec::new();
+    let entries = fs::read_dir(&analysis_dir).map_err(|err| err.to_string())?;
+    for entry in entries {
+        let entry = entry.map_err(|err| err.to_string())?;
+        let path = entry.path();
+        if !path.is_file() {
+            continue;
+        }
+        if TemplateFormat::from_path(&path).is_none() {
+            continue;
+        }
+        if let Some(stem) = path.file_stem().and_then(|value| value.to_str()) {
+            names.push(stem.to_string());
+        }
+    }
+    names.sort();
+    names.dedup();
+    Ok(names)
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DeleteAnalysisTemplateArgs {
+    project_id: String,
+    study_id: String,
+    analysis_name: String,
+}
+
+pub fn delete_analysis_template(
+    app_root: &Path,
+    args: DeleteAnalysisTemplateArgs,
+) -> Result<String, String> {
+    let store = read_projects_store(app_root)?;
+    let project = store
+        .projects
+        .iter()
+        .find(|project| project.id == args.project_id)
+        .ok_or_else(|| "Project not found.".to_string())?;
+    let study = project
+        .studies
+        .iter()
+        .find(|study| study.id == args.study_id)
+        .ok_or_else(|| "Study not found.".to_string())?;
+
+    let trimmed_name = args.analysis_name.trim();
+    if trimmed_name.is_empty() {
+        return Err("Analysis name is required.".to_string());
+    }
+    if trimmed_name.contains('/') || trimmed_name.contains('\\') || trimmed_name.contains("..") {
+        return Err("Analysis name must be a single file name.".to_string());
+    }
+    if trimmed_name.contains('.') {
+        return Err("Analysis name should not include a file extension.".to_string());
+    }
+
+    let study_root = resolve_study_root(project, study);
+    if !study_root.exists() {
+        return Err("Study folder does not exist.".to_string());
+    }
+
+    let analysis_dir = study_root.join(ANALYSIS_FOLDER);
+    let target = TemplateFormat::ALL
+        .iter()
+        .map(|format| analysis_dir.join(format!("{trimmed_name}.{}", format.extension())))
+        .find(|path| path.exists())
+        .ok_or_else(|| "Analysis template does not exist.".to_string())?;
+    fs::remove_file(&target).map_err(|err| err.to_string())?;
+    let manifest = manifest_path(&target);
+    if manifest.exists() {
+        fs::remove_file(&manifest).map_err(|err| err.to_string())?;
+    }
+
+    Ok(format!(
+        "Deleted analysis template at {}",
+        target.to_string_lossy()
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use uuid::Uuid;
+
+    fn empty_options() -> AnalysisTemplateOptions {
+        AnalysisTemplateOptions {
+            analysis_file_name: None,
+            data_source_paths: None,
+            data_source_sheet: None,
+            dataset_path_hint: None,
+            outcome_var_hint: None,
+            treatment_var_hint: None,
+            id_var_hint: None,
+            time_var_hint: None,
+            group_var_hint: None,
+            descriptives: Vec::new(),
+            plots: Vec::new(),
+            balance_checks: Vec::new(),
+            models: Vec::new(),
+            diagnostics: Vec::new(),
+            tables: Vec::new(),
+            robustness: Vec::new(),
+            robustness_params: RobustnessParams::default(),
+            model_layouts: Vec::new(),
+            model_table_format: None,
+            expected_values: BTreeMap::new(),
+            bayes_options: BayesOptions::default(),
+            style_profile: None,
+            omit_region_markers: false,
+            effect_sizes: false,
+            output_format: None,
+            use_renv: false,
+            exploratory: false,
+            export_artifacts: false,
+        }
+    }
+
+    #[test]
+    fn render_requires_model_layouts_for_model_scaffolding() {
+        let mut options = empty_options();
+        options.model_layouts = vec![ModelLayout {
+            name: "OLS Main".to_string(),
+            model_type: "ols".to_string(),
+            outcome_var: "outcome_y".to_string(),
+            treatment_var: Some("treat_x".to_string()),
+            layout: "simple".to_string(),
+            covariates: Some("cov1 + cov2".to_string()),
+            figures: vec!["coef_plot".to_string()],
+            include_in_main_table: true,
+            ..Default::default()
+        }];
+        let rendered = render_analysis_rmd(
+            Path::new("project"),
+            Path::new("project/studies/S-ABC123"),
+            "S-ABC123",
+            "Test Study",
+            &options,
+        );
+        assert!(rendered.contains("## OLS Main (ols)"));
+        assert!(rendered.contains("outcome_y ~ treat_x + cov1 + cov2"));
+        assert!(rendered.contains("style_pkg_name <- \"researchworkflowstyle\""));
+        assert!(rendered.contains("source(here::here(\"R/style/theme_plots.R\"))"));
+
+        let rendered_without_layouts = render_analysis_rmd(
+            Path::new("project"),
+            Path::new("project/studies/S-ABC123"),
+            "S-ABC123",
+            "Test Study",
+            &empty_options(),
+        );
+        assert!(
+            rendered_without_layouts.contains("Add at least one Model Layout in the model builder")
+        );
+    }
+
+    #[test]
+    fn render_mediation_layout_with_and_without_mediator() {
+        let layout = |mediator: Option<&str>| ModelLayout {
+            name: "Mediation".to_string(),
+            model_type: "mediation".to_string(),
+            outcome_var: "wellbeing".to_string(),
+            treatment_var: Some("condition".to_string()),
+            layout: "simple".to_string(),
+            covariates: Some("age".to_string()),
+            figures: vec!["mediation_plot".to_string()],
+            include_in_main_table: true,
+            mediator_var: mediator.map(str::to_string),
+            ..Default::default()
+        };
+        let mut options = empty_options();
+        options.tables = vec!["model_table".to_string()];
+        options.model_layouts = vec![layout(Some(" rumination "))];
+        let rendered = render_analysis_rmd(
+            Path::new("project"),
+            Path::new("project/studies/S-ABC123"),
+            "S-ABC123",
+            "Test Study",
+            &options,
+        );
+        assert!(rendered.contains("library(mediation)"));
+        assert!(rendered.contains("m_1_mediator <- lm(rumination ~ condition + age, data = df)"));
+        assert!(rendered
+            .contains("m_1_outcome <- lm(wellbeing ~ condition + age + rumination, data = df)"));
+        assert!(rendered.contains(
+            "m_1 <- mediation::mediate(m_1_mediator, m_1_outcome, treat = \"condition\", mediator = \"rumination\", boot = TRUE, sims = 1000)"
+        ));
+        assert!(rendered.contains("model_registry[[\"Mediation\"]] <- m_1\n"));
+        assert!(rendered.contains("\"Mediation\" = m_1_outcome"));
+        assert!(rendered.contains("if (inherits(main_model, \"mediate\")) {"));
+
+        options.model_layouts = vec![layout(Some("  "))];
+        let rendered = render_models(&options, "wellbeing", "condition", "id", "time");
+        assert!(rendered.contains("# TODO: set a mediator variable for this mediation model"));
+        assert!(!rendered.contains("mediation::mediate("));
+        assert!(!rendered.contains("models_for_outcome"));
+    }
+
+    #[test]
+    fn clean_chunk_sets_treatment_factor_levels_from_expected_values() {
+        let mut options = empty_options();
+        options.model_layouts = vec![ModelLayout {
+            name: "Main".to_string(),
+            model_type: "ols".to_string(),
+            outcome_var: "wellbeing".to_string(),
+            treatment_var: Some("condition".to_string()),
+            layout: "simple".to_string(),
+            include_in_main_table: true,
+            ..Default::default()
+        }];
+        options.expected_values = BTreeMap::from([
+            (
+                "condition".to_string(),
+                vec!["control".to_string(), "treat".to_string()],
+            ),
+            ("wave".to_string(), vec!["1".to_string()]),
+        ]);
+        let rendered = render_analysis_rmd(
+            Path::new("project"),
+            Path::new("project/studies/S-ABC123"),
+            "S-ABC123",
+            "Test Study",
+            &options,
+        );
+        assert!(rendered.contains(
+            "df <- df %>% dplyr::mutate(condition = factor(condition, levels = c(\"control\", \"treat\")))"
+        ));
+        assert!(!rendered.contains("factor(wave"));
+
+        options.expected_values =
+            BTreeMap::from([("condition".to_string(), vec!["treat".to_string()])]);
+        let rendered = render_analysis_rmd(
+            Path::new("project"),
+            Path::new("project/studies/S-ABC123"),
+            "S-ABC123",
+            "Test Study",
+            &options,
+        );
+        assert!(rendered.contains(
+            "# TODO: set factor levels for condition explicitly (observed defaults: \"treat\")"
+        ));
+    }
+
+    #[test]
+    fn model_table_format_controls_table_extensions() {
+        let mut options = empty_options();
+        options.tables = vec!["model_table".to_string()];
+        options.model_layouts = vec![ModelLayout {
+            name: "OLS Main".to_string(),
+            model_type: "ols".to_string(),
+            outcome_var: "outcome_y".to_string(),
+            treatment_var: Some("treat_x".to_string()),
+            layout: "simple".to_string(),
+            include_in_main_table: true,
+            ..Default::default()
+        }];
+        let html_call = "output_path = file.path(tables_dir, \"models_outcome_y.html\")";
+        let docx_call =
+            "save_model_table_docx(models_for_outcome, file.path(tables_dir, \"models_outcome_y.docx\"))";
+
+        for format in [None, Some("html")] {
+            options.model_table_format = format.map(|f| f.to_string());
+            let rendered = render_models(&options, "outcome_y", "treat_x", "id", "time");
+            assert!(rendered.contains(html_call));
+            assert!(!rendered.contains("save_model_table_docx"));
+        }
+
+        options.model_table_format = Some("docx".to_string());
+        let rendered = render_models(&options, "outcome_y", "treat_x", "id", "time");
+        assert!(rendered.contains("output = \"flextable\""));
+        assert!(rendered.contains("flextable::save_as_docx"));
+        assert!(rendered.contains(docx_call));
+        assert!(!rendered.contains(html_call));
+        assert!(render_packages(&options).contains("library(officer)"));
+
+        options.model_table_format = Some("both".to_string());
+        let rendered = render_models(&options, "outcome_y", "treat_x", "id", "time");
+        assert!(rendered.contains(html_call));
+        assert!(rendered.contains(docx_call));
+    }
+
+    #[test]
+    fn create_template_writes_file_and_output_folders() {
+        let base = std::env::temp_dir().join(format!("analysis-test-{}", Uuid::new_v4()));
+        let study_root = base.join("S-ABC123");
+        let analysis_dir = study_root.join("06_analysis");
+        fs::create_dir_all(&analysis_dir).expect("failed to create temp analysis dir");
+
+        let options = empty_options();
+        let first = create_analysis_template_in_dir(
+            &base,
+            &study_root,
+            &analysis_dir,
+            "S-ABC123",
+            "Test Study",
+            &options,
+        )
+        .expect("expected first template to be created");
+        assert!(first.exists());
+        assert!(study_root.join("07_outputs").exists());
+        assert!(study_root.join("07_outputs").join("tables").exists());
+        assert!(study_root.join("07_outputs").join("figures").exists());
+        assert!(study_root.join("07_outputs").join("reports").exists());
+
+        let second = create_analysis_template_in_dir(
+            &base,
+            &study_root,
+            &analysis_dir,
+            "S-ABC123",
+            "Test Study",
+            &options,
+        )
+        .expect("expected second template to be created with timestamp");
+        assert!(second.exists());
+        assert_ne!(first, second);
+
+        let _ = fs::remove_dir_all(base);
+    }
+
+    #[test]
+    fn create_template_uses_custom_analysis_file_name() {
+        let base = std::env::temp_dir().join(format!("analysis-name-test-{}", Uuid::new_v4()));
+        let study_root = base.join("S-ABC123");
+        let analysis_dir = study_root.join("06_analysis");
+        fs::create_dir_all(&analysis_dir).expect("failed to create temp analysis dir");
+
+        let mut options = empty_options();
+        options.analysis_file_name = Some("pilot_analysis".to_string());
+
+        let first = create_analysis_template_in_dir(
+            &base,
+            &study_root,
+            &analysis_dir,
+            "S-ABC123",
+            "Test Study",
+            &options,
+        )
+        .expect("expected template with custom file name");
+
+        assert!(first.ends_with("pilot_analysis.Rmd"));
+
+        let _ = fs::remove_dir_all(base);
+    }
+
+    #[test]
+    fn render_uses_selected_data_sources_when_provided() {
+        let mut options = empty_options();
+        options.data_source_paths = Some(vec![
+            "/tmp/project/data/clean/a.csv".to_string(),
+            "/tmp/project/data/clean/b.tsv".to_string(),
+        ]);
+
+        let rendered = render_analysis_rmd(
+            Path::new("project"),
+            Path::new("project/studies/S-ABC123"),
+            "S-ABC123",
+            "Test Study",
+            &options,
+        );
+
+        assert!(rendered.contains("read_data_source <- function(path)"));
+        assert!(rendered.contains("/tmp/project/data/clean/a.csv"));
+        assert!(rendered.contains("/tmp/project/data/clean/b.tsv"));
+        assert!(rendered.contains("Supported: csv, tsv, txt, rds, sav, zsav, dta, xlsx, xls."));
+        assert!(!rendered.contains("library(haven)"));
+        assert!(!rendered.contains("library(readxl)"));
+        assert!(!rendered.contains("haven::as_factor"));
+    }
+
+    #[test]
+    fn spss_and_excel_sources_add_their_readers_only_when_present() {
+        let mut options = empty_options();
+        options.data_source_paths = Some(vec!["data/raw/wave1.SAV".to_string()]);
+        let packages = render_packages(&options);
+        assert!(packages.contains("library(haven)\n"));
+        assert!(!packages.contains("library(readxl)"));
+        let rendered = render_analysis_rmd(
+            Path::new("project"),
+            Path::new("project/studies/S-ABC123"),
+            "S-ABC123",
+            "Test Study",
+            &options,
+        );
+        assert!(rendered.contains("return(haven::read_sav(path))"));
+        assert!(rendered.contains("haven::as_factor"));
+
+        options.data_source_paths = Some(vec!["data/raw/wave2.xlsx".to_string()]);
+        options.data_source_sheet = Some("Responses".to_string());
+        let packages = render_packages(&options);
+        assert!(packages.contains("library(readxl)\n"));
+        assert!(!packages.contains("library(haven)"));
+        let rendered = render_analysis_rmd(
+            Path::new("project"),
+            Path::new("project/studies/S-ABC123"),
+            "S-ABC123",
+            "Test Study",
+            &options,
+        );
+        assert!(rendered.contains("readxl::read_excel(path, sheet = \"Responses\")"));
+        assert!(!rendered.contains("haven::as_factor"));
+
+        options.data_source_paths = Some(vec!["a.dta".to_string(), "b.xls".to_string()]);
+        options.data_source_sheet = Some("2".to_string());
+        let packages = render_packages(&options);
+        assert!(packages.contains("library(haven)\n") && packages.contains("library(readxl)\n"));
+        assert_eq!(excel_sheet_arg(&options), ", sheet = 2");
+    }
+
+    #[test]
+    fn render_groups_model_tables_by_outcome_from_layouts() {
+        let mut options = empty_options();
+        options.tables = vec!["model_table".to_string()];
+        options.model_layouts = vec![
+            ModelLayout {
+                name: "Model A".to_string(),
+                model_type: "ols".to_string(),
+                outcome_var: "y1".to_string(),
+                treatment_var: Some("x1 + x2".to_string()),
+                layout: "simple".to_string(),
+                covariates: Some("x1 + x2".to_string()),
+                figures: vec!["coef_plot".to_string()],
+                include_in_main_table: true,
+                ..Default::default()
+            },
+            ModelLayout {
+                name: "Model B".to_string(),
+                model_type: "ols".to_string(),
+                outcome_var: "y2".to_string(),
+                treatment_var: Some("x3".to_string()),
+                layout: "simple".to_string(),
+                covariates: Some("x3".to_string()),
+                figures: vec!["coef_plot".to_string()],
+                include_in_main_table: true,
+                ..Default::default()
+            },
+        ];
+
+        let rendered = render_analysis_rmd(
+            Path::new("project"),
+            Path::new("project/studies/S-ABC123"),
+            "S-ABC123",
+            "Test Study",
+            &options,
+        );
+        assert!(rendered.contains("models_y1.html"));
+        assert!(rendered.contains("models_y2.html"));
+        assert!(rendered.contains("Main Figures by Model Builder Input"));
+    }
+
+    #[test]
+    fn render_output_is_stable_and_follows_layout_order() {
+        let layout = |name: &str, model_type: &str, outcome: &str| ModelLayout {
+            name: name.to_string(),
+            model_type: model_type.to_string(),
+            outcome_var: outcome.to_string(),
+            treatment_var: Some("condition".to_string()),
+            layout: "simple".to_string(),
+            figures: vec!["coef_plot".to_string()],
+            include_in_main_table: true,
+            ..Default::default()
+        };
+        let mut options = empty_options();
+        options.tables = vec!["model_table".to_string()];
+        options.descriptives = vec!["missingness".to_string()];
+        options.model_layouts = vec![
+            layout("Count", "negbin", "y_count"),
+            layout("Main", "ols", "y_main"),
+        ];
+        let render = |options: &AnalysisTemplateOptions| {
+            render_analysis_rmd(
+                Path::new("project"),
+                Path::new("project/studies/S-ABC123"),
+                "S-ABC123",
+                "Test Study",
+                options,
+            )
+        };
+
+        let first = render(&options);
+        assert_eq!(first, render(&options));
+        let table_pos = |text: &str, outcome: &str| {
+            text.find(&format!("models_{outcome}.html"))
+                .expect("outcome table")
+        };
+        assert!(table_pos(&first, "y_count") < table_pos(&first, "y_main"));
+
+        let packages = render_packages(&options);
+        assert!(packages.contains(
+            "library(kableExtra)\nlibrary(car)\nlibrary(lmtest)\nlibrary(MASS)\nlibrary(naniar)"
+        ));
+
+        options.model_layouts.reverse();
+        assert_eq!(packages, render_packages(&options));
+        let reversed = render(&options);
+        assert!(table_pos(&reversed, "y_main") < table_pos(&reversed, "y_count"));
+    }
+
+    #[test]
+    fn winsorize_check_uses_configured_cut_points_and_refits_the_registry() {
+        let mut options: AnalysisTemplateOptions = serde_json::from_value(serde_json::json!({
+          "robustness": ["winsorize"],
+          "robustnessParams": {"winsorize": {"lower": 0.05, "upper": 0.95}}
+        }))
+        .expect("options");
+        validate_robustness_params(&options).expect("valid cut points");
+
+        let robustness =
+            render_robustness(&options, &["wellbeing".to_string(), "stress".to_string()]);
+        assert!(robustness.contains("```{r robustness_winsorize}"));
+        assert!(robustness.contains("winsorize_vars <- c(\"wellbeing\", \"stress\")\n"));
+        assert!(robustness.contains("winsorize_probs <- c(0.05, 0.95)\n"));
+        assert!(robustness.contains("DescTools::Winsorize(.x, val = stats::quantile("));
+        assert!(robustness.contains(
+            "for (nm in names(model_registry)) {\n  refit <- tryCatch(\n    stats::update(model_registry[[nm]], data = df_winsorized),"
+        ));
+        assert!(robustness.contains(
+            "list(Original = model_registry[[nm]], Winsorized = winsorized_registry[[nm]])"
+        ));
+        assert!(!robustness.contains("TODO"));
+        assert!(render_packages(&options).contains("library(DescTools)\n"));
+
+        options.robustness_params.winsorize.variables = vec!["income".to_string()];
+        assert!(render_robustness(&options, &["wellbeing".to_string()])
+            .contains("winsorize_vars <- c(\"income\")\n"));
+        options.robustness_params.winsorize.lower = 0.99;
+        assert!(validate_robustness_params(&options).is_err());
+    }
+
+    #[test]
+    fn effect_sizes_cover_ols_and_logit_layouts() {
+        let layout = |name: &str, model_type: &str| ModelLayout {
+            name: name.to_string(),
+            model_type: model_type.to_string(),
+            outcome_var: "y".to_string(),
+            treatment_var: Some("condition".to_string()),
+            layout: "simple".to_string(),
+            include_in_main_table: true,
+            ..Default::default()
+        };
+        let mut options = empty_options();
+        options.model_layouts = vec![
+            layout("Main", "ols"),
+            layout("Choice", "logit"),
+            layout("Panel", "fixed_effects"),
+        ];
+        let render = |options: &AnalysisTemplateOptions| {
+            render_analysis_rmd(
+                Path::new("project"),
+                Path::new("project/studies/S-ABC123"),
+                "S-ABC123",
+                "Test Study",
+                options,
+            )
+        };
+
+        let without = render(&options);
+        assert!(!without.contains("effect_sizes"));
+        assert!(!without.contains("library(effectsize)"));
+
+        options.effect_sizes = true;
+        options.export_artifacts = true;
+        let rendered = render(&options);
+        assert!(rendered.contains("library(effectsize)"));
+        assert!(rendered.contains("<!-- rw:begin:effect_sizes -->"));
+        assert!(rendered.contains("std_betas_m_1 <- effectsize::standardize_parameters(m_1)"));
+        assert!(rendered
+            .contains("eta_sq_m_1 <- effectsize::eta_squared(car::Anova(m_1), partial = TRUE)"));
+        assert!(rendered
+            .contains("odds_ratios_m_2 <- broom::tidy(m_2, conf.int = TRUE, exponentiate = TRUE)"));
+        assert!(rendered.contains("effect_size_rows(\"Choice\", \"odds_ratio\""));
+        assert!(!rendered.contains("standardize_parameters(m_2)\n"));
+        assert!(rendered.contains("# Note: effectsize support for fixed_effects fits (Panel)"));
+        assert!(rendered.contains("effect_sizes_ft <- ft_apa(effect_sizes)"));
+        assert!(rendered.contains(
+            "flextable::save_as_docx(effect_sizes_ft, path = file.path(tables_dir, \"effect_sizes.docx\"))"
+        ));
+    }
+
+    #[test]
+    fn parallel_trends_fit_leads_and_lags_for_did_layouts() {
+        let mut options = empty_options();
+        options.diagnostics = vec!["parallel_trends".to_string()];
+        options.time_var_hint = Some("wave".to_string());
+        let skipped = render_diagnostics(&options);
+        assert!(skipped.contains("need a did or event_study model layout"));
+        assert!(!skipped.contains("fixest::wald"));
+
+        options.model_layouts = vec![ModelLayout {
+            name: "Policy".to_string(),
+            model_type: "did".to_string(),
+            outcome_var: "turnout".to_string(),
+            treatment_var: Some("treated".to_string()),
+            layout: "simple".to_string(),
+            id_var: Some("county".to_string()),
+            include_in_main_table: true,
+            ..Default::default()
+        }];
+        let rendered = render_diagnostics(&options);
+        assert!(rendered.contains("min(wave[treated == 1], na.rm = TRUE)"));
+        assert!(rendered.contains(
+            "pretrend_1 <- fixest::feols(turnout ~ i(rel_time, ref = c(-1, -1000)) | county + wave, data = pretrend_1_df, cluster = ~county)\n"
+        ));
+        assert!(rendered.contains("fixest::iplot(pretrend_1, main = \"Pre-trends: Policy\""));
+        assert!(
+            rendered.contains("print(fixest::wald(pretrend_1, keep = \"^rel_time::-[0-9]+$\"))\n")
+        );
+        assert!(!rendered.contains("TODO: implement pre-trend"));
+    }
+
+    #[test]
+    fn subgroup_vars_generate_one_refit_loop_and_export_per_variable() {
+        let mut options = empty_options();
+        options.exploratory = true;
+        let layout = ModelLayout {
+            name: "Main effect".to_string(),
+            model_type: "ols".to_string(),
+            outcome_var: "wellbeing".to_string(),
+            treatment_var: Some("condition".to_string()),
+            layout: "simple".to_string(),
+            covariates: Some("age".to_string()),
+            include_in_main_table: true,
+            subgroup_vars: vec!["gender".to_string(), " Age-Group ".to_string()],
+            ..Default::default()
+        };
+        options.model_layouts = vec![layout];
+        let rendered = render_exploratory(&options);
+        assert_eq!(rendered.matches("for (level in sort(unique(").count(), 2);
+        assert_eq!(rendered.matches("flextable::save_as_docx(").count(), 2);
+        assert!(rendered.contains("```{r subgroup_model_1_main_effect_gender}\n"));
+        assert!(rendered.contains("```{r subgroup_model_1_main_effect_age_group}\n"));
+        assert!(rendered.contains("broom::tidy(update(m_1, data = level_df), conf.int = TRUE)"));
+        assert!(rendered.contains("facet_wrap(~ subgroup)"));
+        assert!(rendered.contains(
+            "subgroup_model_1_main_effect_gender_ft <- ft_apa(subgroup_model_1_main_effect_gender)\n"
+        ));
+        assert!(rendered.contains(
+            "# subgroup_model_1_main_effect_gender_interaction <- update(m_1, . ~ . + condition * gender)\n"
+        ));
+        assert!(!rendered.contains("TODO: add subgroup analyses"));
+
+        options.model_layouts[0].subgroup_vars = vec!["gender; system('x')".to_string()];
+        assert!(validate_model_layouts(&options)
+            .expect_err("subgroup vars must be column names")
+            .contains("plain column name"));
+    }
+
+    fn iv_layout(instruments: &[&str]) -> ModelLayout {
+        ModelLayout {
+            name: "Uptake IV".to_string(),
+            model_type: "iv".to_string(),
+            outcome_var: "earnings".to_string(),
+            treatment_var: Some("offered".to_string()),
+            layout: "simple".to_string(),
+            covariates: Some("age + female".to_string()),
+            id_var: Some("village".to_string()),
+            include_in_main_table: true,
+            instrument_vars: instruments.iter().map(|v| v.to_string()).collect(),
+            endogenous_var: Some("took_up".to_string()),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn iv_layout_renders_feols_instrument_syntax_and_first_stage_check() {
+        let mut options = empty_options();
+        options.model_layouts = vec![iv_layout(&["offered", " lottery_rank "])];
+        let rendered = render_models(&options, "earnings", "offered", "id", "time");
+        assert!(rendered.contains(
+            "m_1 <- fixest::feols(earnings ~ age + female | took_up ~ offered + lottery_rank, data = df, vcov = ~village)\n"
+        ));
+        assert!(rendered.contains("model_registry[[\"Uptake IV\"]] <- m_1\n"));
+        assert!(rendered.contains("include_main_table = TRUE"));
+        assert!(!rendered.contains("TODO: set instrument variables"));
+
+        let diagnostics = render_diagnostics(&options);
+        assert!(diagnostics.contains("```{r diag_weak_instruments}\n"));
+        assert!(diagnostics.contains("print(fixest::fitstat(m, \"ivf\"))\n"));
+        assert!(render_packages(&options).contains("fixest"));
+    }
+
+    #[test]
+    fn cluster_var_adds_vcovcl_reporting_and_feeds_the_tables_and_robustness_check() {
+        let mut options = empty_options();
+        let mut ols = iv_layout(&[]);
+        ols.name = "Main".to_string();
+        ols.model_type = "ols".to_string();
+        ols.endogenous_var = None;
+        ols.cluster_var = Some(" participant_id ".to_string());
+        let mut fe = iv_layout(&[]);
+        fe.endogenous_var = None;
+        fe.name = "Panel".to_string();
+        fe.model_type = "fixed_effects".to_string();
+        fe.time_var = Some("wave".to_string());
+        fe.cluster_var = Some("session".to_string());
+        options.model_layouts = vec![ols, fe];
+        options.tables = vec!["model_table".to_string()];
+        options.robustness = vec!["cluster_se".to_string()];
+        validate_model_layouts(&options).expect("valid cluster vars");
+
+        let rendered = render_models(&options, "earnings", "offered", "id", "time");
+        assert!(rendered.contains(
+            "m_1_vcov <- sandwich::vcovCL(m_1, cluster = ~participant_id)\nprint(lmtest::coeftest(m_1, vcov = m_1_vcov))\n"
+        ));
+        assert!(rendered.contains("data = df, vcov = ~session)\n"));
+        assert!(rendered.contains("cluster = \"participant_id\")\n"));
+        assert!(rendered.contains("vcov_for_outcome <- list(m_1_vcov, ~session)\n"));
+        assert!(rendered.contains("\"models_earnings.html\"), vcov = vcov_for_outcome)\n"));
+
+        let robustness = render_robustness(&options, &[]);
+        assert!(robustness.contains("dplyr::filter(model_metadata, !is.na(cluster))"));
+        assert!(robustness.contains("sandwich::vcovCL(m, cluster = cluster_formula)"));
+        assert!(!robustness.contains("cluster_id"));
+
+        options.model_layouts[0].model_type = "mediation".to_string();
+        assert!(validate_model_layouts(&options)
+            .expect_err("mediation is not clustered")
+            .contains("does not support clustered standard errors"));
+    }
+
+    #[test]
+    fn iv_layout_without_instruments_renders_a_todo_instead_of_a_formula() {
+        let mut options = empty_options();
+        let mut layout = iv_layout(&[" "]);
+        layout.id_var = None;
+        layout.covariates = None;
+        options.model_layouts = vec![layout];
+        let rendered = render_models(&options, "earnings", "offered", "id", "time");
+        assert!(rendered.contains(
+            "# TODO: set instrument variables for this IV model in the model builder; fixest needs earnings ~ controls | took_up ~ instruments.\n"
+        ));
+        assert!(rendered.contains("m_1 <- NULL\n"));
+        assert!(!rendered.contains("fixest::feols("));
+        assert!(!rendered.contains("main_model <- m_1\n"));
+
+        options.model_layouts[0].instrument_vars = vec!["z); system('x'".to_string()];
+        assert!(validate_model_layouts(&options)
+            .expect_err("instruments must be column names")
+            .contains("plain column name"));
+    }
+
+    #[test]
+    fn marginal_effects_need_the_table_option_and_an_eligible_model() {
+        let layout = |name: &str, model_type: &str| ModelLayout {
+            name: name.to_string(),
+            model_type: model_type.to_string(),
+            outcome_var: "y".to_string(),
+            treatment_var: Some("condition".to_string()),
+            layout: "simple".to_string(),
+            include_in_main_table: true,
+            ..Default::default()
+        };
+        let render = |options: &AnalysisTemplateOptions| {
+            render_analysis_rmd(
+                Path::new("project"),
+                Path::new("project/studies/S-ABC123"),
+                "S-ABC123",
+                "Test Study",
+                options,
+            )
+        };
+        let mut options = empty_options();
+        options.model_layouts = vec![layout("Main", "ols"), layout("Hazard", "survival")];
+        options.tables = vec!["marginal_effects_table".to_string()];
+        let ineligible = render(&options);
+        assert!(!ineligible.contains("```{r marginal_effects}"));
+        assert!(!ineligible.contains("library(marginaleffects)"));
+
+        options.model_layouts.push(layout("Choice", "logit"));
+        let rendered = render(&options);
+        assert!(rendered.contains("library(marginaleffects)"));
+        assert!(rendered.contains("<!-- rw:begin:marginal_effects -->"));
+        assert!(
+            rendered.contains("marginal_effect_rows(\"Main\", marginaleffects::avg_slopes(m_1))")
+        );
+        assert!(
+            rendered.contains("marginal_effect_rows(\"Choice\", marginaleffects::avg_slopes(m_3))")
+        );
+        assert!(rendered.contains("# Note: Hazard (survival) is skipped"));
+        assert!(!rendered.contains("avg_slopes(m_2)"));
+        assert!(rendered.contains(
+            "flextable::save_as_html(marginal_effects_ft, path = file.path(tables_dir, \"marginal_effects.html\"))"
+        ));
+        assert!(rendered.contains(
+            "flextable::save_as_docx(marginal_effects_ft, path = file.path(tables_dir, \"marginal_effects.docx\"))"
+        ));
+
+        options.tables.clear();
+        let unselected = render(&options);
+        assert!(!unselected.contains("```{r marginal_effects}"));
+        assert!(!unselected.contains("library(marginaleffects)"));
+    }
+
+    #[test]
+    fn bayesian_layout_uses_brms_alongside_frequentist_layout() {
+        let mut options = empty_options();
+        options.bayes_options = BayesOptions {
+            chains: 2,
+            iter: 1000,
+            seed: 42,
+        };
+        options.model_layouts = vec![
+            ModelLayout {
+                name: "Bayes Logit".to_string(),
+                model_type: "logit".to_string(),
+                outcome_var: "clicked".to_string(),
+                treatment_var: Some("condition".to_string()),
+                layout: "simple".to_string(),
+                figures: vec!["coef_plot".to_string()],
+                include_in_main_table: true,
+                estimation: Some("bayesian".to_string()),
+                ..Default::default()
+            },
+            ModelLayout {
+                name: "OLS".to_string(),
+                model_type: "ols".to_string(),
+                outcome_var: "score".to_string(),
+                treatment_var: Some("condition".to_string()),
+                layout: "simple".to_string(),
+                figures: vec!["coef_plot".to_string()],
+                include_in_main_table: true,
+                ..Default::default()
+            },
+        ];
+
+        let rendered = render_analysis_rmd(
+            Path::new("project"),
+            Path::new("project/studies/S-ABC123"),
+            "S-ABC123",
+            "Test Study",
+            &options,
+        );
+        assert!(rendered.contains(
+            "m_1 <- brms::brm(clicked ~ condition, data = df, family = bernoulli(), chains = 2, iter = 1000, seed = 42)"
+        ));
+        assert!(rendered.contains("m_2 <- lm(score ~ condition, data = df)"));
+        assert!(rendered.contains("model_registry[[\"Bayes Logit\"]] <- m_1"));
+        assert!(rendered.contains("print(summary(model_registry[[\"Bayes Logit\"]]))"));
+        assert!(rendered.contains("print(broom::glance(model_registry[[\"OLS\"]]))"));
+        assert!(rendered.contains("brms::mcmc_plot(main_model, type = \"intervals\""));
+        assert!(rendered.contains("compiles a Stan model"));
+        assert!(rendered.contains("library(brms)"));
+        assert!(rendered.contains("library(bayesplot)"));
+        assert!(!rendered.contains("glm(clicked"));
+    }
+
+    #[test]
+    fn frequentist_layouts_do_not_load_bayesian_packages() {
+        let mut options = empty_options();
+        options.model_layouts = vec![ModelLayout {
+            name: "Logit".to_string(),
+            model_type: "logit".to_string(),
+            outcome_var: "clicked".to_string(),
+            treatment_var: Some("condition".to_string()),
+            layout: "simple".to_string(),
+            include_in_main_table: true,
+            estimation: Some("frequentist".to_string()),
+            ..Default::default()
+        }];
+        let rendered = render_analysis_rmd(
+            Path::new("project"),
+            Path::new("project/studies/S-ABC123"),
+            "S-ABC123",
+            "Test Study",
+            &options,
+        );
+        assert!(rendered.contains("glm(clicked ~ condition, data = df, family = binomial())"));
+        assert!(!rendered.contains("brms"));
+        assert_eq!(brms_family("logit"), Some("bernoulli()"));
+        assert_eq!(brms_family("negbin"), Some("negbinomial()"));
+        assert_eq!(brms_family("rd"), None);
+    }
+
+    fn contrast(name: &str, levels: &[&str], weights: &[f64]) -> ContrastSpec {
+        ContrastSpec {
+            name: name.to_string(),
+            levels: levels.iter().map(|level| level.to_string()).collect(),
+            weights: weights.to_vec(),
+        }
+    }
+
+    #[test]
+    fn contrasts_render_weight_lists_after_model() {
+        let mut options = empty_options();
+        options.export_artifacts = true;
+        options.model_layouts = vec![ModelLayout {
+            name: "Main".to_string(),
+            model_type: "ols".to_string(),
+            outcome_var: "score".to_string(),
+            treatment_var: Some("condition".to_string()),
+            layout: "simple".to_string(),
+            covariates: Some("age".to_string()),
+            include_in_main_table: true,
+            contrasts: vec![
+                contrast("A vs control", &["control", "treat_a"], &[-1.0, 1.0]),
+                contrast(
+                    "A vs B",
+                    &["control", "treat_a", "treat_b"],
+                    &[0.0, 0.5, -0.5],
+                ),
+            ],
+            ..Default::default()
+        }];
+
+        let rendered = render_analysis_rmd(
+            Path::new("project"),
+            Path::new("project/studies/S-ABC123"),
+            "S-ABC123",
+            "Test Study",
+            &options,
+        );
+        assert!(rendered.contains("emm_m_1 <- emmeans::emmeans(m_1, specs = ~ condition)"));
+        assert!(rendered.contains("  \"A vs control\" = c(\"control\" = -1, \"treat_a\" = 1),\n"));
+        assert!(rendered.contains(
+            "  \"A vs B\" = c(\"control\" = 0, \"treat_a\" = 0.5, \"treat_b\" = -0.5)\n"
+        ));
+        assert!(rendered.contains("emmeans::contrast(emm_m_1, method = contrast_matrix_m_1)"));
+        assert!(rendered.contains("file.path(tables_dir, \"contrasts_main.docx\")"));
+        assert!(rendered.contains("library(emmeans)"));
+        let model_pos = rendered.find("m_1 <- lm(").expect("model fit");
+        let contrast_pos = rendered.find("emm_m_1 <-").expect("contrast chunk");
+        assert!(model_pos < contrast_pos);
+    }
+
+    #[test]
+    fn contrast_validation_rejects_bad_weights() {
+        assert!(validate_contrast(&contrast("ok", &["a", "b"], &[1.0, -1.0])).is_ok());
+        let err = validate_contrast(&contrast("skewed", &["a", "b"], &[1.0, 0.0]))
+            .expect_err("non-zero sum should fail");
+        assert!(err.contains("sum to zero"));
+        assert!(validate_contrast(&contrast("short", &["a", "b", "c"], &[1.0, -1.0])).is_err());
+    }
+
+    #[test]
+    fn style_profiles_swap_table_helpers_and_packages() {
+        let mut options = empty_options();
+        options.descriptives = vec!["summary_stats".to_string(), "counts".to_string()];
+        options.tables = vec!["model_table".to_string()];
+        options.model_table_format = Some("docx".to_string());
+        options.model_layouts = vec![ModelLayout {
+            name: "OLS Main".to_string(),
+            model_type: "ols".to_string(),
+            outcome_var: "outcome_y".to_string(),
+            treatment_var: Some("treat_x".to_string()),
+            layout: "simple".to_string(),
+            include_in_main_table: true,
+            ..Default::default()
+        }];
+        let render = |options: &AnalysisTemplateOptions| {
+            render_analysis_rmd(
+                Path::new("project"),
+                Path::new("project/studies/S-ABC123"),
+                "S-ABC123",
+                "Test Study",
+                options,
+            )
+        };
+
+        let apa = render(&options);
+        assert!(apa.contains("summary_stats_ft <- ft_apa_descriptives("));
+        assert!(apa.contains("ft_apa(counts_tbl)"));
+        assert!(apa.contains("theme_apa()"));
+        assert!(apa.contains("style_pkg_loaded"));
+        assert!(apa.contains("output = \"flextable\""));
+        let apa_packages = render_packages(&options);
+        assert!(apa_packages.contains("library(flextable)"));
+        assert!(apa_packages.contains("library(officer)"));
+
+        options.style_profile = Some("minimal_gt".to_string());
+        let minimal = render(&options);
+        assert!(minimal.contains("summary_stats_ft <- gt_minimal_descriptives("));
+        assert!(minimal.contains("gt_minimal(counts_tbl)"));
+        assert!(minimal.contains("theme_minimal(base_size = 12)"));
+        assert!(minimal.contains("output = \"gt\""));
+        assert!(!minimal.contains("ft_apa"));
+        assert!(!minimal.contains("theme_apa"));
+        assert!(!minimal.contains("style_pkg_loaded"));
+        let minimal_packages = render_packages(&options);
+        assert!(minimal_packages.contains("library(gt)"));
+        assert!(!minimal_packages.contains("library(flextable)"));
+        assert!(!minimal_packages.contains("library(officer)"));
+        assert!(!minimal_packages.contains("library(ggpubr)"));
+    }
+
+    #[test]
+    fn unknown_style_profile_fails_template_creation() {
+        let base = std::env::temp_dir().join(format!("analysis-style-{}", Uuid::new_v4()));
+        let study_root = base.join("S-ABC123");
+        let mut options = empty_options();
+        options.style_profile = Some("chicago".to_string());
+        let err = create_analysis_template_in_dir(
+            &base,
+            &study_root,
+            &study_root.join(ANALYSIS_FOLDER),
+            "S-ABC123",
+            "Study",
+            &options,
+        )
+        .expect_err("unknown profile should fail");
+        assert!(err.contains("Available profiles: apa, minimal_gt"));
+        let _ = fs::remove_dir_all(base);
+    }
+
+    #[test]
+    fn subset_filter_validation_rejects_unsafe_expressions() {
+        assert!(validate_subset_filter("information_condition == 1").is_ok());
+        assert!(validate_subset_filter("(age >= 18) & (country %in% c(\"US\", \"UK\"))").is_ok());
+        assert!(validate_subset_filter("x == 1; unlink(\"data\")")
+            .expect_err("semicolon")
+            .contains("';'"));
+        assert!(validate_subset_filter("system (\"rm -rf /\") == 0")
+            .expect_err("system call")
+            .contains("system()"));
+        assert!(validate_subset_filter("(x == 1")
+            .expect_err("unbalanced")
+            .contains("unbalanced"));
+        assert!(validate_subset_filter("x == 1)(").is_err());
+    }
+
+    #[test]
+    fn subset_filter_wraps_model_data_and_records_metadata() {
+        let layout = |name: &str, model_type: &str, subset: Option<&str>| ModelLayout {
+            name: name.to_string(),
+            model_type: model_type.to_string(),
+            outcome_var: "donation".to_string(),
+            treatment_var: Some("condition".to_string()),
+            layout: "simple".to_string(),
+            id_var: Some("pid".to_string()),
+            time_var: Some("wave".to_string()),
+            include_in_main_table: true,
+            subset_filter: subset.map(|s| s.to_string()),
+            ..Default::default()
+        };
+        let mut options = empty_options();
+        options.model_layouts = vec![
+            layout("Informed", "ols", Some("information_condition == 1")),
+            layout("Panel", "fixed_effects", Some(" wave > 1 ")),
+            layout("Full", "ols", None),
+        ];
+        let rendered = render_models(&options, "donation", "condition", "pid", "wave");
+        assert!(rendered.contains(
+            "m_1 <- lm(donation ~ condition, data = dplyr::filter(df, information_condition == 1))"
+        ));
+        assert!(
+            rendered.contains("# Subset: model fit only on rows where information_condition == 1")
+        );
+        assert!(rendered.contains(
+            "cat(\"N in subset:\", nrow(dplyr::filter(df, information_condition == 1)), \"\\n\")"
+        ));
+        assert!(rendered.contains("data = df, subset = ~ (wave > 1), vcov = \"cluster\")"));
+        assert!(rendered.contains("m_3 <- lm(donation ~ condition, data = df)"));
+        assert!(
+            rendered.contains("subset = \"information_condition == 1\", cluster = NA_character_)")
+        );
+        assert!(rendered.contains("subset = NA_character_, cluster = NA_character_)"));
+
+        options.model_layouts = vec![layout("Bad", "ols", Some("x == 1; q()"))];
+        let err = validate_model_layouts(&options).expect_err("invalid subset");
+        assert!(err.starts_with("Model layout 'Bad'"));
+    }
+
+    #[test]
+    fn qmd_render_uses_quarto_header_and_chunk_options_in_same_order() {
+        let mut options = empty_options();
+        options.descriptives = vec!["summary_stats".to_string()];
+        options.diagnostics = vec!["normality".to_string()];
+        options.export_artifacts = true;
+        options.model_layouts = vec![ModelLayout {
+            name: "Main".to_string(),
+            model_type: "ols".to_string(),
+            outcome_var: "wellbeing".to_string(),
+            treatment_var: Some("condition".to_string()),
+            layout: "simple".to_string(),
+            include_in_main_table: true,
+            ..Default::default()
+        }];
+        let render = |options: &AnalysisTemplateOptions| {
+            render_analysis_rmd(
+                Path::new("/tmp/project"),
+                Path::new("/tmp/project/studies/S-1"),
+                "S-1",
+                "Study",
+                options,
+            )
+        };
+        let rmd = render(&options);
+        options.output_format = Some("qmd".to_string());
+        let qmd = render(&options);
+
+        assert!(rmd.contains("```{r setup, include=FALSE}"));
+        assert!(qmd.contains("format:\n  html:\n    toc: true\n    toc-depth: 3\n"));
+        assert!(!qmd.contains("html_document"));
+        assert!(qmd.contains("```{r}\n#| label: setup\n#| include: false\n"));
+        assert!(qmd.contains("#| label: packages\n#| message: false\n#| warning: false\n"));
+        assert!(qmd.lines().all(|line| !line.starts_with("```{r ")));
+
+        let headings = |text: &str| -> Vec<String> {
+            text.lines()
+                .filter(|line| line.starts_with('#') && !line.starts_with("#|"))
+                .filter(|line| line.starts_with("# ") || line.starts_with("## "))
+                .map(str::to_string)
+                .collect()
+        };
+        assert_eq!(headings(&rmd), headings(&qmd));
+        let labels = |text: &str| -> Vec<String> {
+            text.lines()
+                .filter_map(|line| {
+                    line.strip_prefix("#| label: ")
+                        .or_else(|| line.strip_prefix("```{r "))
+                        .map(|rest| {
+                            rest.trim_end_matches('}')
+                                .split(',')
+                                .next()
+                                .unwrap_or("")
+                                .to_string()
+                        })
+                })
+                .collect()
+        };
+        assert_eq!(labels(&rmd), labels(&qmd));
+        assert!(labels(&qmd).len() > 5);
+
+        assert_eq!(
+            split_template_name("pilot.qmd"),
+            ("pilot", Some(TemplateFormat::Qmd))
+        );
+        assert_eq!(
+            normalized_analysis_file_base(&Some("pilot.QMD".to_string())).expect("base"),
+            "pilot"
+        );
+        assert!(TemplateFormat::parse(Some("docx")).is_err());
+    }
+
+    #[test]
+    fn template_writes_models_manifest_matching_layouts() {
+        let layout = |name: &str, layout: &str, figures: Vec<String>, main: bool| ModelLayout {
+            name: name.to_string(),
+            model_type: "ols".to_string(),
+            outcome_var: "wellbeing".to_string(),
+            layout: layout.to_string(),
+            interaction_var: Some("age".to_string()),
+            covariates: Some(" income + age ".to_string()),
+            figures,
+            include_in_main_table: main,
+            ..Default::default()
+        };
+        let mut options = empty_options();
+        options.treatment_var_hint = Some("condition".to_string());
+        options.model_layouts = vec![
+            layout("Main Effect", "simple", vec!["coef_plot".to_string()], true),
+            layout("Moderation", "interaction", Vec::new(), false),
+        ];
+        let base = std::env::temp_dir().join(format!("template-manifest-{}", Uuid::new_v4()));
+        let study_root = base.join("study");
+        let analysis_dir = study_root.join(ANALYSIS_FOLDER);
+
+        let rmd = create_analysis_template_in_dir(
+            &base,
+            &study_root,
+            &analysis_dir,
+            "S-ABC123",
+            "Study",
+            &options,
+        )
+        .expect("create template");
+        let text = fs::read_to_string(&rmd).expect("read rmd");
+        let raw = fs::read_to_string(manifest_path(&rmd)).expect("manifest written");
+        let manifest: models_manifest::ModelsManifest =
+            serde_json::from_str(&raw).expect("manifest json");
+        assert_eq!(manifest.source_file, "analysis.Rmd");
+        let summary: Vec<(&str, &str, &str, bool)> = manifest
+            .models
+            .iter()
+            .map(|m| {
+                (
+                    m.name.as_str(),
+                    m.layout.as_str(),
+                    m.chunk_id.as_str(),
+                    m.include_in_main_table,
+                )
+            })
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                ("Main Effect", "simple", "model_1_main_effect", true),
+                ("Moderation", "interaction", "model_2_moderation", false),
+            ]
+        );
+        for model in &manifest.models {
+            assert!(text.contains(&format!("```{{r {}}}", model.chunk_id)));
+            assert_eq!(model.treatment, "condition");
+            assert_eq!(model.covariates, "income + age");
+        }
+        assert_eq!(manifest.models[0].figures, vec!["coef_plot".to_string()]);
+
+        options.model_layouts.truncate(1);
+        regenerate_analysis_template_in_dir(
+            &base,
+            &study_root,
+            &analysis_dir,
+            "S-ABC123",
+            "Study",
+            "analysis",
+            &options,
+        )
+        .expect("regenerate");
+        let raw = fs::read_to_string(manifest_path(&rmd)).expect("manifest rewritten");
+        let manifest: models_manifest::ModelsManifest =
+            serde_json::from_str(&raw).expect("manifest json");
+        assert_eq!(manifest.models.len(), 1);
+        assert!(fs::read_dir(&analysis_dir)
+            .expect("dir")
+            .filter_map(|entry| entry.ok())
+            .all(|entry| !entry.file_name().to_string_lossy().ends_with(".tmp")));
+        let _ = fs::remove_dir_all(base);
+    }
+
+    #[test]
+    fn weighted_ols_layout_passes_weights_to_fit_and_descriptives() {
+        let layout = |model_type: &str, weight: &str| ModelLayout {
+            name: "Weighted".to_string(),
+            model_type: model_type.to_string(),
+            outcome_var: "wellbeing".to_string(),
+            treatment_var: Some("condition".to_string()),
+            layout: "simple".to_string(),
+            covariates: Some("age".to_string()),
+            include_in_main_table: true,
+            weight_var: Some(weight.to_string()),
+            ..Default::default()
+        };
+        let mut options = empty_options();
+        options.descriptives = vec!["summary_stats".to_string()];
+        options.tables = vec!["table1_descriptives".to_string()];
+        options.model_layouts = vec![layout("ols", " ps_weight ")];
+        validate_model_layouts(&options).expect("ols accepts weights");
+
+        let models = render_models(&options, "wellbeing", "condition", "pid", "wave");
+        assert!(models.contains("# Weighted by ps_weight\n"));
+        assert!(render_packages(&options).contains("library(survey)\n"));
+        let robustness = render_robustness(&options, &["wellbeing".to_string()]);
+        assert!(robustness.contains("```{r robustness_weight_sensitivity}"));
+        assert!(robustness.contains("m_weighted <- model_registry[[\"Weighted\"]]"));
+        assert!(robustness.contains("stats::update(m_weighted, weights = NULL)"));
+        assert!(models
+            .contains("m_1 <- lm(wellbeing ~ condition + age, data = df, weights = ps_weight)"));
+
+        let descriptives = render_descriptives(
+            &options,
+            &["wellbeing".to_string()],
+            "condition",
+            "condition",
+        );
+        assert!(descriptives.contains("Weighted means use `ps_weight`"));
+        assert!(descriptives.contains(
+            "Heading(\\\"Weighted mean\\\") * weighted.mean * Arguments(w = ps_weight, na.rm = TRUE)"
+        ));
+
+        let parsed: ModelLayout = serde_json::from_str(
+            r#"{"name":"W","modelType":"ols","outcomeVar":"y","layout":"simple","weightsVar":"w"}"#,
+        )
+        .expect("layout json");
+        assert_eq!(weight_var(&parsed).as_deref(), Some("w"));
+
+        options.model_layouts = vec![ModelLayout {
+            name: "Panel".to_string(),
+            id_var: Some("pid".to_string()),
+            time_var: Some("wave".to_string()),
+            ..layout("fixed_effects", "ps_weight")
+        }];
+        validate_model_layouts(&options).expect("fixest accepts weights");
+        let models = render_models(&options, "wellbeing", "condition", "pid", "wave");
+        assert!(models.contains("data = df, weights = ~ps_weight, vcov = \"cluster\")"));
+        let robustness = render_robustness(&options, &["wellbeing".to_string()]);
+        assert!(robustness.contains(
+            "m_weighted <- model_registry[[\"Panel\"]]\nunweighted_call <- stats::getCall(m_weighted)\nunweighted_call$weights <- NULL\nm_unweighted <- eval(unweighted_call)\n"
+        ));
+        assert!(!robustness.contains("stats::update"));
+
+        options.model_layouts = vec![layout("ols", "w; system(\"x\")")];
+        assert!(validate_model_layouts(&options).is_err());
+        options.model_layouts = vec![layout("custom_gmm", "ps_weight")];
+        let err = validate_model_layouts(&options).expect_err("unsupported type");
+        assert!(err.contains("does not support a weight variable"));
+    }
+
+    #[test]
+    fn regenerate_replaces_regions_and_keeps_manual_edits() {
+        let base = std::env::temp_dir().join(format!("analysis-regen-{}", Uuid::new_v4()));
+        let study_root = base.join("S-ABC123");
+        let analysis_dir = study_root.join("06_analysis");
+        fs::create_dir_all(&analysis_dir).expect("failed to create temp analysis dir");
+
+        let mut options = empty_options();
+        options.outcome_var_hint = Some("wellbeing".to_string());
+        options.plots = vec!["histogram".to_string()];
+        let path = create_analysis_template_in_dir(
+            &base,
+            &study_root,
+            &analysis_dir,
+            "S-ABC123",
+            "Test Study",
+            &options,
+        )
+        .expect("template created");
+        let original = fs::read_to_string(&path).expect("read template");
+        assert!(original.contains("<!-- rw:begin:descriptives -->"));
+        assert!(original.contains("<!-- rw:end:clean_data -->"));
+        assert!(!original.contains("descriptives_plot_boxplot_wellbeing"));
+
+        let edited = original
+            .replace(
+                "<!-- rw:begin:descriptives -->",
+                "Participants were recruited via Prolific.\n\n<!-- rw:begin:descriptives -->",
+            )
+            .replace(
+                "  mutate()\n",
+                "  mutate()\n# rw:manual:begin\ndf <- dplyr::filter(df, attention_check == 1)\n# rw:manual:end\n",
+            );
+        fs::write(&path, edited).expect("write edits");
+
+        options.analysis_file_name = Some("analysis".to_string());
+        options.plots.push("boxplot".to_string());
+        let report = regenerate_analysis_template_in_dir(
+            &base,
+            &study_root,
+            &analysis_dir,
+            "S-ABC123",
+            "Test Study",
+            "analysis",
+            &options,
+        )
+        .expect("regenerated");
+        let merged = fs::read_to_string(&path).expect("read merged");
+        assert!(merged.contains("```{r descriptives_plot_boxplot_wellbeing}"));
+        assert!(merged.contains(
+            "Participants were recruited via Prolific.\n\n<!-- rw:begin:descriptives -->"
+        ));
+        assert!(merged.contains(
+            "  mutate()\n# rw:manual:begin\ndf <- dplyr::filter(df, attention_check == 1)\n# rw:manual:end\n"
+        ));
+        assert!(report.conflicts.is_empty());
+        assert!(report.updated_regions.contains(&"descriptives".to_string()));
+        assert_eq!(
+            fs::read_dir(analysis_dir.join(".trash"))
+                .expect("trash folder")
+                .count(),
+            1
+        );
+
+        let without_descriptives = {
+            let start = merged
+                .find("<!-- rw:begin:descriptives -->")
+                .expect("descriptives region");
+            let end_marker = "<!-- rw:end:descriptives -->\n";
+            let end = merged.find(end_marker).expect("descriptives end") + end_marker.len();
+            format!("{}{}", &merged[..start], &merged[end..])
+        };
+        fs::write(&path, without_descriptives).expect("delete region");
+        let report = regenerate_analysis_template_in_dir(
+            &base,
+            &study_root,
+            &analysis_dir,
+            "S-ABC123",
+            "Test Study",
+            "analysis",
+            &options,
+        )
+        .expect("regenerated again");
+        assert_eq!(report.conflicts, vec!["descriptives".to_string()]);
+        assert!(!fs::read_to_string(&path)
+            .expect("read")
+            .contains("rw:begin:descriptives"));
+
+        let _ = fs::remove_dir_all(base);
+    }
+
+    #[test]
+    fn renv_scaffolding_is_opt_in_and_keeps_existing_lockfile() {
+        let base = std::env::temp_dir().join(format!("analysis-renv-{}", Uuid::new_v4()));
+        let study_root = base.join("S-ABC123");
+        let analysis_dir = study_root.join("06_analysis");
+        fs::create_dir_all(&analysis_dir).expect("failed to create temp analysis dir");
+
+        let mut options = empty_options();
+        options.analysis_file_name = Some("plain".to_string());
+        let plain = create_analysis_template_in_dir(
+            &base,
+            &study_root,
+            &analysis_dir,
+            "S-ABC123",
+            "Test Study",
+            &options,
+        )
+        .expect("template created");
+        assert!(!fs::read_to_string(plain).expect("read").contains("renv"));
+        assert!(!analysis_dir.join(".Rprofile").exists());
+        assert!(!analysis_dir.join("renv").exists());
+
+        fs::write(
+            analysis_dir.join("renv.lock"),
+            "{\"R\":{\"Version\":\"4.3.1\"}}",
+        )
+        .expect("write lockfile");
+        options.analysis_file_name = Some("analysis".to_string());
+        options.use_renv = true;
+        let path = create_analysis_template_in_dir(
+            &base,
+            &study_root,
+            &analysis_dir,
+            "S-ABC123",
+            "Test Study",
+            &options,
+        )
+        .expect("template created");
+        let rendered = fs::read_to_string(&path).expect("read");
+        assert!(rendered.contains("```{r setup, include=FALSE}\nif (requireNamespace(\"renv\""));
+        assert!(rendered.contains("<!-- rw:begin:renv -->\n# Reproducibility"));
+        assert!(rendered.contains("renv::snapshot()"));
+        assert_eq!(
+            fs::read_to_string(analysis_dir.join(".Rprofile")).expect("rprofile"),
+            "source(\"renv/activate.R\")\n"
+        );
+        assert!(analysis_dir.join("renv").join("activate.R").exists());
+
+        fs::write(analysis_dir.join(".Rprofile"), "# customised\n").expect("edit rprofile");
+        regenerate_analysis_template_in_dir(
+            &base,
+            &study_root,
+            &analysis_dir,
+            "S-ABC123",
+            "Test Study",
+            "analysis",
+            &options,
+        )
+        .expect("regenerated");
+        assert_eq!(
+            fs::read_to_string(analysis_dir.join("renv.lock")).expect("lockfile"),
+            "{\"R\":{\"Version\":\"4.3.1\"}}"
+        );
+        assert_eq!(
+            fs::read_to_string(analysis_dir.join(".Rprofile")).expect("rprofile"),
+            "# customised\n"
+        );
+
+        let _ = fs::remove_dir_all(base);
+    }
+
+    #[test]
+    fn region_markers_can_be_omitted() {
+        let mut options = empty_options();
+        options.omit_region_markers = true;
+        let rendered = render_analysis_rmd(
+            Path::new("project"),
+            Path::new("project/studies/S-ABC123"),
+            "S-ABC123",
+            "Test Study",
+            &options,
+        );
+        assert!(!rendered.contains("rw:begin"));
+        assert!(!rendered.contains("rw:manifest"));
+        assert!(rendered.contains("```{r clean_data}"));
+    }
+}
@@ -0,0 +1,292 @@
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::spec::types::{AnalysisSpec, ModelSpec};
+use crate::store::{now_string, read_projects_store, resolve_study_root};
+
+use super::ANALYSIS_FOLDER;
+
+/// Suffix of the manifest written next to a generated Rmd: `analysis.Rmd` gets
+/// `analysis.models_manifest.json`, so several templates can share one folder.
+pub const MODELS_MANIFEST_SUFFIX: &str = "models_manifest.json";
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct ModelManifestEntry {
+    pub name: String,
+    /// Model type for template layouts, model family for spec models.
+    pub model_type: String,
+    pub outcome: String,
+    pub treatment: String,
+    pub covariates: String,
+    /// Layout for template models; "main", "robustness" or "exploratory" for spec models.
+    pub layout: String,
+    pub figures: Vec<String>,
+    pub include_in_main_table: bool,
+    pub chunk_id: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ModelsManifest {
+    pub source_file: String,
+    pub generated_at: String,
+    pub models: Vec<ModelManifestEntry>,
+}
+
+#[derive(Debug, Serialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct ProjectModelEntry {
+    pub study_id: String,
+    /// Generated file the model belongs to, relative to the study folder.
+    pub source_file: String,
+    #[serde(flatten)]
+    pub model: ModelManifestEntry,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ListModelsAcrossProjectArgs {
+    project_id: String,
+}
+
+pub fn manifest_path(rmd_path: &Path) -> PathBuf {
+    let stem = rmd_path
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().to_string())
+        .unwrap_or_default();
+    rmd_path.with_file_name(format!("{stem}.{MODELS_MANIFEST_SUFFIX}"))
+}
+
+pub fn manifest_json(rmd_path: &Path, models: Vec<ModelManifestEntry>) -> Result<String, String> {
+    let manifest = ModelsManifest {
+        source_file: rmd_path
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_default(),
+        generated_at: now_string(),
+        models,
+    };
+    serde_json::to_string_pretty(&manifest).map_err(|err| err.to_string())
+}
+
+fn spec_entry(
+    model: &ModelSpec,
+    section: &str,
+    chunk_id: &str,
+    figures: &[String],
+) -> ModelManifestEntry {
+    ModelManifestEntry {
+        name: model.id.clone(),
+        model_type: model.family.clone(),
+        outcome: model.dv.clone(),
+        treatment: model.iv.join(" + "),
+        covariates: model.controls.join(" + "),
+        layout: section.to_string(),
+        figures: figures.to_vec(),
+        include_in_main_table: section == "main",
+        chunk_id: chunk_id.to_string(),
+    }
+}
+
+/// Spec models share one chunk per section (`main_models`, `robustness`, `exploratory`);
+/// the requested figures apply to the main models.
+pub fn spec_models_manifest(spec: &AnalysisSpec) -> Vec<ModelManifestEntry> {
+    let models = &spec.models;
+    let main = models
+        .main
+        .iter()
+        .map(|m| spec_entry(m, "main", "main_models", &spec.outputs.figures));
+    let robustness = models
+        .robustness
+        .iter()
+        .map(|m| spec_entry(m, "robustness", "robustness", &[]));
+    let exploratory = models
+        .exploratory
+        .iter()
+        .map(|m| spec_entry(m, "exploratory", "exploratory", &[]));
+    main.chain(robustness).chain(exploratory).collect()
+}
+
+fn is_manifest(path: &Path) -> bool {
+    path.is_file()
+        && path
+            .file_name()
+            .and_then(|name| name.to_str())
+            .is_some_and(|name| name.ends_with(&format!(".{MODELS_MANIFEST_SUFFIX}")))
+}
+
+/// Manifests of templates (`06_analysis/*.models_manifest.json`) and of rendered specs
+/// (`06_analysis/<analysis>/analysis/*.models_manifest.json`), sorted by path.
+fn study_manifests(study_root: &Path) -> Vec<PathBuf> {
+    let analysis_dir = study_root.join(ANALYSIS_FOLDER);
+    let mut found: Vec<PathBuf> = Vec::new();
+    let Ok(entries) = fs::read_dir(&analysis_dir) else {
+        return found;
+    };
+    for entry in entries.filter_map(|entry| entry.ok()) {
+        let path = entry.path();
+        if is_manifest(&path) {
+            found.push(path);
+        } else if path.is_dir() {
+            if let Ok(inner) = fs::read_dir(path.join("analysis")) {
+                found.extend(
+                    inner
+                        .filter_map(|entry| entry.ok())
+                        .map(|entry| entry.path())
+                        .filter(|path| is_manifest(path)),
+                );
+            }
+        }
+    }
+    found.sort();
+    found
+}
+
+fn study_models(study_id: &str, study_root: &Path) -> Vec<ProjectModelEntry> {
+    let mut out = Vec::new();
+    for path in study_manifests(study_root) {
+        let parsed = fs::read_to_string(&path)
+            .map_err(|err| err.to_string())
+            .and_then(|raw| {
+                serde_json::from_str::<ModelsManifest>(&raw).map_err(|err| err.to_string())
+            });
+        let manifest = match parsed {
+            Ok(manifest) => manifest,
+            Err(err) => {
+                println!("models manifest: skipping {}: {err}", path.display());
+                continue;
+            }
+        };
+        let source = path
+            .with_file_name(&manifest.source_file)
+            .strip_prefix(study_root)
+            .map(|rel| rel.to_string_lossy().replace('\\', "/"))
+            .unwrap_or_else(|_| manifest.source_file.clone());
+        out.extend(manifest.models.into_iter().map(|model| ProjectModelEntry {
+            study_id: study_id.to_string(),
+            source_file: source.clone(),
+            model,
+        }));
+    }
+    out
+}
+
+/// Every model defined by the generated templates and specs of a project, one row per model.
+pub fn list_models_across_project(
+    app_root: &Path,
+    args: ListModelsAcrossProjectArgs,
+) -> Result<Vec<ProjectModelEntry>, String> {
+    let store = read_projects_store(app_root)?;
+    let project = store
+        .projects
+        .iter()
+        .find(|project| project.id == args.project_id)
+        .ok_or_else(|| "Project not found.".to_string())?;
+    Ok(project
+        .studies
+        .iter()
+        .flat_map(|study| study_models(&study.id, &resolve_study_root(project, study)))
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::store::{write_projects_store, ProjectsStore};
+    use uuid::Uuid;
+
+    fn entry(name: &str, chunk_id: &str) -> ModelManifestEntry {
+        ModelManifestEntry {
+            name: name.to_string(),
+            model_type: "ols".to_string(),
+            outcome: "wellbeing".to_string(),
+            treatment: "condition".to_string(),
+            covariates: "age".to_string(),
+            layout: "simple".to_string(),
+            figures: vec!["coef_plot".to_string()],
+            include_in_main_table: true,
+            chunk_id: chunk_id.to_string(),
+        }
+    }
+
+    #[test]
+    fn project_listing_flattens_manifests_of_every_study() {
+        let base = std::env::temp_dir().join(format!("models-manifest-{}", Uuid::new_v4()));
+        let app_root = base.join("app");
+        let project_root = base.join("project");
+        let store: ProjectsStore = serde_json::from_value(serde_json::json!({
+            "projects": [{
+                "id": "p1",
+                "name": "Lab",
+                "rootPath": project_root.to_string_lossy(),
+                "createdAt": "2026-01-01T00:00:00Z",
+                "studies": [
+                    { "id": "S-ONE", "title": "One", "createdAt": "2026-01-01T00:00:00Z" },
+                    { "id": "S-TWO", "title": "Two", "createdAt": "2026-01-01T00:00:00Z" }
+                ]
+            }]
+        }))
+        .expect("store");
+        fs::create_dir_all(&app_root).expect("app root");
+        write_projects_store(&app_root, &store).expect("write store");
+
+        let one = project_root
+            .join("studies")
+            .join("S-ONE")
+            .join(ANALYSIS_FOLDER);
+        let two = project_root
+            .join("studies")
+            .join("S-TWO")
+            .join(ANALYSIS_FOLDER);
+        let template_rmd = one.join("analysis.Rmd");
+        let spec_rmd = two.join("a1").join("analysis").join("analysis.Rmd");
+        for (rmd, models) in [
+            (
+                &template_rmd,
+                vec![entry("Main", "model_1_main"), entry("Alt", "model_2_alt")],
+            ),
+            (&spec_rmd, vec![entry("m1", "main_models")]),
+        ] {
+            fs::create_dir_all(rmd.parent().expect("parent")).expect("dir");
+            let json = manifest_json(rmd, models).expect("manifest");
+            fs::write(manifest_path(rmd), json).expect("write manifest");
+        }
+        fs::write(one.join(format!("broken.{MODELS_MANIFEST_SUFFIX}")), "{").expect("broken");
+
+        let listed = list_models_across_project(
+            &app_root,
+            ListModelsAcrossProjectArgs {
+                project_id: "p1".to_string(),
+            },
+        )
+        .expect("list");
+        let rows: Vec<(&str, &str, &str)> = listed
+            .iter()
+            .map(|row| {
+                (
+                    row.study_id.as_str(),
+                    row.source_file.as_str(),
+                    row.model.chunk_id.as_str(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            rows,
+            vec![
+                ("S-ONE", "06_analysis/analysis.Rmd", "model_1_main"),
+                ("S-ONE", "06_analysis/analysis.Rmd", "model_2_alt"),
+                (
+                    "S-TWO",
+                    "06_analysis/a1/analysis/analysis.Rmd",
+                    "main_models"
+                ),
+            ]
+        );
+        let json = serde_json::to_value(&listed[0]).expect("json");
+        assert_eq!(json["studyId"], "S-ONE");
+        assert_eq!(json["includeInMainTable"], true);
+        let _ = fs::remove_dir_all(base);
+    }
+}
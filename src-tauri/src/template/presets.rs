@@ -0,0 +1,229 @@
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::store::read_projects_store;
+
+use super::{ensure_single_file_name, AnalysisTemplateOptions};
+
+pub const ANALYSIS_PRESETS_PATH: &str = "config/analysis_presets.json";
+
+/// A named set of analysis template options. The options are kept as the JSON the UI sent,
+/// so presets saved by other app versions still load: unknown fields are ignored and missing
+/// ones take their defaults when the preset is applied.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct AnalysisOptionsPreset {
+    pub name: String,
+    pub options: Value,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SaveAnalysisOptionsPresetArgs {
+    project_id: String,
+    name: String,
+    options: Value,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AnalysisOptionsPresetArgs {
+    project_id: String,
+    name: String,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ListAnalysisOptionsPresetsArgs {
+    project_id: String,
+}
+
+fn project_root(app_root: &Path, project_id: &str) -> Result<PathBuf, String> {
+    let store = read_projects_store(app_root)?;
+    store
+        .projects
+        .iter()
+        .find(|project| project.id == project_id)
+        .map(|project| PathBuf::from(&project.root_path))
+        .ok_or_else(|| "Project not found.".to_string())
+}
+
+fn preset_name(name: &str) -> Result<String, String> {
+    let name = name.trim();
+    if name.is_empty() {
+        return Err("Preset name is required.".to_string());
+    }
+    ensure_single_file_name(name, "Preset name")?;
+    Ok(name.to_string())
+}
+
+fn read_presets(project_root: &Path) -> Result<BTreeMap<String, Value>, String> {
+    let path = project_root.join(ANALYSIS_PRESETS_PATH);
+    if !path.exists() {
+        return Ok(BTreeMap::new());
+    }
+    let raw = fs::read_to_string(&path).map_err(|err| err.to_string())?;
+    serde_json::from_str(&raw).map_err(|err| format!("Invalid {ANALYSIS_PRESETS_PATH}: {err}"))
+}
+
+fn write_presets(project_root: &Path, presets: &BTreeMap<String, Value>) -> Result<(), String> {
+    let path = project_root.join(ANALYSIS_PRESETS_PATH);
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).map_err(|err| err.to_string())?;
+    }
+    let payload = serde_json::to_string_pretty(presets).map_err(|err| err.to_string())?;
+    fs::write(&path, payload).map_err(|err| err.to_string())
+}
+
+/// Saves (or replaces) a preset after checking the options parse as template options.
+pub fn save_preset_at(
+    project_root: &Path,
+    name: &str,
+    options: Value,
+) -> Result<AnalysisOptionsPreset, String> {
+    let name = preset_name(name)?;
+    if !options.is_object() {
+        return Err("Preset options must be a JSON object.".to_string());
+    }
+    serde_json::from_value::<AnalysisTemplateOptions>(options.clone())
+        .map_err(|err| format!("Invalid analysis options: {err}"))?;
+    let mut presets = read_presets(project_root)?;
+    presets.insert(name.clone(), options.clone());
+    write_presets(project_root, &presets)?;
+    Ok(AnalysisOptionsPreset { name, options })
+}
+
+pub fn list_presets_at(project_root: &Path) -> Result<Vec<AnalysisOptionsPreset>, String> {
+    Ok(read_presets(project_root)?
+        .into_iter()
+        .map(|(name, options)| AnalysisOptionsPreset { name, options })
+        .collect())
+}
+
+/// Removes a preset; `false` when no preset had that name.
+pub fn delete_preset_at(project_root: &Path, name: &str) -> Result<bool, String> {
+    let name = preset_name(name)?;
+    let mut presets = read_presets(project_root)?;
+    if presets.remove(&name).is_none() {
+        return Ok(false);
+    }
+    write_presets(project_root, &presets)?;
+    Ok(true)
+}
+
+/// Template options from `overrides`, layered over the named preset when one is given.
+/// Fields present and non-null in `overrides` win over the preset's values.
+pub fn resolve_template_options(
+    project_root: &Path,
+    preset: Option<&str>,
+    overrides: Value,
+) -> Result<AnalysisTemplateOptions, String> {
+    let merged = match preset.map(str::trim).filter(|name| !name.is_empty()) {
+        None => overrides,
+        Some(name) => {
+            let name = preset_name(name)?;
+            let base = read_presets(project_root)?
+                .remove(&name)
+                .ok_or_else(|| format!("Analysis options preset '{name}' not found."))?;
+            let mut merged = match base {
+                Value::Object(map) => map,
+                _ => Map::new(),
+            };
+            if let Value::Object(explicit) = overrides {
+                merged.extend(explicit.into_iter().filter(|(_, value)| !value.is_null()));
+            }
+            Value::Object(merged)
+        }
+    };
+    serde_json::from_value(merged).map_err(|err| format!("Invalid analysis options: {err}"))
+}
+
+pub fn save_analysis_options_preset(
+    app_root: &Path,
+    args: SaveAnalysisOptionsPresetArgs,
+) -> Result<AnalysisOptionsPreset, String> {
+    save_preset_at(
+        &project_root(app_root, &args.project_id)?,
+        &args.name,
+        args.options,
+    )
+}
+
+pub fn list_analysis_options_presets(
+    app_root: &Path,
+    args: ListAnalysisOptionsPresetsArgs,
+) -> Result<Vec<AnalysisOptionsPreset>, String> {
+    list_presets_at(&project_root(app_root, &args.project_id)?)
+}
+
+pub fn delete_analysis_options_preset(
+    app_root: &Path,
+    args: AnalysisOptionsPresetArgs,
+) -> Result<bool, String> {
+    delete_preset_at(&project_root(app_root, &args.project_id)?, &args.name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::template::create_analysis_template_in_dir;
+    use uuid::Uuid;
+
+    #[test]
+    fn preset_round_trips_and_explicit_options_win_when_applied() {
+        let base = std::env::temp_dir().join(format!("analysis-preset-test-{}", Uuid::new_v4()));
+        let study_root = base.join("S-ABC123");
+        let analysis_dir = study_root.join("06_analysis");
+        fs::create_dir_all(&analysis_dir).expect("analysis dir");
+
+        let options = serde_json::json!({
+          "descriptives": ["summary_stats", "missingness"],
+          "plots": ["histogram"],
+          "optionFromANewerVersion": true
+        });
+        save_preset_at(&base, "lab default", options.clone()).expect("save preset");
+        assert_eq!(
+            list_presets_at(&base).expect("list presets"),
+            vec![AnalysisOptionsPreset {
+                name: "lab default".to_string(),
+                options,
+            }]
+        );
+        assert!(save_preset_at(&base, "../escape", serde_json::json!({})).is_err());
+
+        let merged = resolve_template_options(
+            &base,
+            Some("lab default"),
+            serde_json::json!({
+              "analysisFileName": "pilot",
+              "descriptives": ["summary_stats"],
+              "plots": null
+            }),
+        )
+        .expect("apply preset");
+        let path = create_analysis_template_in_dir(
+            &base,
+            &study_root,
+            &analysis_dir,
+            "S-ABC123",
+            "Test Study",
+            &merged,
+        )
+        .expect("create template");
+        assert!(path.ends_with("pilot.Rmd"));
+        let rendered = fs::read_to_string(&path).expect("read template");
+        assert!(rendered.contains("```{r descriptives_summary_stats}"));
+        assert!(!rendered.contains("```{r descriptives_missingness}"));
+        assert!(rendered.contains("histogram"));
+
+        assert!(delete_preset_at(&base, "lab default").expect("delete preset"));
+        assert!(
+            resolve_template_options(&base, Some("lab default"), serde_json::json!({})).is_err()
+        );
+
+        let _ = fs::remove_dir_all(base);
+    }
+}
@@ -0,0 +1,835 @@
+use std::fs;
+use std::path::Path;
+
+const STYLE_KIT_DIR: &str = "R/style";
+pub const STYLE_PACKAGE_NAME: &str = "researchworkflowstyle";
+const STYLE_PACKAGE_DIR: &str = "R/researchworkflowstyle";
+const ANALYSIS_CONFIG_PATH: &str = "config/analysis_defaults.json";
+
+const DEFAULT_ANALYSIS_CONFIG_JSON: &str = r#"{
+  "version": 1,
+  "styleProfile": "apa",
+  "styleKit": {
+    "mode": "project",
+    "path": "R/style"
+  },
+  "stylePackage": {
+    "name": "researchworkflowstyle",
+    "path": "R/researchworkflowstyle"
+  },
+  "modules": {
+    "plots": true,
+    "tables": true
+  },
+  "plots": {
+    "base_family": "Times New Roman",
+    "base_size": 12,
+    "dpi": 300,
+    "ggpubr_palette": "jco"
+  },
+  "tables": {
+    "font_family": "Times New Roman",
+    "font_size": 12,
+    "header_bold": true,
+    "autofit": true
+  }
+}"#;
+
+const THEME_PLOTS_R: &str = r#"# R/style/theme_plots.R
+
+suppressPackageStartupMessages({
+  library(ggplot2)
+  library(ggpubr)
+  library(rlang)
+})
+
+theme_apa <- function(base_size = 12, base_family = "Times New Roman") {
+  ggplot2::theme_classic(base_size = base_size, base_family = base_family) +
+    ggplot2::theme(
+      plot.title = element_text(face = "bold", size = base_size + 2, hjust = 0),
+      plot.subtitle = element_text(size = base_size, hjust = 0),
+      plot.caption = element_text(size = base_size - 2, hjust = 1),
+      axis.title = element_text(size = base_size),
+      axis.text  = element_text(size = base_size - 1),
+      legend.title = element_text(size = base_size - 1),
+      legend.text = element_text(size = base_size - 1),
+      panel.grid.major.y = element_line(linewidth = 0.25, color = "grey85"),
+      panel.grid.minor = element_blank(),
+      axis.line = element_line(linewidth = 0.5, color = "black"),
+      plot.margin = margin(10, 10, 10, 10)
+    )
+}
+
+set_apa_plot_defaults <- function(base_size = 12, base_family = "Times New Roman") {
+  ggplot2::theme_set(theme_apa(base_size = base_size, base_family = base_family))
+  invisible(TRUE)
+}
+
+apa_scatter <- function(df, x, y, add_lm = FALSE, se = TRUE, ...) {
+  xq <- enquo(x); yq <- enquo(y)
+  p <- ggplot(df, aes(x = !!xq, y = !!yq)) +
+    geom_point(...) +
+    theme_apa()
+  if (isTRUE(add_lm)) {
+    p <- p + geom_smooth(method = "lm", se = se, linewidth = 0.6)
+  }
+  p
+}
+
+apa_hist <- function(df, x, bins = 30, ...) {
+  xq <- enquo(x)
+  ggplot(df, aes(x = !!xq)) +
+    geom_histogram(bins = bins, ...) +
+    theme_apa()
+}
+
+apa_box <- function(df, x, y, ...) {
+  xq <- enquo(x); yq <- enquo(y)
+  ggplot(df, aes(x = !!xq, y = !!yq)) +
+    geom_boxplot(...) +
+    theme_apa()
+}
+
+theme_study_plot <- function(base_family = "Times New Roman") {
+  ggplot2::theme(
+    text = ggplot2::element_text(family = base_family),
+    axis.text.x = ggplot2::element_text(size = 11),
+    axis.text.y = ggplot2::element_text(size = 14),
+    axis.title = ggplot2::element_text(size = 12, face = "bold"),
+    axis.title.x = ggplot2::element_text(size = 16, face = "bold"),
+    axis.title.y = ggplot2::element_text(size = 16, face = "bold"),
+    legend.text = ggplot2::element_text(size = 12),
+    legend.title = ggplot2::element_text(size = 12),
+    plot.title = ggplot2::element_text(size = 18, face = "bold", hjust = 0.5),
+    strip.background = ggplot2::element_rect(fill = "white"),
+    strip.text = ggplot2::element_text(size = 12, face = "bold"),
+    legend.background = ggplot2::element_rect(fill = "white", color = "white"),
+    panel.background = ggplot2::element_rect(fill = "white", color = "white"),
+    plot.background = ggplot2::element_rect(fill = "white", color = "white")
+  )
+}
+
+style_box_plot <- function(
+  data,
+  x_col,
+  y_col,
+  fill_col = x_col,
+  color_col = x_col,
+  facet_col = NULL,
+  palette = NULL,
+  add_jitter = TRUE,
+  y_breaks = NULL,
+  y_labels = waiver(),
+  hline_at = NULL,
+  xlab = "",
+  ylab = "",
+  title = ""
+) {
+  p <- ggpubr::ggboxplot(
+    data = data,
+    x = x_col,
+    y = y_col,
+    fill = fill_col,
+    color = color_col,
+    facet.by = facet_col,
+    add = if (isTRUE(add_jitter)) "jitter" else "none",
+    add.params = list(alpha = 0.3, size = 0.9),
+    palette = palette,
+    xlab = xlab,
+    ylab = ylab,
+    title = title
+  )
+  if (!is.null(y_breaks)) {
+    p <- p + ggplot2::scale_y_continuous(breaks = y_breaks, labels = y_labels)
+  }
+  if (!is.null(hline_at)) {
+    p <- p + ggplot2::geom_hline(yintercept = hline_at, linetype = "dashed", color = "black", linewidth = 0.8)
+  }
+  p + theme_study_plot()
+}
+
+style_bar_plot <- function(
+  data,
+  x_col,
+  y_col,
+  fill_col = x_col,
+  color_col = x_col,
+  facet_col = NULL,
+  palette = NULL,
+  add = "mean_se",
+  y_breaks = NULL,
+  compare_groups = NULL,
+  compare_method = "t.test",
+  compare_label = "p.signif",
+  compare_label_y = NULL,
+  xlab = "",
+  ylab = "",
+  title = ""
+) {
+  p <- ggpubr::ggbarplot(
+    data = data,
+    x = x_col,
+    y = y_col,
+    fill = fill_col,
+    color = color_col,
+    facet.by = facet_col,
+    add = add,
+    palette = palette,
+    xlab = xlab,
+    ylab = ylab,
+    title = title
+  )
+  if (!is.null(y_breaks)) {
+    p <- p + ggplot2::scale_y_continuous(breaks = y_breaks)
+  }
+  if (!is.null(compare_groups)) {
+    p <- p + ggpubr::stat_compare_means(
+      comparisons = compare_groups,
+      method = compare_method,
+      label = compare_label,
+      label.y = compare_label_y
+    )
+  }
+  p + theme_study_plot()
+}
+"#;
+
+const TABLES_FLEXTABLE_R: &str = r#"# R/style/tables_flextable.R
+
+suppressPackageStartupMessages({
+  library(flextable)
+})
+
+ft_apa <- function(x,
+                   font_family = "Times New Roman",
+                   font_size = 12,
+                   header_bold = TRUE,
+                   autofit = TRUE) {
+  ft <- flextable::flextable(x)
+  ft <- flextable::font(ft, fontname = font_family, part = "all")
+  ft <- flextable::fontsize(ft, size = font_size, part = "all")
+  ft <- flextable::align(ft, align = "center", part = "header")
+  ft <- flextable::align(ft, align = "center", part = "body")
+  ft <- flextable::border_remove(ft)
+  ft <- flextable::hline_top(ft, border = officer::fp_border(width = 1))
+  ft <- flextable::hline(ft, i = 1, border = officer::fp_border(width = 1), part = "header")
+  ft <- flextable::hline_bottom(ft, border = officer::fp_border(width = 1))
+  if (isTRUE(header_bold)) {
+    ft <- flextable::bold(ft, part = "header")
+  }
+  if (isTRUE(autofit)) {
+    ft <- flextable::autofit(ft)
+  }
+  ft
+}
+
+ft_apa_descriptives <- function(df, digits = 2) {
+  # Basic descriptive summary for numeric columns
+  num <- df[, vapply(df, is.numeric, logical(1)), drop = FALSE]
+  if (ncol(num) == 0) stop("No numeric columns found for descriptives.")
+  out <- data.frame(
+    Variable = names(num),
+    N = vapply(num, function(x) sum(!is.na(x)), numeric(1)),
+    Mean = vapply(num, function(x) mean(x, na.rm = TRUE), numeric(1)),
+    SD = vapply(num, function(x) stats::sd(x, na.rm = TRUE), numeric(1)),
+    Min = vapply(num, function(x) min(x, na.rm = TRUE), numeric(1)),
+    Max = vapply(num, function(x) max(x, na.rm = TRUE), numeric(1)),
+    check.names = FALSE
+  )
+  out$Mean <- round(out$Mean, digits)
+  out$SD   <- round(out$SD, digits)
+  out$Min  <- round(out$Min, digits)
+  out$Max  <- round(out$Max, digits)
+
+  ft_apa(out)
+}
+
+ft_apa_regression <- function(model, ...) {
+  stop("ft_apa_regression() is a placeholder. Consider using broom + dplyr to create a data.frame, then pass to ft_apa().")
+}
+
+style_model_table <- function(
+  models,
+  output_path = NULL,
+  estimate = "{estimate}{stars}",
+  statistic = "({std.error})",
+  stars = c("*" = .05, "**" = .01, "***" = .001),
+  ...
+) {
+  if (!requireNamespace("modelsummary", quietly = TRUE)) {
+    stop("Package `modelsummary` is required for style_model_table().")
+  }
+  tbl <- modelsummary::modelsummary(
+    models,
+    estimate = estimate,
+    statistic = statistic,
+    stars = stars,
+    ...
+  )
+  if (!is.null(output_path)) {
+    modelsummary::modelsummary(
+      models,
+      estimate = estimate,
+      statistic = statistic,
+      stars = stars,
+      output = output_path,
+      ...
+    )
+  }
+  tbl
+}
+"#;
+
+const STYLE_INIT_R: &str = r#"# R/style/style_init.R
+
+suppressPackageStartupMessages({
+  library(here)
+})
+
+init_project_style <- function(config_path = here::here("config/analysis_defaults.json")) {
+  cfg <- list(
+    plots = list(base_family = "Times New Roman", base_size = 12),
+    tables = list(font_family = "Times New Roman", font_size = 12, header_bold = TRUE, autofit = TRUE)
+  )
+
+  if (file.exists(config_path)) {
+    if (requireNamespace("jsonlite", quietly = TRUE)) {
+      user_cfg <- jsonlite::fromJSON(config_path, simplifyVector = TRUE)
+      # shallow merge
+      if (!is.null(user_cfg$plots)) cfg$plots <- modifyList(cfg$plots, user_cfg$plots)
+      if (!is.null(user_cfg$tables)) cfg$tables <- modifyList(cfg$tables, user_cfg$tables)
+    }
+  }
+
+  # Apply plot defaults if available
+  if (exists("set_apa_plot_defaults", mode = "function")) {
+    set_apa_plot_defaults(base_size = cfg$plots$base_size, base_family = cfg$plots$base_family)
+  }
+
+  invisible(cfg)
+}
+"#;
+
+const STYLE_README_MD: &str = r#"# Project Style Kit
+
+This folder contains shared, project-level styling helpers used by generated analysis templates.
+
+- `theme_plots.R`: APA-ish plot theme and helper plot wrappers.
+- `tables_flextable.R`: APA-ish table formatting helpers with `flextable`.
+- `style_init.R`: Initializes style defaults from `config/analysis_defaults.json`.
+
+Customize these files once to affect all future analyses that source them.
+"#;
+
+const STYLE_PACKAGE_DESCRIPTION: &str = r#"Package: researchworkflowstyle
+Type: Package
+Title: Shared Figure and Table Style Helpers
+Version: 0.1.0
+Authors@R: person("Research", "Team", email = "noreply@example.com", role = c("aut", "cre"))
+Description: Shared plotting and table helpers for project analysis templates.
+License: MIT + file LICENSE
+Encoding: UTF-8
+LazyData: true
+RoxygenNote: 7.3.2
+Depends:
+    R (>= 4.1.0)
+Imports:
+    flextable,
+    ggpubr,
+    ggplot2,
+    here,
+    rlang,
+    officer
+Suggests:
+    dplyr,
+    gganimate,
+    jsonlite,
+    modelsummary,
+    stringr
+"#;
+
+pub const STYLE_PACKAGE_NAMESPACE: &str = r#"export(theme_apa)
+export(set_apa_plot_defaults)
+export(apa_scatter)
+export(apa_hist)
+export(apa_box)
+export(theme_study_plot)
+export(style_box_plot)
+export(style_bar_plot)
+export(ft_apa)
+export(ft_apa_descriptives)
+export(ft_apa_regression)
+export(style_model_table)
+export(init_project_style)
+"#;
+
+const STYLE_PACKAGE_LICENSE: &str = r#"MIT License
+
+Copyright (c) 2026
+"#;
+
+const STYLE_PACKAGE_PLOTS_R: &str = r#"# R/researchworkflowstyle/R/plots.R
+
+theme_apa <- function(base_size = 12, base_family = "Times New Roman") {
+  ggplot2::theme_classic(base_size = base_size, base_family = base_family) +
+    ggplot2::theme(
+      plot.title = ggplot2::element_text(face = "bold", size = base_size + 2, hjust = 0),
+      plot.subtitle = ggplot2::element_text(size = base_size, hjust = 0),
+      plot.caption = ggplot2::element_text(size = base_size - 2, hjust = 1),
+      axis.title = ggplot2::element_text(size = base_size),
+      axis.text  = ggplot2::element_text(size = base_size - 1),
+      legend.title = ggplot2::element_text(size = base_size - 1),
+      legend.text = ggplot2::element_text(size = base_size - 1),
+      panel.grid.major.y = ggplot2::element_line(linewidth = 0.25, color = "grey85"),
+      panel.grid.minor = ggplot2::element_blank(),
+      axis.line = ggplot2::element_line(linewidth = 0.5, color = "black"),
+      plot.margin = ggplot2::margin(10, 10, 10, 10)
+    )
+}
+
+set_apa_plot_defaults <- function(base_size = 12, base_family = "Times New Roman") {
+  ggplot2::theme_set(theme_apa(base_size = base_size, base_family = base_family))
+  invisible(TRUE)
+}
+
+apa_scatter <- function(df, x, y, add_lm = FALSE, se = TRUE, ...) {
+  xq <- rlang::enquo(x)
+  yq <- rlang::enquo(y)
+  p <- ggplot2::ggplot(df, ggplot2::aes(x = !!xq, y = !!yq)) +
+    ggplot2::geom_point(...) +
+    theme_apa()
+  if (isTRUE(add_lm)) {
+    p <- p + ggplot2::geom_smooth(method = "lm", se = se, linewidth = 0.6)
+  }
+  p
+}
+
+apa_hist <- function(df, x, bins = 30, ...) {
+  xq <- rlang::enquo(x)
+  ggplot2::ggplot(df, ggplot2::aes(x = !!xq)) +
+    ggplot2::geom_histogram(bins = bins, ...) +
+    theme_apa()
+}
+
+apa_box <- function(df, x, y, ...) {
+  xq <- rlang::enquo(x)
+  yq <- rlang::enquo(y)
+  ggplot2::ggplot(df, ggplot2::aes(x = !!xq, y = !!yq)) +
+    ggplot2::geom_boxplot(...) +
+    theme_apa()
+}
+
+theme_study_plot <- function(base_family = "Times New Roman") {
+  ggplot2::theme(
+    text = ggplot2::element_text(family = base_family),
+    axis.text.x = ggplot2::element_text(size = 11),
+    axis.text.y = ggplot2::element_text(size = 14),
+    axis.title = ggplot2::element_text(size = 12, face = "bold"),
+    axis.title.x = ggplot2::element_text(size = 16, face = "bold"),
+    axis.title.y = ggplot2::element_text(size = 16, face = "bold"),
+    legend.text = ggplot2::element_text(size = 12),
+    legend.title = ggplot2::element_text(size = 12),
+    plot.title = ggplot2::element_text(size = 18, face = "bold", hjust = 0.5),
+    strip.background = ggplot2::element_rect(fill = "white"),
+    strip.text = ggplot2::element_text(size = 12, face = "bold"),
+    legend.background = ggplot2::element_rect(fill = "white", color = "white"),
+    panel.background = ggplot2::element_rect(fill = "white", color = "white"),
+    plot.background = ggplot2::element_rect(fill = "white", color = "white")
+  )
+}
+
+style_box_plot <- function(
+  data,
+  x_col,
+  y_col,
+  fill_col = x_col,
+  color_col = x_col,
+  facet_col = NULL,
+  palette = NULL,
+  add_jitter = TRUE,
+  y_breaks = NULL,
+  y_labels = waiver(),
+  hline_at = NULL,
+  xlab = "",
+  ylab = "",
+  title = ""
+) {
+  p <- ggpubr::ggboxplot(
+    data = data,
+    x = x_col,
+    y = y_col,
+    fill = fill_col,
+    color = color_col,
+    facet.by = facet_col,
+    add = if (isTRUE(add_jitter)) "jitter" else "none",
+    add.params = list(alpha = 0.3, size = 0.9),
+    palette = palette,
+    xlab = xlab,
+    ylab = ylab,
+    title = title
+  )
+  if (!is.null(y_breaks)) {
+    p <- p + ggplot2::scale_y_continuous(breaks = y_breaks, labels = y_labels)
+  }
+  if (!is.null(hline_at)) {
+    p <- p + ggplot2::geom_hline(yintercept = hline_at, linetype = "dashed", color = "black", linewidth = 0.8)
+  }
+  p + theme_study_plot()
+}
+
+style_bar_plot <- function(
+  data,
+  x_col,
+  y_col,
+  fill_col = x_col,
+  color_col = x_col,
+  facet_col = NULL,
+  palette = NULL,
+  add = "mean_se",
+  y_breaks = NULL,
+  compare_groups = NULL,
+  compare_method = "t.test",
+  compare_label = "p.signif",
+  compare_label_y = NULL,
+  xlab = "",
+  ylab = "",
+  title = ""
+) {
+  p <- ggpubr::ggbarplot(
+    data = data,
+    x = x_col,
+    y = y_col,
+    fill = fill_col,
+    color = color_col,
+    facet.by = facet_col,
+    add = add,
+    palette = palette,
+    xlab = xlab,
+    ylab = ylab,
+    title = title
+  )
+  if (!is.null(y_breaks)) {
+    p <- p + ggplot2::scale_y_continuous(breaks = y_breaks)
+  }
+  if (!is.null(compare_groups)) {
+    p <- p + ggpubr::stat_compare_means(
+      comparisons = compare_groups,
+      method = compare_method,
+      label = compare_label,
+      label.y = compare_label_y
+    )
+  }
+  p + theme_study_plot()
+}
+"#;
+
+const STYLE_PACKAGE_TABLES_R: &str = r#"# R/researchworkflowstyle/R/tables.R
+
+ft_apa <- function(
+  x,
+  font_family = "Times New Roman",
+  font_size = 12,
+  header_bold = TRUE,
+  autofit = TRUE,
+  digits = NULL
+) {
+  if (is.data.frame(x) && !is.null(digits)) {
+    num_cols <- vapply(x, is.numeric, logical(1))
+    x[num_cols] <- lapply(x[num_cols], round, digits = digits)
+  }
+  ft <- flextable::flextable(x)
+  ft <- flextable::font(ft, fontname = font_family, part = "all")
+  ft <- flextable::fontsize(ft, size = font_size, part = "all")
+  ft <- flextable::align(ft, align = "center", part = "header")
+  ft <- flextable::align(ft, align = "center", part = "body")
+  ft <- flextable::border_remove(ft)
+  ft <- flextable::hline_top(ft, border = officer::fp_border(width = 1))
+  ft <- flextable::hline(ft, i = 1, border = officer::fp_border(width = 1), part = "header")
+  ft <- flextable::hline_bottom(ft, border = officer::fp_border(width = 1))
+  if (isTRUE(header_bold)) {
+    ft <- flextable::bold(ft, part = "header")
+  }
+  if (isTRUE(autofit)) {
+    ft <- flextable::autofit(ft)
+  }
+  ft
+}
+
+ft_apa_descriptives <- function(df, digits = 2) {
+  num <- df[, vapply(df, is.numeric, logical(1)), drop = FALSE]
+  if (ncol(num) == 0) {
+    stop("No numeric columns found for descriptives.")
+  }
+  out <- data.frame(
+    Variable = names(num),
+    N = vapply(num, function(x) sum(!is.na(x)), numeric(1)),
+    Mean = vapply(num, function(x) mean(x, na.rm = TRUE), numeric(1)),
+    SD = vapply(num, function(x) stats::sd(x, na.rm = TRUE), numeric(1)),
+    Min = vapply(num, function(x) min(x, na.rm = TRUE), numeric(1)),
+    Max = vapply(num, function(x) max(x, na.rm = TRUE), numeric(1)),
+    check.names = FALSE
+  )
+
+  out$Mean <- round(out$Mean, digits)
+  out$SD <- round(out$SD, digits)
+  out$Min <- round(out$Min, digits)
+  out$Max <- round(out$Max, digits)
+
+  ft_apa(out)
+}
+
+ft_apa_regression <- function(model, ...) {
+  stop("ft_apa_regression() is a placeholder. Build a data.frame then pass to ft_apa().")
+}
+
+style_model_table <- function(
+  models,
+  output_path = NULL,
+  digits = 3,
+  estimate = "{estimate}{stars}",
+  statistic = "({std.error})",
+  stars = c("*" = .05, "**" = .01, "***" = .001),
+  ...
+) {
+  if (!requireNamespace("modelsummary", quietly = TRUE)) {
+    stop("Package `modelsummary` is required for style_model_table().")
+  }
+  tbl <- modelsummary::modelsummary(
+    models,
+    fmt = digits,
+    estimate = estimate,
+    statistic = statistic,
+    stars = stars,
+    ...
+  )
+  if (!is.null(output_path)) {
+    modelsummary::modelsummary(
+      models,
+      fmt = digits,
+      estimate = estimate,
+      statistic = statistic,
+      stars = stars,
+      output = output_path,
+      ...
+    )
+  }
+  tbl
+}
+"#;
+
+const STYLE_PACKAGE_INIT_R: &str = r#"# R/researchworkflowstyle/R/init.R
+
+init_project_style <- function(config_path = here::here("config/analysis_defaults.json")) {
+  cfg <- list(
+    plots = list(base_family = "Times New Roman", base_size = 12),
+    tables = list(font_family = "Times New Roman", font_size = 12, header_bold = TRUE, autofit = TRUE)
+  )
+
+  if (file.exists(config_path) && requireNamespace("jsonlite", quietly = TRUE)) {
+    user_cfg <- jsonlite::fromJSON(config_path, simplifyVector = TRUE)
+    if (!is.null(user_cfg$plots)) {
+      cfg$plots <- utils::modifyList(cfg$plots, user_cfg$plots)
+    }
+    if (!is.null(user_cfg$tables)) {
+      cfg$tables <- utils::modifyList(cfg$tables, user_cfg$tables)
+    }
+  }
+
+  set_apa_plot_defaults(
+    base_size = cfg$plots$base_size,
+    base_family = cfg$plots$base_family
+  )
+
+  invisible(cfg)
+}
+"#;
+
+const STYLE_PACKAGE_README_MD: &str = r#"# researchworkflowstyle
+
+Local project package for shared figure and table styling helpers used by generated analysis templates.
+
+Usage in analysis scripts:
+- Prefer `researchworkflowstyle::init_project_style()`.
+- Use `researchworkflowstyle::theme_apa()` and `researchworkflowstyle::ft_apa()` directly.
+- Use `researchworkflowstyle::style_box_plot()` and `researchworkflowstyle::style_bar_plot()` for consistent figure styling.
+- Use `researchworkflowstyle::style_model_table()` for consistent regression table output.
+"#;
+
+fn write_if_missing(path: &Path, content: &str) -> Result<(), String> {
+    if !path.exists() {
+        fs::write(path, content).map_err(|err| err.to_string())?;
+    }
+    Ok(())
+}
+
+fn merge_missing_json_keys(current: &mut serde_json::Value, defaults: &serde_json::Value) {
+    match (current, defaults) {
+        (serde_json::Value::Object(current_map), serde_json::Value::Object(default_map)) => {
+            for (key, default_value) in default_map {
+                match current_map.get_mut(key) {
+                    Some(current_value) => merge_missing_json_keys(current_value, default_value),
+                    None => {
+                        current_map.insert(key.clone(), default_value.clone());
+                    }
+                }
+            }
+        }
+        _ => {}
+    }
+}
+
+fn ensure_analysis_defaults_config(project_root: &Path) -> Result<(), String> {
+    let config_path = project_root.join(ANALYSIS_CONFIG_PATH);
+    if let Some(parent) = config_path.parent() {
+        fs::create_dir_all(parent).map_err(|err| err.to_string())?;
+    }
+
+    let defaults: serde_json::Value =
+        serde_json::from_str(DEFAULT_ANALYSIS_CONFIG_JSON).map_err(|err| err.to_string())?;
+
+    if !config_path.exists() {
+        let payload = serde_json::to_string_pretty(&defaults).map_err(|err| err.to_string())?;
+        fs::write(config_path, payload).map_err(|err| err.to_string())?;
+        return Ok(());
+    }
+
+    let raw = fs::read_to_string(&config_path).map_err(|err| err.to_string())?;
+    let mut existing: serde_json::Value = if raw.trim().is_empty() {
+        serde_json::json!({})
+    } else {
+        serde_json::from_str(&raw).map_err(|err| {
+            format!(
+                "Existing analysis defaults config is not valid JSON at {}: {}",
+                config_path.to_string_lossy(),
+                err
+            )
+        })?
+    };
+
+    merge_missing_json_keys(&mut existing, &defaults);
+    let merged = serde_json::to_string_pretty(&existing).map_err(|err| err.to_string())?;
+    fs::write(config_path, merged).map_err(|err| err.to_string())?;
+    Ok(())
+}
+
+/// Project-wide default quick-template style profile from `config/analysis_defaults.json`.
+pub fn default_style_profile(project_root: &Path) -> Option<String> {
+    let raw = fs::read_to_string(project_root.join(ANALYSIS_CONFIG_PATH)).ok()?;
+    let config: serde_json::Value = serde_json::from_str(&raw).ok()?;
+    config
+        .get("styleProfile")
+        .and_then(|value| value.as_str())
+        .map(|value| value.trim().to_string())
+        .filter(|value| !value.is_empty())
+}
+
+pub fn ensure_project_style_kit(project_root: &Path) -> Result<(), String> {
+    ensure_analysis_defaults_config(project_root)?;
+
+    let style_dir = project_root.join(STYLE_KIT_DIR);
+    fs::create_dir_all(&style_dir).map_err(|err| err.to_string())?;
+
+    write_if_missing(&style_dir.join("theme_plots.R"), THEME_PLOTS_R)?;
+    write_if_missing(&style_dir.join("tables_flextable.R"), TABLES_FLEXTABLE_R)?;
+    write_if_missing(&style_dir.join("style_init.R"), STYLE_INIT_R)?;
+    write_if_missing(&style_dir.join("README.md"), STYLE_README_MD)?;
+
+    let pkg_dir = project_root.join(STYLE_PACKAGE_DIR);
+    let pkg_r_dir = pkg_dir.join("R");
+    fs::create_dir_all(&pkg_r_dir).map_err(|err| err.to_string())?;
+
+    write_if_missing(&pkg_dir.join("DESCRIPTION"), STYLE_PACKAGE_DESCRIPTION)?;
+    write_if_missing(&pkg_dir.join("NAMESPACE"), STYLE_PACKAGE_NAMESPACE)?;
+    write_if_missing(&pkg_dir.join("LICENSE"), STYLE_PACKAGE_LICENSE)?;
+    write_if_missing(&pkg_r_dir.join("plots.R"), STYLE_PACKAGE_PLOTS_R)?;
+    write_if_missing(&pkg_r_dir.join("tables.R"), STYLE_PACKAGE_TABLES_R)?;
+    write_if_missing(&pkg_r_dir.join("init.R"), STYLE_PACKAGE_INIT_R)?;
+    write_if_missing(&pkg_dir.join("README.md"), STYLE_PACKAGE_README_MD)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use uuid::Uuid;
+
+    #[test]
+    fn ensure_style_kit_creates_and_merges_config() {
+        let base = std::env::temp_dir().join(format!("style-kit-test-{}", Uuid::new_v4()));
+        fs::create_dir_all(base.join("config")).expect("failed to create temp config dir");
+        fs::write(
+            base.join("config").join("analysis_defaults.json"),
+            "{\n  \"version\": 9,\n  \"plots\": {\"base_size\": 10}\n}\n",
+        )
+        .expect("failed to seed config");
+
+        ensure_project_style_kit(&base).expect("style kit ensure should succeed");
+
+        assert!(base.join("R").join("style").join("theme_plots.R").exists());
+        assert!(base
+            .join("R")
+            .join("style")
+            .join("tables_flextable.R")
+            .exists());
+        assert!(base.join("R").join("style").join("style_init.R").exists());
+        assert!(base.join("R").join("style").join("README.md").exists());
+        assert!(base
+            .join("R")
+            .join("researchworkflowstyle")
+            .join("DESCRIPTION")
+            .exists());
+        assert!(base
+            .join("R")
+            .join("researchworkflowstyle")
+            .join("NAMESPACE")
+            .exists());
+        assert!(base
+            .join("R")
+            .join("researchworkflowstyle")
+            .join("R")
+            .join("plots.R")
+            .exists());
+        assert!(base
+            .join("R")
+            .join("researchworkflowstyle")
+            .join("R")
+            .join("tables.R")
+            .exists());
+        assert!(base
+            .join("R")
+            .join("researchworkflowstyle")
+            .join("R")
+            .join("init.R")
+            .exists());
+
+        let merged_raw = fs::read_to_string(base.join("config").join("analysis_defaults.json"))
+            .expect("config should be readable");
+        let merged: serde_json::Value =
+            serde_json::from_str(&merged_raw).expect("config should be valid json");
+        assert_eq!(merged.get("version").and_then(|v| v.as_i64()), Some(9));
+        assert_eq!(
+            merged
+                .get("plots")
+                .and_then(|v| v.get("base_size"))
+                .and_then(|v| v.as_i64()),
+            Some(10)
+        );
+        assert_eq!(
+            merged
+                .get("styleKit")
+                .and_then(|v| v.get("path"))
+                .and_then(|v| v.as_str()),
+            Some("R/style")
+        );
+        assert_eq!(
+            merged
+                .get("stylePackage")
+                .and_then(|v| v.get("path"))
+                .and_then(|v| v.as_str()),
+            Some("R/researchworkflowstyle")
+        );
+        assert_eq!(default_style_profile(&base).as_deref(), Some("apa"));
+
+        let _ = fs::remove_dir_all(base);
+    }
+}
@@ -0,0 +1,137 @@
+pub const STYLE_PROFILES: &[&str] = &["apa", "minimal_gt"];
+
+/// Table and plot styling used by quick analysis templates.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StyleProfile {
+    /// APA styling from the project style kit (flextable + ggpubr).
+    Apa,
+    /// gt tables and `theme_minimal()` plots, with no style kit dependency.
+    MinimalGt,
+}
+
+impl StyleProfile {
+    /// Parses a profile name; a missing or blank name selects the APA profile.
+    pub fn parse(value: Option<&str>) -> Result<Self, String> {
+        match value.map(str::trim).filter(|value| !value.is_empty()) {
+            None | Some("apa") => Ok(StyleProfile::Apa),
+            Some("minimal_gt") => Ok(StyleProfile::MinimalGt),
+            Some(other) => Err(format!(
+                "Unknown style profile '{}'. Available profiles: {}.",
+                other,
+                STYLE_PROFILES.join(", ")
+            )),
+        }
+    }
+
+    pub fn table_fn(self) -> &'static str {
+        match self {
+            StyleProfile::Apa => "ft_apa",
+            StyleProfile::MinimalGt => "gt_minimal",
+        }
+    }
+
+    pub fn descriptives_table_fn(self) -> &'static str {
+        match self {
+            StyleProfile::Apa => "ft_apa_descriptives",
+            StyleProfile::MinimalGt => "gt_minimal_descriptives",
+        }
+    }
+
+    pub fn plot_theme(self) -> &'static str {
+        match self {
+            StyleProfile::Apa => "theme_apa()",
+            StyleProfile::MinimalGt => "theme_minimal(base_size = 12)",
+        }
+    }
+
+    /// R statement writing a styled table object to a .docx file.
+    pub fn save_table_docx(self, object: &str, path_expr: &str) -> String {
+        match self {
+            StyleProfile::Apa => format!("flextable::save_as_docx({object}, path = {path_expr})"),
+            StyleProfile::MinimalGt => format!("gt::gtsave({object}, filename = {path_expr})"),
+        }
+    }
+
+    /// R statement writing a styled table object to an .html file.
+    pub fn save_table_html(self, object: &str, path_expr: &str) -> String {
+        match self {
+            StyleProfile::Apa => format!("flextable::save_as_html({object}, path = {path_expr})"),
+            StyleProfile::MinimalGt => format!("gt::gtsave({object}, filename = {path_expr})"),
+        }
+    }
+
+    /// Template packages the profile's helpers never call.
+    pub fn unused_packages(self) -> &'static [&'static str] {
+        match self {
+            StyleProfile::Apa => &[],
+            StyleProfile::MinimalGt => &["ggpubr", "flextable", "kableExtra"],
+        }
+    }
+
+    /// Package providing .docx output for model tables.
+    pub fn docx_package(self) -> &'static str {
+        match self {
+            StyleProfile::Apa => "officer",
+            StyleProfile::MinimalGt => "gt",
+        }
+    }
+}
+
+pub const MODEL_TABLE_GT_DOCX_R: &str = r#"save_model_table_docx <- function(models, output_path) {
+  tbl <- modelsummary::modelsummary(
+    models,
+    estimate = "{estimate}{stars}",
+    statistic = "({std.error})",
+    stars = c("*" = .05, "**" = .01, "***" = .001),
+    output = "gt"
+  )
+  gt::gtsave(tbl, filename = output_path)
+  invisible(tbl)
+}
+"#;
+
+pub const MINIMAL_GT_HELPERS_R: &str = r#"# Table and plot helpers for the minimal_gt style profile
+gt_minimal <- function(x) {
+  gt::gt(as.data.frame(x)) %>%
+    gt::tab_options(
+      table.font.size = gt::px(12),
+      column_labels.font.weight = "bold",
+      table.border.top.style = "none"
+    )
+}
+gt_minimal_descriptives <- function(data, digits = 2) {
+  data %>%
+    dplyr::select(where(is.numeric)) %>%
+    tidyr::pivot_longer(dplyr::everything(), names_to = "Variable") %>%
+    dplyr::group_by(Variable) %>%
+    dplyr::summarise(
+      N = sum(!is.na(value)),
+      M = mean(value, na.rm = TRUE),
+      SD = sd(value, na.rm = TRUE),
+      .groups = "drop"
+    ) %>%
+    gt_minimal() %>%
+    gt::fmt_number(columns = c(M, SD), decimals = digits)
+}
+style_model_table <- function(models, output_path) {
+  modelsummary::modelsummary(models, stars = TRUE, output = output_path)
+}
+theme_set(theme_minimal(base_size = 12))
+"#;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn unknown_profiles_list_available_names() {
+        assert_eq!(StyleProfile::parse(None), Ok(StyleProfile::Apa));
+        assert_eq!(
+            StyleProfile::parse(Some(" minimal_gt ")),
+            Ok(StyleProfile::MinimalGt)
+        );
+        let err = StyleProfile::parse(Some("chicago")).expect_err("unknown profile");
+        assert!(err.contains("'chicago'"));
+        assert!(err.contains("apa, minimal_gt"));
+    }
+}
@@ -0,0 +1,388 @@
+use serde::Serialize;
+use std::collections::BTreeSet;
+use std::fs::File;
+use std::io::BufReader;
+use std::path::Path;
+
+use csv::{ByteRecord, Reader, ReaderBuilder, StringRecord};
+
+/// Rows read by sample mode when a command does not pass its own cap.
+pub const DEFAULT_SAMPLE_ROWS: usize = 1_000;
+/// Upper bound on any caller-supplied sample size.
+pub const MAX_SAMPLE_ROWS: usize = 100_000;
+pub const DEFAULT_BUFFER_BYTES: usize = 64 * 1024;
+/// Distinct values tracked per column before counting stops.
+pub const DISTINCT_VALUE_CAP: usize = 50;
+
+const QUALTRICS_IMPORT_ID_PREFIX: &str = "{\"ImportId\"";
+
+#[derive(Debug, Clone)]
+pub struct CsvReadOptions {
+    pub buffer_bytes: usize,
+    /// Field delimiter; `None` picks tab for .tsv/.txt files and comma otherwise.
+    pub delimiter: Option<u8>,
+}
+
+impl Default for CsvReadOptions {
+    fn default() -> Self {
+        Self {
+            buffer_bytes: DEFAULT_BUFFER_BYTES,
+            delimiter: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct CsvHeader {
+    pub columns: Vec<String>,
+    /// Qualtrics question-text/ImportId rows skipped after the header (0, 1 or 2).
+    pub qualtrics_header_rows: usize,
+}
+
+#[derive(Debug, Clone, Serialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct ColumnProfile {
+    pub name: String,
+    pub missing: u64,
+    pub non_missing: u64,
+    /// Observed values in sorted order, at most `DISTINCT_VALUE_CAP` of them.
+    pub distinct_values: Vec<String>,
+    /// True when the column had more distinct values than were kept.
+    pub distinct_capped: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CsvSample {
+    pub header: CsvHeader,
+    pub rows: Vec<Vec<String>>,
+    /// True when rows past the sample were left unread.
+    pub truncated: bool,
+    pub profiles: Vec<ColumnProfile>,
+}
+
+fn delimiter_for(path: &Path, options: &CsvReadOptions) -> u8 {
+    options.delimiter.unwrap_or_else(|| {
+        let ext = path
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .unwrap_or("")
+            .to_lowercase();
+        if ext == "tsv" || ext == "txt" {
+            b'\t'
+        } else {
+            b','
+        }
+    })
+}
+
+/// Reader positioned after the header and any Qualtrics extra header rows.
+struct OpenedCsv {
+    reader: Reader<BufReader<File>>,
+    header: CsvHeader,
+    /// Records read while probing for Qualtrics rows that turned out to be data.
+    pending: Vec<StringRecord>,
+}
+
+fn is_import_id_row(record: &StringRecord) -> bool {
+    record
+        .iter()
+        .any(|field| field.trim_start().starts_with(QUALTRICS_IMPORT_ID_PREFIX))
+}
+
+fn open_csv(path: &Path, options: &CsvReadOptions) -> Result<OpenedCsv, String> {
+    let file = File::open(path)
+        .map_err(|err| format!("Unable to open data file {}: {err}", path.display()))?;
+    let mut reader = ReaderBuilder::new()
+        .delimiter(delimiter_for(path, options))
+        .flexible(true)
+        .has_headers(false)
+        .from_reader(BufReader::with_capacity(
+            options.buffer_bytes.max(1024),
+            file,
+        ));
+
+    let mut record = ByteRecord::new();
+    let mut read_record =
+        |reader: &mut Reader<BufReader<File>>| -> Result<Option<StringRecord>, String> {
+            if reader
+                .read_byte_record(&mut record)
+                .map_err(|err| format!("Unable to read {}: {err}", path.display()))?
+            {
+                Ok(Some(StringRecord::from_byte_record_lossy(record.clone())))
+            } else {
+                Ok(None)
+            }
+        };
+
+    let columns: Vec<String> = match read_record(&mut reader)? {
+        Some(header) => header
+            .iter()
+            .map(|name| name.trim_start_matches('\u{feff}').trim().to_string())
+            .collect(),
+        None => return Err(format!("Data file {} is empty.", path.display())),
+    };
+
+    // Qualtrics exports add a question-text row and an ImportId row below the header.
+    let mut probe = Vec::new();
+    while probe.len() < 2 {
+        match read_record(&mut reader)? {
+            Some(row) => probe.push(row),
+            None => break,
+        }
+    }
+    let qualtrics_header_rows = match probe.iter().position(is_import_id_row) {
+        Some(index) => index + 1,
+        None => 0,
+    };
+    let pending = probe.split_off(qualtrics_header_rows);
+
+    Ok(OpenedCsv {
+        reader,
+        header: CsvHeader {
+            columns,
+            qualtrics_header_rows,
+        },
+        pending,
+    })
+}
+
+/// Header-only mode: reads the column names, probing at most two rows for the Qualtrics quirk.
+pub fn read_header(path: &Path, options: &CsvReadOptions) -> Result<CsvHeader, String> {
+    Ok(open_csv(path, options)?.header)
+}
+
+/// Full streaming mode: calls `on_row` for every data row until it returns `Ok(false)`.
+/// Returns the header and the number of rows passed to the callback.
+pub fn stream_rows(
+    path: &Path,
+    options: &CsvReadOptions,
+    mut on_row: impl FnMut(&StringRecord) -> Result<bool, String>,
+) -> Result<(CsvHeader, u64), String> {
+    let OpenedCsv {
+        mut reader,
+        header,
+        pending,
+    } = open_csv(path, options)?;
+    let mut visited = 0;
+    for row in &pending {
+        visited += 1;
+        if !on_row(row)? {
+            return Ok((header, visited));
+        }
+    }
+    let mut record = ByteRecord::new();
+    while reader
+        .read_byte_record(&mut record)
+        .map_err(|err| format!("Unable to read {}: {err}", path.display()))?
+    {
+        visited += 1;
+        let row = StringRecord::from_byte_record_lossy(record.clone());
+        if !on_row(&row)? {
+            break;
+        }
+    }
+    Ok((header, visited))
+}
+
+fn is_missing(value: &str) -> bool {
+    let trimmed = value.trim();
+    trimmed.is_empty() || trimmed == "NA"
+}
+
+struct ColumnTally {
+    missing: u64,
+    non_missing: u64,
+    distinct: BTreeSet<String>,
+    capped: bool,
+}
+
+/// First-N-rows mode with per-column missingness and capped distinct-value counts.
+pub fn read_sample(
+    path: &Path,
+    sample_rows: usize,
+    options: &CsvReadOptions,
+) -> Result<CsvSample, String> {
+    if sample_rows == 0 {
+        let header = read_header(path, options)?;
+        let profiles = header
+            .columns
+            .iter()
+            .map(|name| ColumnProfile {
+                name: name.clone(),
+                missing: 0,
+                non_missing: 0,
+                distinct_values: Vec::new(),
+                distinct_capped: false,
+            })
+            .collect();
+        return Ok(CsvSample {
+            header,
+            rows: Vec::new(),
+            truncated: true,
+            profiles,
+        });
+    }
+    let limit = sample_rows.min(MAX_SAMPLE_ROWS);
+    let mut rows: Vec<Vec<String>> = Vec::new();
+    let mut tallies: Vec<ColumnTally> = Vec::new();
+    let mut truncated = false;
+    let (header, _) = stream_rows(path, options, |record| {
+        if rows.len() >= limit {
+            truncated = true;
+            return Ok(false);
+        }
+        if tallies.len() < record.len() {
+            tallies.resize_with(record.len(), || ColumnTally {
+                missing: 0,
+                non_missing: 0,
+                distinct: BTreeSet::new(),
+                capped: false,
+            });
+        }
+        for (value, tally) in record.iter().zip(tallies.iter_mut()) {
+            if is_missing(value) {
+                tally.missing += 1;
+                continue;
+            }
+            tally.non_missing += 1;
+            if tally.distinct.contains(value) {
+                continue;
+            }
+            if tally.distinct.len() < DISTINCT_VALUE_CAP {
+                tally.distinct.insert(value.to_string());
+            } else {
+                tally.capped = true;
+            }
+        }
+        rows.push(record.iter().map(str::to_string).collect());
+        Ok(true)
+    })?;
+
+    let profiles = header
+        .columns
+        .iter()
+        .enumerate()
+        .map(|(index, name)| {
+            let tally = tallies.get(index);
+            // Short rows leave trailing columns unset; count those cells as missing.
+            let seen = tally.map(|t| t.missing + t.non_missing).unwrap_or(0);
+            ColumnProfile {
+                name: name.clone(),
+                missing: tally.map(|t| t.missing).unwrap_or(0) + (rows.len() as u64 - seen),
+                non_missing: tally.map(|t| t.non_missing).unwrap_or(0),
+                distinct_values: tally
+                    .map(|t| t.distinct.iter().cloned().collect())
+                    .unwrap_or_default(),
+                distinct_capped: tally.map(|t| t.capped).unwrap_or(false),
+            }
+        })
+        .collect();
+
+    Ok(CsvSample {
+        header,
+        rows,
+        truncated,
+        profiles,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use std::io::Write;
+    use uuid::Uuid;
+
+    fn large_qualtrics_csv(rows: usize) -> std::path::PathBuf {
+        let path = std::env::temp_dir().join(format!("csv-stream-{}.csv", Uuid::new_v4()));
+        let mut file = std::io::BufWriter::new(File::create(&path).expect("create csv"));
+        writeln!(
+            file,
+            "\u{feff}ResponseId,Duration (in seconds),condition,wellbeing"
+        )
+        .unwrap();
+        writeln!(
+            file,
+            "Response ID,Duration (in seconds),Condition,\"How are you, today?\""
+        )
+        .unwrap();
+        writeln!(
+            file,
+            "\"{{\"\"ImportId\"\":\"\"_recordId\"\"}}\",\"{{\"\"ImportId\"\":\"\"duration\"\"}}\",\"{{\"\"ImportId\"\":\"\"QID1\"\"}}\",\"{{\"\"ImportId\"\":\"\"QID2\"\"}}\""
+        )
+        .unwrap();
+        for index in 0..rows {
+            let wellbeing = if index % 10 == 0 {
+                String::new()
+            } else {
+                (index % 7).to_string()
+            };
+            writeln!(file, "R_{index},{},{},{wellbeing}", 100 + index, index % 3).unwrap();
+        }
+        file.flush().unwrap();
+        path
+    }
+
+    #[test]
+    fn header_sample_and_stream_skip_qualtrics_rows() {
+        let path = large_qualtrics_csv(100_000);
+        let options = CsvReadOptions {
+            buffer_bytes: 8 * 1024,
+            delimiter: None,
+        };
+
+        let header = read_header(&path, &options).expect("header");
+        assert_eq!(
+            header.columns,
+            vec![
+                "ResponseId",
+                "Duration (in seconds)",
+                "condition",
+                "wellbeing"
+            ]
+        );
+        assert_eq!(header.qualtrics_header_rows, 2);
+
+        let sample = read_sample(&path, 200, &options).expect("sample");
+        assert_eq!(sample.rows.len(), 200);
+        assert!(sample.truncated);
+        assert_eq!(sample.rows[0], vec!["R_0", "100", "0", ""]);
+        let condition = &sample.profiles[2];
+        assert_eq!(condition.distinct_values, vec!["0", "1", "2"]);
+        assert!(!condition.distinct_capped);
+        let wellbeing = &sample.profiles[3];
+        assert_eq!(wellbeing.missing, 20);
+        assert_eq!(wellbeing.non_missing, 180);
+        let response_id = &sample.profiles[0];
+        assert_eq!(response_id.distinct_values.len(), DISTINCT_VALUE_CAP);
+        assert!(response_id.distinct_capped);
+
+        let mut duration_sum: u64 = 0;
+        let (_, visited) = stream_rows(&path, &options, |row| {
+            duration_sum += row[1].parse::<u64>().map_err(|err| err.to_string())?;
+            Ok(true)
+        })
+        .expect("stream");
+        assert_eq!(visited, 100_000);
+        assert_eq!(duration_sum, (100..100_100u64).sum::<u64>());
+
+        let (_, stopped_at) = stream_rows(&path, &options, |_| Ok(false)).expect("stream");
+        assert_eq!(stopped_at, 1);
+        let _ = fs::remove_file(path);
+    }
+
+    #[test]
+    fn plain_csv_keeps_first_rows_as_data() {
+        let path = std::env::temp_dir().join(format!("csv-plain-{}.tsv", Uuid::new_v4()));
+        fs::write(&path, "id\tscore\n1\t4\n2\tNA\n").expect("write tsv");
+        let sample =
+            read_sample(&path, DEFAULT_SAMPLE_ROWS, &CsvReadOptions::default()).expect("sample");
+        assert_eq!(sample.header.qualtrics_header_rows, 0);
+        assert_eq!(sample.rows, vec![vec!["1", "4"], vec!["2", "NA"]]);
+        assert!(!sample.truncated);
+        assert_eq!(sample.profiles[1].missing, 1);
+        let _ = fs::remove_file(path);
+    }
+}
@@ -1,7 +1,44 @@
 use sha2::{Digest, Sha256};
+use std::fs::File;
+use std::io::Read;
+use std::path::Path;
 
-pub fn sha256_hex(bytes: &[u8]) -> String {
+/// Hashes a file in fixed-size chunks so large datasets are never loaded whole.
+pub fn sha256_file(path: &Path) -> Result<String, String> {
+    let mut file =
+        File::open(path).map_err(|e| format!("Unable to open {}: {e}", path.display()))?;
     let mut hasher = Sha256::new();
-    hasher.update(bytes);
-    format!("{:x}", hasher.finalize())
+    let mut buf = [0u8; 64 * 1024];
+    loop {
+        let n = file
+            .read(&mut buf)
+            .map_err(|e| format!("Unable to read {}: {e}", path.display()))?;
+        if n == 0 {
+            break;
+        }
+        hasher.update(&buf[..n]);
+    }
+    Ok(format!("{:x}", hasher.finalize()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use uuid::Uuid;
+
+    #[test]
+    fn streamed_hash_matches_whole_file_hash() {
+        let path = std::env::temp_dir().join(format!("hash-parity-{}.bin", Uuid::new_v4()));
+        // Not a multiple of the chunk size, so the final partial read is exercised too.
+        let bytes: Vec<u8> = (0..5 * 1024 * 1024 + 777)
+            .map(|i: u32| (i.wrapping_mul(2_654_435_761) >> 13) as u8)
+            .collect();
+        std::fs::write(&path, &bytes).expect("write temp file");
+
+        let streamed = sha256_file(&path).expect("hash");
+        assert_eq!(streamed, hex::encode(Sha256::digest(&bytes)));
+        assert_eq!(streamed, format!("{:x}", Sha256::digest(&bytes)));
+        assert!(sha256_file(&path.with_extension("missing")).is_err());
+        let _ = std::fs::remove_file(path);
+    }
 }
@@ -1,2 +1,3 @@
+pub mod csv_stream;
 pub mod hash;
 pub mod text;
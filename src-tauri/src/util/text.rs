@@ -11,6 +11,36 @@ pub fn normalize_token(value: &str) -> String {
         .join("_")
 }
 
+/// Mirrors `janitor::clean_names()` for ASCII column names: camelCase boundaries become
+/// underscores, other punctuation collapses to `_`, and the result is lowercased.
+pub fn clean_names(value: &str) -> String {
+    let chars = value.chars().collect::<Vec<char>>();
+    let mut out = String::new();
+    for (idx, &c) in chars.iter().enumerate() {
+        if !c.is_ascii_alphanumeric() {
+            if !out.is_empty() && !out.ends_with('_') {
+                out.push('_');
+            }
+            continue;
+        }
+        if c.is_ascii_uppercase() && idx > 0 {
+            let prev = chars[idx - 1];
+            let next_lower = chars
+                .get(idx + 1)
+                .map(|n| n.is_ascii_lowercase())
+                .unwrap_or(false);
+            let boundary = prev.is_ascii_lowercase()
+                || prev.is_ascii_digit()
+                || (prev.is_ascii_uppercase() && next_lower);
+            if boundary && !out.is_empty() && !out.ends_with('_') {
+                out.push('_');
+            }
+        }
+        out.push(c.to_ascii_lowercase());
+    }
+    out.trim_end_matches('_').to_string()
+}
+
 pub fn tokenize_identifiers(text: &str) -> Vec<String> {
     let backticks = Regex::new(r"`([A-Za-z][A-Za-z0-9_]*)`").expect("regex");
     let snake = Regex::new(r"\b[A-Za-z][A-Za-z0-9]*_[A-Za-z0-9_]+\b").expect("regex");
@@ -33,3 +63,191 @@ pub fn tokenize_identifiers(text: &str) -> Vec<String> {
     out.dedup();
     out
 }
+
+/// Words too generic to name a measured concept (articles, study boilerplate, analysis verbs).
+pub fn is_concept_stopword(word: &str) -> bool {
+    let stop = [
+        "a",
+        "an",
+        "the",
+        "of",
+        "for",
+        "to",
+        "in",
+        "on",
+        "at",
+        "by",
+        "and",
+        "or",
+        "is",
+        "are",
+        "be",
+        "being",
+        "been",
+        "that",
+        "this",
+        "these",
+        "those",
+        "our",
+        "their",
+        "participant",
+        "participants",
+        "self",
+        "reported",
+        "measure",
+        "measured",
+        "item",
+        "items",
+        "question",
+        "questions",
+        "respond",
+        "response",
+        "responses",
+        "asked",
+        "ask",
+        "student",
+        "students",
+        "advisee",
+        "advisors",
+        "advisor",
+        "company",
+        "offer",
+        "offers",
+        "option",
+        "options",
+        "using",
+        "will",
+        "would",
+        "should",
+        "can",
+        "could",
+        "anything",
+        "after",
+        "before",
+        "during",
+        "between",
+        "then",
+        "than",
+        "where",
+        "which",
+        "what",
+        "when",
+        "with",
+        "without",
+        "include",
+        "excluding",
+        "exclude",
+        "remove",
+        "drop",
+        "control",
+        "controls",
+        "covariate",
+        "covariates",
+        "outcome",
+        "outcomes",
+        "analysis",
+        "variable",
+        "variables",
+    ];
+    stop.iter().any(|v| v == &word)
+}
+
+/// Text decoded from raw file bytes. `lossy` is set when the bytes were not valid UTF-8 and
+/// had to be reinterpreted (Windows-1252 or a UTF-16 BOM), so callers can surface a warning.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DecodedText {
+    pub text: String,
+    pub lossy: bool,
+}
+
+/// Windows-1252 code points for bytes 0x80..=0x9F; the rest of the range matches Latin-1.
+const WINDOWS_1252_HIGH: [char; 32] = [
+    '\u{20AC}', '\u{FFFD}', '\u{201A}', '\u{0192}', '\u{201E}', '\u{2026}', '\u{2020}', '\u{2021}',
+    '\u{02C6}', '\u{2030}', '\u{0160}', '\u{2039}', '\u{0152}', '\u{FFFD}', '\u{017D}', '\u{FFFD}',
+    '\u{FFFD}', '\u{2018}', '\u{2019}', '\u{201C}', '\u{201D}', '\u{2022}', '\u{2013}', '\u{2014}',
+    '\u{02DC}', '\u{2122}', '\u{0161}', '\u{203A}', '\u{0153}', '\u{FFFD}', '\u{017E}', '\u{0178}',
+];
+
+fn decode_windows_1252(bytes: &[u8]) -> String {
+    bytes
+        .iter()
+        .map(|&b| match b {
+            0x80..=0x9F => WINDOWS_1252_HIGH[(b - 0x80) as usize],
+            _ => b as char,
+        })
+        .collect()
+}
+
+fn decode_utf16(bytes: &[u8], little_endian: bool) -> String {
+    let units = bytes
+        .chunks_exact(2)
+        .map(|pair| {
+            if little_endian {
+                u16::from_le_bytes([pair[0], pair[1]])
+            } else {
+                u16::from_be_bytes([pair[0], pair[1]])
+            }
+        })
+        .collect::<Vec<u16>>();
+    String::from_utf16_lossy(&units)
+}
+
+/// Converts CRLF and bare CR line endings to LF.
+pub fn normalize_line_endings(text: &str) -> String {
+    text.replace("\r\n", "\n").replace('\r', "\n")
+}
+
+/// Decodes text file bytes without failing: strips a BOM, falls back to Windows-1252 when the
+/// bytes are not UTF-8, and normalizes line endings.
+pub fn decode_text(bytes: &[u8]) -> DecodedText {
+    let (text, lossy) = if let Some(rest) = bytes.strip_prefix(&[0xEF, 0xBB, 0xBF]) {
+        match std::str::from_utf8(rest) {
+            Ok(text) => (text.to_string(), false),
+            Err(_) => (String::from_utf8_lossy(rest).to_string(), true),
+        }
+    } else if let Some(rest) = bytes.strip_prefix(&[0xFF, 0xFE]) {
+        (decode_utf16(rest, true), true)
+    } else if let Some(rest) = bytes.strip_prefix(&[0xFE, 0xFF]) {
+        (decode_utf16(rest, false), true)
+    } else {
+        match std::str::from_utf8(bytes) {
+            Ok(text) => (text.to_string(), false),
+            Err(_) => (decode_windows_1252(bytes), true),
+        }
+    };
+    DecodedText {
+        text: normalize_line_endings(&text),
+        lossy,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn clean_names_matches_janitor_for_qualtrics_meta_columns() {
+        assert_eq!(clean_names("Duration (in seconds)"), "duration_in_seconds");
+        assert_eq!(clean_names("ResponseId"), "response_id");
+        assert_eq!(clean_names("DistributionChannel"), "distribution_channel");
+        assert_eq!(clean_names("Q1_4_TEXT"), "q1_4_text");
+        assert_eq!(clean_names("IDNumber"), "id_number");
+    }
+
+    #[test]
+    fn decode_strips_bom_and_normalizes_crlf() {
+        let decoded = decode_text(b"\xEF\xBB\xBFDV: outcome_y\r\nIV: treat_x\rend");
+        assert_eq!(decoded.text, "DV: outcome_y\nIV: treat_x\nend");
+        assert!(!decoded.lossy);
+    }
+
+    #[test]
+    fn decode_falls_back_to_windows_1252() {
+        let decoded = decode_text(b"\x93quoted\x94 caf\xE9 \x96 done");
+        assert_eq!(
+            decoded.text,
+            "\u{201C}quoted\u{201D} caf\u{E9} \u{2013} done"
+        );
+        assert!(decoded.lossy);
+    }
+}
@@ -1,18 +1,25 @@
 import { useEffect, useMemo, useState } from "react";
 import { convertFileSrc, invoke } from "@tauri-apps/api/tauri";
 import { open } from "@tauri-apps/api/dialog";
+import { listen } from "@tauri-apps/api/event";
 import { AnalysisTemplateWizard } from "./components/AnalysisTemplateWizard";
 import { AnalysisCreateFromInputs } from "./components/AnalysisCreateFromInputs";
 import { AnalysisTemplateOptions } from "./types/analysisTemplate";
+import type {
+  GitCommitPushReport,
+  GitSummary,
+  ProjectExportReport,
+  StorageHealth
+} from "./tauri/api";
 
 const STATUSES = [
   "planning",
+  "building",
+  "piloting",
   "collecting",
   "analyzing",
   "writing",
-  "submitted",
-  "published",
-  "archived"
+  "released"
 ];
 
 const STUDY_CODE_PATTERN = /^S-[A-Z0-9]{6}$/;
@@ -33,10 +40,38 @@ type Project = {
   studies: JsonStudy[];
 };
 
+type RelocationReport = {
+  fromRoot: string;
+  toRoot: string;
+  dryRun: boolean;
+  rewrites: Array<{ kind: string; id: string; from: string; to: string }>;
+  missingStudies: string[];
+  missingFiles: string[];
+  project: Project;
+};
+
+type OsfPackageResult = {
+  kind: "complete" | "condensed";
+  folderName: string;
+  path: string;
+  files: number;
+  skippedLinks: Array<{ source: string; target: string }>;
+  excluded: Array<{ path: string; reason: string }>;
+  manifest: {
+    fileCount: number;
+    totalBytes: number;
+    generatedAt: string;
+    includePilots: boolean;
+    appVersion: string;
+  };
+};
+
 type FileRef = {
   path: string;
   name: string;
   kind: string;
+  sha256?: string;
+  folder?: string;
 };
 
 type JsonStudy = {
@@ -71,6 +106,12 @@ type ModelStatus = {
   lock?: ModelLock | null;
 };
 
+type DownloadProgress = {
+  bytesDone: number;
+  bytesTotal?: number | null;
+  assetName: string;
+};
+
 type LlmSettings = {
   modelDir: string;
   updatePolicy: "stable" | "latest";
@@ -81,6 +122,10 @@ type LlmSettings = {
   githubRepo: string;
   allowPrerelease: boolean;
   autoCheckDays: number;
+  provider?: "heuristic" | "openai_compatible";
+  baseUrl?: string | null;
+  apiKey?: string | null;
+  model?: string | null;
 };
 
 type LlmProjectPreset = {
@@ -100,21 +145,32 @@ type LegacyStudy = {
   paperLabel: string | null;
   status: string;
   createdAt: string;
+  updatedAt?: string;
   folderPath: string;
 };
 
 type Artifact = {
   id: string;
   studyId: string;
-  kind: "url" | "path";
+  kind: "url" | "path" | "analysis_template" | "rendered_analysis" | "osf_package";
   value: string;
   label: string | null;
   createdAt: string;
+  updatedAt?: string;
+  wasAutoAdded?: boolean;
+};
+
+type StudyStatusChange = {
+  fromStatus: string | null;
+  toStatus: string;
+  changedAt: string;
+  note: string | null;
 };
 
 type StudyDetail = {
   study: LegacyStudy;
   artifacts: Artifact[];
+  statusHistory: StudyStatusChange[];
 };
 
 type RootDirInfo = {
@@ -136,6 +192,7 @@ export default function App() {
   const [legacyStudies, setLegacyStudies] = useState<LegacyStudy[]>([]);
   const [selectedLegacyStudyId, setSelectedLegacyStudyId] = useState<string | null>(null);
   const [legacyDetail, setLegacyDetail] = useState<StudyDetail | null>(null);
+  const [studyStatuses, setStudyStatuses] = useState<string[]>(STATUSES);
   const [showLegacy, setShowLegacy] = useState(false);
   const [error, setError] = useState<string | null>(null);
   const [loading, setLoading] = useState(false);
@@ -208,6 +265,7 @@ export default function App() {
     note: null,
   });
   const [llmResolvedModel, setLlmResolvedModel] = useState<ModelStatus | null>(null);
+  const [llmDownloadProgress, setLlmDownloadProgress] = useState<DownloadProgress | null>(null);
   const [llmProjectPreset, setLlmProjectPreset] = useState<LlmProjectPreset>({
     name: "Reproducible Stable",
     updatePolicy: "stable",
@@ -250,10 +308,29 @@ export default function App() {
     [legacyStudies, selectedLegacyStudyId]
   );
 
+  useEffect(() => {
+    invoke<string[]>("list_study_statuses")
+      .then(setStudyStatuses)
+      .catch(() => setStudyStatuses(STATUSES));
+  }, []);
+
+  useEffect(() => {
+    const unlisten = listen<DownloadProgress>("llm://download-progress", (event) => {
+      setLlmDownloadProgress(event.payload);
+    });
+    return () => {
+      unlisten.then((stop) => stop());
+    };
+  }, []);
+
   useEffect(() => {
     const init = async () => {
       try {
         setLoading(true);
+        const storage = await invoke<StorageHealth>("get_storage_health");
+        if (storage.warning || !storage.writable) {
+          setError(storage.warning ?? `No writable data folder: ${storage.fallbackReason ?? ""}`);
+        }
         await invoke("init_db");
         const list = await invoke<Project[]>("list_projects");
         const settings = await invoke<LlmSettings>("llm_get_settings");
@@ -491,6 +568,7 @@ export default function App() {
       setLlmResolvedModel(null);
     } finally {
       setLoading(false);
+      setLlmDownloadProgress(null);
     }
   };
 
@@ -645,10 +723,27 @@ export default function App() {
     }
     try {
       setLoading(true);
-      const project = await invoke<Project>("update_project_root", {
+      const plan = await invoke<RelocationReport>("relocate_project", {
+        args: {
+          projectId: selectedProject.id,
+          rootDir: trimmedRoot,
+          dryRun: true
+        }
+      });
+      const missing = [...plan.missingStudies, ...plan.missingFiles];
+      if (
+        missing.length > 0 &&
+        !window.confirm(
+          `These studies or files were not found under the new root:\n\n${missing.join("\n")}\n\nMove the project anyway?`
+        )
+      ) {
+        return;
+      }
+      const { project } = await invoke<RelocationReport>("relocate_project", {
         args: {
           projectId: selectedProject.id,
-          rootDir: trimmedRoot
+          rootDir: trimmedRoot,
+          dryRun: false
         }
       });
       setProjects((prev) =>
@@ -666,7 +761,7 @@ export default function App() {
   const handleDeleteProject = async () => {
     if (!selectedProject) return;
     const confirmMessage = deleteProjectOnDisk
-      ? `Delete project "${selectedProject.name}" and permanently remove its folder on disk?\n\nFolder:\n${selectedProject.rootPath}`
+      ? `Delete project "${selectedProject.name}" and move its folder to the trash?\n\nFolder:\n${selectedProject.rootPath}`
       : `Delete project "${selectedProject.name}" from the app?\nThis does not delete files on disk.`;
     if (!window.confirm(confirmMessage)) return;
     try {
@@ -688,6 +783,54 @@ export default function App() {
     }
   };
 
+  const handleExportProject = async () => {
+    if (!selectedProject) return;
+    try {
+      const destination = await open({
+        multiple: false,
+        directory: true,
+        title: "Select folder for the project archive"
+      });
+      if (typeof destination !== "string") return;
+      setLoading(true);
+      const report = await invoke<ProjectExportReport>("export_project", {
+        args: { projectId: selectedProject.id, destination }
+      });
+      window.alert(`Exported ${report.fileCount} file(s) to:\n${report.archivePath}`);
+    } catch (err) {
+      setProjectRootEditError(String(err));
+    } finally {
+      setLoading(false);
+    }
+  };
+
+  const handleImportProject = async () => {
+    try {
+      const archivePath = await open({
+        multiple: false,
+        directory: false,
+        title: "Select project archive",
+        filters: [{ name: "Project archive", extensions: ["zip"] }]
+      });
+      if (typeof archivePath !== "string") return;
+      const parentDir = await open({
+        multiple: false,
+        directory: true,
+        title: "Select folder to unpack the project into"
+      });
+      if (typeof parentDir !== "string") return;
+      setLoading(true);
+      const project = await invoke<Project>("import_project", {
+        args: { archivePath, parentDir }
+      });
+      await refreshProjects(project.id);
+    } catch (err) {
+      setError(String(err));
+    } finally {
+      setLoading(false);
+    }
+  };
+
   const handleAddStudy = async () => {
     setAddStudyClickCount((prev) => prev + 1);
     if (!selectedProjectId) return;
@@ -802,17 +945,26 @@ export default function App() {
     }
     try {
       setLoading(true);
-      const project = await invoke<Project>("rename_study_folder_json", {
-        args: {
-          projectId: selectedProject.id,
-          studyId: selectedStudy.id,
-          folderName: normalizedFolder
+      const report = await invoke<{ project: Project; staleStudyReferences: string[] }>(
+        "rename_study_folder_json",
+        {
+          args: {
+            projectId: selectedProject.id,
+            studyId: selectedStudy.id,
+            folderName: normalizedFolder
+          }
         }
-      });
+      );
+      const project = report.project;
       setProjects((prev) =>
         prev.map((item) => (item.id === project.id ? project : item))
       );
       setSelectedStudyId(normalizedFolder);
+      if (report.staleStudyReferences.length > 0) {
+        setError(
+          `These analysis files still mention ${selectedStudy.id}: ${report.staleStudyReferences.join(", ")}`
+        );
+      }
     } catch (err) {
       setError(String(err));
     } finally {
@@ -864,10 +1016,12 @@ export default function App() {
 
   const handleUpdateLegacyStatus = async (status: string) => {
     if (!legacyDetail) return;
+    const note = window.prompt(`Note for the change to "${status}" (optional):`, "");
+    if (note === null) return;
     try {
       setLoading(true);
       await invoke("update_study_status", {
-        args: { studyId: legacyDetail.study.id, status }
+        args: { studyId: legacyDetail.study.id, status, note: note.trim() || null }
       });
       const updated = await invoke<StudyDetail>("get_study_detail", {
         args: { studyId: legacyDetail.study.id }
@@ -934,13 +1088,39 @@ export default function App() {
     );
     try {
       setLoading(true);
-      const result = await invoke<string>("generate_osf_packages", {
-        args: {
-          studyId: legacyDetail.study.id,
-          includePilots: includePilots
-        }
-      });
-      alert(result);
+      const generate = (force: boolean) =>
+        invoke<OsfPackageResult[]>("generate_osf_packages", {
+          args: {
+            studyId: legacyDetail.study.id,
+            includePilots: includePilots,
+            enforceReleaseGate: true,
+            force
+          }
+        });
+      let results: OsfPackageResult[];
+      try {
+        results = await generate(false);
+      } catch (err) {
+        const message = String(err);
+        if (!message.startsWith("Release gate failed")) throw err;
+        if (!window.confirm(`${message}\n\nGenerate the OSF packages anyway?`)) return;
+        results = await generate(true);
+      }
+      alert(
+        `OSF packages generated.\n${results
+          .map(
+            (item) =>
+              `${item.folderName}: ${item.manifest.fileCount} files, ${(
+                item.manifest.totalBytes /
+                1024 /
+                1024
+              ).toFixed(1)} MB` +
+              (item.skippedLinks.length > 0
+                ? ` (${item.skippedLinks.length} symlinks skipped)`
+                : "")
+          )
+          .join("\n")}`
+      );
     } catch (err) {
       setError(String(err));
     } finally {
@@ -948,10 +1128,24 @@ export default function App() {
     }
   };
   const handleGitStatus = async () => {
+    if (!selectedProject) return;
     try {
       setLoading(true);
-      const output = await invoke<string>("git_status");
-      alert(output);
+      const summary = await invoke<GitSummary>("git_status", {
+        args: { projectId: selectedProject.id },
+      });
+      const tracking = summary.upstream
+        ? ` (${summary.upstream}: ${summary.ahead} ahead, ${summary.behind} behind)`
+        : "";
+      alert(
+        [
+          `Branch: ${summary.branch ?? "detached HEAD"}${tracking}`,
+          `Staged: ${summary.staged.length}`,
+          `Unstaged: ${summary.unstaged.length}`,
+          `Untracked: ${summary.untracked.length}`,
+          ...(summary.conflicted.length > 0 ? [`Conflicted: ${summary.conflicted.join(", ")}`] : [])
+        ].join("\n")
+      );
     } catch (err) {
       setError(String(err));
     } finally {
@@ -960,12 +1154,40 @@ export default function App() {
   };
 
   const handleGitCommitPush = async () => {
+    if (!selectedProject) return;
     const message = window.prompt("Commit message?");
     if (!message) return;
     try {
       setLoading(true);
-      const output = await invoke<string>("git_commit_push", { message });
-      alert(output);
+      const push = (setUpstream: boolean) =>
+        invoke<GitCommitPushReport>("git_commit_push", {
+          args: { message, projectId: selectedProject.id, setUpstream },
+        });
+      let report: GitCommitPushReport;
+      try {
+        report = await push(false);
+      } catch (err) {
+        const code = (() => {
+          try {
+            return JSON.parse(String(err)).code as string;
+          } catch {
+            return null;
+          }
+        })();
+        if (
+          code !== "GIT_NO_UPSTREAM" ||
+          !window.confirm("This branch has no upstream on origin. Push and set it now?")
+        ) {
+          throw err;
+        }
+        report = await push(true);
+      }
+      alert(
+        report.outcome === "nothingToCommit"
+          ? "Nothing to commit; the branch is up to date."
+          : `Pushed ${report.filesCommitted} changed file(s) to ${report.branch ?? "origin"}.` +
+              (report.remoteMessage ? `\n\n${report.remoteMessage}` : "")
+      );
     } catch (err) {
       setError(String(err));
     } finally {
@@ -1000,11 +1222,23 @@ export default function App() {
           : [];
       if (paths.length === 0) return;
       setLoading(true);
-      const updatedStudy = await invoke<JsonStudy>("import_files", {
+      const report = await invoke<{
+        study: JsonStudy;
+        results: { source: string; status: string; path?: string | null }[];
+      }>("import_files", {
         projectId: selectedProject.id,
         studyId: selectedStudy.id,
         paths
       });
+      const updatedStudy = report.study;
+      const duplicates = report.results.filter((item) => item.status === "duplicate");
+      if (duplicates.length > 0) {
+        setError(
+          `Skipped ${duplicates.length} duplicate file(s) already in the study: ${duplicates
+            .map((item) => item.path ?? item.source)
+            .join(", ")}`
+        );
+      }
       setProjects((prev) =>
         prev.map((project) =>
           project.id === selectedProject.id
@@ -1317,6 +1551,7 @@ export default function App() {
             <h2>Projects</h2>
             <div className="inline-actions">
               <button onClick={openProjectModal}>New Project</button>
+              <button onClick={handleImportProject}>Import Project</button>
               <button onClick={openProjectSettings} disabled={!selectedProject}>
                 Project Settings
               </button>
@@ -1627,7 +1862,12 @@ export default function App() {
                         value={legacyDetail.study.status}
                         onChange={(event) => handleUpdateLegacyStatus(event.target.value)}
                       >
-                        {STATUSES.map((status) => (
+                        {!studyStatuses.includes(legacyDetail.study.status) && (
+                          <option value={legacyDetail.study.status} disabled>
+                            {legacyDetail.study.status}
+                          </option>
+                        )}
+                        {studyStatuses.map((status) => (
                           <option key={status} value={status}>
                             {status}
                           </option>
@@ -1636,6 +1876,25 @@ export default function App() {
                     </div>
                   </div>
 
+                  {legacyDetail.statusHistory.length > 0 && (
+                    <div className="artifacts">
+                      <h3>Status history</h3>
+                      <ul className="artifact-list">
+                        {legacyDetail.statusHistory.map((change, index) => (
+                          <li key={`${change.changedAt}-${index}`}>
+                            <div>
+                              <strong>
+                                {change.fromStatus ?? "—"} → {change.toStatus}
+                              </strong>
+                              <span>{new Date(change.changedAt).toLocaleString()}</span>
+                              {change.note && <span className="muted">{change.note}</span>}
+                            </div>
+                          </li>
+                        ))}
+                      </ul>
+                    </div>
+                  )}
+
                   <div className="artifacts">
                     <div className="panel-header compact">
                       <h3>Artifacts</h3>
@@ -1650,6 +1909,7 @@ export default function App() {
                           <div>
                             <strong>{artifact.label ?? artifact.kind}</strong>
                             <span>{artifact.value}</span>
+                            {artifact.wasAutoAdded && <span className="muted">auto</span>}
                           </div>
                           <button onClick={() => handleRemoveLegacyArtifact(artifact.id)}>
                             Remove
@@ -2081,6 +2341,17 @@ export default function App() {
                         <button onClick={handleVerifyLlmModel}>Verify</button>
                         <button onClick={handleLoadLlmModel}>Load</button>
                       </div>
+                      {llmDownloadProgress && (
+                        <p className="muted">
+                          Downloading {llmDownloadProgress.assetName}:{" "}
+                          {(llmDownloadProgress.bytesDone / 1048576).toFixed(1)} MB
+                          {llmDownloadProgress.bytesTotal
+                            ? ` of ${(llmDownloadProgress.bytesTotal / 1048576).toFixed(1)} MB (${Math.floor(
+                                (100 * llmDownloadProgress.bytesDone) / llmDownloadProgress.bytesTotal
+                              )}%)`
+                            : ""}
+                        </p>
+                      )}
                       {llmResolvedModel && (
                         <p className="muted">
                           Tag: {llmResolvedModel.selectedTag ?? "(none)"} | SHA: {llmResolvedModel.sha256 ?? "(none)"} | Loaded: {String(llmResolvedModel.loaded)}
@@ -2098,6 +2369,9 @@ export default function App() {
               <button className="danger" onClick={handleDeleteProject}>
                 Delete Project
               </button>
+              <button className="ghost" onClick={handleExportProject}>
+                Export Project
+              </button>
               <button onClick={handleUpdateProjectRoot}>Save</button>
             </div>
           </div>
@@ -15,9 +15,11 @@ type Confidence = "high" | "medium" | "low";
 type MappingRow = {
   preregVar: string;
   resolvedTo: string | null;
+  resolutionKind: string;
+  derivedSources: string[];
   topCandidate: string | null;
   topScore: number;
-  candidates: Array<{ key: string; score: number }>;
+  candidates: Array<{ key: string; score: number; matchedOn?: string; reason?: string }>;
   confidence: Confidence;
 };
 
@@ -182,7 +184,10 @@ export function AnalysisCreateFromInputs({ projectId, studyId, onUseInBuilder }:
                     <strong>{row.preregVar}</strong> <span className="muted">({row.confidence}, top {row.topScore.toFixed(2)})</span>
                   </div>
                   <div>
-                    {row.confidence === "high" && row.topCandidate && (
+                    {row.resolutionKind === "derived_merge" && (
+                      <span className="muted">Merged from counterbalanced columns: {row.derivedSources.join(" + ")}</span>
+                    )}
+                    {row.resolutionKind !== "derived_merge" && row.confidence === "high" && row.topCandidate && (
                       <span className="muted">Auto-accepted: {row.topCandidate}</span>
                     )}
                     {row.confidence === "medium" && row.topCandidate && !selected && (
@@ -199,8 +204,8 @@ export function AnalysisCreateFromInputs({ projectId, studyId, onUseInBuilder }:
                   >
                     <option value="">{row.confidence === "low" ? "Select mapping (required)" : "Optional override"}</option>
                     {row.candidates.map((c) => (
-                      <option key={`${row.preregVar}-${c.key}`} value={c.key}>
-                        {c.key} ({c.score.toFixed(2)})
+                      <option key={`${row.preregVar}-${c.key}`} value={c.key} title={c.reason}>
+                        {c.key} ({c.score.toFixed(2)}{c.matchedOn === "question_text" ? ", question text" : c.matchedOn === "qid" ? ", QID" : ""})
                       </option>
                     ))}
                   </select>
@@ -263,9 +268,16 @@ function getMappingRows(spec: any | null): MappingRow[] {
     return {
       preregVar: String(m?.preregVar ?? ""),
       resolvedTo: m?.resolvedTo ? String(m.resolvedTo) : null,
+      resolutionKind: String(m?.resolutionKind ?? (m?.resolvedTo ? "direct" : "unresolved")),
+      derivedSources: Array.isArray(m?.derivedSources) ? m.derivedSources.map(String) : [],
       topCandidate: top?.key ? String(top.key) : null,
       topScore,
-      candidates: candidates.map((c: any) => ({ key: String(c.key), score: Number(c.score ?? 0) })),
+      candidates: candidates.map((c: any) => ({
+        key: String(c.key),
+        score: Number(c.score ?? 0),
+        matchedOn: c.matchedOn ? String(c.matchedOn) : undefined,
+        reason: c.reason ? String(c.reason) : undefined,
+      })),
       confidence
     };
   }).filter((r) => r.preregVar);
@@ -354,6 +366,7 @@ function specToWizardOptions(
     tables: ["table1_descriptives", "model_table", "balance_table"],
     robustness: spec?.models?.robustness?.length ? ["alt_controls"] : [],
     modelLayouts,
+    expectedValues: spec?.dataContract?.expectedValues ?? {},
     exploratory: Boolean(spec?.models?.exploratory?.length),
     exportArtifacts: true
   };
@@ -59,7 +59,9 @@ const MODEL_OPTIONS: Array<{ value: ModelType; label: string }> = [
   { value: "survival", label: "Survival" },
   { value: "rd", label: "Regression discontinuity" },
   { value: "did", label: "DiD" },
-  { value: "event_study", label: "Event study" }
+  { value: "event_study", label: "Event study" },
+  { value: "mediation", label: "Mediation" },
+  { value: "iv", label: "Instrumental variables (2SLS)" }
 ];
 
 const MODEL_LAYOUT_OPTIONS: Array<{ value: ModelLayoutKind; label: string }> = [
@@ -71,7 +73,8 @@ const MODEL_FIGURE_OPTIONS: Array<{ value: ModelFigureType; label: string }> = [
   { value: "coef_plot", label: "Coefficient plot" },
   { value: "fitted_plot", label: "Fitted vs observed" },
   { value: "residual_plot", label: "Residual plot" },
-  { value: "event_study_plot", label: "Event-study plot" }
+  { value: "event_study_plot", label: "Event-study plot" },
+  { value: "mediation_plot", label: "Mediation (indirect effect) plot" }
 ];
 
 const DIAGNOSTIC_OPTIONS: Array<{ value: Diagnostic; label: string }> = [
@@ -154,6 +157,12 @@ const buildDraftFormulaPreview = (draft: ModelLayout): string => {
   const interaction = (draft.interactionVar || "").trim() || "moderator_var";
   const covariates = (draft.covariates || "").trim();
 
+  if (draft.modelType === "iv") {
+    const endogenous = (draft.endogenousVar || "").trim() || treatment;
+    const instruments = (draft.instrumentVars ?? []).filter(Boolean).join(" + ") || "TODO_instruments";
+    return `${outcome} ~ ${covariates || "1"} | ${endogenous} ~ ${instruments}`;
+  }
+
   let rhs = draft.layout === "interaction" ? `(${treatment}) * ${interaction}` : treatment;
   if (covariates) rhs += ` + ${covariates}`;
 
@@ -442,8 +451,51 @@ export function AnalysisTemplateWizard({
                 />
               </label>
             )}
+            {modelLayoutDraft.modelType === "mediation" && (
+              <label>
+                Mediator variable
+                <input
+                  {...textEntryProps}
+                  value={modelLayoutDraft.mediatorVar ?? ""}
+                  onChange={(event) =>
+                    setModelLayoutDraft((prev) => ({ ...prev, mediatorVar: event.target.value }))
+                  }
+                  placeholder="mediator_var"
+                />
+              </label>
+            )}
+            {modelLayoutDraft.modelType === "iv" && (
+              <>
+                <label>
+                  Endogenous variable
+                  <input
+                    {...textEntryProps}
+                    value={modelLayoutDraft.endogenousVar ?? ""}
+                    onChange={(event) =>
+                      setModelLayoutDraft((prev) => ({ ...prev, endogenousVar: event.target.value }))
+                    }
+                    placeholder="defaults to the treatment variable"
+                  />
+                </label>
+                <label>
+                  Instruments (comma separated)
+                  <input
+                    {...textEntryProps}
+                    value={(modelLayoutDraft.instrumentVars ?? []).join(", ")}
+                    onChange={(event) =>
+                      setModelLayoutDraft((prev) => ({
+                        ...prev,
+                        instrumentVars: event.target.value.split(",").map((value) => value.trim())
+                      }))
+                    }
+                    placeholder="assigned, lottery_rank"
+                  />
+                </label>
+              </>
+            )}
             {(modelLayoutDraft.modelType === "mixed_effects" ||
               modelLayoutDraft.modelType === "fixed_effects" ||
+              modelLayoutDraft.modelType === "iv" ||
               modelLayoutDraft.modelType === "did" ||
               modelLayoutDraft.modelType === "event_study") && (
               <label>
@@ -1,4 +1,4 @@
-export function MappingResolver({ unresolved }: { unresolved: Array<{ preregVar: string; candidates: Array<{ key: string; score: number }> }> }) {
+export function MappingResolver({ unresolved }: { unresolved: Array<{ preregVar: string; candidates: Array<{ key: string; score: number; matchedOn?: string }> }> }) {
   return (
     <div>
       <h3>Resolve Variable Mappings</h3>
@@ -8,7 +8,9 @@ export function MappingResolver({ unresolved }: { unresolved: Array<{ preregVar:
           <strong>{u.preregVar}</strong>
           <ul>
             {u.candidates.map((c) => (
-              <li key={c.key}>{c.key} ({c.score.toFixed(2)})</li>
+              <li key={c.key}>
+                {c.key} ({c.score.toFixed(2)}{c.matchedOn === "question_text" ? ", question text" : c.matchedOn === "qid" ? ", QID" : ""})
+              </li>
             ))}
           </ul>
         </div>
@@ -1,10 +1,25 @@
-export function WarningsPanel({ warnings }: { warnings: Array<{ code: string; message: string }> }) {
+import type { WarningSeverity } from "../tauri/api";
+
+const SEVERITY_ORDER: Record<WarningSeverity, number> = { error: 0, warning: 1, info: 2 };
+
+export function WarningsPanel({
+  warnings
+}: {
+  warnings: Array<{ code: string; message: string; severity?: WarningSeverity }>;
+}) {
+  const sorted = [...warnings].sort(
+    (a, b) => SEVERITY_ORDER[a.severity ?? "warning"] - SEVERITY_ORDER[b.severity ?? "warning"]
+  );
   return (
     <div>
       <h3>Warnings</h3>
-      {warnings.length === 0 ? <p>No warnings.</p> : (
+      {sorted.length === 0 ? <p>No warnings.</p> : (
         <ul>
-          {warnings.map((w, i) => <li key={`${w.code}-${i}`}>{w.code}: {w.message}</li>)}
+          {sorted.map((w, i) => (
+            <li key={`${w.code}-${i}`}>
+              [{w.severity ?? "warning"}] {w.code}: {w.message}
+            </li>
+          ))}
         </ul>
       )}
     </div>
@@ -1,4 +1,5 @@
 import { invoke } from "@tauri-apps/api/tauri";
+import { AnalysisTemplateOptions } from "../types/analysisTemplate";
 
 export type AssetRef = { name: string; path: string };
 
@@ -16,8 +17,26 @@ export const generateAnalysisSpec = (payload: {
   preregPath: string;
   templateSet: string;
   styleProfile: string;
+  modelTableFormat?: "html" | "docx" | "both";
+  allowEmptyPrereg?: boolean;
+  llmEnrichment?: boolean;
+  weightVar?: string;
+  loopIterations?: number;
 }) => invoke("generate_analysis_spec", { args: payload });
 
+/** Column -> (exported value -> label). */
+export type ValueLabels = Record<string, Record<string, string>>;
+
+export const getQsfValueLabels = (qsfPath: string) =>
+  invoke<ValueLabels>("get_qsf_value_labels", { args: { qsfPath } });
+
+export const saveValueLabelOverrides = (payload: {
+  projectId: string;
+  studyId: string;
+  analysisId: string;
+  overrides: ValueLabels;
+}) => invoke<string>("save_value_label_overrides", { args: payload });
+
 export const saveAnalysisSpec = (payload: {
   projectId: string;
   studyId: string;
@@ -25,6 +44,74 @@ export const saveAnalysisSpec = (payload: {
   spec: unknown;
 }) => invoke("save_analysis_spec", { args: payload });
 
+export type SpecHistoryEntry = {
+  version: string;
+  savedAt: string;
+  sizeBytes: number;
+};
+
+export const listSpecHistory = (payload: {
+  projectId: string;
+  studyId: string;
+  analysisId: string;
+}) => invoke<SpecHistoryEntry[]>("list_spec_history", { args: payload });
+
+export const restoreSpecVersion = (payload: {
+  projectId: string;
+  studyId: string;
+  analysisId: string;
+  version: string;
+}) => invoke("restore_spec_version", { args: payload });
+
+type SpecWarning = { code: string; message: string; details: unknown; severity: string };
+
+export type SpecDiff = {
+  modelsAdded: { group: string; id: string; formula: string }[];
+  modelsRemoved: { group: string; id: string; formula: string }[];
+  modelsModified: {
+    group: string;
+    id: string;
+    changes: { field: string; before: unknown; after: unknown }[];
+  }[];
+  mappingChanges: { preregVar: string; before: string | null; after: string | null }[];
+  warningsAdded: SpecWarning[];
+  warningsResolved: SpecWarning[];
+  columnsAdded: string[];
+  columnsRemoved: string[];
+  inputChanges: {
+    input: "qsf" | "prereg";
+    beforePath: string;
+    afterPath: string;
+    beforeSha256: string;
+    afterSha256: string;
+  }[];
+  unchanged: boolean;
+};
+
+/** `before` defaults to the saved spec; without `after`, one is built from `generate` (not saved). */
+export const diffAnalysisSpec = (payload: {
+  projectId: string;
+  studyId: string;
+  analysisId: string;
+  before?: unknown;
+  after?: unknown;
+  generate?: Parameters<typeof generateAnalysisSpec>[0];
+}) => invoke<SpecDiff>("diff_analysis_spec", { args: payload });
+
+export type MappingResolutionPreview = {
+  mappingChanges: Array<{ preregVar: string; from: string | null; to: string }>;
+  removedWarnings: Array<{ code: string; message: string }>;
+  formulaChanges: Array<{ group: string; modelId: string; from: string; to: string }>;
+  spec: unknown;
+};
+
+export const previewMappingResolution = (payload: {
+  projectId: string;
+  studyId: string;
+  analysisId: string;
+  mappingUpdates: Array<{ preregVar: string; resolvedTo: string }>;
+}) => invoke<MappingResolutionPreview>("preview_mapping_resolution", { args: payload });
+
 export const resolveMappings = (payload: {
   projectId: string;
   studyId: string;
@@ -32,8 +119,538 @@ export const resolveMappings = (payload: {
   mappingUpdates: Array<{ preregVar: string; resolvedTo: string }>;
 }) => invoke("resolve_mappings", { args: payload });
 
+/** Project-wide prereg variable -> column aliases, recorded by resolveMappings. */
+export type VariableAlias = { preregVar: string; column: string; updatedAt: string };
+
+export const listVariableAliases = (projectId: string) =>
+  invoke<VariableAlias[]>("list_variable_aliases", { args: { projectId } });
+
+export const addVariableAlias = (projectId: string, preregVar: string, column: string) =>
+  invoke<VariableAlias[]>("add_variable_alias", { args: { projectId, preregVar, column } });
+
+export const removeVariableAlias = (projectId: string, preregVar: string) =>
+  invoke<VariableAlias[]>("remove_variable_alias", { args: { projectId, preregVar } });
+
+export type WarningSeverity = "error" | "warning" | "info";
+
+export type WarningCodeInfo = {
+  code: string;
+  defaultSeverity: WarningSeverity;
+  description: string;
+};
+
+export const listWarningCodes = () => invoke<WarningCodeInfo[]>("list_warning_codes");
+
+export type REnvironmentStatus = "r_not_found" | "timed_out" | "packages_missing" | "ready";
+
+export type REnvironmentReport = {
+  status: REnvironmentStatus;
+  rscriptPath: string | null;
+  rVersion: string | null;
+  checkedPackages: string[];
+  missingPackages: string[];
+  installCommand: string | null;
+};
+
+/** Checks the analysis spec's packages when all three ids are given, else the default package list. */
+export const checkREnvironment = (payload: {
+  rscriptPath?: string;
+  projectId?: string;
+  studyId?: string;
+  analysisId?: string;
+}) => invoke<REnvironmentReport>("check_r_environment", { args: payload });
+
+/** Emitted once per output line while install_r_packages runs. */
+export const R_INSTALL_OUTPUT_EVENT = "r-install-output";
+
+export type RInstallLine = { stream: "stdout" | "stderr"; line: string };
+
+export type RInstallReport = { success: boolean; exitCode: number | null; installedPackages: string[] };
+
+export const installRPackages = (payload: { packages: string[]; rscriptPath?: string }) =>
+  invoke<RInstallReport>("install_r_packages", { args: payload });
+
+/** Emitted once per output line while run_analysis knits; `studyId` tells concurrent runs apart. */
+export const ANALYSIS_RUN_OUTPUT_EVENT = "analysis-run-output";
+
+export type AnalysisRunLine = { studyId: string; stream: "stdout" | "stderr"; line: string };
+
+export type AnalysisRunReport = {
+  success: boolean;
+  exitCode: number | null;
+  rmdPath: string;
+  reportPath: string | null;
+  logPath: string;
+  /** Last log lines when the run failed. */
+  logTail: string[];
+};
+
+/** Knits `06_analysis/<analysisName>.Rmd`, or the spec-driven analysis when `analysisId` is given. */
+export const runAnalysis = (payload: {
+  projectId: string;
+  studyId: string;
+  analysisName?: string;
+  analysisId?: string;
+  rscriptPath?: string;
+}) => invoke<AnalysisRunReport>("run_analysis", { args: payload });
+
+/** Refused with a BLOCKING_WARNINGS_UNRESOLVED error while error-severity warnings remain, unless `force`. */
 export const renderAnalysisFromSpec = (payload: {
   projectId: string;
   studyId: string;
   analysisId: string;
+  force?: boolean;
 }) => invoke<{ rmdPath: string; rPath: string }>("render_analysis_from_spec", { args: payload });
+
+export type CodebookOutput = {
+  csvPath: string;
+  markdownPath: string;
+  columns: number;
+  unresolved: string[];
+};
+
+export const generateCodebook = (payload: {
+  projectId: string;
+  studyId: string;
+  analysisId: string;
+}) => invoke<CodebookOutput>("generate_codebook", { args: payload });
+
+export type DataContractReport = {
+  csvPath: string;
+  missingColumns: string[];
+  extraColumns: string[];
+  nearMatches: Array<{ expected: string; found: string; kind: "case" | "suffix" }>;
+  models: Array<{ modelId: string; missing: string[]; allPresent: boolean }>;
+  ok: boolean;
+};
+
+export const validateDataContract = (payload: {
+  projectId: string;
+  studyId: string;
+  analysisId: string;
+  csvPath?: string;
+}) => invoke<DataContractReport>("validate_data_contract", { args: payload });
+
+export type StageTiming = {
+  stage: string;
+  startedAtUtc: string;
+  durationMs: number;
+  ok: boolean;
+  error?: string | null;
+};
+
+export type GenerationReport = {
+  analysisId: string;
+  ok: boolean;
+  totalMs: number;
+  stages: StageTiming[];
+};
+
+export const getLastGenerationReport = (analysisId: string) =>
+  invoke<GenerationReport | null>("get_last_generation_report", { analysisId });
+
+export type RerenderItem = {
+  studyId: string;
+  analysisId: string;
+  specPath: string;
+  status: "rendered" | "would_render" | "skipped" | "failed";
+  message?: string | null;
+};
+
+export type RerenderReport = {
+  projectId: string;
+  dryRun: boolean;
+  rendered: number;
+  skipped: number;
+  failed: number;
+  items: RerenderItem[];
+};
+
+export const rerenderAllAnalyses = (payload: { projectId: string; dryRun?: boolean; force?: boolean }) =>
+  invoke<RerenderReport>("rerender_all_analyses", { args: payload });
+
+export type RegenerationReport = {
+  path: string;
+  backupPath: string;
+  updatedRegions: string[];
+  addedRegions: string[];
+  removedRegions: string[];
+  preservedRegions: string[];
+  conflictedRegions: string[];
+  conflicts: string[];
+};
+
+export const regenerateAnalysisTemplate = (payload: {
+  projectId: string;
+  studyId: string;
+  analysisName: string;
+  options: AnalysisTemplateOptions;
+}) => invoke<RegenerationReport>("regenerate_analysis_template", payload);
+
+/** Named analysis options saved under the project's config/analysis_presets.json. */
+export type AnalysisOptionsPreset = {
+  name: string;
+  options: Partial<AnalysisTemplateOptions>;
+};
+
+export const saveAnalysisOptionsPreset = (
+  projectId: string,
+  name: string,
+  options: Partial<AnalysisTemplateOptions>
+) =>
+  invoke<AnalysisOptionsPreset>("save_analysis_options_preset", {
+    args: { projectId, name, options }
+  });
+
+export const listAnalysisOptionsPresets = (projectId: string) =>
+  invoke<AnalysisOptionsPreset[]>("list_analysis_options_presets", { args: { projectId } });
+
+export const deleteAnalysisOptionsPreset = (projectId: string, name: string) =>
+  invoke<boolean>("delete_analysis_options_preset", { args: { projectId, name } });
+
+/** Creates a template from a saved preset; fields set in `options` override the preset. */
+export const createAnalysisTemplateFromPreset = (payload: {
+  projectId: string;
+  studyId: string;
+  presetName: string;
+  options: Partial<AnalysisTemplateOptions>;
+}) => invoke<string>("create_analysis_template", payload);
+
+export type ReadinessItem = {
+  id: string;
+  label: string;
+  status: "pass" | "warn" | "fail";
+  evidence: string[];
+};
+
+export type StudyReadiness = {
+  studyId: string;
+  items: ReadinessItem[];
+  gates: { name: string; passed: boolean; failing: string[] }[];
+};
+
+export const getStudyReadiness = (projectId: string, studyId: string) =>
+  invoke<StudyReadiness>("get_study_readiness", { args: { projectId, studyId } });
+
+export type DataDictionaryReport = {
+  path: string;
+  variables: number;
+  rowsScanned: number;
+  preservedDescriptions: number;
+};
+
+export type ActivityEntry = {
+  timestamp: string;
+  command: string;
+  projectId: string | null;
+  studyId: string | null;
+  summary: string;
+};
+
+export const getRecentActivity = (limit?: number, projectId?: string) =>
+  invoke<ActivityEntry[]>("get_recent_activity", { args: { limit, projectId } });
+
+export type SearchHit = {
+  kind: "study" | "artifact" | "file";
+  projectId: string;
+  studyId: string;
+  artifactId: string | null;
+  path: string | null;
+  field: string;
+  snippet: string;
+};
+
+export const searchProject = (projectId: string, query: string, limit?: number) =>
+  invoke<SearchHit[]>("search_project", { args: { projectId, query, limit } });
+
+export const searchAllProjects = (query: string, limit?: number) =>
+  invoke<SearchHit[]>("search_all_projects", { args: { query, limit } });
+
+/** Emitted while a project is watched, for files created, removed or renamed under `studies/`. */
+export const PROJECT_FILES_EVENT = "project-files-changed";
+
+export type ProjectFileEvent = {
+  projectId: string;
+  kind: "created" | "removed" | "renamed" | "unavailable" | "resynced";
+  studyId: string | null;
+  path: string | null;
+  fromPath: string | null;
+};
+
+export type WatchStatus = {
+  projectId: string;
+  watching: boolean;
+  available: boolean;
+  indexedFiles: number;
+};
+
+export const watchProject = (projectId: string) =>
+  invoke<WatchStatus>("watch_project", { args: { projectId } });
+
+export const unwatchProject = (projectId: string) =>
+  invoke<WatchStatus>("unwatch_project", { args: { projectId } });
+
+export type FileRefDrift = { studyId: string; path: string; folder: string | null };
+
+export type FileRefReconciliation = { missing: FileRefDrift[]; orphaned: FileRefDrift[] };
+
+export const reconcileFileRefs = (projectId: string) =>
+  invoke<FileRefReconciliation>("reconcile_file_refs", { args: { projectId } });
+
+export type AdoptedStudy = {
+  studyId: string;
+  title: string;
+  files: number;
+  createdFolders: string[];
+};
+
+export type AdoptionReport = {
+  dryRun: boolean;
+  studies: AdoptedStudy[];
+  skipped: Array<{ folder: string; reason: string }>;
+  project: { id: string; name: string; rootPath: string };
+};
+
+export const adoptExistingProject = (payload: {
+  name: string;
+  rootDir: string;
+  googleDriveUrl?: string;
+  dryRun?: boolean;
+}) => invoke<AdoptionReport>("adopt_existing_project", { args: payload });
+
+export type ProjectStructure = {
+  studyFolderTemplate: string[] | null;
+  studyFolders: string[];
+};
+
+export const getProjectStructure = (projectId: string) =>
+  invoke<ProjectStructure>("get_project_structure", { args: { projectId } });
+
+export const updateProjectStructure = (projectId: string, studyFolderTemplate: string[] | null) =>
+  invoke<ProjectStructure>("update_project_structure", {
+    args: { projectId, studyFolderTemplate },
+  });
+
+export type ProjectModelEntry = {
+  studyId: string;
+  sourceFile: string;
+  name: string;
+  modelType: string;
+  outcome: string;
+  treatment: string;
+  covariates: string;
+  layout: string;
+  figures: string[];
+  includeInMainTable: boolean;
+  chunkId: string;
+};
+
+export const listModelsAcrossProject = (projectId: string) =>
+  invoke<ProjectModelEntry[]>("list_models_across_project", { args: { projectId } });
+
+export type StorageHealth = {
+  activePath: string;
+  source: "standard" | "override" | "emergency";
+  writable: boolean;
+  fallbackReason: string | null;
+  warning: string | null;
+};
+
+export const getStorageHealth = () => invoke<StorageHealth>("get_storage_health");
+
+export const setStorageOverride = (dataDir?: string) =>
+  invoke<string>("set_storage_override", { args: { dataDir } });
+
+export type GitRepoInfo = {
+  branch: string | null;
+  hasOrigin: boolean;
+  upstream: string | null;
+  ahead: number;
+  behind: number;
+  dirtyFiles: number;
+};
+
+export type GitCommitPushReport = {
+  outcome: "nothingToCommit" | "pushed";
+  filesCommitted: number;
+  commitHash: string | null;
+  pushed: boolean;
+  remoteMessage: string | null;
+  branch: string | null;
+};
+
+export type GitFileChange = { path: string; status: string; originalPath: string | null };
+
+export type GitSummary = {
+  branch: string | null;
+  upstream: string | null;
+  ahead: number;
+  behind: number;
+  staged: GitFileChange[];
+  unstaged: GitFileChange[];
+  untracked: string[];
+  conflicted: string[];
+};
+
+export type GitLogEntry = { hash: string; author: string; date: string; subject: string };
+
+export const gitStatus = (projectId: string) =>
+  invoke<GitSummary>("git_status", { args: { projectId } });
+
+export const gitCurrentBranch = (projectId: string) =>
+  invoke<string | null>("git_current_branch", { args: { projectId } });
+
+export const gitLog = (projectId: string, limit?: number, subpath?: string) =>
+  invoke<GitLogEntry[]>("git_log", { args: { projectId, limit, subpath } });
+
+export const gitRepoInfo = (projectId: string) =>
+  invoke<GitRepoInfo>("git_repo_info", { args: { projectId } });
+
+export const gitCommitPush = (message: string, projectId: string, setUpstream?: boolean) =>
+  invoke<GitCommitPushReport>("git_commit_push", { args: { message, projectId, setUpstream } });
+
+export const generateDataDictionary = (
+  projectId: string,
+  studyId: string,
+  dataFile: string,
+  sampleRows?: number
+) =>
+  invoke<DataDictionaryReport>("generate_data_dictionary", {
+    args: { projectId, studyId, dataFile, sampleRows }
+  });
+
+export type ColumnProfile = {
+  name: string;
+  missing: number;
+  nonMissing: number;
+  distinctValues: string[];
+  distinctCapped: boolean;
+};
+
+export type DataFileSample = {
+  header: { columns: string[]; qualtricsHeaderRows: number };
+  rows: string[][];
+  truncated: boolean;
+  profiles: ColumnProfile[];
+};
+
+export const inspectDataFile = (path: string, sampleRows?: number) =>
+  invoke<DataFileSample>("inspect_data_file", { args: { path, sampleRows } });
+
+export const unlockProjectSecrets = (projectId: string, passphrase: string) =>
+  invoke<void>("unlock_project_secrets", { args: { projectId, passphrase } });
+
+export const lockProjectSecrets = (projectId: string) =>
+  invoke<void>("lock_project_secrets", { projectId });
+
+export const listProjectSecrets = (projectId: string) =>
+  invoke<string[]>("list_project_secrets", { projectId });
+
+export const setProjectSecret = (projectId: string, name: string, value: string) =>
+  invoke<void>("set_project_secret", { args: { projectId, name, value } });
+
+export const getProjectSecret = (projectId: string, name: string) =>
+  invoke<string | null>("get_project_secret", { args: { projectId, name } });
+
+export const deleteProjectSecret = (projectId: string, name: string) =>
+  invoke<boolean>("delete_project_secret", { args: { projectId, name } });
+
+export type DuplicateStudyReport = {
+  studyId: string;
+  copiedFolders: string[];
+  filesCopied: number;
+  fileRefsCloned: number;
+  skippedLinks: Array<{ source: string; target: string }>;
+  staleStudyReferences: string[];
+  project: unknown;
+};
+
+export const duplicateStudy = (payload: {
+  projectId: string;
+  studyId: string;
+  title?: string;
+  copyFolders: string[];
+}) => invoke<DuplicateStudyReport>("duplicate_study", { args: payload });
+
+export type ProjectExportReport = { archivePath: string; fileCount: number };
+
+export const exportProject = (projectId: string, destination: string) =>
+  invoke<ProjectExportReport>("export_project", { args: { projectId, destination } });
+
+type OpenPathPayload = { projectId: string; studyId?: string; path?: string };
+
+export const revealInFileManager = (payload: OpenPathPayload) =>
+  invoke<string>("reveal_in_file_manager", { args: payload });
+
+export const openPath = (payload: OpenPathPayload) =>
+  invoke<string>("open_path", { args: payload });
+
+/** A deleted project kept for undo; `location` says where its folder went. */
+export type ProjectTombstone = {
+  project: { id: string; name: string; rootPath: string } & Record<string, unknown>;
+  originalPath: string;
+  deletedAt: string;
+  location: "kept" | "os_trash" | "app_trash";
+  trashedPath?: string | null;
+};
+
+export const listDeletedProjects = () => invoke<ProjectTombstone[]>("list_deleted_projects");
+
+export const restoreDeletedProject = (projectId: string) =>
+  invoke("restore_deleted_project", { args: { projectId } });
+
+/** Purges the given tombstones, or all of them when `projectIds` is empty. */
+export const purgeDeletedProjects = (projectIds: string[] = []) =>
+  invoke<number>("purge_deleted_projects", { args: { projectIds } });
+
+/** `apiToken` is write-only: it is saved to the project's secrets store and never returned. */
+export type QualtricsSettings = { datacenter: string; apiToken?: string | null };
+
+export const qualtricsGetSettings = () => invoke<QualtricsSettings>("qualtrics_get_settings");
+
+/** Pass `projectId` (with its secrets unlocked) when `settings.apiToken` is set. */
+export const qualtricsSaveSettings = (settings: QualtricsSettings, projectId?: string) =>
+  invoke<QualtricsSettings>("qualtrics_save_settings", { settings, projectId });
+
+export type FetchedQsf = {
+  path: string;
+  relativePath: string;
+  surveyId: string;
+  surveyName?: string | null;
+};
+
+/** Downloads the live survey definition into the study's 02_build folder. */
+export const fetchQsfFromQualtrics = (payload: {
+  projectId: string;
+  studyId: string;
+  surveyId: string;
+}) => invoke<FetchedQsf>("fetch_qsf_from_qualtrics", { args: payload });
+
+export type ModelLockRef = { projectId: string; projectName: string; tag: string };
+
+export type LocalModel = {
+  fileName: string;
+  path: string;
+  bytes: number;
+  sha256?: string | null;
+  isCurrentTarget: boolean;
+  lockedBy: ModelLockRef[];
+};
+
+export type ModelDirUsage = {
+  modelDir: string;
+  fileCount: number;
+  totalBytes: number;
+  reclaimableBytes: number;
+};
+
+export const llmListLocalModels = () => invoke<LocalModel[]>("llm_list_local_models");
+
+export const llmModelDirUsage = () => invoke<ModelDirUsage>("llm_model_dir_usage");
+
+/** A file some project is locked to is kept unless `force`; `referencedBy` names those projects. */
+export const llmDeleteLocalModel = (fileName: string, force = false) =>
+  invoke<{ deleted: boolean; bytesFreed: number; referencedBy: ModelLockRef[] }>(
+    "llm_delete_local_model",
+    { fileName, force },
+  );
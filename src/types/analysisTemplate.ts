@@ -30,7 +30,9 @@ export type ModelType =
   | "survival"
   | "rd"
   | "did"
-  | "event_study";
+  | "event_study"
+  | "mediation"
+  | "iv";
 
 export type ModelLayoutKind = "simple" | "interaction";
 
@@ -38,7 +40,22 @@ export type ModelFigureType =
   | "coef_plot"
   | "fitted_plot"
   | "residual_plot"
-  | "event_study_plot";
+  | "event_study_plot"
+  | "mediation_plot";
+
+export type ModelEstimation = "frequentist" | "bayesian";
+
+export interface BayesOptions {
+  chains: number;
+  iter: number;
+  seed: number;
+}
+
+export interface ContrastSpec {
+  name: string;
+  levels: string[];
+  weights: number[];
+}
 
 export interface ModelLayout {
   name: string;
@@ -52,6 +69,19 @@ export interface ModelLayout {
   timeVar?: string;
   figures: ModelFigureType[];
   includeInMainTable: boolean;
+  estimation?: ModelEstimation;
+  contrasts?: ContrastSpec[];
+  subsetFilter?: string;
+  weightVar?: string;
+  mediatorVar?: string;
+  /** Columns whose levels the model is refit within, as exploratory subgroup analyses. */
+  subgroupVars?: string[];
+  /** Excluded instruments for "iv" models. */
+  instrumentVars?: string[];
+  /** Instrumented regressor for "iv" models; defaults to the treatment variable. */
+  endogenousVar?: string;
+  /** Column standard errors are clustered on (e.g. participant or session). */
+  clusterVar?: string;
 }
 
 export type Diagnostic =
@@ -72,9 +102,21 @@ export type TableType =
   | "model_table"
   | "marginal_effects_table";
 
+export type ModelTableFormat = "html" | "docx" | "both";
+
+export type StyleProfile = "apa" | "minimal_gt";
+export type TemplateOutputFormat = "rmd" | "qmd";
+
+/** Cut points are quantiles; `variables` defaults to every model outcome. */
+export interface RobustnessParams {
+  winsorize?: { lower?: number; upper?: number; variables?: string[] };
+}
+
 export interface AnalysisTemplateOptions {
   analysisFileName?: string;
   dataSourcePaths?: string[];
+  /** Sheet name or 1-based number for .xlsx/.xls data sources. */
+  dataSourceSheet?: string;
   datasetPathHint?: string;
   outcomeVarHint?: string;
   treatmentVarHint?: string;
@@ -88,7 +130,16 @@ export interface AnalysisTemplateOptions {
   diagnostics: Diagnostic[];
   tables: TableType[];
   robustness: string[];
+  robustnessParams?: RobustnessParams;
   modelLayouts?: ModelLayout[];
+  modelTableFormat?: ModelTableFormat;
+  expectedValues?: Record<string, string[]>;
+  bayesOptions?: BayesOptions;
+  styleProfile?: StyleProfile;
+  omitRegionMarkers?: boolean;
+  effectSizes?: boolean;
+  outputFormat?: TemplateOutputFormat;
+  useRenv?: boolean;
   exploratory: boolean;
   exportArtifacts: boolean;
 }